@@ -2,26 +2,30 @@ use std::env::consts::ARCH as CURRENT_ARCH;
 
 use crate::util::str::char_is_word_separator;
 
-use super::{executable_parsing::parse_executable, OS};
+use super::{executable_parsing::parse_executable, UnsupportedPlatformError, OS};
 
 // Matching substrings - these can be partial matches, eg. "wordwin64" will match as x64 arch
 // These will take priority over full word matches, and should be as precise as possible
 #[rustfmt::skip]
-const ARCH_SUBSTRINGS: [(Arch, &[&str]); 4] = [
-    (Arch::Arm64, &["aarch64", "arm64", "armv9"]),
-    (Arch::X64,   &["x86-64", "x86_64", "amd64", "win64", "win-x64"]),
-    (Arch::Arm32, &["arm32", "armv7"]),
-    (Arch::X86,   &["i686", "i386", "win32", "win-x86"]),
+const ARCH_SUBSTRINGS: [(Arch, &[&str]); 6] = [
+    (Arch::Arm64,       &["aarch64", "arm64", "armv9"]),
+    (Arch::X64,         &["x86-64", "x86_64", "amd64", "win64", "win-x64"]),
+    (Arch::Arm32,       &["arm32", "armv7"]),
+    (Arch::X86,         &["i686", "i386", "win32", "win-x86"]),
+    (Arch::Riscv64,     &["riscv64"]),
+    (Arch::LoongArch64, &["loongarch64", "loong64"]),
 ];
 
 // Matching words - these must be full word matches, eg. "tarmac" will not match as arm arch
 // Note that these can not contain word separators like "-" or "_", since they're stripped
 #[rustfmt::skip]
-const ARCH_FULL_WORDS: [(Arch, &[&str]); 4] = [
-    (Arch::Arm64, &[]),
-    (Arch::X64,   &["x64", "win"]),
-    (Arch::Arm32, &["arm"]),
-    (Arch::X86,   &["x86"]),
+const ARCH_FULL_WORDS: [(Arch, &[&str]); 6] = [
+    (Arch::Arm64,       &[]),
+    (Arch::X64,         &["x64", "win"]),
+    (Arch::Arm32,       &["arm"]),
+    (Arch::X86,         &["x86"]),
+    (Arch::Riscv64,     &[]),
+    (Arch::LoongArch64, &[]),
 ];
 
 /**
@@ -40,20 +44,42 @@ pub enum Arch {
     X64,
     Arm32,
     X86,
+    // NOTE: These architectures can not run binaries for any of the above
+    // architectures, nor vice versa, so their ordering does not matter
+    Riscv64,
+    LoongArch64,
 }
 
 impl Arch {
     /**
         Get the architecture of the current host system.
+
+        # Panics
+
+        Panics if the current architecture is not supported by Rokit.
+        See [`Arch::try_current_system`] for a fallible version.
     */
     #[must_use]
     pub fn current_system() -> Self {
+        Self::try_current_system().expect("current architecture should be supported")
+    }
+
+    /**
+        Get the architecture of the current host system.
+
+        # Errors
+
+        - If the current architecture is not supported by Rokit.
+    */
+    pub fn try_current_system() -> Result<Self, UnsupportedPlatformError> {
         match CURRENT_ARCH {
-            "aarch64" => Self::Arm64,
-            "x86_64" => Self::X64,
-            "x86" => Self::X86,
-            "arm" => Self::Arm32,
-            _ => panic!("Unsupported architecture: {CURRENT_ARCH}"),
+            "aarch64" => Ok(Self::Arm64),
+            "x86_64" => Ok(Self::X64),
+            "x86" => Ok(Self::X86),
+            "arm" => Ok(Self::Arm32),
+            "riscv64" => Ok(Self::Riscv64),
+            "loongarch64" => Ok(Self::LoongArch64),
+            _ => Err(UnsupportedPlatformError::Arch(CURRENT_ARCH)),
         }
     }
 
@@ -124,6 +150,8 @@ impl Arch {
             Self::X64 => "x64",
             Self::Arm32 => "arm32",
             Self::X86 => "x86",
+            Self::Riscv64 => "riscv64",
+            Self::LoongArch64 => "loongarch64",
         }
     }
 }
@@ -178,6 +206,10 @@ mod tests {
             assert_eq!(arch, Arch::X86);
         } else if cfg!(target_arch = "arm") {
             assert_eq!(arch, Arch::Arm32);
+        } else if cfg!(target_arch = "riscv64") {
+            assert_eq!(arch, Arch::Riscv64);
+        } else if cfg!(target_arch = "loongarch64") {
+            assert_eq!(arch, Arch::LoongArch64);
         } else {
             panic!("Unknown architecture for testing: {CURRENT_ARCH}");
         }
@@ -185,7 +217,7 @@ mod tests {
 
     #[test]
     fn detect_arch_valid() {
-        const REAL_ARCHITECTURES: [(&str, Arch); 8] = [
+        const REAL_ARCHITECTURES: [(&str, Arch); 11] = [
             ("APP-x86-64-VER", Arch::X64),
             ("APP-x86_64-VER", Arch::X64),
             ("APP-x64-VER", Arch::X64),
@@ -194,6 +226,9 @@ mod tests {
             ("APP-i686-VER", Arch::X86),
             ("APP-arm64-VER", Arch::Arm64),
             ("APP-arm-VER", Arch::Arm32),
+            ("APP-riscv64-VER", Arch::Riscv64),
+            ("APP-riscv64gc-VER", Arch::Riscv64),
+            ("APP-loongarch64-VER", Arch::LoongArch64),
         ];
         for (real_arch, expected) in REAL_ARCHITECTURES {
             assert_eq!(Arch::detect(real_arch), Some(expected));
@@ -221,7 +256,7 @@ mod tests {
 
     #[test]
     fn real_tool_specs() {
-        const REAL_TOOLS: [(&str, Option<Arch>); 10] = [
+        const REAL_TOOLS: [(&str, Option<Arch>); 12] = [
             ("stylua-linux-x86_64-musl", Some(Arch::X64)),
             ("remodel-0.11.0-linux-x86_64", Some(Arch::X64)),
             ("rojo-0.6.0-alpha.1-win64", Some(Arch::X64)),
@@ -238,6 +273,11 @@ mod tests {
                 "just-1.28.0-arm-unknown-linux-musleabihf",
                 Some(Arch::Arm32),
             ),
+            (
+                "ripgrep-14.1.1-riscv64gc-unknown-linux-gnu",
+                Some(Arch::Riscv64),
+            ),
+            ("uv-loongarch64-unknown-linux-gnu", Some(Arch::LoongArch64)),
         ];
         for (tool, expected) in REAL_TOOLS {
             assert_eq!(Arch::detect(tool), expected, "Tool: {tool}");
@@ -1,23 +1,28 @@
 use goblin::{elf::Elf, mach::Mach, pe::header::Header as PEHeader};
+use semver::Version;
 use tokio::time::Instant;
 
-use super::{Arch, OS};
+use super::{Arch, Toolchain, OS};
 
 /**
-    Tries to parse the contents of and executable file and
-    return the OS and architecture it was compiled for.
+    Tries to parse the contents of and executable file and return
+    the OS and architecture it was compiled for, as well as the
+    toolchain that it requires to run, if it requires any.
 
-    Currently supports ELF, Mach-O and PE formats.
+    Currently supports ELF, Mach-O and PE formats, and
+    detecting toolchain requirements for ELF executables.
 */
 #[tracing::instrument(skip(binary_contents), level = "trace")]
-pub fn parse_executable(binary_contents: impl AsRef<[u8]>) -> Option<(OS, Arch)> {
+pub fn parse_executable(
+    binary_contents: impl AsRef<[u8]>,
+) -> Option<(OS, Arch, Option<Toolchain>)> {
     let binary_contents = binary_contents.as_ref();
 
     // Prioritize parsing the current OS's format first since
     // it's the most likely to be correct, for most use cases.
     let start = Instant::now();
 
-    #[cfg(target_os = "linux")]
+    #[cfg(any(target_os = "linux", target_os = "freebsd"))]
     let opt = {
         parse_elf(binary_contents)
             .or_else(|| parse_mach(binary_contents))
@@ -46,23 +51,111 @@ pub fn parse_executable(binary_contents: impl AsRef<[u8]>) -> Option<(OS, Arch)>
     opt
 }
 
-fn parse_elf(binary_contents: &[u8]) -> Option<(OS, Arch)> {
+fn parse_elf(binary_contents: &[u8]) -> Option<(OS, Arch, Option<Toolchain>)> {
     Elf::parse_header(binary_contents).ok().and_then(|head| {
-        use goblin::elf::header::{EM_386, EM_AARCH64, EM_ARM, EM_X86_64};
+        use goblin::elf::header::{
+            EI_CLASS, EI_OSABI, ELFCLASS64, ELFOSABI_FREEBSD, EM_386, EM_AARCH64, EM_ARM, EM_RISCV,
+            EM_X86_64,
+        };
+
+        // NOTE: Not yet defined by goblin
+        const EM_LOONGARCH: u16 = 258;
 
+        let is_64_bit = head.e_ident[EI_CLASS] == ELFCLASS64;
         let arch = match head.e_machine {
             EM_AARCH64 => Arch::Arm64,
             EM_X86_64 => Arch::X64,
             EM_386 => Arch::X86,
             EM_ARM => Arch::Arm32,
+            EM_RISCV if is_64_bit => Arch::Riscv64,
+            EM_LOONGARCH if is_64_bit => Arch::LoongArch64,
             _ => return None,
         };
 
-        Some((OS::Linux, arch))
+        // Dynamically linked executables specify an interpreter (dynamic loader),
+        // which tells us which libc they need - statically linked executables
+        // have no interpreter and will run regardless of the system libc
+        let interpreter = Elf::parse(binary_contents)
+            .ok()
+            .and_then(|elf| elf.interpreter.map(ToString::to_string));
+
+        // FreeBSD executables are usually branded using the OS ABI field,
+        // but may also only be identifiable by their dynamic loader
+        let is_freebsd = head.e_ident[EI_OSABI] == ELFOSABI_FREEBSD
+            || interpreter
+                .as_deref()
+                .is_some_and(|i| i.ends_with("ld-elf.so.1"));
+        if is_freebsd {
+            return Some((OS::FreeBSD, arch, None));
+        }
+
+        let toolchain = interpreter
+            .as_deref()
+            .and_then(toolchain_from_elf_interpreter);
+
+        Some((OS::Linux, arch, toolchain))
     })
 }
 
-fn parse_mach(binary_contents: &[u8]) -> Option<(OS, Arch)> {
+/**
+    Tries to parse the contents of an ELF executable and return
+    the newest version of glibc that it requires symbols from.
+
+    Returns `None` if the executable does not link against glibc.
+*/
+pub fn parse_elf_glibc_requirement(binary_contents: impl AsRef<[u8]>) -> Option<Version> {
+    let elf = Elf::parse(binary_contents.as_ref()).ok()?;
+    let verneed = elf.verneed.as_ref()?;
+    verneed
+        .iter()
+        .flat_map(|need| need.iter().map(|aux| aux.vna_name).collect::<Vec<_>>())
+        .filter_map(|name| elf.dynstrtab.get_at(name))
+        .filter_map(glibc_version_from_symbol_version)
+        .max()
+}
+
+/**
+    Tries to parse the contents of a glibc shared library (`libc.so.6`)
+    and return the newest version of glibc that it provides symbols for.
+
+    Only used on Linux systems where Rokit itself does not link against glibc.
+*/
+#[cfg_attr(
+    not(all(target_os = "linux", not(target_env = "gnu"))),
+    allow(dead_code)
+)]
+pub fn parse_elf_glibc_version(binary_contents: impl AsRef<[u8]>) -> Option<Version> {
+    let elf = Elf::parse(binary_contents.as_ref()).ok()?;
+    let verdef = elf.verdef.as_ref()?;
+    verdef
+        .iter()
+        .flat_map(|def| def.iter().map(|aux| aux.vda_name).collect::<Vec<_>>())
+        .filter_map(|name| elf.dynstrtab.get_at(name))
+        .filter_map(glibc_version_from_symbol_version)
+        .max()
+}
+
+fn glibc_version_from_symbol_version(symbol_version: &str) -> Option<Version> {
+    let version = symbol_version.strip_prefix("GLIBC_")?;
+    let mut parts = version.split('.').map(str::parse::<u64>);
+    let major = parts.next()?.ok()?;
+    let minor = parts.next()?.ok()?;
+    let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+    Some(Version::new(major, minor, patch))
+}
+
+fn toolchain_from_elf_interpreter(interpreter: &str) -> Option<Toolchain> {
+    let file_name = interpreter.rsplit('/').next().unwrap_or(interpreter);
+    if file_name.starts_with("ld-musl") {
+        Some(Toolchain::Musl)
+    } else if file_name.starts_with("ld-linux") || file_name.starts_with("ld64.so") {
+        Some(Toolchain::Gnu)
+    } else {
+        None
+    }
+}
+
+fn parse_mach(binary_contents: &[u8]) -> Option<(OS, Arch, Option<Toolchain>)> {
     use goblin::mach::{
         constants::cputype::{
             CPU_TYPE_ARM, CPU_TYPE_ARM64, CPU_TYPE_ARM64_32, CPU_TYPE_X86, CPU_TYPE_X86_64,
@@ -85,7 +178,7 @@ fn parse_mach(binary_contents: &[u8]) -> Option<(OS, Arch)> {
     match Mach::parse(binary_contents).ok()? {
         Mach::Binary(macho) => {
             let arch = cputype_to_arch(macho.header.cputype())?;
-            Some((OS::MacOS, arch))
+            Some((OS::MacOS, arch, None))
         }
         Mach::Fat(fat) => {
             let arches = fat.arches().ok()?;
@@ -96,7 +189,7 @@ fn parse_mach(binary_contents: &[u8]) -> Option<(OS, Arch)> {
             if arches.is_empty() {
                 None
             } else if arches.len() == 1 {
-                Some((OS::MacOS, arches[0]))
+                Some((OS::MacOS, arches[0], None))
             } else {
                 // FUTURE: Handle multiple architectures / universal
                 // binaries in Arch enum and propagate results here
@@ -106,7 +199,7 @@ fn parse_mach(binary_contents: &[u8]) -> Option<(OS, Arch)> {
     }
 }
 
-fn parse_pe(binary_contents: &[u8]) -> Option<(OS, Arch)> {
+fn parse_pe(binary_contents: &[u8]) -> Option<(OS, Arch, Option<Toolchain>)> {
     PEHeader::parse(binary_contents).ok().and_then(|header| {
         use goblin::pe::header::{
             COFF_MACHINE_ARM, COFF_MACHINE_ARM64, COFF_MACHINE_ARMNT, COFF_MACHINE_X86,
@@ -121,6 +214,65 @@ fn parse_pe(binary_contents: &[u8]) -> Option<(OS, Arch)> {
             _ => return None,
         };
 
-        Some((OS::Windows, arch))
+        Some((OS::Windows, arch, None))
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elf_interpreter_gnu() {
+        const GNU_INTERPRETERS: &[&str] = &[
+            "/lib64/ld-linux-x86-64.so.2",
+            "/lib/ld-linux-aarch64.so.1",
+            "/lib/ld-linux-armhf.so.3",
+            "/lib/ld-linux.so.2",
+            "/lib64/ld64.so.2",
+        ];
+        for interpreter in GNU_INTERPRETERS {
+            assert_eq!(
+                toolchain_from_elf_interpreter(interpreter),
+                Some(Toolchain::Gnu),
+                "{interpreter}"
+            );
+        }
+    }
+
+    #[test]
+    fn elf_interpreter_musl() {
+        const MUSL_INTERPRETERS: &[&str] = &[
+            "/lib/ld-musl-x86_64.so.1",
+            "/lib/ld-musl-aarch64.so.1",
+            "/lib/ld-musl-armhf.so.1",
+        ];
+        for interpreter in MUSL_INTERPRETERS {
+            assert_eq!(
+                toolchain_from_elf_interpreter(interpreter),
+                Some(Toolchain::Musl),
+                "{interpreter}"
+            );
+        }
+    }
+
+    #[test]
+    fn glibc_symbol_versions() {
+        assert_eq!(
+            glibc_version_from_symbol_version("GLIBC_2.2.5"),
+            Some(Version::new(2, 2, 5))
+        );
+        assert_eq!(
+            glibc_version_from_symbol_version("GLIBC_2.34"),
+            Some(Version::new(2, 34, 0))
+        );
+        assert_eq!(glibc_version_from_symbol_version("GLIBC_PRIVATE"), None);
+        assert_eq!(glibc_version_from_symbol_version("GCC_3.0"), None);
+    }
+
+    #[test]
+    fn elf_interpreter_unknown() {
+        assert_eq!(toolchain_from_elf_interpreter("/system/bin/linker64"), None);
+        assert_eq!(toolchain_from_elf_interpreter(""), None);
+    }
+}
@@ -2,6 +2,8 @@ use std::{cmp::Ordering, str::FromStr};
 
 use thiserror::Error;
 
+use crate::result::RokitResult;
+
 mod arch;
 mod executable_parsing;
 mod os;
@@ -19,6 +21,14 @@ pub enum DescriptionParseError {
     OS,
 }
 
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum UnsupportedPlatformError {
+    #[error("operating system '{0}' is not supported")]
+    OS(&'static str),
+    #[error("architecture '{0}' is not supported")]
+    Arch(&'static str),
+}
+
 /**
     Information describing a system, such as its operating
     system, architecture, and preferred toolchain.
@@ -36,14 +46,48 @@ pub struct Descriptor {
 impl Descriptor {
     /**
         Get the description for the current host system.
+
+        # Panics
+
+        Panics if the current system is not supported by Rokit.
+        See [`Descriptor::try_current_system`] for a fallible version.
     */
     #[must_use]
     pub fn current_system() -> Self {
-        Self {
-            os: OS::current_system(),
-            arch: Some(Arch::current_system()),
-            toolchain: Toolchain::current_system(),
-        }
+        Self::try_current_system().expect("current system should be supported")
+    }
+
+    /**
+        Get the description for the current host system.
+
+        # Errors
+
+        - If the operating system or architecture of the current system is not supported.
+    */
+    pub fn try_current_system() -> RokitResult<Self> {
+        Ok(Self {
+            os: OS::try_current_system()?,
+            arch: Some(Arch::try_current_system()?),
+            toolchain: Toolchain::preferred_for_current_system(),
+        })
+    }
+
+    /**
+        Check that the operating system and architecture of
+        the current host system are supported by Rokit.
+
+        Unlike [`Descriptor::try_current_system`], this never detects
+        the preferred toolchain, which may need to read from disk, so it
+        is cheap enough to call every time a tool is run.
+
+        # Errors
+
+        - If the operating system or architecture of the current system is not supported.
+    */
+    pub fn check_current_system() -> RokitResult<()> {
+        OS::try_current_system()?;
+        Arch::try_current_system()?;
+        Ok(())
     }
 
     /**
@@ -65,19 +109,36 @@ impl Descriptor {
         })
     }
 
+    /**
+        Get the description for a target triple, such as `x86_64-unknown-linux-gnu`
+        or `aarch64-apple-darwin`, to select artifacts for systems other than
+        the current one.
+
+        Returns `None` if the operating system or architecture could not be detected.
+    */
+    #[must_use]
+    pub fn from_target_triple(target_triple: impl AsRef<str>) -> Option<Self> {
+        let desc = Self::detect(target_triple)?;
+        desc.arch.is_some().then_some(desc)
+    }
+
     /**
         Detect system descriptor from the binary contents of an executable file.
 
+        The toolchain of the returned descriptor will be the toolchain that the
+        executable _requires_ to run, such as glibc for dynamically linked GNU
+        executables on Linux, and `None` if it has no such requirements.
+
         Parsing binaries is a potentially expensive operation, so this method should
         preferrably only be used as a fallback or for more descriptive error messages.
     */
     #[must_use]
     pub fn detect_from_executable(binary_contents: impl AsRef<[u8]>) -> Option<Self> {
-        let (os, arch) = parse_executable(binary_contents)?;
+        let (os, arch, toolchain) = parse_executable(binary_contents)?;
         Some(Self {
             os,
             arch: Some(arch),
-            toolchain: None,
+            toolchain,
         })
     }
 
@@ -105,6 +166,35 @@ impl Descriptor {
         self.toolchain
     }
 
+    /**
+        Get the name of the platform for this description, such as
+        `windows-x64`, or only the operating system if the architecture
+        is unknown - this is used when listing platforms to users.
+    */
+    #[must_use]
+    pub fn platform_name(&self) -> String {
+        match self.arch {
+            Some(arch) => format!("{}-{}", self.os.as_str(), arch.as_str()),
+            None => self.os.as_str().to_string(),
+        }
+    }
+
+    /**
+        Check if this description may be able to run executables for
+        other architectures of the same operating system, such as through
+        emulation, or through running 32-bit executables on 64-bit systems.
+
+        Windows on 32-bit ARM, and 32-bit macOS, can never do this, so
+        artifacts for other architectures are not even partially compatible.
+    */
+    #[must_use]
+    pub fn can_run_other_architectures(&self) -> bool {
+        !matches!(
+            (self.os, self.arch),
+            (OS::Windows, Some(Arch::Arm32)) | (OS::MacOS, Some(Arch::X86))
+        )
+    }
+
     /**
         Check if this description is compatible with another description.
 
@@ -132,6 +222,18 @@ impl Descriptor {
             )
     }
 
+    /**
+        Check if this description is natively compatible with another description,
+        meaning that they have the same operating system and architecture, and
+        executables for the other description can run without any emulation.
+
+        See [`Descriptor::is_compatible_with`] for compatibility using emulation.
+    */
+    #[must_use]
+    pub fn is_natively_compatible_with(&self, other: &Descriptor) -> bool {
+        self.os == other.os && self.arch == other.arch
+    }
+
     /**
         Sort two descriptions by their preferred order, compared to this description.
 
@@ -144,8 +246,8 @@ impl Descriptor {
     #[must_use]
     pub fn sort_by_preferred_compat(self, a: &Self, b: &Self) -> Ordering {
         // Check for strict compatibility first (exact matches)
-        let a_compat = a.os == self.os && a.arch == self.arch;
-        let b_compat = b.os == self.os && b.arch == self.arch;
+        let a_compat = self.is_natively_compatible_with(a);
+        let b_compat = self.is_natively_compatible_with(b);
         if a_compat && !b_compat {
             return Ordering::Less;
         }
@@ -153,11 +255,19 @@ impl Descriptor {
             return Ordering::Greater;
         }
 
-        // Sort by preferred architecture or toolchain
+        // Sort by preferred architecture or toolchain, where the
+        // toolchain of this description is always preferred, since
+        // other toolchains may not be available (musl vs glibc)
         if a.arch != b.arch {
             return a.arch.cmp(&b.arch);
         }
         if a.toolchain != b.toolchain {
+            if self.toolchain.is_some() && a.toolchain == self.toolchain {
+                return Ordering::Less;
+            }
+            if self.toolchain.is_some() && b.toolchain == self.toolchain {
+                return Ordering::Greater;
+            }
             return a.toolchain.cmp(&b.toolchain);
         }
 
@@ -198,7 +308,7 @@ mod tests {
         let current = Descriptor::current_system();
         assert_eq!(current.os, OS::current_system());
         assert_eq!(current.arch, Some(Arch::current_system()));
-        assert_eq!(current.toolchain, Toolchain::current_system());
+        assert_eq!(current.toolchain, Toolchain::preferred_for_current_system());
     }
 
     #[test]
@@ -294,6 +404,22 @@ mod tests {
                 toolchain: Some(Toolchain::Musl),
             },
         );
+        check_desc(
+            "riscv64gc-unknown-linux-gnu",
+            Descriptor {
+                os: OS::Linux,
+                arch: Some(Arch::Riscv64),
+                toolchain: Some(Toolchain::Gnu),
+            },
+        );
+        check_desc(
+            "loongarch64-unknown-linux-musl",
+            Descriptor {
+                os: OS::Linux,
+                arch: Some(Arch::LoongArch64),
+                toolchain: Some(Toolchain::Musl),
+            },
+        );
     }
 
     #[test]
@@ -335,6 +461,49 @@ mod tests {
         }
     }
 
+    #[test]
+    fn sort_prefers_own_toolchain() {
+        let gnu = Descriptor::detect("linux-x86_64-gnu").unwrap();
+        let musl = Descriptor::detect("linux-x86_64-musl").unwrap();
+        let unknown = Descriptor::detect("linux-x86_64").unwrap();
+        assert_eq!(gnu.sort_by_preferred_compat(&gnu, &musl), Ordering::Less);
+        assert_eq!(
+            musl.sort_by_preferred_compat(&gnu, &musl),
+            Ordering::Greater
+        );
+        assert_eq!(
+            musl.sort_by_preferred_compat(&musl, &unknown),
+            Ordering::Less
+        );
+        // Without a known toolchain, gnu is preferred over musl
+        assert_eq!(
+            unknown.sort_by_preferred_compat(&gnu, &musl),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn native_and_emulated_compatibility() {
+        let mac_arm = Descriptor::detect("macos-aarch64").unwrap();
+        let mac_x64 = Descriptor::detect("macos-x86_64").unwrap();
+        let win_x64 = Descriptor::detect("windows-x86_64").unwrap();
+        let win_x86 = Descriptor::detect("windows-i686").unwrap();
+        let linux_arm = Descriptor::detect("linux-aarch64").unwrap();
+        let linux_x64 = Descriptor::detect("linux-x86_64").unwrap();
+
+        // Rosetta and WOW64 can run these, but not natively
+        assert!(mac_arm.is_compatible_with(&mac_x64));
+        assert!(!mac_arm.is_natively_compatible_with(&mac_x64));
+        assert!(win_x64.is_compatible_with(&win_x86));
+        assert!(!win_x64.is_natively_compatible_with(&win_x86));
+
+        // Nothing can run these, natively or not
+        assert!(!linux_x64.is_compatible_with(&linux_arm));
+        assert!(!mac_x64.is_compatible_with(&mac_arm));
+
+        assert!(linux_arm.is_natively_compatible_with(&linux_arm));
+    }
+
     #[test]
     fn parse_from_str_valid() {
         const VALID_STRINGS: &[&str] = &[
@@ -370,4 +539,53 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn platform_names() {
+        let desc = Descriptor::detect("rojo-7.4.4-windows-x86_64.zip").unwrap();
+        assert_eq!(desc.platform_name(), "windows-x64");
+        let desc = Descriptor::detect("rojo-7.4.4-macos.zip").unwrap();
+        assert_eq!(desc.platform_name(), "macos");
+    }
+
+    #[test]
+    fn other_architectures_on_unsupported_hosts() {
+        let windows_arm32 = Descriptor::from_target_triple("armv7-pc-windows-msvc").unwrap();
+        let macos_x86 = Descriptor::from_target_triple("i686-apple-darwin").unwrap();
+        let linux_arm32 = Descriptor::from_target_triple("armv7-unknown-linux-gnueabihf").unwrap();
+        assert!(!windows_arm32.can_run_other_architectures());
+        assert!(!macos_x86.can_run_other_architectures());
+        assert!(linux_arm32.can_run_other_architectures());
+    }
+
+    #[test]
+    fn from_target_triple() {
+        assert_eq!(
+            Descriptor::from_target_triple("x86_64-unknown-linux-musl"),
+            Some(Descriptor {
+                os: OS::Linux,
+                arch: Some(Arch::X64),
+                toolchain: Some(Toolchain::Musl),
+            })
+        );
+        assert_eq!(
+            Descriptor::from_target_triple("aarch64-apple-darwin"),
+            Some(Descriptor {
+                os: OS::MacOS,
+                arch: Some(Arch::Arm64),
+                toolchain: None,
+            })
+        );
+        assert_eq!(
+            Descriptor::from_target_triple("x86_64-pc-windows-msvc"),
+            Some(Descriptor {
+                os: OS::Windows,
+                arch: Some(Arch::X64),
+                toolchain: Some(Toolchain::Msvc),
+            })
+        );
+        // Both the operating system and architecture are required
+        assert_eq!(Descriptor::from_target_triple("unknown-linux-gnu"), None);
+        assert_eq!(Descriptor::from_target_triple("x86_64-unknown-none"), None);
+    }
 }
@@ -2,24 +2,26 @@ use std::env::consts::OS as CURRENT_OS;
 
 use crate::util::str::char_is_word_separator;
 
-use super::executable_parsing::parse_executable;
+use super::{executable_parsing::parse_executable, UnsupportedPlatformError};
 
 // Matching substrings - these can be partial matches, eg. "wordwin64" will match as windows OS
 // These will take priority over full word matches, and should be as precise as possible
 #[rustfmt::skip]
-const OS_SUBSTRINGS: [(OS, &[&str]); 3] = [
+const OS_SUBSTRINGS: [(OS, &[&str]); 4] = [
     (OS::Windows, &["windows"]),
     (OS::MacOS,   &["macos", "darwin", "apple"]),
     (OS::Linux,   &["linux", "ubuntu", "debian", "fedora"]),
+    (OS::FreeBSD, &["freebsd"]),
 ];
 
 // Matching words - these must be full word matches, eg. "tarmac" will not match as mac OS
 // Note that these can not contain word separators like "-" or "_", since they're stripped
 #[rustfmt::skip]
-const OS_FULL_WORDS: [(OS, &[&str]); 3] = [
+const OS_FULL_WORDS: [(OS, &[&str]); 4] = [
     (OS::Windows, &["win", "win32", "win64"]),
     (OS::MacOS,   &["mac", "osx"]),
     (OS::Linux,   &[]),
+    (OS::FreeBSD, &[]),
 ];
 
 /**
@@ -31,19 +33,37 @@ pub enum OS {
     Windows,
     MacOS, // aka OS X
     Linux,
+    FreeBSD,
 }
 
 impl OS {
     /**
         Get the operating system of the current host system.
+
+        # Panics
+
+        Panics if the current operating system is not supported by Rokit.
+        See [`OS::try_current_system`] for a fallible version.
     */
     #[must_use]
     pub fn current_system() -> Self {
+        Self::try_current_system().expect("current OS should be supported")
+    }
+
+    /**
+        Get the operating system of the current host system.
+
+        # Errors
+
+        - If the current operating system is not supported by Rokit.
+    */
+    pub fn try_current_system() -> Result<Self, UnsupportedPlatformError> {
         match CURRENT_OS {
-            "windows" => Self::Windows,
-            "macos" => Self::MacOS,
-            "linux" => Self::Linux,
-            _ => panic!("Unsupported OS: {CURRENT_OS}"),
+            "windows" => Ok(Self::Windows),
+            "macos" => Ok(Self::MacOS),
+            "linux" => Ok(Self::Linux),
+            "freebsd" => Ok(Self::FreeBSD),
+            _ => Err(UnsupportedPlatformError::OS(CURRENT_OS)),
         }
     }
 
@@ -98,6 +118,7 @@ impl OS {
             Self::Windows => "windows",
             Self::MacOS => "macos",
             Self::Linux => "linux",
+            Self::FreeBSD => "freebsd",
         }
     }
 }
@@ -147,6 +168,8 @@ mod tests {
             assert_eq!(os, OS::MacOS);
         } else if cfg!(target_os = "linux") {
             assert_eq!(os, OS::Linux);
+        } else if cfg!(target_os = "freebsd") {
+            assert_eq!(os, OS::FreeBSD);
         } else {
             panic!("Unknown OS for testing: {CURRENT_OS}");
         }
@@ -165,6 +188,7 @@ mod tests {
         assert_eq!(OS::detect("APP-ubuntu-ARCH-VER"), Some(OS::Linux));
         assert_eq!(OS::detect("APP-debian-ARCH-VER"), Some(OS::Linux));
         assert_eq!(OS::detect("APP-fedora-ARCH-VER"), Some(OS::Linux));
+        assert_eq!(OS::detect("APP-freebsd-ARCH-VER"), Some(OS::FreeBSD));
     }
 
     #[test]
@@ -178,7 +202,7 @@ mod tests {
 
     #[test]
     fn real_tool_specs() {
-        const REAL_TOOLS: [(&str, Option<OS>); 10] = [
+        const REAL_TOOLS: [(&str, Option<OS>); 11] = [
             ("stylua-linux-x86_64-musl", Some(OS::Linux)),
             ("remodel-0.11.0-linux-x86_64", Some(OS::Linux)),
             ("rojo-0.6.0-alpha.1-win64", Some(OS::Windows)),
@@ -192,6 +216,7 @@ mod tests {
                 Some(OS::Linux),
             ),
             ("just-1.28.0-arm-unknown-linux-musleabihf", Some(OS::Linux)),
+            ("just-1.28.0-x86_64-unknown-freebsd", Some(OS::FreeBSD)),
         ];
         for (tool, expected) in REAL_TOOLS {
             assert_eq!(OS::detect(tool), expected, "Tool: {tool}");
@@ -1,3 +1,19 @@
+use once_cell::sync::Lazy;
+use semver::Version;
+
+use super::executable_parsing::parse_elf_glibc_requirement;
+#[cfg(all(target_os = "linux", not(target_env = "gnu")))]
+use super::executable_parsing::parse_elf_glibc_version;
+
+/**
+    The oldest version of glibc that prebuilt gnu artifacts are assumed to run on.
+
+    Most tools are built on CI runners with a fairly recent glibc, and gnu builds
+    will fail to run on systems with an older glibc, so musl builds (which are
+    usually statically linked) are preferred on systems older than this.
+*/
+const MIN_PREFERRED_GLIBC_VERSION: Version = Version::new(2, 31, 0);
+
 #[rustfmt::skip]
 const TOOLCHAIN_KEYWORDS: [(Toolchain, &[&str]); 3] = [
     (Toolchain::Msvc, &["msvc"]),
@@ -19,10 +35,53 @@ pub enum Toolchain {
 impl Toolchain {
     /**
         Get the toolchain of the current host system.
+
+        On Linux, this is the libc that is available on the system, `Gnu`
+        if the glibc dynamic loader is present, `Musl` if only the musl
+        dynamic loader is present, and `None` if neither could be found.
     */
     #[must_use]
     pub fn current_system() -> Option<Self> {
-        None // TODO: Implement detection of the host toolchain
+        *CURRENT_TOOLCHAIN
+    }
+
+    /**
+        Get the toolchain that artifacts should preferably use on the current host system.
+
+        This is the same as [`Toolchain::current_system`], except that `Musl` is
+        preferred on systems that do have glibc, but a version of it that is
+        likely too old to run most prebuilt gnu artifacts.
+    */
+    #[must_use]
+    pub fn preferred_for_current_system() -> Option<Self> {
+        match Self::current_system()? {
+            Self::Gnu => match Self::current_glibc_version() {
+                Some(version) if version < MIN_PREFERRED_GLIBC_VERSION => Some(Self::Musl),
+                _ => Some(Self::Gnu),
+            },
+            toolchain => Some(toolchain),
+        }
+    }
+
+    /**
+        Get the version of glibc available on the current host system.
+
+        Returns `None` if the system does not use glibc, or if the version could not be detected.
+    */
+    #[must_use]
+    pub fn current_glibc_version() -> Option<Version> {
+        CURRENT_GLIBC_VERSION.clone()
+    }
+
+    /**
+        Get the version of glibc that the given executable requires, if any.
+
+        Parsing binaries is a potentially expensive operation, so this method should
+        preferrably only be used as a fallback or for more descriptive error messages.
+    */
+    #[must_use]
+    pub fn required_glibc_version(binary_contents: impl AsRef<[u8]>) -> Option<Version> {
+        parse_elf_glibc_requirement(binary_contents)
     }
 
     /**
@@ -53,6 +112,101 @@ impl Toolchain {
     }
 }
 
+static CURRENT_TOOLCHAIN: Lazy<Option<Toolchain>> = Lazy::new(detect_current_toolchain);
+
+// NOTE: Rokit itself linking against glibc means that glibc is present,
+// which avoids scanning the filesystem for dynamic loaders entirely
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+#[allow(clippy::unnecessary_wraps)]
+fn detect_current_toolchain() -> Option<Toolchain> {
+    Some(Toolchain::Gnu)
+}
+
+#[cfg(all(target_os = "linux", not(target_env = "gnu")))]
+fn detect_current_toolchain() -> Option<Toolchain> {
+    const LOADER_DIRS: [&str; 3] = ["/lib", "/lib64", "/usr/lib"];
+
+    // NOTE: Systems with glibc may also have musl installed, but
+    // not the other way around, so finding glibc takes priority
+    let mut found_musl = false;
+    for dir in LOADER_DIRS {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file_name = entry.file_name();
+            let file_name = file_name.to_string_lossy();
+            if file_name.starts_with("ld-linux") {
+                return Some(Toolchain::Gnu);
+            } else if file_name.starts_with("ld-musl") {
+                found_musl = true;
+            }
+        }
+    }
+
+    found_musl.then_some(Toolchain::Musl)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn detect_current_toolchain() -> Option<Toolchain> {
+    None // TODO: Implement detection of the host toolchain on other systems
+}
+
+static CURRENT_GLIBC_VERSION: Lazy<Option<Version>> = Lazy::new(detect_current_glibc_version);
+
+// NOTE: Asking the glibc that Rokit is linked against for its version is
+// much cheaper than finding and parsing the glibc shared library from disk
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+fn detect_current_glibc_version() -> Option<Version> {
+    use std::ffi::CStr;
+
+    // SAFETY: gnu_get_libc_version always returns a pointer to
+    // a static, nul-terminated string, such as "2.31"
+    let version = unsafe { CStr::from_ptr(libc::gnu_get_libc_version()) };
+    let version = version.to_str().ok()?;
+    let mut parts = version.split('.').map(str::parse::<u64>);
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().and_then(Result::ok).unwrap_or_default();
+    let patch = parts.next().and_then(Result::ok).unwrap_or_default();
+    Some(Version::new(major, minor, patch))
+}
+
+#[cfg(all(target_os = "linux", not(target_env = "gnu")))]
+fn detect_current_glibc_version() -> Option<Version> {
+    use std::{fs, path::PathBuf};
+
+    const LIBRARY_DIRS: [&str; 4] = ["/lib", "/lib64", "/usr/lib", "/usr/lib64"];
+
+    if Toolchain::current_system() != Some(Toolchain::Gnu) {
+        return None;
+    }
+
+    // The glibc shared library may either be directly in one of the library
+    // directories, or in a multiarch subdirectory such as "x86_64-linux-gnu"
+    let mut candidates = Vec::<PathBuf>::new();
+    for dir in LIBRARY_DIRS {
+        candidates.push(PathBuf::from(dir).join("libc.so.6"));
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().contains("-linux-gnu") {
+                candidates.push(entry.path().join("libc.so.6"));
+            }
+        }
+    }
+
+    candidates
+        .into_iter()
+        .filter_map(|path| fs::read(path).ok())
+        .find_map(parse_elf_glibc_version)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn detect_current_glibc_version() -> Option<Version> {
+    None
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::uninlined_format_args)]
@@ -75,6 +229,17 @@ mod tests {
         }
     }
 
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn current_glibc_version() {
+        if Toolchain::current_system() == Some(Toolchain::Gnu) {
+            let version = Toolchain::current_glibc_version().expect("glibc version");
+            assert!(version >= Version::new(2, 17, 0), "{version}");
+        } else {
+            assert_eq!(Toolchain::current_glibc_version(), None);
+        }
+    }
+
     #[test]
     fn detect_toolchain_valid() {
         assert_eq!(Toolchain::detect("msvc"), Some(Toolchain::Msvc));
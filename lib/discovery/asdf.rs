@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+
+use semver::Version;
+
+use crate::tool::{get_known_tool, ToolAlias, ToolSpec};
+
+use super::Manifest;
+
+/**
+    A `.tool-versions` file, as used by the asdf version manager.
+
+    Only entries for well-known tools with exact versions are used - any
+    other entries, such as for tools that Rokit does not know about, or
+    special versions such as `system`, `latest`, or `ref:main`, are ignored.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct AsdfManifest {
+    tools: HashMap<ToolAlias, ToolSpec>,
+}
+
+impl Manifest for AsdfManifest {
+    fn home_dir() -> &'static str {
+        ""
+    }
+
+    fn manifest_file_name() -> &'static str {
+        ".tool-versions"
+    }
+
+    fn parse_manifest(contents: &str) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        let tools = contents
+            .lines()
+            .filter_map(|line| {
+                // NOTE: Lines may have trailing comments, and may list several versions
+                // as fallbacks for each other - we only ever use the first version
+                let line = line.split('#').next().unwrap_or_default();
+                let mut words = line.split_whitespace();
+                let name = words.next()?;
+                let version = words.next()?.trim_start_matches('v');
+                let alias = name.parse::<ToolAlias>().ok()?;
+                let id = get_known_tool(name)?;
+                let version = version.parse::<Version>().ok()?;
+                Some((alias, (id, version).into()))
+            })
+            .collect();
+        Some(Self { tools })
+    }
+
+    fn into_tools(self) -> HashMap<ToolAlias, ToolSpec> {
+        self.tools
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOOL_VERSIONS: &str = "
+# Tools managed by asdf
+rojo 7.4.1
+stylua v0.20.0 # formatter
+selene 0.27.1 0.26.0
+nodejs 20.11.0
+lune latest
+wally ref:main
+";
+
+    #[test]
+    fn parses_known_tools() {
+        let tools = AsdfManifest::parse_manifest(TOOL_VERSIONS)
+            .unwrap()
+            .into_tools();
+
+        let mut specs = tools
+            .iter()
+            .map(|(alias, spec)| format!("{alias} = {spec}"))
+            .collect::<Vec<_>>();
+        specs.sort();
+        assert_eq!(
+            specs,
+            vec![
+                "rojo = rojo-rbx/rojo@7.4.1",
+                "selene = Kampfkarren/selene@0.27.1",
+                "stylua = JohnnyMorganz/StyLua@0.20.0",
+            ]
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use tokio::fs::{metadata, read_to_string};
+
+use crate::manifests::RokitManifest;
+
+use super::{
+    aftman::AftmanManifest, asdf::AsdfManifest, foreman::ForemanManifest, Manifest, ManifestKind,
+};
+
+/*
+    Manifests that have been read and parsed during this process,
+    keyed by path, so that discovering the same manifests several
+    times during a single invocation of Rokit is essentially free.
+*/
+static CACHED_MANIFESTS: Lazy<DashMap<PathBuf, CachedManifest>> = Lazy::new(DashMap::new);
+
+/**
+    A parsed manifest of any kind supported by Rokit.
+*/
+#[derive(Debug, Clone)]
+pub(super) enum ParsedManifest {
+    Rokit(RokitManifest),
+    Aftman(AftmanManifest),
+    Foreman(ForemanManifest),
+    Asdf(AsdfManifest),
+}
+
+/*
+    The modification time and size of a manifest file, used to
+    detect changes to manifests that were parsed and cached before.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+#[derive(Debug, Clone)]
+struct CachedManifest {
+    stamp: FileStamp,
+    parsed: Option<ParsedManifest>,
+}
+
+/**
+    Loads and parses the manifest of the given kind at the given path.
+
+    Parsed manifests are cached for the rest of the process, and are only
+    read and parsed again if the modification time or size of the file changes.
+
+    Returns `None` if the manifest does not exist, or could not be parsed.
+*/
+pub(super) async fn load_manifest(kind: ManifestKind, path: &Path) -> Option<ParsedManifest> {
+    let meta = metadata(path).await.ok()?;
+    let stamp = FileStamp {
+        modified: meta.modified().ok(),
+        len: meta.len(),
+    };
+
+    if let Some(cached) = CACHED_MANIFESTS.get(path) {
+        if cached.stamp == stamp {
+            return cached.parsed.clone();
+        }
+    }
+
+    let contents = read_to_string(path).await.ok()?;
+    let parsed = match kind {
+        ManifestKind::Rokit => RokitManifest::parse_manifest(&contents).map(ParsedManifest::Rokit),
+        ManifestKind::Aftman => {
+            AftmanManifest::parse_manifest(&contents).map(ParsedManifest::Aftman)
+        }
+        ManifestKind::Foreman => {
+            ForemanManifest::parse_manifest(&contents).map(ParsedManifest::Foreman)
+        }
+        ManifestKind::Asdf => AsdfManifest::parse_manifest(&contents).map(ParsedManifest::Asdf),
+    };
+
+    CACHED_MANIFESTS.insert(
+        path.to_path_buf(),
+        CachedManifest {
+            stamp,
+            parsed: parsed.clone(),
+        },
+    );
+    parsed
+}
+
+/**
+    Removes any cached manifest for the given path, such as after the
+    manifest was written by Rokit itself, since a file modification time
+    may not have a high enough resolution to detect quick changes.
+*/
+pub(crate) fn forget_cached_manifest(path: &Path) {
+    CACHED_MANIFESTS.remove(path);
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::fs::write;
+
+    use super::*;
+
+    async fn load_tools(path: &Path) -> Vec<String> {
+        let Some(ParsedManifest::Rokit(manifest)) = load_manifest(ManifestKind::Rokit, path).await
+        else {
+            panic!("expected a Rokit manifest");
+        };
+        let mut tools = manifest
+            .tool_specs()
+            .into_iter()
+            .map(|(_, spec)| spec.to_string())
+            .collect::<Vec<_>>();
+        tools.sort();
+        tools
+    }
+
+    #[tokio::test]
+    async fn reloads_changed_manifests() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rokit.toml");
+        assert!(load_manifest(ManifestKind::Rokit, &path).await.is_none());
+
+        write(&path, "[tools]\nrojo = \"rojo-rbx/rojo@7.4.1\"\n")
+            .await
+            .unwrap();
+        assert_eq!(load_tools(&path).await, ["rojo-rbx/rojo@7.4.1"]);
+        assert_eq!(load_tools(&path).await, ["rojo-rbx/rojo@7.4.1"]);
+
+        write(
+            &path,
+            "[tools]\nrojo = \"rojo-rbx/rojo@7.4.1\"\nlune = \"lune-org/lune@0.8.9\"\n",
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            load_tools(&path).await,
+            ["lune-org/lune@0.8.9", "rojo-rbx/rojo@7.4.1"]
+        );
+
+        // Changes that keep the same size may happen within the resolution
+        // of file modification times, and need the cache to be cleared
+        write(&path, "[tools]\nrojo = \"rojo-rbx/rojo@7.4.2\"\n")
+            .await
+            .unwrap();
+        forget_cached_manifest(&path);
+        assert_eq!(load_tools(&path).await, ["rojo-rbx/rojo@7.4.2"]);
+    }
+}
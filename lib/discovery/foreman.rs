@@ -1,9 +1,12 @@
 use std::{collections::HashMap, str::FromStr};
 
-use semver::Version;
+use semver::{Op, Version, VersionReq};
 use toml_edit::{DocumentMut, InlineTable, Table};
 
-use crate::tool::{ToolAlias, ToolId, ToolSpec};
+use crate::{
+    storage::ResolvedVersions,
+    tool::{ToolAlias, ToolId, ToolSpec},
+};
 
 use super::Manifest;
 
@@ -36,12 +39,24 @@ impl Manifest for ForemanManifest {
     }
 
     fn into_tools(self) -> HashMap<ToolAlias, ToolSpec> {
+        self.into_tools_resolved(&ResolvedVersions::default())
+    }
+}
+
+impl ForemanManifest {
+    /**
+        Gets the tool identifier and version requirement for each tool in the manifest.
+
+        Foreman manifests use version requirements instead of exact versions,
+        so these must be resolved to specific versions before they can be used.
+    */
+    pub(crate) fn tool_requirements(&self) -> HashMap<ToolAlias, (ToolId, VersionReq)> {
         let mut tools = HashMap::new();
         if let Some(map) = self.document.get("tools").and_then(|t| t.as_table()) {
             for (alias, tool_def) in map {
                 let tool_alias = alias.parse::<ToolAlias>().ok();
 
-                let tool_spec = if tool_def.is_inline_table() {
+                let tool_req = if tool_def.is_inline_table() {
                     tool_def
                         .as_inline_table()
                         .cloned()
@@ -53,31 +68,113 @@ impl Manifest for ForemanManifest {
                         .and_then(|map| parse_foreman_tool_definition(SpecType::Table(map)))
                 };
 
-                if let (Some(alias), Some(spec)) = (tool_alias, tool_spec) {
-                    tools.insert(alias, spec);
+                if let (Some(alias), Some(req)) = (tool_alias, tool_req) {
+                    tools.insert(alias, req);
                 }
             }
         }
         tools
     }
+
+    /**
+        Converts the manifest into exact tool specifications.
+
+        Version requirements use the version they were last resolved to, if any,
+        and otherwise the minimum version that satisfies the requirement.
+    */
+    pub(crate) fn into_tools_resolved(
+        self,
+        resolved: &ResolvedVersions,
+    ) -> HashMap<ToolAlias, ToolSpec> {
+        self.tool_requirements()
+            .into_iter()
+            .filter_map(|(alias, (id, req))| {
+                let version = match resolved.get(&id, &req) {
+                    Some(version) => version.clone(),
+                    None => minimum_version(&req)?,
+                };
+                Some((alias, (id, version).into()))
+            })
+            .collect()
+    }
 }
 
-fn parse_foreman_tool_definition(map: SpecType) -> Option<ToolSpec> {
+/**
+    Gets the minimum version that satisfies the given version requirement, if
+    one can be determined without knowing which versions actually exist.
+*/
+fn minimum_version(req: &VersionReq) -> Option<Version> {
+    let first = req.comparators.first()?;
+    if !matches!(
+        first.op,
+        Op::Exact | Op::Caret | Op::Tilde | Op::GreaterEq | Op::Wildcard
+    ) {
+        return None;
+    }
+
+    let mut version = Version::new(
+        first.major,
+        first.minor.unwrap_or_default(),
+        first.patch.unwrap_or_default(),
+    );
+    version.pre = first.pre.clone();
+
+    req.matches(&version).then_some(version)
+}
+
+fn parse_foreman_tool_definition(map: SpecType) -> Option<(ToolId, VersionReq)> {
     let map = match map {
         SpecType::InlineTable(table) => table,
         SpecType::Table(table) => table.into_inline_table(),
     };
 
-    let version = map.get("version").and_then(|t| t.as_str()).and_then(|v| {
-        // TODO: Support real version requirements instead of just exact/min versions
-        let without_prefix = v.trim_start_matches('=').trim_start_matches('^');
-        without_prefix.parse::<Version>().ok()
-    })?;
+    let version_req = map
+        .get("version")
+        .and_then(|t| t.as_str())
+        .and_then(|v| v.parse::<VersionReq>().ok())?;
     // TODO: Support gitlab tool ids
     let github_tool_id = map
         .get("github")
         .or(map.get("source"))
         .and_then(|t| t.as_str())
         .and_then(|s| s.parse::<ToolId>().ok());
-    github_tool_id.map(|id| (id, version).into())
+    github_tool_id.map(|id| (id, version_req))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+[tools]
+rojo = { source = "rojo-rbx/rojo", version = "7.3.0" }
+selene = { github = "Kampfkarren/selene", version = "=0.27.1" }
+stylua = { github = "JohnnyMorganz/StyLua", version = "~0.20" }
+wally = { github = "UpliftGames/wally", version = ">0.3" }
+"#;
+
+    fn spec(s: &str) -> ToolSpec {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_version_requirements() {
+        let manifest = ForemanManifest::parse_manifest(MANIFEST).unwrap();
+        let reqs = manifest.tool_requirements();
+        let (_, req) = &reqs[&"stylua".parse::<ToolAlias>().unwrap()];
+        assert_eq!(req, &"~0.20".parse::<VersionReq>().unwrap());
+        assert_eq!(reqs.len(), 4);
+    }
+
+    #[test]
+    fn uses_minimum_versions() {
+        let tools = ForemanManifest::parse_manifest(MANIFEST)
+            .unwrap()
+            .into_tools();
+        let get = |alias: &str| tools.get(&alias.parse::<ToolAlias>().unwrap()).cloned();
+        assert_eq!(get("rojo"), Some(spec("rojo-rbx/rojo@7.3.0")));
+        assert_eq!(get("selene"), Some(spec("Kampfkarren/selene@0.27.1")));
+        assert_eq!(get("stylua"), Some(spec("JohnnyMorganz/StyLua@0.20.0")));
+        assert_eq!(get("wally"), None);
+    }
 }
@@ -1,32 +1,72 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     env::var_os,
+    fmt,
     path::{Path, PathBuf},
+    sync::OnceLock,
 };
 
-use futures::{stream::FuturesOrdered, StreamExt};
-use tokio::fs::read_to_string;
+use futures::{
+    stream::{FuturesOrdered, FuturesUnordered},
+    StreamExt,
+};
+use tokio::fs::try_exists;
+use tracing::warn;
 
 use crate::{
-    manifests::RokitManifest,
-    storage::Home,
+    manifests::{AliasRules, Fallthrough, RokitManifest, ToolOptions, LOCKFILE_NAME},
+    result::RokitResult,
+    sources::{ArtifactSource, ExtractLimits},
+    storage::{Home, ResolvedVersions},
     system::current_dir,
     tool::{ToolAlias, ToolSpec},
 };
 
-use self::{aftman::AftmanManifest, foreman::ForemanManifest};
+use self::{
+    aftman::AftmanManifest,
+    asdf::AsdfManifest,
+    cache::{load_manifest, ParsedManifest},
+    foreman::ForemanManifest,
+};
 
 mod aftman;
+mod asdf;
+mod cache;
 mod foreman;
 mod rokit;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-enum ManifestKind {
+pub(crate) use self::cache::forget_cached_manifest;
+
+/**
+    The kind of a discovered manifest - either a Rokit manifest,
+    or a manifest for one of the tool managers that Rokit supports.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ManifestKind {
+    Asdf,
     Foreman,
     Aftman,
     Rokit,
 }
 
+impl ManifestKind {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Asdf => "asdf",
+            Self::Foreman => "Foreman",
+            Self::Aftman => "Aftman",
+            Self::Rokit => "Rokit",
+        }
+    }
+}
+
+impl fmt::Display for ManifestKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
 trait Manifest
 where
     Self: Sized,
@@ -40,21 +80,77 @@ where
 /**
     A discovered manifest.
 
-    Contains tools as well as the path where the manifest was found.
+    Contains tools, their options, as well as the kind of manifest and the path
+    where it was found. Note that only Rokit manifests may declare options for tools.
 */
 #[derive(Debug, Clone)]
 pub struct DiscoveredManifest {
-    _kind: ManifestKind,
+    pub kind: ManifestKind,
     pub path: PathBuf,
     pub tools: HashMap<ToolAlias, ToolSpec>,
+    pub options: HashMap<ToolAlias, ToolOptions>,
+}
+
+/*
+    Set when a specific manifest directory has been requested,
+    instead of discovering manifests upwards from the current directory.
+*/
+static MANIFEST_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/**
+    Uses manifests in the given directory for all discovery, instead of
+    discovering manifests in the current directory and its ancestors.
+
+    Manifests in home directories are still discovered as usual.
+    This may only be set once, and any later calls are ignored.
+*/
+pub fn set_manifest_dir(dir: impl Into<PathBuf>) {
+    let _ = MANIFEST_DIR.set(dir.into());
+}
+
+/**
+    Gets the manifest directory set using [`set_manifest_dir`], if any.
+*/
+#[must_use]
+pub fn manifest_dir() -> Option<&'static Path> {
+    MANIFEST_DIR.get().map(PathBuf::as_path)
+}
+
+/*
+    Gets the project directories to search for manifests in, nearest
+    first - either the manifest directory, or the current directory and up.
+*/
+fn project_dirs<'a>(cwd: &'a Path, manifest_dir: Option<&'a Path>) -> Vec<&'a Path> {
+    match manifest_dir {
+        Some(dir) => vec![dir],
+        None => cwd.ancestors().collect(),
+    }
 }
 
-fn search_paths(cwd: &Path, rokit_only: bool, skip_home: bool) -> Vec<(ManifestKind, PathBuf)> {
+fn search_paths(
+    cwd: &Path,
+    rokit_only: bool,
+    skip_home: bool,
+    tool_versions: bool,
+) -> Vec<(ManifestKind, PathBuf)> {
+    let dirs = project_dirs(cwd, manifest_dir());
+    search_paths_in(&dirs, rokit_only, skip_home, tool_versions)
+}
+
+/*
+    Gets the paths of all manifests to search in the given project
+    directories, nearest first, followed by any home directories.
+*/
+fn search_paths_in(
+    dirs: &[&Path],
+    rokit_only: bool,
+    skip_home: bool,
+    tool_versions: bool,
+) -> Vec<(ManifestKind, PathBuf)> {
     let mut ordered_paths = Vec::new();
 
-    // Gather paths from current directory and up
-    let mut current = Some(cwd);
-    while let Some(dir) = current {
+    // Gather paths from project directories, nearest first
+    for dir in dirs {
         ordered_paths.push((
             ManifestKind::Rokit,
             dir.join(RokitManifest::manifest_file_name()),
@@ -68,12 +164,25 @@ fn search_paths(cwd: &Path, rokit_only: bool, skip_home: bool) -> Vec<(ManifestK
                 ManifestKind::Foreman,
                 dir.join(ForemanManifest::manifest_file_name()),
             ));
+            if tool_versions {
+                ordered_paths.push((
+                    ManifestKind::Asdf,
+                    dir.join(AsdfManifest::manifest_file_name()),
+                ));
+            }
         }
-        current = dir.parent();
     }
 
-    // Gather paths from program-specific home directories, if desired
-    if !skip_home {
+    // Gather paths from program-specific home directories, if desired - note
+    // that isolated homes must never use manifests from the user's home directory
+    if !skip_home && Home::is_isolated() {
+        if let Ok(home) = Home::path_from_env() {
+            ordered_paths.push((
+                ManifestKind::Rokit,
+                home.join(RokitManifest::manifest_file_name()),
+            ));
+        }
+    } else if !skip_home {
         if let Some(home) = dirs::home_dir() {
             ordered_paths.push((
                 ManifestKind::Rokit,
@@ -91,6 +200,13 @@ fn search_paths(cwd: &Path, rokit_only: bool, skip_home: bool) -> Vec<(ManifestK
                     home.join(ForemanManifest::home_dir())
                         .join(ForemanManifest::manifest_file_name()),
                 ));
+                if tool_versions {
+                    ordered_paths.push((
+                        ManifestKind::Asdf,
+                        home.join(AsdfManifest::home_dir())
+                            .join(AsdfManifest::manifest_file_name()),
+                    ));
+                }
             }
         }
     }
@@ -98,44 +214,172 @@ fn search_paths(cwd: &Path, rokit_only: bool, skip_home: bool) -> Vec<(ManifestK
     ordered_paths
 }
 
+/*
+    Checks if asdf `.tool-versions` files should be discovered, using the
+    `tool-versions` setting of the first Rokit manifest that has it.
+
+    This is disabled by default, since these files are usually managed by
+    asdf itself, and Rokit running tools from them may come as a surprise.
+*/
+async fn tool_versions_enabled(dirs: &[&Path], rokit_only: bool) -> bool {
+    if rokit_only {
+        return false;
+    }
+    for (kind, path) in search_paths_in(dirs, true, false, false) {
+        let Some(ParsedManifest::Rokit(manifest)) = load_manifest(kind, &path).await else {
+            continue;
+        };
+        if let Some(enabled) = manifest.get_tool_versions() {
+            return enabled;
+        }
+    }
+    false
+}
+
 /**
     Discovers all known tool manifests in the current directory and its ancestors, as well as home directories.
 
     This is a slow operation that reads many potential files - use `discover_tool_spec` if possible.
+
+    Manifests are returned in order of precedence, nearest first, meaning that a tool
+    in a manifest is shadowed by a tool with the same alias in any earlier manifest.
 */
 pub async fn discover_all_manifests(rokit_only: bool, skip_home: bool) -> Vec<DiscoveredManifest> {
     let cwd = current_dir().await;
+    let dirs = project_dirs(&cwd, manifest_dir());
+    discover_manifests_in_dirs(&dirs, rokit_only, skip_home).await
+}
+
+/**
+    Discovers all known tool manifests in the given directory and its ancestors, as well as home directories.
+
+    This is the same as [`discover_all_manifests`], but for any directory, such as a project
+    opened in an editor - the manifest directory set using [`set_manifest_dir`] is not used.
+*/
+pub async fn discover_all_manifests_in(
+    dir: &Path,
+    rokit_only: bool,
+    skip_home: bool,
+) -> Vec<DiscoveredManifest> {
+    let dirs = dir.ancestors().collect::<Vec<_>>();
+    discover_manifests_in_dirs(&dirs, rokit_only, skip_home).await
+}
 
-    let found_manifest_contents = search_paths(&cwd, rokit_only, skip_home)
+async fn discover_manifests_in_dirs(
+    dirs: &[&Path],
+    rokit_only: bool,
+    skip_home: bool,
+) -> Vec<DiscoveredManifest> {
+    // NOTE: Manifests are read and parsed concurrently, but kept in order
+    let tool_versions = tool_versions_enabled(dirs, rokit_only).await;
+    let found_manifests = search_paths_in(dirs, rokit_only, skip_home, tool_versions)
         .into_iter()
         .map(|(kind, path)| async move {
-            let contents = read_to_string(&path).await.ok()?;
-            Some((kind, path, contents))
+            let parsed = load_manifest(kind, &path).await?;
+            Some((kind, path, parsed))
         })
         .collect::<FuturesOrdered<_>>()
+        .filter_map(|found| async move { found })
         .collect::<Vec<_>>()
-        .await
-        .into_iter()
-        .flatten()
-        .collect::<Vec<_>>();
+        .await;
 
-    found_manifest_contents
+    let resolved = if found_manifests
+        .iter()
+        .any(|(kind, _, _)| *kind == ManifestKind::Foreman)
+    {
+        load_resolved_versions().await
+    } else {
+        ResolvedVersions::default()
+    };
+
+    found_manifests
         .into_iter()
-        .filter_map(|(kind, path, contents)| {
-            let tools = match kind {
-                ManifestKind::Rokit => RokitManifest::parse_manifest(&contents)?.into_tools(),
-                ManifestKind::Aftman => AftmanManifest::parse_manifest(&contents)?.into_tools(),
-                ManifestKind::Foreman => ForemanManifest::parse_manifest(&contents)?.into_tools(),
+        .map(|(kind, path, parsed)| {
+            let (tools, options) = match parsed {
+                ParsedManifest::Rokit(manifest) => {
+                    let options = manifest
+                        .tool_specs()
+                        .into_iter()
+                        .map(|(alias, _)| {
+                            let options = manifest.get_tool_options(&alias);
+                            (alias, options)
+                        })
+                        .collect();
+                    (manifest.into_tools(), options)
+                }
+                ParsedManifest::Aftman(manifest) => (manifest.into_tools(), HashMap::new()),
+                ParsedManifest::Asdf(manifest) => (manifest.into_tools(), HashMap::new()),
+                ParsedManifest::Foreman(manifest) => {
+                    (manifest.into_tools_resolved(&resolved), HashMap::new())
+                }
             };
-            Some(DiscoveredManifest {
-                _kind: kind,
+            DiscoveredManifest {
+                kind,
                 path,
                 tools,
-            })
+                options,
+            }
         })
         .collect()
 }
 
+/**
+    A discovered tool.
+
+    Contains the tool specification, the kind and path of the manifest
+    it was found in, as well as any options for running the tool.
+*/
+#[derive(Debug, Clone)]
+pub struct DiscoveredTool {
+    pub spec: ToolSpec,
+    pub kind: ManifestKind,
+    pub path: PathBuf,
+    pub options: ToolOptions,
+}
+
+impl DiscoveredTool {
+    /**
+        Gets the working directory that the tool should run in, if
+        it should not run in the current working directory.
+
+        This resolves the `cwd` option relative to the directory
+        containing the manifest that the tool was found in.
+    */
+    #[must_use]
+    pub fn working_dir(&self) -> Option<PathBuf> {
+        let cwd = self.options.cwd.as_deref()?;
+        let root = self.path.parent()?;
+        if cwd == "project-root" {
+            Some(root.to_path_buf())
+        } else {
+            Some(root.join(cwd))
+        }
+    }
+}
+
+/**
+    A tool specification resolved from the nearest manifest that declares it.
+
+    Contains the tool specification, as well as the path and kind of the manifest
+    that took precedence - any other manifests declaring the same alias are ignored.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedTool {
+    pub spec: ToolSpec,
+    pub manifest_path: PathBuf,
+    pub kind: ManifestKind,
+}
+
+impl From<DiscoveredTool> for ResolvedTool {
+    fn from(tool: DiscoveredTool) -> Self {
+        Self {
+            spec: tool.spec,
+            manifest_path: tool.path,
+            kind: tool.kind,
+        }
+    }
+}
+
 /**
     Discovers a tool spec by searching for manifests in the current directory and its ancestors.
 
@@ -145,28 +389,446 @@ pub async fn discover_tool_spec(
     alias: &ToolAlias,
     rokit_only: bool,
     skip_home: bool,
-) -> Option<ToolSpec> {
+) -> Option<ResolvedTool> {
+    let tool = discover_tool(alias, rokit_only, skip_home).await?;
+    Some(tool.into())
+}
+
+/**
+    Discovers a tool spec by searching for manifests in the given directory and its ancestors.
+
+    This is the same as [`discover_tool_spec`], but for any directory, such as a project
+    opened in an editor - the manifest directory set using [`set_manifest_dir`] is not used.
+*/
+pub async fn discover_tool_spec_in(
+    dir: &Path,
+    alias: &ToolAlias,
+    rokit_only: bool,
+    skip_home: bool,
+) -> Option<ResolvedTool> {
+    let tool = discover_tool_in(dir, alias, rokit_only, skip_home).await?;
+    Some(tool.into())
+}
+
+/**
+    Discovers a tool by searching for manifests in the current directory and its ancestors.
+
+    This is a fast operation that reads only the necessary files.
+
+    Note that only Rokit manifests may declare options for running tools.
+*/
+pub async fn discover_tool(
+    alias: &ToolAlias,
+    rokit_only: bool,
+    skip_home: bool,
+) -> Option<DiscoveredTool> {
     let cwd = current_dir().await;
+    let dirs = project_dirs(&cwd, manifest_dir());
+    discover_tool_in_dirs(&dirs, alias, rokit_only, skip_home).await
+}
+
+/**
+    Discovers a tool by searching for manifests in the given directory and its ancestors.
+
+    This is the same as [`discover_tool`], but for any directory, such as a project
+    opened in an editor - the manifest directory set using [`set_manifest_dir`] is not used.
+*/
+pub async fn discover_tool_in(
+    dir: &Path,
+    alias: &ToolAlias,
+    rokit_only: bool,
+    skip_home: bool,
+) -> Option<DiscoveredTool> {
+    let dirs = dir.ancestors().collect::<Vec<_>>();
+    discover_tool_in_dirs(&dirs, alias, rokit_only, skip_home).await
+}
 
-    for (kind, path) in search_paths(&cwd, rokit_only, skip_home) {
-        let Ok(contents) = read_to_string(&path).await else {
+async fn discover_tool_in_dirs(
+    dirs: &[&Path],
+    alias: &ToolAlias,
+    rokit_only: bool,
+    skip_home: bool,
+) -> Option<DiscoveredTool> {
+    let tool_versions = tool_versions_enabled(dirs, rokit_only).await;
+    for (kind, path) in search_paths_in(dirs, rokit_only, skip_home, tool_versions) {
+        let Some(parsed) = load_manifest(kind, &path).await else {
             continue;
         };
 
-        let tools = match kind {
-            ManifestKind::Rokit => RokitManifest::parse_manifest(&contents)?.into_tools(),
-            ManifestKind::Aftman => AftmanManifest::parse_manifest(&contents)?.into_tools(),
-            ManifestKind::Foreman => ForemanManifest::parse_manifest(&contents)?.into_tools(),
+        let (tools, options) = tools_with_options(parsed, alias).await;
+        if let Some(spec) = tools.get(alias) {
+            return Some(DiscoveredTool {
+                spec: spec.clone(),
+                kind,
+                path,
+                options,
+            });
+        }
+    }
+
+    None
+}
+
+/**
+    A manifest that was consulted while discovering a tool.
+
+    Contains the kind and path of the manifest, as well as the tool
+    specification that the manifest declares for the alias, if any.
+*/
+#[derive(Debug, Clone)]
+pub struct ConsultedManifest {
+    pub kind: ManifestKind,
+    pub path: PathBuf,
+    pub spec: Option<ToolSpec>,
+}
+
+/**
+    Traces the discovery of a tool, returning all manifests that
+    exist, in the same order of precedence as [`discover_tool`].
+
+    The first manifest declaring the alias is the one that [`discover_tool`]
+    would use, and any later manifests declaring it are shadowed by it.
+*/
+pub async fn trace_tool_discovery(
+    alias: &ToolAlias,
+    rokit_only: bool,
+    skip_home: bool,
+) -> Vec<ConsultedManifest> {
+    let cwd = current_dir().await;
+    let dirs = project_dirs(&cwd, manifest_dir());
+    trace_tool_discovery_in_dirs(&dirs, alias, rokit_only, skip_home).await
+}
+
+/**
+    Traces the discovery of a tool in the given directory and its ancestors.
+
+    This is the same as [`trace_tool_discovery`], but for any directory, such as a project
+    opened in an editor - the manifest directory set using [`set_manifest_dir`] is not used.
+*/
+pub async fn trace_tool_discovery_in(
+    dir: &Path,
+    alias: &ToolAlias,
+    rokit_only: bool,
+    skip_home: bool,
+) -> Vec<ConsultedManifest> {
+    let dirs = dir.ancestors().collect::<Vec<_>>();
+    trace_tool_discovery_in_dirs(&dirs, alias, rokit_only, skip_home).await
+}
+
+async fn trace_tool_discovery_in_dirs(
+    dirs: &[&Path],
+    alias: &ToolAlias,
+    rokit_only: bool,
+    skip_home: bool,
+) -> Vec<ConsultedManifest> {
+    let tool_versions = tool_versions_enabled(dirs, rokit_only).await;
+    let mut consulted = Vec::new();
+    for (kind, path) in search_paths_in(dirs, rokit_only, skip_home, tool_versions) {
+        let Some(parsed) = load_manifest(kind, &path).await else {
+            continue;
         };
+        let (mut tools, _) = tools_with_options(parsed, alias).await;
+        consulted.push(ConsultedManifest {
+            kind,
+            path,
+            spec: tools.remove(alias),
+        });
+    }
 
-        if let Some(spec) = tools.get(alias) {
-            return Some(spec.clone());
+    consulted
+}
+
+/*
+    Gets the tools declared in a parsed manifest, and the options
+    for running the tool with the given alias, if it has any.
+*/
+async fn tools_with_options(
+    parsed: ParsedManifest,
+    alias: &ToolAlias,
+) -> (HashMap<ToolAlias, ToolSpec>, ToolOptions) {
+    match parsed {
+        ParsedManifest::Rokit(manifest) => {
+            let options = manifest.get_tool_options(alias);
+            (manifest.into_tools(), options)
+        }
+        ParsedManifest::Aftman(manifest) => (manifest.into_tools(), ToolOptions::default()),
+        ParsedManifest::Asdf(manifest) => (manifest.into_tools(), ToolOptions::default()),
+        ParsedManifest::Foreman(manifest) => (
+            manifest.into_tools_resolved(&load_resolved_versions().await),
+            ToolOptions::default(),
+        ),
+    }
+}
+
+/**
+    Resolves the version requirements in all discovered Foreman manifests to the
+    latest matching versions available from their providers, and stores the
+    resolved versions so that any tool discovery afterwards will use them.
+
+    Requirements that could not be resolved keep using their previously resolved
+    version, or if never resolved, the minimum version satisfying the requirement.
+
+    Returns the tool specifications that requirements were resolved to.
+
+    # Errors
+
+    - If the resolved versions could not be saved.
+*/
+pub async fn resolve_foreman_requirements(
+    home: &Home,
+    source: &ArtifactSource,
+) -> RokitResult<Vec<ToolSpec>> {
+    let cwd = current_dir().await;
+
+    let mut requirements = HashSet::new();
+    for (kind, path) in search_paths(&cwd, false, false, false) {
+        if kind != ManifestKind::Foreman {
+            continue;
+        }
+        if let Some(ParsedManifest::Foreman(manifest)) = load_manifest(kind, &path).await {
+            requirements.extend(manifest.tool_requirements().into_values());
+        }
+    }
+    if requirements.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let resolved = requirements
+        .into_iter()
+        .map(|(id, req)| async move {
+            match source.get_latest_release_matching(&id, &req).await {
+                Ok(release) => {
+                    let version = release.artifacts.first()?.tool_spec.version().clone();
+                    Some((id, req, version))
+                }
+                Err(e) => {
+                    warn!("Failed to resolve version '{req}' for tool '{id}': {e}");
+                    None
+                }
+            }
+        })
+        .collect::<FuturesUnordered<_>>()
+        .filter_map(|resolved| async move { resolved })
+        .collect::<Vec<_>>()
+        .await;
+
+    ResolvedVersions::record(home.path(), &resolved).await?;
+
+    Ok(resolved
+        .into_iter()
+        .map(|(id, _, version)| (id, version).into())
+        .collect())
+}
+
+/*
+    Loads the versions that Foreman version requirements were last
+    resolved to, which are stored in the Rokit home directory.
+*/
+async fn load_resolved_versions() -> ResolvedVersions {
+    match Home::path_from_env() {
+        Ok(path) => ResolvedVersions::load(path).await,
+        Err(_) => ResolvedVersions::default(),
+    }
+}
+
+/**
+    Discovers an Aftman manifest for the current project that has not been migrated
+    to a Rokit manifest yet, searching the current directory and its ancestors.
+
+    Returns the path to the nearest Aftman manifest, but only if the project has no
+    Rokit manifest at all. Manifests in home directories are never considered.
+*/
+pub async fn discover_unmigrated_aftman_manifest() -> Option<PathBuf> {
+    let cwd = current_dir().await;
+
+    let mut nearest_aftman = None;
+    for (kind, path) in search_paths(&cwd, false, true, false) {
+        let exists = || async { try_exists(&path).await.unwrap_or_default() };
+        match kind {
+            ManifestKind::Rokit if exists().await => return None,
+            ManifestKind::Aftman if nearest_aftman.is_none() && exists().await => {
+                nearest_aftman = Some(path.clone());
+            }
+            _ => {}
         }
     }
 
+    nearest_aftman
+}
+
+/**
+    Discovers the nearest lockfile for the current project, searching the current
+    directory and its ancestors, or only the directory set using [`set_manifest_dir`].
+
+    Returns the directory that contains the lockfile, if one was found.
+*/
+pub async fn discover_lockfile_dir() -> Option<PathBuf> {
+    let cwd = current_dir().await;
+    for dir in project_dirs(&cwd, manifest_dir()) {
+        if try_exists(dir.join(LOCKFILE_NAME))
+            .await
+            .unwrap_or_default()
+        {
+            return Some(dir.to_path_buf());
+        }
+    }
     None
 }
 
+/**
+    Discovers the fallthrough setting for the runner, by searching for Rokit
+    manifests in the current directory and its ancestors, and then in the
+    global Rokit manifest, using the first manifest that has the setting.
+
+    Returns the default setting if no manifest has the setting.
+*/
+pub async fn discover_fallthrough() -> Fallthrough {
+    let cwd = current_dir().await;
+    let dirs = project_dirs(&cwd, manifest_dir());
+    discover_fallthrough_in_dirs(&dirs).await
+}
+
+/**
+    Discovers the fallthrough setting for the runner in the given directory and its ancestors.
+
+    This is the same as [`discover_fallthrough`], but for any directory, such as a project
+    opened in an editor - the manifest directory set using [`set_manifest_dir`] is not used.
+*/
+pub async fn discover_fallthrough_in(dir: &Path) -> Fallthrough {
+    let dirs = dir.ancestors().collect::<Vec<_>>();
+    discover_fallthrough_in_dirs(&dirs).await
+}
+
+async fn discover_fallthrough_in_dirs(dirs: &[&Path]) -> Fallthrough {
+    for (kind, path) in search_paths_in(dirs, true, false, false) {
+        let Some(ParsedManifest::Rokit(manifest)) = load_manifest(kind, &path).await else {
+            continue;
+        };
+        if let Some(fallthrough) = manifest.get_fallthrough() {
+            return fallthrough;
+        }
+    }
+
+    Fallthrough::default()
+}
+
+/*
+    Discovers a setting using the given getter, by searching for Rokit manifests
+    in the current directory and its ancestors, and then in the global Rokit
+    manifest, returning the value from the first manifest that has the setting.
+*/
+async fn discover_first_setting<T>(get: impl Fn(&RokitManifest) -> Option<T>) -> Option<T> {
+    let cwd = current_dir().await;
+
+    for (kind, path) in search_paths(&cwd, true, false, false) {
+        let Some(ParsedManifest::Rokit(manifest)) = load_manifest(kind, &path).await else {
+            continue;
+        };
+        if let Some(value) = get(&manifest) {
+            return Some(value);
+        }
+    }
+
+    None
+}
+
+/*
+    Discovers a setting using the given getter, the same way as [`discover_first_setting`],
+    but returning the values from all manifests that have the setting, nearest first.
+*/
+async fn discover_all_settings<T>(get: impl Fn(&RokitManifest) -> Option<T>) -> Vec<T> {
+    let cwd = current_dir().await;
+
+    let mut values = Vec::new();
+    for (kind, path) in search_paths(&cwd, true, false, false) {
+        let Some(ParsedManifest::Rokit(manifest)) = load_manifest(kind, &path).await else {
+            continue;
+        };
+        values.extend(get(&manifest));
+    }
+
+    values
+}
+
+/**
+    Discovers the rules for deriving default aliases of newly added tools,
+    by searching for Rokit manifests in the current directory and its ancestors,
+    and then in the global Rokit manifest, using the first manifest that has them.
+
+    Returns rules that keep tool names as-is if no manifest has them.
+*/
+pub async fn discover_alias_rules() -> AliasRules {
+    discover_first_setting(RokitManifest::get_alias_rules)
+        .await
+        .unwrap_or_default()
+}
+
+/**
+    Discovers user-defined command aliases, by searching for Rokit manifests
+    in the current directory and its ancestors, and then in the global Rokit
+    manifest - aliases in nearer manifests take precedence over farther ones.
+
+    Returns an empty map if no manifest defines any command aliases.
+*/
+pub async fn discover_command_aliases() -> BTreeMap<String, Vec<String>> {
+    let mut aliases = BTreeMap::new();
+    for manifest_aliases in discover_all_settings(RokitManifest::get_command_aliases).await {
+        for (name, args) in manifest_aliases {
+            aliases.entry(name).or_insert(args);
+        }
+    }
+
+    aliases
+}
+
+/**
+    Discovers the strict platform setting, by searching for Rokit manifests
+    in the current directory and its ancestors, and then in the global
+    Rokit manifest, using the first manifest that has the setting.
+
+    Returns `false` if no manifest has the setting.
+*/
+pub async fn discover_strict_platform() -> bool {
+    discover_first_setting(RokitManifest::get_strict_platform)
+        .await
+        .unwrap_or_default()
+}
+
+/**
+    Discovers the limits for extracting artifacts, by searching for Rokit
+    manifests in the current directory and its ancestors, and then in the
+    global Rokit manifest, using the first manifest that has the setting.
+
+    Returns the default limits if no manifest has the setting.
+*/
+pub async fn discover_extract_limits() -> ExtractLimits {
+    discover_first_setting(RokitManifest::get_extract_limits)
+        .await
+        .unwrap_or_default()
+}
+
+/**
+    The default size in megabytes above which artifacts are
+    considered unusually large - see [`discover_large_download_threshold`].
+*/
+pub const DEFAULT_LARGE_DOWNLOAD_THRESHOLD_MB: u64 = 100;
+
+/**
+    Discovers the size in bytes above which artifacts are considered unusually
+    large, by searching for Rokit manifests in the current directory and its
+    ancestors, and then in the global Rokit manifest.
+
+    Returns `None` if the nearest manifest setting it has disabled the warning,
+    or the default of [`DEFAULT_LARGE_DOWNLOAD_THRESHOLD_MB`] if no manifest sets it.
+*/
+pub async fn discover_large_download_threshold() -> Option<u64> {
+    let megabytes = discover_first_setting(RokitManifest::get_large_download_threshold)
+        .await
+        .unwrap_or(DEFAULT_LARGE_DOWNLOAD_THRESHOLD_MB);
+
+    (megabytes > 0).then(|| megabytes * 1024 * 1024)
+}
+
 /**
     Discovers a tool explicitly **not** managed by Rokit,
     by traversing the system PATH environment variable.
@@ -179,14 +841,308 @@ pub async fn discover_non_rokit_tool(home: &Home, alias: &ToolAlias) -> Option<P
     let cwd = current_dir().await;
 
     let binary_name = alias.name().to_string();
-    let home_path = home.path().to_owned();
     let search_paths = var_os("PATH")?;
 
-    let mut found_tool_paths = which::which_in_all(binary_name, Some(search_paths), &cwd)
-        .ok()
+    // NOTE: The bin directory may be customized to live outside of the
+    // home directory, and must never be found here, since running the
+    // Rokit link for the alias would just end up right back in Rokit
+    let rokit_dirs = [home.path(), home.bin_dir()];
+    let found_tool_paths = which::which_in_all(binary_name, Some(search_paths), &cwd).ok()?;
+
+    find_non_rokit_tool(found_tool_paths, &rokit_dirs)
+}
+
+fn find_non_rokit_tool(
+    executables: impl IntoIterator<Item = PathBuf>,
+    rokit_dirs: &[&Path],
+) -> Option<PathBuf> {
+    executables
+        .into_iter()
+        .find(|path| !rokit_dirs.iter().any(|dir| path.starts_with(dir)))
+}
+
+/**
+    A tool alias declared in a manifest, which resolves to an executable
+    that is not managed by Rokit, since it comes earlier in the PATH.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShadowedTool {
+    pub alias: ToolAlias,
+    /**
+        All executables found for the alias, in the order that they appear
+        in the PATH - the first one is the executable that actually runs.
+    */
+    pub executables: Vec<PathBuf>,
+    /**
+        The Rokit link for the alias, if the Rokit bin directory is in the PATH.
+    */
+    pub rokit_link: Option<PathBuf>,
+}
+
+impl ShadowedTool {
+    /**
+        Gets the executable that runs when the alias is used.
+    */
+    #[must_use]
+    pub fn resolved(&self) -> &Path {
+        &self.executables[0]
+    }
+}
+
+/**
+    Discovers all tool aliases declared in the given manifests, for which
+    a different executable than the Rokit link runs when using the alias,
+    such as a tool installed by another package manager earlier in the PATH.
+
+    Relative directories in the PATH are resolved against the current directory.
+*/
+pub async fn discover_shadowed_tools(
+    home: &Home,
+    manifests: &[DiscoveredManifest],
+) -> Vec<ShadowedTool> {
+    let cwd = current_dir().await;
+    let Some(search_paths) = var_os("PATH") else {
+        return Vec::new();
+    };
+
+    let rokit_dirs = [home.path(), home.bin_dir()];
+    let aliases = manifests
+        .iter()
+        .flat_map(|manifest| manifest.tools.keys().cloned())
+        .collect::<BTreeSet<_>>();
+
+    aliases
         .into_iter()
-        .flatten()
-        .filter(|path| !path.starts_with(&home_path));
+        .filter_map(|alias| {
+            let executables = which::which_in_all(alias.name(), Some(&search_paths), &cwd)
+                .ok()?
+                .collect::<Vec<_>>();
+            find_shadowed_tool(alias, executables, &rokit_dirs)
+        })
+        .collect()
+}
+
+fn find_shadowed_tool(
+    alias: ToolAlias,
+    executables: Vec<PathBuf>,
+    rokit_dirs: &[&Path],
+) -> Option<ShadowedTool> {
+    // NOTE: The same directory may be in the PATH more than once
+    let mut seen = HashSet::new();
+    let executables = executables
+        .into_iter()
+        .filter(|path| seen.insert(path.clone()))
+        .collect::<Vec<_>>();
+
+    let is_rokit = |path: &PathBuf| rokit_dirs.iter().any(|dir| path.starts_with(dir));
+    if executables.first().map_or(true, is_rokit) {
+        return None;
+    }
 
-    found_tool_paths.next()
+    let rokit_link = executables.iter().find(|path| is_rokit(path)).cloned();
+    Some(ShadowedTool {
+        alias,
+        executables,
+        rokit_link,
+    })
+}
+
+/**
+    A tool alias declared for different tools in several manifests,
+    such as `author-a/tool` globally, and `author-b/tool` in a project.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AliasCollision {
+    pub alias: ToolAlias,
+    /**
+        All tools declared for the alias, along with the path of the manifest
+        declaring each one, in order of precedence - the first tool is the one
+        that the alias actually runs, and all others are shadowed by it.
+    */
+    pub declarations: Vec<(ToolSpec, PathBuf)>,
+}
+
+impl AliasCollision {
+    /**
+        Gets the tool that runs when the alias is used.
+    */
+    #[must_use]
+    pub fn resolved(&self) -> &ToolSpec {
+        &self.declarations[0].0
+    }
+}
+
+/**
+    Finds all tool aliases that are declared for different tools in the given
+    manifests, which should be in order of precedence, nearest first.
+
+    Declaring different versions of the same tool is not a collision,
+    since that is how projects override the version of a global tool.
+*/
+#[must_use]
+pub fn find_alias_collisions(manifests: &[DiscoveredManifest]) -> Vec<AliasCollision> {
+    let mut declarations = BTreeMap::<ToolAlias, Vec<(ToolSpec, PathBuf)>>::new();
+    for manifest in manifests {
+        for (alias, spec) in &manifest.tools {
+            declarations
+                .entry(alias.clone())
+                .or_default()
+                .push((spec.clone(), manifest.path.clone()));
+        }
+    }
+
+    declarations
+        .into_iter()
+        .filter(|(_, declared)| {
+            let first = declared[0].0.id();
+            declared.iter().any(|(spec, _)| spec.id() != first)
+        })
+        .map(|(alias, declarations)| AliasCollision {
+            alias,
+            declarations,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alias_collisions_between_authors() {
+        let manifest = |path: &str, tools: &[(&str, &str)]| DiscoveredManifest {
+            kind: ManifestKind::Rokit,
+            path: PathBuf::from(path),
+            tools: tools
+                .iter()
+                .map(|(alias, spec)| (alias.parse().unwrap(), spec.parse().unwrap()))
+                .collect(),
+            options: HashMap::new(),
+        };
+        let manifests = [
+            manifest(
+                "project/rokit.toml",
+                &[
+                    ("tool", "author-b/tool@2.0.0"),
+                    ("rojo", "rojo-rbx/rojo@7.4.4"),
+                ],
+            ),
+            manifest(
+                "home/rokit.toml",
+                &[
+                    ("tool", "author-a/tool@1.0.0"),
+                    ("rojo", "rojo-rbx/rojo@7.3.0"),
+                ],
+            ),
+        ];
+
+        let collisions = find_alias_collisions(&manifests);
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].alias.name(), "tool");
+        assert_eq!(collisions[0].resolved().author(), "author-b");
+        assert_eq!(
+            collisions[0].declarations[1],
+            (
+                "author-a/tool@1.0.0".parse().unwrap(),
+                PathBuf::from("home/rokit.toml")
+            )
+        );
+
+        assert!(find_alias_collisions(&manifests[..1]).is_empty());
+    }
+
+    #[test]
+    fn project_dirs_use_manifest_dir() {
+        let cwd = Path::new("/projects/game/src");
+        assert_eq!(
+            project_dirs(cwd, None),
+            [
+                Path::new("/projects/game/src"),
+                Path::new("/projects/game"),
+                Path::new("/projects"),
+                Path::new("/"),
+            ]
+        );
+        assert_eq!(
+            project_dirs(cwd, Some(Path::new("/other/project"))),
+            [Path::new("/other/project")]
+        );
+    }
+
+    #[tokio::test]
+    async fn discover_tools_in_any_directory() {
+        let project = tempfile::tempdir().unwrap();
+        let nested = project.path().join("src").join("client");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(
+            project.path().join("rokit.toml"),
+            "[tools]\nrojo = \"rojo-rbx/rojo@7.4.4\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            nested.join("foreman.toml"),
+            "[tools]\nrojo = { github = \"other/rojo\", version = \"1.0.0\" }\n",
+        )
+        .unwrap();
+
+        let alias = "rojo".parse::<ToolAlias>().unwrap();
+
+        // Nearer manifests take precedence, unless only Rokit manifests are searched
+        let tool = discover_tool_spec_in(&nested, &alias, false, true)
+            .await
+            .unwrap();
+        assert_eq!(tool.spec.id().author(), "other");
+        assert_eq!(tool.kind, ManifestKind::Foreman);
+
+        let tool = discover_tool_spec_in(&nested, &alias, true, true)
+            .await
+            .unwrap();
+        assert_eq!(tool.spec.to_string(), "rojo-rbx/rojo@7.4.4");
+        assert_eq!(tool.manifest_path, project.path().join("rokit.toml"));
+
+        let manifests = discover_all_manifests_in(&nested, false, true).await;
+        assert_eq!(manifests.len(), 2);
+
+        let consulted = trace_tool_discovery_in(project.path(), &alias, false, true).await;
+        assert!(consulted.iter().any(|consulted| consulted.spec.is_some()));
+    }
+
+    #[test]
+    fn shadowed_tools_are_found() {
+        let alias = "rojo".parse::<ToolAlias>().unwrap();
+        let rokit_dirs = [Path::new("/home/user/.rokit")];
+        let rokit_link = PathBuf::from("/home/user/.rokit/bin/rojo");
+        let other = PathBuf::from("/opt/homebrew/bin/rojo");
+
+        let not_shadowed = vec![rokit_link.clone(), other.clone()];
+        assert_eq!(
+            find_shadowed_tool(alias.clone(), not_shadowed, &rokit_dirs),
+            None
+        );
+        assert_eq!(find_shadowed_tool(alias.clone(), vec![], &rokit_dirs), None);
+
+        let shadowed = vec![other.clone(), other.clone(), rokit_link.clone()];
+        let tool = find_shadowed_tool(alias.clone(), shadowed, &rokit_dirs).unwrap();
+        assert_eq!(tool.resolved(), other);
+        assert_eq!(tool.executables, [other.clone(), rokit_link.clone()]);
+        assert_eq!(tool.rokit_link, Some(rokit_link));
+
+        let tool = find_shadowed_tool(alias, vec![other.clone()], &rokit_dirs).unwrap();
+        assert_eq!(tool.rokit_link, None);
+    }
+
+    #[test]
+    fn non_rokit_tools_skip_custom_bin_dirs() {
+        let rokit_dirs = [Path::new("/home/user/.rokit"), Path::new("/opt/rokit/bin")];
+        let rokit_link = PathBuf::from("/opt/rokit/bin/rojo");
+        let rokit_tool = PathBuf::from("/home/user/.rokit/tool-storage/rojo");
+        let other = PathBuf::from("/usr/local/bin/rojo");
+
+        let found = vec![rokit_link.clone(), rokit_tool.clone(), other.clone()];
+        assert_eq!(find_non_rokit_tool(found, &rokit_dirs), Some(other));
+        assert_eq!(
+            find_non_rokit_tool(vec![rokit_link, rokit_tool], &rokit_dirs),
+            None
+        );
+    }
 }
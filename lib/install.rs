@@ -0,0 +1,856 @@
+/*!
+    Planning for installing tools from discovered manifests, which
+    describes everything that installing would do, without doing it,
+    and executing those plans, with support for progress and cancellation.
+*/
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt,
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use futures::{future::try_join_all, stream::FuturesUnordered, StreamExt};
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+
+use crate::{
+    descriptor::Descriptor,
+    discovery::DiscoveredManifest,
+    manifests::{
+        exclude_artifacts, find_asset_artifact, verify_artifact_sha256, LockedArtifact, LockedTool,
+        Lockfile, ToolOptions,
+    },
+    result::{RokitError, RokitResult},
+    sources::{Artifact, ArtifactProvider, ArtifactSource, ExtractLimits},
+    storage::{Home, InstalledToolMetadata},
+    tool::{ToolAlias, ToolSpec},
+};
+
+/**
+    Options for planning an install - see [`plan`].
+*/
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlanOptions {
+    /**
+        Install tools even if they are already installed.
+    */
+    pub force: bool,
+    /**
+        Plan to install tools that have not been trusted yet,
+        instead of skipping them - note that installing may still
+        prompt the user to trust them, depending on the caller.
+    */
+    pub skip_trust_check: bool,
+}
+
+/**
+    Options for executing an install plan - see [`execute`].
+*/
+#[derive(Debug, Clone, Default)]
+pub struct ExecuteOptions {
+    /**
+        Only install artifacts that are fully compatible with the current
+        system, instead of falling back to artifacts for the same operating
+        system with a different architecture, that may run using emulation.
+    */
+    pub strict_platform: bool,
+    /**
+        Limits for extracting artifacts, which archives that
+        decompress to more data than expected are rejected by.
+    */
+    pub extract_limits: ExtractLimits,
+    /**
+        Stop installing tools as soon as any tool fails to install,
+        instead of installing all other tools that do not require it.
+    */
+    pub fail_fast: bool,
+    /**
+        Token for cancelling the install - once cancelled, ongoing
+        downloads and extractions are abandoned as soon as possible.
+    */
+    pub cancellation: CancellationToken,
+}
+
+/**
+    The reason for skipping a tool when installing.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /**
+        The tool is already installed, and its binary exists.
+    */
+    AlreadyInstalled,
+    /**
+        The tool has not been trusted.
+    */
+    NotTrusted,
+    /**
+        The tool requires another tool that is not declared in any manifest.
+    */
+    MissingRequirement(ToolAlias),
+    /**
+        The tool requires another tool that is neither installed,
+        nor going to be installed, such as a tool that is not trusted.
+    */
+    RequirementNotInstalled(ToolAlias),
+    /**
+        The tool requires another tool that failed to install.
+    */
+    RequirementFailed(ToolAlias),
+}
+
+impl SkipReason {
+    /**
+        Returns `true` if the tool can not be installed for this reason,
+        as opposed to not needing to be, or not being allowed to be, installed.
+    */
+    #[must_use]
+    pub fn is_failure(&self) -> bool {
+        matches!(
+            self,
+            Self::MissingRequirement(_)
+                | Self::RequirementNotInstalled(_)
+                | Self::RequirementFailed(_)
+        )
+    }
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AlreadyInstalled => write!(f, "already installed"),
+            Self::NotTrusted => write!(f, "not trusted"),
+            Self::MissingRequirement(alias) => {
+                write!(
+                    f,
+                    "requires '{alias}', which is not declared in any manifest"
+                )
+            }
+            Self::RequirementNotInstalled(alias) => {
+                write!(f, "requires '{alias}', which is not trusted or installed")
+            }
+            Self::RequirementFailed(alias) => {
+                write!(f, "requires '{alias}', which failed to install")
+            }
+        }
+    }
+}
+
+/**
+    The reason for creating a tool link when installing.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkReason {
+    /**
+        The link does not exist yet.
+    */
+    Missing,
+    /**
+        The link exists, but is recreated in case it has been corrupted.
+    */
+    Recreate,
+}
+
+impl fmt::Display for LinkReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing => write!(f, "link is missing"),
+            Self::Recreate => write!(f, "link exists, and is recreated"),
+        }
+    }
+}
+
+/**
+    A single action taken when installing tools.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstallAction {
+    /**
+        Fetch the release for the tool, and find its most compatible artifact,
+        ignoring any assets with names matching the given exclude patterns -
+        or the artifact for the asset with exactly the given name, if set.
+
+        Tools required by the tool, by alias, are installed before it.
+    */
+    Resolve {
+        spec: ToolSpec,
+        exclude_assets: Vec<String>,
+        asset: Option<String>,
+        requires: BTreeMap<ToolAlias, ToolSpec>,
+    },
+    /**
+        Download the artifact for the tool, and verify it
+        against the given SHA-256 hash, if there is one.
+    */
+    Download {
+        spec: ToolSpec,
+        sha256: Option<String>,
+    },
+    /**
+        Extract the tool binary, or all files if `extract_all` is
+        set, optionally only using files matching the given pattern.
+    */
+    Extract {
+        spec: ToolSpec,
+        extract_all: bool,
+        file_pattern: Option<String>,
+    },
+    /**
+        Create the link for running the tool using the given alias.
+    */
+    Link {
+        alias: ToolAlias,
+        reason: LinkReason,
+    },
+    /**
+        Skip installing the tool, for the given reason.
+    */
+    Skip { spec: ToolSpec, reason: SkipReason },
+}
+
+/**
+    A plan for installing tools - all actions, in the order they would be taken.
+
+    Resolving, downloading, and extracting happen once per tool specification,
+    even if several aliases or manifests use the same tool, and all links are
+    created after all tools have been installed. Tools that require other tools
+    come after the tools they require, and tools that do not require each other
+    may be installed concurrently, with their actions taken in any order.
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstallPlan {
+    pub actions: Vec<InstallAction>,
+}
+
+impl InstallPlan {
+    /**
+        Gets all tools that would be installed by this plan.
+    */
+    #[must_use]
+    pub fn tools_to_install(&self) -> Vec<&ToolSpec> {
+        self.actions
+            .iter()
+            .filter_map(|action| match action {
+                InstallAction::Resolve { spec, .. } => Some(spec),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /**
+        Gets all tools in this plan, both those that would be
+        installed, and those that would be skipped.
+    */
+    #[must_use]
+    pub fn tools(&self) -> Vec<&ToolSpec> {
+        self.actions
+            .iter()
+            .filter_map(|action| match action {
+                InstallAction::Resolve { spec, .. } | InstallAction::Skip { spec, .. } => {
+                    Some(spec)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/**
+    The outcome of installing a single tool - see [`execute`].
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstallOutcome {
+    /**
+        The tool was installed, after downloading the given
+        number of bytes, which took the given amount of time.
+    */
+    Installed { downloaded: u64, elapsed: Duration },
+    /**
+        The tool was skipped, for a reason that is not a failure.
+    */
+    Skipped(SkipReason),
+}
+
+/**
+    Progress made while executing an install plan - see [`execute`].
+*/
+#[derive(Debug, Clone, Copy)]
+pub enum InstallProgress<'a> {
+    /**
+        An action has completed - this is also used for tools that are skipped
+        while executing, since a tool they require failed to install.
+    */
+    Completed(&'a InstallAction),
+    /**
+        An artifact was chosen for a tool, out of all
+        artifacts in its release, and is about to be downloaded.
+    */
+    ArtifactChosen {
+        artifact: &'a Artifact,
+        release_artifacts: &'a [Artifact],
+    },
+    /**
+        The binary for a tool was extracted from its
+        artifact, and is about to be written to storage.
+    */
+    BinaryExtracted {
+        artifact: &'a Artifact,
+        release_artifacts: &'a [Artifact],
+        binary: &'a [u8],
+    },
+}
+
+/**
+    Plans installing all tools in the given manifests, without
+    downloading, extracting, or writing anything to disk.
+
+    Manifests should be in order of precedence, nearest first, such as
+    manifests from [`discover_all_manifests`], since the first options
+    found for a tool, and the nearest tool for an alias, are used.
+
+    [`discover_all_manifests`]: crate::discovery::discover_all_manifests
+*/
+pub async fn plan(
+    home: &Home,
+    manifests: &[DiscoveredManifest],
+    options: PlanOptions,
+) -> InstallPlan {
+    let tool_cache = home.tool_cache();
+    let tool_storage = home.tool_storage();
+
+    // NOTE: Aliases and options resolve the same way as when
+    // installing tools and running them - nearest manifest first
+    let mut aliases = BTreeMap::new();
+    let mut tool_options = HashMap::<ToolSpec, ToolOptions>::new();
+    for manifest in manifests {
+        for (alias, spec) in &manifest.tools {
+            aliases.entry(alias.clone()).or_insert_with(|| spec.clone());
+            if let Some(opts) = manifest.options.get(alias) {
+                tool_options
+                    .entry(spec.clone())
+                    .or_insert_with(|| opts.clone());
+            }
+        }
+    }
+    let specs = manifests
+        .iter()
+        .flat_map(|manifest| manifest.tools.values().cloned())
+        .collect::<BTreeSet<_>>();
+
+    let mut installed = HashSet::new();
+    let mut skip_reasons = HashMap::new();
+    for spec in &specs {
+        let opts = tool_options.get(spec);
+        let missing = opts
+            .into_iter()
+            .flat_map(|opts| &opts.requires)
+            .find(|required| !aliases.contains_key(*required));
+        // NOTE: Binaries may go missing even when recorded as installed,
+        // such as when only part of the Rokit home was restored from a cache
+        let is_installed =
+            tool_cache.is_installed(spec) && tool_storage.has_tool_binary(spec).await;
+        if is_installed {
+            installed.insert(spec.clone());
+        }
+
+        let skip_reason = if let Some(required) = missing {
+            Some(SkipReason::MissingRequirement(required.clone()))
+        } else if !options.skip_trust_check && !tool_cache.is_trusted_spec(spec) {
+            Some(SkipReason::NotTrusted)
+        } else if is_installed && !options.force {
+            Some(SkipReason::AlreadyInstalled)
+        } else {
+            None
+        };
+        if let Some(reason) = skip_reason {
+            skip_reasons.insert(spec.clone(), reason);
+        }
+    }
+
+    let requirements = specs
+        .iter()
+        .map(|spec| {
+            let requires = tool_options
+                .get(spec)
+                .into_iter()
+                .flat_map(|opts| &opts.requires)
+                .filter_map(|alias| Some((alias.clone(), aliases.get(alias)?.clone())))
+                .collect::<BTreeMap<_, _>>();
+            (spec.clone(), requires)
+        })
+        .collect::<HashMap<_, _>>();
+
+    // NOTE: Tools requiring tools that will not be installed can not be installed
+    // either, and since those tools may be required by other tools in turn,
+    // this is repeated until no more tools need to be skipped for that reason
+    loop {
+        let newly_skipped = specs
+            .iter()
+            .filter(|spec| !skip_reasons.contains_key(*spec))
+            .find_map(|spec| {
+                requirements[spec].iter().find_map(|(alias, required)| {
+                    let will_install = installed.contains(required)
+                        || (required != spec && !skip_reasons.contains_key(required));
+                    let reason = SkipReason::RequirementNotInstalled(alias.clone());
+                    (!will_install).then(|| (spec.clone(), reason))
+                })
+            });
+        let Some((spec, reason)) = newly_skipped else {
+            break;
+        };
+        skip_reasons.insert(spec, reason);
+    }
+
+    let mut actions = Vec::new();
+    for spec in install_levels(&specs, &requirements).into_iter().flatten() {
+        if let Some(reason) = skip_reasons.remove(&spec) {
+            actions.push(InstallAction::Skip { spec, reason });
+            continue;
+        }
+
+        let opts = tool_options.get(&spec).cloned().unwrap_or_default();
+        let system = Descriptor::current_system();
+        let sha256 = opts.sha256_for(&system).map(ToString::to_string);
+        actions.push(InstallAction::Resolve {
+            spec: spec.clone(),
+            asset: opts.asset_for(&system).map(ToString::to_string),
+            exclude_assets: opts.exclude_assets,
+            requires: requirements[&spec].clone(),
+        });
+        actions.push(InstallAction::Download {
+            spec: spec.clone(),
+            sha256,
+        });
+        actions.push(InstallAction::Extract {
+            spec,
+            extract_all: opts.extract_all,
+            file_pattern: opts.file,
+        });
+    }
+
+    for alias in aliases.into_keys() {
+        let reason = if tool_storage.has_tool_link(&alias).await {
+            LinkReason::Recreate
+        } else {
+            LinkReason::Missing
+        };
+        actions.push(InstallAction::Link { alias, reason });
+    }
+
+    InstallPlan { actions }
+}
+
+/**
+    Executes the given install plan, and returns the outcome of installing
+    each tool in it, ordered by tool specification.
+
+    Tools are installed after all of the tools they require, and tools that do not
+    require each other are installed concurrently. All tools are installed to completion,
+    so that one broken tool does not prevent the rest of them from being installed,
+    unless failing fast - tools that require a tool that failed to install are skipped.
+    Tools that can not be installed, according to the plan, are reported as failures,
+    using [`RokitError::UnmetRequirement`]. Links are created after installing all tools.
+
+    The `progress` callback is called after each action has completed,
+    including actions for skipped tools, which makes it possible to track
+    progress using the number of actions in the plan.
+
+    Installing can be cancelled using the token in the given options.
+    Cancelling abandons any ongoing download or extraction, and removes
+    partially downloaded files, but tools that have already been written
+    to storage, and links that have been created, are kept as-is.
+
+    Note that the home must be saved afterwards, for any
+    installed tools to be remembered by future Rokit invocations.
+
+    # Errors
+
+    - If the install was cancelled.
+    - If a link could not be written.
+*/
+pub async fn execute(
+    home: &Home,
+    source: &ArtifactSource,
+    plan: &InstallPlan,
+    options: &ExecuteOptions,
+    progress: impl FnMut(InstallProgress<'_>),
+) -> RokitResult<Vec<(ToolSpec, RokitResult<InstallOutcome>)>> {
+    let cancel = &options.cancellation;
+    let tool_storage = home.tool_storage();
+
+    // NOTE: Tools are installed concurrently, but progress must
+    // still be reported to the caller one event at a time
+    let progress = Mutex::new(progress);
+    let report = |event: InstallProgress<'_>| {
+        let mut progress = progress.lock().expect("progress callback panicked");
+        progress(event);
+    };
+
+    let mut tool_actions = BTreeMap::<ToolSpec, Vec<&InstallAction>>::new();
+    let mut requirements = HashMap::<ToolSpec, BTreeMap<ToolAlias, ToolSpec>>::new();
+    let mut results = Vec::new();
+    let mut links = Vec::new();
+    for action in &plan.actions {
+        match action {
+            InstallAction::Resolve { spec, requires, .. } => {
+                requirements.insert(spec.clone(), requires.clone());
+                tool_actions.entry(spec.clone()).or_default().push(action);
+            }
+            InstallAction::Download { spec, .. } | InstallAction::Extract { spec, .. } => {
+                tool_actions.entry(spec.clone()).or_default().push(action);
+            }
+            InstallAction::Link { alias, .. } => links.push((alias, action)),
+            InstallAction::Skip { spec, reason } => {
+                if cancel.is_cancelled() {
+                    return Err(RokitError::Cancelled);
+                }
+                let result = if reason.is_failure() {
+                    Err(RokitError::UnmetRequirement(reason.clone()))
+                } else {
+                    Ok(InstallOutcome::Skipped(reason.clone()))
+                };
+                results.push((spec.clone(), result));
+                report(InstallProgress::Completed(action));
+            }
+        }
+    }
+
+    let specs = tool_actions.keys().cloned().collect::<BTreeSet<_>>();
+    let mut failed = HashSet::new();
+    for level in install_levels(&specs, &requirements) {
+        if cancel.is_cancelled() {
+            return Err(RokitError::Cancelled);
+        }
+
+        let mut installs = FuturesUnordered::new();
+        for spec in level {
+            let requires = requirements.get(&spec).into_iter().flatten();
+            let failed_requirement = requires
+                .filter(|(_, required)| **required != spec)
+                .find(|(_, required)| failed.contains(*required));
+            if let Some((alias, _)) = failed_requirement {
+                let reason = SkipReason::RequirementFailed(alias.clone());
+                let error = RokitError::UnmetRequirement(reason.clone());
+                report(InstallProgress::Completed(&InstallAction::Skip {
+                    spec: spec.clone(),
+                    reason,
+                }));
+                results.push((spec, Err(error)));
+                continue;
+            }
+
+            let actions = &tool_actions[&spec];
+            installs.push(async move {
+                let result = install_tool(home, source, &spec, actions, options, &report).await;
+                (spec, result)
+            });
+        }
+
+        while let Some((spec, result)) = installs.next().await {
+            if matches!(result, Err(RokitError::Cancelled)) {
+                return Err(RokitError::Cancelled);
+            }
+            let is_failure = result.is_err();
+            if is_failure {
+                failed.insert(spec.clone());
+            }
+            results.push((spec, result));
+            if is_failure && options.fail_fast {
+                results.sort_by(|(a, _), (b, _)| a.cmp(b));
+                return Ok(results);
+            }
+        }
+    }
+
+    if cancel.is_cancelled() {
+        return Err(RokitError::Cancelled);
+    }
+
+    // NOTE: Links are created even if tools are already installed,
+    // in case the links have been corrupted, and the user tries
+    // to install tools again to fix them
+    try_join_all(links.into_iter().map(|(alias, action)| async {
+        tool_storage.create_tool_link(alias).await?;
+        report(InstallProgress::Completed(action));
+        RokitResult::Ok(())
+    }))
+    .await?;
+
+    results.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(results)
+}
+
+/*
+    Takes all of the given actions for a single tool, in order.
+*/
+async fn install_tool(
+    home: &Home,
+    source: &ArtifactSource,
+    spec: &ToolSpec,
+    actions: &[&InstallAction],
+    options: &ExecuteOptions,
+    report: &impl Fn(InstallProgress<'_>),
+) -> RokitResult<InstallOutcome> {
+    let cancel = &options.cancellation;
+    let tool_cache = home.tool_cache();
+    let tool_storage = home.tool_storage();
+
+    let mut resolved = None::<(Artifact, Vec<Artifact>)>;
+    let mut contents = None::<Vec<u8>>;
+    let (mut downloaded, mut elapsed) = (0, Duration::ZERO);
+
+    for action in actions {
+        if cancel.is_cancelled() {
+            return Err(RokitError::Cancelled);
+        }
+
+        match action {
+            InstallAction::Resolve {
+                exclude_assets,
+                asset,
+                ..
+            } => {
+                let resolving = resolve(
+                    source,
+                    spec,
+                    exclude_assets,
+                    asset.as_deref(),
+                    options.strict_platform,
+                );
+                let (artifact, release_artifacts) = until_cancelled(cancel, resolving).await?;
+                report(InstallProgress::ArtifactChosen {
+                    artifact: &artifact,
+                    release_artifacts: &release_artifacts,
+                });
+                resolved = Some((artifact, release_artifacts));
+            }
+            InstallAction::Download { sha256, .. } => {
+                let (artifact, release_artifacts) = match resolved.take() {
+                    Some(resolved) => resolved,
+                    None => resolve(source, spec, &[], None, options.strict_platform).await?,
+                };
+                let start = Instant::now();
+                let downloading =
+                    until_cancelled(cancel, source.download_artifact_contents(&artifact)).await;
+                if matches!(downloading, Err(RokitError::Cancelled)) {
+                    // NOTE: The download is abandoned, so it must not be resumed
+                    // later, since the artifact may have changed in the meantime
+                    source.remove_partial_download(&artifact).await?;
+                }
+                let artifact_contents = downloading?;
+                (downloaded, elapsed) = (artifact_contents.len() as u64, start.elapsed());
+                debug!(%spec, size = ?artifact.size, downloaded, ?elapsed, "downloaded artifact");
+                if let Some(expected) = sha256 {
+                    verify_artifact_sha256(spec, expected, &artifact_contents)?;
+                }
+                contents = Some(artifact_contents);
+                resolved = Some((artifact, release_artifacts));
+            }
+            InstallAction::Extract {
+                extract_all,
+                file_pattern,
+                ..
+            } => {
+                let (Some((artifact, release_artifacts)), Some(contents)) =
+                    (resolved.take(), contents.take())
+                else {
+                    debug!(%spec, "skipping extraction of tool that was not downloaded");
+                    continue;
+                };
+                let file_pattern = file_pattern.as_deref();
+                let metadata = InstalledToolMetadata::new(&artifact, &contents);
+
+                // NOTE: Writing to storage is never cancelled halfway through,
+                // since that could leave a tool with only some of its files
+                if *extract_all {
+                    let (extracted, files) = until_cancelled(
+                        cancel,
+                        artifact.extract_all_contents_matching(
+                            contents,
+                            file_pattern,
+                            options.extract_limits,
+                        ),
+                    )
+                    .await?;
+                    report(InstallProgress::BinaryExtracted {
+                        artifact: &artifact,
+                        release_artifacts: &release_artifacts,
+                        binary: &extracted,
+                    });
+                    tool_storage
+                        .replace_tool_contents_with_files(spec, extracted, &files)
+                        .await?;
+                } else {
+                    let extracted = until_cancelled(
+                        cancel,
+                        artifact.extract_contents_matching(
+                            contents,
+                            file_pattern,
+                            options.extract_limits,
+                        ),
+                    )
+                    .await?;
+                    report(InstallProgress::BinaryExtracted {
+                        artifact: &artifact,
+                        release_artifacts: &release_artifacts,
+                        binary: &extracted,
+                    });
+                    tool_storage.replace_tool_contents(spec, extracted).await?;
+                }
+                tool_storage.save_tool_metadata(spec, metadata).await?;
+
+                let _ = tool_cache.add_installed(spec.clone());
+            }
+            InstallAction::Link { .. } | InstallAction::Skip { .. } => {}
+        }
+
+        report(InstallProgress::Completed(action));
+    }
+
+    Ok(InstallOutcome::Installed {
+        downloaded,
+        elapsed,
+    })
+}
+
+/**
+    Updates the given lockfile with the tools in the given project manifests,
+    recording the artifacts that are currently installed for them on this system.
+
+    Manifests should be in order of precedence, nearest first, the same as for [`plan`].
+    Aliases that are no longer in any manifest are removed from the lockfile, and
+    artifacts recorded for other platforms are kept for tools that did not change.
+*/
+pub async fn lock(
+    home: &Home,
+    manifests: &[DiscoveredManifest],
+    mut lockfile: Lockfile,
+) -> Lockfile {
+    let tool_storage = home.tool_storage();
+    let platform = Descriptor::current_system().platform_name();
+
+    let mut aliases = BTreeMap::new();
+    for manifest in manifests {
+        for (alias, spec) in &manifest.tools {
+            aliases.entry(alias.clone()).or_insert_with(|| {
+                let opts = manifest.options.get(alias).cloned().unwrap_or_default();
+                (spec.clone(), opts)
+            });
+        }
+    }
+
+    lockfile.retain_tools(|alias| aliases.contains_key(alias));
+    for (alias, (spec, opts)) in aliases {
+        let mut tool = LockedTool::new(spec, &opts);
+        // NOTE: Tools that failed to install, or were never trusted, are still
+        // locked without an artifact for this platform, so that the lockfile
+        // always contains every tool in the project manifests
+        if let Some(metadata) = tool_storage.tool_metadata(&tool.spec).await {
+            if let Some(asset) = metadata.artifact_name() {
+                let artifact = LockedArtifact {
+                    asset: asset.to_string(),
+                    sha256: metadata.sha256().to_string(),
+                };
+                tool.artifacts.insert(platform.clone(), artifact);
+            }
+        }
+        lockfile.lock_tool(alias, tool);
+    }
+
+    lockfile
+}
+
+/*
+    Groups the given tools into levels to install in order, where every tool
+    is in a later level than the tools it requires - tools in the same level
+    do not require each other, and may be installed concurrently.
+
+    Tools that require each other, directly or indirectly, can not
+    be ordered, and are installed together in the last level instead.
+*/
+fn install_levels(
+    specs: &BTreeSet<ToolSpec>,
+    requirements: &HashMap<ToolSpec, BTreeMap<ToolAlias, ToolSpec>>,
+) -> Vec<Vec<ToolSpec>> {
+    let mut remaining = specs.clone();
+    let mut levels = Vec::new();
+    while !remaining.is_empty() {
+        let mut level = remaining
+            .iter()
+            .filter(|spec| {
+                requirements.get(*spec).map_or(true, |requires| {
+                    requires
+                        .values()
+                        .all(|required| required == *spec || !remaining.contains(required))
+                })
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        if level.is_empty() {
+            level = remaining.iter().cloned().collect();
+        }
+        for spec in &level {
+            remaining.remove(spec);
+        }
+        levels.push(level);
+    }
+    levels
+}
+
+/*
+    Fetches the release for the given tool, and finds its most compatible
+    artifact, along with all artifacts that were considered - repository
+    archives are the only artifact for their commit, and contain files
+    for all systems, so they are always compatible.
+
+    Assets named exactly are used as-is, without checking compatibility.
+*/
+async fn resolve(
+    source: &ArtifactSource,
+    spec: &ToolSpec,
+    exclude_assets: &[String],
+    asset: Option<&str>,
+    strict_platform: bool,
+) -> RokitResult<(Artifact, Vec<Artifact>)> {
+    let release = source.get_specific_release(spec).await?;
+    if spec.id().provider() == ArtifactProvider::GitHubBranch {
+        if let Some(artifact) = release.artifacts.first() {
+            return Ok((artifact.clone(), release.artifacts));
+        }
+    }
+    if let Some(asset) = asset {
+        let artifact = find_asset_artifact(spec, &release.artifacts, asset)?;
+        return Ok((artifact, release.artifacts));
+    }
+
+    let artifacts = exclude_artifacts(&release.artifacts, exclude_assets);
+    let artifact = Artifact::sort_by_system_compatibility(&artifacts)
+        .into_iter()
+        .next()
+        .or_else(|| {
+            if strict_platform {
+                None
+            } else {
+                Artifact::find_partially_compatible_fallback(&artifacts)
+            }
+        })
+        .ok_or_else(|| RokitError::no_compatible_artifact(spec, &release.artifacts))?;
+    Ok((artifact, artifacts))
+}
+
+async fn until_cancelled<T, E>(
+    cancel: &CancellationToken,
+    future: impl Future<Output = Result<T, E>>,
+) -> RokitResult<T>
+where
+    E: Into<RokitError>,
+{
+    tokio::select! {
+        biased;
+        () = cancel.cancelled() => Err(RokitError::Cancelled),
+        result = future => result.map_err(Into::into),
+    }
+}
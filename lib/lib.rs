@@ -2,9 +2,15 @@ pub(crate) mod util;
 
 pub mod descriptor;
 pub mod discovery;
+pub mod install;
 pub mod manifests;
 pub mod result;
+#[cfg(feature = "asset-selection")]
+pub mod selection;
 pub mod sources;
 pub mod storage;
 pub mod system;
 pub mod tool;
+
+#[cfg(test)]
+mod testing;
@@ -3,15 +3,18 @@
 // make library consumers think that auth manifests are meant
 // to be displayed - they are only meant to be stringified.
 
-use std::{collections::HashMap, path::Path, str::FromStr};
+use std::{collections::HashMap, fmt, path::Path, str::FromStr};
 
-use toml_edit::{DocumentMut, Formatted, Item, Value};
+use toml_edit::{value, DocumentMut, Item, Table};
 use tracing::warn;
 
 use crate::{
     result::{RokitError, RokitResult},
     sources::ArtifactProvider,
-    util::fs::{load_from_file, save_to_file},
+    util::{
+        fs::{load_from_file, path_exists, save_to_file_without_backup},
+        keychain,
+    },
 };
 
 pub const MANIFEST_FILE_NAME: &str = "auth.toml";
@@ -19,17 +22,75 @@ pub(super) const MANIFEST_DEFAULT_CONTENTS: &str = "
 # This file lists authentication tokens managed by Rokit, a toolchain manager for Roblox projects.
 # For more information, see <|REPOSITORY_URL|>
 
+# Uncomment to store tokens in the secure storage provided by your OS instead of this file
+# storage = \"keychain\"
+
 # github = \"ghp_tokenabcdef1234567890\"
+
+# Tokens may also be given for specific owners, such as GitHub organizations, like so:
+# [github]
+# default = \"ghp_tokenabcdef1234567890\"
+# my-org = \"ghp_tokenabcdef0987654321\"
 ";
 
+const STORAGE_KEY: &str = "storage";
+const DEFAULT_KEY: &str = "default";
+
+/**
+    Where authentication tokens are stored.
+
+    The default is [`AuthStorage::Plaintext`], which stores tokens directly in the auth manifest.
+*/
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AuthStorage {
+    #[default]
+    Plaintext,
+    Keychain,
+}
+
+impl AuthStorage {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Plaintext => "plaintext",
+            Self::Keychain => "keychain",
+        }
+    }
+}
+
+impl FromStr for AuthStorage {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let l = s.trim().to_lowercase();
+        match l.as_str() {
+            "plaintext" => Ok(Self::Plaintext),
+            "keychain" => Ok(Self::Keychain),
+            _ => Err(format!("unknown auth storage '{l}'")),
+        }
+    }
+}
+
+impl fmt::Display for AuthStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
 /**
     Authentication manifest file.
 
     Contains authentication tokens managed by Rokit.
+
+    If the manifest uses [`AuthStorage::Keychain`], tokens are instead stored
+    in the secure storage provided by the operating system, and are retrieved
+    from it when the manifest is loaded. Any tokens that are still present in
+    the manifest file itself will continue to be used as a fallback.
 */
 #[derive(Debug, Clone)]
 pub struct AuthManifest {
     document: DocumentMut,
+    keychain_tokens: HashMap<ArtifactProvider, String>,
+    keychain_changed: bool,
 }
 
 impl AuthManifest {
@@ -46,8 +107,11 @@ impl AuthManifest {
     */
     pub async fn load_or_create(dir: impl AsRef<Path>) -> RokitResult<Self> {
         let path = dir.as_ref().join(MANIFEST_FILE_NAME);
-        match load_from_file(path).await {
-            Ok(manifest) => Ok(manifest),
+        match load_from_file::<_, Self, _>(path).await {
+            Ok(mut manifest) => {
+                manifest.load_keychain_tokens().await?;
+                Ok(manifest)
+            }
             Err(RokitError::FileNotFound(_)) => {
                 let new = Self::default();
                 new.save(dir).await?;
@@ -57,6 +121,25 @@ impl AuthManifest {
         }
     }
 
+    /**
+        Creates a new manifest with default contents in the given directory, if one doesn't exist.
+
+        Unlike [`AuthManifest::load_or_create`], this never reads an existing manifest,
+        and never queries the keychain, so it is cheap enough to use whenever a home
+        directory is loaded - tokens should only be loaded when they are needed.
+
+        # Errors
+
+        - If the manifest did not exist, and could not be created.
+    */
+    pub(crate) async fn create_if_missing(dir: impl AsRef<Path>) -> RokitResult<()> {
+        let dir = dir.as_ref();
+        if path_exists(dir.join(MANIFEST_FILE_NAME)).await {
+            return Ok(());
+        }
+        Self::default().save(dir).await
+    }
+
     /**
         Loads the manifest from the given directory.
 
@@ -65,12 +148,27 @@ impl AuthManifest {
         # Errors
 
         - If the manifest file could not be loaded.
+        - If the manifest uses the keychain, and it could not be read.
     */
     #[tracing::instrument(skip(dir), level = "trace")]
     pub async fn load(dir: impl AsRef<Path>) -> RokitResult<Self> {
         let path = dir.as_ref().join(MANIFEST_FILE_NAME);
         tracing::trace!(?path, "Loading manifest");
-        load_from_file(path).await
+        let mut manifest: Self = load_from_file(path).await?;
+        manifest.load_keychain_tokens().await?;
+        Ok(manifest)
+    }
+
+    async fn load_keychain_tokens(&mut self) -> RokitResult<()> {
+        if self.storage() != AuthStorage::Keychain {
+            return Ok(());
+        }
+        for provider in ArtifactProvider::ALL {
+            if let Some(token) = keychain::get_token(provider).await? {
+                self.keychain_tokens.insert(provider, token);
+            }
+        }
+        Ok(())
     }
 
     /**
@@ -81,12 +179,71 @@ impl AuthManifest {
         # Errors
 
         - If the manifest file could not be saved.
+        - If tokens were changed, and the keychain could not be updated.
     */
     #[tracing::instrument(skip(self, dir), level = "trace")]
     pub async fn save(&self, dir: impl AsRef<Path>) -> RokitResult<()> {
         let path = dir.as_ref().join(MANIFEST_FILE_NAME);
         tracing::trace!(?path, "Saving manifest");
-        save_to_file(path, self.clone()).await
+
+        // NOTE: We update the keychain first, so that tokens being moved
+        // from the manifest into the keychain are never lost on failure
+        if self.keychain_changed {
+            for provider in ArtifactProvider::ALL {
+                match self.keychain_tokens.get(&provider) {
+                    Some(token) => keychain::set_token(provider, token.clone()).await?,
+                    None => keychain::delete_token(provider).await?,
+                }
+            }
+        }
+
+        // NOTE: Auth manifests may contain tokens in plaintext, so we
+        // never keep backups that would leave removed tokens on disk
+        save_to_file_without_backup(path, self.to_string()).await
+    }
+
+    /**
+        Gets where authentication tokens are stored.
+    */
+    #[must_use]
+    pub fn storage(&self) -> AuthStorage {
+        self.document
+            .get(STORAGE_KEY)
+            .and_then(|value| value.as_str())
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default()
+    }
+
+    /**
+        Sets where authentication tokens are stored, and moves
+        all existing tokens to the new storage location.
+
+        Note that only the default token for each provider is moved,
+        tokens for specific owners are always stored in the manifest.
+
+        Returns `true` if the storage changed, `false` otherwise.
+    */
+    #[must_use]
+    pub fn set_storage(&mut self, storage: AuthStorage) -> bool {
+        if self.storage() == storage {
+            return false;
+        }
+
+        let tokens = self.get_all_tokens();
+        self.keychain_tokens.clear();
+        for provider in ArtifactProvider::ALL {
+            self.remove_plaintext_token(provider);
+        }
+
+        let tab = self.document.as_table_mut();
+        tab.insert(STORAGE_KEY, value(storage.as_str()));
+
+        for (provider, token) in tokens {
+            let _ = self.set_token(provider, token);
+        }
+        self.keychain_changed = true;
+
+        true
     }
 
     /**
@@ -94,37 +251,77 @@ impl AuthManifest {
     */
     #[must_use]
     pub fn has_token(&self, artifact_provider: ArtifactProvider) -> bool {
-        self.document.contains_key(artifact_provider.as_str())
+        self.get_token(artifact_provider).is_some()
     }
 
     /**
-        Gets the authentication token for the given artifact provider.
+        Gets the default authentication token for the given artifact provider.
 
         Returns `None` if the token is not present.
     */
     #[must_use]
     pub fn get_token(&self, artifact_provider: ArtifactProvider) -> Option<String> {
-        let token = self.document.get(artifact_provider.as_str())?;
-        token.as_str().map(ToString::to_string)
+        if let Some(token) = self.keychain_tokens.get(&artifact_provider) {
+            return Some(token.clone());
+        }
+        self.plaintext_token(artifact_provider)
+    }
+
+    /**
+        Gets the authentication token to use for tools by the given owner,
+        such as a GitHub user or organization, for the given artifact provider.
+
+        Falls back to the default token for the provider if
+        there is no token specifically for the given owner.
+    */
+    #[must_use]
+    pub fn get_owner_token(
+        &self,
+        artifact_provider: ArtifactProvider,
+        owner: &str,
+    ) -> Option<String> {
+        self.get_owner_tokens(artifact_provider)
+            .remove(&owner.to_lowercase())
+            .or_else(|| self.get_token(artifact_provider))
     }
 
     /**
-        Gets all authentication tokens found in the manifest.
+        Gets all default authentication tokens found in the manifest, or the keychain.
     */
     #[must_use]
     pub fn get_all_tokens(&self) -> HashMap<ArtifactProvider, String> {
-        self.document
+        let mut tokens = ArtifactProvider::ALL
+            .into_iter()
+            .filter_map(|provider| Some((provider, self.plaintext_token(provider)?)))
+            .collect::<HashMap<_, _>>();
+        tokens.extend(self.keychain_tokens.clone());
+        tokens
+    }
+
+    /**
+        Gets all authentication tokens for specific owners for the given
+        artifact provider. This does not include the default token.
+
+        Owners are always returned in lowercase.
+    */
+    #[must_use]
+    pub fn get_owner_tokens(&self, artifact_provider: ArtifactProvider) -> HashMap<String, String> {
+        let Some(table) = self
+            .document
+            .get(artifact_provider.as_str())
+            .and_then(Item::as_table_like)
+        else {
+            return HashMap::new();
+        };
+        table
             .iter()
-            .filter_map(|(key, value)| {
-                let provider = ArtifactProvider::from_str(key).ok()?;
-                let token = value.as_str()?.to_string();
-                Some((provider, token))
-            })
+            .filter(|(key, _)| *key != DEFAULT_KEY)
+            .filter_map(|(key, value)| Some((key.to_lowercase(), value.as_str()?.to_string())))
             .collect()
     }
 
     /**
-        Sets the authentication token for the given artifact provider.
+        Sets the default authentication token for the given artifact provider.
 
         Returns `true` if the token replaced an older
         one, `false` if an older token was not present.
@@ -135,23 +332,119 @@ impl AuthManifest {
         artifact_provider: ArtifactProvider,
         token: impl Into<String>,
     ) -> bool {
+        if self.storage() == AuthStorage::Keychain {
+            let old_plaintext = self.remove_plaintext_token(artifact_provider);
+            let old = self.keychain_tokens.insert(artifact_provider, token.into());
+            self.keychain_changed = true;
+            return old.is_some() || old_plaintext.is_some();
+        }
+
+        let token = token.into();
         let tab = self.document.as_table_mut();
-        let old = tab.insert(
-            artifact_provider.as_str(),
-            Item::Value(Value::String(Formatted::new(token.into()))),
-        );
-        old.is_some()
+        if let Some(table) = tab
+            .get_mut(artifact_provider.as_str())
+            .and_then(Item::as_table_like_mut)
+        {
+            return table.insert(DEFAULT_KEY, value(token)).is_some();
+        }
+        tab.insert(artifact_provider.as_str(), value(token))
+            .is_some()
+    }
+
+    /**
+        Sets the authentication token to use for tools by the given
+        owner, such as a GitHub user or organization, for the given
+        artifact provider. Owner tokens are always stored in the manifest.
+
+        Returns `true` if the token replaced an older
+        one, `false` if an older token was not present.
+    */
+    #[must_use]
+    pub fn set_owner_token(
+        &mut self,
+        artifact_provider: ArtifactProvider,
+        owner: &str,
+        token: impl Into<String>,
+    ) -> bool {
+        let owner = owner.to_lowercase();
+        let tab = self.document.as_table_mut();
+
+        // Convert the plain default token into a table, if necessary
+        let is_table = tab
+            .get(artifact_provider.as_str())
+            .is_some_and(Item::is_table_like);
+        if !is_table {
+            let mut table = Table::new();
+            let default = tab.remove(artifact_provider.as_str());
+            if let Some(default) = default.as_ref().and_then(Item::as_str) {
+                table.insert(DEFAULT_KEY, value(default));
+            }
+            tab.insert(artifact_provider.as_str(), Item::Table(table));
+        }
+
+        let table = tab
+            .get_mut(artifact_provider.as_str())
+            .and_then(Item::as_table_like_mut)
+            .expect("provider entry was converted to a table");
+        table.insert(&owner, value(token.into())).is_some()
     }
 
     /**
-        Unsets the authentication token for the given artifact provider.
+        Unsets the default authentication token for the given artifact provider.
 
         Returns `true` if the token was removed, `false` if it was not present.
     */
     #[must_use]
     pub fn unset_token(&mut self, artifact_provider: ArtifactProvider) -> bool {
+        let old_plaintext = self.remove_plaintext_token(artifact_provider);
+        let old_keychain = self.keychain_tokens.remove(&artifact_provider);
+        if old_keychain.is_some() {
+            self.keychain_changed = true;
+        }
+        old_plaintext.is_some() || old_keychain.is_some()
+    }
+
+    /**
+        Unsets the authentication token for the given owner and artifact provider.
+
+        Returns `true` if the token was removed, `false` if it was not present.
+    */
+    #[must_use]
+    pub fn unset_owner_token(&mut self, artifact_provider: ArtifactProvider, owner: &str) -> bool {
+        let owner = owner.to_lowercase();
+        let Some(table) = self
+            .document
+            .get_mut(artifact_provider.as_str())
+            .and_then(Item::as_table_like_mut)
+        else {
+            return false;
+        };
+        let key = table
+            .iter()
+            .map(|(key, _)| key.to_string())
+            .find(|key| key.to_lowercase() == owner && key != DEFAULT_KEY);
+        key.is_some_and(|key| table.remove(&key).is_some())
+    }
+
+    fn plaintext_token(&self, artifact_provider: ArtifactProvider) -> Option<String> {
+        let item = self.document.get(artifact_provider.as_str())?;
+        let token = match item.as_table_like() {
+            Some(table) => table.get(DEFAULT_KEY)?.as_str(),
+            None => item.as_str(),
+        };
+        token.map(ToString::to_string)
+    }
+
+    fn remove_plaintext_token(&mut self, artifact_provider: ArtifactProvider) -> Option<String> {
         let tab = self.document.as_table_mut();
-        tab.remove(artifact_provider.as_str()).is_some()
+        let item = tab.get_mut(artifact_provider.as_str())?;
+        if let Some(table) = item.as_table_like_mut() {
+            let old = table.remove(DEFAULT_KEY)?;
+            old.as_str().map(ToString::to_string)
+        } else {
+            let old = tab.remove(artifact_provider.as_str())?;
+            old.as_str().map(ToString::to_string)
+        }
     }
 }
 
@@ -166,6 +459,12 @@ impl FromStr for AuthManifest {
             in manifest methods to avoid duplicate warnings being emitted.
         */
         for (key, value) in document.iter() {
+            if key == STORAGE_KEY {
+                if let Some(Err(e)) = value.as_str().map(AuthStorage::from_str) {
+                    warn!("Encountered invalid auth storage in auth manifest!\nError: {e}");
+                }
+                continue;
+            }
             if let Err(e) = ArtifactProvider::from_str(key) {
                 warn!(
                     "Encountered unknown artifact provider '{}' in auth manifest!\
@@ -173,10 +472,22 @@ impl FromStr for AuthManifest {
                     key
                 );
             }
-            if !value.is_str() {
+            if let Some(table) = value.as_table_like() {
+                for (owner, value) in table.iter() {
+                    if !value.is_str() {
+                        warn!(
+                            "Encountered invalid value for '{owner}' under artifact provider '{}' in auth manifest!\
+                            \nExpected: String\
+                            \nActual: {}",
+                            key,
+                            value.type_name()
+                        );
+                    }
+                }
+            } else if !value.is_str() {
                 warn!(
                     "Encountered invalid value for artifact provider '{}' in auth manifest!\
-                    \nExpected: String\
+                    \nExpected: String or Table\
                     \nActual: {}",
                     key,
                     value.type_name()
@@ -184,7 +495,11 @@ impl FromStr for AuthManifest {
             }
         }
 
-        Ok(Self { document })
+        Ok(Self {
+            document,
+            keychain_tokens: HashMap::new(),
+            keychain_changed: false,
+        })
     }
 }
 
@@ -199,6 +514,93 @@ impl Default for AuthManifest {
         let document = super::make_manifest_template(MANIFEST_DEFAULT_CONTENTS)
             .parse::<DocumentMut>()
             .expect("default manifest template should be valid");
-        Self { document }
+        Self {
+            document,
+            keychain_tokens: HashMap::new(),
+            keychain_changed: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "ghp_tokenabcdef1234567890";
+
+    #[test]
+    fn default_storage_is_plaintext() {
+        let manifest = AuthManifest::default();
+        assert_eq!(manifest.storage(), AuthStorage::Plaintext);
+    }
+
+    #[test]
+    fn keychain_storage_moves_tokens() {
+        let mut manifest = format!("github = \"{TOKEN}\"")
+            .parse::<AuthManifest>()
+            .unwrap();
+
+        assert!(manifest.set_storage(AuthStorage::Keychain));
+        assert!(!manifest.set_storage(AuthStorage::Keychain));
+        assert_eq!(manifest.storage(), AuthStorage::Keychain);
+        assert_eq!(
+            manifest.get_token(ArtifactProvider::GitHub).as_deref(),
+            Some(TOKEN)
+        );
+
+        // The token must no longer be stored in plaintext
+        let contents = manifest.to_string();
+        assert!(!contents.contains(TOKEN), "{contents}");
+        assert!(contents.contains("storage = \"keychain\""), "{contents}");
+
+        assert!(manifest.set_storage(AuthStorage::Plaintext));
+        assert!(manifest.to_string().contains(TOKEN));
+        assert!(manifest.keychain_tokens.is_empty());
+    }
+
+    #[test]
+    fn keychain_storage_falls_back_to_plaintext() {
+        let manifest = format!("storage = \"keychain\"\ngithub = \"{TOKEN}\"")
+            .parse::<AuthManifest>()
+            .unwrap();
+        assert_eq!(
+            manifest.get_all_tokens().get(&ArtifactProvider::GitHub),
+            Some(&TOKEN.to_string())
+        );
+    }
+
+    #[test]
+    fn owner_tokens() {
+        let mut manifest = format!("github = \"{TOKEN}\"")
+            .parse::<AuthManifest>()
+            .unwrap();
+        let github = ArtifactProvider::GitHub;
+
+        assert!(!manifest.set_owner_token(github, "My-Org", "ghp_org"));
+        assert_eq!(manifest.get_token(github).as_deref(), Some(TOKEN));
+        assert_eq!(
+            manifest.get_owner_token(github, "my-org").as_deref(),
+            Some("ghp_org")
+        );
+        assert_eq!(
+            manifest.get_owner_token(github, "other").as_deref(),
+            Some(TOKEN)
+        );
+
+        // The manifest should now use the table form, and still parse
+        let contents = manifest.to_string();
+        assert!(contents.contains("[github]"), "{contents}");
+        let manifest = contents.parse::<AuthManifest>().unwrap();
+        assert_eq!(
+            manifest.get_all_tokens().get(&github),
+            Some(&TOKEN.to_string())
+        );
+        assert_eq!(manifest.get_owner_tokens(github).len(), 1);
+
+        let mut manifest = manifest;
+        assert!(manifest.unset_token(github));
+        assert_eq!(manifest.get_token(github), None);
+        assert!(manifest.unset_owner_token(github, "MY-ORG"));
+        assert!(manifest.get_owner_tokens(github).is_empty());
     }
 }
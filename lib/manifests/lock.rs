@@ -0,0 +1,419 @@
+#![allow(clippy::to_string_trait_impl)]
+// NOTE: We don't want to implement Display here since it may
+// make library consumers think that lockfiles are meant
+// to be displayed - they are only meant to be stringified.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use toml_edit::{value, DocumentMut, Item, Table};
+
+use crate::{
+    descriptor::Descriptor,
+    discovery::{DiscoveredManifest, ManifestKind},
+    result::{RokitError, RokitResult},
+    tool::{ToolAlias, ToolSpec},
+    util::fs::{load_from_file, save_to_file_without_backup},
+};
+
+use super::ToolOptions;
+
+pub const LOCKFILE_NAME: &str = "rokit.lock";
+const LOCKFILE_HEADER: &str = "
+# This file records the exact tools installed for a project by Rokit, a toolchain manager for Roblox projects.
+# For more information, see <|REPOSITORY_URL|>
+
+# It is generated by `rokit install`, and should not be edited by hand.
+";
+
+/**
+    An artifact installed for a locked tool, on a specific platform.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockedArtifact {
+    /**
+        The name of the release asset that was installed.
+    */
+    pub asset: String,
+    /**
+        The SHA-256 hash of the downloaded asset.
+    */
+    pub sha256: String,
+}
+
+/**
+    A tool recorded in a lockfile, along with everything needed
+    to install the exact same binary for it again.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockedTool {
+    pub spec: ToolSpec,
+    pub extract_all: bool,
+    pub file: Option<String>,
+    /**
+        Artifacts installed for the tool, by platform name, such as `linux-x64`.
+    */
+    pub artifacts: BTreeMap<String, LockedArtifact>,
+}
+
+impl LockedTool {
+    /**
+        Creates a new locked tool, with no artifacts recorded yet,
+        using the options from a manifest that affect installing it.
+    */
+    #[must_use]
+    pub fn new(spec: ToolSpec, options: &ToolOptions) -> Self {
+        Self {
+            spec,
+            extract_all: options.extract_all,
+            file: options.file.clone(),
+            artifacts: BTreeMap::new(),
+        }
+    }
+
+    /**
+        Gets the artifact installed for this tool on the given system, if any.
+    */
+    #[must_use]
+    pub fn artifact_for(&self, system: &Descriptor) -> Option<&LockedArtifact> {
+        self.artifacts.get(&system.platform_name())
+    }
+
+    /**
+        Checks if this is the same tool as another locked tool, installed the same
+        way - meaning that artifacts recorded for one of them are valid for both.
+    */
+    #[must_use]
+    pub fn is_same_tool(&self, other: &Self) -> bool {
+        self.spec == other.spec && self.extract_all == other.extract_all && self.file == other.file
+    }
+}
+
+/**
+    A lockfile, named `rokit.lock`, which records the exact tools installed
+    for a project, and the artifacts that were installed for them per platform.
+
+    Unlike manifests, lockfiles contain everything needed to install the
+    same tools again, and can be installed from without any manifest.
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Lockfile {
+    tools: BTreeMap<ToolAlias, LockedTool>,
+}
+
+impl Lockfile {
+    /**
+        Loads the lockfile from the given directory.
+
+        This will search for a file named `rokit.lock` in the given directory.
+
+        # Errors
+
+        - If the lockfile could not be loaded, or is not valid.
+    */
+    #[tracing::instrument(skip(dir), level = "trace")]
+    pub async fn load(dir: impl AsRef<Path>) -> RokitResult<Self> {
+        let path = dir.as_ref().join(LOCKFILE_NAME);
+        tracing::trace!(?path, "Loading lockfile");
+        load_from_file(path).await
+    }
+
+    /**
+        Saves the lockfile to the given directory.
+
+        This will write the lockfile to a file named `rokit.lock` in the given directory.
+
+        # Errors
+
+        - If the lockfile could not be saved.
+    */
+    #[tracing::instrument(skip(self, dir), level = "trace")]
+    pub async fn save(&self, dir: impl AsRef<Path>) -> RokitResult<()> {
+        let path = dir.as_ref().join(LOCKFILE_NAME);
+        tracing::trace!(?path, "Saving lockfile");
+        // NOTE: Lockfiles are always generated, and usually committed
+        // to version control, so backups would only be clutter
+        save_to_file_without_backup(path, self.clone()).await
+    }
+
+    /**
+        Gets all tools in the lockfile, by alias.
+    */
+    #[must_use]
+    pub fn tools(&self) -> &BTreeMap<ToolAlias, LockedTool> {
+        &self.tools
+    }
+
+    /**
+        Records the given tool for the given alias, replacing any previously
+        recorded tool - artifacts recorded for the previous tool are kept
+        if it is the same tool, since those are for other platforms.
+    */
+    pub fn lock_tool(&mut self, alias: ToolAlias, mut tool: LockedTool) {
+        if let Some(previous) = self.tools.get(&alias) {
+            if previous.is_same_tool(&tool) {
+                for (platform, artifact) in &previous.artifacts {
+                    tool.artifacts
+                        .entry(platform.clone())
+                        .or_insert_with(|| artifact.clone());
+                }
+            }
+        }
+        self.tools.insert(alias, tool);
+    }
+
+    /**
+        Removes all tools with aliases not matching the given predicate.
+    */
+    pub fn retain_tools(&mut self, mut keep: impl FnMut(&ToolAlias) -> bool) {
+        self.tools.retain(|alias, _| keep(alias));
+    }
+
+    /**
+        Creates a manifest for installing the tools in this lockfile, which
+        pins the asset and SHA-256 hash of every tool that has an artifact
+        recorded for the current system.
+    */
+    #[must_use]
+    pub fn to_manifest(&self, path: impl Into<PathBuf>) -> DiscoveredManifest {
+        let system = Descriptor::current_system();
+        let mut tools = HashMap::new();
+        let mut options = HashMap::new();
+        for (alias, tool) in &self.tools {
+            let mut opts = ToolOptions {
+                extract_all: tool.extract_all,
+                file: tool.file.clone(),
+                ..ToolOptions::default()
+            };
+            if let Some(artifact) = tool.artifact_for(&system) {
+                let platform = system.platform_name();
+                opts.asset = BTreeMap::from([(platform.clone(), artifact.asset.clone())]);
+                opts.sha256 = BTreeMap::from([(platform, artifact.sha256.clone())]);
+            }
+            tools.insert(alias.clone(), tool.spec.clone());
+            options.insert(alias.clone(), opts);
+        }
+        DiscoveredManifest {
+            kind: ManifestKind::Rokit,
+            path: path.into(),
+            tools,
+            options,
+        }
+    }
+}
+
+fn parse_locked_tool(alias: &str, item: &Item) -> RokitResult<LockedTool> {
+    let invalid = |reason: &str| RokitError::InvalidLockfile(format!("tool '{alias}' {reason}"));
+    let table = item
+        .as_table_like()
+        .ok_or_else(|| invalid("is not a table"))?;
+
+    let spec = table
+        .get("source")
+        .and_then(Item::as_str)
+        .and_then(|s| s.parse::<ToolSpec>().ok())
+        .ok_or_else(|| invalid("has no valid 'source' tool specification"))?;
+    let extract_all = table
+        .get("extract-all")
+        .and_then(Item::as_bool)
+        .unwrap_or_default();
+    let file = table
+        .get("file")
+        .and_then(Item::as_str)
+        .map(ToString::to_string);
+
+    let mut artifacts = BTreeMap::new();
+    if let Some(item) = table.get("artifacts") {
+        let platforms = item
+            .as_table_like()
+            .ok_or_else(|| invalid("has 'artifacts' that are not a table"))?;
+        for (platform, item) in platforms.iter() {
+            let artifact = item.as_table_like().and_then(|artifact| {
+                let asset = artifact.get("asset")?.as_str()?.to_string();
+                let sha256 = artifact.get("sha256")?.as_str()?.to_string();
+                let is_hash = sha256.len() == 64 && sha256.chars().all(|c| c.is_ascii_hexdigit());
+                is_hash.then_some(LockedArtifact { asset, sha256 })
+            });
+            let artifact = artifact.ok_or_else(|| {
+                invalid(&format!(
+                    "has no valid 'asset' and 'sha256' for platform '{platform}'"
+                ))
+            })?;
+            artifacts.insert(platform.to_string(), artifact);
+        }
+    }
+
+    Ok(LockedTool {
+        spec,
+        extract_all,
+        file,
+        artifacts,
+    })
+}
+
+impl FromStr for Lockfile {
+    type Err = RokitError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let document = s.parse::<DocumentMut>()?;
+
+        let mut tools = BTreeMap::new();
+        if let Some(item) = document.get("tools") {
+            let table = item.as_table_like().ok_or_else(|| {
+                RokitError::InvalidLockfile(String::from("'tools' is not a table"))
+            })?;
+            for (alias, item) in table.iter() {
+                let parsed_alias = alias.parse::<ToolAlias>().map_err(|_| {
+                    RokitError::InvalidLockfile(format!("'{alias}' is not a valid tool alias"))
+                })?;
+                tools.insert(parsed_alias, parse_locked_tool(alias, item)?);
+            }
+        }
+
+        Ok(Self { tools })
+    }
+}
+
+impl ToString for Lockfile {
+    fn to_string(&self) -> String {
+        let mut tools = Table::new();
+        tools.set_implicit(true);
+        for (alias, tool) in &self.tools {
+            let mut table = Table::new();
+            table.insert("source", value(tool.spec.to_string()));
+            if tool.extract_all {
+                table.insert("extract-all", value(true));
+            }
+            if let Some(file) = &tool.file {
+                table.insert("file", value(file.as_str()));
+            }
+
+            let mut artifacts = Table::new();
+            artifacts.set_implicit(true);
+            for (platform, artifact) in &tool.artifacts {
+                let mut artifact_table = Table::new();
+                artifact_table.insert("asset", value(artifact.asset.as_str()));
+                artifact_table.insert("sha256", value(artifact.sha256.as_str()));
+                artifacts.insert(platform, Item::Table(artifact_table));
+            }
+            if !artifacts.is_empty() {
+                table.insert("artifacts", Item::Table(artifacts));
+            }
+
+            tools.insert(alias.name(), Item::Table(table));
+        }
+
+        let mut document = DocumentMut::new();
+        document.insert("tools", Item::Table(tools));
+
+        let header = super::make_manifest_template(LOCKFILE_HEADER);
+        format!("{header}\n{document}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locked_tool(spec: &str, platforms: &[&str]) -> LockedTool {
+        LockedTool {
+            spec: spec.parse().unwrap(),
+            extract_all: false,
+            file: None,
+            artifacts: platforms
+                .iter()
+                .map(|platform| {
+                    let artifact = LockedArtifact {
+                        asset: format!("tool-{platform}.zip"),
+                        sha256: "a".repeat(64),
+                    };
+                    (platform.to_string(), artifact)
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut lockfile = Lockfile::default();
+        lockfile.lock_tool(
+            "rojo".parse().unwrap(),
+            locked_tool("rojo-rbx/rojo@7.4.1", &["linux-x64", "windows-x64"]),
+        );
+        let mut selene = locked_tool("kampfkarren/selene@0.27.1", &[]);
+        selene.extract_all = true;
+        selene.file = Some(String::from("bin/selene*"));
+        lockfile.lock_tool("selene".parse().unwrap(), selene);
+
+        let contents = lockfile.to_string();
+        assert!(contents.starts_with("# This file records"));
+        assert!(contents.contains("[tools.rojo.artifacts.linux-x64]"));
+        assert_eq!(contents.parse::<Lockfile>().unwrap(), lockfile);
+    }
+
+    #[test]
+    fn artifacts_for_other_platforms_are_kept() {
+        let alias = "rojo".parse::<ToolAlias>().unwrap();
+        let mut lockfile = Lockfile::default();
+        lockfile.lock_tool(
+            alias.clone(),
+            locked_tool("rojo-rbx/rojo@7.4.1", &["linux-x64"]),
+        );
+
+        // The same tool keeps artifacts recorded on other platforms
+        lockfile.lock_tool(
+            alias.clone(),
+            locked_tool("rojo-rbx/rojo@7.4.1", &["macos-arm64"]),
+        );
+        let platforms = lockfile.tools()[&alias]
+            .artifacts
+            .keys()
+            .collect::<Vec<_>>();
+        assert_eq!(platforms, ["linux-x64", "macos-arm64"]);
+
+        // A different version of the tool does not
+        lockfile.lock_tool(
+            alias.clone(),
+            locked_tool("rojo-rbx/rojo@7.4.4", &["macos-arm64"]),
+        );
+        let platforms = lockfile.tools()[&alias]
+            .artifacts
+            .keys()
+            .collect::<Vec<_>>();
+        assert_eq!(platforms, ["macos-arm64"]);
+    }
+
+    #[test]
+    fn invalid_lockfiles_are_errors() {
+        let invalid = [
+            "[tools.rojo]\nsource = \"not a spec\"",
+            "[tools.rojo]\nsource = \"rojo-rbx/rojo@7.4.1\"\n\
+            [tools.rojo.artifacts.linux-x64]\nasset = \"rojo.zip\"\nsha256 = \"abc\"",
+            "tools = 1",
+        ];
+        for contents in invalid {
+            let err = contents.parse::<Lockfile>().unwrap_err();
+            assert!(matches!(err, RokitError::InvalidLockfile(_)), "{contents}");
+        }
+    }
+
+    #[test]
+    fn manifest_pins_current_platform() {
+        let system = Descriptor::current_system();
+        let alias = "rojo".parse::<ToolAlias>().unwrap();
+        let mut lockfile = Lockfile::default();
+        lockfile.lock_tool(
+            alias.clone(),
+            locked_tool("rojo-rbx/rojo@7.4.1", &[system.platform_name().as_str()]),
+        );
+
+        let manifest = lockfile.to_manifest(LOCKFILE_NAME);
+        assert_eq!(manifest.tools[&alias].to_string(), "rojo-rbx/rojo@7.4.1");
+        let options = &manifest.options[&alias];
+        let asset = format!("tool-{}.zip", system.platform_name());
+        assert_eq!(options.asset_for(&system), Some(asset.as_str()));
+        assert_eq!(options.sha256_for(&system), Some("a".repeat(64).as_str()));
+    }
+}
@@ -1,8 +1,14 @@
 mod auth;
+mod lock;
 mod rokit;
 
-pub use self::auth::{AuthManifest, MANIFEST_FILE_NAME as AUTH_MANIFEST_FILE_NAME};
-pub use self::rokit::{RokitManifest, MANIFEST_FILE_NAME as ROKIT_MANIFEST_FILE_NAME};
+pub use self::auth::{AuthManifest, AuthStorage, MANIFEST_FILE_NAME as AUTH_MANIFEST_FILE_NAME};
+pub use self::lock::{LockedArtifact, LockedTool, Lockfile, LOCKFILE_NAME};
+pub(crate) use self::rokit::verify_artifact_sha256;
+pub use self::rokit::{
+    exclude_artifacts, find_asset_artifact, AliasRules, Fallthrough, RokitManifest,
+    TelemetrySettings, ToolOptions, MANIFEST_FILE_NAME as ROKIT_MANIFEST_FILE_NAME,
+};
 
 /**
     Helper function to make sure our authored manifest templates
@@ -3,15 +3,29 @@
 // make library consumers think that Rokit manifests are meant
 // to be displayed - they are only meant to be stringified.
 
-use std::{path::Path, str::FromStr};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
-use toml_edit::{DocumentMut, Formatted, Item, Value};
+use glob::{MatchOptions, Pattern};
+use toml_edit::{DocumentMut, Formatted, Item, RawString, Table, TableLike, Value};
 use tracing::warn;
+use url::Url;
 
 use crate::{
+    descriptor::Descriptor,
+    discovery::forget_cached_manifest,
     result::{RokitError, RokitResult},
+    sources::{Artifact, ExtractLimits},
+    storage::{LinkStrategy, TrustScope},
     tool::{ToolAlias, ToolSpec},
-    util::fs::{load_from_file, save_to_file},
+    util::{
+        fs::{load_from_file, save_to_file},
+        hash::sha256_hex,
+    },
 };
 
 pub const MANIFEST_FILE_NAME: &str = "rokit.toml";
@@ -24,6 +38,393 @@ pub(super) const MANIFEST_DEFAULT_CONTENTS: &str = "
 [tools]
 ";
 
+/**
+    Options for running a tool, declared in a Rokit manifest:
+
+    - `env` - environment variables to set when running the tool.
+    - `cwd` - the working directory to run the tool in - either `project-root`, for
+      the directory containing the manifest, or a path relative to that directory.
+    - `prepend-args` - arguments to always pass to the tool, before any other arguments.
+    - `file` - a glob pattern for the path of the tool binary inside of downloaded
+      archives, such as `bin/tool*`, used instead of guessing which file to use.
+    - `extract-all` - if the entire archive should be extracted when installing the tool,
+      instead of only the tool binary, for tools that need files such as libraries next to them.
+    - `channel` - the release channel to stay within when updating the tool, such as
+      `nightly`, for tools that publish more than one kind of release. The `stable`
+      channel contains releases that are not prereleases, and any other channel
+      contains releases with the name of the channel in their tag.
+    - `sandbox` - if the tool should run in a restricted environment, for less trusted tools,
+      without access to credentials in environment variables or the real home directory.
+    - `requires` - aliases of other tools that this tool runs, such as a wrapper running `rojo`,
+      which are installed before this tool, and must be declared in a discovered manifest.
+    - `sha256` - the SHA-256 hash of the artifact to download for the tool, which installing
+      verifies, failing if the downloaded artifact does not match - for projects where the
+      manifest is the single reviewed source of truth for which tools may be installed.
+      Either a string for all platforms, or a table with platform keys, the same as `asset`.
+    - `exclude-assets` - glob patterns for names of release assets to never install, such as
+      `*-debug*`, for repositories with assets that confuse the automatic artifact selection.
+    - `asset` - the exact name of the release asset to install, bypassing automatic artifact
+      selection entirely - either a string for all platforms, or a table with platform keys,
+      such as `windows`, `linux-x64`, or `macos-arm64`, where the most specific key matching
+      the current system is used. A plain string is stored under the `*` key, which may also
+      be used in tables, for platforms without a more specific key.
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolOptions {
+    pub env: BTreeMap<String, String>,
+    pub cwd: Option<String>,
+    pub prepend_args: Vec<String>,
+    pub file: Option<String>,
+    pub extract_all: bool,
+    pub channel: Option<String>,
+    pub sandbox: bool,
+    pub requires: Vec<ToolAlias>,
+    pub sha256: BTreeMap<String, String>,
+    pub exclude_assets: Vec<String>,
+    pub asset: BTreeMap<String, String>,
+}
+
+impl ToolOptions {
+    /**
+        Gets the given artifacts, without any artifacts that have file names
+        matching the `exclude-assets` patterns - matched case-insensitively.
+    */
+    #[must_use]
+    pub fn exclude_artifacts(&self, artifacts: &[Artifact]) -> Vec<Artifact> {
+        exclude_artifacts(artifacts, &self.exclude_assets)
+    }
+
+    /**
+        Gets the exact name of the release asset to install on the given system,
+        from the `asset` option, using the most specific matching platform key.
+
+        Returns `None` if the option is not set, or has no key matching the system.
+    */
+    #[must_use]
+    pub fn asset_for(&self, system: &Descriptor) -> Option<&str> {
+        platform_value_for(&self.asset, *system)
+    }
+
+    /**
+        Gets the SHA-256 hash of the artifact to download on the given system,
+        from the `sha256` option, using the most specific matching platform key.
+
+        Returns `None` if the option is not set, or has no key matching the system.
+    */
+    #[must_use]
+    pub fn sha256_for(&self, system: &Descriptor) -> Option<&str> {
+        platform_value_for(&self.sha256, *system)
+    }
+
+    /**
+        Verifies the downloaded artifact contents for the given tool against the
+        `sha256` option for the given system - does nothing if no hash is set.
+
+        # Errors
+
+        - If the contents do not match the hash in the `sha256` option.
+    */
+    pub fn verify_sha256(
+        &self,
+        spec: &ToolSpec,
+        system: &Descriptor,
+        contents: &[u8],
+    ) -> RokitResult<()> {
+        match self.sha256_for(system) {
+            Some(expected) => verify_artifact_sha256(spec, expected, contents),
+            None => Ok(()),
+        }
+    }
+}
+
+/**
+    Gets the given artifacts, without any artifacts that have file names
+    matching the given `exclude-assets` patterns - matched case-insensitively.
+
+    Patterns that are not valid glob patterns are ignored.
+*/
+#[must_use]
+pub fn exclude_artifacts(artifacts: &[Artifact], patterns: &[String]) -> Vec<Artifact> {
+    let options = MatchOptions {
+        case_sensitive: false,
+        ..MatchOptions::default()
+    };
+    let patterns = patterns
+        .iter()
+        .filter_map(|pattern| Pattern::new(pattern).ok())
+        .collect::<Vec<_>>();
+    artifacts
+        .iter()
+        .filter(|artifact| {
+            let Some(file_name) = artifact.file_name() else {
+                return true;
+            };
+            !patterns
+                .iter()
+                .any(|pattern| pattern.matches_with(&file_name, options))
+        })
+        .cloned()
+        .collect()
+}
+
+/**
+    Finds the artifact for the release asset with exactly the given name,
+    as set using the `asset` option for a tool, without checking if the
+    artifact is compatible with the current system in any way.
+
+    # Errors
+
+    - If no artifact has the given asset name.
+*/
+pub fn find_asset_artifact(
+    spec: &ToolSpec,
+    artifacts: &[Artifact],
+    asset: &str,
+) -> RokitResult<Artifact> {
+    artifacts
+        .iter()
+        .find(|artifact| artifact.has_asset_name(asset))
+        .cloned()
+        .ok_or_else(|| RokitError::AssetNotFound {
+            spec: spec.clone().into(),
+            asset: asset.to_string(),
+            available: artifacts.iter().filter_map(Artifact::file_name).collect(),
+        })
+}
+
+/*
+    Key in the `asset` and `sha256` options that matches all platforms,
+    and which a plain string for the option is stored under when parsed.
+*/
+const ANY_PLATFORM_KEY: &str = "*";
+
+/*
+    Gets the value for the most specific platform key matching the
+    given system, in an option such as `asset` or `sha256`.
+*/
+fn platform_value_for(values: &BTreeMap<String, String>, system: Descriptor) -> Option<&str> {
+    values
+        .iter()
+        .filter_map(|(key, value)| {
+            let specificity = platform_key_specificity(key, system)?;
+            Some((specificity, value.as_str()))
+        })
+        .max_by_key(|(specificity, _)| *specificity)
+        .map(|(_, value)| value)
+}
+
+/*
+    Checks how specifically a platform key in an option matches the
+    given system - `None` if it does not match at all, and higher
+    values for keys that also match the architecture or toolchain.
+*/
+fn platform_key_specificity(key: &str, system: Descriptor) -> Option<u8> {
+    if key == ANY_PLATFORM_KEY {
+        return Some(0);
+    }
+    let desc = key.parse::<Descriptor>().ok()?;
+    if desc.os() != system.os() {
+        return None;
+    }
+    let mut specificity = 1;
+    if let Some(arch) = desc.arch() {
+        if Some(arch) != system.arch() {
+            return None;
+        }
+        specificity += 1;
+    }
+    if let Some(toolchain) = desc.toolchain() {
+        if Some(toolchain) != system.toolchain() {
+            return None;
+        }
+        specificity += 1;
+    }
+    Some(specificity)
+}
+
+fn is_platform_key(key: &str) -> bool {
+    key == ANY_PLATFORM_KEY || key.parse::<Descriptor>().is_ok()
+}
+
+/*
+    Checks if an option is either a valid value for all platforms,
+    or a table with platform keys and only valid values.
+*/
+fn is_platform_table(item: &Item, is_value: fn(&Item) -> bool) -> bool {
+    is_value(item)
+        || item.as_table_like().is_some_and(|t| {
+            t.iter()
+                .all(|(key, value)| is_platform_key(key) && is_value(value))
+        })
+}
+
+/*
+    Parses an option that is either a string for all platforms, or a table
+    with platform keys and string values, ignoring any invalid keys or values.
+*/
+fn parse_platform_table(item: Option<&Item>) -> BTreeMap<String, String> {
+    match item {
+        Some(item) if item.is_str() => item
+            .as_str()
+            .map(|s| BTreeMap::from([(ANY_PLATFORM_KEY.to_string(), s.to_string())]))
+            .unwrap_or_default(),
+        Some(item) => item
+            .as_table_like()
+            .map(|t| {
+                t.iter()
+                    .filter(|(key, _)| is_platform_key(key))
+                    .filter_map(|(key, value)| Some((key.to_string(), value.as_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        None => BTreeMap::new(),
+    }
+}
+
+/**
+    Verifies downloaded artifact contents for the given
+    tool against a SHA-256 hash recorded in a manifest.
+*/
+pub(crate) fn verify_artifact_sha256(
+    spec: &ToolSpec,
+    expected: &str,
+    contents: &[u8],
+) -> RokitResult<()> {
+    let actual = sha256_hex(contents);
+    if actual.eq_ignore_ascii_case(expected) {
+        Ok(())
+    } else {
+        Err(RokitError::ChecksumMismatch {
+            spec: spec.clone().into(),
+            expected: expected.to_string(),
+            actual,
+        })
+    }
+}
+
+/**
+    What the runner should do when running a tool that
+    is not in any manifest, but exists elsewhere in `PATH`:
+
+    - `allow` - run the tool from `PATH`, silently.
+    - `warn` - run the tool from `PATH`, but warn the user about it.
+    - `deny` - do not run the tool, and error instead.
+
+    The default is [`Fallthrough::Warn`].
+*/
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Fallthrough {
+    Allow,
+    #[default]
+    Warn,
+    Deny,
+}
+
+impl Fallthrough {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Warn => "warn",
+            Self::Deny => "deny",
+        }
+    }
+}
+
+impl FromStr for Fallthrough {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let l = s.trim().to_lowercase();
+        match l.as_str() {
+            "allow" => Ok(Self::Allow),
+            "warn" => Ok(Self::Warn),
+            "deny" => Ok(Self::Deny),
+            _ => Err(format!("unknown fallthrough setting '{l}'")),
+        }
+    }
+}
+
+impl fmt::Display for Fallthrough {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+/**
+    Where the runner should send records of tool invocations, declared
+    in the `[settings.telemetry]` table of the global Rokit manifest:
+
+    - `file` - a path to append invocation records to, as JSON lines.
+      Relative paths are relative to the Rokit home directory.
+    - `endpoint` - a URL to send each invocation record to, using a POST request.
+
+    Telemetry is fully disabled unless at least one of these is set.
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TelemetrySettings {
+    pub file: Option<PathBuf>,
+    pub endpoint: Option<Url>,
+}
+
+/**
+    Rules for deriving the default alias of a newly added tool from its
+    name, declared in the `[settings.alias-rules]` table of a Rokit manifest:
+
+    - `strip-prefixes` - prefixes to remove from the name, such as `roblox-`.
+    - `strip-suffixes` - suffixes to remove from the name, such as `-cli`.
+    - `lowercase` - if the name should be converted to lowercase.
+
+    Prefixes and suffixes are matched without regard to casing,
+    and only the first matching prefix and suffix are removed.
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AliasRules {
+    pub strip_prefixes: Vec<String>,
+    pub strip_suffixes: Vec<String>,
+    pub lowercase: bool,
+}
+
+impl AliasRules {
+    /**
+        Applies the rules to the given tool name, returning the
+        alias to use - names are never stripped down to nothing.
+    */
+    #[must_use]
+    pub fn apply(&self, name: &str) -> String {
+        let mut alias = name;
+        if let Some(prefix) = self
+            .strip_prefixes
+            .iter()
+            .find(|prefix| starts_with_ignore_case(alias, prefix) && alias.len() > prefix.len())
+        {
+            alias = &alias[prefix.len()..];
+        }
+        if let Some(suffix) = self
+            .strip_suffixes
+            .iter()
+            .find(|suffix| ends_with_ignore_case(alias, suffix) && alias.len() > suffix.len())
+        {
+            alias = &alias[..alias.len() - suffix.len()];
+        }
+        if self.lowercase {
+            alias.to_lowercase()
+        } else {
+            alias.to_string()
+        }
+    }
+}
+
+fn starts_with_ignore_case(s: &str, prefix: &str) -> bool {
+    s.get(..prefix.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+}
+
+fn ends_with_ignore_case(s: &str, suffix: &str) -> bool {
+    s.len() >= suffix.len()
+        && s.get(s.len() - suffix.len()..)
+            .is_some_and(|end| end.eq_ignore_ascii_case(suffix))
+}
+
 /**
     Rokit manifest file.
 
@@ -88,7 +489,36 @@ impl RokitManifest {
     pub async fn save(&self, dir: impl AsRef<Path>) -> RokitResult<()> {
         let path = dir.as_ref().join(MANIFEST_FILE_NAME);
         tracing::trace!(?path, "Saving manifest");
-        save_to_file(path, self.clone()).await
+        let result = save_to_file(&path, self.clone()).await;
+        forget_cached_manifest(&path);
+        result
+    }
+
+    /**
+        Creates a new manifest from the contents of an Aftman manifest.
+
+        Aftman manifests list tools in the same format as Rokit manifests, so
+        the contents are kept as-is, including any comments and formatting.
+        A leading comment block mentioning Aftman is replaced with the
+        comments found at the top of new Rokit manifests.
+
+        # Errors
+
+        - If the contents are not valid TOML.
+    */
+    pub fn from_aftman_manifest(contents: &str) -> RokitResult<Self> {
+        let (leading_comments, rest) = split_leading_comments(contents);
+        let rest = if leading_comments.to_ascii_lowercase().contains("aftman") {
+            rest
+        } else {
+            contents
+        };
+
+        let template = super::make_manifest_template(MANIFEST_DEFAULT_CONTENTS);
+        let header = template.trim_end().trim_end_matches("[tools]");
+        let manifest = format!("{header}{}", rest.trim_start());
+
+        manifest.parse()
     }
 
     /**
@@ -101,13 +531,458 @@ impl RokitManifest {
     }
 
     /**
-        Gets a tool specification from the manifest by its alias, if it exists.
+        Gets a tool specification from the manifest by its alias, if it exists.
+    */
+    #[must_use]
+    pub fn get_tool(&self, alias: &ToolAlias) -> Option<ToolSpec> {
+        let tools = self.document.get("tools")?.as_table()?;
+        let tool_str = tool_source(tools.get(alias.name())?)?;
+        tool_str.parse::<ToolSpec>().ok()
+    }
+
+    /**
+        Gets the options for running the tool with the given alias.
+
+        Options are declared using the table form for a tool:
+
+        ```toml
+        [tools]
+        rojo = { source = "rojo-rbx/rojo@7.4.1", env = { RUST_LOG = "info" } }
+        ```
+
+        See [`ToolOptions`] for all available options.
+        Any options with invalid values will be ignored.
+    */
+    #[must_use]
+    pub fn get_tool_options(&self, alias: &ToolAlias) -> ToolOptions {
+        let tool = self
+            .document
+            .get("tools")
+            .and_then(|t| t.as_table())
+            .and_then(|t| t.get(alias.name()))
+            .and_then(|t| t.as_table_like());
+        let Some(tool) = tool else {
+            return ToolOptions::default();
+        };
+
+        let env = tool
+            .get("env")
+            .and_then(|e| e.as_table_like())
+            .map(|e| {
+                e.iter()
+                    .filter_map(|(key, value)| Some((key.to_string(), value.as_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let cwd = tool
+            .get("cwd")
+            .and_then(|c| c.as_str())
+            .map(ToString::to_string);
+        let prepend_args = tool
+            .get("prepend-args")
+            .and_then(|a| a.as_array())
+            .map(|a| {
+                a.iter()
+                    .filter_map(|v| Some(v.as_str()?.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let file = tool
+            .get("file")
+            .and_then(|f| f.as_str())
+            .map(ToString::to_string);
+        let extract_all = tool
+            .get("extract-all")
+            .and_then(Item::as_bool)
+            .unwrap_or_default();
+        let channel = tool
+            .get("channel")
+            .and_then(|c| c.as_str())
+            .map(ToString::to_string);
+        let sandbox = tool
+            .get("sandbox")
+            .and_then(Item::as_bool)
+            .unwrap_or_default();
+        let requires = tool
+            .get("requires")
+            .and_then(|r| r.as_array())
+            .map(|r| r.iter().filter_map(|v| v.as_str()?.parse().ok()).collect())
+            .unwrap_or_default();
+        let sha256 = parse_platform_table(tool.get("sha256"))
+            .into_iter()
+            .filter(|(_, hash)| is_sha256_hex(hash))
+            .map(|(key, hash)| (key, hash.to_ascii_lowercase()))
+            .collect();
+        let exclude_assets = tool
+            .get("exclude-assets")
+            .and_then(|e| e.as_array())
+            .map(|e| {
+                e.iter()
+                    .filter_map(|v| v.as_str())
+                    .filter(|p| Pattern::new(p).is_ok())
+                    .map(ToString::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let asset = parse_platform_table(tool.get("asset"));
+
+        ToolOptions {
+            env,
+            cwd,
+            prepend_args,
+            file,
+            extract_all,
+            channel,
+            sandbox,
+            requires,
+            sha256,
+            exclude_assets,
+            asset,
+        }
+    }
+
+    /**
+        Gets the fallthrough setting for the runner, if set in this manifest:
+
+        ```toml
+        [settings]
+        fallthrough = "deny"
+        ```
+
+        See [`Fallthrough`] for more information.
+    */
+    #[must_use]
+    pub fn get_fallthrough(&self) -> Option<Fallthrough> {
+        let settings = self.document.get("settings")?.as_table_like()?;
+        settings.get("fallthrough")?.as_str()?.parse().ok()
+    }
+
+    /**
+        Gets the update check setting, if set in this manifest:
+
+        ```toml
+        [settings]
+        update-check = true
+        ```
+
+        When enabled, Rokit will periodically check for newer versions of
+        itself and of project tools, and let the user know if any exist.
+        This setting is only read from the global manifest.
+    */
+    #[must_use]
+    pub fn get_update_check(&self) -> Option<bool> {
+        let settings = self.document.get("settings")?.as_table_like()?;
+        settings.get("update-check")?.as_bool()
+    }
+
+    /**
+        Gets the strict platform setting, if set in this manifest:
+
+        ```toml
+        [settings]
+        strict-platform = true
+        ```
+
+        When enabled, only artifacts that are fully compatible with the current
+        system are installed, and partially compatible fallbacks, such as x64
+        binaries on arm64 systems that may not be able to emulate them, are
+        rejected with an error listing the available artifacts instead.
+    */
+    #[must_use]
+    pub fn get_strict_platform(&self) -> Option<bool> {
+        let settings = self.document.get("settings")?.as_table_like()?;
+        settings.get("strict-platform")?.as_bool()
+    }
+
+    /**
+        Gets the size in megabytes above which artifacts are considered
+        unusually large, if set in this manifest:
+
+        ```toml
+        [settings]
+        large-download-threshold = 250
+        ```
+
+        Rokit warns before downloading artifacts larger than this, since they
+        are often the wrong asset, such as an archive of debug symbols.
+        Setting this to `0` disables the warning.
+    */
+    #[must_use]
+    pub fn get_large_download_threshold(&self) -> Option<u64> {
+        let settings = self.document.get("settings")?.as_table_like()?;
+        let megabytes = settings.get("large-download-threshold")?.as_integer()?;
+        u64::try_from(megabytes).ok()
+    }
+
+    /**
+        Gets the limits for extracting artifacts, if set in this manifest:
+
+        ```toml
+        [settings.extract-limits]
+        max-file-size = 512
+        max-total-size = 1024
+        max-compression-ratio = 200
+        ```
+
+        Sizes are in megabytes, and any limit that is not set, or is not a
+        positive integer, uses the default - see [`ExtractLimits`].
+    */
+    #[must_use]
+    pub fn get_extract_limits(&self) -> Option<ExtractLimits> {
+        let settings = self.document.get("settings")?.as_table_like()?;
+        let limits = settings.get("extract-limits")?.as_table_like()?;
+
+        let positive = |key: &str| {
+            limits
+                .get(key)
+                .and_then(Item::as_integer)
+                .and_then(|n| u64::try_from(n).ok())
+                .filter(|n| *n > 0)
+        };
+        let megabytes = |key: &str| positive(key).map(|mb| mb.saturating_mul(1024 * 1024));
+
+        let defaults = ExtractLimits::default();
+        Some(ExtractLimits {
+            max_file_size: megabytes("max-file-size").unwrap_or(defaults.max_file_size),
+            max_total_size: megabytes("max-total-size").unwrap_or(defaults.max_total_size),
+            max_compression_ratio: positive("max-compression-ratio")
+                .unwrap_or(defaults.max_compression_ratio),
+        })
+    }
+
+    /**
+        Gets the setting for discovering asdf `.tool-versions` files, if set in this manifest:
+
+        ```toml
+        [settings]
+        tool-versions = true
+        ```
+
+        When enabled, entries in `.tool-versions` files for well-known tools,
+        such as `rojo 7.4.1`, are discovered the same way as tools in other
+        manifests, so that Rokit can install and run them.
+    */
+    #[must_use]
+    pub fn get_tool_versions(&self) -> Option<bool> {
+        let settings = self.document.get("settings")?.as_table_like()?;
+        settings.get("tool-versions")?.as_bool()
+    }
+
+    /**
+        Gets the setting for installing missing tools when running them, if set in this manifest:
+
+        ```toml
+        [settings]
+        auto-install = false
+        ```
+
+        When enabled, which is the default, trusted tools with a missing or empty
+        binary are installed on demand by their tool links, instead of failing.
+        This setting is only read from the global manifest.
+    */
+    #[must_use]
+    pub fn get_auto_install(&self) -> Option<bool> {
+        let settings = self.document.get("settings")?.as_table_like()?;
+        settings.get("auto-install")?.as_bool()
+    }
+
+    /**
+        Gets the setting for installing missing tools from the shell hook, if set in this manifest:
+
+        ```toml
+        [settings]
+        hook-install = true
+        ```
+
+        When enabled, the shell hook from `rokit hook` installs missing project
+        tools when entering a project directory, if they have all been trusted,
+        instead of only printing a hint. This setting is only read from the global manifest.
+    */
+    #[must_use]
+    pub fn get_hook_install(&self) -> Option<bool> {
+        let settings = self.document.get("settings")?.as_table_like()?;
+        settings.get("hook-install")?.as_bool()
+    }
+
+    /**
+        Gets the strategy for creating tool links, if set in this manifest:
+
+        ```toml
+        [settings]
+        link-strategy = "symlink"
+        ```
+
+        See [`LinkStrategy`] for more information. This setting is only read from the global manifest.
+    */
+    #[must_use]
+    pub fn get_link_strategy(&self) -> Option<LinkStrategy> {
+        let settings = self.document.get("settings")?.as_table_like()?;
+        settings.get("link-strategy")?.as_str()?.parse().ok()
+    }
+
+    /**
+        Gets the scope that trust is recorded with when trusting tools, if set in this manifest:
+
+        ```toml
+        [settings]
+        trust-scope = "major"
+        ```
+
+        See [`TrustScope`] for more information. This setting is only read from the global manifest.
+    */
+    #[must_use]
+    pub fn get_trust_scope(&self) -> Option<TrustScope> {
+        let settings = self.document.get("settings")?.as_table_like()?;
+        settings.get("trust-scope")?.as_str()?.parse().ok()
+    }
+
+    /**
+        Gets the directory for tool links and Rokit itself, if set in this manifest:
+
+        ```toml
+        [settings]
+        bin-dir = "/opt/rokit/bin"
+        ```
+
+        Relative paths are relative to the Rokit home directory. This setting is only
+        read from the global manifest, and is overridden by the `ROKIT_BIN_DIR` variable.
+    */
+    #[must_use]
+    pub fn get_bin_dir(&self) -> Option<PathBuf> {
+        let settings = self.document.get("settings")?.as_table_like()?;
+        let dir = settings.get("bin-dir")?.as_str()?;
+        if dir.trim().is_empty() {
+            None
+        } else {
+            Some(PathBuf::from(dir))
+        }
+    }
+
+    /**
+        Sets the directory for tool links and Rokit itself in this manifest.
+
+        See [`RokitManifest::get_bin_dir`] for more information.
+
+        # Errors
+
+        - If the path is not valid UTF-8.
+    */
+    pub fn set_bin_dir(&mut self, dir: impl AsRef<Path>) -> RokitResult<()> {
+        let dir = dir.as_ref().to_str().ok_or(RokitError::InvalidUtf8)?;
+        let doc = self.document.as_table_mut();
+        let settings = doc.entry("settings").or_insert_with(toml_edit::table);
+        if settings.as_table_like().is_none() {
+            *settings = toml_edit::table();
+        }
+        settings.as_table_like_mut().unwrap().insert(
+            "bin-dir",
+            Item::Value(Value::String(Formatted::new(dir.to_string()))),
+        );
+        Ok(())
+    }
+
+    /**
+        Gets the telemetry settings for the runner, if set in this manifest:
+
+        ```toml
+        [settings.telemetry]
+        file = "invocations.jsonl"
+        endpoint = "https://example.com/rokit/invocations"
+        ```
+
+        Returns `None` if neither a file nor an endpoint is set, meaning
+        that telemetry is disabled. This setting is only read from the global manifest.
+
+        See [`TelemetrySettings`] for more information.
+    */
+    #[must_use]
+    pub fn get_telemetry(&self) -> Option<TelemetrySettings> {
+        let settings = self.document.get("settings")?.as_table_like()?;
+        let telemetry = settings.get("telemetry")?.as_table_like()?;
+
+        let file = telemetry
+            .get("file")
+            .and_then(Item::as_str)
+            .filter(|file| !file.trim().is_empty())
+            .map(PathBuf::from);
+        let endpoint = telemetry
+            .get("endpoint")
+            .and_then(Item::as_str)
+            .and_then(|endpoint| endpoint.parse::<Url>().ok());
+
+        if file.is_none() && endpoint.is_none() {
+            None
+        } else {
+            Some(TelemetrySettings { file, endpoint })
+        }
+    }
+
+    /**
+        Gets the rules for deriving default aliases of newly added tools,
+        if set in this manifest:
+
+        ```toml
+        [settings.alias-rules]
+        strip-suffixes = ["-cli"]
+        lowercase = true
+        ```
+
+        See [`AliasRules`] for more information.
+    */
+    #[must_use]
+    pub fn get_alias_rules(&self) -> Option<AliasRules> {
+        let settings = self.document.get("settings")?.as_table_like()?;
+        let rules = settings.get("alias-rules")?.as_table_like()?;
+
+        let str_array = |key: &str| -> Vec<String> {
+            rules
+                .get(key)
+                .and_then(Item::as_array)
+                .map(|a| {
+                    a.iter()
+                        .filter_map(|v| v.as_str())
+                        .filter(|v| !v.is_empty())
+                        .map(ToString::to_string)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        Some(AliasRules {
+            strip_prefixes: str_array("strip-prefixes"),
+            strip_suffixes: str_array("strip-suffixes"),
+            lowercase: rules
+                .get("lowercase")
+                .and_then(Item::as_bool)
+                .unwrap_or_default(),
+        })
+    }
+
+    /**
+        Gets the user-defined command aliases, if set in this manifest:
+
+        ```toml
+        [settings.command-aliases]
+        sync = "install --no-trust-check"
+        bump = ["update", "--all"]
+        ```
+
+        Each alias expands to the given arguments, either written as a string
+        of arguments separated by whitespace, or as an array of arguments.
     */
     #[must_use]
-    pub fn get_tool(&self, alias: &ToolAlias) -> Option<ToolSpec> {
-        let tools = self.document.get("tools")?.as_table()?;
-        let tool_str = tools.get(alias.name())?.as_str()?;
-        tool_str.parse::<ToolSpec>().ok()
+    pub fn get_command_aliases(&self) -> Option<BTreeMap<String, Vec<String>>> {
+        let settings = self.document.get("settings")?.as_table_like()?;
+        let aliases = settings.get("command-aliases")?.as_table_like()?;
+        Some(
+            aliases
+                .iter()
+                .filter_map(|(name, item)| {
+                    let args = command_alias_args(item)?;
+                    Some((name.to_string(), args))
+                })
+                .collect(),
+        )
     }
 
     /**
@@ -121,7 +996,7 @@ impl RokitManifest {
             doc.insert("tools", toml_edit::table());
         }
         let tools = doc["tools"].as_table_mut().unwrap();
-        if tools.contains_value(alias.name()) {
+        if tools.contains_key(alias.name()) {
             false
         } else {
             tools.insert(
@@ -135,6 +1010,9 @@ impl RokitManifest {
     /**
         Updates a tool in the manifest with a new tool specification.
 
+        Any `sha256` option for the tool is removed if the specification changes,
+        since it was recorded for the artifact of the previous specification.
+
         If the tool doesn't exist, this will return `false` and do nothing.
     */
     pub fn update_tool(&mut self, alias: &ToolAlias, spec: &ToolSpec) -> bool {
@@ -143,15 +1021,63 @@ impl RokitManifest {
             return false;
         }
         let tools = doc["tools"].as_table_mut().unwrap();
-        if tools.contains_value(alias.name()) {
-            tools.insert(
-                alias.name(),
-                Item::Value(Value::String(Formatted::new(spec.to_string()))),
-            );
-            true
-        } else {
-            false
+        let Some(tool) = tools.get_mut(alias.name()) else {
+            return false;
+        };
+        let source = Item::Value(Value::String(Formatted::new(spec.to_string())));
+        // NOTE: Tools using the table form may have other fields, such as
+        // environment variables, that we must preserve, so only the source
+        // is replaced for those - the simple string form is replaced fully
+        match tool.as_table_like_mut() {
+            Some(table) => {
+                let is_changed =
+                    tool_source(&source) != table.get("source").and_then(|s| s.as_str());
+                if is_changed {
+                    table.remove("sha256");
+                }
+                table.insert("source", source);
+            }
+            None => *tool = source,
+        }
+        true
+    }
+
+    /**
+        Removes a tool from the manifest.
+
+        If the tool doesn't exist, this will return `false` and do nothing.
+    */
+    pub fn remove_tool(&mut self, alias: &ToolAlias) -> bool {
+        let doc = self.document.as_table_mut();
+        let Some(tools) = doc.get_mut("tools").and_then(|t| t.as_table_mut()) else {
+            return false;
+        };
+        tools.remove(alias.name()).is_some()
+    }
+
+    /**
+        Formats the manifest, to reduce noise in diffs:
+
+        - Sorts tools alphabetically by their alias.
+        - Normalizes whitespace around keys, values, and comments.
+        - Normalizes strings to use double quotes, where possible.
+
+        Comments are preserved, and move along with the key below them.
+
+        Returns `true` if anything was changed.
+    */
+    pub fn format(&mut self) -> bool {
+        let before = self.document.to_string();
+        for (key, item) in self.document.as_table_mut().iter_mut() {
+            let Some(table) = item.as_table_mut() else {
+                continue;
+            };
+            if key.get() == "tools" {
+                table.sort_values();
+            }
+            format_table(table);
         }
+        self.document.to_string() != before
     }
 
     /**
@@ -162,12 +1088,14 @@ impl RokitManifest {
     #[must_use]
     pub fn tool_specs(&self) -> Vec<(ToolAlias, ToolSpec)> {
         let tools = self.document.get("tools").and_then(|v| v.as_table());
-        let tool_kv_pairs = tools.map(|t| t.get_values()).unwrap_or_default();
-        tool_kv_pairs
-            .into_iter()
-            .filter_map(|(keys, value)| {
-                let alias = keys.last()?.parse::<ToolAlias>().ok()?;
-                let spec = value.as_str()?.parse::<ToolSpec>().ok()?;
+        let Some(tools) = tools else {
+            return Vec::new();
+        };
+        tools
+            .iter()
+            .filter_map(|(key, item)| {
+                let alias = key.parse::<ToolAlias>().ok()?;
+                let spec = tool_source(item)?.parse::<ToolSpec>().ok()?;
                 Some((alias, spec))
             })
             .collect()
@@ -175,7 +1103,7 @@ impl RokitManifest {
 }
 
 impl FromStr for RokitManifest {
-    type Err = toml_edit::TomlError;
+    type Err = RokitError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut document = s.parse::<DocumentMut>()?;
 
@@ -212,40 +1140,571 @@ impl FromStr for RokitManifest {
         };
 
         // Check all of the tools.
-        let tool_kv_pairs = tools.map(|t| t.get_values()).unwrap_or_default();
-        for (keys, value) in tool_kv_pairs {
-            if let Err(e) = keys.last().unwrap().parse::<ToolAlias>() {
+        for (key, item) in tools.into_iter().flat_map(|t| t.iter()) {
+            if let Err(e) = key.parse::<ToolAlias>() {
                 warn!(
                     "A tool alias could not be parsed!\
                     \nThe tool will be ignored and may not be available.\
                     \nError: {e}",
                 );
             };
-            let Some(spec_str) = value.as_str() else {
+            let Some(spec_str) = tool_source(item) else {
                 warn!(
-                    "A tool spec with alias '{}' could not be parsed!\
+                    "A tool spec with alias '{key}' could not be parsed!\
                     \nThe tool will be ignored and may not be available.\
-                    \nExpected: String\
+                    \nExpected: String, or table with a 'source' string\
                     \nActual: {}",
-                    keys.into_iter().last().unwrap(),
-                    value.type_name()
+                    item.type_name()
                 );
                 continue;
             };
             if let Err(e) = spec_str.parse::<ToolSpec>() {
                 warn!(
-                    "A tool spec with alias '{}' could not be parsed!\
+                    "A tool spec with alias '{key}' could not be parsed!\
                     \nThe tool will be ignored and may not be available.\
                     \nError: {e}",
-                    keys.into_iter().last().unwrap(),
                 );
             };
+            if let Some(tool) = item.as_table_like() {
+                check_tool_options(key, tool);
+                // NOTE: Checksums are what makes a manifest the reviewed source of
+                // truth for installed tools, so one that is not valid must never be
+                // ignored, or installing would silently skip verifying the artifact
+                let is_sha256 = |h: &Item| h.as_str().is_some_and(is_sha256_hex);
+                if tool
+                    .get("sha256")
+                    .is_some_and(|h| !is_platform_table(h, is_sha256))
+                {
+                    return Err(RokitError::InvalidChecksum {
+                        alias: key.to_string(),
+                    });
+                }
+            }
+        }
+
+        // Check the settings, too.
+        let fallthrough = document
+            .get("settings")
+            .and_then(|s| s.as_table_like())
+            .and_then(|s| s.get("fallthrough"));
+        if let Some(fallthrough) = fallthrough {
+            if let Err(e) = fallthrough
+                .as_str()
+                .unwrap_or_default()
+                .parse::<Fallthrough>()
+            {
+                warn!(
+                    "The 'fallthrough' setting could not be parsed!\
+                    \nExpected one of: 'allow', 'warn', 'deny'\
+                    \nThe default setting will be used instead.\
+                    \nError: {e}"
+                );
+            }
+        }
+        let update_check = document
+            .get("settings")
+            .and_then(|s| s.as_table_like())
+            .and_then(|s| s.get("update-check"));
+        if update_check.is_some_and(|u| !u.is_bool()) {
+            warn!(
+                "The 'update-check' setting could not be parsed!\
+                \nExpected: Boolean\
+                \nUpdate checks will be disabled."
+            );
+        }
+        let strict_platform = document
+            .get("settings")
+            .and_then(|s| s.as_table_like())
+            .and_then(|s| s.get("strict-platform"));
+        if strict_platform.is_some_and(|s| !s.is_bool()) {
+            warn!(
+                "The 'strict-platform' setting could not be parsed!\
+                \nExpected: Boolean\
+                \nPartially compatible artifacts will be installed as fallbacks."
+            );
+        }
+        let large_download_threshold = document
+            .get("settings")
+            .and_then(|s| s.as_table_like())
+            .and_then(|s| s.get("large-download-threshold"));
+        if large_download_threshold.is_some_and(|l| l.as_integer().map_or(true, |l| l < 0)) {
+            warn!(
+                "The 'large-download-threshold' setting could not be parsed!\
+                \nExpected: Non-negative integer, in megabytes\
+                \nThe default threshold will be used instead."
+            );
+        }
+        let tool_versions = document
+            .get("settings")
+            .and_then(|s| s.as_table_like())
+            .and_then(|s| s.get("tool-versions"));
+        if tool_versions.is_some_and(|t| !t.is_bool()) {
+            warn!(
+                "The 'tool-versions' setting could not be parsed!\
+                \nExpected: Boolean\
+                \nFiles named '.tool-versions' will not be used."
+            );
+        }
+        let auto_install = document
+            .get("settings")
+            .and_then(|s| s.as_table_like())
+            .and_then(|s| s.get("auto-install"));
+        if auto_install.is_some_and(|a| !a.is_bool()) {
+            warn!(
+                "The 'auto-install' setting could not be parsed!\
+                \nExpected: Boolean\
+                \nMissing tools will be installed automatically."
+            );
+        }
+        let hook_install = document
+            .get("settings")
+            .and_then(|s| s.as_table_like())
+            .and_then(|s| s.get("hook-install"));
+        if hook_install.is_some_and(|h| !h.is_bool()) {
+            warn!(
+                "The 'hook-install' setting could not be parsed!\
+                \nExpected: Boolean\
+                \nThe shell hook will only print hints for missing tools."
+            );
+        }
+        let link_strategy = document
+            .get("settings")
+            .and_then(|s| s.as_table_like())
+            .and_then(|s| s.get("link-strategy"));
+        if let Some(link_strategy) = link_strategy {
+            if let Err(e) = link_strategy
+                .as_str()
+                .unwrap_or_default()
+                .parse::<LinkStrategy>()
+            {
+                warn!(
+                    "The 'link-strategy' setting could not be parsed!\
+                    \nExpected one of: 'copy', 'hardlink', 'symlink'\
+                    \nThe default setting will be used instead.\
+                    \nError: {e}"
+                );
+            }
+        }
+        let trust_scope = document
+            .get("settings")
+            .and_then(|s| s.as_table_like())
+            .and_then(|s| s.get("trust-scope"));
+        if let Some(trust_scope) = trust_scope {
+            if let Err(e) = trust_scope
+                .as_str()
+                .unwrap_or_default()
+                .parse::<TrustScope>()
+            {
+                warn!(
+                    "The 'trust-scope' setting could not be parsed!\
+                    \nExpected one of: 'tool', 'major'\
+                    \nThe default setting will be used instead.\
+                    \nError: {e}"
+                );
+            }
+        }
+        let bin_dir = document
+            .get("settings")
+            .and_then(|s| s.as_table_like())
+            .and_then(|s| s.get("bin-dir"));
+        if bin_dir.is_some_and(|b| !b.is_str()) {
+            warn!(
+                "The 'bin-dir' setting could not be parsed!\
+                \nExpected: String\
+                \nThe default directory will be used instead."
+            );
+        }
+        let telemetry = document
+            .get("settings")
+            .and_then(|s| s.as_table_like())
+            .and_then(|s| s.get("telemetry"));
+        if let Some(telemetry) = telemetry {
+            check_telemetry_settings(telemetry);
+        }
+        let alias_rules = document
+            .get("settings")
+            .and_then(|s| s.as_table_like())
+            .and_then(|s| s.get("alias-rules"));
+        if let Some(alias_rules) = alias_rules {
+            check_alias_rules(alias_rules);
+        }
+        let command_aliases = document
+            .get("settings")
+            .and_then(|s| s.as_table_like())
+            .and_then(|s| s.get("command-aliases"));
+        if let Some(command_aliases) = command_aliases {
+            check_command_aliases(command_aliases);
+        }
+        let extract_limits = document
+            .get("settings")
+            .and_then(|s| s.as_table_like())
+            .and_then(|s| s.get("extract-limits"));
+        if let Some(extract_limits) = extract_limits {
+            check_extract_limits(extract_limits);
         }
 
         Ok(Self { document })
     }
 }
 
+/**
+    Checks the options for a tool in table form, and warns the
+    user about any options that are invalid and will be ignored.
+*/
+fn check_tool_options(key: &str, tool: &dyn TableLike) {
+    let is_str_table = |item: &Item| {
+        item.as_table_like()
+            .is_some_and(|t| t.iter().all(|(_, v)| v.is_str()))
+    };
+    let is_glob_pattern = |item: &Item| {
+        item.as_str()
+            .is_some_and(|pattern| Pattern::new(pattern).is_ok())
+    };
+    let is_str_array = |item: &Item| {
+        item.as_array()
+            .is_some_and(|a| a.iter().all(|v| v.as_str().is_some()))
+    };
+    let is_glob_array = |item: &Item| {
+        item.as_array().is_some_and(|a| {
+            a.iter()
+                .all(|v| v.as_str().is_some_and(|p| Pattern::new(p).is_ok()))
+        })
+    };
+    let is_alias_array = |item: &Item| {
+        item.as_array().is_some_and(|a| {
+            a.iter()
+                .all(|v| v.as_str().is_some_and(|s| s.parse::<ToolAlias>().is_ok()))
+        })
+    };
+
+    let invalid = [
+        (
+            "env",
+            "Table with string values",
+            tool.get("env").is_some_and(|e| !is_str_table(e)),
+        ),
+        (
+            "cwd",
+            "String",
+            tool.get("cwd").is_some_and(|c| !c.is_str()),
+        ),
+        (
+            "prepend-args",
+            "Array of strings",
+            tool.get("prepend-args").is_some_and(|a| !is_str_array(a)),
+        ),
+        (
+            "file",
+            "String with a valid glob pattern",
+            tool.get("file").is_some_and(|f| !is_glob_pattern(f)),
+        ),
+        (
+            "extract-all",
+            "Boolean",
+            tool.get("extract-all").is_some_and(|e| !e.is_bool()),
+        ),
+        (
+            "channel",
+            "String",
+            tool.get("channel").is_some_and(|c| !c.is_str()),
+        ),
+        (
+            "sandbox",
+            "Boolean",
+            tool.get("sandbox").is_some_and(|s| !s.is_bool()),
+        ),
+        (
+            "requires",
+            "Array of tool aliases",
+            tool.get("requires").is_some_and(|r| !is_alias_array(r)),
+        ),
+        (
+            "exclude-assets",
+            "Array of strings with valid glob patterns",
+            tool.get("exclude-assets")
+                .is_some_and(|e| !is_glob_array(e)),
+        ),
+        (
+            "asset",
+            "String, or table of platforms such as 'windows-x64' with string values",
+            tool.get("asset")
+                .is_some_and(|a| !is_platform_table(a, Item::is_str)),
+        ),
+    ];
+    for (option, expected, _) in invalid.into_iter().filter(|(_, _, invalid)| *invalid) {
+        warn!(
+            "The '{option}' option for tool with alias '{key}' could not be parsed!\
+            \nExpected: {expected}\
+            \nAny values that are not valid will be ignored."
+        );
+    }
+}
+
+/**
+    Checks the telemetry settings, and warns the user about
+    any settings that are invalid and will be ignored.
+*/
+fn check_telemetry_settings(telemetry: &Item) {
+    let Some(telemetry) = telemetry.as_table_like() else {
+        warn!(
+            "The 'telemetry' setting could not be parsed!\
+            \nExpected: Table with 'file' and/or 'endpoint' strings\
+            \nTelemetry will be disabled."
+        );
+        return;
+    };
+
+    let invalid = [
+        (
+            "file",
+            "String",
+            telemetry.get("file").is_some_and(|f| !f.is_str()),
+        ),
+        (
+            "endpoint",
+            "String with a valid URL",
+            telemetry
+                .get("endpoint")
+                .is_some_and(|e| e.as_str().and_then(|e| e.parse::<Url>().ok()).is_none()),
+        ),
+    ];
+    for (setting, expected, _) in invalid.into_iter().filter(|(_, _, invalid)| *invalid) {
+        warn!(
+            "The '{setting}' telemetry setting could not be parsed!\
+            \nExpected: {expected}\
+            \nThe setting will be ignored."
+        );
+    }
+}
+
+/**
+    Checks the alias rules setting, and warns the user
+    about any rules that are invalid and will be ignored.
+*/
+fn check_command_aliases(aliases: &Item) {
+    let Some(aliases) = aliases.as_table_like() else {
+        warn!(
+            "The 'command-aliases' setting could not be parsed!\
+            \nExpected: Table with aliases as keys\
+            \nNo command aliases will be used."
+        );
+        return;
+    };
+    for (name, item) in aliases.iter() {
+        if command_alias_args(item).is_none() {
+            warn!(
+                "The command alias '{name}' could not be parsed!\
+                \nExpected: Non-empty string, or array of strings\
+                \nThe alias will be ignored."
+            );
+        }
+    }
+}
+
+/*
+    Gets the arguments that a command alias expands to,
+    from either a string of arguments, or an array of them.
+*/
+fn command_alias_args(item: &Item) -> Option<Vec<String>> {
+    let args = if let Some(command) = item.as_str() {
+        command
+            .split_whitespace()
+            .map(ToString::to_string)
+            .collect()
+    } else {
+        item.as_array()?
+            .iter()
+            .map(|v| v.as_str().map(ToString::to_string))
+            .collect::<Option<Vec<_>>>()?
+    };
+    if args.is_empty() {
+        None
+    } else {
+        Some(args)
+    }
+}
+
+fn check_alias_rules(rules: &Item) {
+    let Some(rules) = rules.as_table_like() else {
+        warn!(
+            "The 'alias-rules' setting could not be parsed!\
+            \nExpected: Table with 'strip-prefixes', 'strip-suffixes', and/or 'lowercase'\
+            \nTool names will be used as aliases as-is."
+        );
+        return;
+    };
+
+    let is_str_array = |item: &Item| {
+        item.as_array()
+            .is_some_and(|a| a.iter().all(|v| v.as_str().is_some()))
+    };
+    let invalid = [
+        (
+            "strip-prefixes",
+            "Array of strings",
+            rules
+                .get("strip-prefixes")
+                .is_some_and(|p| !is_str_array(p)),
+        ),
+        (
+            "strip-suffixes",
+            "Array of strings",
+            rules
+                .get("strip-suffixes")
+                .is_some_and(|s| !is_str_array(s)),
+        ),
+        (
+            "lowercase",
+            "Boolean",
+            rules.get("lowercase").is_some_and(|l| !l.is_bool()),
+        ),
+    ];
+    for (setting, expected, _) in invalid.into_iter().filter(|(_, _, invalid)| *invalid) {
+        warn!(
+            "The '{setting}' alias rule could not be parsed!\
+            \nExpected: {expected}\
+            \nAny values that are not valid will be ignored."
+        );
+    }
+}
+
+/**
+    Checks the extraction limits, and warns the user about
+    any limits that are invalid and will be ignored.
+*/
+fn check_extract_limits(limits: &Item) {
+    let Some(limits) = limits.as_table_like() else {
+        warn!(
+            "The 'extract-limits' setting could not be parsed!\
+            \nExpected: Table with 'max-file-size', 'max-total-size', and/or 'max-compression-ratio'\
+            \nThe default limits will be used."
+        );
+        return;
+    };
+
+    let is_positive = |item: &Item| item.as_integer().is_some_and(|n| n > 0);
+    for limit in ["max-file-size", "max-total-size", "max-compression-ratio"] {
+        if limits.get(limit).is_some_and(|l| !is_positive(l)) {
+            warn!(
+                "The '{limit}' extraction limit could not be parsed!\
+                \nExpected: Positive integer\
+                \nThe default limit will be used."
+            );
+        }
+    }
+}
+
+/**
+    Splits the given manifest contents into its leading block of
+    comment lines, up to the first empty line, and everything else.
+*/
+fn split_leading_comments(contents: &str) -> (&str, &str) {
+    let mut end = 0;
+    for line in contents.split_inclusive('\n') {
+        if !line.trim_start().starts_with('#') {
+            break;
+        }
+        end += line.len();
+    }
+    contents.split_at(end)
+}
+
+/*
+    Formats all keys and values in the given table, and in its
+    subtables, such as `[tools.rojo]` for tools using the table form.
+*/
+fn format_table(table: &mut Table) {
+    for (mut key, item) in table.iter_mut() {
+        match item {
+            Item::Value(value) => {
+                let decor = key.leaf_decor_mut();
+                let prefix = decor.prefix().and_then(RawString::as_str).unwrap_or("");
+                let prefix = format_key_prefix(prefix);
+                decor.set_prefix(prefix);
+                decor.set_suffix(" ");
+
+                let decor = value.decor_mut();
+                let suffix = decor.suffix().and_then(RawString::as_str).unwrap_or("");
+                let suffix = format_value_suffix(suffix);
+                decor.set_prefix(" ");
+                decor.set_suffix(suffix);
+                format_value(value);
+            }
+            Item::Table(table) => format_table(table),
+            _ => {}
+        }
+    }
+}
+
+/*
+    Formats the given value and any values nested inside of it - note that
+    arrays keep their whitespace, since they may contain comments.
+*/
+fn format_value(value: &mut Value) {
+    match value {
+        Value::String(s) => s.fmt(),
+        Value::Array(array) => array.iter_mut().for_each(format_value),
+        Value::InlineTable(table) => {
+            table.fmt();
+            table.iter_mut().for_each(|(_, value)| format_value(value));
+        }
+        _ => {}
+    }
+}
+
+/*
+    Formats the whitespace and comments above a key - comments are kept,
+    without indentation, along with a single empty line before them if
+    there was one, to keep groups of tools apart. Lone empty lines are removed.
+*/
+fn format_key_prefix(prefix: &str) -> String {
+    let comments = prefix
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with('#'))
+        .collect::<Vec<_>>();
+    if comments.is_empty() {
+        return String::new();
+    }
+    let empty_line_before = prefix
+        .lines()
+        .take_while(|line| !line.trim().starts_with('#'))
+        .any(|line| line.trim().is_empty());
+    let mut formatted = String::from(if empty_line_before { "\n" } else { "" });
+    for comment in comments {
+        formatted.push_str(comment);
+        formatted.push('\n');
+    }
+    formatted
+}
+
+/*
+    Formats the whitespace after a value, keeping any trailing comment.
+*/
+fn format_value_suffix(suffix: &str) -> String {
+    let comment = suffix.trim();
+    if comment.is_empty() {
+        String::new()
+    } else {
+        format!(" {comment}")
+    }
+}
+
+fn is_sha256_hex(s: &str) -> bool {
+    s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/**
+    Gets the tool specification string for a tool entry in a manifest,
+    which is either a string, or a table with a `source` string.
+*/
+fn tool_source(item: &Item) -> Option<&str> {
+    match item.as_str() {
+        Some(s) => Some(s),
+        None => item.as_table_like()?.get("source")?.as_str(),
+    }
+}
+
 impl ToString for RokitManifest {
     fn to_string(&self) -> String {
         self.document.to_string()
@@ -260,3 +1719,542 @@ impl Default for RokitManifest {
         Self { document }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::sources::{ArtifactFormat, ArtifactProvider};
+
+    use super::*;
+
+    const TABLE_MANIFEST: &str = r#"
+[tools]
+rojo = { source = "rojo-rbx/rojo@7.3.0", env = { RUST_LOG = "info", INVALID = 1 } }
+lune = { source = "lune-org/lune@0.8.9", cwd = "project-root", prepend-args = ["run"], file = "**/bin/lune*" }
+wally = "UpliftGames/wally@0.3.2"
+
+[tools.selene]
+source = "Kampfkarren/selene@0.27.1"
+extract-all = true
+sandbox = true
+channel = "nightly"
+requires = ["rojo", "not an alias"]
+sha256 = "BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD"
+"#;
+
+    #[test]
+    fn table_tools_are_parsed() {
+        let manifest = TABLE_MANIFEST.parse::<RokitManifest>().unwrap();
+        let alias = "rojo".parse::<ToolAlias>().unwrap();
+
+        let mut specs = manifest.tool_specs();
+        specs.sort();
+        let aliases = specs.iter().map(|(a, _)| a.name()).collect::<Vec<_>>();
+        assert_eq!(aliases, vec!["lune", "rojo", "selene", "wally"]);
+        assert_eq!(
+            manifest.get_tool(&alias),
+            Some("rojo-rbx/rojo@7.3.0".parse().unwrap())
+        );
+
+        let options = manifest.get_tool_options(&alias);
+        assert_eq!(options.env.len(), 1);
+        assert_eq!(
+            options.env.get("RUST_LOG").map(String::as_str),
+            Some("info")
+        );
+
+        let lune = "lune".parse::<ToolAlias>().unwrap();
+        let options = manifest.get_tool_options(&lune);
+        assert_eq!(options.cwd.as_deref(), Some("project-root"));
+        assert_eq!(options.prepend_args, vec!["run".to_string()]);
+        assert_eq!(options.file.as_deref(), Some("**/bin/lune*"));
+
+        let selene = "selene".parse::<ToolAlias>().unwrap();
+        assert!(manifest.get_tool_options(&selene).extract_all);
+        assert!(manifest.get_tool_options(&selene).sandbox);
+        assert_eq!(
+            manifest.get_tool_options(&selene).channel.as_deref(),
+            Some("nightly")
+        );
+        assert!(!manifest.get_tool_options(&lune).sandbox);
+        assert_eq!(manifest.get_tool_options(&selene).requires, vec![alias]);
+
+        let selene_options = manifest.get_tool_options(&selene);
+        let selene_spec = manifest.get_tool(&selene).unwrap();
+        let system = Descriptor::current_system();
+        assert!(selene_options
+            .verify_sha256(&selene_spec, &system, b"abc")
+            .is_ok());
+        assert!(matches!(
+            selene_options.verify_sha256(&selene_spec, &system, b"abd"),
+            Err(RokitError::ChecksumMismatch { .. })
+        ));
+
+        let wally = "wally".parse::<ToolAlias>().unwrap();
+        assert_eq!(manifest.get_tool_options(&wally), ToolOptions::default());
+    }
+
+    #[test]
+    fn fallthrough_setting() {
+        let manifest = TABLE_MANIFEST.parse::<RokitManifest>().unwrap();
+        assert_eq!(manifest.get_fallthrough(), None);
+
+        let manifest = "[settings]\nfallthrough = \"Deny\"\n"
+            .parse::<RokitManifest>()
+            .unwrap();
+        assert_eq!(manifest.get_fallthrough(), Some(Fallthrough::Deny));
+
+        let manifest = "[settings]\nfallthrough = \"sometimes\"\n"
+            .parse::<RokitManifest>()
+            .unwrap();
+        assert_eq!(manifest.get_fallthrough(), None);
+    }
+
+    #[test]
+    fn update_check_setting() {
+        let manifest = TABLE_MANIFEST.parse::<RokitManifest>().unwrap();
+        assert_eq!(manifest.get_update_check(), None);
+
+        let manifest = "[settings]\nupdate-check = true\n"
+            .parse::<RokitManifest>()
+            .unwrap();
+        assert_eq!(manifest.get_update_check(), Some(true));
+
+        let manifest = "[settings]\nupdate-check = \"yes\"\n"
+            .parse::<RokitManifest>()
+            .unwrap();
+        assert_eq!(manifest.get_update_check(), None);
+    }
+
+    #[test]
+    fn tool_versions_setting() {
+        let manifest = TABLE_MANIFEST.parse::<RokitManifest>().unwrap();
+        assert_eq!(manifest.get_tool_versions(), None);
+
+        let manifest = "[settings]\ntool-versions = true\n"
+            .parse::<RokitManifest>()
+            .unwrap();
+        assert_eq!(manifest.get_tool_versions(), Some(true));
+    }
+
+    #[test]
+    fn auto_install_setting() {
+        let manifest = TABLE_MANIFEST.parse::<RokitManifest>().unwrap();
+        assert_eq!(manifest.get_auto_install(), None);
+
+        let manifest = "[settings]\nauto-install = false\n"
+            .parse::<RokitManifest>()
+            .unwrap();
+        assert_eq!(manifest.get_auto_install(), Some(false));
+    }
+
+    #[test]
+    fn hook_install_setting() {
+        let manifest = TABLE_MANIFEST.parse::<RokitManifest>().unwrap();
+        assert_eq!(manifest.get_hook_install(), None);
+
+        let manifest = "[settings]\nhook-install = true\n"
+            .parse::<RokitManifest>()
+            .unwrap();
+        assert_eq!(manifest.get_hook_install(), Some(true));
+    }
+
+    #[test]
+    fn strict_platform_setting() {
+        let manifest = TABLE_MANIFEST.parse::<RokitManifest>().unwrap();
+        assert_eq!(manifest.get_strict_platform(), None);
+
+        let manifest = "[settings]\nstrict-platform = true\n"
+            .parse::<RokitManifest>()
+            .unwrap();
+        assert_eq!(manifest.get_strict_platform(), Some(true));
+
+        let manifest = "[settings]\nstrict-platform = \"yes\"\n"
+            .parse::<RokitManifest>()
+            .unwrap();
+        assert_eq!(manifest.get_strict_platform(), None);
+    }
+
+    #[test]
+    fn link_strategy_setting() {
+        let manifest = TABLE_MANIFEST.parse::<RokitManifest>().unwrap();
+        assert_eq!(manifest.get_link_strategy(), None);
+
+        let manifest = "[settings]\nlink-strategy = \"symlink\"\n"
+            .parse::<RokitManifest>()
+            .unwrap();
+        assert_eq!(manifest.get_link_strategy(), Some(LinkStrategy::Symlink));
+
+        let manifest = "[settings]\nlink-strategy = \"junction\"\n"
+            .parse::<RokitManifest>()
+            .unwrap();
+        assert_eq!(manifest.get_link_strategy(), None);
+    }
+
+    #[test]
+    fn trust_scope_setting() {
+        let manifest = TABLE_MANIFEST.parse::<RokitManifest>().unwrap();
+        assert_eq!(manifest.get_trust_scope(), None);
+
+        let manifest = "[settings]\ntrust-scope = \"major\"\n"
+            .parse::<RokitManifest>()
+            .unwrap();
+        assert_eq!(manifest.get_trust_scope(), Some(TrustScope::Major));
+    }
+
+    #[test]
+    fn bin_dir_setting() {
+        let mut manifest = TABLE_MANIFEST.parse::<RokitManifest>().unwrap();
+        assert_eq!(manifest.get_bin_dir(), None);
+
+        manifest.set_bin_dir("/opt/rokit/bin").unwrap();
+        assert_eq!(
+            manifest.get_bin_dir(),
+            Some(PathBuf::from("/opt/rokit/bin"))
+        );
+
+        let reparsed = manifest.to_string().parse::<RokitManifest>().unwrap();
+        assert_eq!(
+            reparsed.get_bin_dir(),
+            Some(PathBuf::from("/opt/rokit/bin"))
+        );
+        assert_eq!(reparsed.tool_specs(), manifest.tool_specs());
+
+        let manifest = "[settings]\nbin-dir = 42\n"
+            .parse::<RokitManifest>()
+            .unwrap();
+        assert_eq!(manifest.get_bin_dir(), None);
+    }
+
+    #[test]
+    fn from_aftman_manifest_keeps_comments() {
+        let aftman = "\
+# This file lists tools managed by Aftman, a cross-platform toolchain manager.
+# For more information, see https://github.com/LPGhatguy/aftman
+
+# To add a new tool, add an entry to this table.
+[tools]
+rojo = \"rojo-rbx/rojo@7.3.0\" # Pinned until the next release
+wally = \"UpliftGames/wally@0.3.2\"
+";
+        let manifest = RokitManifest::from_aftman_manifest(aftman).unwrap();
+        let contents = manifest.to_string();
+        assert!(!contents.contains("Aftman"), "{contents}");
+        assert!(contents.starts_with("# This file lists tools managed by Rokit"));
+        assert!(contents.contains("# To add a new tool, add an entry to this table."));
+        assert!(contents.contains("# Pinned until the next release"));
+        assert_eq!(
+            manifest.get_tool(&"rojo".parse().unwrap()),
+            Some("rojo-rbx/rojo@7.3.0".parse().unwrap())
+        );
+
+        // Manifests without an Aftman header keep all of their comments
+        let aftman = "# Our tools\n[tools]\nwally = \"UpliftGames/wally@0.3.2\"\n";
+        let manifest = RokitManifest::from_aftman_manifest(aftman).unwrap();
+        assert!(manifest.to_string().contains("# Our tools\n[tools]"));
+    }
+
+    #[test]
+    fn telemetry_setting() {
+        let manifest = TABLE_MANIFEST.parse::<RokitManifest>().unwrap();
+        assert_eq!(manifest.get_telemetry(), None);
+
+        let manifest = "[settings.telemetry]\nfile = \"invocations.jsonl\"\n"
+            .parse::<RokitManifest>()
+            .unwrap();
+        assert_eq!(
+            manifest.get_telemetry(),
+            Some(TelemetrySettings {
+                file: Some(PathBuf::from("invocations.jsonl")),
+                endpoint: None,
+            })
+        );
+
+        let manifest = "[settings.telemetry]\nfile = \"\"\nendpoint = \"not a url\"\n"
+            .parse::<RokitManifest>()
+            .unwrap();
+        assert_eq!(manifest.get_telemetry(), None);
+
+        let manifest = "[settings]\ntelemetry = { endpoint = \"https://example.com/rokit\" }\n"
+            .parse::<RokitManifest>()
+            .unwrap();
+        let telemetry = manifest.get_telemetry().unwrap();
+        assert_eq!(telemetry.file, None);
+        assert_eq!(
+            telemetry.endpoint.map(String::from).as_deref(),
+            Some("https://example.com/rokit")
+        );
+    }
+
+    #[test]
+    fn alias_rules_setting() {
+        let manifest = TABLE_MANIFEST.parse::<RokitManifest>().unwrap();
+        assert_eq!(manifest.get_alias_rules(), None);
+
+        let manifest = "[settings.alias-rules]\n\
+            strip-prefixes = [\"roblox-\"]\n\
+            strip-suffixes = [\"-cli\", 42]\n\
+            lowercase = true\n"
+            .parse::<RokitManifest>()
+            .unwrap();
+        let rules = manifest.get_alias_rules().unwrap();
+        assert_eq!(rules.strip_suffixes, vec!["-cli".to_string()]);
+        assert_eq!(rules.apply("Some-Tool-CLI"), "some-tool");
+        assert_eq!(rules.apply("roblox-ts-cli"), "ts");
+        assert_eq!(rules.apply("rojo"), "rojo");
+        assert_eq!(rules.apply("-cli"), "-cli");
+    }
+
+    #[test]
+    fn extract_limits_setting() {
+        let manifest = TABLE_MANIFEST.parse::<RokitManifest>().unwrap();
+        assert_eq!(manifest.get_extract_limits(), None);
+
+        let manifest = "[settings.extract-limits]\n\
+            max-file-size = 64\n\
+            max-total-size = -1\n\
+            max-compression-ratio = 50\n"
+            .parse::<RokitManifest>()
+            .unwrap();
+        assert_eq!(
+            manifest.get_extract_limits(),
+            Some(ExtractLimits {
+                max_file_size: 64 * 1024 * 1024,
+                max_total_size: ExtractLimits::default().max_total_size,
+                max_compression_ratio: 50,
+            })
+        );
+    }
+
+    #[test]
+    fn large_download_threshold_setting() {
+        let manifest = TABLE_MANIFEST.parse::<RokitManifest>().unwrap();
+        assert_eq!(manifest.get_large_download_threshold(), None);
+
+        let manifest = "[settings]\nlarge-download-threshold = 250\n"
+            .parse::<RokitManifest>()
+            .unwrap();
+        assert_eq!(manifest.get_large_download_threshold(), Some(250));
+
+        let manifest = "[settings]\nlarge-download-threshold = -1\n"
+            .parse::<RokitManifest>()
+            .unwrap();
+        assert_eq!(manifest.get_large_download_threshold(), None);
+    }
+
+    #[test]
+    fn exclude_assets_option() {
+        let manifest = "[tools]\n\
+            rojo = { source = \"rojo-rbx/rojo@7.3.0\", exclude-assets = [\"*-DEBUG*\", \"[invalid\"] }\n"
+            .parse::<RokitManifest>()
+            .unwrap();
+        let alias = "rojo".parse::<ToolAlias>().unwrap();
+        let options = manifest.get_tool_options(&alias);
+        assert_eq!(options.exclude_assets, vec!["*-DEBUG*".to_string()]);
+
+        let spec = manifest.get_tool(&alias).unwrap();
+        let artifacts = [
+            "rojo-7.3.0-linux-x86_64.zip",
+            "rojo-7.3.0-linux-x86_64-debug.zip",
+        ]
+        .into_iter()
+        .map(|name| Artifact {
+            provider: ArtifactProvider::GitHub,
+            format: ArtifactFormat::from_path_or_url(name),
+            id: None,
+            url: None,
+            name: Some(name.trim_end_matches(".zip").to_string()),
+            size: None,
+            sha256: None,
+            tool_spec: spec.clone(),
+        })
+        .collect::<Vec<_>>();
+
+        let kept = options.exclude_artifacts(&artifacts);
+        assert_eq!(kept, vec![artifacts[0].clone()]);
+        assert_eq!(exclude_artifacts(&artifacts, &[]), artifacts);
+    }
+
+    #[test]
+    fn asset_option() {
+        let manifest = "[tools]\n\
+            rojo = { source = \"rojo-rbx/rojo@7.3.0\", asset = \"rojo.tgz\" }\n\
+            lune = { source = \"lune-org/lune@0.8.9\", asset = { linux = \"lune-linux.zip\", \
+                linux-arm64 = \"lune-linux-aarch64.zip\", \"*\" = \"lune.zip\", nowhere = \"x\" } }\n"
+            .parse::<RokitManifest>()
+            .unwrap();
+        let linux_x64 = "linux-x64".parse::<Descriptor>().unwrap();
+        let linux_arm64 = "linux-arm64".parse::<Descriptor>().unwrap();
+        let windows_x64 = "windows-x64".parse::<Descriptor>().unwrap();
+
+        let rojo = "rojo".parse::<ToolAlias>().unwrap();
+        let options = manifest.get_tool_options(&rojo);
+        assert_eq!(options.asset_for(&linux_x64), Some("rojo.tgz"));
+        assert_eq!(options.asset_for(&windows_x64), Some("rojo.tgz"));
+
+        let lune = "lune".parse::<ToolAlias>().unwrap();
+        let options = manifest.get_tool_options(&lune);
+        assert_eq!(options.asset.len(), 3);
+        assert_eq!(options.asset_for(&linux_x64), Some("lune-linux.zip"));
+        assert_eq!(
+            options.asset_for(&linux_arm64),
+            Some("lune-linux-aarch64.zip")
+        );
+        assert_eq!(options.asset_for(&windows_x64), Some("lune.zip"));
+
+        let spec = manifest.get_tool(&rojo).unwrap();
+        let artifacts = ["rojo-linux.zip", "rojo.tgz"]
+            .into_iter()
+            .map(|name| Artifact {
+                provider: ArtifactProvider::GitHub,
+                format: ArtifactFormat::from_path_or_url(name),
+                id: None,
+                url: None,
+                name: Some(name.split('.').next().unwrap().to_string()),
+                size: None,
+                sha256: None,
+                tool_spec: spec.clone(),
+            })
+            .collect::<Vec<_>>();
+        let found = find_asset_artifact(&spec, &artifacts, "rojo.tgz").unwrap();
+        assert_eq!(found, artifacts[1]);
+        assert!(matches!(
+            find_asset_artifact(&spec, &artifacts, "rojo.zip"),
+            Err(RokitError::AssetNotFound { available, .. }) if available.len() == 2
+        ));
+    }
+
+    #[test]
+    fn per_platform_sha256_option() {
+        let hash = |c: char| c.to_string().repeat(64);
+        let manifest = format!(
+            "[tools.lune]\n\
+            source = \"lune-org/lune@0.8.9\"\n\
+            sha256 = {{ linux = \"{}\", linux-arm64 = \"{}\", \"*\" = \"{}\" }}\n",
+            hash('a'),
+            hash('B'),
+            hash('c'),
+        )
+        .parse::<RokitManifest>()
+        .unwrap();
+        let linux_x64 = "linux-x64".parse::<Descriptor>().unwrap();
+        let linux_arm64 = "linux-arm64".parse::<Descriptor>().unwrap();
+        let windows_x64 = "windows-x64".parse::<Descriptor>().unwrap();
+
+        let lune = "lune".parse::<ToolAlias>().unwrap();
+        let options = manifest.get_tool_options(&lune);
+        assert_eq!(options.sha256_for(&linux_x64), Some(hash('a').as_str()));
+        assert_eq!(options.sha256_for(&linux_arm64), Some(hash('b').as_str()));
+        assert_eq!(options.sha256_for(&windows_x64), Some(hash('c').as_str()));
+
+        let spec = manifest.get_tool(&lune).unwrap();
+        assert!(matches!(
+            options.verify_sha256(&spec, &linux_x64, b"abc"),
+            Err(RokitError::ChecksumMismatch { expected, .. }) if expected == hash('a')
+        ));
+    }
+
+    #[test]
+    fn malformed_sha256_option_is_an_error() {
+        let malformed = [
+            "sha256 = \"not a hash\"",
+            "sha256 = 42",
+            "sha256 = { linux = \"abc\" }",
+            "sha256 = { nowhere = \"0000000000000000000000000000000000000000000000000000000000000000\" }",
+        ];
+        for sha256 in malformed {
+            let result = format!("[tools.rojo]\nsource = \"rojo-rbx/rojo@7.3.0\"\n{sha256}\n")
+                .parse::<RokitManifest>();
+            assert!(
+                matches!(result, Err(RokitError::InvalidChecksum { ref alias }) if alias == "rojo"),
+                "{sha256} should not be accepted"
+            );
+        }
+    }
+
+    #[test]
+    fn command_aliases_setting() {
+        let manifest = TABLE_MANIFEST.parse::<RokitManifest>().unwrap();
+        assert_eq!(manifest.get_command_aliases(), None);
+
+        let manifest = "[settings.command-aliases]\n\
+            sync = \"install  --no-trust-check\"\n\
+            bump = [\"update\", \"--all\"]\n\
+            empty = \"\"\n\
+            broken = [\"update\", 42]\n"
+            .parse::<RokitManifest>()
+            .unwrap();
+        let aliases = manifest.get_command_aliases().unwrap();
+        assert_eq!(aliases.len(), 2);
+        assert_eq!(aliases["sync"], ["install", "--no-trust-check"]);
+        assert_eq!(aliases["bump"], ["update", "--all"]);
+    }
+
+    #[test]
+    fn format_sorts_tools_and_keeps_comments() {
+        let mut manifest = "\
+# Tools for this project
+[tools]
+  wally='UpliftGames/wally@0.3.2'   # Package manager
+
+# Build tools
+rojo =   { source='rojo-rbx/rojo@7.3.0',env={ RUST_LOG = 'info' } }
+
+lune = \"lune-org/lune@0.8.9\"
+
+[tools.selene]
+source = 'Kampfkarren/selene@0.27.1'
+
+[settings]
+fallthrough  =  'deny'
+"
+        .parse::<RokitManifest>()
+        .unwrap();
+
+        assert!(manifest.format());
+        assert_eq!(
+            manifest.to_string(),
+            "\
+# Tools for this project
+[tools]
+lune = \"lune-org/lune@0.8.9\"
+
+# Build tools
+rojo = { source = \"rojo-rbx/rojo@7.3.0\", env = { RUST_LOG = \"info\" } }
+wally = \"UpliftGames/wally@0.3.2\" # Package manager
+
+[tools.selene]
+source = \"Kampfkarren/selene@0.27.1\"
+
+[settings]
+fallthrough = \"deny\"
+"
+        );
+        assert!(!manifest.format());
+    }
+
+    #[test]
+    fn update_keeps_tool_env() {
+        let mut manifest = TABLE_MANIFEST.parse::<RokitManifest>().unwrap();
+        let alias = "rojo".parse::<ToolAlias>().unwrap();
+        let spec = "rojo-rbx/rojo@7.4.1".parse::<ToolSpec>().unwrap();
+
+        assert!(manifest.update_tool(&alias, &spec));
+        let manifest = manifest.to_string().parse::<RokitManifest>().unwrap();
+        assert_eq!(manifest.get_tool(&alias), Some(spec));
+        assert_eq!(manifest.get_tool_options(&alias).env.len(), 1);
+    }
+
+    #[test]
+    fn update_removes_stale_checksum() {
+        let mut manifest = TABLE_MANIFEST.parse::<RokitManifest>().unwrap();
+        let alias = "selene".parse::<ToolAlias>().unwrap();
+        let same = manifest.get_tool(&alias).unwrap();
+        let newer = "Kampfkarren/selene@0.28.0".parse::<ToolSpec>().unwrap();
+
+        assert!(manifest.update_tool(&alias, &same));
+        assert!(!manifest.get_tool_options(&alias).sha256.is_empty());
+        assert!(manifest.update_tool(&alias, &newer));
+        assert!(manifest.get_tool_options(&alias).sha256.is_empty());
+        assert!(manifest.get_tool_options(&alias).sandbox);
+    }
+}
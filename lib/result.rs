@@ -1,6 +1,10 @@
+use std::collections::BTreeMap;
 use std::io::Error as IoError;
 use std::path::PathBuf;
+use std::{fmt, fmt::Write as _, str::FromStr};
 
+#[cfg(feature = "keychain")]
+use keyring::Error as KeyringError;
 use postcard::Error as PostcardError;
 use serde_json::Error as JsonError;
 use thiserror::Error;
@@ -8,7 +12,12 @@ use tokio::task::JoinError;
 use toml_edit::TomlError;
 use zip::result::ZipError;
 
-use crate::sources::{github::GithubError, ExtractError};
+use crate::{
+    descriptor::{Descriptor, UnsupportedPlatformError},
+    install::SkipReason,
+    sources::{github::GithubError, Artifact, DownloadError, ExtractError},
+    tool::ToolSpec,
+};
 
 #[derive(Debug, Error)]
 pub enum RokitError {
@@ -16,10 +25,25 @@ pub enum RokitError {
     HomeNotFound,
     #[error("file not found: {0}")]
     FileNotFound(PathBuf),
+    #[error(
+        "file at '{}' is corrupted: {error}{}",
+        path.display(),
+        backup_path.as_ref().map(|backup| format!(
+            "\nA backup of its previous contents is available at '{}'",
+            backup.display()
+        )).unwrap_or_default()
+    )]
+    CorruptedFile {
+        path: PathBuf,
+        backup_path: Option<PathBuf>,
+        error: Box<RokitError>,
+    },
     #[error("unexpected invalid UTF-8")]
     InvalidUtf8,
     #[error("failed to extract artifact: {0}")]
     Extract(Box<ExtractError>),
+    #[error("failed to download artifact: {0}")]
+    Download(Box<DownloadError>),
     #[error("task join error: {0}")]
     TaskJoinError(Box<JoinError>),
     #[error("TOML parse error: {0}")]
@@ -34,10 +58,239 @@ pub enum RokitError {
     Zip(Box<ZipError>),
     #[error("GitHub error: {0}")]
     GitHub(Box<GithubError>),
+    #[cfg(feature = "keychain")]
+    #[error("keychain error: {0}")]
+    Keychain(Box<KeyringError>),
+    #[cfg(not(feature = "keychain"))]
+    #[error("keychain error: this build of Rokit does not support storing tokens in the keychain")]
+    KeychainUnsupported,
+    #[error("unsupported platform: {0}")]
+    UnsupportedPlatform(#[from] UnsupportedPlatformError),
+    /**
+        The release of a tool has no artifact that can run on the current
+        platform - `available` lists the file names of all artifacts in
+        the release, grouped by the platforms that they were built for.
+    */
+    #[error(
+        "platform '{platform}' is not supported by the release of {spec}{}",
+        format_available_platforms(available)
+    )]
+    NoCompatibleArtifact {
+        spec: Box<ToolSpec>,
+        platform: String,
+        available: BTreeMap<String, Vec<String>>,
+    },
+    #[error(
+        "release asset '{asset}' was not found for {spec}{}",
+        if available.is_empty() {
+            String::from(" - the release has no installable assets")
+        } else {
+            format!(" - available assets are: {}", available.join(", "))
+        }
+    )]
+    AssetNotFound {
+        spec: Box<ToolSpec>,
+        asset: String,
+        available: Vec<String>,
+    },
+    #[error("operation was cancelled")]
+    Cancelled,
+    #[error("{0}")]
+    UnmetRequirement(SkipReason),
+    #[error(
+        "checksum mismatch for {spec} - the manifest expects SHA-256 hash {expected}, \
+        but the downloaded artifact has SHA-256 hash {actual}"
+    )]
+    ChecksumMismatch {
+        spec: Box<ToolSpec>,
+        expected: String,
+        actual: String,
+    },
+    #[error(
+        "the 'sha256' option for tool '{alias}' is not valid - expected a SHA-256 hash \
+        as 64 hexadecimal characters, or a table of platforms with such hashes"
+    )]
+    InvalidChecksum { alias: String },
+    #[error("lockfile is not valid: {0}")]
+    InvalidLockfile(String),
 }
 
 pub type RokitResult<T> = Result<T, RokitError>;
 
+impl RokitError {
+    /**
+        Creates the error for a release of a tool, with the given
+        artifacts, that has no artifact for the current platform.
+    */
+    #[must_use]
+    pub fn no_compatible_artifact(spec: &ToolSpec, artifacts: &[Artifact]) -> Self {
+        Self::NoCompatibleArtifact {
+            spec: spec.clone().into(),
+            platform: Descriptor::current_system().platform_name(),
+            available: Artifact::group_by_platform(artifacts),
+        }
+    }
+
+    /**
+        Returns the broad category of this error.
+
+        See [`ErrorKind`] for more information.
+    */
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::HomeNotFound | Self::Io(_) => ErrorKind::Io,
+            Self::FileNotFound(_) | Self::AssetNotFound { .. } => ErrorKind::NotFound,
+            Self::Extract(e) => e.kind(),
+            Self::Download(e) => e.kind(),
+            Self::CorruptedFile { .. }
+            | Self::TomlParseError(_)
+            | Self::Json(_)
+            | Self::InvalidChecksum { .. }
+            | Self::InvalidLockfile(_) => ErrorKind::Manifest,
+            Self::Zip(_) => ErrorKind::Extraction,
+            Self::GitHub(e) => e.kind(),
+            #[cfg(feature = "keychain")]
+            Self::Keychain(_) => ErrorKind::Authentication,
+            #[cfg(not(feature = "keychain"))]
+            Self::KeychainUnsupported => ErrorKind::Authentication,
+            Self::ChecksumMismatch { .. } => ErrorKind::Trust,
+            Self::UnsupportedPlatform(_) | Self::NoCompatibleArtifact { .. } => {
+                ErrorKind::IncompatiblePlatform
+            }
+            Self::InvalidUtf8
+            | Self::TaskJoinError(_)
+            | Self::Postcard(_)
+            | Self::Cancelled
+            | Self::UnmetRequirement(_) => ErrorKind::Other,
+        }
+    }
+}
+
+fn format_available_platforms(available: &BTreeMap<String, Vec<String>>) -> String {
+    if available.is_empty() {
+        return String::from(" - the release has no installable artifacts");
+    }
+    let list = available
+        .iter()
+        .fold(String::new(), |mut list, (platform, names)| {
+            let _ = write!(list, "\n  {platform}: {}", names.join(", "));
+            list
+        });
+    format!("\nthe release only has artifacts for:{list}")
+}
+
+/**
+    A broad category of error, used to communicate *why* an operation failed.
+
+    Each kind maps to a distinct and stable process exit code, which
+    makes it possible for scripts to react to specific failures:
+
+    | Kind                    | Exit code | Description                                          |
+    |-------------------------|-----------|------------------------------------------------------|
+    | `other`                 | 1         | Any error not covered by a more specific kind        |
+    | `usage`                 | 2         | Invalid command line arguments or usage              |
+    | `io`                    | 3         | Filesystem or other I/O error                        |
+    | `manifest`              | 4         | A manifest or data file could not be parsed          |
+    | `network`               | 5         | A request to an artifact provider failed             |
+    | `not-found`             | 6         | A tool, release, or file could not be found          |
+    | `trust`                 | 7         | A tool was not trusted                               |
+    | `extraction`            | 8         | An artifact could not be extracted                   |
+    | `incompatible-platform` | 9         | No artifact or binary is compatible with this system |
+    | `authentication`        | 10        | Authentication with an artifact provider failed      |
+    | `tool-not-installed`    | 11        | The binary for a tool to run is missing or empty     |
+
+    Note that exit codes above 128 are reserved for processes interrupted by signals.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    Other,
+    Usage,
+    Io,
+    Manifest,
+    Network,
+    NotFound,
+    Trust,
+    Extraction,
+    IncompatiblePlatform,
+    Authentication,
+    ToolNotInstalled,
+}
+
+impl ErrorKind {
+    pub const ALL: [Self; 11] = [
+        Self::Other,
+        Self::Usage,
+        Self::Io,
+        Self::Manifest,
+        Self::Network,
+        Self::NotFound,
+        Self::Trust,
+        Self::Extraction,
+        Self::IncompatiblePlatform,
+        Self::Authentication,
+        Self::ToolNotInstalled,
+    ];
+
+    /**
+        Returns the process exit code for this kind of error.
+    */
+    #[must_use]
+    pub const fn exit_code(self) -> i32 {
+        match self {
+            Self::Other => 1,
+            Self::Usage => 2,
+            Self::Io => 3,
+            Self::Manifest => 4,
+            Self::Network => 5,
+            Self::NotFound => 6,
+            Self::Trust => 7,
+            Self::Extraction => 8,
+            Self::IncompatiblePlatform => 9,
+            Self::Authentication => 10,
+            Self::ToolNotInstalled => 11,
+        }
+    }
+
+    /**
+        Returns the machine-readable name of this kind of error.
+    */
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Other => "other",
+            Self::Usage => "usage",
+            Self::Io => "io",
+            Self::Manifest => "manifest",
+            Self::Network => "network",
+            Self::NotFound => "not-found",
+            Self::Trust => "trust",
+            Self::Extraction => "extraction",
+            Self::IncompatiblePlatform => "incompatible-platform",
+            Self::Authentication => "authentication",
+            Self::ToolNotInstalled => "tool-not-installed",
+        }
+    }
+}
+
+impl FromStr for ErrorKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let l = s.trim().to_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == l)
+            .ok_or_else(|| format!("unknown error kind '{l}'"))
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
 // FUTURE: Figure out some way to reduce this boxing boilerplate
 
 impl From<ExtractError> for RokitError {
@@ -46,6 +299,12 @@ impl From<ExtractError> for RokitError {
     }
 }
 
+impl From<DownloadError> for RokitError {
+    fn from(err: DownloadError) -> Self {
+        RokitError::Download(err.into())
+    }
+}
+
 impl From<JoinError> for RokitError {
     fn from(err: JoinError) -> Self {
         RokitError::TaskJoinError(err.into())
@@ -82,8 +341,47 @@ impl From<ZipError> for RokitError {
     }
 }
 
+#[cfg(feature = "keychain")]
+impl From<KeyringError> for RokitError {
+    fn from(err: KeyringError) -> Self {
+        RokitError::Keychain(err.into())
+    }
+}
+
 impl From<GithubError> for RokitError {
     fn from(err: GithubError) -> Self {
         RokitError::GitHub(err.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn exit_codes_are_unique() {
+        let codes = ErrorKind::ALL
+            .into_iter()
+            .map(ErrorKind::exit_code)
+            .collect::<HashSet<_>>();
+        assert_eq!(codes.len(), ErrorKind::ALL.len());
+    }
+
+    #[test]
+    fn exit_codes_are_not_reserved() {
+        for kind in ErrorKind::ALL {
+            let code = kind.exit_code();
+            assert!(code > 0, "exit code for {kind} must be nonzero");
+            assert!(code < 128, "exit code for {kind} is reserved for signals");
+        }
+    }
+
+    #[test]
+    fn names_roundtrip() {
+        for kind in ErrorKind::ALL {
+            assert_eq!(kind.as_str().parse::<ErrorKind>(), Ok(kind));
+        }
+    }
+}
@@ -0,0 +1,40 @@
+/*!
+    Utilities for choosing release assets that are compatible with the
+    current system, using the same detection that Rokit uses internally,
+    for other tools that want to pick assets without installing anything.
+
+    Requires the `asset-selection` feature to be enabled.
+
+    ```no_run
+    use rokit::selection::select_best_asset;
+
+    let names = ["tool-linux-x86_64.zip", "tool-windows-x86_64.zip"];
+    if let Some(index) = select_best_asset(&names) {
+        println!("Selected {}", names[index]);
+    }
+    ```
+
+    Assets for other systems, such as when preparing downloads for CI runners,
+    can be selected using target triples, and Rokit can also fetch the release:
+
+    ```no_run
+    # async fn example() -> rokit::result::RokitResult<()> {
+    use rokit::selection::{select_release_artifact, ArtifactSource, Descriptor};
+
+    let source = ArtifactSource::new()?;
+    let spec = "rojo-rbx/rojo@7.4.1".parse().unwrap();
+    let target = Descriptor::from_target_triple("aarch64-apple-darwin").unwrap();
+    if let Some(artifact) = select_release_artifact(&source, &spec, target).await? {
+        println!("Selected {:?} in format {:?}", artifact.url, artifact.format);
+    }
+    # Ok(())
+    # }
+    ```
+*/
+
+pub use crate::descriptor::{Arch, Descriptor, Toolchain, OS};
+pub use crate::sources::{
+    select_best_asset, select_best_asset_for, select_release_artifact, Artifact, ArtifactFormat,
+    ArtifactSource,
+};
+pub use crate::tool::ToolSpec;
@@ -0,0 +1,136 @@
+use semver::{Version, VersionReq};
+use url::Url;
+
+/**
+    A published security advisory for a tool, such as
+    a repository security advisory on GitHub.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Advisory {
+    pub id: String,
+    pub summary: String,
+    pub severity: Option<String>,
+    pub url: Option<Url>,
+    pub vulnerable_ranges: Vec<VersionReq>,
+    pub patched_versions: Vec<Version>,
+}
+
+impl Advisory {
+    /**
+        Checks if the given version is affected by this advisory.
+    */
+    #[must_use]
+    pub fn affects(&self, version: &Version) -> bool {
+        self.vulnerable_ranges
+            .iter()
+            .any(|range| range.matches(version))
+    }
+
+    /**
+        Gets the lowest patched version that is newer than the
+        given version, which is the suggested version to upgrade to.
+
+        Returns `None` if no patched version is known.
+    */
+    #[must_use]
+    pub fn patched_version_for(&self, version: &Version) -> Option<&Version> {
+        self.patched_versions
+            .iter()
+            .filter(|patched| *patched > version)
+            .min()
+    }
+}
+
+/**
+    Parses a vulnerable version range, as used in GitHub advisories,
+    such as `>= 1.0.0, < 1.2.3` or `= 0.4.0`, into a version requirement.
+
+    Unlike semver requirements, bare versions mean exact versions.
+*/
+pub(crate) fn parse_version_range(range: &str) -> Option<VersionReq> {
+    let comparators = range
+        .split(',')
+        .map(str::trim)
+        .filter(|comparator| !comparator.is_empty())
+        .map(|comparator| {
+            if comparator.starts_with(|c: char| c.is_ascii_digit()) {
+                format!("={comparator}")
+            } else {
+                comparator.to_string()
+            }
+        })
+        .collect::<Vec<_>>();
+    if comparators.is_empty() {
+        return None;
+    }
+    comparators.join(", ").parse().ok()
+}
+
+/**
+    Parses a list of patched versions, as used in GitHub advisories,
+    such as `1.2.3` or `1.2.3, 2.0.1`, skipping any invalid versions.
+*/
+pub(crate) fn parse_patched_versions(versions: &str) -> Vec<Version> {
+    versions
+        .split(',')
+        .filter_map(|version| {
+            let version = version.trim().trim_start_matches(['=', 'v', ' ']);
+            version.parse().ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(s: &str) -> Version {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_ranges() {
+        let range = parse_version_range(">= 1.0.0, < 1.2.3").unwrap();
+        assert!(range.matches(&version("1.2.2")));
+        assert!(!range.matches(&version("1.2.3")));
+        assert!(!range.matches(&version("0.9.0")));
+
+        let range = parse_version_range("<= 0.4").unwrap();
+        assert!(range.matches(&version("0.4.7")));
+        assert!(!range.matches(&version("0.5.0")));
+
+        // Bare versions must only match exactly, and not as caret requirements
+        let range = parse_version_range("0.4.0").unwrap();
+        assert!(range.matches(&version("0.4.0")));
+        assert!(!range.matches(&version("0.4.1")));
+
+        assert_eq!(parse_version_range(""), None);
+        assert_eq!(parse_version_range("not a range"), None);
+    }
+
+    #[test]
+    fn patched_version_suggestion() {
+        let advisory = Advisory {
+            id: "GHSA-xxxx-xxxx-xxxx".to_string(),
+            summary: "Something bad".to_string(),
+            severity: Some("high".to_string()),
+            url: None,
+            vulnerable_ranges: vec![
+                parse_version_range("< 1.2.3").unwrap(),
+                parse_version_range(">= 2.0.0, < 2.0.1").unwrap(),
+            ],
+            patched_versions: parse_patched_versions("1.2.3, 2.0.1"),
+        };
+
+        assert!(advisory.affects(&version("1.0.0")));
+        assert!(advisory.affects(&version("2.0.0")));
+        assert!(!advisory.affects(&version("1.2.3")));
+
+        let v = version("1.0.0");
+        assert_eq!(advisory.patched_version_for(&v), Some(&version("1.2.3")));
+        let v = version("2.0.0");
+        assert_eq!(advisory.patched_version_for(&v), Some(&version("2.0.1")));
+        let v = version("3.0.0");
+        assert_eq!(advisory.patched_version_for(&v), None);
+    }
+}
@@ -1,39 +1,76 @@
-use tracing::instrument;
+use std::collections::BTreeMap;
+
+use glob::Pattern;
+use tracing::{instrument, warn};
 use url::Url;
 
 use crate::{
-    descriptor::{Descriptor, OS},
-    result::RokitResult,
+    descriptor::{Descriptor, Toolchain},
+    result::{RokitError, RokitResult},
     tool::ToolSpec,
+    util::str::timestamp_date,
 };
 
 use super::{
+    client::redact_tokens,
     decompression::decompress_gzip,
-    extraction::{extract_tar_file, extract_zip_file},
-    github::models::GithubAsset,
+    extraction::{
+        extract_all_files, extract_nested_archive, extract_tar_file, extract_zip_file,
+        ExtractLimits, ExtractedFile,
+    },
+    github::models::{GithubAsset, GithubWorkflowArtifact},
     ExtractError,
 };
 
 mod format;
 mod provider;
+#[cfg(any(test, feature = "asset-selection"))]
+mod selection;
 mod sorting;
 mod util;
 
 use self::sorting::sort_preferred_artifact;
 use self::sorting::sort_preferred_formats;
-use self::util::split_filename_and_extensions;
+use self::util::{is_checksum_asset, is_non_installable_asset, parse_checksums};
 
 pub use self::format::ArtifactFormat;
 pub use self::provider::ArtifactProvider;
+#[cfg(any(test, feature = "asset-selection"))]
+pub use self::selection::{select_best_asset, select_best_asset_for, select_release_artifact};
+pub use self::util::split_filename_and_extensions;
 
 /**
-    A release found by Rokit, containing a list
-    of artifacts, and optionally a changelog.
+    A release found by Rokit, containing a list of artifacts,
+    and optionally a changelog and other details about the release.
+
+    Checksum files published with the release, if any, are
+    kept separately, since they can never be installed.
+
+    Details are optional, since providers may not have all of them,
+    and repository archives are not published as releases at all.
 */
 #[derive(Debug, Clone)]
 pub struct Release {
     pub changelog: Option<String>,
     pub artifacts: Vec<Artifact>,
+    pub checksums: Vec<Artifact>,
+    pub tag_name: Option<String>,
+    pub prerelease: bool,
+    pub author: Option<String>,
+    /**
+        The time that the release was published, as an RFC 3339 timestamp.
+    */
+    pub published_at: Option<String>,
+}
+
+impl Release {
+    /**
+        Gets the date that the release was published, formatted as `YYYY-MM-DD`.
+    */
+    #[must_use]
+    pub fn published_date(&self) -> Option<&str> {
+        self.published_at.as_deref().map(timestamp_date)
+    }
 }
 
 /**
@@ -46,6 +83,8 @@ pub struct Artifact {
     pub id: Option<String>,
     pub url: Option<Url>,
     pub name: Option<String>,
+    pub size: Option<u64>,
+    pub sha256: Option<String>,
     pub tool_spec: ToolSpec,
 }
 
@@ -59,10 +98,122 @@ impl Artifact {
             id: Some(asset.id.to_string()),
             url: Some(asset.url.clone()),
             name: Some(name.to_string()),
+            size: asset.size,
+            sha256: asset
+                .digest
+                .as_deref()
+                .and_then(|digest| digest.strip_prefix("sha256:"))
+                .map(str::to_string),
+            tool_spec: spec.clone(),
+        }
+    }
+
+    pub(crate) fn from_github_repository_archive(url: Url, spec: &ToolSpec) -> Self {
+        let name = format!("{}-{}", spec.name(), spec.git_ref().unwrap_or_default());
+        Self {
+            provider: ArtifactProvider::GitHub,
+            format: Some(ArtifactFormat::Zip),
+            id: None,
+            url: Some(url),
+            name: Some(name),
+            size: None,
+            sha256: None,
+            tool_spec: spec.clone(),
+        }
+    }
+
+    pub(crate) fn from_github_workflow_artifact(
+        artifact: &GithubWorkflowArtifact,
+        spec: &ToolSpec,
+    ) -> Self {
+        Self {
+            provider: ArtifactProvider::GitHub,
+            format: Some(ArtifactFormat::Zip),
+            id: None,
+            url: Some(artifact.archive_download_url.clone()),
+            name: Some(artifact.name.clone()),
+            // NOTE: Sizes of older workflow artifacts are the size of
+            // their files before zipping, and can not be verified against
+            size: None,
+            sha256: artifact
+                .digest
+                .as_deref()
+                .and_then(|digest| digest.strip_prefix("sha256:"))
+                .map(str::to_string),
             tool_spec: spec.clone(),
         }
     }
 
+    /**
+        Gets the file name of this artifact, including the
+        extension for its format, such as `rojo-7.4.4-linux.zip`.
+    */
+    #[must_use]
+    pub fn file_name(&self) -> Option<String> {
+        let name = self.name.as_deref()?;
+        Some(match self.format {
+            Some(format) => format!("{name}.{}", format.as_str()),
+            None => name.to_string(),
+        })
+    }
+
+    /**
+        Checks if this artifact is for the release asset with exactly the
+        given name, such as `rojo-7.4.4-linux.tgz`, including its extension.
+    */
+    #[must_use]
+    pub fn has_asset_name(&self, asset_name: &str) -> bool {
+        let (name, extensions) = split_filename_and_extensions(asset_name);
+        self.name.as_deref() == Some(name)
+            && self.format == ArtifactFormat::from_extensions(extensions)
+    }
+
+    /**
+        Checks if the asset with the given name could possibly be installed.
+
+        Installers, system packages, checksums, signatures, and similar files,
+        are never installable, and should be filtered out before selection.
+    */
+    pub(crate) fn is_installable_asset(name: &str) -> bool {
+        !is_non_installable_asset(name)
+    }
+
+    /**
+        Checks if the asset with the given name is a file
+        containing SHA-256 checksums for other assets.
+    */
+    pub(crate) fn is_checksum_asset(name: &str) -> bool {
+        is_checksum_asset(name)
+    }
+
+    /**
+        Finds the SHA-256 hash for this artifact in the contents of a
+        checksum file with the given name, published with its release.
+
+        Returns `None` if the checksum file does not contain this artifact.
+    */
+    #[must_use]
+    pub fn find_sha256_in_checksums(
+        &self,
+        checksums_name: &str,
+        checksums: &str,
+    ) -> Option<String> {
+        let name = self.name.as_deref()?;
+        parse_checksums(checksums)
+            .into_iter()
+            .find_map(|(hash, file_name)| {
+                // Files with the hash of a single asset are named after that asset
+                let file_name = if file_name.is_empty() {
+                    checksums_name.rsplit_once('.')?.0
+                } else {
+                    file_name
+                };
+                let (stem, extensions) = split_filename_and_extensions(file_name);
+                let format = ArtifactFormat::from_extensions(extensions);
+                (stem == name && format == self.format).then(|| hash.to_ascii_lowercase())
+            })
+    }
+
     /**
         Extract the contents of the artifact.
 
@@ -73,56 +224,213 @@ impl Artifact {
         is used to both create and download the artifact, the format
         should be known and the contents should be in the correct format.
     */
-    #[instrument(skip(self, contents), level = "debug")]
     pub async fn extract_contents(&self, contents: Vec<u8>) -> RokitResult<Vec<u8>> {
+        self.extract_contents_matching(contents, None, ExtractLimits::default())
+            .await
+    }
+
+    /**
+        Extract the contents of the artifact, using the given glob pattern
+        to find the tool binary inside of archives, if one is given, and
+        never extracting more than the given limits allow.
+
+        See [`Artifact::extract_contents`] for more information.
+
+        # Errors
+
+        - If the file pattern is not a valid glob pattern.
+        - If the contents could not be extracted, or did not contain the tool.
+        - If the contents exceed the given limits.
+        - If the tool binary is not compatible with the current system.
+    */
+    pub async fn extract_contents_matching(
+        &self,
+        contents: Vec<u8>,
+        file_pattern: Option<&str>,
+        limits: ExtractLimits,
+    ) -> RokitResult<Vec<u8>> {
+        let (file_bytes, _) = self
+            .extract_impl(contents, file_pattern, false, limits)
+            .await?;
+        Ok(file_bytes)
+    }
+
+    /**
+        Extract the tool binary from the artifact, together with all other
+        files found next to it in the archive, such as libraries or data
+        files that the tool needs at runtime.
+
+        Paths of the other files are relative to the directory
+        that contained the tool binary in the archive.
+
+        See [`Artifact::extract_contents_matching`] for more information.
+
+        # Errors
+
+        - If the file pattern is not a valid glob pattern.
+        - If the contents could not be extracted, or did not contain the tool.
+        - If the contents exceed the given limits.
+        - If the tool binary is not compatible with the current system.
+    */
+    pub async fn extract_all_contents_matching(
+        &self,
+        contents: Vec<u8>,
+        file_pattern: Option<&str>,
+        limits: ExtractLimits,
+    ) -> RokitResult<(Vec<u8>, Vec<ExtractedFile>)> {
+        self.extract_impl(contents, file_pattern, true, limits)
+            .await
+    }
+
+    #[instrument(skip(self, contents), level = "debug")]
+    async fn extract_impl(
+        &self,
+        contents: Vec<u8>,
+        file_pattern: Option<&str>,
+        all_files: bool,
+        limits: ExtractLimits,
+    ) -> RokitResult<(Vec<u8>, Vec<ExtractedFile>)> {
         let format = self.format.ok_or(ExtractError::UnknownFormat)?;
+        let file_pattern = file_pattern
+            .map(|pattern| {
+                Pattern::new(pattern).map_err(|source| ExtractError::InvalidFilePattern {
+                    pattern: pattern.to_string(),
+                    source,
+                })
+            })
+            .transpose()?;
 
         let file_name = self.tool_spec.name().to_string();
-        let file_res = match format {
-            ArtifactFormat::Zip => extract_zip_file(&contents, &file_name).await,
-            ArtifactFormat::Tar => extract_tar_file(&contents, &file_name).await,
-            ArtifactFormat::TarGz => {
-                let tar = decompress_gzip(&contents).await?;
-                extract_tar_file(&tar, &file_name).await
-            }
-            ArtifactFormat::Gz => decompress_gzip(&contents).await.map(Some),
+        let extract = |format, contents| {
+            extract_files(
+                format,
+                contents,
+                &file_name,
+                file_pattern.as_ref(),
+                all_files,
+                limits,
+            )
+        };
+        let file_res = match extract(format, &contents).await {
+            // Some tools ship an archive containing another archive, so if
+            // we didn't find the file, look for a nested archive containing it
+            Ok(None) => match extract_nested_archive(&contents, format, limits).await {
+                Ok(Some((nested_format, _, nested))) => extract(nested_format, &nested).await,
+                Ok(None) => Ok(None),
+                Err(e) => Err(e),
+            },
+            res => res,
         };
 
         // Make sure we got back the file we need ...
 
-        let file_opt = file_res.map_err(|err| ExtractError::Generic {
-            source: err.into(),
-            body: {
-                if contents.len() > 128 + 6 {
-                    let bytes = contents.iter().copied().take(128).collect::<Vec<_>>();
-                    format!("{} <...>", String::from_utf8_lossy(bytes.as_slice()).trim())
-                } else {
-                    String::from_utf8_lossy(&contents).to_string()
-                }
+        let file_opt = file_res.map_err(|err| match err {
+            // Archives exceeding the extraction limits or with unsafe paths are
+            // reported as-is, since their first bytes would not help with anything
+            RokitError::Extract(e) if e.is_limit_exceeded() || e.is_unsafe_path() => {
+                e.with_archive_name(self.name.as_deref().unwrap_or_default())
+            }
+            err => ExtractError::Generic {
+                source: err.into(),
+                body: {
+                    // NOTE: Error pages may echo back request details, including tokens
+                    let body = if contents.len() > 128 + 6 {
+                        let bytes = contents.iter().copied().take(128).collect::<Vec<_>>();
+                        format!("{} <...>", String::from_utf8_lossy(bytes.as_slice()).trim())
+                    } else {
+                        String::from_utf8_lossy(&contents).to_string()
+                    };
+                    redact_tokens(&body).into_owned()
+                },
             },
         })?;
 
-        let file_bytes = file_opt.ok_or_else(|| ExtractError::FileMissing {
+        let (file_bytes, files) = file_opt.ok_or_else(|| ExtractError::FileMissing {
             format,
             file_name: self.tool_spec.name().to_string(),
             archive_name: self.name.clone().unwrap_or_default(),
         })?;
 
-        // ... and parse the OS from the executable binary, or error,
-        // to ensure that the user will actually be able to run it
-
-        let os_current = OS::current_system();
-        let os_file = OS::detect_from_executable(&file_bytes);
-        if os_file.is_some_and(|os| os != os_current) {
-            Err(ExtractError::OSMismatch {
-                current_os: os_current,
-                file_os: os_file.unwrap(),
-                file_name: self.tool_spec.name().to_string(),
-                archive_name: self.name.clone().unwrap_or_default(),
-            })?;
+        // ... and parse the OS and architecture from the executable binary,
+        // or error, to ensure that the user will actually be able to run it
+
+        let desc_current = Descriptor::current_system();
+        let desc_file = Descriptor::detect_from_executable(&file_bytes);
+        if let Some(desc_file) = desc_file {
+            if desc_file.os() != desc_current.os() {
+                Err(ExtractError::OSMismatch {
+                    current_os: desc_current.os(),
+                    file_os: desc_file.os(),
+                    file_name: self.tool_spec.name().to_string(),
+                    archive_name: self.name.clone().unwrap_or_default(),
+                })?;
+            }
+
+            // NOTE: Emulated binaries, such as x64 binaries on Apple Silicon, are
+            // still compatible - callers may warn about those, if they want to
+            if !desc_current.is_compatible_with(&desc_file) {
+                if let (Some(current_arch), Some(file_arch)) =
+                    (desc_current.arch(), desc_file.arch())
+                {
+                    Err(ExtractError::ArchMismatch {
+                        current_arch,
+                        file_arch,
+                        file_name: self.tool_spec.name().to_string(),
+                        archive_name: self.name.clone().unwrap_or_default(),
+                    })?;
+                }
+            }
+
+            // The executable may also require glibc on a system that only has musl,
+            // or a newer glibc than the system has, in which case it will fail
+            // to run with a cryptic loader error, so we try to warn about that
+            if desc_file.toolchain() == Some(Toolchain::Gnu) {
+                self.warn_about_glibc_requirements(&file_bytes);
+            }
         }
 
-        Ok(file_bytes)
+        Ok((file_bytes, files))
+    }
+
+    fn warn_about_glibc_requirements(&self, file_bytes: &[u8]) {
+        if Toolchain::current_system() == Some(Toolchain::Musl) {
+            warn!(
+                "Tool {} is dynamically linked against glibc, but this system only has musl.\
+                \nIt will most likely fail to run - consider using a musl build of the tool instead.",
+                self.tool_spec,
+            );
+        } else if let (Some(required), Some(current)) = (
+            Toolchain::required_glibc_version(file_bytes),
+            Toolchain::current_glibc_version(),
+        ) {
+            if required > current {
+                warn!(
+                    "Tool {} requires glibc {required}, but this system has glibc {current}.\
+                    \nIt will most likely fail to run - consider using a musl build of the tool instead.",
+                    self.tool_spec,
+                );
+            }
+        }
+    }
+
+    /**
+        Finds another artifact in the given artifacts, such as from the same
+        release, that is named as being native to the current system.
+
+        Useful for suggesting alternatives when an extracted binary
+        would only be able to run on the current system using emulation.
+    */
+    #[must_use]
+    pub fn find_native_alternative<'a>(&self, artifacts: &'a [Self]) -> Option<&'a Self> {
+        let current_desc = Descriptor::current_system();
+        artifacts.iter().find(|artifact| {
+            artifact.name != self.name
+                && artifact
+                    .name
+                    .as_deref()
+                    .and_then(Descriptor::detect)
+                    .is_some_and(|desc| current_desc.is_natively_compatible_with(&desc))
+        })
     }
 
     /**
@@ -135,7 +443,21 @@ impl Artifact {
         - [`Descriptor::sort_by_preferred_compat`]
     */
     pub fn sort_by_system_compatibility(artifacts: impl AsRef<[Self]>) -> Vec<Self> {
-        Self::sort_by_system_compatibility_inner(artifacts, false)
+        Self::sort_by_compatibility_inner(artifacts, Descriptor::current_system(), false)
+    }
+
+    /**
+        Sorts the given artifacts by their compatibility with the given target
+        system, which does not need to be the current system - this is useful
+        for choosing artifacts to download for other systems.
+
+        See [`Artifact::sort_by_system_compatibility`] for more information.
+    */
+    pub fn sort_by_target_compatibility(
+        artifacts: impl AsRef<[Self]>,
+        target: Descriptor,
+    ) -> Vec<Self> {
+        Self::sort_by_compatibility_inner(artifacts, target, false)
     }
 
     /**
@@ -145,28 +467,56 @@ impl Artifact {
 
         Note that this not is guaranteed to be compatible with the current
         system, the contents of the artifact should be checked before use.
+
+        Systems that can never run executables for other architectures, such
+        as Windows on 32-bit ARM, never get a fallback - see
+        [`Descriptor::can_run_other_architectures`] for details.
     */
     pub fn find_partially_compatible_fallback(artifacts: impl AsRef<[Self]>) -> Option<Self> {
-        Self::sort_by_system_compatibility_inner(artifacts, true)
+        Self::sort_by_compatibility_inner(artifacts, Descriptor::current_system(), true)
             .into_iter()
             .next()
     }
 
-    fn sort_by_system_compatibility_inner(
+    /**
+        Groups the file names of the given artifacts by the platform
+        they were built for, such as `windows-x64` - artifacts for
+        platforms that could not be detected are grouped as `unknown`.
+
+        This is useful for telling users which platforms a release supports.
+    */
+    #[must_use]
+    pub fn group_by_platform(artifacts: impl AsRef<[Self]>) -> BTreeMap<String, Vec<String>> {
+        let mut platforms = BTreeMap::<String, Vec<String>>::new();
+        for artifact in artifacts.as_ref() {
+            let (Some(name), Some(file_name)) = (artifact.name.as_deref(), artifact.file_name())
+            else {
+                continue;
+            };
+            let platform = Descriptor::detect(name)
+                .map_or_else(|| "unknown".to_string(), |desc| desc.platform_name());
+            platforms.entry(platform).or_default().push(file_name);
+        }
+        platforms
+    }
+
+    fn sort_by_compatibility_inner(
         artifacts: impl AsRef<[Self]>,
+        target_desc: Descriptor,
         allow_partial_compatibility: bool,
     ) -> Vec<Self> {
-        let current_desc = Descriptor::current_system();
-
         let mut compatible_artifacts = artifacts
             .as_ref()
             .iter()
             .filter_map(|artifact| {
                 let name = artifact.name.as_deref()?;
                 if let Some(asset_desc) = Descriptor::detect(name) {
-                    let is_fully_compatible = current_desc.is_compatible_with(&asset_desc);
-                    let is_os_compatible = current_desc.os() == asset_desc.os();
-                    if is_fully_compatible || (allow_partial_compatibility && is_os_compatible) {
+                    let is_fully_compatible = target_desc.is_compatible_with(&asset_desc);
+                    let is_partially_compatible = target_desc.os() == asset_desc.os()
+                        && target_desc.can_run_other_architectures();
+                    if is_fully_compatible
+                        || (allow_partial_compatibility && is_partially_compatible)
+                    {
                         Some((asset_desc, artifact))
                     } else {
                         None
@@ -178,7 +528,7 @@ impl Artifact {
             .collect::<Vec<_>>();
 
         compatible_artifacts.sort_by(|(desc_a, artifact_a), (desc_b, artifact_b)| {
-            current_desc
+            target_desc
                 .sort_by_preferred_compat(desc_a, desc_b)
                 .then_with(|| sort_preferred_artifact(artifact_a, artifact_b))
                 .then_with(|| sort_preferred_formats(artifact_a, artifact_b))
@@ -190,3 +540,45 @@ impl Artifact {
             .collect()
     }
 }
+
+/**
+    Extracts the file with the given name, or matching the
+    given pattern, from contents in the given format.
+
+    Note that this does not look for the file in any nested archives.
+*/
+async fn extract_files(
+    format: ArtifactFormat,
+    contents: &[u8],
+    file_name: &str,
+    file_pattern: Option<&Pattern>,
+    all_files: bool,
+    limits: ExtractLimits,
+) -> RokitResult<Option<(Vec<u8>, Vec<ExtractedFile>)>> {
+    // NOTE: Gzip files only ever contain a single file, the binary
+    if all_files && format != ArtifactFormat::Gz {
+        extract_all_files(contents, format, file_name, file_pattern.cloned(), limits).await
+    } else {
+        let file = extract_file(format, contents, file_name, file_pattern, limits).await?;
+        Ok(file.map(|file| (file, Vec::new())))
+    }
+}
+
+async fn extract_file(
+    format: ArtifactFormat,
+    contents: &[u8],
+    file_name: &str,
+    file_pattern: Option<&Pattern>,
+    limits: ExtractLimits,
+) -> RokitResult<Option<Vec<u8>>> {
+    let file_pattern = file_pattern.cloned();
+    match format {
+        ArtifactFormat::Zip => extract_zip_file(contents, file_name, file_pattern, limits).await,
+        ArtifactFormat::Tar => extract_tar_file(contents, file_name, file_pattern, limits).await,
+        ArtifactFormat::TarGz => {
+            let tar = decompress_gzip(contents, limits).await?;
+            extract_tar_file(&tar, file_name, file_pattern, limits).await
+        }
+        ArtifactFormat::Gz => decompress_gzip(contents, limits).await.map(Some),
+    }
+}
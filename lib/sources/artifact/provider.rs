@@ -1,28 +1,74 @@
 use std::{fmt, str::FromStr};
 
+use serde_with::{DeserializeFromStr, SerializeDisplay};
+
 /**
     An artifact provider supported by Rokit.
 
     The default provider is [`ArtifactProvider::GitHub`].
+
+    [`ArtifactProvider::GitHubBranch`] is used for tools without releases,
+    which are instead downloaded as an archive of a branch, tag, or commit
+    of their GitHub repository, and the version of such a tool is its git ref.
+
+    [`ArtifactProvider::GitHubActions`] is used for unreleased builds of tools,
+    which are downloaded from the artifacts of a successful run of a GitHub
+    Actions workflow, and the version of such a tool is the ID of the run.
 */
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    DeserializeFromStr,
+    SerializeDisplay,
+)]
 pub enum ArtifactProvider {
     #[default]
     GitHub,
+    GitHubBranch,
+    GitHubActions,
 }
 
 impl ArtifactProvider {
+    /**
+        All artifact providers supported by Rokit that can be authenticated with.
+
+        Providers that download artifacts from the same host as another provider,
+        such as [`ArtifactProvider::GitHubBranch`], are not included here, and use
+        the authentication of their host instead - see [`ArtifactProvider::host`].
+    */
+    pub const ALL: [Self; 1] = [Self::GitHub];
+
     #[must_use]
     pub fn as_str(self) -> &'static str {
         match self {
             Self::GitHub => "github",
+            Self::GitHubBranch => "github-branch",
+            Self::GitHubActions => "github-actions",
         }
     }
 
     #[must_use]
     pub fn display_name(self) -> &'static str {
         match self {
-            Self::GitHub => "GitHub",
+            Self::GitHub | Self::GitHubBranch | Self::GitHubActions => "GitHub",
+        }
+    }
+
+    /**
+        Gets the provider that hosts artifacts for this provider,
+        and whose authentication is used when downloading them.
+    */
+    #[must_use]
+    pub fn host(self) -> Self {
+        match self {
+            Self::GitHub | Self::GitHubBranch | Self::GitHubActions => Self::GitHub,
         }
     }
 }
@@ -33,6 +79,8 @@ impl FromStr for ArtifactProvider {
         let l = s.trim().to_lowercase();
         match l.as_str() {
             "github" => Ok(Self::GitHub),
+            "github-branch" => Ok(Self::GitHubBranch),
+            "github-actions" => Ok(Self::GitHubActions),
             _ => Err(format!("unknown artifact provider '{l}'")),
         }
     }
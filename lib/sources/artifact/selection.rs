@@ -0,0 +1,150 @@
+use std::cmp::Ordering;
+
+use crate::{descriptor::Descriptor, result::RokitResult, sources::ArtifactSource, tool::ToolSpec};
+
+use super::{
+    util::{is_non_installable_asset, split_filename_and_extensions},
+    Artifact, ArtifactFormat,
+};
+
+/**
+    Selects the asset that is most compatible with the current system,
+    out of a list of asset names, such as those of a GitHub release.
+
+    Returns the index of the selected asset in the given list, or `None`
+    if no asset is compatible with the current system.
+
+    See [`select_best_asset_for`] for more information.
+*/
+#[must_use]
+pub fn select_best_asset<S: AsRef<str>>(names: &[S]) -> Option<usize> {
+    select_best_asset_for(names, Descriptor::current_system())
+}
+
+/**
+    Selects the asset that is most compatible with the given system,
+    out of a list of asset names, such as those of a GitHub release.
+
+    Assets that could never be installed, such as installers, system packages,
+    and checksum files, are never selected. Out of the remaining assets, the
+    one with the most preferred operating system, architecture, and toolchain
+    is selected, and then the one with the most preferred archive format.
+
+    Returns the index of the selected asset in the given list, or `None`
+    if no asset is compatible with the given system.
+*/
+#[must_use]
+pub fn select_best_asset_for<S: AsRef<str>>(names: &[S], system: Descriptor) -> Option<usize> {
+    names
+        .iter()
+        .enumerate()
+        .filter_map(|(index, name)| {
+            let name = name.as_ref();
+            if is_non_installable_asset(name) {
+                return None;
+            }
+            let (stem, extensions) = split_filename_and_extensions(name);
+            let format = ArtifactFormat::from_extensions(extensions);
+            let desc = Descriptor::detect(stem)?;
+            system
+                .is_compatible_with(&desc)
+                .then_some((index, desc, format))
+        })
+        .min_by(|(index_a, desc_a, format_a), (index_b, desc_b, format_b)| {
+            system
+                .sort_by_preferred_compat(desc_a, desc_b)
+                .then_with(|| match (format_a, format_b) {
+                    (None, None) => Ordering::Equal,
+                    (None, _) => Ordering::Greater,
+                    (_, None) => Ordering::Less,
+                    (Some(a), Some(b)) => a.cmp(b),
+                })
+                .then_with(|| index_a.cmp(index_b))
+        })
+        .map(|(index, _, _)| index)
+}
+
+/**
+    Fetches the release for the given tool specification, such as
+    `rojo-rbx/rojo@7.4.1`, and selects the artifact that is most
+    compatible with the given target system.
+
+    The target system does not need to be the current system, and is typically
+    created using [`Descriptor::from_target_triple`]. The selected artifact
+    contains the URL and format of the asset, and can be downloaded using
+    [`ArtifactSource::download_artifact_contents`].
+
+    Returns `None` if the release has no artifact compatible with the target system.
+
+    # Errors
+
+    - If the release could not be fetched.
+*/
+pub async fn select_release_artifact(
+    source: &ArtifactSource,
+    spec: &ToolSpec,
+    target: Descriptor,
+) -> RokitResult<Option<Artifact>> {
+    let release = source.get_specific_release(spec).await?;
+    Ok(
+        Artifact::sort_by_target_compatibility(release.artifacts, target)
+            .into_iter()
+            .next(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ASSETS: [&str; 7] = [
+        "rojo-7.4.1-linux-x86_64.zip",
+        "rojo-7.4.1-linux-x86_64.zip.sha256",
+        "rojo-7.4.1-macos-aarch64.tar.gz",
+        "rojo-7.4.1-macos-aarch64.zip",
+        "rojo-7.4.1-windows-x86_64.zip",
+        "rojo-7.4.1-windows-x86_64.msi",
+        "rojo_7.4.1_amd64.deb",
+    ];
+
+    fn system(search: &str) -> Descriptor {
+        Descriptor::detect(search).unwrap()
+    }
+
+    #[test]
+    fn selects_compatible_asset() {
+        assert_eq!(
+            select_best_asset_for(&ASSETS, system("linux-x86_64")),
+            Some(0)
+        );
+        assert_eq!(
+            select_best_asset_for(&ASSETS, system("windows-x86_64")),
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn selects_preferred_format() {
+        assert_eq!(
+            select_best_asset_for(&ASSETS, system("macos-aarch64")),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn selects_for_current_system() {
+        assert_eq!(
+            select_best_asset(&ASSETS),
+            select_best_asset_for(&ASSETS, Descriptor::current_system())
+        );
+    }
+
+    #[test]
+    fn selects_nothing_when_incompatible() {
+        assert_eq!(
+            select_best_asset_for(&ASSETS, system("windows-aarch64")),
+            None
+        );
+        assert_eq!(select_best_asset_for::<&str>(&[], system("linux")), None);
+    }
+}
@@ -194,6 +194,8 @@ mod tests {
                 id: Some("id".to_string()),
                 url: Some("https://github.com".parse().unwrap()),
                 name: Some(name.to_string()),
+                size: None,
+                sha256: None,
                 tool_spec: new_id("author", name).into_spec(Version::parse("1.0.0").unwrap()),
             })
             .collect::<Vec<_>>();
@@ -3,39 +3,137 @@ use std::path::Path;
 const ALLOWED_EXTENSION_NAMES: [&str; 4] = ["zip", "tar", "gz", "tgz"];
 const ALLOWED_EXTENSION_COUNT: usize = 2;
 
-pub(super) fn split_filename_and_extensions(name: &str) -> (&str, Vec<&str>) {
-    let mut path = Path::new(name);
-    let mut exts = Vec::new();
+// Installers, system packages, checksums, signatures, and other
+// metadata files, which sometimes share names with tool archives
+const NON_INSTALLABLE_EXTENSION_NAMES: [&str; 20] = [
+    "msi", "msix", "deb", "rpm", "dmg", "pkg", "apk", "snap", "flatpak", "sha1", "sha256",
+    "sha512", "md5", "sig", "asc", "minisig", "pem", "sbom", "json", "txt",
+];
+const NON_INSTALLABLE_FILE_NAMES: [&str; 4] = ["checksums", "sha256sums", "sha512sums", "md5sums"];
 
-    // Reverse-pop extensions off the path until we reach the
-    // base name - we will then need to reverse afterwards, too
-    while let Some(ext) = path.extension() {
-        let ext = ext.to_str().expect("input was str");
-        let stem = path.file_stem().expect("had an extension");
+/**
+    Checks if the given asset name is for an asset that can never
+    contain a tool that Rokit could install, such as an installer,
+    system package, checksum, or signature file.
+*/
+pub(super) fn is_non_installable_asset(name: &str) -> bool {
+    let path = Path::new(name);
+    let has_extension = |names: &[&str]| {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| names.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+    };
+    let has_file_name = |names: &[&str]| {
+        path.file_stem()
+            .and_then(|stem| stem.to_str())
+            .is_some_and(|stem| names.iter().any(|n| n.eq_ignore_ascii_case(stem)))
+    };
+    has_extension(&NON_INSTALLABLE_EXTENSION_NAMES) || has_file_name(&NON_INSTALLABLE_FILE_NAMES)
+}
 
-        if !ALLOWED_EXTENSION_NAMES
-            .iter()
-            .any(|e| e.eq_ignore_ascii_case(ext))
-        {
-            break;
-        }
+// Checksum files that Rokit knows how to read - these
+// always contain SHA-256 hashes, one line per file
+const CHECKSUM_EXTENSION_NAMES: [&str; 2] = ["sha256", "sha256sum"];
+const CHECKSUM_FILE_NAMES: [&str; 2] = ["checksums", "sha256sums"];
 
-        exts.push(ext);
-        path = Path::new(stem);
+/**
+    Checks if the given asset name is for a file containing SHA-256
+    checksums, either for a single asset, or for all assets in a release.
+*/
+pub(super) fn is_checksum_asset(name: &str) -> bool {
+    let path = Path::new(name);
+    let has_extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            CHECKSUM_EXTENSION_NAMES
+                .iter()
+                .any(|e| e.eq_ignore_ascii_case(ext))
+        });
+    let has_file_name = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .is_some_and(|stem| {
+            CHECKSUM_FILE_NAMES.iter().any(|n| {
+                stem.eq_ignore_ascii_case(n)
+                    || stem.to_ascii_lowercase().ends_with(&format!("-{n}"))
+            })
+        });
+    has_extension || has_file_name
+}
+
+/**
+    Parses the contents of a checksum file, in the format used by `sha256sum`,
+    returning pairs of hashes and file names - the file name may be empty,
+    for files that only contain the hash of a single file.
+*/
+pub(super) fn parse_checksums(contents: &str) -> Vec<(&str, &str)> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let (hash, file_name) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let is_sha256 = hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit());
+            if !is_sha256 {
+                return None;
+            }
+            // NOTE: Binary mode is marked using an asterisk before the file name
+            let file_name = file_name.trim_start().trim_start_matches('*');
+            let file_name = file_name.trim_start_matches("./");
+            Some((hash, file_name))
+        })
+        .collect()
+}
+
+/**
+    Splits the given file name into its base name and known archive
+    extensions, such as `tool-1.2.3-linux` and `["tar", "gz"]` for
+    `tool-1.2.3-linux.tar.gz` - at most two extensions are split off.
+
+    Paths and URLs may also be given, in which case only the last
+    segment is used, without any query string or fragment.
+
+    Version numbers, and any other dotted parts of names, such as the
+    `1.2.3.linux.amd64` in `tool-1.2.3.linux.amd64.tar.gz`, always stay
+    in the base name, since only known archive extensions are split off.
+    Extensions are matched case-insensitively, but returned as given.
+*/
+#[must_use]
+pub fn split_filename_and_extensions(name: &str) -> (&str, Vec<&str>) {
+    let name = name.split(['?', '#']).next().unwrap_or_default();
+    let name = name.rsplit(['/', '\\']).next().unwrap_or_default();
 
-        if exts.len() >= ALLOWED_EXTENSION_COUNT {
+    // Reverse-pop extensions off the name until we reach the
+    // base name - we will then need to reverse afterwards, too
+    let mut stem = name;
+    let mut exts = Vec::new();
+    while exts.len() < ALLOWED_EXTENSION_COUNT {
+        let Some((rest, ext)) = stem.rsplit_once('.') else {
+            break;
+        };
+        // NOTE: Names starting with a dot, such as `.tar`, are hidden
+        // files without any extension, and never have an empty base name
+        let is_allowed = ALLOWED_EXTENSION_NAMES
+            .iter()
+            .any(|e| e.eq_ignore_ascii_case(ext));
+        if rest.is_empty() || !is_allowed {
             break;
         }
+        exts.push(ext);
+        stem = rest;
     }
 
     exts.reverse();
-
-    let path = path.to_str().expect("input was str");
-    (path, exts)
+    (stem, exts)
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::{
+        descriptor::{Arch, OS},
+        sources::ArtifactFormat,
+    };
+
     use super::*;
 
     #[test]
@@ -85,4 +183,183 @@ mod tests {
             ("sentry-cli-linux-i686-2.32.1", vec!["tgz"])
         );
     }
+
+    #[test]
+    fn split_filename_ext_paths_and_urls() {
+        assert_eq!(
+            split_filename_and_extensions("dist/bin/tool-1.2.3.tar.gz"),
+            ("tool-1.2.3", vec!["tar", "gz"])
+        );
+        assert_eq!(
+            split_filename_and_extensions("C:\\Downloads\\tool-1.2.3.zip"),
+            ("tool-1.2.3", vec!["zip"])
+        );
+        assert_eq!(
+            split_filename_and_extensions(
+                "https://github.com/owner/tool/releases/download/v1.2/tool-v1.2-linux.zip?raw=true"
+            ),
+            ("tool-v1.2-linux", vec!["zip"])
+        );
+        assert_eq!(
+            split_filename_and_extensions("https://example.com/tool-1.2.3.tgz#download"),
+            ("tool-1.2.3", vec!["tgz"])
+        );
+        assert_eq!(split_filename_and_extensions(".tar"), (".tar", vec![]));
+        assert_eq!(
+            split_filename_and_extensions(".tar.gz"),
+            (".tar", vec!["gz"])
+        );
+        assert_eq!(split_filename_and_extensions(""), ("", vec![]));
+    }
+
+    #[test]
+    fn split_filename_ext_versioned_names() {
+        // Real-world asset names, along with their expected base
+        // name, format, operating system, and architecture
+        let fixtures = [
+            (
+                "tool-1.2.3.linux.amd64.tar.gz",
+                "tool-1.2.3.linux.amd64",
+                Some(ArtifactFormat::TarGz),
+                Some(OS::Linux),
+                Some(Arch::X64),
+            ),
+            (
+                "tool-v1.2.linux.gz",
+                "tool-v1.2.linux",
+                Some(ArtifactFormat::Gz),
+                Some(OS::Linux),
+                None,
+            ),
+            ("tool-1.2.3", "tool-1.2.3", None, None, None),
+            (
+                "tool-1.2.3-linux-x86_64",
+                "tool-1.2.3-linux-x86_64",
+                None,
+                Some(OS::Linux),
+                Some(Arch::X64),
+            ),
+            (
+                "tool.1.2.3.windows.x64.zip",
+                "tool.1.2.3.windows.x64",
+                Some(ArtifactFormat::Zip),
+                Some(OS::Windows),
+                None,
+            ),
+            (
+                "stylua-0.20.0-linux-aarch64.zip",
+                "stylua-0.20.0-linux-aarch64",
+                Some(ArtifactFormat::Zip),
+                Some(OS::Linux),
+                Some(Arch::Arm64),
+            ),
+            (
+                "darklua-macos-x86_64.zip",
+                "darklua-macos-x86_64",
+                Some(ArtifactFormat::Zip),
+                Some(OS::MacOS),
+                Some(Arch::X64),
+            ),
+            (
+                "ripgrep-14.1.0-x86_64-pc-windows-msvc.zip",
+                "ripgrep-14.1.0-x86_64-pc-windows-msvc",
+                Some(ArtifactFormat::Zip),
+                Some(OS::Windows),
+                Some(Arch::X64),
+            ),
+            (
+                "gh_2.40.0_linux_arm64.tar.gz",
+                "gh_2.40.0_linux_arm64",
+                Some(ArtifactFormat::TarGz),
+                Some(OS::Linux),
+                Some(Arch::Arm64),
+            ),
+            (
+                "hyperfine-v1.18.0-i686-unknown-linux-gnu.tar.gz",
+                "hyperfine-v1.18.0-i686-unknown-linux-gnu",
+                Some(ArtifactFormat::TarGz),
+                Some(OS::Linux),
+                Some(Arch::X86),
+            ),
+            (
+                "tool-2.0.0-rc.1-macos-universal.TGZ",
+                "tool-2.0.0-rc.1-macos-universal",
+                Some(ArtifactFormat::TarGz),
+                Some(OS::MacOS),
+                Some(Arch::X64),
+            ),
+            (
+                "tool-1.0.0.tar",
+                "tool-1.0.0",
+                Some(ArtifactFormat::Tar),
+                None,
+                None,
+            ),
+        ];
+
+        for (name, stem, format, os, arch) in fixtures {
+            let (actual_stem, extensions) = split_filename_and_extensions(name);
+            assert_eq!(actual_stem, stem, "base name of {name}");
+            assert_eq!(
+                ArtifactFormat::from_extensions(extensions),
+                format,
+                "format of {name}"
+            );
+            assert_eq!(OS::detect(actual_stem), os, "os of {name}");
+            assert_eq!(Arch::detect(actual_stem), arch, "arch of {name}");
+        }
+    }
+
+    #[test]
+    fn non_installable_real_assets() {
+        let non_installable = [
+            "ripgrep_14.1.0-1_amd64.deb",
+            "ripgrep-14.1.0-x86_64-unknown-linux-musl.tar.gz.sha256",
+            "gh_2.40.0_windows_amd64.msi",
+            "gh_2.40.0_linux_amd64.rpm",
+            "gh_2.40.0_macOS_universal.pkg",
+            "gh_2.40.0_checksums.txt",
+            "sentry-cli-2.32.1.dmg",
+            "SHA256SUMS",
+            "checksums.sha256",
+            "cosign-linux-amd64.sig",
+            "lune-0.8.9-linux-x86_64.zip.minisig",
+        ];
+        for name in non_installable {
+            assert!(is_non_installable_asset(name), "{name} is not installable");
+        }
+
+        let installable = [
+            "rojo-7.4.1-windows-x86_64.zip",
+            "wally-v0.3.2-linux.zip",
+            "just-1.31.0-aarch64-apple-darwin.tar.gz",
+            "sentry-cli-linux-i686-2.32.1.tgz",
+            "selene-0.27.1-linux",
+        ];
+        for name in installable {
+            assert!(!is_non_installable_asset(name), "{name} is installable");
+        }
+    }
+
+    #[test]
+    fn checksum_assets() {
+        assert!(is_checksum_asset("SHA256SUMS"));
+        assert!(is_checksum_asset("checksums.txt"));
+        assert!(is_checksum_asset("rokit-1.0.0-checksums.txt"));
+        assert!(is_checksum_asset("rokit-1.0.0-linux-x86_64.zip.sha256"));
+        assert!(!is_checksum_asset("rokit-1.0.0-linux-x86_64.zip"));
+        assert!(!is_checksum_asset("MD5SUMS"));
+    }
+
+    #[test]
+    fn parse_checksum_formats() {
+        let hash = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        let contents =
+            format!("{hash}  rokit-linux.zip\n{hash} *./rokit-windows.zip\nnot a checksum line\n");
+        assert_eq!(
+            parse_checksums(&contents),
+            vec![(hash, "rokit-linux.zip"), (hash, "rokit-windows.zip")]
+        );
+        assert_eq!(parse_checksums(&format!("{hash}\n")), vec![(hash, "")]);
+    }
 }
@@ -0,0 +1,205 @@
+use std::path::Path;
+
+use reqwest::{header::RANGE, StatusCode};
+use reqwest_middleware::RequestBuilder;
+use thiserror::Error;
+use tokio::{
+    fs::{create_dir_all, metadata, read, remove_file, OpenOptions},
+    io::AsyncWriteExt,
+};
+use tracing::debug;
+
+use crate::{result::ErrorKind, util::hash::sha256_hex};
+
+use super::client::{redact_middleware_error, redact_reqwest_error, redact_tokens};
+
+#[derive(Debug, Error)]
+pub enum DownloadError {
+    #[error("download is incomplete - expected {expected} bytes, but got {actual} bytes")]
+    SizeMismatch { expected: u64, actual: u64 },
+    #[error("download is corrupted - expected SHA-256 hash {expected}, but got {actual}")]
+    HashMismatch { expected: String, actual: String },
+    #[error("failed to store partial download: {0}")]
+    Io(Box<std::io::Error>),
+    #[error("reqwest middleware error: {}", redact_tokens(&.0.to_string()))]
+    ReqwestMiddleware(Box<reqwest_middleware::Error>),
+    #[error("reqwest error: {0}")]
+    Reqwest(Box<reqwest::Error>),
+}
+
+pub type DownloadResult<T> = Result<T, DownloadError>;
+
+impl DownloadError {
+    /**
+        Returns the broad category of this error.
+    */
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Io(_) => ErrorKind::Io,
+            Self::Reqwest(e) => match e.status() {
+                Some(StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) => ErrorKind::Authentication,
+                Some(StatusCode::NOT_FOUND) => ErrorKind::NotFound,
+                _ => ErrorKind::Network,
+            },
+            Self::SizeMismatch { .. } | Self::HashMismatch { .. } | Self::ReqwestMiddleware(_) => {
+                ErrorKind::Network
+            }
+        }
+    }
+}
+
+impl From<std::io::Error> for DownloadError {
+    fn from(err: std::io::Error) -> Self {
+        DownloadError::Io(err.into())
+    }
+}
+
+impl From<reqwest_middleware::Error> for DownloadError {
+    fn from(err: reqwest_middleware::Error) -> Self {
+        DownloadError::ReqwestMiddleware(redact_middleware_error(err).into())
+    }
+}
+
+impl From<reqwest::Error> for DownloadError {
+    fn from(err: reqwest::Error) -> Self {
+        DownloadError::Reqwest(redact_reqwest_error(err).into())
+    }
+}
+
+/**
+    Downloads the response body for a request, persisting the partially
+    downloaded contents at the given path while the download is ongoing.
+
+    If a previous download to the same path was interrupted, and the server
+    supports range requests, the download will continue from where it left off.
+
+    Once complete, the size and SHA-256 hash of the contents are verified, if
+    known, and the partial download is removed - corrupted downloads are never
+    resumed, they will always start over from the beginning on the next attempt.
+*/
+pub(crate) async fn download_resumable<F>(
+    request: F,
+    partial_path: &Path,
+    expected_size: Option<u64>,
+    expected_sha256: Option<&str>,
+) -> DownloadResult<Vec<u8>>
+where
+    F: Fn() -> RequestBuilder,
+{
+    if let Some(parent) = partial_path.parent() {
+        create_dir_all(parent).await?;
+    }
+
+    let mut existing_len = metadata(partial_path).await.map_or(0, |m| m.len());
+    if expected_size.is_some_and(|size| existing_len > size) {
+        remove_file(partial_path).await?;
+        existing_len = 0;
+    }
+
+    let is_complete = existing_len > 0 && expected_size == Some(existing_len);
+    if !is_complete {
+        let mut builder = request();
+        if existing_len > 0 {
+            debug!(
+                path = %partial_path.display(),
+                bytes = existing_len,
+                "resuming partial download"
+            );
+            builder = builder.header(RANGE, format!("bytes={existing_len}-"));
+        }
+
+        // The partial download may no longer match the file on
+        // the server, in which case we need to start over again
+        let mut response = builder.send().await?;
+        if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+            remove_file(partial_path).await?;
+            response = request().send().await?;
+        }
+
+        // NOTE: Servers that don't support range requests will respond with
+        // the full contents and a 200 status, so we only append for a 206
+        let mut response = response.error_for_status()?;
+        let append = response.status() == StatusCode::PARTIAL_CONTENT;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(partial_path)
+            .await?;
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk).await?;
+        }
+        file.flush().await?;
+    }
+
+    let contents = read(partial_path).await?;
+    let verified = verify_contents(&contents, expected_size, expected_sha256);
+    remove_file(partial_path).await?;
+    verified?;
+
+    Ok(contents)
+}
+
+fn verify_contents(
+    contents: &[u8],
+    expected_size: Option<u64>,
+    expected_sha256: Option<&str>,
+) -> DownloadResult<()> {
+    let actual_size = contents.len() as u64;
+    if let Some(expected_size) = expected_size {
+        if actual_size != expected_size {
+            return Err(DownloadError::SizeMismatch {
+                expected: expected_size,
+                actual: actual_size,
+            });
+        }
+    }
+
+    if let Some(expected_sha256) = expected_sha256 {
+        let actual_sha256 = sha256_hex(contents);
+        if !actual_sha256.eq_ignore_ascii_case(expected_sha256) {
+            return Err(DownloadError::HashMismatch {
+                expected: expected_sha256.to_string(),
+                actual: actual_sha256,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABC_SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[test]
+    fn verify_unknown_size_and_hash() {
+        assert!(verify_contents(b"abc", None, None).is_ok());
+    }
+
+    #[test]
+    fn verify_size() {
+        assert!(verify_contents(b"abc", Some(3), None).is_ok());
+        assert!(matches!(
+            verify_contents(b"ab", Some(3), None),
+            Err(DownloadError::SizeMismatch {
+                expected: 3,
+                actual: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn verify_hash() {
+        assert!(verify_contents(b"abc", Some(3), Some(ABC_SHA256)).is_ok());
+        assert!(verify_contents(b"abc", None, Some(&ABC_SHA256.to_uppercase())).is_ok());
+        assert!(matches!(
+            verify_contents(b"abd", None, Some(ABC_SHA256)),
+            Err(DownloadError::HashMismatch { .. })
+        ));
+    }
+}
@@ -1,22 +1,35 @@
 #![allow(clippy::struct_excessive_bools)]
 
 use std::{
+    collections::HashMap,
     env::consts::{EXE_EXTENSION, EXE_SUFFIX},
     io::{self, Read},
-    path::{Path, PathBuf, MAIN_SEPARATOR_STR},
+    path::{Component, Path, PathBuf, MAIN_SEPARATOR_STR},
 };
 
-use tar::Archive as TarArchive;
+use flate2::read::GzDecoder;
+use glob::{MatchOptions, Pattern, PatternError};
+use tar::{Archive as TarArchive, Entry as TarEntry};
 use thiserror::Error;
 use tokio::{task::spawn_blocking, time::Instant};
 use zip::ZipArchive;
 
-use crate::{descriptor::OS, result::RokitResult, sources::ArtifactFormat};
+use crate::{
+    descriptor::{Arch, Descriptor, OS},
+    result::{ErrorKind, RokitResult},
+    sources::ArtifactFormat,
+    util::path::is_safe_relative_path,
+};
 
 #[derive(Debug, Error)]
 pub enum ExtractError {
     #[error("unknown format")]
     UnknownFormat,
+    #[error("invalid file pattern '{pattern}' - {source}")]
+    InvalidFilePattern {
+        pattern: String,
+        source: PatternError,
+    },
     #[error(
         "missing binary '{file_name}' \
         in {format} file '{archive_name}'"
@@ -36,6 +49,51 @@ pub enum ExtractError {
         file_name: String,
         archive_name: String,
     },
+    #[error(
+        "mismatch in architecture for binary '{file_name}' in archive '{archive_name}'\
+        \ncurrent architecture is {current_arch:?}, binary is {file_arch:?}"
+    )]
+    ArchMismatch {
+        current_arch: Arch,
+        file_arch: Arch,
+        file_name: String,
+        archive_name: String,
+    },
+    #[error(
+        "file '{path}' in archive '{archive_name}' is larger than the limit of {} MB\
+        \nthe archive may be malformed or malicious, and was not extracted\
+        \nthe limit may be changed using the 'extract-limits' setting of a Rokit manifest",
+        limit / MEGABYTE
+    )]
+    FileTooLarge {
+        path: String,
+        archive_name: String,
+        limit: u64,
+    },
+    #[error(
+        "archive '{archive_name}' decompresses to more than the limit of {} MB\
+        \nthe archive may be malformed or malicious, and was not extracted\
+        \nthe limit may be changed using the 'extract-limits' setting of a Rokit manifest",
+        limit / MEGABYTE
+    )]
+    ArchiveTooLarge { archive_name: String, limit: u64 },
+    #[error(
+        "{} in archive '{archive_name}' decompresses to more than \
+        {ratio} times its compressed size, which is typical of zip bombs\
+        \nthe archive may be malformed or malicious, and was not extracted",
+        if path.is_empty() { String::from("contents") } else { format!("file '{path}'") }
+    )]
+    SuspiciousCompression {
+        path: String,
+        archive_name: String,
+        ratio: u64,
+    },
+    #[error(
+        "file '{path}' in archive{} has a path that could escape the directory it is installed to\
+        \nthe archive may be malformed or malicious, and was not extracted",
+        if archive_name.is_empty() { String::new() } else { format!(" '{archive_name}'") }
+    )]
+    UnsafePath { path: String, archive_name: String },
     #[error(
         "{source}\
         \nresponse body first bytes:\
@@ -47,6 +105,196 @@ pub enum ExtractError {
     },
 }
 
+impl ExtractError {
+    /**
+        Returns the broad category of this error.
+    */
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::OSMismatch { .. } | Self::ArchMismatch { .. } => ErrorKind::IncompatiblePlatform,
+            _ => ErrorKind::Extraction,
+        }
+    }
+
+    /**
+        Returns `true` if this error was caused by one of the [`ExtractLimits`].
+    */
+    #[must_use]
+    pub fn is_limit_exceeded(&self) -> bool {
+        matches!(
+            self,
+            Self::FileTooLarge { .. }
+                | Self::ArchiveTooLarge { .. }
+                | Self::SuspiciousCompression { .. }
+        )
+    }
+
+    /**
+        Returns `true` if this error was caused by a file in an archive
+        with a path that could escape the directory it is installed to.
+    */
+    #[must_use]
+    pub fn is_unsafe_path(&self) -> bool {
+        matches!(self, Self::UnsafePath { .. })
+    }
+
+    pub(crate) fn unsafe_path(path: &Path) -> Self {
+        Self::UnsafePath {
+            path: path.display().to_string(),
+            archive_name: String::new(),
+        }
+    }
+
+    /*
+        Sets the name of the archive that this error happened in, for
+        errors created while extracting, which only know file paths.
+    */
+    pub(super) fn with_archive_name(mut self: Box<Self>, name: &str) -> Self {
+        if let Self::FileTooLarge { archive_name, .. }
+        | Self::ArchiveTooLarge { archive_name, .. }
+        | Self::SuspiciousCompression { archive_name, .. }
+        | Self::UnsafePath { archive_name, .. } = self.as_mut()
+        {
+            name.clone_into(archive_name);
+        }
+        *self
+    }
+}
+
+const MEGABYTE: u64 = 1024 * 1024;
+
+/**
+    Files smaller than this are never checked for their compression
+    ratio, since small files full of padding are perfectly normal.
+*/
+const MIN_RATIO_CHECK_SIZE: u64 = 16 * MEGABYTE;
+
+/**
+    Limits for the sizes of files extracted from archives, which guard against
+    archives that decompress to far more data than expected, such as zip bombs,
+    that would otherwise exhaust all available memory while being extracted.
+
+    - `max_file_size` - the maximum size of any single file, in bytes.
+    - `max_total_size` - the maximum size of all files combined, in bytes.
+    - `max_compression_ratio` - the maximum ratio between the decompressed and
+      compressed size of any file, which is only checked for files over 16 MB.
+
+    The default limits are above the sizes of any real tool, and may be
+    changed using the `extract-limits` setting of a Rokit manifest.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtractLimits {
+    pub max_file_size: u64,
+    pub max_total_size: u64,
+    pub max_compression_ratio: u64,
+}
+
+impl Default for ExtractLimits {
+    fn default() -> Self {
+        Self {
+            max_file_size: 256 * MEGABYTE,
+            max_total_size: 512 * MEGABYTE,
+            max_compression_ratio: 100,
+        }
+    }
+}
+
+/**
+    Tracks how much data has been extracted from an archive, and reads
+    files from it while making sure that none of the limits are exceeded.
+*/
+#[derive(Debug, Clone, Copy)]
+pub(super) struct ExtractBudget {
+    limits: ExtractLimits,
+    extracted: u64,
+}
+
+impl ExtractBudget {
+    pub(super) fn new(limits: ExtractLimits) -> Self {
+        Self {
+            limits,
+            extracted: 0,
+        }
+    }
+
+    /*
+        Reads all contents of a file in an archive, with its compressed size,
+        if known - reading stops as soon as any limit would be exceeded, so
+        that malicious archives never have to be fully decompressed.
+    */
+    pub(super) fn read_file(
+        &mut self,
+        path: &str,
+        compressed_size: Option<u64>,
+        reader: impl Read,
+    ) -> RokitResult<Vec<u8>> {
+        let remaining = self.limits.max_total_size.saturating_sub(self.extracted);
+        let ratio_limit = compressed_size.map_or(u64::MAX, |size| {
+            size.saturating_mul(self.limits.max_compression_ratio)
+                .max(MIN_RATIO_CHECK_SIZE)
+        });
+        let limit = self.limits.max_file_size.min(remaining).min(ratio_limit);
+
+        let mut bytes = Vec::new();
+        reader
+            .take(limit.saturating_add(1))
+            .read_to_end(&mut bytes)?;
+        let size = bytes.len() as u64;
+        if size > limit {
+            let path = path.to_string();
+            let archive_name = String::new();
+            return Err(if limit == ratio_limit {
+                ExtractError::SuspiciousCompression {
+                    path,
+                    archive_name,
+                    ratio: self.limits.max_compression_ratio,
+                }
+            } else if limit == remaining {
+                ExtractError::ArchiveTooLarge {
+                    archive_name,
+                    limit: self.limits.max_total_size,
+                }
+            } else {
+                ExtractError::FileTooLarge {
+                    path,
+                    archive_name,
+                    limit,
+                }
+            }
+            .into());
+        }
+
+        self.extracted += size;
+        Ok(bytes)
+    }
+
+    /*
+        Decompresses an entire gzip stream, such as the tar archive inside of
+        a `.tar.gz` file - this does not count towards the extracted files,
+        but must be within the total size limit and compression ratio.
+    */
+    pub(super) fn decompress_gzip(&self, contents: &[u8]) -> RokitResult<Vec<u8>> {
+        let limits = ExtractLimits {
+            max_file_size: self.limits.max_total_size,
+            ..self.limits
+        };
+        let compressed_size = contents.len() as u64;
+        Self::new(limits).read_file("", Some(compressed_size), GzDecoder::new(contents))
+    }
+}
+
+/**
+    Options for matching file patterns against paths in archives -
+    `*` only matches within a single directory, while `**` may match
+    any number of directories, which is similar to most other tools.
+*/
+const PATTERN_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
 /**
     A candidate for extraction from an archive.
 
@@ -72,9 +320,16 @@ impl Candidate {
             + u32::from(self.has_exec_suffix)
     }
 
+    /**
+        Finds the best candidate to extract, out of the given entry paths.
+
+        If a file pattern is given, only paths matching it are considered, and any
+        matching path is a valid candidate, even if it would otherwise not be.
+    */
     fn find_best(
         entry_paths: impl AsRef<[(PathBuf, Option<u32>)]>,
         desired_file_path: impl AsRef<Path>,
+        file_pattern: Option<&Pattern>,
     ) -> Option<Self> {
         let entry_paths = entry_paths.as_ref();
         let desired_file_path = desired_file_path.as_ref();
@@ -87,6 +342,9 @@ impl Candidate {
                 if path.ends_with(MAIN_SEPARATOR_STR) {
                     return None;
                 }
+                if file_pattern.is_some_and(|p| !p.matches_path_with(path, PATTERN_OPTIONS)) {
+                    return None;
+                }
 
                 let file_name = path.file_name().and_then(|name| name.to_str());
 
@@ -107,7 +365,7 @@ impl Candidate {
                     has_exec_suffix,
                 })
             })
-            .filter(|c| c.priority() > 0) // Filter out candidates with no matches at all
+            .filter(|c| file_pattern.is_some() || c.priority() > 0) // Filter out candidates with no matches at all
             .collect::<Vec<_>>();
 
         // Sort by their priority, best first
@@ -124,11 +382,14 @@ impl Candidate {
 /**
     Searches for and extracts the best matching file from a zip archive.
 
+    If a file pattern is given, it takes precedence over the name of the desired file.
     May return `None` if no desired file was found in the archive.
 */
 pub async fn extract_zip_file(
     zip_contents: impl AsRef<[u8]>,
     desired_file_name: impl Into<String>,
+    file_pattern: Option<Pattern>,
+    limits: ExtractLimits,
 ) -> RokitResult<Option<Vec<u8>>> {
     let desired_file_name = format!("{}{EXE_SUFFIX}", desired_file_name.into());
     let desired_file_path = PathBuf::from(&desired_file_name);
@@ -141,6 +402,7 @@ pub async fn extract_zip_file(
     // spawn it as a blocking task and use the tokio thread pool.
     spawn_blocking(move || {
         let mut found = None;
+        let mut budget = ExtractBudget::new(limits);
         let mut reader = io::Cursor::new(&zip_contents);
         let mut zip = ZipArchive::new(&mut reader)?;
 
@@ -156,12 +418,12 @@ pub async fn extract_zip_file(
             .collect::<Vec<_>>();
 
         // Find the best candidate to extract, if any
-        let best = Candidate::find_best(entry_paths, &desired_file_path);
+        let best = Candidate::find_best(entry_paths, &desired_file_path, file_pattern.as_ref());
         if let Some(candidate) = best {
             if let Some(path_str) = candidate.path.to_str() {
                 if let Ok(mut entry) = zip.by_name(path_str) {
-                    let mut bytes = Vec::new();
-                    entry.read_to_end(&mut bytes)?;
+                    let compressed_size = entry.compressed_size();
+                    let bytes = budget.read_file(path_str, Some(compressed_size), &mut entry)?;
                     found = Some(bytes);
                 }
             }
@@ -187,11 +449,14 @@ pub async fn extract_zip_file(
 /**
     Searches for and extracts the best matching file from a tar archive.
 
+    If a file pattern is given, it takes precedence over the name of the desired file.
     May return `None` if no desired file was found in the archive.
 */
 pub async fn extract_tar_file(
     tar_contents: impl AsRef<[u8]>,
     desired_file_name: impl Into<String>,
+    file_pattern: Option<Pattern>,
+    limits: ExtractLimits,
 ) -> RokitResult<Option<Vec<u8>>> {
     let desired_file_name = format!("{}{EXE_SUFFIX}", desired_file_name.into());
     let desired_file_path = PathBuf::from(&desired_file_name);
@@ -204,6 +469,7 @@ pub async fn extract_tar_file(
     // spawn it as a blocking task and use the tokio thread pool.
     spawn_blocking(move || {
         let mut found = None;
+        let mut budget = ExtractBudget::new(limits);
 
         /*
             Gather paths and their permissions - note that we
@@ -215,6 +481,7 @@ pub async fn extract_tar_file(
         */
         let mut entry_cursor = io::Cursor::new(&tar_contents);
         let mut entry_reader = TarArchive::new(&mut entry_cursor);
+        let mut links = HashMap::new();
         let entry_paths = entry_reader
             .entries_with_seek()?
             .filter_map(|entry| {
@@ -222,24 +489,38 @@ pub async fn extract_tar_file(
                 if entry.header().entry_type().is_dir() {
                     return None;
                 }
-                let path = entry.path().ok()?;
+                let path = entry.path().ok()?.to_path_buf();
+                // NOTE: Link targets are always normalized, so links must be
+                // keyed by normalized paths too, or archives with paths such
+                // as `./bin/tool` would never have their links resolved
+                if let Some(target) = link_target(&entry, &path) {
+                    if let Some(normalized) = normalize_path(&path) {
+                        links.insert(normalized, target);
+                    }
+                }
                 let perms = entry.header().mode().ok();
-                Some((path.to_path_buf(), perms))
+                Some((path, perms))
             })
             .collect::<Vec<_>>();
 
-        // Find the best candidate to extract, if any
-        let best = Candidate::find_best(entry_paths, &desired_file_path);
+        // Find the best candidate to extract, if any - note that the candidate
+        // may be a symlink or hardlink, and we need the contents of its target
+        let best = Candidate::find_best(entry_paths, &desired_file_path, file_pattern.as_ref());
         if let Some(candidate) = best {
+            let candidate_path =
+                normalize_path(&candidate.path).unwrap_or_else(|| candidate.path.clone());
+            let target_path = resolve_links(&links, &candidate_path);
             let contents_cursor = io::Cursor::new(&tar_contents);
             let mut contents_reader = TarArchive::new(contents_cursor);
             for entry in contents_reader.entries_with_seek()? {
                 let mut entry = entry?;
-                let entry_path = entry.path()?;
-                if entry_path == candidate.path.as_path() {
-                    let mut bytes = Vec::new();
-                    entry.read_to_end(&mut bytes)?;
-                    found = Some(bytes);
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+                let entry_path = normalize_path(&entry.path()?);
+                if entry_path.as_ref() == Some(&target_path) {
+                    let path = target_path.to_string_lossy();
+                    found = Some(budget.read_file(&path, None, &mut entry)?);
                     break;
                 }
             }
@@ -261,3 +542,557 @@ pub async fn extract_tar_file(
     })
     .await?
 }
+
+/**
+    The maximum number of links to follow when resolving a link in an archive,
+    which guards against links that (indirectly) point to themselves.
+*/
+const MAX_LINK_DEPTH: usize = 16;
+
+/**
+    Gets the path that a symlink or hardlink entry in a tar archive points
+    to, relative to the root of the archive, if the entry is a link.
+
+    Symlink targets are relative to the directory containing the link,
+    while hardlink targets are always relative to the root of the archive.
+*/
+fn link_target<R: Read>(entry: &TarEntry<'_, R>, path: &Path) -> Option<PathBuf> {
+    let entry_type = entry.header().entry_type();
+    let target = entry.link_name().ok()??;
+    if entry_type.is_symlink() {
+        normalize_path(&path.parent().unwrap_or(Path::new("")).join(target))
+    } else if entry_type.is_hard_link() {
+        normalize_path(&target)
+    } else {
+        None
+    }
+}
+
+/**
+    Follows links, starting at the given path, until a path
+    that is not a link is found, and returns that path.
+*/
+fn resolve_links(links: &HashMap<PathBuf, PathBuf>, path: &Path) -> PathBuf {
+    let mut resolved = path.to_path_buf();
+    for _ in 0..MAX_LINK_DEPTH {
+        match links.get(&resolved) {
+            Some(target) => resolved.clone_from(target),
+            None => break,
+        }
+    }
+    resolved
+}
+
+/**
+    Normalizes the given relative path, by resolving any `.` and `..`
+    components, returning `None` if the path would escape its root.
+*/
+fn normalize_path(path: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => normalized.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    return None;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(normalized)
+}
+
+/**
+    A file extracted from an archive, other than the tool binary itself.
+
+    The path is relative to the directory that contained the tool binary.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractedFile {
+    pub path: PathBuf,
+    pub contents: Vec<u8>,
+    pub executable: bool,
+}
+
+/**
+    Searches for and extracts the best matching file from a zip or tar archive,
+    together with all other files in the same directory as it, or subdirectories.
+
+    Files outside of the directory containing the best matching file are skipped,
+    since only the tool binary and any files next to it can be installed, while
+    archives containing any file or link with a path that could escape the
+    archive are rejected entirely, with an [`ExtractError::UnsafePath`] error.
+
+    If a file pattern is given, it takes precedence over the name of the desired file.
+    May return `None` if no desired file was found in the archive.
+*/
+pub async fn extract_all_files(
+    contents: impl AsRef<[u8]>,
+    format: ArtifactFormat,
+    desired_file_name: impl Into<String>,
+    file_pattern: Option<Pattern>,
+    limits: ExtractLimits,
+) -> RokitResult<Option<(Vec<u8>, Vec<ExtractedFile>)>> {
+    let desired_file_name = format!("{}{EXE_SUFFIX}", desired_file_name.into());
+    let desired_file_path = PathBuf::from(&desired_file_name);
+
+    let contents = contents.as_ref().to_vec();
+    let num_kilobytes = contents.len() / 1024;
+    let start = Instant::now();
+
+    // Reading all files in an archive is a potentially expensive operation,
+    // so spawn it as a blocking task and use the tokio thread pool.
+    spawn_blocking(move || {
+        let entries = read_all_entries(&contents, format, limits)?;
+        let entry_paths = entries
+            .iter()
+            .map(|(path, perms, _)| (path.clone(), *perms))
+            .collect::<Vec<_>>();
+
+        let Some(best) =
+            Candidate::find_best(entry_paths, &desired_file_path, file_pattern.as_ref())
+        else {
+            return Ok(None);
+        };
+        let base_dir = best.path.parent().unwrap_or(Path::new("")).to_path_buf();
+
+        let mut binary = None;
+        let mut files = Vec::new();
+        for (path, perms, bytes) in entries {
+            if path == best.path {
+                binary = Some(bytes);
+                continue;
+            }
+            match path.strip_prefix(&base_dir) {
+                Ok(relative) if is_safe_relative_path(relative) => files.push(ExtractedFile {
+                    path: relative.to_path_buf(),
+                    contents: bytes,
+                    executable: perms.is_some_and(|perms| (perms & 0o111) != 0),
+                }),
+                _ => tracing::trace!(?path, "skipping file outside of binary directory"),
+            }
+        }
+
+        tracing::debug!(
+            num_kilobytes,
+            num_files = files.len(),
+            elapsed = ?start.elapsed(),
+            found = binary.is_some(),
+            "extracted all files"
+        );
+        Ok(binary.map(|binary| (binary, files)))
+    })
+    .await?
+}
+
+/**
+    The path, permissions, and contents, of a file in an archive.
+*/
+type ArchiveEntry = (PathBuf, Option<u32>, Vec<u8>);
+
+/**
+    Reads the paths, permissions, and contents, of all files in a zip or tar archive.
+
+    Returns no entries for formats that are not archives.
+*/
+fn read_all_entries(
+    contents: &[u8],
+    format: ArtifactFormat,
+    limits: ExtractLimits,
+) -> RokitResult<Vec<ArchiveEntry>> {
+    let mut entries = Vec::new();
+    let mut budget = ExtractBudget::new(limits);
+    match format {
+        ArtifactFormat::Zip => {
+            let mut zip = ZipArchive::new(io::Cursor::new(contents))?;
+            for index in 0..zip.len() {
+                let mut entry = zip.by_index(index)?;
+                if entry.is_dir() {
+                    continue;
+                }
+                // NOTE: Unlike when only matching paths, we need sanitized
+                // paths here, since the files are written, and archives
+                // with any path that could escape are rejected entirely
+                let path = entry
+                    .enclosed_name()
+                    .and_then(|path| normalize_path(&path))
+                    .filter(|path| is_safe_relative_path(path))
+                    .ok_or_else(|| ExtractError::unsafe_path(Path::new(entry.name())))?;
+                let perms = entry.unix_mode();
+                let name = entry.name().to_string();
+                let compressed_size = entry.compressed_size();
+                let bytes = budget.read_file(&name, Some(compressed_size), &mut entry)?;
+                entries.push((path, perms, bytes));
+            }
+        }
+        ArtifactFormat::Tar | ArtifactFormat::TarGz => {
+            let tar = if format == ArtifactFormat::TarGz {
+                budget.decompress_gzip(contents)?
+            } else {
+                contents.to_vec()
+            };
+            let mut links = Vec::new();
+            let mut reader = TarArchive::new(io::Cursor::new(&tar));
+            for entry in reader.entries()? {
+                let mut entry = entry?;
+                let entry_type = entry.header().entry_type();
+                let is_link = entry_type.is_symlink() || entry_type.is_hard_link();
+                if !entry_type.is_file() && !is_link {
+                    continue;
+                }
+                // Archives with any file or link that could escape, either by
+                // its own path or by pointing outside the archive, are rejected
+                let raw_path = entry.path()?.to_path_buf();
+                let path = normalize_path(&raw_path)
+                    .filter(|path| is_safe_relative_path(path))
+                    .ok_or_else(|| ExtractError::unsafe_path(&raw_path))?;
+                let perms = entry.header().mode().ok();
+                if is_link {
+                    let target = link_target(&entry, &path)
+                        .filter(|path| is_safe_relative_path(path))
+                        .ok_or_else(|| ExtractError::unsafe_path(&raw_path))?;
+                    links.push((path, perms, target));
+                    continue;
+                }
+                let bytes = budget.read_file(&path.to_string_lossy(), None, &mut entry)?;
+                entries.push((path, perms, bytes));
+            }
+
+            // Links are installed as copies of the files they point to,
+            // and count towards the limits, since links can be repeated
+            let link_targets = links
+                .iter()
+                .map(|(path, _, target)| (path.clone(), target.clone()))
+                .collect::<HashMap<_, _>>();
+            for (path, perms, _) in links {
+                let target = resolve_links(&link_targets, &path);
+                let contents = entries
+                    .iter()
+                    .find(|(entry_path, _, _)| entry_path == &target)
+                    .map(|(_, target_perms, bytes)| (target_perms.or(perms), bytes.as_slice()));
+                if let Some((perms, bytes)) = contents {
+                    let bytes = budget.read_file(&path.to_string_lossy(), None, bytes)?;
+                    entries.push((path, perms, bytes));
+                }
+            }
+        }
+        ArtifactFormat::Gz => {}
+    }
+    Ok(entries)
+}
+
+/**
+    Searches for and reads a nested archive from a zip or tar archive.
+
+    This should be used as a fallback, if the archive does not directly contain the
+    desired file, since some tools ship an archive that contains another archive.
+    If there are several nested archives, one compatible with the current system
+    is preferred, and only archives in a format supported by Rokit are considered.
+
+    Returns the format, path, and contents of the nested archive, if one was found.
+*/
+pub async fn extract_nested_archive(
+    contents: impl AsRef<[u8]>,
+    format: ArtifactFormat,
+    limits: ExtractLimits,
+) -> RokitResult<Option<(ArtifactFormat, String, Vec<u8>)>> {
+    let contents = contents.as_ref().to_vec();
+
+    spawn_blocking(move || {
+        let mut budget = ExtractBudget::new(limits);
+        let current = Descriptor::current_system();
+        let is_compatible = |path: &str| {
+            Descriptor::detect(path).is_some_and(|desc| current.is_compatible_with(&desc))
+        };
+
+        let mut found: Option<(ArtifactFormat, String, Vec<u8>)> = None;
+        let mut consider =
+            |path: &str, compressed_size: Option<u64>, read: &mut dyn Read| -> RokitResult<bool> {
+                let Some(nested_format) = ArtifactFormat::from_path_or_url(path) else {
+                    return Ok(false);
+                };
+                if found.is_some() && !is_compatible(path) {
+                    return Ok(false);
+                }
+                let bytes = budget.read_file(path, compressed_size, read)?;
+                found = Some((nested_format, path.to_string(), bytes));
+                // Stop searching once we found a compatible archive
+                Ok(is_compatible(path))
+            };
+
+        match format {
+            ArtifactFormat::Zip => {
+                let mut zip = ZipArchive::new(io::Cursor::new(&contents))?;
+                for index in 0..zip.len() {
+                    let mut entry = zip.by_index(index)?;
+                    if entry.is_dir() {
+                        continue;
+                    }
+                    let path = entry.name().to_string();
+                    let compressed_size = entry.compressed_size();
+                    if consider(&path, Some(compressed_size), &mut entry)? {
+                        break;
+                    }
+                }
+            }
+            ArtifactFormat::Tar | ArtifactFormat::TarGz => {
+                let tar = if format == ArtifactFormat::TarGz {
+                    ExtractBudget::new(limits).decompress_gzip(&contents)?
+                } else {
+                    contents
+                };
+                let mut reader = TarArchive::new(io::Cursor::new(&tar));
+                for entry in reader.entries_with_seek()? {
+                    let mut entry = entry?;
+                    if entry.header().entry_type().is_dir() {
+                        continue;
+                    }
+                    let path = entry.path()?.to_string_lossy().to_string();
+                    if consider(&path, None, &mut entry)? {
+                        break;
+                    }
+                }
+            }
+            ArtifactFormat::Gz => {}
+        }
+
+        if let Some((nested_format, path, _)) = &found {
+            tracing::debug!(%path, format = %nested_format, "found nested archive");
+        }
+        Ok(found)
+    })
+    .await?
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        result::RokitError,
+        testing::{binary_contents, tar_gz_archive, zip_archive, zip_archive_with_paths},
+    };
+
+    use super::*;
+
+    fn extract_error(result: RokitResult<impl std::fmt::Debug>) -> ExtractError {
+        match result {
+            Err(RokitError::Extract(e)) => *e,
+            other => panic!("expected an extraction error, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn reject_highly_compressed_files() {
+        let zeros = vec![0; 32 * 1024 * 1024];
+        let limits = ExtractLimits::default();
+
+        let zip = zip_archive("rojo", &zeros);
+        let err = extract_error(extract_zip_file(zip, "rojo", None, limits).await);
+        assert!(
+            matches!(&err, ExtractError::SuspiciousCompression { path, .. } if path.starts_with("rojo")),
+            "{err:?}"
+        );
+
+        let tar_gz = tar_gz_archive("rojo", &zeros);
+        let err = extract_error(decompress_tar_gz(&tar_gz, limits));
+        assert!(
+            matches!(&err, ExtractError::SuspiciousCompression { path, .. } if path.is_empty()),
+            "{err:?}"
+        );
+        assert!(err.to_string().starts_with("contents in archive"));
+    }
+
+    #[tokio::test]
+    async fn reject_files_over_size_limits() {
+        let contents = (0..600u16).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let zip = zip_archive_with_paths(&[("bin/rojo", &contents), ("bin/data", &contents)]);
+        let limits = |max_file_size, max_total_size| ExtractLimits {
+            max_file_size,
+            max_total_size,
+            ..ExtractLimits::default()
+        };
+
+        let extracted =
+            extract_all_files(&zip, ArtifactFormat::Zip, "rojo", None, limits(600, 1200))
+                .await
+                .unwrap();
+        assert!(extracted.is_some());
+
+        let err = extract_error(
+            extract_all_files(&zip, ArtifactFormat::Zip, "rojo", None, limits(500, 1200)).await,
+        );
+        assert!(
+            matches!(err, ExtractError::FileTooLarge { limit: 500, .. }),
+            "{err:?}"
+        );
+
+        let err = extract_error(
+            extract_all_files(&zip, ArtifactFormat::Zip, "rojo", None, limits(600, 1000)).await,
+        );
+        assert!(
+            matches!(err, ExtractError::ArchiveTooLarge { limit: 1000, .. }),
+            "{err:?}"
+        );
+        assert!(err.is_limit_exceeded());
+    }
+
+    fn decompress_tar_gz(contents: &[u8], limits: ExtractLimits) -> RokitResult<Vec<u8>> {
+        ExtractBudget::new(limits).decompress_gzip(contents)
+    }
+
+    /*
+        Creates a tar archive with the given paths, entry types, link targets, and
+        contents - paths and link targets are written to headers directly, since
+        the tar crate refuses to write the malicious paths that we want to test.
+    */
+    fn raw_tar_archive(entries: &[(&str, tar::EntryType, &str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, entry_type, link_name, contents) in entries {
+            let mut header = tar::Header::new_old();
+            let old = header.as_old_mut();
+            old.name[..path.len()].copy_from_slice(path.as_bytes());
+            old.linkname[..link_name.len()].copy_from_slice(link_name.as_bytes());
+            header.set_entry_type(*entry_type);
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o755);
+            header.set_cksum();
+            builder.append(&header, *contents).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    #[tokio::test]
+    async fn extract_from_nested_archive() {
+        let limits = ExtractLimits::default();
+        let binary = binary_contents("nested");
+        let compatible = format!(
+            "rojo-{}-{}.zip",
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        );
+
+        // Some tools wrap their archives in another archive, and when
+        // there are several, the one for the current system is preferred
+        let contents = zip_archive_with_paths(&[
+            ("rojo-source.tar.gz", b"source"),
+            (&compatible, &zip_archive("rojo", &binary)),
+        ]);
+        let (format, path, nested) = extract_nested_archive(&contents, ArtifactFormat::Zip, limits)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(format, ArtifactFormat::Zip);
+        assert_eq!(path, compatible);
+
+        let extracted = extract_zip_file(nested, "rojo", None, limits)
+            .await
+            .unwrap();
+        assert_eq!(extracted, Some(binary));
+
+        let contents = zip_archive("rojo.txt", b"not an archive");
+        let nested = extract_nested_archive(&contents, ArtifactFormat::Zip, limits).await;
+        assert_eq!(nested.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn extract_linked_binary_from_tar() {
+        use tar::EntryType::{Link, Regular, Symlink};
+
+        let limits = ExtractLimits::default();
+        let binary = binary_contents("binary");
+
+        // Archives created using `tar -C dir .` prefix all paths with `./`,
+        // including the targets of hardlinks, but not those of symlinks
+        for prefix in ["", "./"] {
+            let real_path = format!("{prefix}dist/libexec/real-binary");
+            let link_path = format!("{prefix}dist/rojo{EXE_SUFFIX}");
+            // Symlinks are relative to their own directory, hardlinks to the root
+            for (link_type, target) in
+                [(Symlink, "libexec/real-binary"), (Link, real_path.as_str())]
+            {
+                let contents = raw_tar_archive(&[
+                    (&real_path, Regular, "", &binary),
+                    (&link_path, link_type, target, b""),
+                ]);
+                let context = format!("{link_type:?} - {link_path}");
+
+                let extracted = extract_tar_file(&contents, "rojo", None, limits)
+                    .await
+                    .unwrap();
+                assert_eq!(extracted.as_ref(), Some(&binary), "{context}");
+
+                let (extracted, files) =
+                    extract_all_files(&contents, ArtifactFormat::Tar, "rojo", None, limits)
+                        .await
+                        .unwrap()
+                        .unwrap();
+                assert_eq!(extracted, binary, "{context}");
+                assert_eq!(files.len(), 1, "{context}");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn reject_archives_with_unsafe_paths() {
+        use tar::EntryType::{Link, Regular, Symlink};
+
+        let limits = ExtractLimits::default();
+        let binary = binary_contents("binary");
+        let binary_path = format!("dist/rojo{EXE_SUFFIX}");
+        let tool = (binary_path.as_str(), Regular, "", binary.as_slice());
+        let malicious: [(&str, tar::EntryType, &str, &[u8]); 5] = [
+            ("dist/../../escaped", Regular, "", b"escaped"),
+            ("/tmp/escaped", Regular, "", b"escaped"),
+            ("dist/escaped", Symlink, "../../etc/passwd", b""),
+            ("dist/escaped", Symlink, "/etc/passwd", b""),
+            ("dist/escaped", Link, "../etc/passwd", b""),
+        ];
+        for entry in malicious {
+            let contents = raw_tar_archive(&[tool, entry]);
+            let err = extract_error(
+                extract_all_files(contents, ArtifactFormat::Tar, "rojo", None, limits).await,
+            );
+            assert!(err.is_unsafe_path(), "{entry:?} - {err}");
+            assert!(err.to_string().contains(entry.0), "{err}");
+        }
+
+        let contents =
+            zip_archive_with_paths(&[(&binary_path, &binary), ("dist/../../escaped", b"escaped")]);
+        let err = extract_error(
+            extract_all_files(contents, ArtifactFormat::Zip, "rojo", None, limits).await,
+        );
+        assert!(err.is_unsafe_path(), "{err}");
+
+        // Paths that stay within the archive are normalized, and links
+        // within the archive are installed as copies of their targets
+        let contents = raw_tar_archive(&[
+            tool,
+            ("./dist/lib/../data.txt", Regular, "", b"data"),
+            ("dist/linked.txt", Symlink, "data.txt", b""),
+        ]);
+        let (extracted, files) =
+            extract_all_files(contents, ArtifactFormat::Tar, "rojo", None, limits)
+                .await
+                .unwrap()
+                .unwrap();
+        assert_eq!(extracted, binary);
+        let mut files = files
+            .iter()
+            .map(|file| {
+                (
+                    file.path.to_string_lossy().to_string(),
+                    file.contents.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        files.sort();
+        assert_eq!(
+            files,
+            [
+                (String::from("data.txt"), b"data".to_vec()),
+                (String::from("linked.txt"), b"data".to_vec()),
+            ]
+        );
+    }
+}
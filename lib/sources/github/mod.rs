@@ -1,23 +1,41 @@
-use reqwest_middleware::ClientWithMiddleware;
-use semver::Version;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use reqwest_middleware::{ClientWithMiddleware, RequestBuilder};
+use semver::{Version, VersionReq};
 use serde::de::DeserializeOwned;
-use tracing::{debug, instrument};
+use tracing::{debug, instrument, trace};
+use url::Url;
 
 use reqwest::{
-    header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, AUTHORIZATION},
-    StatusCode,
+    header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, AUTHORIZATION, RETRY_AFTER},
+    Response, StatusCode,
 };
 
 use crate::tool::{ToolId, ToolSpec};
 
-use super::{client::create_client, Artifact, ArtifactProvider, Release};
+use super::{
+    advisory::{parse_patched_versions, parse_version_range},
+    client::create_client,
+    Advisory, Artifact, ArtifactProvider, Provenance, ProvenanceAsset, Release, SearchResult,
+};
 
 const BASE_URL: &str = "https://api.github.com";
+const RELEASES_PER_PAGE: usize = 30;
+const STABLE_CHANNEL: &str = "stable";
+const SEARCH_RESULTS_MAX: usize = 100;
+const DEFAULT_BRANCH: &str = "main";
 
 pub mod models;
 mod result;
 
-use self::models::GithubRelease;
+use self::models::{
+    GithubAdvisory, GithubCommit, GithubRelease, GithubRepository, GithubSearchResults,
+    GithubWorkflowArtifacts, GithubWorkflowRun, GithubWorkflowRuns,
+};
 
 pub use self::result::{GithubError, GithubResult};
 
@@ -25,10 +43,12 @@ pub use self::result::{GithubError, GithubResult};
 pub struct GithubProvider {
     client: ClientWithMiddleware,
     has_auth: bool,
+    base_url: String,
+    owner_auth: Arc<HashMap<String, HeaderValue>>,
 }
 
 impl GithubProvider {
-    fn new_inner(pat: Option<String>) -> GithubResult<Self> {
+    fn new_inner(pat: Option<String>, base_url: &str) -> GithubResult<Self> {
         let has_auth = pat.is_some();
         let headers = {
             let mut headers = HeaderMap::new();
@@ -43,30 +63,50 @@ impl GithubProvider {
             headers
         };
 
-        let client = create_client(headers)?;
+        // NOTE: Only the mock server used in tests is served over plain HTTP
+        let https_only = base_url.starts_with("https://");
+        let client = create_client(headers, https_only)?;
+
+        Ok(Self {
+            client,
+            has_auth,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            owner_auth: Arc::new(HashMap::new()),
+        })
+    }
+
+    /**
+        Creates a request to the given url, for a repository with the given owner.
 
-        Ok(Self { client, has_auth })
+        If a token was given for the owner using [`GithubProvider::with_owner_tokens`],
+        it will be used instead of the default token for this provider.
+    */
+    fn get(&self, url: &str, owner: &str) -> RequestBuilder {
+        let request = self.client.get(url);
+        match self.owner_auth.get(&owner.to_lowercase()) {
+            Some(auth) => request.header(AUTHORIZATION, auth.clone()),
+            None => request,
+        }
     }
 
-    async fn get_json<T: DeserializeOwned>(&self, url: &str) -> GithubResult<T> {
+    async fn get_json<T: DeserializeOwned>(&self, url: &str, owner: &str) -> GithubResult<T> {
         let response = self
-            .client
-            .get(url)
+            .get(url, owner)
             .header(ACCEPT, "application/vnd.github.v3+json")
             .send()
-            .await?
-            .error_for_status()?;
+            .await?;
+        let response = check_response_status(response)?;
         Ok(response.json().await?)
     }
 
-    async fn get_bytes(&self, url: &str) -> GithubResult<Vec<u8>> {
-        let response = self
-            .client
-            .get(url)
+    fn get_bytes_request(&self, url: &str, owner: &str) -> RequestBuilder {
+        self.get(url, owner)
             .header(ACCEPT, HeaderValue::from_static("application/octet-stream"))
-            .send()
-            .await?
-            .error_for_status()?;
+    }
+
+    async fn get_bytes(&self, url: &str, owner: &str) -> GithubResult<Vec<u8>> {
+        let response = self.get_bytes_request(url, owner).send().await?;
+        let response = check_response_status(response)?;
         let bytes = response.bytes().await.map(|bytes| bytes.to_vec());
         Ok(bytes?)
     }
@@ -79,7 +119,16 @@ impl GithubProvider {
         - If the GitHub API client could not be created.
     */
     pub fn new() -> GithubResult<Self> {
-        Self::new_inner(None)
+        Self::new_inner(None, BASE_URL)
+    }
+
+    /**
+        Creates a new GitHub source instance using the given API base url,
+        which is typically the url of a mock server for use in tests.
+    */
+    #[cfg(test)]
+    pub(crate) fn new_with_base_url(base_url: &str) -> GithubResult<Self> {
+        Self::new_inner(None, base_url)
     }
 
     /**
@@ -94,7 +143,28 @@ impl GithubProvider {
     */
     pub fn new_authenticated(pat: impl AsRef<str>) -> GithubResult<Self> {
         let pat: String = pat.as_ref().trim().to_string();
-        Self::new_inner(Some(pat))
+        Self::new_inner(Some(pat), BASE_URL)
+    }
+
+    /**
+        Adds tokens to use for repositories by specific owners, such as
+        users or organizations, instead of the default token, if any.
+
+        Owners are matched case-insensitively.
+
+        # Errors
+
+        - If any of the tokens is not a valid header value.
+    */
+    pub fn with_owner_tokens(mut self, tokens: &HashMap<String, String>) -> GithubResult<Self> {
+        let mut owner_auth = HashMap::new();
+        for (owner, token) in tokens {
+            let token = format!("Bearer {}", token.trim());
+            owner_auth.insert(owner.to_lowercase(), HeaderValue::from_str(&token)?);
+        }
+        self.has_auth |= !owner_auth.is_empty();
+        self.owner_auth = Arc::new(owner_auth);
+        Ok(self)
     }
 
     /**
@@ -113,8 +183,8 @@ impl GithubProvider {
             return Ok(false);
         }
 
-        let url = format!("{BASE_URL}/rate_limit");
-        let res = self.get_json::<serde_json::Value>(&url).await;
+        let url = format!("{}/rate_limit", self.base_url);
+        let res = self.get_json::<serde_json::Value>(&url, "").await;
 
         match res {
             Ok(_) => Ok(true),
@@ -125,18 +195,34 @@ impl GithubProvider {
 
     /**
         Fetches the latest release for a given tool.
+
+        # Errors
+
+        - If the tool has no published releases.
+        - If the latest release has no assets yet, such as while they are being uploaded.
+        - If the request to the GitHub API failed.
     */
     #[instrument(skip(self), fields(%tool_id), level = "debug")]
     pub async fn get_latest_release(&self, tool_id: &ToolId) -> GithubResult<Release> {
+        let (release, tool_spec) = self.get_latest_github_release(tool_id).await?;
+        check_release_state(&release, &tool_spec)?;
+        Ok(release_from_github(&release, &tool_spec))
+    }
+
+    async fn get_latest_github_release(
+        &self,
+        tool_id: &ToolId,
+    ) -> GithubResult<(GithubRelease, ToolSpec)> {
         debug!(id = %tool_id, "fetching latest release for tool");
 
         let url = format!(
-            "{BASE_URL}/repos/{owner}/{repo}/releases/latest",
+            "{base}/repos/{owner}/{repo}/releases/latest",
+            base = self.base_url,
             owner = tool_id.author(),
             repo = tool_id.name(),
         );
 
-        let release: GithubRelease = match self.get_json(&url).await {
+        let release: GithubRelease = match self.get_json(&url, tool_id.author()).await {
             Err(e) if is_404(&e) => {
                 return Err(GithubError::LatestReleaseNotFound(tool_id.clone().into()));
             }
@@ -151,34 +237,182 @@ impl GithubProvider {
             .map_err(|e| GithubError::Other(e.to_string()))?;
 
         let tool_spec: ToolSpec = (tool_id.clone(), version).into();
-        Ok(Release {
-            changelog: release.changelog.clone(),
-            artifacts: artifacts_from_release(&release, &tool_spec),
-        })
+        Ok((release, tool_spec))
     }
 
     /**
-        Fetches a specific release for a given tool.
+        Fetches the latest release for a given tool, including prereleases.
+
+        Releases are compared using their versions, and not when they were
+        published, so that a patch for an older version is never preferred.
+        Only the most recent releases are considered.
+
+        # Errors
+
+        - If the tool has no releases with valid versions.
+        - If the request to the GitHub API failed.
     */
-    #[instrument(skip(self), fields(%tool_spec), level = "debug")]
-    pub async fn get_specific_release(&self, tool_spec: &ToolSpec) -> GithubResult<Release> {
+    #[instrument(skip(self), fields(%tool_id), level = "debug")]
+    pub async fn get_latest_prerelease(&self, tool_id: &ToolId) -> GithubResult<Release> {
+        debug!(id = %tool_id, "fetching latest prerelease for tool");
+
+        let latest = self
+            .get_recent_releases(tool_id)
+            .await?
+            .into_iter()
+            .max_by(|(a, _), (b, _)| a.cmp(b));
+        let Some((version, release)) = latest else {
+            return Err(GithubError::LatestReleaseNotFound(tool_id.clone().into()));
+        };
+
+        let tool_spec: ToolSpec = (tool_id.clone(), version).into();
+        Ok(release_from_github(&release, &tool_spec))
+    }
+
+    /**
+        Fetches the latest release for a given tool with a
+        version that matches the given version requirement.
+
+        Prereleases only match if the requirement itself contains a prerelease
+        version, following the usual semver rules. Only the most recent releases
+        are considered.
+
+        # Errors
+
+        - If the tool has no recent releases matching the requirement.
+        - If the request to the GitHub API failed.
+    */
+    #[instrument(skip(self), fields(%tool_id, %version_req), level = "debug")]
+    pub async fn get_latest_release_matching(
+        &self,
+        tool_id: &ToolId,
+        version_req: &VersionReq,
+    ) -> GithubResult<Release> {
+        debug!(id = %tool_id, req = %version_req, "fetching latest matching release for tool");
+
+        let latest = self
+            .get_recent_releases(tool_id)
+            .await?
+            .into_iter()
+            .filter(|(version, _)| version_req.matches(version))
+            .max_by(|(a, _), (b, _)| a.cmp(b));
+        let Some((version, release)) = latest else {
+            return Err(GithubError::MatchingReleaseNotFound(
+                tool_id.clone().into(),
+                version_req.clone(),
+            ));
+        };
+
+        let tool_spec: ToolSpec = (tool_id.clone(), version).into();
+        Ok(release_from_github(&release, &tool_spec))
+    }
+
+    /**
+        Fetches the latest release for a given tool in the given release channel.
+
+        The `stable` channel only contains releases that are not prereleases,
+        while any other channel, such as `nightly`, contains the releases that
+        have the name of the channel in their tag, such as `v1.2.0-nightly.3`.
+        Only the most recent releases are considered.
+
+        # Errors
+
+        - If the tool has no recent releases in the channel.
+        - If the request to the GitHub API failed.
+    */
+    #[instrument(skip(self), fields(%tool_id, %channel), level = "debug")]
+    pub async fn get_latest_release_in_channel(
+        &self,
+        tool_id: &ToolId,
+        channel: &str,
+    ) -> GithubResult<Release> {
+        debug!(id = %tool_id, %channel, "fetching latest release in channel for tool");
+
+        let channel_lowercase = channel.to_ascii_lowercase();
+        let latest = self
+            .get_recent_releases(tool_id)
+            .await?
+            .into_iter()
+            .filter(|(version, release)| {
+                if channel_lowercase == STABLE_CHANNEL {
+                    !release.prerelease && version.pre.is_empty()
+                } else {
+                    release
+                        .tag_name
+                        .to_ascii_lowercase()
+                        .contains(&channel_lowercase)
+                }
+            })
+            .max_by(|(a, _), (b, _)| a.cmp(b));
+        let Some((version, release)) = latest else {
+            return Err(GithubError::ChannelReleaseNotFound(
+                tool_id.clone().into(),
+                channel.to_string(),
+            ));
+        };
+
+        let tool_spec: ToolSpec = (tool_id.clone(), version).into();
+        Ok(release_from_github(&release, &tool_spec))
+    }
+
+    /*
+        Fetches the most recent releases for a given tool, skipping
+        any releases without a valid version, and any draft releases,
+        which are only listed for users with access to the repository.
+    */
+    async fn get_recent_releases(
+        &self,
+        tool_id: &ToolId,
+    ) -> GithubResult<Vec<(Version, GithubRelease)>> {
+        let url = format!(
+            "{base}/repos/{owner}/{repo}/releases?per_page={RELEASES_PER_PAGE}",
+            base = self.base_url,
+            owner = tool_id.author(),
+            repo = tool_id.name(),
+        );
+
+        let releases: Vec<GithubRelease> = match self.get_json(&url, tool_id.author()).await {
+            Err(e) if is_404(&e) => {
+                return Err(GithubError::LatestReleaseNotFound(tool_id.clone().into()));
+            }
+            Err(e) => return Err(e),
+            Ok(r) => r,
+        };
+
+        Ok(releases
+            .into_iter()
+            .filter(|release| !release.draft)
+            .filter_map(|release| {
+                let version = release.tag_name.trim_start_matches('v').parse().ok()?;
+                Some((version, release))
+            })
+            .collect())
+    }
+
+    async fn get_specific_github_release(
+        &self,
+        tool_spec: &ToolSpec,
+    ) -> GithubResult<GithubRelease> {
         debug!(spec = %tool_spec, "fetching release for tool");
 
         let url_with_prefix = format!(
-            "{BASE_URL}/repos/{owner}/{repo}/releases/tags/v{tag}",
+            "{base}/repos/{owner}/{repo}/releases/tags/v{tag}",
+            base = self.base_url,
             owner = tool_spec.author(),
             repo = tool_spec.name(),
             tag = tool_spec.version(),
         );
         let url_without_prefix = format!(
-            "{BASE_URL}/repos/{owner}/{repo}/releases/tags/{tag}",
+            "{base}/repos/{owner}/{repo}/releases/tags/{tag}",
+            base = self.base_url,
             owner = tool_spec.author(),
             repo = tool_spec.name(),
             tag = tool_spec.version(),
         );
 
-        let release: GithubRelease = match self.get_json(&url_with_prefix).await {
-            Err(e) if is_404(&e) => match self.get_json(&url_without_prefix).await {
+        let owner = tool_spec.author();
+        let release: GithubRelease = match self.get_json(&url_with_prefix, owner).await {
+            Err(e) if is_404(&e) => match self.get_json(&url_without_prefix, owner).await {
                 Err(e) if is_404(&e) => {
                     return Err(GithubError::ReleaseNotFound(tool_spec.clone().into()));
                 }
@@ -189,9 +423,421 @@ impl GithubProvider {
             Ok(r) => r,
         };
 
+        Ok(release)
+    }
+
+    /**
+        Fetches a specific release for a given tool.
+
+        # Errors
+
+        - If the release was not found.
+        - If the release is a draft, or has no assets yet, such as while they are being uploaded.
+        - If the request to the GitHub API failed.
+    */
+    #[instrument(skip(self), fields(%tool_spec), level = "debug")]
+    pub async fn get_specific_release(&self, tool_spec: &ToolSpec) -> GithubResult<Release> {
+        let release = self.get_specific_github_release(tool_spec).await?;
+        check_release_state(&release, tool_spec)?;
+        Ok(release_from_github(&release, tool_spec))
+    }
+
+    /**
+        Fetches the changelog for a given tool, using the release notes of
+        either a specific version, or the latest release if no version is given.
+
+        Returns `None` if the release has no release notes.
+
+        # Errors
+
+        - If the release was not found.
+        - If the request to the GitHub API failed.
+    */
+    #[instrument(skip(self), fields(%tool_id), level = "debug")]
+    pub async fn get_release_changelog(
+        &self,
+        tool_id: &ToolId,
+        version: Option<&Version>,
+    ) -> GithubResult<Option<String>> {
+        // NOTE: Releases without assets may still have release notes
+        let release = match version {
+            Some(version) => {
+                let tool_spec: ToolSpec = (tool_id.clone(), version.clone()).into();
+                self.get_specific_github_release(&tool_spec).await?
+            }
+            None => self.get_latest_github_release(tool_id).await?.0,
+        };
+        Ok(release
+            .changelog
+            .filter(|changelog| !changelog.trim().is_empty()))
+    }
+
+    /**
+        Fetches all published security advisories for the repository of a given tool.
+
+        Returns an empty list if the repository has no advisories, or
+        if advisories are not available for the repository at all.
+
+        # Errors
+
+        - If the request to the GitHub API failed.
+    */
+    #[instrument(skip(self), fields(%tool_id), level = "debug")]
+    pub async fn get_security_advisories(&self, tool_id: &ToolId) -> GithubResult<Vec<Advisory>> {
+        debug!(id = %tool_id, "fetching security advisories for tool");
+
+        let url = format!(
+            "{base}/repos/{owner}/{repo}/security-advisories?state=published&per_page=100",
+            base = self.base_url,
+            owner = tool_id.author(),
+            repo = tool_id.name(),
+        );
+
+        let advisories: Vec<GithubAdvisory> = match self.get_json(&url, tool_id.author()).await {
+            Err(e) if is_404(&e) => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+            Ok(a) => a,
+        };
+
+        Ok(advisories
+            .into_iter()
+            .map(|advisory| {
+                let vulnerable_ranges = advisory
+                    .vulnerabilities
+                    .iter()
+                    .filter_map(|v| v.vulnerable_version_range.as_deref())
+                    .filter_map(parse_version_range)
+                    .collect();
+                let patched_versions = advisory
+                    .vulnerabilities
+                    .iter()
+                    .filter_map(|v| v.patched_versions.as_deref())
+                    .flat_map(parse_patched_versions)
+                    .collect();
+                Advisory {
+                    id: advisory.ghsa_id,
+                    summary: advisory.summary,
+                    severity: advisory.severity,
+                    url: advisory.html_url,
+                    vulnerable_ranges,
+                    patched_versions,
+                }
+            })
+            .collect())
+    }
+
+    /**
+        Fetches details about the repository of a given tool, without any release details.
+
+        # Errors
+
+        - If the repository was not found.
+        - If the request to the GitHub API failed.
+    */
+    #[instrument(skip(self), fields(%tool_id), level = "debug")]
+    pub async fn get_repository_provenance(&self, tool_id: &ToolId) -> GithubResult<Provenance> {
+        debug!(id = %tool_id, "fetching repository details for tool");
+
+        let url = format!(
+            "{base}/repos/{owner}/{repo}",
+            base = self.base_url,
+            owner = tool_id.author(),
+            repo = tool_id.name(),
+        );
+
+        let repository: GithubRepository = self.get_json(&url, tool_id.author()).await?;
+        Ok(Provenance {
+            repository_url: Some(repository.html_url),
+            description: repository
+                .description
+                .filter(|description| !description.trim().is_empty()),
+            stars: repository.stargazers_count,
+            ..Provenance::default()
+        })
+    }
+
+    /**
+        Fetches details about the repository of a given tool, along with details about
+        either a specific release, or the latest release if no version is given.
+
+        Release details are left empty if the release was not found.
+
+        # Errors
+
+        - If the repository was not found.
+        - If the request to the GitHub API failed.
+    */
+    #[instrument(skip(self), fields(%tool_id), level = "debug")]
+    pub async fn get_provenance(
+        &self,
+        tool_id: &ToolId,
+        version: Option<&Version>,
+    ) -> GithubResult<Provenance> {
+        let mut provenance = self.get_repository_provenance(tool_id).await?;
+
+        let release = if let Some(version) = version {
+            let tool_spec: ToolSpec = (tool_id.clone(), version.clone()).into();
+            self.get_specific_github_release(&tool_spec).await
+        } else {
+            let url = format!(
+                "{base}/repos/{owner}/{repo}/releases/latest",
+                base = self.base_url,
+                owner = tool_id.author(),
+                repo = tool_id.name(),
+            );
+            self.get_json::<GithubRelease>(&url, tool_id.author()).await
+        };
+        let release = match release {
+            Err(e) if is_404(&e) || matches!(e, GithubError::ReleaseNotFound(_)) => {
+                return Ok(provenance);
+            }
+            Err(e) => return Err(e),
+            Ok(r) => r,
+        };
+
+        provenance.release_tag = Some(release.tag_name);
+        provenance.publisher = release.author.map(|author| author.login);
+        provenance.published_at = release.published_at;
+        provenance.assets = release
+            .assets
+            .into_iter()
+            .map(|asset| ProvenanceAsset {
+                name: asset.name,
+                size: asset.size,
+            })
+            .collect();
+        Ok(provenance)
+    }
+
+    /**
+        Searches for repositories matching the given query, optionally only
+        including repositories with the given topic, sorted by their stars.
+
+        Repositories with names that are not valid tool identifiers are skipped.
+
+        # Errors
+
+        - If the request to the GitHub API failed.
+    */
+    #[instrument(skip(self), level = "debug")]
+    pub async fn search_repositories(
+        &self,
+        query: &str,
+        topic: Option<&str>,
+        limit: usize,
+    ) -> GithubResult<Vec<SearchResult>> {
+        debug!(query, topic, "searching for repositories");
+
+        let query = match topic {
+            Some(topic) => format!("{query} topic:{topic}"),
+            None => query.to_string(),
+        };
+        let per_page = limit.clamp(1, SEARCH_RESULTS_MAX).to_string();
+        let url = Url::parse_with_params(
+            &format!("{base}/search/repositories", base = self.base_url),
+            [
+                ("q", query.as_str()),
+                ("sort", "stars"),
+                ("order", "desc"),
+                ("per_page", per_page.as_str()),
+            ],
+        )
+        .map_err(|e| GithubError::Other(e.to_string()))?;
+
+        let results: GithubSearchResults = self.get_json(url.as_str(), "").await?;
+        Ok(results
+            .items
+            .into_iter()
+            .filter_map(|repository| {
+                let id = repository.full_name.parse::<ToolId>().ok()?;
+                Some(SearchResult {
+                    id,
+                    url: repository.html_url,
+                    description: repository
+                        .description
+                        .filter(|description| !description.trim().is_empty()),
+                    stars: repository.stargazers_count,
+                })
+            })
+            .collect())
+    }
+
+    /**
+        Fetches an archive of the repository for a given tool, at the given git ref.
+
+        The git ref - a branch, tag, or commit - is resolved to a specific commit, and
+        the returned release contains a single artifact for the repository archive,
+        with its tool specification pinned to the full SHA of that commit.
+
+        # Errors
+
+        - If the git ref was not found in the repository.
+        - If the request to the GitHub API failed.
+    */
+    #[instrument(skip(self), fields(%tool_id), level = "debug")]
+    pub async fn get_repository_archive(
+        &self,
+        tool_id: &ToolId,
+        git_ref: &str,
+    ) -> GithubResult<Release> {
+        debug!(id = %tool_id, git_ref, "fetching repository archive for tool");
+
+        let url = format!(
+            "{base}/repos/{owner}/{repo}/commits/{git_ref}",
+            base = self.base_url,
+            owner = tool_id.author(),
+            repo = tool_id.name(),
+        );
+
+        let commit: GithubCommit = match self.get_json(&url, tool_id.author()).await {
+            Err(e) if is_404(&e) || is_422(&e) => {
+                return Err(GithubError::GitRefNotFound(
+                    tool_id.clone().into(),
+                    git_ref.to_string(),
+                ));
+            }
+            Err(e) => return Err(e),
+            Ok(c) => c,
+        };
+
+        let tool_spec = ToolSpec::with_git_ref(tool_id.clone(), &commit.sha)
+            .ok_or_else(|| GithubError::Other(format!("invalid commit sha '{}'", commit.sha)))?;
+        let archive_url = format!(
+            "{base}/repos/{owner}/{repo}/zipball/{sha}",
+            base = self.base_url,
+            owner = tool_id.author(),
+            repo = tool_id.name(),
+            sha = commit.sha,
+        );
+        let archive_url = archive_url
+            .parse()
+            .map_err(|e: url::ParseError| GithubError::Other(e.to_string()))?;
+
+        Ok(Release {
+            changelog: None,
+            artifacts: vec![Artifact::from_github_repository_archive(
+                archive_url,
+                &tool_spec,
+            )],
+            checksums: Vec::new(),
+            tag_name: None,
+            prerelease: false,
+            author: None,
+            published_at: None,
+        })
+    }
+
+    /**
+        Fetches the artifacts of a GitHub Actions workflow run for a given tool,
+        using the workflow of the tool, for unreleased builds of the tool.
+
+        The workflow ref may be the ID of a specific workflow run, or a branch to use
+        the latest successful workflow run of - if no workflow ref is given, the default
+        branch of the repository is used. The returned release contains all unexpired
+        artifacts of the run, with their tool specification pinned to the ID of the run.
+
+        # Errors
+
+        - If GitHub authentication is missing, which is required for workflow artifacts.
+        - If the workflow run was not found, or has no unexpired artifacts.
+        - If the request to the GitHub API failed.
+    */
+    #[instrument(skip(self), fields(%tool_id), level = "debug")]
+    pub async fn get_workflow_artifacts(
+        &self,
+        tool_id: &ToolId,
+        workflow_ref: Option<&str>,
+    ) -> GithubResult<Release> {
+        debug!(id = %tool_id, workflow_ref, "fetching workflow artifacts for tool");
+
+        if !self.has_auth {
+            return Err(GithubError::WorkflowRequiresAuth);
+        }
+        let workflow = tool_id
+            .workflow()
+            .ok_or_else(|| GithubError::Other(format!("tool '{tool_id}' has no workflow")))?;
+        let repo_url = format!(
+            "{base}/repos/{owner}/{repo}",
+            base = self.base_url,
+            owner = tool_id.author(),
+            repo = tool_id.name(),
+        );
+        let owner = tool_id.author();
+        let not_found = |workflow_ref: &str| {
+            GithubError::WorkflowRunNotFound(tool_id.clone().into(), workflow_ref.to_string())
+        };
+
+        let run: GithubWorkflowRun = match workflow_ref {
+            Some(run_id) if run_id.parse::<u64>().is_ok() => {
+                let url = format!("{repo_url}/actions/runs/{run_id}");
+                match self.get_json(&url, owner).await {
+                    Err(e) if is_404(&e) => return Err(not_found(run_id)),
+                    Err(e) => return Err(e),
+                    Ok(r) => r,
+                }
+            }
+            _ => {
+                let branch = if let Some(branch) = workflow_ref {
+                    branch.to_string()
+                } else {
+                    let repository: GithubRepository = self.get_json(&repo_url, owner).await?;
+                    repository
+                        .default_branch
+                        .unwrap_or_else(|| DEFAULT_BRANCH.to_string())
+                };
+                // NOTE: Branches and workflow file names may contain characters
+                // such as '/', '&' or '#', so they must be encoded, and never
+                // interpolated into the url directly
+                let mut url = Url::parse_with_params(
+                    &repo_url,
+                    [
+                        ("branch", branch.as_str()),
+                        ("status", "success"),
+                        ("per_page", "1"),
+                    ],
+                )
+                .map_err(|e| GithubError::Other(e.to_string()))?;
+                url.path_segments_mut()
+                    .map_err(|()| GithubError::Other(format!("invalid base url '{repo_url}'")))?
+                    .extend(["actions", "workflows", workflow, "runs"]);
+                let runs: GithubWorkflowRuns = match self.get_json(url.as_str(), owner).await {
+                    Err(e) if is_404(&e) => return Err(not_found(&branch)),
+                    Err(e) => return Err(e),
+                    Ok(r) => r,
+                };
+                runs.workflow_runs
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| not_found(&branch))?
+            }
+        };
+        let run_id = run.id.to_string();
+        if run.conclusion.as_deref().is_some_and(|c| c != "success") {
+            return Err(not_found(&run_id));
+        }
+
+        let url = format!("{repo_url}/actions/runs/{run_id}/artifacts?per_page=100");
+        let artifacts: GithubWorkflowArtifacts = self.get_json(&url, owner).await?;
+        let tool_spec = ToolSpec::with_workflow_ref(tool_id.clone(), &run_id)
+            .ok_or_else(|| GithubError::Other(format!("invalid workflow run id '{run_id}'")))?;
+        let artifacts = artifacts
+            .artifacts
+            .iter()
+            .filter(|artifact| !artifact.expired)
+            .map(|artifact| Artifact::from_github_workflow_artifact(artifact, &tool_spec))
+            .collect::<Vec<_>>();
+        if artifacts.is_empty() {
+            return Err(not_found(&run_id));
+        }
+
         Ok(Release {
-            changelog: release.changelog.clone(),
-            artifacts: artifacts_from_release(&release, tool_spec),
+            changelog: None,
+            artifacts,
+            checksums: Vec::new(),
+            tag_name: None,
+            prerelease: true,
+            author: run.actor.map(|actor| actor.login),
+            published_at: run.created_at,
         })
     }
 
@@ -200,24 +846,83 @@ impl GithubProvider {
     */
     #[instrument(skip(self, artifact), level = "debug")]
     pub async fn download_artifact_contents(&self, artifact: &Artifact) -> GithubResult<Vec<u8>> {
+        let url = self.artifact_download_url(artifact);
+        debug!(name = ?artifact.name, %url, "downloading artifact contents");
+        self.get_bytes(&url, artifact.tool_spec.author()).await
+    }
+
+    /**
+        Creates a request for downloading the contents of the given artifact.
+
+        Used for downloads that need more control over the request,
+        such as resuming partial downloads using range requests.
+    */
+    pub(crate) fn download_artifact_request(&self, artifact: &Artifact) -> RequestBuilder {
+        let url = self.artifact_download_url(artifact);
+        debug!(name = ?artifact.name, %url, "downloading artifact contents");
+        self.get_bytes_request(&url, artifact.tool_spec.author())
+    }
+
+    /**
+        Gets the URL that the contents of the given artifact are downloaded from.
+    */
+    pub(crate) fn artifact_download_url(&self, artifact: &Artifact) -> String {
         assert_eq!(
             artifact.provider,
             ArtifactProvider::GitHub,
             "artifact must be from GitHub"
         );
 
-        let id = artifact.id.as_ref().expect("GitHub artifacts have ids");
-        let name = artifact.name.as_ref().expect("GitHub artifacts have names");
-        debug!(id, name, "downloading artifact contents");
+        // NOTE: Repository archives have no asset id, only a url
+        let Some(id) = artifact.id.as_ref() else {
+            let url = artifact.url.as_ref().expect("GitHub archives have urls");
+            return url.to_string();
+        };
 
-        let url = format!(
-            "{BASE_URL}/repos/{owner}/{repo}/releases/assets/{id}",
+        format!(
+            "{base}/repos/{owner}/{repo}/releases/assets/{id}",
+            base = self.base_url,
             owner = artifact.tool_spec.author(),
             repo = artifact.tool_spec.name(),
-        );
+        )
+    }
+}
 
-        self.get_bytes(&url).await
+/*
+    Checks the status of a response from the GitHub API, turning rejected
+    tokens and exceeded rate limits into their own errors - GitHub responds
+    with either a 403 or a 429 when the rate limit has been exceeded.
+
+    https://docs.github.com/en/rest/using-the-rest-api/rate-limits-for-the-rest-api
+*/
+fn check_response_status(response: Response) -> GithubResult<Response> {
+    let status = response.status();
+    if status == StatusCode::UNAUTHORIZED {
+        return Err(GithubError::Unauthorized);
+    }
+
+    let headers = response.headers();
+    let header_secs = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+    };
+    let remaining = header_secs("x-ratelimit-remaining");
+    let retry_after = header_secs(RETRY_AFTER.as_str());
+    let is_rate_limited = status == StatusCode::TOO_MANY_REQUESTS
+        || (status == StatusCode::FORBIDDEN && (remaining == Some(0) || retry_after.is_some()));
+    if is_rate_limited {
+        let reset_at = match retry_after {
+            Some(secs) => Some(SystemTime::now() + Duration::from_secs(secs)),
+            None => {
+                header_secs("x-ratelimit-reset").map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+            }
+        };
+        return Err(GithubError::RateLimited { reset_at });
     }
+
+    Ok(response.error_for_status()?)
 }
 
 fn is_404(err: &GithubError) -> bool {
@@ -229,7 +934,19 @@ fn is_404(err: &GithubError) -> bool {
     false
 }
 
+fn is_422(err: &GithubError) -> bool {
+    if let GithubError::Reqwest(reqwest_err) = err {
+        if let Some(status) = reqwest_err.status() {
+            return status == StatusCode::UNPROCESSABLE_ENTITY;
+        }
+    }
+    false
+}
+
 fn is_unauthenticated(err: &GithubError) -> bool {
+    if matches!(err, GithubError::Unauthorized) {
+        return true;
+    }
     if let GithubError::Reqwest(reqwest_err) = err {
         if let Some(status) = reqwest_err.status() {
             return matches!(status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN);
@@ -238,10 +955,53 @@ fn is_unauthenticated(err: &GithubError) -> bool {
     false
 }
 
+/*
+    Checks that a release can be installed from - draft releases are only
+    visible to users with access to the repository, and releases may have
+    no assets for a short while after publishing, while they are uploaded.
+*/
+fn check_release_state(release: &GithubRelease, spec: &ToolSpec) -> GithubResult<()> {
+    if release.draft {
+        return Err(GithubError::DraftRelease(spec.clone().into()));
+    }
+    if release.assets.is_empty() {
+        return Err(GithubError::ReleaseHasNoAssets(spec.clone().into()));
+    }
+    Ok(())
+}
+
+fn release_from_github(release: &GithubRelease, spec: &ToolSpec) -> Release {
+    Release {
+        changelog: release.changelog.clone(),
+        artifacts: artifacts_from_release(release, spec),
+        checksums: checksums_from_release(release, spec),
+        tag_name: Some(release.tag_name.clone()),
+        prerelease: release.prerelease,
+        author: release.author.as_ref().map(|author| author.login.clone()),
+        published_at: release.published_at.clone(),
+    }
+}
+
 fn artifacts_from_release(release: &GithubRelease, spec: &ToolSpec) -> Vec<Artifact> {
     release
         .assets
         .iter()
+        .filter(|asset| {
+            let installable = Artifact::is_installable_asset(&asset.name);
+            if !installable {
+                trace!(name = %asset.name, "skipping non-installable asset");
+            }
+            installable
+        })
+        .map(|asset| Artifact::from_github_release_asset(asset, spec))
+        .collect::<Vec<_>>()
+}
+
+fn checksums_from_release(release: &GithubRelease, spec: &ToolSpec) -> Vec<Artifact> {
+    release
+        .assets
+        .iter()
+        .filter(|asset| Artifact::is_checksum_asset(&asset.name))
         .map(|asset| Artifact::from_github_release_asset(asset, spec))
         .collect::<Vec<_>>()
 }
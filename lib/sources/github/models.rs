@@ -6,8 +6,14 @@ pub struct GithubRelease {
     pub assets: Vec<GithubAsset>,
     pub tag_name: String,
     pub prerelease: bool,
+    #[serde(default)]
+    pub draft: bool,
     #[serde(rename = "body")]
     pub changelog: Option<String>,
+    #[serde(default)]
+    pub author: Option<GithubUser>,
+    #[serde(default)]
+    pub published_at: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -15,4 +21,95 @@ pub struct GithubAsset {
     pub id: u64,
     pub url: Url,
     pub name: String,
+    #[serde(default)]
+    pub size: Option<u64>,
+    #[serde(default)]
+    pub digest: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GithubUser {
+    pub login: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GithubRepository {
+    pub html_url: Url,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub stargazers_count: Option<u64>,
+    #[serde(default)]
+    pub default_branch: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GithubSearchResults {
+    pub items: Vec<GithubSearchRepository>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GithubSearchRepository {
+    pub full_name: String,
+    pub html_url: Url,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub stargazers_count: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GithubCommit {
+    pub sha: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GithubWorkflowRuns {
+    pub workflow_runs: Vec<GithubWorkflowRun>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GithubWorkflowRun {
+    pub id: u64,
+    #[serde(default)]
+    pub conclusion: Option<String>,
+    #[serde(default)]
+    pub actor: Option<GithubUser>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GithubWorkflowArtifacts {
+    pub artifacts: Vec<GithubWorkflowArtifact>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GithubWorkflowArtifact {
+    pub name: String,
+    pub archive_download_url: Url,
+    #[serde(default)]
+    pub digest: Option<String>,
+    #[serde(default)]
+    pub expired: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GithubAdvisory {
+    pub ghsa_id: String,
+    pub summary: String,
+    #[serde(default)]
+    pub severity: Option<String>,
+    #[serde(default)]
+    pub html_url: Option<Url>,
+    #[serde(default)]
+    pub vulnerabilities: Vec<GithubVulnerability>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GithubVulnerability {
+    #[serde(default)]
+    pub vulnerable_version_range: Option<String>,
+    #[serde(default)]
+    pub patched_versions: Option<String>,
 }
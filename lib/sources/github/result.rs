@@ -1,8 +1,22 @@
-use reqwest::{header::InvalidHeaderValue, Error as ReqwestError};
+use std::time::SystemTime;
+
+use reqwest::{header::InvalidHeaderValue, Error as ReqwestError, StatusCode};
+use semver::VersionReq;
 use thiserror::Error;
 
-use crate::tool::{ToolId, ToolSpec};
+use crate::{
+    result::ErrorKind,
+    sources::client::{redact_middleware_error, redact_reqwest_error, redact_tokens},
+    tool::{ToolId, ToolSpec},
+};
+
+/**
+    An error from the GitHub artifact provider.
 
+    Credentials in the URLs of failed requests are redacted when the
+    error is created, and any other access tokens are redacted when
+    the error is displayed, so that errors are always safe to log.
+*/
 #[derive(Debug, Error)]
 pub enum GithubError {
     #[error("unrecognized access token format - must begin with `ghp_` or `gho_`.")]
@@ -11,18 +25,72 @@ pub enum GithubError {
     LatestReleaseNotFound(Box<ToolId>),
     #[error("no release was found for tool '{0}'")]
     ReleaseNotFound(Box<ToolSpec>),
+    #[error("no release matching '{1}' was found for tool '{0}'")]
+    MatchingReleaseNotFound(Box<ToolId>, VersionReq),
+    #[error("no release in the '{1}' channel was found for tool '{0}'")]
+    ChannelReleaseNotFound(Box<ToolId>, String),
+    #[error("no branch, tag, or commit '{1}' was found for tool '{0}'")]
+    GitRefNotFound(Box<ToolId>, String),
+    #[error("the release for '{0}' is a draft, and has not been published yet - retry later")]
+    DraftRelease(Box<ToolSpec>),
+    #[error("the release for '{0}' exists, but has no downloadable assets yet - retry later")]
+    ReleaseHasNoAssets(Box<ToolSpec>),
+    #[error("no successful workflow run '{1}' with unexpired artifacts was found for tool '{0}'")]
+    WorkflowRunNotFound(Box<ToolId>, String),
+    #[error("downloading artifacts from GitHub Actions workflow runs requires authentication")]
+    WorkflowRequiresAuth,
+    /**
+        The GitHub API rate limit was exceeded - `reset_at` is the
+        time that the limit resets, if GitHub reported it.
+    */
+    #[error("the GitHub API rate limit was exceeded")]
+    RateLimited { reset_at: Option<SystemTime> },
+    /**
+        The access token used for a request was rejected by GitHub.
+    */
+    #[error("the GitHub access token is invalid, has expired, or has been revoked")]
+    Unauthorized,
     #[error("failed to build client - invalid header value: {0}")]
     ReqwestHeader(Box<InvalidHeaderValue>),
-    #[error("reqwest middleware error: {0}")]
+    #[error("reqwest middleware error: {}", redact_tokens(&.0.to_string()))]
     ReqwestMiddleware(Box<reqwest_middleware::Error>),
     #[error("reqwest error: {0}")]
     Reqwest(Box<reqwest::Error>),
-    #[error("other error: {0}")]
+    #[error("other error: {}", redact_tokens(.0))]
     Other(String),
 }
 
 pub type GithubResult<T> = Result<T, GithubError>;
 
+impl GithubError {
+    /**
+        Returns the broad category of this error.
+    */
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::UnrecognizedAccessToken | Self::Unauthorized | Self::WorkflowRequiresAuth => {
+                ErrorKind::Authentication
+            }
+            Self::LatestReleaseNotFound(_)
+            | Self::ReleaseNotFound(_)
+            | Self::MatchingReleaseNotFound(_, _)
+            | Self::ChannelReleaseNotFound(_, _)
+            | Self::GitRefNotFound(_, _)
+            | Self::DraftRelease(_)
+            | Self::ReleaseHasNoAssets(_)
+            | Self::WorkflowRunNotFound(_, _) => ErrorKind::NotFound,
+            Self::Reqwest(e) => match e.status() {
+                Some(StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) => ErrorKind::Authentication,
+                Some(StatusCode::NOT_FOUND) => ErrorKind::NotFound,
+                _ => ErrorKind::Network,
+            },
+            Self::RateLimited { .. } | Self::ReqwestMiddleware(_) => ErrorKind::Network,
+            Self::ReqwestHeader(_) | Self::Other(_) => ErrorKind::Other,
+        }
+    }
+}
+
 // FUTURE: Figure out some way to reduce this boxing boilerplate
 
 impl From<InvalidHeaderValue> for GithubError {
@@ -33,12 +101,12 @@ impl From<InvalidHeaderValue> for GithubError {
 
 impl From<reqwest_middleware::Error> for GithubError {
     fn from(err: reqwest_middleware::Error) -> Self {
-        GithubError::ReqwestMiddleware(err.into())
+        GithubError::ReqwestMiddleware(redact_middleware_error(err).into())
     }
 }
 
 impl From<ReqwestError> for GithubError {
     fn from(err: ReqwestError) -> Self {
-        GithubError::Reqwest(err.into())
+        GithubError::Reqwest(redact_reqwest_error(err).into())
     }
 }
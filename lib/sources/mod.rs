@@ -1,11 +1,24 @@
+mod advisory;
 mod artifact;
 mod client;
 mod decompression;
+mod download;
 mod extraction;
+mod provenance;
+mod search;
 mod source;
 
 pub mod github;
 
-pub use self::artifact::{Artifact, ArtifactFormat, ArtifactProvider, Release};
-pub use self::extraction::ExtractError;
+pub use self::advisory::Advisory;
+#[cfg(any(test, feature = "asset-selection"))]
+pub use self::artifact::{select_best_asset, select_best_asset_for, select_release_artifact};
+pub use self::artifact::{
+    split_filename_and_extensions, Artifact, ArtifactFormat, ArtifactProvider, Release,
+};
+pub use self::client::{redact_tokens, HTTP_TRACE_TARGET};
+pub use self::download::DownloadError;
+pub use self::extraction::{ExtractError, ExtractLimits, ExtractedFile};
+pub use self::provenance::{Provenance, ProvenanceAsset};
+pub use self::search::SearchResult;
 pub use self::source::ArtifactSource;
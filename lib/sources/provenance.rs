@@ -0,0 +1,44 @@
+use url::Url;
+
+use crate::util::str::timestamp_date;
+
+/**
+    Details about where a tool, and one of its releases, come from - such as
+    the repository description and the account that published the release.
+
+    These are shown to users when deciding if a tool should be trusted.
+    All details are optional, since providers may not have all of them,
+    and tools downloaded as repository archives never have release details.
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Provenance {
+    pub repository_url: Option<Url>,
+    pub description: Option<String>,
+    pub stars: Option<u64>,
+    pub release_tag: Option<String>,
+    pub publisher: Option<String>,
+    /**
+        The time that the release was published, as an RFC 3339 timestamp.
+    */
+    pub published_at: Option<String>,
+    pub assets: Vec<ProvenanceAsset>,
+}
+
+impl Provenance {
+    /**
+        Gets the date that the release was published, formatted as `YYYY-MM-DD`.
+    */
+    #[must_use]
+    pub fn published_date(&self) -> Option<&str> {
+        self.published_at.as_deref().map(timestamp_date)
+    }
+}
+
+/**
+    A single asset of a release, as listed by the provider.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProvenanceAsset {
+    pub name: String,
+    pub size: Option<u64>,
+}
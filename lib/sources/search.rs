@@ -0,0 +1,14 @@
+use url::Url;
+
+use crate::tool::ToolId;
+
+/**
+    A tool found when searching for tools, such as a repository on GitHub.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    pub id: ToolId,
+    pub url: Url,
+    pub description: Option<String>,
+    pub stars: u64,
+}
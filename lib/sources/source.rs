@@ -1,11 +1,25 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, path::PathBuf};
+
+use semver::{Version, VersionReq};
+use tokio::fs::remove_file;
+use tracing::debug;
 
 use crate::{
     result::RokitResult,
     tool::{ToolId, ToolSpec},
+    util::{hash::sha256_hex, lock::FileLock},
+};
+
+use super::{
+    download::download_resumable, github::GithubProvider, Advisory, Artifact, ArtifactProvider,
+    DownloadError, Provenance, Release, SearchResult,
 };
 
-use super::{github::GithubProvider, Artifact, ArtifactProvider, Release};
+/**
+    The git ref used for tools downloaded as repository
+    archives, when no specific git ref is requested.
+*/
+const DEFAULT_GIT_REF: &str = "HEAD";
 
 /**
     A source for artifacts.
@@ -15,6 +29,7 @@ use super::{github::GithubProvider, Artifact, ArtifactProvider, Release};
 #[derive(Debug, Clone)]
 pub struct ArtifactSource {
     github: GithubProvider,
+    download_dir: Option<PathBuf>,
 }
 
 impl ArtifactSource {
@@ -30,7 +45,10 @@ impl ArtifactSource {
     */
     pub fn new() -> RokitResult<Self> {
         let github = GithubProvider::new()?;
-        Ok(Self { github })
+        Ok(Self {
+            github,
+            download_dir: None,
+        })
     }
 
     /**
@@ -47,12 +65,68 @@ impl ArtifactSource {
             Some(token) => GithubProvider::new_authenticated(token)?,
             None => GithubProvider::new()?,
         };
-        Ok(Self { github })
+        Ok(Self {
+            github,
+            download_dir: None,
+        })
+    }
+
+    /**
+        Adds tokens for the given artifact provider, to use for tools
+        by specific owners, such as GitHub users or organizations.
+
+        # Errors
+
+        - If any of the tokens could not be used.
+    */
+    pub fn with_owner_tokens(
+        mut self,
+        provider: ArtifactProvider,
+        tokens: &HashMap<String, String>,
+    ) -> RokitResult<Self> {
+        match provider {
+            ArtifactProvider::GitHub
+            | ArtifactProvider::GitHubBranch
+            | ArtifactProvider::GitHubActions => {
+                self.github = self.github.with_owner_tokens(tokens)?;
+            }
+        }
+        Ok(self)
+    }
+
+    /**
+        Creates a new artifact source that uses the given mock server
+        url instead of the real GitHub API, for use in tests.
+    */
+    #[cfg(test)]
+    pub(crate) fn new_mocked(base_url: &str) -> RokitResult<Self> {
+        let github = GithubProvider::new_with_base_url(base_url)?;
+        Ok(Self {
+            github,
+            download_dir: None,
+        })
+    }
+
+    /**
+        Sets the directory to store partial downloads in.
+
+        When set, interrupted downloads will be resumed from where
+        they left off, instead of starting over from the beginning.
+    */
+    #[must_use]
+    pub fn with_download_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.download_dir = Some(dir.into());
+        self
     }
 
     /**
         Gets the latest release for a tool.
 
+        For tools downloaded as repository archives, this is an
+        archive of the latest commit on the default branch, and for
+        tools downloaded from workflow artifacts, this is the latest
+        successful workflow run on the default branch.
+
         # Errors
 
         - If the latest release could not be fetched.
@@ -60,6 +134,86 @@ impl ArtifactSource {
     pub async fn get_latest_release(&self, id: &ToolId) -> RokitResult<Release> {
         Ok(match id.provider() {
             ArtifactProvider::GitHub => self.github.get_latest_release(id).await?,
+            ArtifactProvider::GitHubBranch => {
+                self.github
+                    .get_repository_archive(id, DEFAULT_GIT_REF)
+                    .await?
+            }
+            ArtifactProvider::GitHubActions => self.github.get_workflow_artifacts(id, None).await?,
+        })
+    }
+
+    /**
+        Gets the latest release for a tool, including prereleases.
+
+        # Errors
+
+        - If the latest release could not be fetched.
+    */
+    pub async fn get_latest_prerelease(&self, id: &ToolId) -> RokitResult<Release> {
+        Ok(match id.provider() {
+            ArtifactProvider::GitHub => self.github.get_latest_prerelease(id).await?,
+            ArtifactProvider::GitHubBranch => {
+                self.github
+                    .get_repository_archive(id, DEFAULT_GIT_REF)
+                    .await?
+            }
+            ArtifactProvider::GitHubActions => self.github.get_workflow_artifacts(id, None).await?,
+        })
+    }
+
+    /**
+        Gets the latest release for a tool in the given release channel,
+        such as `stable` or `nightly` - see [`ToolOptions`] for details.
+
+        For tools downloaded as repository archives or workflow artifacts, channels
+        are ignored, and this is the same as [`Self::get_latest_release`].
+
+        [`ToolOptions`]: crate::manifests::ToolOptions
+
+        # Errors
+
+        - If the latest release in the channel could not be fetched.
+    */
+    pub async fn get_latest_release_in_channel(
+        &self,
+        id: &ToolId,
+        channel: &str,
+    ) -> RokitResult<Release> {
+        Ok(match id.provider() {
+            ArtifactProvider::GitHub => {
+                self.github
+                    .get_latest_release_in_channel(id, channel)
+                    .await?
+            }
+            ArtifactProvider::GitHubBranch => {
+                self.github
+                    .get_repository_archive(id, DEFAULT_GIT_REF)
+                    .await?
+            }
+            ArtifactProvider::GitHubActions => self.github.get_workflow_artifacts(id, None).await?,
+        })
+    }
+
+    /**
+        Gets the latest release for a tool matching the given version requirement.
+
+        # Errors
+
+        - If no release matching the requirement could be fetched.
+    */
+    pub async fn get_latest_release_matching(
+        &self,
+        id: &ToolId,
+        version_req: &VersionReq,
+    ) -> RokitResult<Release> {
+        Ok(match id.provider() {
+            ArtifactProvider::GitHub | ArtifactProvider::GitHubBranch => {
+                self.github
+                    .get_latest_release_matching(id, version_req)
+                    .await?
+            }
+            ArtifactProvider::GitHubActions => self.github.get_workflow_artifacts(id, None).await?,
         })
     }
 
@@ -73,19 +227,218 @@ impl ArtifactSource {
     pub async fn get_specific_release(&self, spec: &ToolSpec) -> RokitResult<Release> {
         Ok(match spec.provider() {
             ArtifactProvider::GitHub => self.github.get_specific_release(spec).await?,
+            ArtifactProvider::GitHubBranch => {
+                let git_ref = spec.git_ref().unwrap_or(DEFAULT_GIT_REF);
+                self.github
+                    .get_repository_archive(spec.id(), git_ref)
+                    .await?
+            }
+            ArtifactProvider::GitHubActions => {
+                self.github
+                    .get_workflow_artifacts(spec.id(), spec.workflow_ref())
+                    .await?
+            }
         })
     }
 
+    /**
+        Gets the changelog for a tool, for a specific version, or
+        for the latest release if no version is given.
+
+        Returns `None` if the release has no changelog, which is always the
+        case for tools downloaded as repository archives or workflow artifacts.
+
+        # Errors
+
+        - If the release could not be fetched.
+    */
+    pub async fn get_release_changelog(
+        &self,
+        id: &ToolId,
+        version: Option<&Version>,
+    ) -> RokitResult<Option<String>> {
+        Ok(match id.provider() {
+            ArtifactProvider::GitHub => self.github.get_release_changelog(id, version).await?,
+            ArtifactProvider::GitHubBranch | ArtifactProvider::GitHubActions => None,
+        })
+    }
+
+    /**
+        Gets all published security advisories for a tool.
+
+        Tools downloaded as repository archives or workflow artifacts are
+        never versioned, and advisories are therefore not checked for those.
+
+        # Errors
+
+        - If the advisories could not be fetched.
+    */
+    pub async fn get_security_advisories(&self, id: &ToolId) -> RokitResult<Vec<Advisory>> {
+        Ok(match id.provider() {
+            ArtifactProvider::GitHub => self.github.get_security_advisories(id).await?,
+            ArtifactProvider::GitHubBranch | ArtifactProvider::GitHubActions => Vec::new(),
+        })
+    }
+
+    /**
+        Gets details about where a tool comes from, to help users decide if
+        it should be trusted - for a specific version, or the latest release
+        if no version is given.
+
+        Tools downloaded as repository archives or workflow artifacts
+        only have repository details.
+
+        # Errors
+
+        - If the repository for the tool was not found.
+        - If the details could not be fetched.
+    */
+    pub async fn get_provenance(
+        &self,
+        id: &ToolId,
+        version: Option<&Version>,
+    ) -> RokitResult<Provenance> {
+        Ok(match id.provider() {
+            ArtifactProvider::GitHub => self.github.get_provenance(id, version).await?,
+            ArtifactProvider::GitHubBranch | ArtifactProvider::GitHubActions => {
+                self.github.get_repository_provenance(id).await?
+            }
+        })
+    }
+
+    /**
+        Searches for tools matching the given query, optionally only including
+        tools with the given topic, such as `roblox` - the most popular tools
+        are returned first, up to the given limit.
+
+        # Errors
+
+        - If the search request failed.
+    */
+    pub async fn search_tools(
+        &self,
+        query: &str,
+        topic: Option<&str>,
+        limit: usize,
+    ) -> RokitResult<Vec<SearchResult>> {
+        Ok(self.github.search_repositories(query, topic, limit).await?)
+    }
+
     /**
         Downloads the contents of an artifact.
 
+        If a download directory has been set, partial downloads will be
+        resumed, and the contents will be verified against the size and
+        hash of the artifact, if those are provided by the artifact provider.
+        The partial download is locked while in use, so that any other
+        process downloading the same artifact waits for this one to finish.
+
         # Errors
 
         - If the artifact contents could not be downloaded.
+        - If the downloaded contents did not match the expected size or hash.
     */
     pub async fn download_artifact_contents(&self, artifact: &Artifact) -> RokitResult<Vec<u8>> {
-        Ok(match &artifact.provider {
-            ArtifactProvider::GitHub => self.github.download_artifact_contents(artifact).await?,
-        })
+        let Some(partial_path) = self.partial_download_path(artifact) else {
+            return Ok(match &artifact.provider {
+                ArtifactProvider::GitHub
+                | ArtifactProvider::GitHubBranch
+                | ArtifactProvider::GitHubActions => {
+                    self.github.download_artifact_contents(artifact).await?
+                }
+            });
+        };
+
+        let _lock = FileLock::acquire(partial_path.with_extension("lock")).await?;
+        Ok(download_resumable(
+            || match &artifact.provider {
+                ArtifactProvider::GitHub
+                | ArtifactProvider::GitHubBranch
+                | ArtifactProvider::GitHubActions => {
+                    self.github.download_artifact_request(artifact)
+                }
+            },
+            &partial_path,
+            artifact.size,
+            artifact.sha256.as_deref(),
+        )
+        .await?)
+    }
+
+    /**
+        Removes any partially downloaded contents of an artifact, such as
+        after a download was cancelled, and should not be resumed later.
+
+        Does nothing if no download directory has been set.
+
+        # Errors
+
+        - If the partial download exists, but could not be removed.
+    */
+    pub async fn remove_partial_download(&self, artifact: &Artifact) -> RokitResult<()> {
+        let Some(partial_path) = self.partial_download_path(artifact) else {
+            return Ok(());
+        };
+        let _lock = FileLock::acquire(partial_path.with_extension("lock")).await?;
+        match remove_file(&partial_path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /*
+        Gets the path of the partial download for an artifact, keyed by a hash of
+        its download URL and tool specification - names and ids of artifacts are
+        not unique, such as for artifacts of different workflow runs, which must
+        never be resumed from each other.
+    */
+    pub(crate) fn partial_download_path(&self, artifact: &Artifact) -> Option<PathBuf> {
+        let download_dir = self.download_dir.as_ref()?;
+        let url = match &artifact.provider {
+            ArtifactProvider::GitHub
+            | ArtifactProvider::GitHubBranch
+            | ArtifactProvider::GitHubActions => self.github.artifact_download_url(artifact),
+        };
+        let key = sha256_hex(format!("{url}\n{}", artifact.tool_spec).as_bytes());
+        Some(download_dir.join(format!("{}-{key}.partial", artifact.provider.as_str())))
+    }
+
+    /**
+        Verifies the downloaded contents of an artifact against the
+        checksum files published with its release, such as `SHA256SUMS`.
+
+        Returns `true` if the contents were verified, or `false` if
+        the release did not publish a checksum for the artifact.
+
+        # Errors
+
+        - If a checksum file could not be downloaded.
+        - If the contents did not match the published checksum.
+    */
+    pub async fn verify_release_checksum(
+        &self,
+        release: &Release,
+        artifact: &Artifact,
+        contents: &[u8],
+    ) -> RokitResult<bool> {
+        for checksums in &release.checksums {
+            let checksums_name = checksums.name.as_deref().unwrap_or_default();
+            let checksums_contents = self.download_artifact_contents(checksums).await?;
+            let checksums_contents = String::from_utf8_lossy(&checksums_contents);
+
+            let Some(expected) =
+                artifact.find_sha256_in_checksums(checksums_name, &checksums_contents)
+            else {
+                continue;
+            };
+
+            let actual = sha256_hex(contents);
+            if actual != expected {
+                return Err(DownloadError::HashMismatch { expected, actual }.into());
+            }
+            debug!(name = checksums_name, "verified artifact checksum");
+            return Ok(true);
+        }
+        Ok(false)
     }
 }
@@ -0,0 +1,346 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env::consts::EXE_SUFFIX,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::fs::{copy, create_dir_all, hard_link, read, read_dir, remove_dir, remove_file, rename};
+use tracing::{debug, trace, warn};
+
+use crate::{
+    result::RokitResult,
+    util::{
+        fs::{path_exists, write_atomic, write_executable_file},
+        hash::sha256_hex,
+    },
+};
+
+/**
+    Name of the directory, stored in the tool storage directory, that contains
+    all blobs - this starts with a dot so that it can never be mistaken for the
+    directory of a tool author, since author names can not start with a dot.
+*/
+pub(super) const BLOBS_DIR_NAME: &str = ".blobs";
+
+/**
+    Name of the index file, stored in the directory for every installed tool
+    version, that records which blob each file in the directory points to.
+*/
+pub(super) const BLOB_INDEX_FILE_NAME: &str = ".rokit-blobs.json";
+
+/**
+    The files in the directory for an installed tool version,
+    mapped to the hashes of the blobs that they point to.
+*/
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) struct BlobIndex {
+    files: BTreeMap<String, String>,
+}
+
+impl BlobIndex {
+    pub(super) fn insert(&mut self, relative_path: &Path, hash: String) {
+        let path = relative_path.to_string_lossy().replace('\\', "/");
+        self.files.insert(path, hash);
+    }
+
+    pub(super) fn get(&self, relative_path: &Path) -> Option<&str> {
+        let path = relative_path.to_string_lossy().replace('\\', "/");
+        self.files.get(&path).map(String::as_str)
+    }
+
+    pub(super) fn hashes(&self) -> impl Iterator<Item = &str> {
+        self.files.values().map(String::as_str)
+    }
+
+    pub(super) async fn load(tool_dir: &Path) -> Option<Self> {
+        let contents = read(tool_dir.join(BLOB_INDEX_FILE_NAME)).await.ok()?;
+        serde_json::from_slice(&contents).ok()
+    }
+
+    pub(super) async fn save(&self, tool_dir: &Path) -> RokitResult<()> {
+        let contents = serde_json::to_vec_pretty(self)?;
+        write_atomic(tool_dir.join(BLOB_INDEX_FILE_NAME), contents).await
+    }
+}
+
+/**
+    The result of collecting garbage in the blob store - see
+    [`ToolStorage::collect_garbage`] for more information.
+
+    [`ToolStorage::collect_garbage`]: super::ToolStorage::collect_garbage
+*/
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CollectedGarbage {
+    pub blobs: usize,
+    pub bytes: u64,
+}
+
+/**
+    A content-addressed store for the files of installed tools, where every
+    unique file is stored exactly once, keyed by the hash of its contents.
+
+    Directories for installed tool versions contain hardlinks to blobs, so that
+    identical files across tool versions, and reinstalls of the same version,
+    share the same data on disk - if hardlinks are not supported, files are
+    copied instead, and only the index of the tool version refers to the blob.
+
+    Callers must hold the tool storage lock while using the store.
+*/
+#[derive(Debug, Clone)]
+pub(super) struct BlobStore {
+    dir: PathBuf,
+}
+
+impl BlobStore {
+    pub(super) fn new(tools_dir: &Path) -> Self {
+        Self {
+            dir: tools_dir.join(BLOBS_DIR_NAME),
+        }
+    }
+
+    fn blob_path(&self, hash: &str, executable: bool) -> PathBuf {
+        let file_name = if executable {
+            format!("{hash}{EXE_SUFFIX}")
+        } else {
+            hash.to_string()
+        };
+        self.dir.join(&hash[..2]).join(file_name)
+    }
+
+    /**
+        Stores the given contents in a blob, unless an identical
+        blob already exists, and links the file at the given path to it.
+
+        Returns the hash of the blob.
+    */
+    pub(super) async fn store_and_link(
+        &self,
+        contents: &[u8],
+        executable: bool,
+        path: &Path,
+    ) -> RokitResult<String> {
+        let hash = sha256_hex(contents);
+        let blob_path = self.blob_path(&hash, executable);
+
+        // NOTE: Blobs may have been tampered with, or partially restored from
+        // a cache, so existing blobs are hashed again before being reused
+        let is_stored = read(&blob_path)
+            .await
+            .is_ok_and(|existing| sha256_hex(existing) == hash);
+        if is_stored {
+            trace!(%hash, "reusing existing blob");
+        } else {
+            create_dir_all(blob_path.parent().unwrap()).await?;
+            if executable {
+                write_executable_file(&blob_path, contents).await?;
+            } else {
+                write_atomic(&blob_path, contents.to_vec()).await?;
+            }
+        }
+
+        link_blob(&blob_path, path).await?;
+        Ok(hash)
+    }
+
+    /**
+        Removes all blobs that are not referenced by any of the given hashes.
+    */
+    pub(super) async fn remove_unreferenced(
+        &self,
+        referenced: &BTreeSet<String>,
+        dry_run: bool,
+    ) -> RokitResult<CollectedGarbage> {
+        let mut collected = CollectedGarbage::default();
+        if !path_exists(&self.dir).await {
+            return Ok(collected);
+        }
+
+        let mut prefix_reader = read_dir(&self.dir).await?;
+        while let Some(prefix_entry) = prefix_reader.next_entry().await? {
+            if !prefix_entry.file_type().await?.is_dir() {
+                continue;
+            }
+            let prefix_dir = prefix_entry.path();
+            let mut blob_reader = read_dir(&prefix_dir).await?;
+            while let Some(blob_entry) = blob_reader.next_entry().await? {
+                let path = blob_entry.path();
+                let file_name = blob_entry.file_name().to_string_lossy().to_string();
+                let hash = file_name.strip_suffix(EXE_SUFFIX).unwrap_or(&file_name);
+                if referenced.contains(hash) {
+                    continue;
+                }
+
+                let meta = blob_entry.metadata().await?;
+                if is_linked_elsewhere(&meta) {
+                    debug!(?path, "skipping unreferenced blob that is still linked");
+                    continue;
+                }
+
+                if !dry_run {
+                    if let Err(e) = remove_file(&path).await {
+                        warn!("Failed to remove unused blob at {path:?}\n{e}");
+                        continue;
+                    }
+                }
+                trace!(?path, "removed unreferenced blob");
+                collected.blobs += 1;
+                collected.bytes += meta.len();
+            }
+
+            // Removing a non-empty directory fails, which is fine
+            if !dry_run {
+                let _ = remove_dir(&prefix_dir).await;
+            }
+        }
+
+        Ok(collected)
+    }
+}
+
+/*
+    Links the file at the given path to the given blob, replacing any existing
+    file - the link is created next to the file first, and then renamed over it,
+    so that the file is never missing, even if linking fails halfway through.
+*/
+async fn link_blob(blob_path: &Path, path: &Path) -> RokitResult<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".rokit-link.tmp");
+    let temp_path = path.with_file_name(temp_name);
+    match remove_file(&temp_path).await {
+        Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+        _ => {}
+    }
+
+    if let Err(e) = hard_link(blob_path, &temp_path).await {
+        debug!(?path, "failed to hardlink blob, copying it instead: {e}");
+        copy(blob_path, &temp_path).await?;
+    }
+    if let Err(e) = rename(&temp_path, path).await {
+        let _ = remove_file(&temp_path).await;
+        return Err(e.into());
+    }
+
+    Ok(())
+}
+
+/*
+    Checks if a blob is still linked from somewhere else, even though no index
+    refers to it, such as if an index was removed or corrupted - the blob must
+    be kept in that case, since removing it would not free any disk space.
+*/
+#[cfg(unix)]
+fn is_linked_elsewhere(meta: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    meta.nlink() > 1
+}
+
+#[cfg(not(unix))]
+fn is_linked_elsewhere(_meta: &std::fs::Metadata) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{read, write};
+
+    use crate::{storage::Home, tool::ToolSpec};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn identical_files_share_blobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = BlobStore::new(dir.path());
+        let first = dir.path().join("first");
+        let second = dir.path().join("second");
+        write(&second, b"old contents").unwrap();
+
+        let hash_first = store.store_and_link(b"tool", false, &first).await.unwrap();
+        let hash_second = store.store_and_link(b"tool", false, &second).await.unwrap();
+        assert_eq!(hash_first, hash_second);
+        assert_eq!(read(&first).unwrap(), b"tool");
+        assert_eq!(read(&second).unwrap(), b"tool");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let blob = store.blob_path(&hash_first, false);
+            assert_eq!(std::fs::metadata(blob).unwrap().nlink(), 3);
+        }
+    }
+
+    #[tokio::test]
+    async fn modified_blobs_are_written_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = BlobStore::new(dir.path());
+        let first = dir.path().join("first");
+        let second = dir.path().join("second");
+
+        let hash = store.store_and_link(b"tool", false, &first).await.unwrap();
+        std::fs::remove_file(&first).unwrap();
+        write(store.blob_path(&hash, false), b"evil").unwrap();
+
+        // Blobs with the same length, but different contents, must not be reused
+        store.store_and_link(b"tool", false, &second).await.unwrap();
+        assert_eq!(read(&second).unwrap(), b"tool");
+        assert_eq!(read(store.blob_path(&hash, false)).unwrap(), b"tool");
+    }
+
+    #[tokio::test]
+    async fn removes_only_unreferenced_blobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = BlobStore::new(dir.path());
+        let kept = store
+            .store_and_link(b"kept", false, &dir.path().join("kept"))
+            .await
+            .unwrap();
+        let unused = store
+            .store_and_link(b"unused", false, &dir.path().join("unused"))
+            .await
+            .unwrap();
+        std::fs::remove_file(dir.path().join("unused")).unwrap();
+
+        let referenced = BTreeSet::from([kept.clone()]);
+        let dry_run = store.remove_unreferenced(&referenced, true).await.unwrap();
+        assert_eq!(dry_run.blobs, 1);
+        assert!(store.blob_path(&unused, false).exists());
+
+        let collected = store.remove_unreferenced(&referenced, false).await.unwrap();
+        assert_eq!(collected, CollectedGarbage { blobs: 1, bytes: 6 });
+        assert!(store.blob_path(&kept, false).exists());
+        assert!(!store.blob_path(&unused, false).exists());
+        assert_eq!(read(dir.path().join("kept")).unwrap(), b"kept");
+    }
+
+    #[tokio::test]
+    async fn identical_tools_share_storage_until_collected() {
+        let home = Home::new_temporary().await.unwrap();
+        let storage = home.tool_storage();
+        let spec = |s: &str| s.parse::<ToolSpec>().unwrap();
+        let (old, new) = (spec("rojo-rbx/rojo@7.4.0"), spec("rojo-rbx/rojo@7.4.1"));
+
+        // Reinstalling and installing identical versions should reuse one blob
+        for spec in [&old, &old, &new] {
+            storage
+                .replace_tool_contents(spec, b"binary")
+                .await
+                .unwrap();
+        }
+        assert_eq!(read(storage.tool_path(&new)).unwrap(), b"binary");
+        assert_eq!(storage.collect_garbage(true).await.unwrap().blobs, 0);
+
+        // Blobs should only be collected once no tool version uses them
+        storage
+            .replace_tool_contents(&new, b"updated binary")
+            .await
+            .unwrap();
+        storage.remove_tool_contents(&old).await.unwrap();
+        let collected = storage.collect_garbage(false).await.unwrap();
+        assert_eq!(collected.blobs, 1);
+        assert_eq!(collected.bytes, 6);
+        assert_eq!(read(storage.tool_path(&new)).unwrap(), b"updated binary");
+        assert_eq!(storage.collect_garbage(false).await.unwrap().blobs, 0);
+    }
+}
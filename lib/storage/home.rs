@@ -1,14 +1,26 @@
-use std::env::var;
+use std::env::{current_dir, var, var_os};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
+use tempfile::TempDir;
 use tokio::fs::create_dir_all;
+use tracing::warn;
 
-use crate::manifests::AuthManifest;
+use crate::discovery::manifest_dir;
+use crate::manifests::{AuthManifest, RokitManifest, ROKIT_MANIFEST_FILE_NAME};
 use crate::result::{RokitError, RokitResult};
-use crate::sources::ArtifactSource;
+use crate::sources::{ArtifactProvider, ArtifactSource};
+use crate::tool::ToolSpec;
+use crate::util::fs::path_exists;
 
-use super::{ToolCache, ToolStorage};
+use super::{migrations, update_check::UpdateCheck, Migration, ToolCache, ToolStorage, ToolUsage};
+
+/*
+    Set when an isolated home has been explicitly requested, instead of
+    using the `ROKIT_ISOLATED` environment variable - see `Home::load_isolated`.
+*/
+static ISOLATED: AtomicBool = AtomicBool::new(false);
 
 /**
     Rokit's home directory - this is where Rokit stores its
@@ -16,54 +28,222 @@ use super::{ToolCache, ToolStorage};
     while still referring to the same underlying data.
 
     By default, this is `$HOME/.rokit`, but can be overridden
-    by setting the `ROKIT_ROOT` environment variable.
+    by setting the `ROKIT_HOME` or `ROKIT_ROOT` environment variable.
+
+    Tool links and Rokit itself are placed in the `bin` directory inside of
+    the home directory, unless overridden by the `ROKIT_BIN_DIR` environment
+    variable, or the `bin-dir` setting in the global manifest.
+
+    In isolated mode, the home directory is instead a `.rokit` directory
+    next to the nearest project manifest - see [`Home::load_isolated`].
+
+    On Windows, Rokit may also be installed for all users, using a system
+    home directory instead - see [`Home::load_system`] for more information.
 */
 #[derive(Debug, Clone)]
 pub struct Home {
     path: Arc<Path>,
+    bin_dir: Arc<Path>,
     tool_storage: ToolStorage,
     tool_cache: ToolCache,
+    temp_dir: Option<Arc<TempDir>>,
 }
 
 impl Home {
     /**
-        Creates a new `Home` from the given path.
+        Creates a new `Home` from the given path, using the given bin directory,
+        or if none was given, the bin directory set in the global manifest.
+
+        Any pending migrations are run for homes created by older versions
+        of Rokit - failing migrations are logged, and may be retried using
+        [`Home::migrate`], but never prevent the home from being loaded.
+        Homes that are created here never need any migrations.
     */
-    async fn load_from_path(path: impl Into<PathBuf>) -> RokitResult<Self> {
+    pub(crate) async fn load_from_path(
+        path: impl Into<PathBuf>,
+        bin_dir: Option<PathBuf>,
+    ) -> RokitResult<Self> {
         let path: Arc<Path> = path.into().into();
 
-        let (tool_storage, tool_cache) =
-            tokio::try_join!(ToolStorage::load(&path), ToolCache::load(&path))?;
+        // NOTE: The global manifest must be read before loading tool storage,
+        // since it may contain a custom bin directory, link strategy, or trust scope
+        create_dir_all(&path).await?;
+        let manifest = RokitManifest::load_or_create(&path).await?;
+        let bin_dir: Arc<Path> = match bin_dir.or_else(|| manifest.get_bin_dir()) {
+            Some(dir) => path.join(dir).into(),
+            None => path.join("bin").into(),
+        };
+
+        // NOTE: Tool storage is created when loading it, so
+        // we must check if this is a new home before doing so
+        let is_new = !path_exists(path.join("tool-storage")).await;
+        let link_strategy = manifest.get_link_strategy().unwrap_or_default();
+        let trust_scope = manifest.get_trust_scope().unwrap_or_default();
+        let (tool_storage, tool_cache) = tokio::try_join!(
+            ToolStorage::load(&path, &bin_dir, link_strategy),
+            ToolCache::load(&path, trust_scope)
+        )?;
+
+        let migrated = if is_new {
+            migrations::mark_all_applied(&path).await
+        } else {
+            migrations::run_pending(&path, &bin_dir).await.map(|_| ())
+        };
+        if let Err(e) = migrated {
+            warn!(
+                "Failed to migrate the Rokit home directory at {path:?}!\
+                \nRun 'rokit doctor --migrate' to try again.\
+                \nError: {e}"
+            );
+        }
 
         Ok(Self {
             path,
+            bin_dir,
             tool_storage,
             tool_cache,
+            temp_dir: None,
         })
     }
 
+    /**
+        Creates a new `Home` in a temporary directory, which is removed
+        once this `Home`, and all clones of it, have been dropped.
+
+        The home is fully initialized, with empty global and auth manifests,
+        tool storage, and a bin directory - and unlike other constructors,
+        no environment variables are used to find or configure it.
+        This is useful for tests, and for tools that need an ephemeral home.
+
+        # Errors
+
+        - If the temporary directory could not be created.
+        - If the home directory could not be initialized.
+    */
+    pub async fn new_temporary() -> RokitResult<Self> {
+        let dir = TempDir::new()?;
+        let mut home = Self::load_from_path(dir.path(), None).await?;
+        AuthManifest::create_if_missing(dir.path()).await?;
+        home.save().await?;
+        home.temp_dir = Some(Arc::new(dir));
+        Ok(home)
+    }
+
     /**
         Creates a new `Home` from the environment.
 
         This will read, and if necessary, create the Rokit home directory
         and its contents - including trust storage, tools storage, etc.
 
-        If the `ROKIT_ROOT` environment variable is set, this will use
-        that as the home directory. Otherwise, it will use `$HOME/.rokit`.
+        If the `ROKIT_HOME` or `ROKIT_ROOT` environment variable is set, this will
+        use that as the home directory. Otherwise, it will use the system home if
+        Rokit is running from its bin directory, or `$HOME/.rokit` if it is not.
+        The bin directory may similarly be set using `ROKIT_BIN_DIR`.
 
         # Errors
 
         - If the home directory could not be read or created.
     */
     pub async fn load_from_env() -> RokitResult<Self> {
-        if let Ok(root_str) = var("ROKIT_ROOT") {
-            Self::load_from_path(root_str).await
+        let path = Self::path_from_env()?;
+        let bin_dir = var_os("ROKIT_BIN_DIR")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from);
+        Self::load_from_path(path, bin_dir).await
+    }
+
+    /**
+        Creates a new isolated `Home`, which keeps all tool storage, caches,
+        and other data inside of the project, separate from any other homes.
+
+        The home directory is a `.rokit` directory next to the nearest Rokit
+        manifest in the current directory or its ancestors, or in the current
+        directory if there is no such manifest. If a manifest directory has been
+        set using [`set_manifest_dir`](crate::discovery::set_manifest_dir),
+        the home directory is always in that directory instead. Global manifests in the user's
+        home directory are ignored while isolated.
+
+        Isolated mode may also be enabled by setting the `ROKIT_ISOLATED`
+        environment variable, which will also apply to [`Home::load_from_env`].
+        Rokit and tool links in the bin directory of an isolated home always
+        use that home, no matter the current directory or environment.
+
+        # Errors
+
+        - If the home directory could not be read or created.
+    */
+    pub async fn load_isolated() -> RokitResult<Self> {
+        ISOLATED.store(true, Ordering::Relaxed);
+        Self::load_from_env().await
+    }
+
+    /**
+        Creates a new `Home` from the system home directory, which is shared
+        by all users on the machine - this is `%ProgramData%\Rokit` on Windows.
+
+        Writing to the system home typically requires elevation, but once
+        Rokit has been installed there, any user may run tools from it -
+        Rokit and tool links in its bin directory use it automatically.
+
+        # Errors
+
+        - If the current system does not support a system home directory.
+        - If the home directory could not be read or created.
+    */
+    pub async fn load_system() -> RokitResult<Self> {
+        let path = Self::system_path().ok_or(RokitError::HomeNotFound)?;
+        Self::load_from_path(path, None).await
+    }
+
+    /**
+        Gets the path to the system home directory, if the
+        current system supports one - see [`Home::load_system`].
+    */
+    #[must_use]
+    pub fn system_path() -> Option<PathBuf> {
+        if cfg!(windows) {
+            var_os("ProgramData")
+                .filter(|dir| !dir.is_empty())
+                .map(|dir| PathBuf::from(dir).join("Rokit"))
         } else {
-            let path = dirs::home_dir()
+            None
+        }
+    }
+
+    /**
+        Checks if Rokit is running in isolated mode.
+
+        See [`Home::load_isolated`] for more information.
+    */
+    #[must_use]
+    pub fn is_isolated() -> bool {
+        ISOLATED.load(Ordering::Relaxed)
+            || var("ROKIT_ISOLATED").is_ok_and(|value| {
+                let value = value.trim();
+                !(value.is_empty() || value == "0" || value.eq_ignore_ascii_case("false"))
+            })
+            || running_isolated_home().is_some()
+    }
+
+    /**
+        Gets the path to the Rokit home directory from the environment,
+        without reading or creating anything in the directory.
+
+        See [`Home::load_from_env`] for more information.
+    */
+    pub(crate) fn path_from_env() -> RokitResult<PathBuf> {
+        if let Some(isolated_home) = running_isolated_home() {
+            Ok(isolated_home)
+        } else if Self::is_isolated() {
+            Ok(isolated_path()?)
+        } else if let Some(root_str) = home_var() {
+            Ok(PathBuf::from(root_str))
+        } else if let Some(system_path) = Self::system_path().filter(|p| is_running_from(p)) {
+            Ok(system_path)
+        } else {
+            Ok(dirs::home_dir()
                 .ok_or(RokitError::HomeNotFound)?
-                .join(".rokit");
-            create_dir_all(&path).await?;
-            Self::load_from_path(path).await
+                .join(".rokit"))
         }
     }
 
@@ -75,6 +255,60 @@ impl Home {
         &self.path
     }
 
+    /**
+        Gets a reference to the bin directory for this `Home`, which
+        contains links to all tools, as well as Rokit itself.
+    */
+    #[must_use]
+    pub fn bin_dir(&self) -> &Path {
+        &self.bin_dir
+    }
+
+    /**
+        Creates a new `Home` using the given bin directory instead,
+        referring to the same underlying data as this `Home`.
+
+        Relative paths are relative to the home directory. Note that this
+        does not persist the bin directory - see [`RokitManifest::set_bin_dir`].
+
+        # Errors
+
+        - If the bin directory could not be created.
+    */
+    pub async fn with_bin_dir(&self, bin_dir: impl AsRef<Path>) -> RokitResult<Self> {
+        let bin_dir: Arc<Path> = self.path.join(bin_dir).into();
+        create_dir_all(&bin_dir).await?;
+        Ok(Self {
+            path: Arc::clone(&self.path),
+            tool_storage: self.tool_storage.with_aliases_dir(Arc::clone(&bin_dir)),
+            tool_cache: self.tool_cache.clone(),
+            bin_dir,
+            temp_dir: self.temp_dir.clone(),
+        })
+    }
+
+    /**
+        Gets all migrations that have not yet been applied to this `Home`.
+
+        Migrations are run automatically when loading a `Home`, so
+        this is typically empty, unless a migration has failed.
+    */
+    pub async fn pending_migrations(&self) -> Vec<Migration> {
+        migrations::pending(&self.path).await
+    }
+
+    /**
+        Runs all migrations that have not yet been applied to this `Home`,
+        and returns the migrations that were run.
+
+        # Errors
+
+        - If any migration failed - migrations before it are still applied.
+    */
+    pub async fn migrate(&self) -> RokitResult<Vec<Migration>> {
+        migrations::run_pending(&self.path, &self.bin_dir).await
+    }
+
     /**
         Returns a reference to the `ToolStorage` for this `Home`.
     */
@@ -91,6 +325,68 @@ impl Home {
         &self.tool_cache
     }
 
+    /**
+        Loads the current `ToolUsage` statistics for this `Home`.
+
+        # Errors
+
+        - If the usage statistics could not be loaded.
+    */
+    pub async fn tool_usage(&self) -> RokitResult<ToolUsage> {
+        ToolUsage::load(&self.path).await
+    }
+
+    /**
+        Records that the given tool was just run.
+
+        This is saved to disk immediately, and does not require
+        a call to [`Home::save`] - it may also safely be called
+        concurrently from several Rokit processes.
+
+        # Errors
+
+        - If the usage statistics could not be saved to disk.
+    */
+    pub async fn record_tool_usage(&self, spec: &ToolSpec) -> RokitResult<()> {
+        ToolUsage::record(&self.path, spec).await
+    }
+
+    /**
+        Removes any recorded usage for the given tools.
+
+        This should be called after uninstalling tools, and is saved
+        to disk immediately, without requiring a call to [`Home::save`].
+
+        # Errors
+
+        - If the usage statistics could not be saved to disk.
+    */
+    pub async fn forget_tool_usage(&self, specs: &[ToolSpec]) -> RokitResult<()> {
+        ToolUsage::forget(&self.path, specs).await
+    }
+
+    /**
+        Checks if a periodic check for updates is due - meaning
+        that no check has been recorded for at least a day.
+    */
+    pub async fn is_update_check_due(&self) -> bool {
+        UpdateCheck::is_due(&self.path).await
+    }
+
+    /**
+        Records that a periodic check for updates was just completed.
+
+        This is saved to disk immediately, and does not
+        require a call to [`Home::save`].
+
+        # Errors
+
+        - If the update check could not be saved to disk.
+    */
+    pub async fn record_update_check(&self) -> RokitResult<()> {
+        UpdateCheck::record(&self.path).await
+    }
+
     /**
         Creates a new `ArtifactSource` for this `Home`.
 
@@ -104,7 +400,11 @@ impl Home {
     */
     pub async fn artifact_source(&self) -> RokitResult<ArtifactSource> {
         let auth = AuthManifest::load_or_create(&self.path).await?;
-        ArtifactSource::new_authenticated(&auth.get_all_tokens())
+        let mut source = ArtifactSource::new_authenticated(&auth.get_all_tokens())?;
+        for provider in ArtifactProvider::ALL {
+            source = source.with_owner_tokens(provider, &auth.get_owner_tokens(provider))?;
+        }
+        Ok(source.with_download_dir(self.path.join("downloads")))
     }
 
     /**
@@ -120,6 +420,69 @@ impl Home {
     }
 }
 
+/*
+    Gets the home directory set using environment variables, if any,
+    preferring `ROKIT_HOME` over the older `ROKIT_ROOT` variable.
+*/
+fn home_var() -> Option<String> {
+    ["ROKIT_HOME", "ROKIT_ROOT"]
+        .into_iter()
+        .find_map(|name| var(name).ok().filter(|value| !value.is_empty()))
+}
+
+/*
+    Checks if the current executable - Rokit itself, or a tool link -
+    is in the default bin directory of the given home directory.
+
+    NOTE: Paths on Windows are case-insensitive, and the
+    casing of environment variables may not match the disk.
+*/
+fn is_running_from(home_path: &Path) -> bool {
+    let bin_dir = home_path.join("bin").to_string_lossy().to_lowercase();
+    std::env::current_exe().is_ok_and(|exe| {
+        exe.parent()
+            .is_some_and(|dir| dir.to_string_lossy().to_lowercase() == bin_dir)
+    })
+}
+
+/*
+    Gets the isolated home directory that the current executable - Rokit
+    itself, or a tool link - is running from, if any, meaning that it is
+    in a `<project>/.rokit/bin` directory, next to a Rokit manifest.
+
+    NOTE: The default home directory is also named `.rokit`, and the user's
+    home directory may contain a global manifest, so it is always excluded.
+*/
+fn running_isolated_home() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    let bin_dir = exe.parent()?;
+    let home_path = bin_dir.parent()?;
+    let project_dir = home_path.parent()?;
+    let is_isolated_home = bin_dir.file_name()? == "bin"
+        && home_path.file_name()? == ".rokit"
+        && project_dir.join(ROKIT_MANIFEST_FILE_NAME).is_file()
+        && dirs::home_dir().is_none_or(|dir| dir.join(".rokit") != home_path);
+    is_isolated_home.then(|| home_path.to_path_buf())
+}
+
+/*
+    Gets the path to the isolated home directory for the current project.
+
+    NOTE: This uses blocking filesystem operations, but only checks if a
+    manifest exists in a few directories, which should always be quick.
+*/
+fn isolated_path() -> RokitResult<PathBuf> {
+    if let Some(dir) = manifest_dir() {
+        return Ok(dir.join(".rokit"));
+    }
+    let cwd = current_dir()?;
+    let project_dir = cwd
+        .ancestors()
+        .find(|dir| dir.join(ROKIT_MANIFEST_FILE_NAME).is_file())
+        .unwrap_or(&cwd);
+    Ok(project_dir.join(".rokit"))
+}
+
 /*
     Implement Drop with an error message if the Home was dropped
     without being saved - this should never happen since a Home
@@ -143,3 +506,36 @@ impl Drop for Home {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn temporary_home_is_initialized_and_removed() {
+        let home = Home::new_temporary().await.unwrap();
+        let path = home.path().to_path_buf();
+        assert!(path.join(ROKIT_MANIFEST_FILE_NAME).exists());
+        assert!(home.bin_dir().is_dir());
+        assert!(home.tool_cache().all_installed().is_empty());
+
+        // NOTE: Clones share the directory, which is only removed once all are dropped
+        let clone = home.clone();
+        drop(home);
+        assert!(path.exists());
+        drop(clone);
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn loading_home_never_reads_auth_manifest() {
+        let home = Home::new_temporary().await.unwrap();
+
+        // NOTE: Reading this manifest would fail, as would querying the
+        // keychain in most test environments, so neither must happen here
+        let auth_path = home.path().join("auth.toml");
+        std::fs::write(&auth_path, "storage = \"keychain\"\n[github").unwrap();
+        Home::load_from_path(home.path(), None).await.unwrap();
+        assert!(home.artifact_source().await.is_err());
+    }
+}
@@ -0,0 +1,478 @@
+use std::{
+    collections::BTreeSet,
+    fmt,
+    io::Result as IoResult,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use same_file::is_same_file;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    fs::{read_dir, read_link, read_to_string, remove_file, rename, symlink_metadata},
+    task::spawn_blocking,
+};
+use tracing::trace;
+
+use crate::{
+    result::RokitResult,
+    util::fs::{path_exists, write_atomic},
+};
+
+const CARGO_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/**
+    Name of the sidecar file, stored in the bin directory, that
+    records how links to the Rokit binary were last created.
+*/
+pub(super) const LINK_SIDECAR_FILE_NAME: &str = ".rokit-links.json";
+
+/**
+    Name of the file, stored in the bin directory, that records links
+    which could not be updated, and should be retried by the next Rokit
+    invocation - such as links that were in use on Windows.
+*/
+pub(super) const PENDING_LINKS_FILE_NAME: &str = ".rokit-pending-links.json";
+
+/**
+    Prefix for temporary files created next to links, which
+    are renamed over the links once they have been created.
+*/
+pub(super) const LINK_TEMP_PREFIX: &str = ".rokit-";
+
+/**
+    Prefix for old links that were moved out of the way, since they could
+    not be replaced directly, and that should be removed once unused.
+*/
+const LINK_JUNK_PREFIX: &str = ".rokit-old-";
+
+/**
+    Whether existing links should be moved out of the way before being replaced.
+
+    Windows does not allow replacing or removing executables that are currently
+    running, but it does allow renaming them, so this lets us update links
+    that are in use, such as when a tool is running during `self-update`.
+*/
+pub(super) const MOVE_LINKS_ASIDE: bool = cfg!(windows);
+
+/**
+    How links for tool aliases are created in the bin directory:
+
+    - `copy` - a full copy of the Rokit binary, with metadata appended to it.
+    - `hardlink` - a hardlink to the Rokit binary, which uses no extra disk space.
+    - `symlink` - a symbolic link to the Rokit binary.
+
+    Links that can not be created using the desired strategy, such as hardlinks
+    on filesystems without support for them, or symlinks on Windows without the
+    necessary privileges, fall back to hardlinks and then copies.
+
+    The default is [`LinkStrategy::Hardlink`].
+*/
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkStrategy {
+    Copy,
+    #[default]
+    Hardlink,
+    Symlink,
+}
+
+impl LinkStrategy {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Copy => "copy",
+            Self::Hardlink => "hardlink",
+            Self::Symlink => "symlink",
+        }
+    }
+
+    /**
+        Returns the strategies to try, in order, when creating links using this strategy.
+    */
+    pub(super) fn with_fallbacks(self) -> &'static [LinkStrategy] {
+        match self {
+            Self::Copy => &[Self::Copy],
+            Self::Hardlink => &[Self::Hardlink, Self::Copy],
+            Self::Symlink => &[Self::Symlink, Self::Hardlink, Self::Copy],
+        }
+    }
+}
+
+impl FromStr for LinkStrategy {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let l = s.trim().to_lowercase();
+        match l.as_str() {
+            "copy" => Ok(Self::Copy),
+            "hardlink" => Ok(Self::Hardlink),
+            "symlink" => Ok(Self::Symlink),
+            _ => Err(format!("unknown link strategy '{l}'")),
+        }
+    }
+}
+
+impl fmt::Display for LinkStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+/**
+    Metadata for links that share the Rokit binary, which can not have
+    metadata appended to them like copies can, stored in a sidecar file.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) struct LinkSidecar {
+    version: String,
+    strategy: LinkStrategy,
+}
+
+impl LinkSidecar {
+    pub(super) fn current(strategy: LinkStrategy) -> Self {
+        Self {
+            version: CARGO_VERSION.to_string(),
+            strategy,
+        }
+    }
+
+    pub(super) async fn load(dir: &Path) -> Option<Self> {
+        let contents = read_to_string(dir.join(LINK_SIDECAR_FILE_NAME))
+            .await
+            .ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    pub(super) async fn save(&self, dir: &Path) -> RokitResult<()> {
+        let contents = serde_json::to_vec_pretty(self)?;
+        write_atomic(dir.join(LINK_SIDECAR_FILE_NAME), contents).await
+    }
+}
+
+/**
+    File names of links that could not be updated, and that
+    should be retried by the next Rokit invocation.
+*/
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) struct PendingLinks {
+    links: BTreeSet<String>,
+}
+
+impl PendingLinks {
+    pub(super) async fn load(dir: &Path) -> Self {
+        let Ok(contents) = read_to_string(dir.join(PENDING_LINKS_FILE_NAME)).await else {
+            return Self::default();
+        };
+        serde_json::from_str(&contents).unwrap_or_default()
+    }
+
+    pub(super) async fn exists(dir: &Path) -> bool {
+        path_exists(dir.join(PENDING_LINKS_FILE_NAME)).await
+    }
+
+    /**
+        Saves the pending links, or removes the
+        file for them if there are none left.
+    */
+    pub(super) async fn save(&self, dir: &Path) -> RokitResult<()> {
+        let path = dir.join(PENDING_LINKS_FILE_NAME);
+        if self.links.is_empty() {
+            if path_exists(&path).await {
+                remove_file(&path).await?;
+            }
+            return Ok(());
+        }
+        let contents = serde_json::to_vec_pretty(self)?;
+        write_atomic(path, contents).await
+    }
+
+    pub(super) fn insert(&mut self, link_path: &Path) {
+        if let Some(name) = link_path.file_name().and_then(|n| n.to_str()) {
+            self.links.insert(name.to_string());
+        }
+    }
+
+    pub(super) fn paths(&self, dir: &Path) -> Vec<PathBuf> {
+        self.links.iter().map(|name| dir.join(name)).collect()
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.links.len()
+    }
+}
+
+/**
+    Moves the existing link at the given path out of the way,
+    so that a new link can be written in its place.
+
+    Returns the path the link was moved to, or `None` if there was no existing link.
+*/
+pub(super) async fn move_link_aside(link_path: &Path) -> IoResult<Option<PathBuf>> {
+    if symlink_metadata(link_path).await.is_err() {
+        return Ok(None);
+    }
+    let file_name = link_path.file_name().unwrap_or_default().to_string_lossy();
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let junk_path = link_path.with_file_name(format!("{LINK_JUNK_PREFIX}{nanos}-{file_name}"));
+    trace!(?link_path, ?junk_path, "moving existing link aside");
+    rename(link_path, &junk_path).await?;
+    Ok(Some(junk_path))
+}
+
+/**
+    Removes any old links in the given directory that were previously moved
+    out of the way - links still in use can not be removed, and are skipped.
+*/
+pub(super) async fn remove_junk_links(dir: &Path) {
+    let Ok(mut reader) = read_dir(dir).await else {
+        return;
+    };
+    while let Ok(Some(entry)) = reader.next_entry().await {
+        let is_junk = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| name.starts_with(LINK_JUNK_PREFIX));
+        if is_junk {
+            match remove_file(entry.path()).await {
+                Ok(()) => trace!(path = ?entry.path(), "removed old link"),
+                Err(e) => trace!(path = ?entry.path(), "old link is still in use: {e}"),
+            }
+        }
+    }
+}
+
+/**
+    Finds existing links next to the given link path whose file names only differ
+    from it in casing, such as `StyLua` for `stylua`, which were created for
+    aliases with different casing - returns these links, and whether a link
+    with the exact file name also exists.
+
+    On case-insensitive filesystems, such links are the same file as the given
+    path, while on case-sensitive filesystems, they are separate, stale links.
+*/
+pub(super) async fn find_case_mismatched_links(link_path: &Path) -> (Vec<PathBuf>, bool) {
+    let (Some(dir), Some(file_name)) = (link_path.parent(), link_path.file_name()) else {
+        return (Vec::new(), false);
+    };
+    let Ok(mut reader) = read_dir(dir).await else {
+        return (Vec::new(), false);
+    };
+
+    let mut mismatched = Vec::new();
+    let mut has_exact = false;
+    while let Ok(Some(entry)) = reader.next_entry().await {
+        let name = entry.file_name();
+        if name == file_name {
+            has_exact = true;
+        } else if name.eq_ignore_ascii_case(file_name) {
+            mismatched.push(entry.path());
+        }
+    }
+    (mismatched, has_exact)
+}
+
+/**
+    Makes sure that the link at the given path uses the exact casing of its
+    file name, renaming any existing link that only differs in casing, or
+    removing it if a link with the exact file name already exists.
+
+    Links that are in use and can not be removed are moved out of the way instead.
+*/
+pub(super) async fn fix_link_casing(link_path: &Path) -> IoResult<()> {
+    let (mismatched, mut has_exact) = find_case_mismatched_links(link_path).await;
+    for path in mismatched {
+        if has_exact {
+            trace!(?path, "removing link with mismatched casing");
+            if remove_file(&path).await.is_err() {
+                move_link_aside(&path).await?;
+            }
+        } else {
+            // NOTE: Some filesystems ignore renames that only change the
+            // casing of a file name, so we rename through a temporary file
+            trace!(?path, ?link_path, "renaming link with mismatched casing");
+            let temp_path = temp_link_path(link_path);
+            rename(&path, &temp_path).await?;
+            rename(&temp_path, link_path).await?;
+            has_exact = true;
+        }
+    }
+    Ok(())
+}
+
+/**
+    Checks if the link at the given path shares the Rokit binary at the given
+    path, either as a symlink pointing to it, or as a hardlink to the same file.
+*/
+pub(super) async fn is_shared_link(link_path: &Path, rokit_path: &Path) -> bool {
+    let Ok(link_meta) = symlink_metadata(link_path).await else {
+        return false;
+    };
+    if link_meta.is_symlink() {
+        return read_link(link_path)
+            .await
+            .is_ok_and(|target| target == rokit_path);
+    }
+
+    // NOTE: Hardlinks are compared by their file identity, which is the
+    // device and inode on Unix, and the volume and file index on Windows
+    let link_path = link_path.to_path_buf();
+    let rokit_path = rokit_path.to_path_buf();
+    spawn_blocking(move || is_same_file(link_path, rokit_path).unwrap_or_default())
+        .await
+        .expect("blocking link task panicked unexpectedly")
+}
+
+/**
+    Creates a link at the given path that shares the Rokit binary at the given
+    path, using the given strategy, which must not be [`LinkStrategy::Copy`].
+
+    Any existing file at the link path is atomically replaced.
+*/
+pub(super) async fn write_shared_link(
+    strategy: LinkStrategy,
+    link_path: &Path,
+    rokit_path: &Path,
+) -> IoResult<()> {
+    let link_path = link_path.to_path_buf();
+    let rokit_path = rokit_path.to_path_buf();
+    spawn_blocking(move || {
+        let temp_path = temp_link_path(&link_path);
+        let _ = std::fs::remove_file(&temp_path);
+        match strategy {
+            LinkStrategy::Copy => unreachable!("copies are not shared links"),
+            LinkStrategy::Hardlink => std::fs::hard_link(&rokit_path, &temp_path)?,
+            LinkStrategy::Symlink => create_symlink(&rokit_path, &temp_path)?,
+        }
+        std::fs::rename(&temp_path, &link_path).inspect_err(|_| {
+            let _ = std::fs::remove_file(&temp_path);
+        })
+    })
+    .await
+    .expect("blocking link task panicked unexpectedly")
+}
+
+fn temp_link_path(link_path: &Path) -> PathBuf {
+    let file_name = link_path.file_name().unwrap_or_default().to_string_lossy();
+    link_path.with_file_name(format!("{LINK_TEMP_PREFIX}{file_name}.tmp"))
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &Path, link: &Path) -> IoResult<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn create_symlink(target: &Path, link: &Path) -> IoResult<()> {
+    std::os::windows::fs::symlink_file(target, link)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn create_symlink(_target: &Path, _link: &Path) -> IoResult<()> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_link_strategy() {
+        assert_eq!("copy".parse(), Ok(LinkStrategy::Copy));
+        assert_eq!(" Hardlink ".parse(), Ok(LinkStrategy::Hardlink));
+        assert_eq!("SYMLINK".parse(), Ok(LinkStrategy::Symlink));
+        assert!("junction".parse::<LinkStrategy>().is_err());
+    }
+
+    #[tokio::test]
+    async fn pending_links_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!PendingLinks::exists(dir.path()).await);
+
+        let mut pending = PendingLinks::default();
+        pending.insert(&dir.path().join("rojo"));
+        pending.save(dir.path()).await.unwrap();
+        assert!(PendingLinks::exists(dir.path()).await);
+
+        let loaded = PendingLinks::load(dir.path()).await;
+        assert_eq!(loaded, pending);
+        assert_eq!(loaded.paths(dir.path()), vec![dir.path().join("rojo")]);
+
+        // Saving without any pending links removes the file
+        PendingLinks::default().save(dir.path()).await.unwrap();
+        assert!(!PendingLinks::exists(dir.path()).await);
+    }
+
+    #[tokio::test]
+    async fn shared_links_are_identified_by_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let rokit = dir.path().join("rokit");
+        let hardlink = dir.path().join("rojo");
+        let copy = dir.path().join("lune");
+        std::fs::write(&rokit, b"rokit").unwrap();
+        std::fs::hard_link(&rokit, &hardlink).unwrap();
+        std::fs::copy(&rokit, &copy).unwrap();
+
+        // Copies with identical contents are not shared links
+        assert!(is_shared_link(&hardlink, &rokit).await);
+        assert!(!is_shared_link(&copy, &rokit).await);
+        assert!(!is_shared_link(&dir.path().join("missing"), &rokit).await);
+    }
+
+    #[tokio::test]
+    async fn move_link_aside_and_remove_junk() {
+        let dir = tempfile::tempdir().unwrap();
+        let link = dir.path().join("rojo");
+        std::fs::write(&link, b"old").unwrap();
+
+        let junk = move_link_aside(&link).await.unwrap().unwrap();
+        assert!(!link.exists());
+        assert_eq!(std::fs::read(&junk).unwrap(), b"old");
+
+        // Moving a link that does not exist does nothing
+        assert_eq!(move_link_aside(&link).await.unwrap(), None);
+
+        remove_junk_links(dir.path()).await;
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn fix_link_casing_renames_and_removes() {
+        let dir = tempfile::tempdir().unwrap();
+        let link = dir.path().join("stylua");
+        std::fs::write(dir.path().join("StyLua"), b"old").unwrap();
+
+        fix_link_casing(&link).await.unwrap();
+        let names = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["stylua"]);
+        assert_eq!(std::fs::read(&link).unwrap(), b"old");
+
+        // Stale links next to a link with the exact name are removed,
+        // which is only possible on case-sensitive filesystems
+        std::fs::write(dir.path().join("StyLua"), b"stale").unwrap();
+        if std::fs::read(&link).unwrap() == b"old" {
+            fix_link_casing(&link).await.unwrap();
+            assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+            assert_eq!(std::fs::read(&link).unwrap(), b"old");
+        }
+    }
+
+    #[test]
+    fn fallbacks_end_with_copy() {
+        for strategy in [
+            LinkStrategy::Copy,
+            LinkStrategy::Hardlink,
+            LinkStrategy::Symlink,
+        ] {
+            let fallbacks = strategy.with_fallbacks();
+            assert_eq!(fallbacks.first(), Some(&strategy));
+            assert_eq!(fallbacks.last(), Some(&LinkStrategy::Copy));
+        }
+    }
+}
@@ -1,12 +1,57 @@
+use std::path::{Path, PathBuf};
+
 use serde::{Deserialize, Serialize};
+use tokio::fs::read;
 
-use crate::result::RokitResult;
+use crate::{result::RokitResult, util::fs::write_atomic};
 
 const ROKIT_META_TRAILER: [u8; 10] = *b"ROKIT_LINK";
 const ROKIT_META_VERSION: u16 = 1;
 
 const CARGO_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/**
+    The current version of the Rokit home directory format.
+
+    This must be bumped together with adding a migration, whenever the format
+    of any stored data changes in a way that older homes need to be upgraded.
+*/
+pub(crate) const HOME_VERSION: u32 = 1;
+
+/**
+    Metadata for a Rokit home directory - currently only the version of its format.
+
+    Homes created before this metadata existed have no metadata file,
+    and are treated as version `0`, which all migrations apply to.
+*/
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct HomeMetadata {
+    #[serde(default)]
+    pub(crate) version: u32,
+}
+
+impl HomeMetadata {
+    fn path(home_path: impl AsRef<Path>) -> PathBuf {
+        home_path.as_ref().join("tool-storage").join("home.json")
+    }
+
+    /**
+        Loads the metadata for the given home directory,
+        defaulting to version `0` if it could not be read.
+    */
+    pub(crate) async fn load(home_path: impl AsRef<Path>) -> Self {
+        let Ok(contents) = read(Self::path(home_path)).await else {
+            return Self::default();
+        };
+        serde_json::from_slice(&contents).unwrap_or_default()
+    }
+
+    pub(crate) async fn save(&self, home_path: impl AsRef<Path>) -> RokitResult<()> {
+        let contents = serde_json::to_vec_pretty(self)?;
+        write_atomic(Self::path(home_path), contents).await
+    }
+}
+
 // FUTURE: We could probably accept impl Read + Seek / impl Write instead
 // of [u8] for the metadata functions here to make things faster. For now
 // it is fine because the Rokit links are pretty small (just a few MB).
@@ -0,0 +1,257 @@
+use std::{
+    env::consts::EXE_EXTENSION,
+    path::{Path, PathBuf},
+};
+
+use tokio::fs::{read, read_dir, remove_file, rename};
+use tracing::{debug, trace};
+
+use crate::{
+    result::RokitResult,
+    util::{fs::path_exists, lock::FileLock},
+};
+
+use super::{
+    links::is_shared_link,
+    metadata::{HomeMetadata, RokitLinkMetadata, HOME_VERSION},
+    STORAGE_LOCK_FILE_NAME,
+};
+
+/**
+    A migration that upgrades a Rokit home directory from
+    an older format, to the given version of the format.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+}
+
+/*
+    All known migrations, in order - every migration must have a version
+    one higher than the previous one, with the last one being `HOME_VERSION`.
+*/
+const MIGRATIONS: &[Migration] = &[Migration {
+    version: 1,
+    description: "Add missing executable extensions to tool links",
+}];
+
+async fn run_migration(version: u32, bin_dir: &Path) -> RokitResult<()> {
+    match version {
+        1 => migrate_link_exe_extensions(bin_dir, EXE_EXTENSION).await,
+        _ => unreachable!("unknown home migration version {version}"),
+    }
+}
+
+/**
+    Gets all migrations that have not yet been applied to the given home directory.
+*/
+pub(super) async fn pending(home_path: &Path) -> Vec<Migration> {
+    let current = HomeMetadata::load(home_path).await.version;
+    if current > HOME_VERSION {
+        debug!(
+            current,
+            supported = HOME_VERSION,
+            "home was used by a newer version of Rokit"
+        );
+    }
+    MIGRATIONS
+        .iter()
+        .filter(|migration| migration.version > current)
+        .copied()
+        .collect()
+}
+
+/**
+    Marks all migrations as applied to the given home directory, without
+    running them - this must only be used for newly created homes.
+*/
+pub(super) async fn mark_all_applied(home_path: &Path) -> RokitResult<()> {
+    let meta = HomeMetadata {
+        version: HOME_VERSION,
+    };
+    meta.save(home_path).await
+}
+
+/**
+    Runs all migrations that have not yet been applied to the given
+    home directory, in order, and returns the migrations that were run.
+
+    The version of the home directory is saved after every migration,
+    so that migrations that succeeded are never run again, even if
+    a later migration fails.
+*/
+pub(super) async fn run_pending(home_path: &Path, bin_dir: &Path) -> RokitResult<Vec<Migration>> {
+    // NOTE: Check without locking first, since
+    // this runs every time a home is loaded
+    if pending(home_path).await.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Another process may have migrated the home while we were waiting
+    // for the lock, so the pending migrations must be checked again
+    let _lock =
+        FileLock::acquire(home_path.join("tool-storage").join(STORAGE_LOCK_FILE_NAME)).await?;
+    let mut meta = HomeMetadata::load(home_path).await;
+
+    let mut applied = Vec::new();
+    for migration in pending(home_path).await {
+        debug!(
+            version = migration.version,
+            "migrating home: {}", migration.description
+        );
+        run_migration(migration.version, bin_dir).await?;
+        meta.version = migration.version;
+        meta.save(home_path).await?;
+        applied.push(migration);
+    }
+
+    Ok(applied)
+}
+
+/*
+    Migration for links created by older versions of Rokit, which did not add
+    executable extensions to links on Windows - links without the extension are
+    renamed to have it, or removed if a link with the extension already exists.
+
+    The bin directory may contain other files, so only files that are verified
+    to be Rokit links are touched - copies of Rokit with link metadata, or files
+    with the same contents as, or sharing the same file as, the Rokit binary.
+*/
+async fn migrate_link_exe_extensions(bin_dir: &Path, exe_extension: &str) -> RokitResult<()> {
+    if exe_extension.is_empty() || !path_exists(bin_dir).await {
+        return Ok(());
+    }
+
+    let rokit_path = append_extension(&bin_dir.join("rokit"), exe_extension);
+    let rokit_contents = read(&rokit_path).await.ok();
+
+    let mut missing_extension = Vec::new();
+    let mut reader = read_dir(bin_dir).await?;
+    while let Some(entry) = reader.next_entry().await? {
+        let path = entry.path();
+        let is_hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if is_hidden || !entry.file_type().await?.is_file() || path.extension().is_some() {
+            continue;
+        }
+        if is_rokit_link(&path, &rokit_path, rokit_contents.as_deref()).await {
+            missing_extension.push(path);
+        } else {
+            trace!(?path, "skipping file that is not a link");
+        }
+    }
+
+    for path in missing_extension {
+        let with_extension = append_extension(&path, exe_extension);
+        if path_exists(&with_extension).await {
+            trace!(?path, "removing link without executable extension");
+            remove_file(&path).await?;
+        } else {
+            trace!(?path, "adding executable extension to link");
+            rename(&path, &with_extension).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn is_rokit_link(path: &Path, rokit_path: &Path, rokit_contents: Option<&[u8]>) -> bool {
+    let Ok(contents) = read(path).await else {
+        return false;
+    };
+    RokitLinkMetadata::parse_from(&contents).is_some()
+        || rokit_contents.is_some_and(|rokit| rokit == contents)
+        || is_shared_link(path, rokit_path).await
+}
+
+fn append_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut path = path.to_path_buf();
+    path.set_extension(extension);
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{read, read_dir, write};
+
+    use super::*;
+
+    #[test]
+    fn migrations_are_in_order() {
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version as usize, index + 1);
+        }
+        assert_eq!(MIGRATIONS.last().map(|m| m.version), Some(HOME_VERSION));
+    }
+
+    #[tokio::test]
+    async fn adds_exe_extensions_to_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let old_link = RokitLinkMetadata::current()
+            .append_to(b"old rokit")
+            .unwrap();
+        write(dir.path().join("rokit.exe"), b"rokit").unwrap();
+        write(dir.path().join("rojo"), &old_link).unwrap();
+        write(dir.path().join("lune"), b"rokit").unwrap();
+        write(dir.path().join("lune.exe"), b"new lune").unwrap();
+        std::fs::hard_link(dir.path().join("rokit.exe"), dir.path().join("selene")).unwrap();
+        write(dir.path().join(".rokit-links.json"), b"{}").unwrap();
+
+        // Files that are not Rokit links must never be touched
+        write(dir.path().join("my-script"), b"#!/bin/sh").unwrap();
+
+        migrate_link_exe_extensions(dir.path(), "exe")
+            .await
+            .unwrap();
+
+        let mut names = read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                ".rokit-links.json",
+                "lune.exe",
+                "my-script",
+                "rojo.exe",
+                "rokit.exe",
+                "selene.exe"
+            ]
+        );
+        assert_eq!(read(dir.path().join("rojo.exe")).unwrap(), old_link);
+        assert_eq!(read(dir.path().join("lune.exe")).unwrap(), b"new lune");
+        assert_eq!(read(dir.path().join("selene.exe")).unwrap(), b"rokit");
+    }
+
+    #[tokio::test]
+    async fn exe_extensions_are_not_added_without_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path().join("rojo"), b"rojo").unwrap();
+
+        migrate_link_exe_extensions(dir.path(), "").await.unwrap();
+        assert!(dir.path().join("rojo").exists());
+    }
+
+    #[tokio::test]
+    async fn runs_pending_migrations_once() {
+        let home = tempfile::tempdir().unwrap();
+        let bin_dir = home.path().join("bin");
+
+        assert_eq!(pending(home.path()).await, MIGRATIONS);
+        let applied = run_pending(home.path(), &bin_dir).await.unwrap();
+        assert_eq!(applied, MIGRATIONS);
+        assert_eq!(HomeMetadata::load(home.path()).await.version, HOME_VERSION);
+
+        assert!(pending(home.path()).await.is_empty());
+        assert!(run_pending(home.path(), &bin_dir).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn new_homes_need_no_migrations() {
+        let home = crate::storage::Home::new_temporary().await.unwrap();
+        assert!(pending(home.path()).await.is_empty());
+        assert_eq!(HomeMetadata::load(home.path()).await.version, HOME_VERSION);
+    }
+}
@@ -1,8 +1,28 @@
+mod blobs;
 mod home;
+mod links;
 mod metadata;
+mod migrations;
+mod resolved_versions;
 mod tool_cache;
+mod tool_metadata;
 mod tool_storage;
+mod tool_usage;
+mod update_check;
 
+pub use self::blobs::CollectedGarbage;
 pub use self::home::Home;
-pub use self::tool_cache::ToolCache;
+pub use self::links::LinkStrategy;
+pub use self::migrations::Migration;
+pub(crate) use self::resolved_versions::ResolvedVersions;
+pub use self::tool_cache::{ToolCache, TrustScope};
+pub use self::tool_metadata::InstalledToolMetadata;
 pub use self::tool_storage::ToolStorage;
+pub use self::tool_usage::ToolUsage;
+
+/**
+    Name of the lock file, stored in the tool storage directory, that must be
+    held while writing to the tool cache, tool binaries, or links - this
+    lets several Rokit processes safely share the same home directory.
+*/
+const STORAGE_LOCK_FILE_NAME: &str = "storage.lock";
@@ -0,0 +1,117 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use tokio::{fs::read, time::Instant};
+use tracing::{instrument, trace};
+
+use crate::{
+    result::RokitResult,
+    tool::ToolId,
+    util::{fs::write_atomic, lock::FileLock},
+};
+
+use super::STORAGE_LOCK_FILE_NAME;
+
+/**
+    Versions that tool version requirements, such as the ones in Foreman
+    manifests, were last resolved to by querying the tool provider.
+
+    This lets the runner use a resolved version without making any
+    network requests, and is only updated when installing tools.
+*/
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub(crate) struct ResolvedVersions {
+    #[serde(default)]
+    resolved: BTreeMap<String, Version>,
+}
+
+impl ResolvedVersions {
+    fn path(home_path: impl AsRef<Path>) -> PathBuf {
+        home_path
+            .as_ref()
+            .join("tool-storage")
+            .join("resolved-versions.json")
+    }
+
+    fn key(id: &ToolId, req: &VersionReq) -> String {
+        format!("{id}@{req}")
+    }
+
+    /**
+        Gets the version that the given requirement was last resolved to, if any.
+    */
+    pub(crate) fn get(&self, id: &ToolId, req: &VersionReq) -> Option<&Version> {
+        self.resolved.get(&Self::key(id, req))
+    }
+
+    #[instrument(skip(home_path), level = "trace")]
+    pub(crate) async fn load(home_path: impl AsRef<Path>) -> Self {
+        let start = Instant::now();
+        let path = Self::path(home_path);
+        let this = load_impl(&path).await;
+        trace!(?path, elapsed = ?start.elapsed(), "Loaded resolved versions");
+        this
+    }
+
+    /**
+        Records the versions that the given requirements were resolved to.
+
+        The file is re-read while holding the storage lock, so that
+        concurrent installs never overwrite each other's resolutions.
+    */
+    #[instrument(skip(home_path, resolved), level = "trace")]
+    pub(crate) async fn record(
+        home_path: impl AsRef<Path>,
+        resolved: &[(ToolId, VersionReq, Version)],
+    ) -> RokitResult<()> {
+        let path = Self::path(home_path);
+        let _lock = FileLock::acquire(path.with_file_name(STORAGE_LOCK_FILE_NAME)).await?;
+
+        let mut this = load_impl(&path).await;
+        for (id, req, version) in resolved {
+            this.resolved.insert(Self::key(id, req), version.clone());
+        }
+        write_atomic(&path, serde_json::to_vec(&this)?).await?;
+
+        trace!(?path, "Recorded resolved versions");
+        Ok(())
+    }
+}
+
+async fn load_impl(path: &Path) -> ResolvedVersions {
+    // NOTE: A missing or unreadable file simply means that nothing has
+    // been resolved yet, and requirements use their minimum versions
+    match read(path).await {
+        Ok(contents) => serde_json::from_slice(&contents).unwrap_or_default(),
+        Err(_) => ResolvedVersions::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn record_and_get() {
+        let dir = tempfile::tempdir().unwrap();
+        let id: ToolId = "rojo-rbx/rojo".parse().unwrap();
+        let req: VersionReq = "~7.3".parse().unwrap();
+        let other_req: VersionReq = "^7".parse().unwrap();
+
+        let resolved = ResolvedVersions::load(dir.path()).await;
+        assert_eq!(resolved.get(&id, &req), None);
+
+        let version = Version::new(7, 3, 5);
+        ResolvedVersions::record(dir.path(), &[(id.clone(), req.clone(), version.clone())])
+            .await
+            .unwrap();
+
+        let resolved = ResolvedVersions::load(dir.path()).await;
+        assert_eq!(resolved.get(&id, &req), Some(&version));
+        assert_eq!(resolved.get(&id, &other_req), None);
+    }
+}
@@ -2,16 +2,18 @@
 #![allow(clippy::inherent_to_string)]
 
 use std::{
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
+    fmt,
     path::{Path, PathBuf},
+    str::FromStr,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
 };
 
-use dashmap::DashSet;
-use semver::Version;
+use dashmap::{DashMap, DashSet};
+use semver::{Version, VersionReq};
 use serde::Deserialize;
 use tokio::{fs::create_dir_all, task::spawn_blocking, time::Instant};
 use tracing::{instrument, trace};
@@ -19,18 +21,77 @@ use tracing::{instrument, trace};
 use crate::{
     result::RokitResult,
     tool::{ToolId, ToolSpec},
+    util::{fs::write_atomic, lock::FileLock},
 };
 
+use super::STORAGE_LOCK_FILE_NAME;
+
+/**
+    How trust is recorded when the user trusts a tool, such as when prompted:
+
+    - `tool` - the tool is trusted for all of its versions.
+    - `major` - the tool is trusted only for versions compatible with the trusted
+      version, such as `^1` for `1.4.2`, or `^0.27` for `0.27.1` - the user is
+      asked again for versions outside of that range, to protect against
+      compromised new major releases.
+
+    Tools may also be trusted for specific versions or version ranges directly,
+    regardless of the scope, using [`ToolCache::add_trust_versions`].
+
+    The default is [`TrustScope::Tool`].
+*/
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrustScope {
+    #[default]
+    Tool,
+    Major,
+}
+
+impl TrustScope {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Tool => "tool",
+            Self::Major => "major",
+        }
+    }
+}
+
+impl FromStr for TrustScope {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let l = s.trim().to_lowercase();
+        match l.as_str() {
+            "tool" => Ok(Self::Tool),
+            "major" => Ok(Self::Major),
+            _ => Err(format!("unknown trust scope '{l}'")),
+        }
+    }
+}
+
+impl fmt::Display for TrustScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
 /**
     Cache for trusted tool identifiers and installed tool specifications.
 
+    Tools may be trusted entirely, or only for specific versions or version
+    ranges - see [`TrustScope`] for how trust is recorded when trusting tools.
+
     Can be cheaply cloned while still referring to the same underlying data.
 */
 #[derive(Debug, Default, Clone, Deserialize)]
 pub struct ToolCache {
     trusted: Arc<DashSet<ToolId>>,
+    #[serde(default)]
+    trusted_versions: Arc<DashMap<ToolId, Vec<VersionReq>>>,
     installed: Arc<DashSet<ToolSpec>>,
     #[serde(default, skip)]
+    trust_scope: TrustScope,
+    #[serde(default, skip)]
     needs_saving: Arc<AtomicBool>,
 }
 
@@ -55,24 +116,104 @@ impl ToolCache {
     }
 
     /**
-        Remove trust for a tool from this `ToolCache`.
+        Add trust for only the versions of a tool matching
+        the given version requirement to this `ToolCache`.
+
+        Returns `true` if the requirement was added and not already trusted.
+    */
+    #[must_use]
+    pub fn add_trust_versions(&self, tool: ToolId, versions: VersionReq) -> bool {
+        self.needs_saving.store(true, Ordering::SeqCst);
+        let mut trusted = self.trusted_versions.entry(tool).or_default();
+        if trusted.contains(&versions) {
+            false
+        } else {
+            trusted.push(versions);
+            true
+        }
+    }
+
+    /**
+        Add trust for the given tool specification to this `ToolCache`,
+        recorded using the trust scope of this cache - see [`TrustScope`].
+
+        Returns `true` if trust was added and not already recorded.
+    */
+    #[must_use]
+    pub fn add_trust_spec(&self, spec: &ToolSpec) -> bool {
+        match self.trust_scope {
+            TrustScope::Tool => self.add_trust(spec.id().clone()),
+            TrustScope::Major => {
+                self.add_trust_versions(spec.id().clone(), compatible_versions(spec.version()))
+            }
+        }
+    }
+
+    /**
+        Remove trust for a tool from this `ToolCache`,
+        including trust for any of its versions.
 
         Returns `true` if the tool was previously trusted and has now been removed.
     */
     #[must_use]
     pub fn remove_trust(&self, tool: &ToolId) -> bool {
         self.needs_saving.store(true, Ordering::SeqCst);
-        self.trusted.remove(tool).is_some()
+        let removed_versions = self.trusted_versions.remove(tool).is_some();
+        self.trusted.remove(tool).is_some() || removed_versions
     }
 
     /**
-        Check if a tool is trusted by this `ToolCache`.
+        Check if a tool is trusted by this `ToolCache`, for all of its versions.
+
+        Use [`ToolCache::is_trusted_spec`] to also check
+        trust for only specific versions of the tool.
     */
     #[must_use]
     pub fn is_trusted(&self, tool: &ToolId) -> bool {
         self.trusted.contains(tool)
     }
 
+    /**
+        Check if a specific version of a tool is trusted by this `ToolCache`,
+        either since the tool is trusted entirely, or for that version.
+
+        Prereleases are trusted if their release version would be trusted.
+    */
+    #[must_use]
+    pub fn is_trusted_spec(&self, spec: &ToolSpec) -> bool {
+        if self.is_trusted(spec.id()) {
+            return true;
+        }
+        let Some(trusted) = self.trusted_versions.get(spec.id()) else {
+            return false;
+        };
+        let version = spec.version();
+        let release = Version::new(version.major, version.minor, version.patch);
+        trusted
+            .iter()
+            .any(|req| req.matches(version) || req.matches(&release))
+    }
+
+    /**
+        Get the version requirements that a tool is trusted for, if the
+        tool is only trusted for some of its versions, in the order added.
+    */
+    #[must_use]
+    pub fn trusted_versions(&self, tool: &ToolId) -> Vec<VersionReq> {
+        self.trusted_versions
+            .get(tool)
+            .map(|trusted| trusted.clone())
+            .unwrap_or_default()
+    }
+
+    /**
+        Get the scope that trust is recorded with when trusting tools.
+    */
+    #[must_use]
+    pub fn trust_scope(&self) -> TrustScope {
+        self.trust_scope
+    }
+
     /**
         Get a sorted copy of the trusted tools in this `ToolCache`.
     */
@@ -167,10 +308,14 @@ impl ToolCache {
     }
 
     #[instrument(skip(home_path), level = "trace")]
-    pub(crate) async fn load(home_path: impl AsRef<Path>) -> RokitResult<Self> {
+    pub(crate) async fn load(
+        home_path: impl AsRef<Path>,
+        trust_scope: TrustScope,
+    ) -> RokitResult<Self> {
         let start = Instant::now();
         let path = Self::path(home_path);
-        let this = load_impl(path.clone()).await?;
+        let mut this = load_impl(path.clone()).await?;
+        this.trust_scope = trust_scope;
         trace!(?path, elapsed = ?start.elapsed(), "Loading tool cache");
         Ok(this)
     }
@@ -180,6 +325,7 @@ impl ToolCache {
         self.needs_saving.store(false, Ordering::SeqCst);
         let start = Instant::now();
         let path = Self::path(home_path);
+        let _lock = FileLock::acquire(path.with_file_name(STORAGE_LOCK_FILE_NAME)).await?;
         save_impl(path.clone(), self).await?;
         trace!(?path, elapsed = ?start.elapsed(), "Saved tool cache");
         Ok(())
@@ -190,6 +336,20 @@ impl ToolCache {
     }
 }
 
+/*
+    Gets the range of versions compatible with the given version, where
+    a new major version, or a new minor version before `1.0.0`, is not.
+*/
+fn compatible_versions(version: &Version) -> VersionReq {
+    let req = if version.major == 0 {
+        format!("^0.{}", version.minor)
+    } else {
+        format!("^{}", version.major)
+    };
+    req.parse()
+        .expect("caret requirement should always be valid")
+}
+
 async fn load_impl(path: PathBuf) -> RokitResult<ToolCache> {
     // Make sure we have created the directory for the cache file, since
     // OpenOptions::create will only create the file and not the directory.
@@ -228,23 +388,76 @@ async fn save_impl(path: PathBuf, cache: &ToolCache) -> RokitResult<()> {
     // NOTE: We save using sorted json arrays here, which is
     // compatible with the deserialize implementation for DashSet,
     // while also being easier to read for any human inspectors.
+    let trusted_versions = cache
+        .trusted_versions
+        .iter()
+        .filter(|entry| !entry.value().is_empty())
+        .map(|entry| (entry.key().to_string(), entry.value().clone()))
+        .collect::<BTreeMap<_, _>>();
     let json = serde_json::json!({
         "trusted": cache.all_trusted(),
+        "trusted_versions": trusted_versions,
         "installed": cache.all_installed(),
     });
 
-    // Same as in our load implementation, see notes there.
-    let result = spawn_blocking(move || {
-        use std::{
-            fs::{create_dir_all, File},
-            io::{BufWriter, Error},
+    let contents = serde_json::to_vec(&json)?;
+    create_dir_all(path.parent().unwrap()).await?;
+    write_atomic(path, contents).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(s: &str) -> ToolSpec {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn trust_for_versions() {
+        let cache = ToolCache::new();
+        let id = "rojo-rbx/rojo".parse::<ToolId>().unwrap();
+        assert!(cache.add_trust_versions(id.clone(), "=7.4.1".parse().unwrap()));
+        assert!(!cache.add_trust_versions(id.clone(), "=7.4.1".parse().unwrap()));
+
+        assert!(!cache.is_trusted(&id));
+        assert!(cache.is_trusted_spec(&spec("rojo-rbx/rojo@7.4.1")));
+        assert!(!cache.is_trusted_spec(&spec("rojo-rbx/rojo@7.4.2")));
+
+        let _ = cache.add_trust(id.clone());
+        assert!(cache.is_trusted_spec(&spec("rojo-rbx/rojo@8.0.0")));
+        assert!(cache.remove_trust(&id));
+        assert!(!cache.is_trusted_spec(&spec("rojo-rbx/rojo@7.4.1")));
+    }
+
+    #[test]
+    fn major_trust_scope() {
+        let cache = ToolCache {
+            trust_scope: TrustScope::Major,
+            ..ToolCache::default()
         };
-        create_dir_all(path.parent().unwrap())?;
-        let writer = BufWriter::new(File::create(path)?);
-        serde_json::to_writer(writer, &json)?;
-        Ok::<_, Error>(())
-    });
+        assert!(cache.add_trust_spec(&spec("rojo-rbx/rojo@7.4.1")));
+        assert!(cache.add_trust_spec(&spec("Kampfkarren/selene@0.27.1")));
+
+        assert!(cache.is_trusted_spec(&spec("rojo-rbx/rojo@7.0.0")));
+        assert!(cache.is_trusted_spec(&spec("rojo-rbx/rojo@7.5.0-rc.1")));
+        assert!(!cache.is_trusted_spec(&spec("rojo-rbx/rojo@8.0.0")));
+        assert!(cache.is_trusted_spec(&spec("Kampfkarren/selene@0.27.3")));
+        assert!(!cache.is_trusted_spec(&spec("Kampfkarren/selene@0.28.0")));
+    }
 
-    result.await??;
-    Ok(())
+    #[tokio::test]
+    async fn trusted_versions_are_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ToolCache::load(dir.path(), TrustScope::Major)
+            .await
+            .unwrap();
+        assert!(cache.add_trust_spec(&spec("rojo-rbx/rojo@7.4.1")));
+        cache.save(dir.path()).await.unwrap();
+
+        let cache = ToolCache::load(dir.path(), TrustScope::Tool).await.unwrap();
+        let id = "rojo-rbx/rojo".parse::<ToolId>().unwrap();
+        assert_eq!(cache.trusted_versions(&id), ["^7".parse().unwrap()]);
+        assert!(cache.is_trusted_spec(&spec("rojo-rbx/rojo@7.4.4")));
+    }
 }
@@ -0,0 +1,128 @@
+use std::{
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::fs::read;
+
+use crate::{
+    result::RokitResult,
+    sources::{Artifact, ArtifactProvider},
+    util::{fs::write_atomic, hash::sha256_hex},
+};
+
+/**
+    Name of the metadata file, stored in the directory for every installed
+    tool version - this starts with `.rokit-`, just like the blob index, so
+    that it never collides with files extracted from an artifact.
+*/
+pub(super) const TOOL_METADATA_FILE_NAME: &str = ".rokit-metadata.json";
+
+/**
+    Metadata for an installed tool version, describing the artifact that
+    it was installed from, which is stored next to the installed binary.
+
+    This makes tool storage self-describing, so that installed tools can be
+    inspected without the tool cache or any manifests, even by other programs.
+
+    Tool versions installed by older versions of Rokit have no metadata.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstalledToolMetadata {
+    provider: ArtifactProvider,
+    #[serde(default)]
+    artifact_name: Option<String>,
+    #[serde(default)]
+    url: Option<String>,
+    sha256: String,
+    #[serde(default)]
+    binary_sha256: Option<String>,
+    installed_at: u64,
+}
+
+impl InstalledToolMetadata {
+    /**
+        Creates metadata for a tool that is being installed right
+        now, from the given artifact and its downloaded contents.
+    */
+    #[must_use]
+    pub fn new(artifact: &Artifact, contents: impl AsRef<[u8]>) -> Self {
+        let installed_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        Self {
+            provider: artifact.tool_spec.provider(),
+            artifact_name: artifact.file_name(),
+            url: artifact.url.as_ref().map(ToString::to_string),
+            sha256: sha256_hex(contents),
+            binary_sha256: None,
+            installed_at,
+        }
+    }
+
+    /**
+        Gets the provider that the tool was installed from.
+    */
+    #[must_use]
+    pub fn provider(&self) -> ArtifactProvider {
+        self.provider
+    }
+
+    /**
+        Gets the file name of the artifact that the tool was installed from.
+    */
+    #[must_use]
+    pub fn artifact_name(&self) -> Option<&str> {
+        self.artifact_name.as_deref()
+    }
+
+    /**
+        Gets the URL that the artifact was downloaded from.
+    */
+    #[must_use]
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    /**
+        Gets the SHA-256 checksum of the downloaded artifact,
+        as a lowercase hexadecimal string.
+    */
+    #[must_use]
+    pub fn sha256(&self) -> &str {
+        &self.sha256
+    }
+
+    /**
+        Gets the SHA-256 checksum of the binary that was installed
+        from the artifact, as a lowercase hexadecimal string.
+    */
+    #[must_use]
+    pub fn binary_sha256(&self) -> Option<&str> {
+        self.binary_sha256.as_deref()
+    }
+
+    /**
+        Gets the time at which the tool was installed.
+    */
+    #[must_use]
+    pub fn installed_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.installed_at)
+    }
+
+    pub(super) fn set_binary_sha256(&mut self, hash: Option<String>) {
+        self.binary_sha256 = hash;
+    }
+
+    pub(super) async fn load(tool_dir: &Path) -> Option<Self> {
+        let contents = read(tool_dir.join(TOOL_METADATA_FILE_NAME)).await.ok()?;
+        serde_json::from_slice(&contents).ok()
+    }
+
+    pub(super) async fn save(&self, tool_dir: &Path) -> RokitResult<()> {
+        let contents = serde_json::to_vec_pretty(self)?;
+        write_atomic(tool_dir.join(TOOL_METADATA_FILE_NAME), contents).await
+    }
+}
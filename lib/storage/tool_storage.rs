@@ -1,29 +1,68 @@
 use std::{
-    env::consts::{EXE_EXTENSION, EXE_SUFFIX},
+    collections::BTreeSet,
+    env::consts::EXE_SUFFIX,
     path::{Path, PathBuf},
     sync::Arc,
 };
 
 use filepath::FilePath;
-use futures::{stream::FuturesUnordered, TryStreamExt};
+use futures::{stream, StreamExt};
 use tokio::{
-    fs::{create_dir_all, read, read_dir, remove_file, rename},
+    fs::{
+        create_dir_all, metadata, read, read_dir, remove_dir, remove_dir_all, remove_file, rename,
+    },
     sync::Mutex as AsyncMutex,
 };
-use tracing::{debug, trace};
+use tracing::{debug, trace, warn};
 
 use crate::{
-    manifests::{AuthManifest, RokitManifest},
+    manifests::AuthManifest,
     result::RokitResult,
-    storage::metadata::RokitLinkMetadata,
+    sources::{ExtractError, ExtractedFile},
+    storage::{
+        blobs::{BlobIndex, BlobStore, CollectedGarbage, BLOBS_DIR_NAME},
+        links::{
+            find_case_mismatched_links, fix_link_casing, is_shared_link, move_link_aside,
+            remove_junk_links, write_shared_link, LinkSidecar, LinkStrategy, PendingLinks,
+            LINK_SIDECAR_FILE_NAME, LINK_TEMP_PREFIX, MOVE_LINKS_ASIDE,
+        },
+        metadata::RokitLinkMetadata,
+        tool_metadata::InstalledToolMetadata,
+    },
     system::current_exe_contents,
     tool::{ToolAlias, ToolSpec},
-    util::fs::{path_exists, write_executable_file},
+    util::{
+        fs::{path_exists, write_executable_file},
+        hash::sha256_hex,
+        lock::FileLock,
+        path::is_safe_relative_path,
+    },
 };
 
+use super::STORAGE_LOCK_FILE_NAME;
+
+/**
+    The maximum number of links that are written at the same time - links
+    are written concurrently, since each one may be slow on network drives,
+    but not all at once, to avoid exhausting file handles or blocking threads.
+*/
+const MAX_CONCURRENT_LINKS: usize = 16;
+
 /**
     Storage for tool binaries and aliases.
 
+    Files for installed tools are stored once per unique contents, in a
+    content-addressed blob store, and the directories for tool versions
+    link to those blobs - unused blobs are removed using `collect_garbage`.
+
+    All writes hold an exclusive lock on the storage directory,
+    and are atomic, so that several Rokit processes may safely
+    install tools and create links at the same time.
+
+    Links for tool aliases share the installed Rokit binary when possible,
+    using the [`LinkStrategy`] set in the global manifest, instead of each
+    being a full copy of it - see [`LinkStrategy`] for more information.
+
     Can be cheaply cloned while still
     referring to the same underlying data.
 */
@@ -31,6 +70,7 @@ use crate::{
 pub struct ToolStorage {
     pub(super) tools_dir: Arc<Path>,
     pub(super) aliases_dir: Arc<Path>,
+    link_strategy: LinkStrategy,
     current_rokit_contents: Arc<AsyncMutex<Option<Vec<u8>>>>,
 }
 
@@ -55,6 +95,14 @@ impl ToolStorage {
         self.aliases_dir.join(alias_file_name)
     }
 
+    fn blobs(&self) -> BlobStore {
+        BlobStore::new(&self.tools_dir)
+    }
+
+    async fn lock(&self) -> RokitResult<FileLock> {
+        FileLock::acquire(self.tools_dir.join(STORAGE_LOCK_FILE_NAME)).await
+    }
+
     fn rokit_path(&self) -> PathBuf {
         self.aliases_dir.join(format!("rokit{EXE_SUFFIX}"))
     }
@@ -79,6 +127,65 @@ impl ToolStorage {
         self.tool_paths(spec).1
     }
 
+    /**
+        Checks if the binary for the given tool exists and is not empty.
+
+        Binaries may go missing even if the tool was installed, such
+        as when a cache of the tool storage was only partially restored.
+    */
+    pub async fn has_tool_binary(&self, spec: &ToolSpec) -> bool {
+        metadata(self.tool_path(spec))
+            .await
+            .is_ok_and(|meta| meta.is_file() && meta.len() > 0)
+    }
+
+    /**
+        Computes the SHA-256 checksum of the installed binary for
+        the given tool, as a lowercase hexadecimal string.
+
+        Returns `None` if the tool is not installed.
+    */
+    pub async fn tool_sha256(&self, spec: &ToolSpec) -> Option<String> {
+        let contents = read(self.tool_path(spec)).await.ok()?;
+        Some(sha256_hex(contents))
+    }
+
+    /**
+        Gets the metadata for the given installed tool, describing
+        the artifact that it was installed from.
+
+        Returns `None` if the tool is not installed, or if it was
+        installed by an older version of Rokit without metadata.
+    */
+    pub async fn tool_metadata(&self, spec: &ToolSpec) -> Option<InstalledToolMetadata> {
+        let (dir_path, _) = self.tool_paths(spec);
+        InstalledToolMetadata::load(&dir_path).await
+    }
+
+    /**
+        Saves the metadata for the given tool, next to its binary - this must
+        be called after the tool contents have been replaced, so that the
+        checksum of the installed binary can be recorded along with it.
+
+        # Errors
+
+        - If the metadata could not be written.
+    */
+    pub async fn save_tool_metadata(
+        &self,
+        spec: &ToolSpec,
+        mut metadata: InstalledToolMetadata,
+    ) -> RokitResult<()> {
+        let (dir_path, file_path) = self.tool_paths(spec);
+        let _lock = self.lock().await?;
+        let binary_sha256 = BlobIndex::load(&dir_path).await.and_then(|index| {
+            let relative_path = file_path.strip_prefix(&dir_path).unwrap();
+            index.get(relative_path).map(ToString::to_string)
+        });
+        metadata.set_binary_sha256(binary_sha256);
+        metadata.save(&dir_path).await
+    }
+
     /**
         Replaces the binary contents for the given tool.
 
@@ -92,11 +199,149 @@ impl ToolStorage {
         contents: impl AsRef<[u8]>,
     ) -> RokitResult<()> {
         let (dir_path, file_path) = self.tool_paths(spec);
-        create_dir_all(dir_path).await?;
-        write_executable_file(&file_path, contents).await?;
+        let _lock = self.lock().await?;
+        create_dir_all(&dir_path).await?;
+
+        let mut index = BlobIndex::load(&dir_path).await.unwrap_or_default();
+        let hash = self
+            .blobs()
+            .store_and_link(contents.as_ref(), true, &file_path)
+            .await?;
+        index.insert(file_path.strip_prefix(&dir_path).unwrap(), hash);
+        index.save(&dir_path).await?;
+
         Ok(())
     }
 
+    /**
+        Replaces the binary contents for the given tool, together with
+        all other files that should be installed next to the binary.
+
+        Any files previously installed for the tool are removed first,
+        so that no stale files from an earlier install are left behind.
+
+        # Errors
+
+        - If any of the other files has a path that could escape the tool directory.
+        - If the previous files could not be removed.
+        - If the binary or any of the other files could not be written.
+    */
+    pub async fn replace_tool_contents_with_files(
+        &self,
+        spec: &ToolSpec,
+        contents: impl AsRef<[u8]>,
+        files: &[ExtractedFile],
+    ) -> RokitResult<()> {
+        // NOTE: Files are extracted with safe paths, but may also come from
+        // elsewhere, and must never be written outside of the tool directory
+        if let Some(file) = files.iter().find(|f| !is_safe_relative_path(&f.path)) {
+            return Err(ExtractError::unsafe_path(&file.path).into());
+        }
+
+        let (dir_path, file_path) = self.tool_paths(spec);
+        let _lock = self.lock().await?;
+        if path_exists(&dir_path).await {
+            remove_dir_all(&dir_path).await?;
+        }
+        create_dir_all(&dir_path).await?;
+
+        let blobs = self.blobs();
+        let mut index = BlobIndex::default();
+        for file in files {
+            let path = dir_path.join(&file.path);
+            if let Some(parent) = path.parent() {
+                create_dir_all(parent).await?;
+            }
+            let hash = blobs
+                .store_and_link(&file.contents, file.executable, &path)
+                .await?;
+            index.insert(&file.path, hash);
+        }
+        let hash = blobs
+            .store_and_link(contents.as_ref(), true, &file_path)
+            .await?;
+        index.insert(file_path.strip_prefix(&dir_path).unwrap(), hash);
+        index.save(&dir_path).await?;
+
+        trace!(%spec, num_files = files.len(), "Installed tool with files");
+        Ok(())
+    }
+
+    /**
+        Removes the binary, and its directory, for the given tool.
+
+        Does nothing if the tool binary does not exist.
+
+        # Errors
+
+        - If the binary or its directory could not be removed.
+    */
+    pub async fn remove_tool_contents(&self, spec: &ToolSpec) -> RokitResult<()> {
+        let (dir_path, _) = self.tool_paths(spec);
+        let _lock = self.lock().await?;
+        if path_exists(&dir_path).await {
+            remove_dir_all(&dir_path).await?;
+        }
+
+        // Also clean up the directories for the tool name and author,
+        // if this was the last version - removing a non-empty directory
+        // fails, which is fine, since other versions are still installed
+        for dir in dir_path.ancestors().skip(1).take(2) {
+            if remove_dir(dir).await.is_err() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /**
+        Removes all blobs in the blob store that are no longer used by any
+        installed tool version, such as blobs for removed tool versions, or
+        for previous contents of tools that were reinstalled.
+
+        If `dry_run` is `true`, nothing is removed, and the returned
+        counts are for the blobs that would have been removed.
+
+        # Errors
+
+        - If the tool storage directory or the blob store could not be read.
+    */
+    pub async fn collect_garbage(&self, dry_run: bool) -> RokitResult<CollectedGarbage> {
+        let _lock = self.lock().await?;
+
+        let mut referenced = BTreeSet::new();
+        for tool_dir in self.all_tool_dirs().await? {
+            if let Some(index) = BlobIndex::load(&tool_dir).await {
+                referenced.extend(index.hashes().map(ToString::to_string));
+            }
+        }
+        debug!(referenced = referenced.len(), "collecting unused blobs");
+
+        self.blobs().remove_unreferenced(&referenced, dry_run).await
+    }
+
+    /*
+        Finds the directories for all installed tool versions,
+        which are nested as `author/name/version` in tool storage.
+    */
+    async fn all_tool_dirs(&self) -> RokitResult<Vec<PathBuf>> {
+        let mut dirs = vec![self.tools_dir.to_path_buf()];
+        for _ in 0..3 {
+            let mut children = Vec::new();
+            for dir in dirs {
+                let mut reader = read_dir(&dir).await?;
+                while let Some(entry) = reader.next_entry().await? {
+                    if entry.file_type().await?.is_dir() && entry.file_name() != BLOBS_DIR_NAME {
+                        children.push(entry.path());
+                    }
+                }
+            }
+            dirs = children;
+        }
+        Ok(dirs)
+    }
+
     /**
         Replaces the contents of the stored Rokit binary in memory.
 
@@ -111,7 +356,9 @@ impl ToolStorage {
     /**
         Creates a link for the given tool alias.
 
-        Note that if the link already exists, it will be overwritten.
+        Note that if the link already exists, it will be overwritten, and any
+        existing link for the same alias with different casing, such as one for
+        `StyLua` when creating a link for `stylua`, is renamed or removed.
 
         # Errors
 
@@ -119,21 +366,71 @@ impl ToolStorage {
     */
     pub async fn create_tool_link(&self, alias: &ToolAlias) -> RokitResult<()> {
         let path = self.alias_path(alias);
+        let _lock = self.lock().await?;
+        fix_link_casing(&path).await?;
 
-        // NOTE: A previous version of Rokit was not adding exe extensions correctly,
-        // so look for and try to remove existing links that do not have the extension
-        if should_check_exe_extensions() {
-            let no_extension = strip_exe_extension(&path);
-            if no_extension != path && path_exists(&no_extension).await {
-                remove_file(&no_extension).await?;
-            }
+        let rokit_contents = self.rokit_contents().await?;
+        let (strategy, is_sidecar_current) = self.link_state(&rokit_contents).await;
+        self.write_link(strategy, is_sidecar_current, &path, &rokit_contents)
+            .await?;
+        if !is_sidecar_current {
+            LinkSidecar::current(strategy)
+                .save(&self.aliases_dir)
+                .await?;
         }
 
-        // Create the new link
-        let rokit_contents = self.rokit_contents().await?;
-        let rokit_metadata = RokitLinkMetadata::current();
-        skip_or_write_link_with_meta(path, &rokit_contents, &rokit_metadata).await?;
+        Ok(())
+    }
 
+    /**
+        Checks if the link for the given tool alias exists, with any casing.
+
+        Note that this does not check if the link is up-to-date.
+    */
+    pub async fn has_tool_link(&self, alias: &ToolAlias) -> bool {
+        let path = self.alias_path(alias);
+        path_exists(&path).await || !find_case_mismatched_links(&path).await.0.is_empty()
+    }
+
+    /**
+        Removes the link for the given tool alias, with any casing.
+
+        Does nothing if the link does not exist.
+
+        # Errors
+
+        - If the link could not be removed.
+    */
+    pub async fn remove_tool_link(&self, alias: &ToolAlias) -> RokitResult<()> {
+        let path = self.alias_path(alias);
+        let _lock = self.lock().await?;
+        let (mismatched, _) = find_case_mismatched_links(&path).await;
+        for path in mismatched {
+            remove_file(&path).await?;
+        }
+        if path_exists(&path).await {
+            remove_file(&path).await?;
+        }
+        Ok(())
+    }
+
+    /**
+        Removes the link at the given path, which must be one of the
+        paths returned by `all_link_paths`, such as a link with a
+        version suffix, that has no corresponding tool alias.
+
+        Does nothing if the link does not exist.
+
+        # Errors
+
+        - If the link could not be removed.
+    */
+    pub async fn remove_link_path(&self, path: &Path) -> RokitResult<()> {
+        debug_assert!(path.parent() == Some(&*self.aliases_dir));
+        let _lock = self.lock().await?;
+        if path_exists(path).await {
+            remove_file(path).await?;
+        }
         Ok(())
     }
 
@@ -154,7 +451,13 @@ impl ToolStorage {
         let mut link_reader = read_dir(&self.aliases_dir).await?;
         while let Some(entry) = link_reader.next_entry().await? {
             let path = entry.path();
-            if path == rokit_path {
+            let is_link_file = entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| !name.starts_with(LINK_TEMP_PREFIX));
+            if !is_link_file || entry.file_name() == LINK_SIDECAR_FILE_NAME {
+                trace!(?path, "skipping non-link file");
+            } else if path == rokit_path {
                 debug!(?path, "found Rokit link");
             } else {
                 debug!(?path, "found tool link");
@@ -169,6 +472,12 @@ impl ToolStorage {
         Recreates all known links for tool aliases in the binary directory.
         This includes the link / main executable for Rokit itself.
 
+        Links that can not be updated, such as links that are currently in use
+        on Windows, do not stop other links from being recreated - they are
+        instead retried by the next Rokit invocation, see `retry_pending_links`.
+        Links that are already up-to-date are skipped, so if this is interrupted,
+        calling it again only writes the links that were not yet written.
+
         Returns a tuple with information about any existing Rokit link:
 
         - The first value is `true` if the existing Rokit link was found, `false` otherwise.
@@ -181,22 +490,33 @@ impl ToolStorage {
         - If any link could not be written.
     */
     pub async fn recreate_all_links(&self) -> RokitResult<(bool, bool)> {
+        self.recreate_all_links_with_progress(|_| {}).await
+    }
+
+    /**
+        Recreates all known links for tool aliases in the binary directory,
+        calling the given function with the path of each tool link once it
+        has been written, or has failed to be written, in no particular order.
+
+        The number of tool links that will be written can be found
+        beforehand using `all_link_paths`, for displaying progress.
+
+        See [`ToolStorage::recreate_all_links`] for more information.
+
+        # Errors
+
+        - If any link could not be written.
+    */
+    pub async fn recreate_all_links_with_progress(
+        &self,
+        progress: impl FnMut(&Path),
+    ) -> RokitResult<(bool, bool)> {
         let rokit_path = self.rokit_path();
         let rokit_contents = self.rokit_contents().await?;
+        let _lock = self.lock().await?;
         let rokit_link_existed = path_exists(&rokit_path).await;
 
-        let mut link_paths = self.all_link_paths().await?;
-
-        // NOTE: A previous version of Rokit was not adding exe extensions correctly,
-        // so look for and try to remove existing links that do not have the extension
-        if should_check_exe_extensions() {
-            for link_path in &mut link_paths {
-                if !has_exe_extension(link_path.as_path()) {
-                    remove_file(link_path.as_path()).await?;
-                    *link_path = append_exe_extension(&link_path);
-                }
-            }
-        }
+        let link_paths = self.all_link_paths().await?;
 
         // Write the Rokit binary if necessary to ensure it's up-to-date
         let existing_rokit_binary = read(&rokit_path).await.unwrap_or_default();
@@ -236,26 +556,90 @@ impl ToolStorage {
         // If any link already has the correct Rokit contents, we
         // can skip creating it, to avoid OS permission errors if the
         // link is currently being used to run some Rokit-managed program.
-        let rokit_metadata = RokitLinkMetadata::current();
-        link_paths
-            .into_iter()
-            .map(|path| skip_or_write_link_with_meta(path, &rokit_contents, &rokit_metadata))
-            .collect::<FuturesUnordered<_>>()
-            .try_collect::<Vec<_>>()
-            .await?;
+        let (strategy, is_sidecar_current) = self.link_state(&rokit_contents).await;
+        let pending = self
+            .write_links(
+                strategy,
+                is_sidecar_current,
+                &link_paths,
+                &rokit_contents,
+                progress,
+            )
+            .await;
+        pending.save(&self.aliases_dir).await?;
+        if !is_sidecar_current {
+            LinkSidecar::current(strategy)
+                .save(&self.aliases_dir)
+                .await?;
+        }
 
         Ok((rokit_link_existed, was_rokit_updated))
     }
 
-    pub(crate) async fn load(home_path: impl AsRef<Path>) -> RokitResult<Self> {
+    /**
+        Retries updating any links that could not be updated during a previous
+        call to `recreate_all_links`, and removes old links that were moved
+        out of the way and are no longer in use.
+
+        This is cheap to call when there are no pending links.
+
+        Returns the number of links that are still pending.
+
+        # Errors
+
+        - If the pending links could not be saved.
+    */
+    pub async fn retry_pending_links(&self) -> RokitResult<usize> {
+        if !PendingLinks::exists(&self.aliases_dir).await {
+            return Ok(0);
+        }
+
+        let rokit_contents = self.rokit_contents().await?;
+        let _lock = self.lock().await?;
+
+        let link_paths = PendingLinks::load(&self.aliases_dir)
+            .await
+            .paths(&self.aliases_dir);
+        let mut retried_paths = Vec::new();
+        for path in link_paths {
+            // Links may have been removed since, which is fine
+            if path_exists(&path).await {
+                retried_paths.push(path);
+            }
+        }
+
+        let (strategy, is_sidecar_current) = self.link_state(&rokit_contents).await;
+        let pending = self
+            .write_links(
+                strategy,
+                is_sidecar_current,
+                &retried_paths,
+                &rokit_contents,
+                |_| {},
+            )
+            .await;
+        pending.save(&self.aliases_dir).await?;
+
+        debug!(
+            retried = retried_paths.len(),
+            pending = pending.len(),
+            "retried pending links"
+        );
+        Ok(pending.len())
+    }
+
+    pub(crate) async fn load(
+        home_path: impl AsRef<Path>,
+        aliases_dir: impl AsRef<Path>,
+        link_strategy: LinkStrategy,
+    ) -> RokitResult<Self> {
         let home_path = home_path.as_ref();
 
         let tools_dir = home_path.join("tool-storage").into();
-        let aliases_dir = home_path.join("bin").into();
+        let aliases_dir = aliases_dir.as_ref().into();
 
         tokio::try_join!(
-            RokitManifest::load_or_create(&home_path),
-            AuthManifest::load_or_create(&home_path),
+            AuthManifest::create_if_missing(&home_path),
             async { Ok(create_dir_all(&tools_dir).await?) },
             async { Ok(create_dir_all(&aliases_dir).await?) },
         )?;
@@ -265,10 +649,149 @@ impl ToolStorage {
         Ok(Self {
             tools_dir,
             aliases_dir,
+            link_strategy,
             current_rokit_contents,
         })
     }
 
+    pub(crate) fn with_aliases_dir(&self, aliases_dir: Arc<Path>) -> Self {
+        Self {
+            tools_dir: Arc::clone(&self.tools_dir),
+            aliases_dir,
+            link_strategy: self.link_strategy,
+            current_rokit_contents: Arc::clone(&self.current_rokit_contents),
+        }
+    }
+
+    /*
+        Gets the strategy that links can actually be created with - links
+        may only share the installed Rokit binary if it is the same as the
+        currently running one, otherwise they must be full copies of it.
+    */
+    async fn usable_link_strategy(&self, rokit_contents: &[u8]) -> LinkStrategy {
+        if self.link_strategy == LinkStrategy::Copy {
+            return LinkStrategy::Copy;
+        }
+        let installed = read(self.rokit_path()).await.unwrap_or_default();
+        if installed == rokit_contents {
+            self.link_strategy
+        } else {
+            debug!("installed Rokit binary differs from current, copying links");
+            LinkStrategy::Copy
+        }
+    }
+
+    /*
+        Gets the strategy that links should be created with, and
+        whether the sidecar was written using that same strategy.
+    */
+    async fn link_state(&self, rokit_contents: &[u8]) -> (LinkStrategy, bool) {
+        let strategy = self.usable_link_strategy(rokit_contents).await;
+        let sidecar = LinkSidecar::load(&self.aliases_dir).await;
+        let is_sidecar_current = sidecar == Some(LinkSidecar::current(strategy));
+        (strategy, is_sidecar_current)
+    }
+
+    /*
+        Writes all of the given links, also cleaning up old links that were
+        previously moved out of the way - links that fail to be written are
+        logged and returned as pending, instead of failing the whole batch.
+
+        The given function is called with the path of each link once it is done.
+    */
+    async fn write_links(
+        &self,
+        strategy: LinkStrategy,
+        is_sidecar_current: bool,
+        link_paths: &[PathBuf],
+        rokit_contents: &[u8],
+        mut progress: impl FnMut(&Path),
+    ) -> PendingLinks {
+        remove_junk_links(&self.aliases_dir).await;
+
+        let mut results = stream::iter(link_paths)
+            .map(|path| async move {
+                let result = self
+                    .write_link(strategy, is_sidecar_current, path, rokit_contents)
+                    .await;
+                (path, result)
+            })
+            .buffer_unordered(MAX_CONCURRENT_LINKS);
+
+        let mut pending = PendingLinks::default();
+        while let Some((path, result)) = results.next().await {
+            progress(path);
+            if let Err(e) = result {
+                warn!(
+                    "Failed to update the link at {path:?}, it will be retried \
+                    the next time Rokit runs - is the tool currently running?\n{e}"
+                );
+                pending.insert(path);
+            }
+        }
+        pending
+    }
+
+    /*
+        Writes the link at the given path using the given strategy, and
+        any fallback strategies, skipping links that are already up-to-date.
+
+        On platforms where links in use can not be replaced, the existing link
+        is moved out of the way first, and moved back if writing fails.
+    */
+    async fn write_link(
+        &self,
+        strategy: LinkStrategy,
+        is_sidecar_current: bool,
+        path: &Path,
+        rokit_contents: &[u8],
+    ) -> RokitResult<()> {
+        let rokit_path = self.rokit_path();
+        if strategy != LinkStrategy::Copy
+            && is_sidecar_current
+            && is_shared_link(path, &rokit_path).await
+        {
+            trace!(?path, "link is up-to-date");
+            return Ok(());
+        }
+        if strategy == LinkStrategy::Copy && is_current_link_copy(path).await {
+            return Ok(());
+        }
+
+        let moved_path = if MOVE_LINKS_ASIDE {
+            move_link_aside(path).await?
+        } else {
+            None
+        };
+        let result = self
+            .write_link_with_fallbacks(strategy, path, &rokit_path, rokit_contents)
+            .await;
+        if let (Err(_), Some(moved_path)) = (&result, moved_path) {
+            let _ = rename(moved_path, path).await;
+        }
+        result
+    }
+
+    async fn write_link_with_fallbacks(
+        &self,
+        strategy: LinkStrategy,
+        path: &Path,
+        rokit_path: &Path,
+        rokit_contents: &[u8],
+    ) -> RokitResult<()> {
+        for &fallback in strategy.with_fallbacks() {
+            if fallback == LinkStrategy::Copy {
+                break;
+            }
+            match write_shared_link(fallback, path, rokit_path).await {
+                Ok(()) => return Ok(()),
+                Err(e) => debug!(?path, %fallback, "failed to create link: {e}"),
+            }
+        }
+        let link_contents = RokitLinkMetadata::current().append_to(rokit_contents)?;
+        write_executable_file(path, link_contents).await
+    }
+
     #[allow(clippy::unused_self)]
     pub(crate) fn needs_saving(&self) -> bool {
         // Tool storage always writes all state directly
@@ -277,57 +800,150 @@ impl ToolStorage {
     }
 }
 
-// Utility functions for migrating missing exe extensions from old Rokit versions
-
-fn should_check_exe_extensions() -> bool {
-    !EXE_EXTENSION.is_empty()
-}
-
-fn has_exe_extension(path: impl AsRef<Path>) -> bool {
-    !EXE_EXTENSION.is_empty()
-        && path
-            .as_ref()
-            .extension()
-            .is_some_and(|ext| ext == EXE_EXTENSION)
-}
+// Utility functions for checking metadata at the _end_ of link executables
 
-fn strip_exe_extension(path: impl Into<PathBuf>) -> PathBuf {
-    let mut path: PathBuf = path.into();
-    if has_exe_extension(&path) {
-        path.set_extension("");
-    }
-    path
-}
-
-fn append_exe_extension(path: impl Into<PathBuf>) -> PathBuf {
-    let mut path: PathBuf = path.into();
-    if !has_exe_extension(&path) {
-        path.set_extension(EXE_EXTENSION);
+async fn is_current_link_copy(path: &Path) -> bool {
+    let existing_contents = read(path).await.unwrap_or_default();
+    match RokitLinkMetadata::parse_from(&existing_contents) {
+        Some(meta) if meta.is_current() => {
+            trace!(?path, ?meta, "link is up-to-date");
+            true
+        }
+        Some(meta) => {
+            trace!(?path, ?meta, "link is outdated");
+            false
+        }
+        None => false,
     }
-    path
 }
 
-// Utility functions for checking and writing metadata at the _end_ of link executables
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use tokio::fs::write;
+
+    use crate::{result::RokitError, storage::Home};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn tool_links_share_rokit_binary() {
+        let home = Home::new_temporary().await.unwrap();
+        let rokit_contents = b"#!/bin/sh\necho rokit\n".to_vec();
+        let alias = ToolAlias::from_str("rojo").unwrap();
+
+        let storage = home.tool_storage();
+        storage.replace_rokit_contents(rokit_contents.clone()).await;
+        storage.recreate_all_links().await.unwrap();
+        storage.create_tool_link(&alias).await.unwrap();
+
+        // Links are hardlinks by default, and not full copies with metadata
+        let link_paths = storage.all_link_paths().await.unwrap();
+        assert_eq!(link_paths.len(), 1);
+        assert_eq!(read(&link_paths[0]).await.unwrap(), rokit_contents);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let rokit_path = home.bin_dir().join("rokit");
+            let link_meta = std::fs::metadata(&link_paths[0]).unwrap();
+            let rokit_meta = std::fs::metadata(rokit_path).unwrap();
+            assert_eq!(link_meta.ino(), rokit_meta.ino());
+        }
 
-async fn skip_or_write_link_with_meta(
-    path: impl AsRef<Path>,
-    rokit_contents: &[u8],
-    rokit_metadata: &RokitLinkMetadata,
-) -> RokitResult<()> {
-    let link_path = path.as_ref();
+        // Links can still be full copies, if configured to be
+        write(
+            home.path().join("rokit.toml"),
+            "[settings]\nlink-strategy = \"copy\"\n",
+        )
+        .await
+        .unwrap();
+        let home = Home::load_from_path(home.path(), None).await.unwrap();
+        let storage = home.tool_storage();
+        storage.replace_rokit_contents(rokit_contents.clone()).await;
+        storage.recreate_all_links().await.unwrap();
+
+        let link_paths = storage.all_link_paths().await.unwrap();
+        assert_eq!(link_paths.len(), 1);
+        let copied = read(&link_paths[0]).await.unwrap();
+        assert!(copied.starts_with(&rokit_contents));
+        assert!(copied.len() > rokit_contents.len());
+    }
 
-    let existing_contents = read(&link_path).await.unwrap_or_default();
-    let existing_metadata = RokitLinkMetadata::parse_from(&existing_contents);
-    if let Some(meta) = existing_metadata {
-        if meta.is_current() {
-            trace!(?link_path, ?meta, "link is up-to-date");
-            return Ok(());
+    #[tokio::test]
+    async fn recreate_many_links_with_progress() {
+        let home = Home::new_temporary().await.unwrap();
+        let storage = home.tool_storage();
+        storage.replace_rokit_contents(b"rokit".to_vec()).await;
+        for index in 0..40 {
+            let alias = ToolAlias::from_str(&format!("tool-{index}")).unwrap();
+            storage.create_tool_link(&alias).await.unwrap();
         }
-        trace!(?link_path, ?meta, "link is outdated");
+
+        // Every tool link is reported exactly once, even though
+        // links are written concurrently, in no particular order
+        let mut reported = Vec::new();
+        storage
+            .recreate_all_links_with_progress(|path| reported.push(path.to_path_buf()))
+            .await
+            .unwrap();
+        let mut link_paths = storage.all_link_paths().await.unwrap();
+        link_paths.sort();
+        reported.sort();
+        assert_eq!(link_paths.len(), 40);
+        assert_eq!(reported, link_paths);
     }
 
-    let link_contents = rokit_metadata.append_to(rokit_contents)?;
-    write_executable_file(link_path, link_contents).await?;
+    // NOTE: Links are moved out of the way before being replaced on Windows,
+    // which also works for the directory used to block the link in this test
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn failed_links_are_retried_later() {
+        let home = Home::new_temporary().await.unwrap();
+        let rokit_contents = b"#!/bin/sh\necho rokit\n".to_vec();
+        let alias = ToolAlias::from_str("rojo").unwrap();
+        let pending_path = home.bin_dir().join(".rokit-pending-links.json");
+
+        let storage = home.tool_storage();
+        storage.replace_rokit_contents(rokit_contents.clone()).await;
+        storage.recreate_all_links().await.unwrap();
+        storage.create_tool_link(&alias).await.unwrap();
+
+        // A link that can not be replaced, here a non-empty directory, should
+        // not stop the other links from being recreated, but be retried later
+        let blocked = home.bin_dir().join(format!("blocked{EXE_SUFFIX}"));
+        create_dir_all(blocked.join("inner")).await.unwrap();
+        storage.recreate_all_links().await.unwrap();
+        assert!(pending_path.exists());
+
+        let link_paths = storage.all_link_paths().await.unwrap();
+        let tool_link = link_paths.iter().find(|path| **path != blocked).unwrap();
+        assert_eq!(read(tool_link).await.unwrap(), rokit_contents);
+
+        // The link is still pending while it can not be replaced
+        assert_eq!(storage.retry_pending_links().await.unwrap(), 1);
+
+        remove_dir_all(&blocked).await.unwrap();
+        write(&blocked, b"outdated").await.unwrap();
+        assert_eq!(storage.retry_pending_links().await.unwrap(), 0);
+        assert_eq!(read(&blocked).await.unwrap(), rokit_contents);
+        assert!(!pending_path.exists());
+    }
 
-    Ok(())
+    #[tokio::test]
+    async fn tool_files_never_escape_tool_dir() {
+        let home = Home::new_temporary().await.unwrap();
+        let spec = ToolSpec::from_str("rojo-rbx/rojo@7.4.1").unwrap();
+        let files = [ExtractedFile {
+            path: "../escaped".into(),
+            contents: b"escaped".to_vec(),
+            executable: false,
+        }];
+        let err = home
+            .tool_storage()
+            .replace_tool_contents_with_files(&spec, b"binary", &files)
+            .await
+            .unwrap_err();
+        assert!(matches!(&err, RokitError::Extract(e) if e.is_unsafe_path()));
+    }
 }
@@ -0,0 +1,204 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::{fs::read, time::Instant};
+use tracing::{instrument, trace};
+
+use crate::{
+    result::RokitResult,
+    tool::{ToolId, ToolSpec},
+    util::{fs::write_atomic, lock::FileLock},
+};
+
+use super::STORAGE_LOCK_FILE_NAME;
+
+/**
+    Tools that were already recorded as used within this interval are not
+    recorded again, so that running a tool often, such as in a build script,
+    only rarely needs to lock and write the usage file.
+*/
+const RECORD_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/**
+    Usage statistics for installed tools - currently
+    the last time each tool was run through a link.
+
+    Unlike the [`ToolCache`], this is not loaded together with
+    the Rokit home directory, and must be loaded on demand using
+    [`Home::tool_usage`], since it is only rarely needed.
+
+    [`ToolCache`]: super::ToolCache
+    [`Home::tool_usage`]: super::Home::tool_usage
+*/
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ToolUsage {
+    #[serde(default)]
+    last_used: BTreeMap<ToolSpec, u64>,
+}
+
+impl ToolUsage {
+    /**
+        Get the last time the given tool was run, if it has ever been run.
+    */
+    #[must_use]
+    pub fn last_used(&self, spec: &ToolSpec) -> Option<SystemTime> {
+        self.last_used.get(spec).map(|secs| from_unix_secs(*secs))
+    }
+
+    /**
+        Get the last time any version of the given tool was run,
+        if any version of the tool has ever been run.
+    */
+    #[must_use]
+    pub fn last_used_for_id(&self, id: &ToolId) -> Option<SystemTime> {
+        self.last_used
+            .iter()
+            .filter(|(spec, _)| spec.matches_id(id))
+            .map(|(_, secs)| *secs)
+            .max()
+            .map(from_unix_secs)
+    }
+
+    fn path(home_path: impl AsRef<Path>) -> PathBuf {
+        home_path.as_ref().join("tool-storage").join("usage.json")
+    }
+
+    #[instrument(skip(home_path), level = "trace")]
+    pub(crate) async fn load(home_path: impl AsRef<Path>) -> RokitResult<Self> {
+        let start = Instant::now();
+        let path = Self::path(home_path);
+        let this = load_impl(&path).await;
+        trace!(?path, elapsed = ?start.elapsed(), "Loaded tool usage");
+        Ok(this)
+    }
+
+    /**
+        Records the given tool as having been run right now.
+
+        The usage file is re-read while holding the storage lock, so
+        that concurrent runs of different tools never overwrite each other.
+
+        If the tool was already recorded as used very recently, this
+        does nothing, since the timestamp only needs to be approximate.
+    */
+    #[instrument(skip(home_path), level = "trace")]
+    pub(crate) async fn record(home_path: impl AsRef<Path>, spec: &ToolSpec) -> RokitResult<()> {
+        let start = Instant::now();
+        let path = Self::path(home_path);
+        let now = SystemTime::now();
+        if is_recent(&load_impl(&path).await, spec, now) {
+            trace!(?path, elapsed = ?start.elapsed(), "Tool usage is already recent");
+            return Ok(());
+        }
+
+        let _lock = FileLock::acquire(path.with_file_name(STORAGE_LOCK_FILE_NAME)).await?;
+        let mut this = load_impl(&path).await;
+        this.last_used.insert(spec.clone(), to_unix_secs(now));
+        write_atomic(&path, serde_json::to_vec(&this)?).await?;
+
+        trace!(?path, elapsed = ?start.elapsed(), "Recorded tool usage");
+        Ok(())
+    }
+
+    /**
+        Removes any recorded usage for the given tools,
+        typically because they have been uninstalled.
+    */
+    #[instrument(skip(home_path), level = "trace")]
+    pub(crate) async fn forget(home_path: impl AsRef<Path>, specs: &[ToolSpec]) -> RokitResult<()> {
+        let path = Self::path(home_path);
+        let _lock = FileLock::acquire(path.with_file_name(STORAGE_LOCK_FILE_NAME)).await?;
+
+        let mut this = load_impl(&path).await;
+        let len_before = this.last_used.len();
+        this.last_used.retain(|spec, _| !specs.contains(spec));
+        if this.last_used.len() != len_before {
+            write_atomic(&path, serde_json::to_vec(&this)?).await?;
+        }
+
+        Ok(())
+    }
+}
+
+async fn load_impl(path: &Path) -> ToolUsage {
+    // NOTE: Usage statistics are not critical, so a missing
+    // or unreadable file simply means nothing has been recorded
+    match read(path).await {
+        Ok(contents) => serde_json::from_slice(&contents).unwrap_or_default(),
+        Err(_) => ToolUsage::default(),
+    }
+}
+
+fn is_recent(usage: &ToolUsage, spec: &ToolSpec, now: SystemTime) -> bool {
+    usage
+        .last_used(spec)
+        .and_then(|last| now.duration_since(last).ok())
+        .is_some_and(|elapsed| elapsed < RECORD_INTERVAL)
+}
+
+fn to_unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn from_unix_secs(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn record_keeps_other_tools() {
+        let dir = tempfile::tempdir().unwrap();
+        let old: ToolSpec = "rojo-rbx/rojo@7.3.0".parse().unwrap();
+        let new: ToolSpec = "rojo-rbx/rojo@7.4.1".parse().unwrap();
+        let other: ToolSpec = "UpliftGames/wally@0.3.2".parse().unwrap();
+
+        let usage = ToolUsage::load(dir.path()).await.unwrap();
+        assert_eq!(usage.last_used(&old), None);
+
+        ToolUsage::record(dir.path(), &old).await.unwrap();
+        ToolUsage::record(dir.path(), &other).await.unwrap();
+
+        let usage = ToolUsage::load(dir.path()).await.unwrap();
+        assert!(usage.last_used(&old).is_some());
+        assert!(usage.last_used(&other).is_some());
+        assert_eq!(usage.last_used(&new), None);
+        assert_eq!(usage.last_used_for_id(new.id()), usage.last_used(&old));
+    }
+
+    #[tokio::test]
+    async fn record_skips_recent_usage() {
+        let dir = tempfile::tempdir().unwrap();
+        let spec: ToolSpec = "rojo-rbx/rojo@7.4.1".parse().unwrap();
+
+        // Pretend the tool was used a bit earlier, but within the interval
+        let mut usage = ToolUsage::default();
+        let earlier = to_unix_secs(SystemTime::now()) - 60;
+        usage.last_used.insert(spec.clone(), earlier);
+        let path = ToolUsage::path(dir.path());
+        tokio::fs::create_dir_all(path.parent().unwrap())
+            .await
+            .unwrap();
+        write_atomic(&path, serde_json::to_vec(&usage).unwrap())
+            .await
+            .unwrap();
+
+        ToolUsage::record(dir.path(), &spec).await.unwrap();
+        let usage = ToolUsage::load(dir.path()).await.unwrap();
+        assert_eq!(usage.last_used(&spec), Some(from_unix_secs(earlier)));
+
+        ToolUsage::forget(dir.path(), std::slice::from_ref(&spec))
+            .await
+            .unwrap();
+        let usage = ToolUsage::load(dir.path()).await.unwrap();
+        assert_eq!(usage.last_used(&spec), None);
+    }
+}
@@ -0,0 +1,101 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::fs::read;
+use tracing::{instrument, trace};
+
+use crate::{
+    result::RokitResult,
+    util::{fs::write_atomic, lock::FileLock},
+};
+
+use super::STORAGE_LOCK_FILE_NAME;
+
+/**
+    The minimum interval between two checks for updates.
+*/
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/**
+    When Rokit last checked for updates to itself and to project tools.
+
+    This is stored separately from the [`ToolCache`], since it is
+    only read and written by the periodic update check, if enabled.
+
+    [`ToolCache`]: super::ToolCache
+*/
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub(crate) struct UpdateCheck {
+    #[serde(default)]
+    last_checked: u64,
+}
+
+impl UpdateCheck {
+    fn path(home_path: impl AsRef<Path>) -> PathBuf {
+        home_path
+            .as_ref()
+            .join("tool-storage")
+            .join("update-check.json")
+    }
+
+    /**
+        Checks if enough time has passed since the last
+        update check, meaning that a new check is due.
+    */
+    #[instrument(skip(home_path), level = "trace")]
+    pub(crate) async fn is_due(home_path: impl AsRef<Path>) -> bool {
+        let this = load_impl(&Self::path(home_path)).await;
+        let last_checked = UNIX_EPOCH + Duration::from_secs(this.last_checked);
+        let elapsed = SystemTime::now().duration_since(last_checked);
+        elapsed.map_or(true, |elapsed| elapsed >= CHECK_INTERVAL)
+    }
+
+    /**
+        Records that an update check was just completed.
+    */
+    #[instrument(skip(home_path), level = "trace")]
+    pub(crate) async fn record(home_path: impl AsRef<Path>) -> RokitResult<()> {
+        let path = Self::path(home_path);
+        let _lock = FileLock::acquire(path.with_file_name(STORAGE_LOCK_FILE_NAME)).await?;
+
+        let this = Self {
+            last_checked: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        };
+        write_atomic(&path, serde_json::to_vec(&this)?).await?;
+
+        trace!(?path, "Recorded update check");
+        Ok(())
+    }
+}
+
+async fn load_impl(path: &Path) -> UpdateCheck {
+    // NOTE: A missing or unreadable file simply means that
+    // no update check has been recorded, so one is now due
+    match read(path).await {
+        Ok(contents) => serde_json::from_slice(&contents).unwrap_or_default(),
+        Err(_) => UpdateCheck::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn due_until_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        tokio::fs::create_dir_all(dir.path().join("tool-storage"))
+            .await
+            .unwrap();
+
+        assert!(UpdateCheck::is_due(dir.path()).await);
+        UpdateCheck::record(dir.path()).await.unwrap();
+        assert!(!UpdateCheck::is_due(dir.path()).await);
+    }
+}
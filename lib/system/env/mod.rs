@@ -5,6 +5,14 @@ use std::{
 
 use crate::{result::RokitResult, storage::Home};
 
+#[cfg(unix)]
+use crate::descriptor::UnsupportedPlatformError;
+#[cfg(unix)]
+use std::env::consts::OS;
+
+#[cfg(any(windows, test))]
+mod repair;
+
 #[cfg(unix)]
 mod shell;
 
@@ -34,15 +42,102 @@ pub async fn add_to_path(home: &Home) -> RokitResult<bool> {
     }
 }
 
+/**
+    Tries to add the Rokit binaries directory to the PATH for all users.
+
+    This is only supported on Windows, where it writes the machine PATH, and
+    requires running as an administrator. Rokit should be installed into the
+    system home directory first - see [`Home::load_system`].
+
+    Returns `true` if the directory was added to the PATH, `false` otherwise.
+
+    # Errors
+
+    - If the current system does not support a machine-wide PATH.
+    - If the directory could not be added to the PATH, such as when not elevated.
+*/
+pub async fn add_to_system_path(home: &Home) -> RokitResult<bool> {
+    #[cfg(unix)]
+    {
+        let _ = home;
+        Err(UnsupportedPlatformError::OS(OS).into())
+    }
+    #[cfg(windows)]
+    {
+        self::windows::add_to_system_path(home).await
+    }
+}
+
+/**
+    A summary of the changes made by [`repair_path`].
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathRepair {
+    /**
+        If the Rokit binaries directory was missing, and has been added.
+    */
+    pub added: bool,
+    /**
+        Entries that were removed, either since they were duplicates,
+        or since they pointed at stale Rokit binaries directories.
+    */
+    pub removed: Vec<String>,
+}
+
+impl PathRepair {
+    /**
+        Checks if anything was changed while repairing.
+    */
+    #[must_use]
+    pub fn is_changed(&self) -> bool {
+        self.added || !self.removed.is_empty()
+    }
+}
+
+/**
+    Re-validates the system PATH, and repairs it if necessary.
+
+    On Windows, this removes duplicate entries and entries for Rokit binaries
+    directories that no longer exist from the user PATH in the registry, adds the current
+    binaries directory if it is missing, and notifies other programs about
+    the change, so that new shells pick it up without restarting.
+
+    On other platforms, where PATH is set by shell profiles,
+    this is the same as [`add_to_path`], and never removes entries.
+
+    # Errors
+
+    - If the PATH could not be read or changed.
+*/
+pub async fn repair_path(home: &Home) -> RokitResult<PathRepair> {
+    #[cfg(unix)]
+    {
+        let added = self::unix::add_to_path(home).await?;
+        Ok(PathRepair {
+            added,
+            removed: Vec::new(),
+        })
+    }
+    #[cfg(windows)]
+    {
+        self::windows::repair_path(home).await
+    }
+}
+
 /**
     Checks if the Rokit binaries directory is in the system PATH.
 
     Returns `true` if the directory is in the PATH, `false` otherwise.
 */
 #[must_use]
-pub fn exists_in_path(_home: &Home) -> bool {
+pub fn exists_in_path(home: &Home) -> bool {
+    // NOTE: The default directory may be in the PATH using a different
+    // home directory, such as when using a symlinked home directory, so
+    // we only require the exact path for custom binaries directories
+    let bin_dir = home.bin_dir();
+    let is_default = bin_dir == home.path().join("bin");
     let pattern = format!(".rokit{MAIN_SEPARATOR_STR}bin");
     var_os("PATH").map_or(false, |path| {
-        split_paths(&path).any(|item| item.ends_with(&pattern))
+        split_paths(&path).any(|item| item == bin_dir || (is_default && item.ends_with(&pattern)))
     })
 }
@@ -0,0 +1,156 @@
+use super::PathRepair;
+
+/**
+    Repairs the given `PATH` value, which uses `;` as separator:
+
+    - Removes empty entries, and entries that are duplicates of earlier ones.
+    - Removes stale entries for Rokit binaries directories, such as ones
+      pointing at the home directory of an old user account, but only if
+      the directory no longer exists - other homes may still be in use.
+    - Adds the given binaries directory, if it is not already present.
+
+    Environment variables in entries, such as `%USERPROFILE%`, are expanded
+    using the given function before comparing, but are kept as-is otherwise.
+    Expanded entries are passed to the given function to check if they exist.
+
+    Returns the repaired `PATH` value, and a summary of what was changed.
+*/
+pub(crate) fn repair_path_entries(
+    path: &str,
+    bin_dir: &str,
+    expand_var: impl Fn(&str) -> Option<String>,
+    dir_exists: impl Fn(&str) -> bool,
+) -> (String, PathRepair) {
+    let normalize = |entry: &str| {
+        expand_vars(entry, &expand_var)
+            .trim_end_matches(['\\', '/'])
+            .replace('/', "\\")
+            .to_ascii_lowercase()
+    };
+    let bin_dir_normalized = normalize(bin_dir);
+
+    let mut repair = PathRepair::default();
+    let mut seen = Vec::new();
+    let mut entries = Vec::new();
+    let mut has_bin_dir = false;
+    for entry in path.split(';') {
+        let normalized = normalize(entry.trim());
+        if normalized.is_empty() {
+            continue;
+        }
+        let is_duplicate = seen.contains(&normalized);
+        let is_bin_dir = normalized == bin_dir_normalized;
+        let is_stale = !is_bin_dir
+            && normalized.ends_with(".rokit\\bin")
+            && !dir_exists(&expand_vars(entry.trim(), &expand_var));
+        if is_duplicate || is_stale {
+            repair.removed.push(entry.to_string());
+            continue;
+        }
+        has_bin_dir |= is_bin_dir;
+        seen.push(normalized);
+        entries.push(entry);
+    }
+
+    if !has_bin_dir {
+        entries.push(bin_dir);
+        repair.added = true;
+    }
+
+    (entries.join(";"), repair)
+}
+
+// Expands environment variables in the form of `%NAME%`, leaving
+// any variables that are not set, or not terminated, untouched
+fn expand_vars(entry: &str, expand_var: impl Fn(&str) -> Option<String>) -> String {
+    let mut expanded = String::new();
+    let mut rest = entry;
+    while let Some(start) = rest.find('%') {
+        let Some(len) = rest[start + 1..].find('%') else {
+            break;
+        };
+        let name = &rest[start + 1..start + 1 + len];
+        expanded.push_str(&rest[..start]);
+        match expand_var(name) {
+            Some(value) => expanded.push_str(&value),
+            None => expanded.push_str(&rest[start..=start + 1 + len]),
+        }
+        rest = &rest[start + 2 + len..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(name: &str) -> Option<String> {
+        match name {
+            "USERPROFILE" => Some(String::from("C:\\Users\\new")),
+            _ => None,
+        }
+    }
+
+    fn exists(dir: &str) -> bool {
+        !dir.contains("\\old\\")
+    }
+
+    #[test]
+    fn removes_stale_and_duplicate_entries() {
+        let (path, repair) = repair_path_entries(
+            "C:\\Windows;C:\\Users\\old\\.rokit\\bin;;c:\\windows\\;%USERPROFILE%\\.rokit\\bin",
+            "C:\\Users\\new\\.rokit\\bin",
+            expand,
+            exists,
+        );
+        assert_eq!(path, "C:\\Windows;%USERPROFILE%\\.rokit\\bin");
+        assert_eq!(
+            repair,
+            PathRepair {
+                added: false,
+                removed: vec![
+                    String::from("C:\\Users\\old\\.rokit\\bin"),
+                    String::from("c:\\windows\\"),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn keeps_other_existing_homes() {
+        let (path, repair) = repair_path_entries(
+            "C:\\Projects\\game\\.rokit\\bin;C:\\Users\\new\\.rokit\\bin",
+            "C:\\Users\\new\\.rokit\\bin",
+            expand,
+            exists,
+        );
+        assert_eq!(
+            path,
+            "C:\\Projects\\game\\.rokit\\bin;C:\\Users\\new\\.rokit\\bin"
+        );
+        assert!(!repair.is_changed());
+    }
+
+    #[test]
+    fn adds_missing_bin_dir() {
+        let (path, repair) = repair_path_entries("C:\\Windows", "D:\\Tools\\bin", expand, exists);
+        assert_eq!(path, "C:\\Windows;D:\\Tools\\bin");
+        assert!(repair.added);
+        assert!(repair.removed.is_empty());
+
+        let (path, repair) = repair_path_entries(&path, "D:\\Tools\\bin", expand, exists);
+        assert_eq!(path, "C:\\Windows;D:\\Tools\\bin");
+        assert!(!repair.is_changed());
+    }
+
+    #[test]
+    fn leaves_unknown_vars_untouched() {
+        assert_eq!(expand_vars("%UNKNOWN%\\bin", expand), "%UNKNOWN%\\bin");
+        assert_eq!(expand_vars("100%", expand), "100%");
+        assert_eq!(
+            expand_vars("%USERPROFILE%\\bin", expand),
+            "C:\\Users\\new\\bin"
+        );
+    }
+}
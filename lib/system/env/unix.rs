@@ -18,7 +18,7 @@ const ENV_SHELL_SCRIPT: &str = include_str!("./env.sh");
 
 pub async fn add_to_path(home: &Home) -> RokitResult<bool> {
     // Find our binaries dir and try to format it as "$HOME/.rokit/bin"
-    let bin_dir = home.path().join("bin");
+    let bin_dir = home.bin_dir();
     let bin_dir_str = bin_dir.to_str().ok_or(RokitError::InvalidUtf8)?;
     let bin_dir_in_home = replace_home_path_with_var(bin_dir_str);
 
@@ -1,7 +1,19 @@
-use std::path::Path;
+use std::{
+    env::var, ffi::OsStr, io::ErrorKind as IoErrorKind, iter::once, os::windows::ffi::OsStrExt,
+    path::Path, ptr::null_mut,
+};
 
 use tokio::task::spawn_blocking;
-use winreg::{enums::HKEY_CURRENT_USER, RegKey};
+use winapi::{
+    shared::minwindef::LPARAM,
+    um::winuser::{SendMessageTimeoutW, HWND_BROADCAST, SMTO_ABORTIFHUNG, WM_SETTINGCHANGE},
+};
+use winreg::{
+    enums::{
+        RegType, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, KEY_READ, KEY_SET_VALUE, REG_EXPAND_SZ,
+    },
+    RegKey, RegValue,
+};
 
 use crate::{
     result::{RokitError, RokitResult},
@@ -9,10 +21,15 @@ use crate::{
     util::path::simplify_path,
 };
 
+use super::{repair::repair_path_entries, PathRepair};
+
+const SYSTEM_ENVIRONMENT_KEY: &str =
+    r"SYSTEM\CurrentControlSet\Control\Session Manager\Environment";
+
 pub async fn add_to_path(home: &Home) -> RokitResult<bool> {
     // NOTE: Calls to canonicalize may use blocking filesystem
     // operations, so we spawn a task where that's acceptable.
-    let dir = home.path().join("bin");
+    let dir = home.bin_dir().to_path_buf();
     let task = spawn_blocking(move || {
         let dir = dir.canonicalize()?;
 
@@ -35,3 +52,112 @@ pub async fn add_to_path(home: &Home) -> RokitResult<bool> {
 
     task.await?
 }
+
+pub async fn add_to_system_path(home: &Home) -> RokitResult<bool> {
+    let dir = home.bin_dir().to_path_buf();
+    let task = spawn_blocking(move || {
+        let dir = simplify_path(dir.canonicalize()?);
+
+        // NOTE: Opening the machine environment for writing fails right
+        // away if the current process is not elevated, before any changes
+        let key = RegKey::predef(HKEY_LOCAL_MACHINE);
+        let env = key.open_subkey_with_flags(SYSTEM_ENVIRONMENT_KEY, KEY_READ | KEY_SET_VALUE)?;
+
+        // NOTE: The machine PATH contains entries such as %SystemRoot%,
+        // so it must be kept as an expandable string, same as the user PATH
+        let (path, vtype) = match env.get_raw_value("Path") {
+            Ok(value) => (value.to_string(), value.vtype),
+            Err(e) if e.kind() == IoErrorKind::NotFound => (String::new(), REG_EXPAND_SZ),
+            Err(e) => return Err(e.into()),
+        };
+
+        let path_already_exists = path.split(';').any(|entry| {
+            Path::new(entry)
+                .canonicalize()
+                .is_ok_and(|p| simplify_path(p) == dir)
+        });
+        if path_already_exists {
+            return Ok::<_, RokitError>(false);
+        }
+
+        let path = path.trim_end_matches(';');
+        let new_path = if path.is_empty() {
+            dir.display().to_string()
+        } else {
+            format!("{path};{}", dir.display())
+        };
+        env.set_raw_value("Path", &to_reg_value(&new_path, vtype))?;
+        broadcast_environment_change();
+
+        Ok::<_, RokitError>(true)
+    });
+
+    task.await?
+}
+
+pub async fn repair_path(home: &Home) -> RokitResult<PathRepair> {
+    let dir = home.bin_dir().to_path_buf();
+    let task = spawn_blocking(move || {
+        let dir = simplify_path(dir.canonicalize()?);
+
+        let key = RegKey::predef(HKEY_CURRENT_USER);
+        let env = key.create_subkey("Environment")?.0;
+
+        // NOTE: The user PATH is usually stored as an expandable string, which
+        // must be preserved, or entries such as %USERPROFILE% would stop working
+        let (path, vtype) = match env.get_raw_value("PATH") {
+            Ok(value) => (value.to_string(), value.vtype),
+            Err(e) if e.kind() == IoErrorKind::NotFound => (String::new(), REG_EXPAND_SZ),
+            Err(e) => return Err(e.into()),
+        };
+
+        let (new_path, repair) = repair_path_entries(
+            &path,
+            &dir.to_string_lossy(),
+            |name| var(name).ok(),
+            |entry| Path::new(entry).is_dir(),
+        );
+        if repair.is_changed() {
+            env.set_raw_value("PATH", &to_reg_value(&new_path, vtype))?;
+            broadcast_environment_change();
+        }
+
+        Ok::<_, RokitError>(repair)
+    });
+
+    task.await?
+}
+
+fn to_reg_value(value: &str, vtype: RegType) -> RegValue {
+    let bytes = OsStr::new(value)
+        .encode_wide()
+        .chain(once(0))
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    RegValue { bytes, vtype }
+}
+
+/*
+    Notifies all top-level windows that environment variables
+    have changed, which makes Explorer, and any shells started from
+    it afterwards, pick up the new PATH without signing out first.
+*/
+fn broadcast_environment_change() {
+    let param = OsStr::new("Environment")
+        .encode_wide()
+        .chain(once(0))
+        .collect::<Vec<_>>();
+    // SAFETY: The message parameter is a valid, null-terminated wide string that
+    // outlives the call, and the result pointer is allowed to be null
+    unsafe {
+        SendMessageTimeoutW(
+            HWND_BROADCAST,
+            WM_SETTINGCHANGE,
+            0,
+            param.as_ptr() as LPARAM,
+            SMTO_ABORTIFHUNG,
+            5000,
+            null_mut(),
+        );
+    }
+}
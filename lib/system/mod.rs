@@ -4,6 +4,8 @@ mod process;
 mod runner;
 
 pub use self::current::{current_dir, current_exe, current_exe_contents, current_exe_name};
-pub use self::env::{add_to_path, exists_in_path};
+pub use self::env::{add_to_path, add_to_system_path, exists_in_path, repair_path, PathRepair};
 pub use self::process::{Launcher as ProcessLauncher, Parent as ProcessParent};
-pub use self::runner::run_interruptible;
+pub use self::runner::{
+    run_interruptible, run_interruptible_captured, run_interruptible_with, CapturedOutput, Sandbox,
+};
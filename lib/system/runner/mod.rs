@@ -0,0 +1,286 @@
+use std::ffi::OsStr;
+use std::io::Result as IoResult;
+use std::iter::empty;
+use std::path::Path;
+use std::process::Stdio;
+
+#[cfg(windows)]
+use command_group::AsyncCommandGroup;
+
+use async_signal::{Signal, Signals};
+use futures::StreamExt;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt},
+    process::Command,
+    task::{spawn, JoinHandle},
+};
+
+mod sandbox;
+
+pub use self::sandbox::Sandbox;
+
+/**
+    The exit code and output of a command that was run
+    with its output captured - see [`run_interruptible_captured`].
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CapturedOutput {
+    pub code: i32,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+/*
+    If we got a signal, we'll return 128 + signal number as our exit code.
+
+    When debugging, and for experienced users, this may
+    make it slightly more obvious that the program they were
+    running didn't error - it was interrupted by a signal.
+*/
+const EXIT_CODE_GOT_SIGNAL: i32 = 128;
+
+fn spawn_signal_listener_task() -> IoResult<JoinHandle<i32>> {
+    let mut signals = if cfg!(target_os = "windows") {
+        Signals::new([Signal::Int])?
+    } else {
+        Signals::new([
+            Signal::Int,  // Interrupt
+            Signal::Term, // Terminate
+            Signal::Quit, // Quit
+        ])?
+    };
+
+    let task = spawn(async move {
+        while let Some(result) = signals.next().await {
+            match result {
+                Ok(sig) => return EXIT_CODE_GOT_SIGNAL + (sig as i32),
+                Err(err) => tracing::error!("Failed to listen for signal: {err}"),
+            }
+        }
+
+        EXIT_CODE_GOT_SIGNAL
+    });
+
+    Ok(task)
+}
+
+/**
+    Runs the given command with the given arguments and returns its exit code.
+
+    This command is interruptible by passing one of the following signals to Rokit:
+
+    - SIGINT (Ctrl+C)
+    - SIGTERM
+    - SIGQUIT
+
+    Note that on Windows, only SIGINT (Ctrl+C) is supported, but
+    the process may also be reaped as part of the current job group.
+
+    # Errors
+
+    - If signal listeners could not be created
+    - If the given command could not be spawned
+*/
+pub async fn run_interruptible<C, A, S>(command: C, args: A) -> IoResult<i32>
+where
+    C: AsRef<OsStr>,
+    A: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    run_interruptible_with(command, args, empty::<(&OsStr, &OsStr)>(), None, None).await
+}
+
+/**
+    Runs the given command with the given arguments and returns its exit code,
+    setting the given environment variables for the command, in addition to
+    the ones inherited from the current process.
+
+    If a working directory is given, the command runs in that
+    directory, instead of the current working directory.
+
+    If a sandbox is given, the command only inherits the environment
+    variables allowed by the sandbox - see [`Sandbox`] for details.
+
+    See [`run_interruptible`] for more information.
+
+    # Errors
+
+    - If signal listeners could not be created
+    - If the given command could not be spawned
+*/
+pub async fn run_interruptible_with<C, A, S, E, K, V>(
+    command: C,
+    args: A,
+    env: E,
+    cwd: Option<&Path>,
+    sandbox: Option<&Sandbox>,
+) -> IoResult<i32>
+where
+    C: AsRef<OsStr>,
+    A: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+    E: IntoIterator<Item = (K, V)>,
+    K: AsRef<OsStr>,
+    V: AsRef<OsStr>,
+{
+    let (code, _) = run_interruptible_impl(command, args, env, cwd, sandbox, false).await?;
+    Ok(code)
+}
+
+/**
+    Runs the given command just like [`run_interruptible_with`], but captures
+    everything it writes to stdout and stderr, instead of letting the command
+    write directly to the stdout and stderr of the current process.
+
+    The command still inherits stdin from the current process.
+
+    # Errors
+
+    - If signal listeners could not be created
+    - If the given command could not be spawned
+*/
+pub async fn run_interruptible_captured<C, A, S, E, K, V>(
+    command: C,
+    args: A,
+    env: E,
+    cwd: Option<&Path>,
+    sandbox: Option<&Sandbox>,
+) -> IoResult<CapturedOutput>
+where
+    C: AsRef<OsStr>,
+    A: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+    E: IntoIterator<Item = (K, V)>,
+    K: AsRef<OsStr>,
+    V: AsRef<OsStr>,
+{
+    let (code, output) = run_interruptible_impl(command, args, env, cwd, sandbox, true).await?;
+    let (stdout, stderr) = output.unwrap_or_default();
+    Ok(CapturedOutput {
+        code,
+        stdout,
+        stderr,
+    })
+}
+
+async fn run_interruptible_impl<C, A, S, E, K, V>(
+    command: C,
+    args: A,
+    env: E,
+    cwd: Option<&Path>,
+    sandbox: Option<&Sandbox>,
+    capture: bool,
+) -> IoResult<(i32, Option<(Vec<u8>, Vec<u8>)>)>
+where
+    C: AsRef<OsStr>,
+    A: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+    E: IntoIterator<Item = (K, V)>,
+    K: AsRef<OsStr>,
+    V: AsRef<OsStr>,
+{
+    let signal_handle = spawn_signal_listener_task()?;
+    let signal_aborter = signal_handle.abort_handle();
+
+    /*
+        Important - we do not want to leave any zombie
+        processes behind if this async function is cancelled.
+
+        Note that since we also want to spawn the child process as part
+        of the current process group, we have to use the builder API from
+        `command-group` to spawn the child process, or this won't work.
+
+        The newer `process-wrap` crate claims to also support this behavior
+        for inheriting process group but it doesn't seem to work as expected.
+    */
+    let mut command = Command::new(command);
+    if let Some(cwd) = cwd {
+        command.current_dir(cwd);
+    }
+    if let Some(sandbox) = sandbox {
+        sandbox.apply(&mut command);
+    }
+    if capture {
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    }
+    let mut child = {
+        #[cfg(unix)]
+        {
+            command.args(args).envs(env).kill_on_drop(true).spawn()?
+        }
+        #[cfg(windows)]
+        {
+            command
+                .args(args)
+                .envs(env)
+                .group()
+                .kill_on_drop(true)
+                .spawn()?
+        }
+    };
+
+    /*
+        Captured output must be read while the command is running, since
+        the command blocks once it has filled up the buffer of a pipe.
+    */
+    let readers = if capture {
+        #[cfg(unix)]
+        let (stdout, stderr) = (child.stdout.take(), child.stderr.take());
+        #[cfg(windows)]
+        let (stdout, stderr) = (child.inner().stdout.take(), child.inner().stderr.take());
+        Some((spawn(read_pipe(stdout)), spawn(read_pipe(stderr))))
+    } else {
+        None
+    };
+
+    let code = tokio::select! {
+        // If the spawned process exits cleanly, we'll return its exit code,
+        // which may or may not exist. Interpret a non-existent code as 1.
+        command_result = child.wait() => {
+            let code = command_result.ok().and_then(|s| s.code()).unwrap_or(1);
+            signal_aborter.abort();
+            code
+        }
+        // If the command was manually interrupted by a signal, we will
+        // return a special exit code for the signal. More details above.
+        task_result = signal_handle => {
+            child.kill().await.ok();
+            task_result.unwrap_or(EXIT_CODE_GOT_SIGNAL)
+        }
+    };
+
+    let output = match readers {
+        Some((stdout, stderr)) => Some((stdout.await??, stderr.await??)),
+        None => None,
+    };
+
+    Ok((code, output))
+}
+
+async fn read_pipe(pipe: Option<impl AsyncRead + Unpin>) -> IoResult<Vec<u8>> {
+    let mut contents = Vec::new();
+    if let Some(mut pipe) = pipe {
+        pipe.read_to_end(&mut contents).await?;
+    }
+    Ok(contents)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn captures_output_and_exit_code() {
+        // Writes more than a pipe buffer holds, to make sure
+        // that output is read while the command is running
+        let script = "head -c 200000 /dev/zero; echo err >&2; exit 3";
+        let output =
+            run_interruptible_captured("sh", ["-c", script], [("ROKIT_TEST", "1")], None, None)
+                .await
+                .unwrap();
+
+        assert_eq!(output.code, 3);
+        assert_eq!(output.stdout.len(), 200_000);
+        assert_eq!(output.stderr, b"err\n");
+    }
+}
@@ -0,0 +1,200 @@
+use std::{
+    ffi::{OsStr, OsString},
+    io::Result as IoResult,
+    path::Path,
+};
+
+use tempfile::TempDir;
+use tokio::process::Command;
+
+/**
+    Environment variables that sandboxed tools may inherit from
+    the current process - anything else, such as access tokens
+    and other credentials, is never passed to sandboxed tools.
+*/
+const INHERITED_VARS: &[&str] = &[
+    "PATH",
+    "TERM",
+    "COLORTERM",
+    "NO_COLOR",
+    "FORCE_COLOR",
+    "LANG",
+    "LANGUAGE",
+    "TZ",
+    // Windows needs these for most programs to even start
+    "SYSTEMROOT",
+    "WINDIR",
+    "COMSPEC",
+    "PATHEXT",
+];
+
+/**
+    Prefixes of environment variables that sandboxed
+    tools may inherit from the current process.
+*/
+const INHERITED_VAR_PREFIXES: &[&str] = &["LC_"];
+
+/**
+    Environment variables pointing to user directories, which
+    are replaced with the temporary home directory of the sandbox.
+*/
+const HOME_VARS: &[&str] = &[
+    "HOME",
+    "TMPDIR",
+    "XDG_CONFIG_HOME",
+    "XDG_CACHE_HOME",
+    "XDG_DATA_HOME",
+    "XDG_STATE_HOME",
+    "USERPROFILE",
+    "APPDATA",
+    "LOCALAPPDATA",
+    "TEMP",
+    "TMP",
+];
+
+/**
+    A restricted environment for running less trusted tools, enabled
+    using the `sandbox` option for a tool in a Rokit manifest:
+
+    - Only a small set of environment variables, such as `PATH` and locale
+      settings, are inherited - credentials in the environment are never passed on.
+    - The home directory, and other user directories, are replaced
+      with a new temporary directory, removed once the sandbox is dropped.
+    - On Linux, the tool and all of its child processes are never allowed
+      to gain any new privileges, such as when running setuid binaries.
+
+    Note that this limits what a tool can accidentally read or change,
+    but the tool may still access the filesystem and network - it is
+    not a replacement for only running trusted tools.
+*/
+#[derive(Debug)]
+pub struct Sandbox {
+    home: TempDir,
+}
+
+impl Sandbox {
+    /**
+        Creates a new sandbox, with its own temporary home directory.
+
+        # Errors
+
+        - If the temporary home directory could not be created.
+    */
+    pub fn new() -> IoResult<Self> {
+        let home = tempfile::Builder::new()
+            .prefix("rokit-sandbox-")
+            .tempdir()?;
+        Ok(Self { home })
+    }
+
+    /**
+        Gets the path to the temporary home directory of this sandbox.
+    */
+    #[must_use]
+    pub fn home_dir(&self) -> &Path {
+        self.home.path()
+    }
+
+    /**
+        Filters the given environment variables down to the ones that
+        sandboxed tools may inherit, and adds variables for user directories
+        that point to the temporary home directory of this sandbox.
+    */
+    #[must_use]
+    pub fn filter_env<I, K, V>(&self, vars: I) -> Vec<(OsString, OsString)>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        let inherited = vars.into_iter().filter_map(|(key, value)| {
+            let name = key.as_ref().to_str()?;
+            let is_inherited = INHERITED_VARS
+                .iter()
+                .any(|var| var.eq_ignore_ascii_case(name))
+                || INHERITED_VAR_PREFIXES
+                    .iter()
+                    .any(|prefix| name.starts_with(prefix));
+            if is_inherited {
+                Some((key.as_ref().to_os_string(), value.as_ref().to_os_string()))
+            } else {
+                None
+            }
+        });
+        let home = HOME_VARS.iter().map(|var| {
+            (
+                OsString::from(var),
+                self.home_dir().as_os_str().to_os_string(),
+            )
+        });
+        inherited.chain(home).collect()
+    }
+
+    /**
+        Applies this sandbox to the given command, which must happen before
+        any other environment variables are set for the command.
+    */
+    pub(super) fn apply(&self, command: &mut Command) {
+        command.env_clear();
+        command.envs(self.filter_env(std::env::vars_os()));
+
+        #[cfg(target_os = "linux")]
+        {
+            // SAFETY: The closure runs in the forked child process before exec, and
+            // only calls prctl, which is async-signal-safe and does not allocate
+            unsafe {
+                command.pre_exec(|| {
+                    if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) == 0 {
+                        Ok(())
+                    } else {
+                        Err(std::io::Error::last_os_error())
+                    }
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(vars: &[(OsString, OsString)]) -> Vec<&str> {
+        vars.iter().map(|(key, _)| key.to_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn filters_environment() {
+        let sandbox = Sandbox::new().unwrap();
+        let vars = sandbox.filter_env([
+            ("PATH", "/usr/bin"),
+            ("LC_ALL", "C"),
+            ("GITHUB_TOKEN", "secret"),
+            ("AWS_SECRET_ACCESS_KEY", "secret"),
+            ("HOME", "/home/user"),
+        ]);
+
+        let names = names(&vars);
+        assert!(names.contains(&"PATH"));
+        assert!(names.contains(&"LC_ALL"));
+        assert!(!names.contains(&"GITHUB_TOKEN"));
+        assert!(!names.contains(&"AWS_SECRET_ACCESS_KEY"));
+
+        // The home directory must always point to the sandbox
+        let homes = vars
+            .iter()
+            .filter(|(key, _)| key == "HOME")
+            .map(|(_, value)| value.as_os_str())
+            .collect::<Vec<_>>();
+        assert_eq!(homes, [sandbox.home_dir().as_os_str()]);
+    }
+
+    #[test]
+    fn removes_home_when_dropped() {
+        let sandbox = Sandbox::new().unwrap();
+        let home = sandbox.home_dir().to_path_buf();
+        assert!(home.is_dir());
+        drop(sandbox);
+        assert!(!home.exists());
+    }
+}
@@ -0,0 +1,142 @@
+use std::{
+    collections::HashMap,
+    env::consts::EXE_SUFFIX,
+    io::{Cursor, Write},
+};
+
+use flate2::{write::GzEncoder, Compression};
+use semver::Version;
+use serde_json::Value as JsonValue;
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+use crate::util::hash::sha256_hex;
+
+use super::server::MockServer;
+
+const RELEASE_TEMPLATE: &str = include_str!("fixtures/github_release.json");
+const TEMPLATE_VERSION: &str = "7.4.1";
+
+pub const TOOL_AUTHOR: &str = "rojo-rbx";
+pub const TOOL_NAME: &str = "rojo";
+
+/**
+    A release of `rojo-rbx/rojo`, based on a real release from the GitHub API,
+    but with archives that contain a tiny placeholder script instead of the tool.
+
+    Asset sizes and digests match the generated archives.
+*/
+#[derive(Debug, Clone)]
+pub struct ReleaseFixture {
+    pub tag: String,
+    pub json: JsonValue,
+    pub archives: HashMap<u64, Vec<u8>>,
+}
+
+impl ReleaseFixture {
+    pub fn new(tag: &str) -> Self {
+        let version = tag.trim_start_matches('v');
+        let parsed = version.parse::<Version>().expect("valid version");
+        // NOTE: Asset ids must be unique across all releases that are
+        // served at the same time, so we offset them using the version
+        let id_offset = (parsed.major * 10_000 + parsed.minor * 100 + parsed.patch) * 10;
+
+        let mut json: JsonValue = serde_json::from_str(RELEASE_TEMPLATE).unwrap();
+        json["tag_name"] = tag.into();
+        json["name"] = format!("Rojo {version}").into();
+
+        let mut archives = HashMap::new();
+        for asset in json["assets"].as_array_mut().unwrap() {
+            let id = asset["id"].as_u64().unwrap() + id_offset;
+            let name = asset["name"]
+                .as_str()
+                .unwrap()
+                .replace(TEMPLATE_VERSION, version);
+            let archive = if let Some(stem) = name.strip_suffix(".zip") {
+                zip_archive(TOOL_NAME, &binary_contents(stem))
+            } else {
+                let stem = name.strip_suffix(".tar.gz").expect("zip or tar.gz asset");
+                tar_gz_archive(TOOL_NAME, &binary_contents(stem))
+            };
+
+            asset["id"] = id.into();
+            asset["name"] = name.into();
+            asset["size"] = archive.len().into();
+            asset["digest"] = format!("sha256:{}", sha256_hex(&archive)).into();
+            archives.insert(id, archive);
+        }
+
+        Self {
+            tag: tag.to_string(),
+            json,
+            archives,
+        }
+    }
+
+    /**
+        Serves this release and all of its assets using the given server.
+
+        If `latest` is true, this release will also be served as the latest release.
+    */
+    pub fn serve(&self, server: &MockServer, latest: bool) {
+        let repo = format!("/repos/{TOOL_AUTHOR}/{TOOL_NAME}");
+
+        let mut json = self.json.clone();
+        for asset in json["assets"].as_array_mut().unwrap() {
+            let id = asset["id"].as_u64().unwrap();
+            asset["url"] = format!("{}{repo}/releases/assets/{id}", server.url()).into();
+        }
+
+        server.route_json(format!("{repo}/releases/tags/{}", self.tag), &json);
+        if latest {
+            server.route_json(format!("{repo}/releases/latest"), &json);
+        }
+        for (id, archive) in &self.archives {
+            server.route_bytes(format!("{repo}/releases/assets/{id}"), archive.clone());
+        }
+    }
+}
+
+/**
+    Returns the contents of the executable found inside the asset with the
+    given name, excluding extensions - this matches [`Artifact::name`].
+
+    [`Artifact::name`]: crate::sources::Artifact::name
+*/
+pub fn binary_contents(artifact_name: &str) -> Vec<u8> {
+    format!("#!/bin/sh\necho \"{artifact_name}\"\n").into_bytes()
+}
+
+pub fn zip_archive(file_name: &str, contents: &[u8]) -> Vec<u8> {
+    zip_archive_with_path(&format!("{file_name}{EXE_SUFFIX}"), contents)
+}
+
+pub fn zip_archive_with_path(path: &str, contents: &[u8]) -> Vec<u8> {
+    zip_archive_with_paths(&[(path, contents)])
+}
+
+pub fn zip_archive_with_paths(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().unix_permissions(0o755);
+    for (path, contents) in files {
+        writer.start_file(*path, options).unwrap();
+        writer.write_all(contents).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+pub fn tar_gz_archive(file_name: &str, contents: &[u8]) -> Vec<u8> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o755);
+    header.set_cksum();
+
+    let mut builder = tar::Builder::new(Vec::new());
+    builder
+        .append_data(&mut header, format!("{file_name}{EXE_SUFFIX}"), contents)
+        .unwrap();
+    let tar = builder.into_inner().unwrap();
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&tar).unwrap();
+    encoder.finish().unwrap()
+}
@@ -0,0 +1,48 @@
+/*
+    Test harness for exercising the full add / install / update pipeline
+    against a local mock of the GitHub API, without touching the network.
+
+    Consists of a mock server built on `wiremock`, fixtures for releases and their
+    archives, and a temporary `Home` wired up to an `ArtifactSource`
+    that talks to the mock server instead of the real GitHub API.
+*/
+
+use tempfile::TempDir;
+
+use crate::{result::RokitResult, sources::ArtifactSource, storage::Home};
+
+mod fixtures;
+mod pipeline;
+mod server;
+
+pub use self::fixtures::*;
+pub use self::server::*;
+
+/**
+    A temporary Rokit home, and an artifact source pointing at a mock server,
+    along with a temporary directory for projects and other files.
+
+    Everything is cleaned up when dropped.
+*/
+pub struct TestEnv {
+    pub server: MockServer,
+    pub home: Home,
+    pub source: ArtifactSource,
+    pub dir: TempDir,
+}
+
+impl TestEnv {
+    pub async fn new() -> RokitResult<Self> {
+        let dir = TempDir::new()?;
+        let server = MockServer::start().await;
+        let home = Home::new_temporary().await?;
+        let source = ArtifactSource::new_mocked(&server.url())?
+            .with_download_dir(home.path().join("downloads"));
+        Ok(Self {
+            server,
+            home,
+            source,
+            dir,
+        })
+    }
+}
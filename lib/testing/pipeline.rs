@@ -0,0 +1,1574 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::{Duration, UNIX_EPOCH},
+};
+
+use semver::Version;
+use tokio::fs::{create_dir_all, read, read_dir, write};
+
+use crate::{
+    descriptor::Descriptor,
+    discovery::{DiscoveredManifest, ManifestKind},
+    install::{
+        execute, lock, plan, ExecuteOptions, InstallAction, InstallOutcome, InstallProgress,
+        LinkReason, PlanOptions, SkipReason,
+    },
+    manifests::{Lockfile, RokitManifest, ToolOptions, LOCKFILE_NAME},
+    result::{ErrorKind, RokitError},
+    sources::{
+        github::GithubError, select_release_artifact, Artifact, ArtifactFormat, ArtifactProvider,
+        DownloadError, ExtractLimits,
+    },
+    tool::{ToolAlias, ToolId, ToolSpec},
+    util::hash::sha256_hex,
+};
+
+use super::{
+    binary_contents, zip_archive_with_path, zip_archive_with_paths, MockResponse, ReleaseFixture,
+    TestEnv, TOOL_AUTHOR, TOOL_NAME,
+};
+
+fn tool_id() -> ToolId {
+    format!("{TOOL_AUTHOR}/{TOOL_NAME}").parse().unwrap()
+}
+
+fn tool_spec(version: &str) -> ToolSpec {
+    (tool_id(), version.parse::<Version>().unwrap()).into()
+}
+
+fn most_compatible(artifacts: &[Artifact]) -> Artifact {
+    Artifact::sort_by_system_compatibility(artifacts)
+        .into_iter()
+        .next()
+        .expect("fixture release should have a compatible artifact")
+}
+
+#[tokio::test]
+async fn install_latest_release() {
+    let env = TestEnv::new().await.unwrap();
+    ReleaseFixture::new("v7.3.0").serve(&env.server, false);
+    ReleaseFixture::new("v7.4.1").serve(&env.server, true);
+
+    let release = env.source.get_latest_release(&tool_id()).await.unwrap();
+    assert!(release.changelog.is_some());
+    assert_eq!(release.artifacts.len(), 5);
+    assert_eq!(release.tag_name.as_deref(), Some("v7.4.1"));
+    assert!(!release.prerelease);
+    assert_eq!(release.author.as_deref(), Some("github-actions[bot]"));
+    assert_eq!(release.published_date(), Some("2024-02-20"));
+
+    let artifact = most_compatible(&release.artifacts);
+    assert_eq!(artifact.tool_spec, tool_spec("7.4.1"));
+
+    let contents = env
+        .source
+        .download_artifact_contents(&artifact)
+        .await
+        .unwrap();
+    let extracted = artifact.extract_contents(contents).await.unwrap();
+    assert_eq!(
+        extracted,
+        binary_contents(artifact.name.as_deref().unwrap())
+    );
+
+    let storage = env.home.tool_storage();
+    storage
+        .replace_tool_contents(&artifact.tool_spec, &extracted)
+        .await
+        .unwrap();
+    assert!(env
+        .home
+        .tool_cache()
+        .add_installed(artifact.tool_spec.clone()));
+    env.home.save().await.unwrap();
+
+    let installed = read(storage.tool_path(&artifact.tool_spec)).await.unwrap();
+    assert_eq!(installed, extracted);
+    assert_eq!(
+        storage.tool_sha256(&artifact.tool_spec).await,
+        Some(sha256_hex(&extracted))
+    );
+    assert_eq!(storage.tool_sha256(&tool_spec("7.3.0")).await, None);
+    assert!(env.home.tool_cache().is_installed(&tool_spec("7.4.1")));
+    assert!(storage.has_tool_binary(&tool_spec("7.4.1")).await);
+    assert!(!storage.has_tool_binary(&tool_spec("7.3.0")).await);
+
+    // Completed downloads should never leave partial files behind
+    let mut downloads = read_dir(env.home.path().join("downloads")).await.unwrap();
+    while let Some(entry) = downloads.next_entry().await.unwrap() {
+        assert_ne!(entry.path().extension(), Some("partial".as_ref()));
+    }
+
+    // Binaries that were emptied, such as by a partial cache restore, are not usable
+    write(storage.tool_path(&tool_spec("7.4.1")), b"")
+        .await
+        .unwrap();
+    assert!(!storage.has_tool_binary(&tool_spec("7.4.1")).await);
+}
+
+#[tokio::test]
+async fn skip_non_installable_assets() {
+    let env = TestEnv::new().await.unwrap();
+    let mut fixture = ReleaseFixture::new("v7.4.1");
+    let assets = fixture.json["assets"].as_array_mut().unwrap();
+    let template = assets[0].clone();
+    for (id, name) in [
+        (1, "rojo-7.4.1-windows-x86_64.msi"),
+        (2, "rojo-7.4.1-linux-x86_64.zip.sha256"),
+        (3, "rojo_7.4.1_amd64.deb"),
+        (4, "SHA256SUMS"),
+    ] {
+        let mut asset = template.clone();
+        asset["id"] = id.into();
+        asset["name"] = name.into();
+        assets.push(asset);
+    }
+    fixture.serve(&env.server, true);
+
+    let release = env.source.get_latest_release(&tool_id()).await.unwrap();
+    assert_eq!(release.artifacts.len(), 5);
+    assert!(release
+        .artifacts
+        .iter()
+        .all(|artifact| artifact.format.is_some()));
+}
+
+#[tokio::test]
+async fn verify_published_checksums() {
+    let env = TestEnv::new().await.unwrap();
+    let mut fixture = ReleaseFixture::new("v7.4.1");
+    let assets = fixture.json["assets"].as_array_mut().unwrap();
+    let checksums = assets
+        .iter()
+        .skip(1) // Leave out one asset, which then can not be verified
+        .map(|asset| {
+            let id = asset["id"].as_u64().unwrap();
+            let name = asset["name"].as_str().unwrap();
+            format!("{}  {name}", sha256_hex(&fixture.archives[&id]))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .into_bytes();
+    let unverified_name = assets[0]["name"].as_str().unwrap().to_string();
+    let mut asset = assets[0].clone();
+    asset["id"] = 1.into();
+    asset["name"] = "SHA256SUMS".into();
+    asset["size"] = checksums.len().into();
+    asset["digest"] = format!("sha256:{}", sha256_hex(&checksums)).into();
+    assets.push(asset);
+    fixture.archives.insert(1, checksums);
+    fixture.serve(&env.server, true);
+
+    let release = env.source.get_latest_release(&tool_id()).await.unwrap();
+    assert_eq!(release.checksums.len(), 1);
+    for artifact in &release.artifacts {
+        let contents = env
+            .source
+            .download_artifact_contents(artifact)
+            .await
+            .unwrap();
+        let verified = env
+            .source
+            .verify_release_checksum(&release, artifact, &contents)
+            .await
+            .unwrap();
+        let is_unverified = unverified_name.starts_with(artifact.name.as_deref().unwrap());
+        assert_eq!(verified, !is_unverified);
+
+        if verified {
+            let tampered = env
+                .source
+                .verify_release_checksum(&release, artifact, b"tampered")
+                .await;
+            assert!(matches!(
+                tampered,
+                Err(RokitError::Download(e)) if matches!(*e, DownloadError::HashMismatch { .. })
+            ));
+        }
+    }
+}
+
+#[tokio::test]
+async fn extract_all_archive_formats() {
+    let env = TestEnv::new().await.unwrap();
+    ReleaseFixture::new("v7.4.1").serve(&env.server, true);
+
+    let release = env
+        .source
+        .get_specific_release(&tool_spec("7.4.1"))
+        .await
+        .unwrap();
+    for artifact in &release.artifacts {
+        let contents = env
+            .source
+            .download_artifact_contents(artifact)
+            .await
+            .unwrap();
+        let extracted = artifact.extract_contents(contents).await.unwrap();
+        assert_eq!(
+            extracted,
+            binary_contents(artifact.name.as_deref().unwrap())
+        );
+    }
+}
+
+#[tokio::test]
+async fn extract_file_matching_pattern() {
+    let env = TestEnv::new().await.unwrap();
+    ReleaseFixture::new("v7.4.1").serve(&env.server, true);
+
+    let release = env.source.get_latest_release(&tool_id()).await.unwrap();
+    let artifact = Artifact {
+        format: Some(ArtifactFormat::Zip),
+        ..most_compatible(&release.artifacts)
+    };
+
+    // The file in the root would normally be preferred, but the pattern
+    // given should always take precedence over any of the usual heuristics
+    let file_name = format!("{TOOL_NAME}{}", std::env::consts::EXE_SUFFIX);
+    let decoy = b"decoy".to_vec();
+    let wanted = binary_contents("wanted");
+    let contents = zip_archive_with_paths(&[
+        (file_name.as_str(), &decoy),
+        (&format!("dist/bin/{file_name}"), &wanted),
+    ]);
+
+    let extracted = artifact
+        .extract_contents_matching(contents.clone(), None, ExtractLimits::default())
+        .await
+        .unwrap();
+    assert_eq!(extracted, decoy);
+
+    let extracted = artifact
+        .extract_contents_matching(contents.clone(), Some("**/bin/*"), ExtractLimits::default())
+        .await
+        .unwrap();
+    assert_eq!(extracted, wanted);
+
+    let result = artifact
+        .extract_contents_matching(contents, Some("[invalid"), ExtractLimits::default())
+        .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn install_repository_archive_for_branch() {
+    let env = TestEnv::new().await.unwrap();
+    let sha = "0123456789abcdef0123456789abcdef01234567";
+    env.server.route_json(
+        format!("/repos/{TOOL_AUTHOR}/{TOOL_NAME}/commits/main"),
+        &serde_json::json!({ "sha": sha }),
+    );
+
+    // Repository archives contain a single directory named after the commit
+    let file_name = format!("{TOOL_NAME}{}", std::env::consts::EXE_SUFFIX);
+    let binary = binary_contents("branch");
+    env.server.route_bytes(
+        format!("/repos/{TOOL_AUTHOR}/{TOOL_NAME}/zipball/{sha}"),
+        zip_archive_with_paths(&[
+            (
+                &format!("{TOOL_AUTHOR}-{TOOL_NAME}-{sha}/README.md"),
+                b"readme",
+            ),
+            (
+                &format!("{TOOL_AUTHOR}-{TOOL_NAME}-{sha}/bin/{file_name}"),
+                &binary,
+            ),
+        ]),
+    );
+
+    let spec: ToolSpec = format!("github-branch:{TOOL_AUTHOR}/{TOOL_NAME}@main")
+        .parse()
+        .unwrap();
+    let release = env.source.get_specific_release(&spec).await.unwrap();
+    assert_eq!(release.artifacts.len(), 1);
+
+    // The branch is resolved to its current commit, which is used as the version
+    let artifact = release.artifacts[0].clone();
+    assert_eq!(artifact.tool_spec.git_ref(), Some(sha));
+    assert_eq!(artifact.tool_spec.id(), spec.id());
+
+    let contents = env
+        .source
+        .download_artifact_contents(&artifact)
+        .await
+        .unwrap();
+    let extracted = artifact
+        .extract_contents_matching(contents, Some("*/bin/*"), ExtractLimits::default())
+        .await
+        .unwrap();
+    assert_eq!(extracted, binary);
+
+    let missing: ToolSpec = format!("github-branch:{TOOL_AUTHOR}/{TOOL_NAME}@missing")
+        .parse()
+        .unwrap();
+    let result = env.source.get_specific_release(&missing).await;
+    assert!(matches!(result, Err(e) if e.kind() == ErrorKind::NotFound));
+}
+
+#[tokio::test]
+async fn install_all_files_next_to_binary() {
+    let env = TestEnv::new().await.unwrap();
+    ReleaseFixture::new("v7.4.1").serve(&env.server, true);
+
+    let release = env.source.get_latest_release(&tool_id()).await.unwrap();
+    let artifact = Artifact {
+        format: Some(ArtifactFormat::Zip),
+        ..most_compatible(&release.artifacts)
+    };
+
+    let file_name = format!("{TOOL_NAME}{}", std::env::consts::EXE_SUFFIX);
+    let binary = binary_contents("binary");
+    let contents = zip_archive_with_paths(&[
+        ("README.md", b"readme"),
+        (&format!("dist/{file_name}"), &binary),
+        ("dist/library.dll", b"library"),
+        ("dist/completions/rojo.bash", b"completions"),
+    ]);
+
+    let (extracted, files) = artifact
+        .extract_all_contents_matching(contents, None, ExtractLimits::default())
+        .await
+        .unwrap();
+    assert_eq!(extracted, binary);
+
+    // Only files next to the binary should be kept, relative to it
+    let mut paths = files
+        .iter()
+        .map(|file| file.path.to_string_lossy().replace('\\', "/"))
+        .collect::<Vec<_>>();
+    paths.sort();
+    assert_eq!(paths, vec!["completions/rojo.bash", "library.dll"]);
+
+    let storage = env.home.tool_storage();
+    let spec = artifact.tool_spec.clone();
+    storage
+        .replace_tool_contents_with_files(&spec, &extracted, &files)
+        .await
+        .unwrap();
+
+    let tool_path = storage.tool_path(&spec);
+    let tool_dir = tool_path.parent().unwrap();
+    assert_eq!(read(&tool_path).await.unwrap(), binary);
+    assert_eq!(
+        read(tool_dir.join("library.dll")).await.unwrap(),
+        b"library"
+    );
+    assert_eq!(
+        read(tool_dir.join("completions").join("rojo.bash"))
+            .await
+            .unwrap(),
+        b"completions"
+    );
+}
+
+/*
+    A minimal 64-bit little-endian ELF executable header for the given machine.
+*/
+#[cfg(target_os = "linux")]
+fn elf_header(machine: u16) -> Vec<u8> {
+    let mut header = vec![0x7f, b'E', b'L', b'F', 2, 1, 1];
+    header.resize(16, 0);
+    header.extend_from_slice(&2u16.to_le_bytes()); // e_type (executable)
+    header.extend_from_slice(&machine.to_le_bytes());
+    header.extend_from_slice(&1u32.to_le_bytes()); // e_version
+    header.extend_from_slice(&[0; 24]); // e_entry, e_phoff, e_shoff
+    header.extend_from_slice(&0u32.to_le_bytes()); // e_flags
+    for value in [64u16, 56, 0, 64, 0, 0] {
+        header.extend_from_slice(&value.to_le_bytes());
+    }
+    header
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn reject_binary_for_other_architecture() {
+    use crate::descriptor::Arch;
+
+    const EM_X86_64: u16 = 62;
+    const EM_AARCH64: u16 = 183;
+
+    let env = TestEnv::new().await.unwrap();
+    ReleaseFixture::new("v7.4.1").serve(&env.server, true);
+
+    let release = env.source.get_latest_release(&tool_id()).await.unwrap();
+    let artifact = Artifact {
+        format: Some(ArtifactFormat::Zip),
+        ..most_compatible(&release.artifacts)
+    };
+
+    let (native, foreign) = if Arch::current_system() == Arch::Arm64 {
+        (EM_AARCH64, EM_X86_64)
+    } else {
+        (EM_X86_64, EM_AARCH64)
+    };
+
+    let contents = zip_archive_with_path(TOOL_NAME, &elf_header(native));
+    let extracted = artifact.extract_contents(contents).await.unwrap();
+    assert_eq!(extracted, elf_header(native));
+
+    let contents = zip_archive_with_path(TOOL_NAME, &elf_header(foreign));
+    let err = artifact.extract_contents(contents).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::IncompatiblePlatform);
+}
+
+#[tokio::test]
+async fn find_native_alternative_in_release() {
+    let env = TestEnv::new().await.unwrap();
+    ReleaseFixture::new("v7.4.1").serve(&env.server, true);
+
+    let release = env.source.get_latest_release(&tool_id()).await.unwrap();
+    let native = most_compatible(&release.artifacts);
+    assert_eq!(native.find_native_alternative(&release.artifacts), None);
+
+    // Any other artifact should point back to the one native artifact
+    for artifact in &release.artifacts {
+        if artifact.name != native.name {
+            assert_eq!(
+                artifact.find_native_alternative(&release.artifacts),
+                Some(&native)
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn latest_prerelease_by_version() {
+    let env = TestEnv::new().await.unwrap();
+    let releases = ["v7.4.1", "v7.5.0-rc.1", "v7.3.0", "nightly"]
+        .into_iter()
+        .map(|tag| {
+            let mut fixture = ReleaseFixture::new(if tag == "nightly" { "v7.6.0" } else { tag });
+            fixture.json["tag_name"] = tag.into();
+            fixture.json["prerelease"] = tag.contains("rc").into();
+            fixture.json
+        })
+        .collect::<Vec<_>>();
+    env.server.route_json(
+        format!("/repos/{TOOL_AUTHOR}/{TOOL_NAME}/releases?per_page=30"),
+        &releases.into(),
+    );
+
+    // Releases without valid versions, such as nightly builds, are skipped
+    let release = env.source.get_latest_prerelease(&tool_id()).await.unwrap();
+    assert!(release
+        .artifacts
+        .iter()
+        .all(|artifact| artifact.tool_spec == tool_spec("7.5.0-rc.1")));
+}
+
+#[tokio::test]
+async fn latest_release_in_channel() {
+    let env = TestEnv::new().await.unwrap();
+    let releases = [
+        "v7.5.0-nightly.2",
+        "v7.4.1",
+        "v7.5.0-rc.1",
+        "v7.5.0-nightly.1",
+    ]
+    .into_iter()
+    .map(|tag| {
+        let mut fixture = ReleaseFixture::new(tag);
+        fixture.json["prerelease"] = tag.contains('-').into();
+        fixture.json
+    })
+    .collect::<Vec<_>>();
+    env.server.route_json(
+        format!("/repos/{TOOL_AUTHOR}/{TOOL_NAME}/releases?per_page=30"),
+        &releases.into(),
+    );
+
+    let latest_in = |channel: &'static str| {
+        let source = &env.source;
+        async move {
+            let release = source
+                .get_latest_release_in_channel(&tool_id(), channel)
+                .await?;
+            Ok::<_, RokitError>(release.artifacts[0].tool_spec.clone())
+        }
+    };
+    assert_eq!(latest_in("stable").await.unwrap(), tool_spec("7.4.1"));
+    assert_eq!(
+        latest_in("Nightly").await.unwrap(),
+        tool_spec("7.5.0-nightly.2")
+    );
+
+    let err = latest_in("beta").await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+#[tokio::test]
+async fn latest_release_matching_requirement() {
+    let env = TestEnv::new().await.unwrap();
+    let releases = ["v7.4.1", "v7.3.2", "v7.3.0", "v7.3.3-rc.1"]
+        .into_iter()
+        .map(|tag| ReleaseFixture::new(tag).json)
+        .collect::<Vec<_>>();
+    env.server.route_json(
+        format!("/repos/{TOOL_AUTHOR}/{TOOL_NAME}/releases?per_page=30"),
+        &releases.into(),
+    );
+
+    // Prereleases only match requirements that contain a prerelease version
+    let req = "~7.3".parse().unwrap();
+    let release = env
+        .source
+        .get_latest_release_matching(&tool_id(), &req)
+        .await
+        .unwrap();
+    assert!(release
+        .artifacts
+        .iter()
+        .all(|artifact| artifact.tool_spec == tool_spec("7.3.2")));
+
+    let req = "^8".parse().unwrap();
+    let result = env
+        .source
+        .get_latest_release_matching(&tool_id(), &req)
+        .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn specific_release_without_tag_prefix() {
+    let env = TestEnv::new().await.unwrap();
+    ReleaseFixture::new("7.3.0").serve(&env.server, false);
+
+    let release = env
+        .source
+        .get_specific_release(&tool_spec("7.3.0"))
+        .await
+        .unwrap();
+    assert!(release
+        .artifacts
+        .iter()
+        .all(|artifact| artifact.tool_spec == tool_spec("7.3.0")));
+
+    let paths = env
+        .server
+        .requests()
+        .into_iter()
+        .map(|request| request.path)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        vec![
+            "/repos/rojo-rbx/rojo/releases/tags/v7.3.0",
+            "/repos/rojo-rbx/rojo/releases/tags/7.3.0",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn release_changelog() {
+    let env = TestEnv::new().await.unwrap();
+    let mut older = ReleaseFixture::new("v7.3.0");
+    older.json["body"] = "## Fixed\n\n- A bug".into();
+    older.serve(&env.server, false);
+    let mut latest = ReleaseFixture::new("v7.4.1");
+    latest.json["body"] = "  \n".into();
+    latest.serve(&env.server, true);
+
+    let version = Version::new(7, 3, 0);
+    let changelog = env
+        .source
+        .get_release_changelog(&tool_id(), Some(&version))
+        .await
+        .unwrap();
+    assert_eq!(changelog.as_deref(), Some("## Fixed\n\n- A bug"));
+
+    // Blank release notes are the same as having no changelog at all
+    let changelog = env
+        .source
+        .get_release_changelog(&tool_id(), None)
+        .await
+        .unwrap();
+    assert_eq!(changelog, None);
+
+    let version = Version::new(1, 0, 0);
+    let err = env
+        .source
+        .get_release_changelog(&tool_id(), Some(&version))
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+#[tokio::test]
+async fn security_advisories() {
+    let env = TestEnv::new().await.unwrap();
+    let advisories = env
+        .source
+        .get_security_advisories(&tool_id())
+        .await
+        .unwrap();
+    assert!(advisories.is_empty());
+
+    env.server.route_json(
+        format!(
+            "/repos/{TOOL_AUTHOR}/{TOOL_NAME}/security-advisories?state=published&per_page=100"
+        ),
+        &serde_json::json!([{
+            "ghsa_id": "GHSA-abcd-efgh-ijkl",
+            "summary": "Path traversal when serving projects",
+            "severity": "high",
+            "html_url": "https://github.com/rojo-rbx/rojo/security/advisories/GHSA-abcd-efgh-ijkl",
+            "vulnerabilities": [{
+                "package": { "ecosystem": "other", "name": "rojo" },
+                "vulnerable_version_range": ">= 7.0.0, < 7.3.1",
+                "patched_versions": "7.3.1",
+            }],
+        }]),
+    );
+
+    let advisories = env
+        .source
+        .get_security_advisories(&tool_id())
+        .await
+        .unwrap();
+    assert_eq!(advisories.len(), 1);
+    let advisory = &advisories[0];
+    assert_eq!(advisory.id, "GHSA-abcd-efgh-ijkl");
+    assert!(advisory.affects(tool_spec("7.3.0").version()));
+    assert!(!advisory.affects(tool_spec("7.3.1").version()));
+    assert_eq!(
+        advisory.patched_version_for(tool_spec("7.3.0").version()),
+        Some(tool_spec("7.3.1").version())
+    );
+}
+
+#[tokio::test]
+async fn provenance_for_release() {
+    let env = TestEnv::new().await.unwrap();
+    ReleaseFixture::new("v7.3.0").serve(&env.server, false);
+    ReleaseFixture::new("v7.4.1").serve(&env.server, true);
+    env.server.route_json(
+        format!("/repos/{TOOL_AUTHOR}/{TOOL_NAME}"),
+        &serde_json::json!({
+            "html_url": "https://github.com/rojo-rbx/rojo",
+            "description": "Rojo enables Roblox developers to use professional-grade software engineering tools",
+            "stargazers_count": 1000,
+        }),
+    );
+
+    let latest = env.source.get_provenance(&tool_id(), None).await.unwrap();
+    assert_eq!(latest.stars, Some(1000));
+    assert_eq!(latest.release_tag.as_deref(), Some("v7.4.1"));
+    assert_eq!(latest.publisher.as_deref(), Some("github-actions[bot]"));
+    assert_eq!(latest.published_date(), Some("2024-02-20"));
+    assert!(!latest.assets.is_empty());
+    assert!(latest.assets.iter().all(|asset| asset.size.is_some()));
+
+    let version = tool_spec("7.3.0").version().clone();
+    let specific = env
+        .source
+        .get_provenance(&tool_id(), Some(&version))
+        .await
+        .unwrap();
+    assert_eq!(specific.release_tag.as_deref(), Some("v7.3.0"));
+    assert_eq!(specific.description, latest.description);
+
+    // Missing releases only leave out release details
+    let version = tool_spec("9.9.9").version().clone();
+    let missing = env
+        .source
+        .get_provenance(&tool_id(), Some(&version))
+        .await
+        .unwrap();
+    assert!(missing.release_tag.is_none());
+    assert!(missing.assets.is_empty());
+    assert_eq!(missing.stars, Some(1000));
+}
+
+#[tokio::test]
+async fn search_for_tools() {
+    let env = TestEnv::new().await.unwrap();
+    env.server.route_json(
+        "/search/repositories?q=rojo+topic%3Aroblox&sort=stars&order=desc&per_page=5",
+        &serde_json::json!({
+            "total_count": 2,
+            "items": [
+                {
+                    "full_name": "rojo-rbx/rojo",
+                    "html_url": "https://github.com/rojo-rbx/rojo",
+                    "description": "Rojo enables Roblox developers to use professional-grade software engineering tools",
+                    "stargazers_count": 1000,
+                },
+                {
+                    "full_name": "rojo-rbx/rojo-plugin",
+                    "html_url": "https://github.com/rojo-rbx/rojo-plugin",
+                    "description": " ",
+                    "stargazers_count": 10,
+                },
+            ],
+        }),
+    );
+
+    let results = env
+        .source
+        .search_tools("rojo", Some("roblox"), 5)
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].id, tool_id());
+    assert_eq!(results[0].stars, 1000);
+    assert!(results[0].description.is_some());
+    assert!(results[1].description.is_none());
+}
+
+#[tokio::test]
+async fn update_manifest_to_latest() {
+    let env = TestEnv::new().await.unwrap();
+    ReleaseFixture::new("v7.3.0").serve(&env.server, false);
+    ReleaseFixture::new("v7.4.1").serve(&env.server, true);
+
+    let project = env.dir.path().join("project");
+    let alias: ToolAlias = TOOL_NAME.parse().unwrap();
+    create_dir_all(&project).await.unwrap();
+    let mut manifest = RokitManifest::load_or_create(&project).await.unwrap();
+    manifest.add_tool(&alias, &tool_spec("7.3.0"));
+    manifest.save(&project).await.unwrap();
+
+    let release = env.source.get_latest_release(&tool_id()).await.unwrap();
+    let artifact = most_compatible(&release.artifacts);
+    let mut manifest = RokitManifest::load(&project).await.unwrap();
+    assert!(manifest.update_tool(&alias, &artifact.tool_spec));
+    manifest.save(&project).await.unwrap();
+
+    let manifest = RokitManifest::load(&project).await.unwrap();
+    assert_eq!(manifest.get_tool(&alias), Some(tool_spec("7.4.1")));
+}
+
+#[tokio::test]
+async fn missing_release_is_not_found() {
+    let env = TestEnv::new().await.unwrap();
+
+    let err = env.source.get_latest_release(&tool_id()).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+
+    let err = env
+        .source
+        .get_specific_release(&tool_spec("1.0.0"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+#[tokio::test]
+async fn unpublished_releases_are_reported() {
+    let env = TestEnv::new().await.unwrap();
+    let github_error = |err: RokitError| match err {
+        RokitError::GitHub(e) => *e,
+        e => panic!("expected a GitHub error, got {e:?}"),
+    };
+
+    // Releases may have no assets for a while, as they are being uploaded
+    let mut uploading = ReleaseFixture::new("v7.4.1");
+    uploading.json["assets"] = serde_json::json!([]);
+    uploading.json["body"] = "## Added\n\n- A feature".into();
+    uploading.serve(&env.server, true);
+
+    let err = env
+        .source
+        .get_specific_release(&tool_spec("7.4.1"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    assert!(matches!(
+        github_error(err),
+        GithubError::ReleaseHasNoAssets(_)
+    ));
+    let err = env.source.get_latest_release(&tool_id()).await.unwrap_err();
+    assert!(matches!(
+        github_error(err),
+        GithubError::ReleaseHasNoAssets(_)
+    ));
+
+    // Release notes can still be read before any assets exist
+    let changelog = env
+        .source
+        .get_release_changelog(&tool_id(), None)
+        .await
+        .unwrap();
+    assert_eq!(changelog.as_deref(), Some("## Added\n\n- A feature"));
+
+    let mut draft = ReleaseFixture::new("v7.5.0");
+    draft.json["draft"] = true.into();
+    draft.serve(&env.server, false);
+    let err = env
+        .source
+        .get_specific_release(&tool_spec("7.5.0"))
+        .await
+        .unwrap_err();
+    assert!(matches!(github_error(err), GithubError::DraftRelease(_)));
+
+    // Drafts are never picked as the latest release
+    env.server.route_json(
+        format!("/repos/{TOOL_AUTHOR}/{TOOL_NAME}/releases?per_page=30"),
+        &vec![draft.json.clone(), ReleaseFixture::new("v7.3.0").json].into(),
+    );
+    let release = env.source.get_latest_prerelease(&tool_id()).await.unwrap();
+    assert_eq!(release.tag_name.as_deref(), Some("v7.3.0"));
+}
+
+#[tokio::test]
+async fn resume_partial_download() {
+    let env = TestEnv::new().await.unwrap();
+    let fixture = ReleaseFixture::new("v7.4.1");
+    fixture.serve(&env.server, true);
+
+    let release = env.source.get_latest_release(&tool_id()).await.unwrap();
+    let artifact = most_compatible(&release.artifacts);
+    let id = artifact.id.as_deref().unwrap();
+    let archive = &fixture.archives[&id.parse::<u64>().unwrap()];
+
+    // Simulate a previous download that was interrupted halfway through
+    let half = archive.len() / 2;
+    let downloads = env.home.path().join("downloads");
+    create_dir_all(&downloads).await.unwrap();
+    let partial_path = env.source.partial_download_path(&artifact).unwrap();
+    write(&partial_path, &archive[..half]).await.unwrap();
+
+    let contents = env
+        .source
+        .download_artifact_contents(&artifact)
+        .await
+        .unwrap();
+    assert_eq!(&contents, archive);
+    assert!(!partial_path.exists());
+
+    let request = env.server.requests().pop().unwrap();
+    assert_eq!(
+        request.path,
+        format!("/repos/rojo-rbx/rojo/releases/assets/{id}")
+    );
+    assert_eq!(
+        request.headers.get("range"),
+        Some(&format!("bytes={half}-"))
+    );
+}
+
+#[tokio::test]
+async fn reject_corrupted_download() {
+    let env = TestEnv::new().await.unwrap();
+    let mut fixture = ReleaseFixture::new("v7.4.1");
+    for asset in fixture.json["assets"].as_array_mut().unwrap() {
+        asset["digest"] = format!("sha256:{}", "0".repeat(64)).into();
+    }
+    fixture.serve(&env.server, true);
+
+    let release = env.source.get_latest_release(&tool_id()).await.unwrap();
+    let artifact = most_compatible(&release.artifacts);
+
+    let err = env
+        .source
+        .download_artifact_contents(&artifact)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        RokitError::Download(ref e) if matches!(**e, DownloadError::HashMismatch { .. })
+    ));
+    assert_eq!(err.kind(), ErrorKind::Network);
+
+    // Corrupted downloads must never be resumed
+    let partial_path = env.source.partial_download_path(&artifact).unwrap();
+    assert!(!partial_path.exists());
+}
+
+#[tokio::test]
+async fn owner_token_is_used_for_owner() {
+    let env = TestEnv::new().await.unwrap();
+    ReleaseFixture::new("v7.4.1").serve(&env.server, true);
+
+    let tokens = HashMap::from([(TOOL_AUTHOR.to_uppercase(), "ghp_owner".to_string())]);
+    let source = env
+        .source
+        .clone()
+        .with_owner_tokens(ArtifactProvider::GitHub, &tokens)
+        .unwrap();
+
+    let release = source.get_latest_release(&tool_id()).await.unwrap();
+    let artifact = most_compatible(&release.artifacts);
+    source.download_artifact_contents(&artifact).await.unwrap();
+
+    let requests = env.server.requests();
+    assert_eq!(requests.len(), 2);
+    for request in requests {
+        assert_eq!(
+            request.headers.get("authorization").map(String::as_str),
+            Some("Bearer ghp_owner")
+        );
+    }
+}
+
+#[tokio::test]
+async fn select_release_artifact_for_other_targets() {
+    let env = TestEnv::new().await.unwrap();
+    ReleaseFixture::new("v7.4.1").serve(&env.server, true);
+
+    let spec = tool_spec("7.4.1");
+    let select = |triple: &'static str| {
+        let target = Descriptor::from_target_triple(triple).unwrap();
+        let source = env.source.clone();
+        let spec = spec.clone();
+        async move {
+            select_release_artifact(&source, &spec, target)
+                .await
+                .unwrap()
+        }
+    };
+
+    let artifact = select("aarch64-unknown-linux-gnu").await.unwrap();
+    assert_eq!(artifact.name.as_deref(), Some("rojo-7.4.1-linux-aarch64"));
+    assert_eq!(artifact.format, Some(ArtifactFormat::TarGz));
+    assert!(artifact.url.is_some());
+
+    let artifact = select("x86_64-pc-windows-msvc").await.unwrap();
+    assert_eq!(artifact.name.as_deref(), Some("rojo-7.4.1-windows-x86_64"));
+    assert_eq!(artifact.format, Some(ArtifactFormat::Zip));
+
+    // Apple Silicon prefers native artifacts over ones that need emulation
+    let artifact = select("aarch64-apple-darwin").await.unwrap();
+    assert_eq!(artifact.name.as_deref(), Some("rojo-7.4.1-macos-aarch64"));
+
+    assert!(select("aarch64-pc-windows-msvc").await.is_none());
+}
+
+#[tokio::test]
+async fn plan_install_without_executing() {
+    let env = TestEnv::new().await.unwrap();
+    let alias = |name: &str| name.parse::<ToolAlias>().unwrap();
+    let spec = |s: &str| s.parse::<ToolSpec>().unwrap();
+
+    // NOTE: Only the installed tool has a binary, and the untrusted
+    // tool is the only tool that has not been trusted by the user
+    let installed = tool_spec("7.4.1");
+    let storage = env.home.tool_storage();
+    storage
+        .replace_tool_contents(&installed, b"binary")
+        .await
+        .unwrap();
+    let tool_cache = env.home.tool_cache();
+    let _ = tool_cache.add_installed(installed.clone());
+    for id in ["author/wrapper", "author/selene", "author/plugin"] {
+        let _ = tool_cache.add_trust(id.parse().unwrap());
+    }
+    let _ = tool_cache.add_trust(installed.id().clone());
+
+    let manifest = DiscoveredManifest {
+        kind: ManifestKind::Rokit,
+        path: env.home.path().join("rokit.toml"),
+        tools: HashMap::from([
+            (alias("tool"), installed.clone()),
+            (alias("untrusted"), spec("author/untrusted@1.0.0")),
+            (alias("wrapper"), spec("author/wrapper@1.0.0")),
+            (alias("selene"), spec("author/selene@0.27.1")),
+            (alias("plugin"), spec("author/plugin@1.0.0")),
+        ]),
+        options: HashMap::from([
+            (
+                alias("plugin"),
+                ToolOptions {
+                    requires: vec![alias("untrusted")],
+                    ..ToolOptions::default()
+                },
+            ),
+            (
+                alias("wrapper"),
+                ToolOptions {
+                    requires: vec![alias("missing")],
+                    ..ToolOptions::default()
+                },
+            ),
+            (
+                alias("selene"),
+                ToolOptions {
+                    extract_all: true,
+                    ..ToolOptions::default()
+                },
+            ),
+        ]),
+    };
+
+    let manifests = [manifest];
+    let planned = plan(&env.home, &manifests, PlanOptions::default()).await;
+    assert_eq!(
+        planned.actions,
+        vec![
+            InstallAction::Resolve {
+                spec: spec("author/selene@0.27.1"),
+                exclude_assets: Vec::new(),
+                asset: None,
+                requires: BTreeMap::new(),
+            },
+            InstallAction::Download {
+                spec: spec("author/selene@0.27.1"),
+                sha256: None,
+            },
+            InstallAction::Extract {
+                spec: spec("author/selene@0.27.1"),
+                extract_all: true,
+                file_pattern: None,
+            },
+            InstallAction::Skip {
+                spec: spec("author/untrusted@1.0.0"),
+                reason: SkipReason::NotTrusted,
+            },
+            InstallAction::Skip {
+                spec: spec("author/wrapper@1.0.0"),
+                reason: SkipReason::MissingRequirement(alias("missing")),
+            },
+            InstallAction::Skip {
+                spec: installed.clone(),
+                reason: SkipReason::AlreadyInstalled,
+            },
+            // Tools are planned after all tools they require
+            InstallAction::Skip {
+                spec: spec("author/plugin@1.0.0"),
+                reason: SkipReason::RequirementNotInstalled(alias("untrusted")),
+            },
+            InstallAction::Link {
+                alias: alias("plugin"),
+                reason: LinkReason::Missing,
+            },
+            InstallAction::Link {
+                alias: alias("selene"),
+                reason: LinkReason::Missing,
+            },
+            InstallAction::Link {
+                alias: alias("tool"),
+                reason: LinkReason::Missing,
+            },
+            InstallAction::Link {
+                alias: alias("untrusted"),
+                reason: LinkReason::Missing,
+            },
+            InstallAction::Link {
+                alias: alias("wrapper"),
+                reason: LinkReason::Missing,
+            },
+        ]
+    );
+
+    // Forcing and skipping trust checks installs everything that can be installed
+    let options = PlanOptions {
+        force: true,
+        skip_trust_check: true,
+    };
+    let planned = plan(&env.home, &manifests, options).await;
+    assert_eq!(
+        planned.tools_to_install(),
+        [
+            &spec("author/selene@0.27.1"),
+            &spec("author/untrusted@1.0.0"),
+            &installed,
+            &spec("author/plugin@1.0.0"),
+        ]
+    );
+    env.home.save().await.unwrap();
+}
+
+fn single_tool_manifest(env: &TestEnv, spec: &ToolSpec) -> DiscoveredManifest {
+    DiscoveredManifest {
+        kind: ManifestKind::Rokit,
+        path: env.home.path().join("rokit.toml"),
+        tools: HashMap::from([(TOOL_NAME.parse().unwrap(), spec.clone())]),
+        options: HashMap::new(),
+    }
+}
+
+#[tokio::test]
+async fn execute_install_plan_with_progress() {
+    let env = TestEnv::new().await.unwrap();
+    ReleaseFixture::new("v7.4.1").serve(&env.server, true);
+    let spec = tool_spec("7.4.1");
+    let _ = env.home.tool_cache().add_trust(spec.id().clone());
+
+    let manifests = [single_tool_manifest(&env, &spec)];
+    let install_plan = plan(&env.home, &manifests, PlanOptions::default()).await;
+    let mut completed = Vec::new();
+    let mut chosen = Vec::new();
+    let results = execute(
+        &env.home,
+        &env.source,
+        &install_plan,
+        &ExecuteOptions::default(),
+        |progress| match progress {
+            InstallProgress::Completed(action) => completed.push(action.clone()),
+            InstallProgress::ArtifactChosen { artifact, .. } => chosen.push(artifact.clone()),
+            InstallProgress::BinaryExtracted { .. } => {}
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].0, spec);
+    assert!(matches!(
+        results[0].1,
+        Ok(InstallOutcome::Installed { downloaded, .. }) if downloaded > 0
+    ));
+    assert_eq!(completed, install_plan.actions);
+    assert_eq!(chosen.len(), 1);
+    assert!(env.home.tool_cache().is_installed(&spec));
+    assert!(env.home.tool_storage().has_tool_binary(&spec).await);
+    let alias = TOOL_NAME.parse::<ToolAlias>().unwrap();
+    assert!(env.home.tool_storage().has_tool_link(&alias).await);
+}
+
+#[tokio::test]
+async fn installed_tools_record_metadata() {
+    let env = TestEnv::new().await.unwrap();
+    let fixture = ReleaseFixture::new("v7.4.1");
+    fixture.serve(&env.server, true);
+    let spec = tool_spec("7.4.1");
+    let _ = env.home.tool_cache().add_trust(spec.id().clone());
+
+    // Tools installed by older versions of Rokit have no metadata
+    let storage = env.home.tool_storage();
+    storage
+        .replace_tool_contents(&spec, b"binary")
+        .await
+        .unwrap();
+    assert_eq!(storage.tool_metadata(&spec).await, None);
+
+    let manifests = [single_tool_manifest(&env, &spec)];
+    let options = PlanOptions {
+        force: true,
+        ..PlanOptions::default()
+    };
+    let install_plan = plan(&env.home, &manifests, options).await;
+    execute(
+        &env.home,
+        &env.source,
+        &install_plan,
+        &ExecuteOptions::default(),
+        |_| {},
+    )
+    .await
+    .unwrap();
+
+    let meta = storage.tool_metadata(&spec).await.unwrap();
+    assert_eq!(meta.provider(), ArtifactProvider::GitHub);
+    let name = meta.artifact_name().unwrap();
+    assert!(name.starts_with("rojo-7.4.1-"), "{name}");
+    assert!(meta.url().unwrap().contains("/releases/assets/"));
+    assert!(fixture
+        .archives
+        .values()
+        .any(|archive| sha256_hex(archive) == meta.sha256()));
+    assert_eq!(
+        meta.binary_sha256(),
+        storage.tool_sha256(&spec).await.as_deref()
+    );
+    assert!(meta.installed_at().elapsed().unwrap() < Duration::from_secs(60));
+
+    // The metadata is a plain file next to the binary, for other programs
+    let path = storage
+        .tool_path(&spec)
+        .with_file_name(".rokit-metadata.json");
+    let json: serde_json::Value = serde_json::from_slice(&read(path).await.unwrap()).unwrap();
+    assert_eq!(json["provider"], "github");
+    assert_eq!(json["sha256"], meta.sha256());
+}
+
+#[tokio::test]
+async fn cancelled_install_plan_writes_nothing() {
+    let env = TestEnv::new().await.unwrap();
+    ReleaseFixture::new("v7.4.1").serve(&env.server, true);
+    let spec = tool_spec("7.4.1");
+
+    let manifests = [single_tool_manifest(&env, &spec)];
+    let options = PlanOptions {
+        skip_trust_check: true,
+        ..PlanOptions::default()
+    };
+    let install_plan = plan(&env.home, &manifests, options).await;
+
+    let options = ExecuteOptions::default();
+    options.cancellation.cancel();
+    let mut completed = 0;
+    let err = execute(&env.home, &env.source, &install_plan, &options, |_| {
+        completed += 1;
+    })
+    .await
+    .unwrap_err();
+
+    assert!(matches!(err, RokitError::Cancelled));
+    assert_eq!(completed, 0);
+    assert!(!env.home.tool_cache().is_installed(&spec));
+    assert!(!env.home.tool_storage().has_tool_binary(&spec).await);
+}
+
+#[tokio::test]
+async fn install_plan_verifies_manifest_checksums() {
+    let env = TestEnv::new().await.unwrap();
+    ReleaseFixture::new("v7.4.1").serve(&env.server, true);
+    let spec = tool_spec("7.4.1");
+    let _ = env.home.tool_cache().add_trust(spec.id().clone());
+
+    let mut manifest = single_tool_manifest(&env, &spec);
+    manifest.options.insert(
+        TOOL_NAME.parse().unwrap(),
+        ToolOptions {
+            sha256: BTreeMap::from([("*".to_string(), "0".repeat(64))]),
+            ..ToolOptions::default()
+        },
+    );
+    let manifests = [manifest];
+    let install_plan = plan(&env.home, &manifests, PlanOptions::default()).await;
+    let mut results = execute(
+        &env.home,
+        &env.source,
+        &install_plan,
+        &ExecuteOptions::default(),
+        |_| {},
+    )
+    .await
+    .unwrap();
+
+    let err = results.remove(0).1.unwrap_err();
+    assert!(matches!(err, RokitError::ChecksumMismatch { .. }));
+    assert_eq!(err.kind(), ErrorKind::Trust);
+    assert!(!env.home.tool_cache().is_installed(&spec));
+}
+
+#[tokio::test]
+async fn install_exact_tools_from_lockfile() {
+    let env = TestEnv::new().await.unwrap();
+    let fixture = ReleaseFixture::new("v7.4.1");
+    fixture.serve(&env.server, true);
+    let spec = tool_spec("7.4.1");
+    let _ = env.home.tool_cache().add_trust(spec.id().clone());
+
+    let manifests = [single_tool_manifest(&env, &spec)];
+    let install_plan = plan(&env.home, &manifests, PlanOptions::default()).await;
+    execute(
+        &env.home,
+        &env.source,
+        &install_plan,
+        &ExecuteOptions::default(),
+        |_| {},
+    )
+    .await
+    .unwrap();
+
+    let lockfile = lock(&env.home, &manifests, Lockfile::default()).await;
+    let alias = TOOL_NAME.parse::<ToolAlias>().unwrap();
+    let locked = &lockfile.tools()[&alias];
+    let meta = env.home.tool_storage().tool_metadata(&spec).await.unwrap();
+    let artifact = locked.artifact_for(&Descriptor::current_system()).unwrap();
+    assert_eq!(locked.spec, spec);
+    assert_eq!(Some(artifact.asset.as_str()), meta.artifact_name());
+    assert_eq!(artifact.sha256, meta.sha256());
+
+    // A fresh home installs the same artifact from the lockfile alone
+    let other = TestEnv::new().await.unwrap();
+    fixture.serve(&other.server, true);
+    let lockfile = lockfile.to_string().parse::<Lockfile>().unwrap();
+    let manifests = [lockfile.to_manifest(other.dir.path().join(LOCKFILE_NAME))];
+    let options = PlanOptions {
+        skip_trust_check: true,
+        ..PlanOptions::default()
+    };
+    let install_plan = plan(&other.home, &manifests, options).await;
+    assert!(install_plan.actions.iter().any(|action| matches!(
+        action,
+        InstallAction::Resolve { asset: Some(asset), .. } if *asset == artifact.asset
+    )));
+    assert!(install_plan.actions.iter().any(|action| matches!(
+        action,
+        InstallAction::Download { sha256: Some(sha256), .. } if *sha256 == artifact.sha256
+    )));
+    execute(
+        &other.home,
+        &other.source,
+        &install_plan,
+        &ExecuteOptions::default(),
+        |_| {},
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        other.home.tool_storage().tool_sha256(&spec).await,
+        env.home.tool_storage().tool_sha256(&spec).await,
+    );
+}
+
+#[tokio::test]
+async fn tools_requiring_failed_tools_are_skipped() {
+    let env = TestEnv::new().await.unwrap();
+    ReleaseFixture::new("v7.4.1").serve(&env.server, true);
+    let spec = tool_spec("7.4.1");
+    let wrapper = "author/wrapper@1.0.0".parse::<ToolSpec>().unwrap();
+    let wrapper_alias = "wrapper".parse::<ToolAlias>().unwrap();
+
+    // NOTE: The required tool fails its checksum, and the wrapper
+    // has no release at all, so it must never even be resolved
+    let mut manifest = single_tool_manifest(&env, &spec);
+    manifest
+        .tools
+        .insert(wrapper_alias.clone(), wrapper.clone());
+    manifest.options.insert(
+        TOOL_NAME.parse().unwrap(),
+        ToolOptions {
+            sha256: BTreeMap::from([("*".to_string(), "0".repeat(64))]),
+            ..ToolOptions::default()
+        },
+    );
+    manifest.options.insert(
+        wrapper_alias,
+        ToolOptions {
+            requires: vec![TOOL_NAME.parse().unwrap()],
+            ..ToolOptions::default()
+        },
+    );
+    let manifests = [manifest];
+    let options = PlanOptions {
+        skip_trust_check: true,
+        ..PlanOptions::default()
+    };
+    let install_plan = plan(&env.home, &manifests, options).await;
+    assert_eq!(install_plan.tools_to_install(), [&spec, &wrapper]);
+
+    let results = execute(
+        &env.home,
+        &env.source,
+        &install_plan,
+        &ExecuteOptions::default(),
+        |_| {},
+    )
+    .await
+    .unwrap();
+
+    let result = |s: &ToolSpec| &results.iter().find(|(spec, _)| spec == s).unwrap().1;
+    assert_eq!(results.len(), 2);
+    assert!(matches!(
+        result(&spec),
+        Err(RokitError::ChecksumMismatch { .. })
+    ));
+    assert!(matches!(
+        result(&wrapper),
+        Err(RokitError::UnmetRequirement(SkipReason::RequirementFailed(alias)))
+            if alias.name() == TOOL_NAME
+    ));
+    assert!(!env.home.tool_cache().is_installed(&wrapper));
+}
+
+#[tokio::test]
+async fn github_errors_are_typed() {
+    let env = TestEnv::new().await.unwrap();
+    let latest = format!("/repos/{TOOL_AUTHOR}/{TOOL_NAME}/releases/latest");
+    let error_response = |status, headers| MockResponse {
+        status,
+        content_type: "application/json",
+        headers,
+        body: br#"{"message":"Error"}"#.to_vec(),
+    };
+    let github_error = |err: RokitError| match err {
+        RokitError::GitHub(e) => *e,
+        e => panic!("expected a GitHub error, got {e:?}"),
+    };
+
+    env.server.route(&latest, error_response(401, Vec::new()));
+    let err = env.source.get_latest_release(&tool_id()).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Authentication);
+    assert!(matches!(github_error(err), GithubError::Unauthorized));
+
+    env.server.route(
+        &latest,
+        error_response(
+            403,
+            vec![
+                ("x-ratelimit-remaining", "0".to_string()),
+                ("x-ratelimit-reset", "1700000000".to_string()),
+            ],
+        ),
+    );
+    let err = env.source.get_latest_release(&tool_id()).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Network);
+    let GithubError::RateLimited { reset_at } = github_error(err) else {
+        panic!("expected a rate limit error");
+    };
+    assert_eq!(
+        reset_at,
+        Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+    );
+
+    // Other forbidden responses, such as for missing permissions, are not rate limits
+    env.server.route(&latest, error_response(403, Vec::new()));
+    let err = env.source.get_latest_release(&tool_id()).await.unwrap_err();
+    assert!(matches!(github_error(err), GithubError::Reqwest(_)));
+}
+
+#[tokio::test]
+async fn install_from_workflow_artifacts() {
+    let env = TestEnv::new().await.unwrap();
+    let id: ToolId = format!("github-actions:{TOOL_AUTHOR}/{TOOL_NAME}#build.yml")
+        .parse()
+        .unwrap();
+    let repo = format!("/repos/{TOOL_AUTHOR}/{TOOL_NAME}");
+
+    // Workflow artifacts can only be downloaded with a token
+    let err = env.source.get_latest_release(&id).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Authentication);
+
+    let source = env
+        .source
+        .clone()
+        .with_owner_tokens(
+            ArtifactProvider::GitHub,
+            &HashMap::from([(TOOL_AUTHOR.to_string(), "token".to_string())]),
+        )
+        .unwrap();
+    let contents = zip_archive_with_path(TOOL_NAME, &binary_contents("linux-x86_64"));
+    env.server.route_json(
+        &repo,
+        &serde_json::json!({
+            "html_url": format!("https://github.com/{TOOL_AUTHOR}/{TOOL_NAME}"),
+            "default_branch": "trunk",
+        }),
+    );
+    env.server.route_json(
+        format!("{repo}/actions/workflows/build.yml/runs?branch=trunk&status=success&per_page=1"),
+        &serde_json::json!({ "workflow_runs": [{
+            "id": 1234,
+            "conclusion": "success",
+            "actor": { "login": "builder" },
+            "created_at": "2024-01-01T00:00:00Z",
+        }] }),
+    );
+    env.server.route_json(
+        format!("{repo}/actions/runs/1234/artifacts?per_page=100"),
+        &serde_json::json!({ "artifacts": [
+            {
+                "name": "tool-linux-x86_64",
+                "archive_download_url": format!("{}/download/current", env.server.url()),
+                "digest": format!("sha256:{}", sha256_hex(&contents)),
+            },
+            {
+                "name": "tool-linux-aarch64",
+                "archive_download_url": format!("{}/download/expired", env.server.url()),
+                "expired": true,
+            },
+        ] }),
+    );
+    env.server
+        .route_bytes("/download/current", contents.clone());
+
+    let release = source.get_latest_release(&id).await.unwrap();
+    assert!(release.prerelease);
+    assert_eq!(release.author.as_deref(), Some("builder"));
+    assert_eq!(release.artifacts.len(), 1);
+    let artifact = &release.artifacts[0];
+    assert_eq!(artifact.tool_spec.workflow_ref(), Some("1234"));
+    assert_eq!(
+        artifact.sha256.as_deref(),
+        Some(sha256_hex(&contents).as_str())
+    );
+    assert_eq!(
+        source.download_artifact_contents(artifact).await.unwrap(),
+        contents
+    );
+
+    // Artifacts of other runs have the same names, but must never share partial downloads
+    let other_run = Artifact {
+        url: Some(
+            format!("{}/download/other", env.server.url())
+                .parse()
+                .unwrap(),
+        ),
+        tool_spec: ToolSpec::with_workflow_ref(id.clone(), "5678").unwrap(),
+        ..artifact.clone()
+    };
+    assert_ne!(
+        source.partial_download_path(artifact),
+        source.partial_download_path(&other_run)
+    );
+
+    // Pinned specs fetch their exact run, and never the latest one
+    env.server.route_json(
+        format!("{repo}/actions/runs/1234"),
+        &serde_json::json!({ "id": 1234, "conclusion": "success" }),
+    );
+    let spec = artifact.tool_spec.to_string().parse::<ToolSpec>().unwrap();
+    let release = source.get_specific_release(&spec).await.unwrap();
+    assert_eq!(release.artifacts[0].tool_spec, artifact.tool_spec);
+
+    let failed = ToolSpec::with_workflow_ref(id.clone(), "5678").unwrap();
+    env.server.route_json(
+        format!("{repo}/actions/runs/5678"),
+        &serde_json::json!({ "id": 5678, "conclusion": "failure" }),
+    );
+    let err = source.get_specific_release(&failed).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+#[tokio::test]
+async fn workflow_file_names_are_encoded() {
+    let env = TestEnv::new().await.unwrap();
+    let id: ToolId = format!("github-actions:{TOOL_AUTHOR}/{TOOL_NAME}#my build?.yml")
+        .parse()
+        .unwrap();
+    let repo = format!("/repos/{TOOL_AUTHOR}/{TOOL_NAME}");
+    let source = env
+        .source
+        .clone()
+        .with_owner_tokens(
+            ArtifactProvider::GitHub,
+            &HashMap::from([(TOOL_AUTHOR.to_string(), "token".to_string())]),
+        )
+        .unwrap();
+    env.server.route_json(
+        &repo,
+        &serde_json::json!({
+            "html_url": format!("https://github.com/{TOOL_AUTHOR}/{TOOL_NAME}"),
+            "default_branch": "trunk",
+        }),
+    );
+    env.server.route_json(
+        format!(
+            "{repo}/actions/workflows/my%20build%3F.yml/runs?branch=trunk&status=success&per_page=1"
+        ),
+        &serde_json::json!({ "workflow_runs": [] }),
+    );
+
+    let err = source.get_latest_release(&id).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    let request = env.server.requests().pop().unwrap();
+    assert_eq!(
+        request.path,
+        format!("{repo}/actions/workflows/my%20build%3F.yml/runs?branch=trunk&status=success&per_page=1")
+    );
+}
+
+#[tokio::test]
+async fn list_platforms_of_release_without_compatible_artifact() {
+    let env = TestEnv::new().await.unwrap();
+    ReleaseFixture::new("v7.4.1").serve(&env.server, true);
+
+    let release = env.source.get_latest_release(&tool_id()).await.unwrap();
+    let platforms = Artifact::group_by_platform(&release.artifacts);
+    let listed = platforms.values().map(Vec::len).sum::<usize>();
+    assert_eq!(listed, release.artifacts.len());
+
+    // Every platform that the release has artifacts for is listed, along with
+    // the current platform, instead of only saying that nothing is compatible
+    let err = RokitError::no_compatible_artifact(&tool_spec("7.4.1"), &release.artifacts);
+    assert_eq!(err.kind(), ErrorKind::IncompatiblePlatform);
+    let message = err.to_string();
+    let current = Descriptor::current_system().platform_name();
+    assert!(
+        message.contains(&format!("platform '{current}'")),
+        "{message}"
+    );
+    for (platform, names) in &platforms {
+        assert!(message.contains(&format!("{platform}: {}", names.join(", "))));
+    }
+
+    let err = RokitError::no_compatible_artifact(&tool_spec("7.4.1"), &[]);
+    assert!(err
+        .to_string()
+        .ends_with("the release has no installable artifacts"));
+}
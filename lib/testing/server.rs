@@ -0,0 +1,175 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use wiremock::{
+    matchers::any, Mock, MockServer as WireMockServer, Request, Respond, ResponseTemplate,
+};
+
+/**
+    A response that the mock server will send for a specific path.
+*/
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: Vec<u8>,
+}
+
+/**
+    A request that was received by the mock server.
+
+    Header names are always lowercase.
+*/
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub path: String,
+    pub headers: HashMap<String, String>,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    routes: HashMap<String, MockResponse>,
+    requests: Vec<MockRequest>,
+}
+
+/**
+    A mock server, listening on localhost, that serves fixed responses
+    for paths registered in advance - paths include the query string,
+    and registering a path again replaces its previous response.
+
+    Supports single open-ended range requests (`Range: bytes=N-`)
+    for successful responses, which is all that Rokit ever sends.
+
+    The server stops when dropped.
+*/
+pub struct MockServer {
+    inner: WireMockServer,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockServer {
+    pub async fn start() -> Self {
+        let inner = WireMockServer::start().await;
+        let state = Arc::new(Mutex::new(MockState::default()));
+        Mock::given(any())
+            .respond_with(MockRouter(Arc::clone(&state)))
+            .mount(&inner)
+            .await;
+        Self { inner, state }
+    }
+
+    /**
+        Returns the base url of the server, without a trailing slash.
+    */
+    pub fn url(&self) -> String {
+        self.inner.uri()
+    }
+
+    /**
+        Serves the given response for all requests to the given path.
+    */
+    pub fn route(&self, path: impl Into<String>, response: MockResponse) {
+        let mut state = self.state.lock().unwrap();
+        state.routes.insert(path.into(), response);
+    }
+
+    pub fn route_json(&self, path: impl Into<String>, json: &serde_json::Value) {
+        self.route(
+            path,
+            MockResponse {
+                status: 200,
+                content_type: "application/json",
+                headers: Vec::new(),
+                body: serde_json::to_vec(json).unwrap(),
+            },
+        );
+    }
+
+    pub fn route_bytes(&self, path: impl Into<String>, bytes: impl Into<Vec<u8>>) {
+        self.route(
+            path,
+            MockResponse {
+                status: 200,
+                content_type: "application/octet-stream",
+                headers: Vec::new(),
+                body: bytes.into(),
+            },
+        );
+    }
+
+    /**
+        Returns all requests received by the server so far, in order.
+    */
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+/*
+    Responds to every request using the routes registered on the server,
+    since wiremock mocks can not be replaced once they have been mounted.
+*/
+struct MockRouter(Arc<Mutex<MockState>>);
+
+impl Respond for MockRouter {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let path = match request.url.query() {
+            Some(query) => format!("{}?{query}", request.url.path()),
+            None => request.url.path().to_string(),
+        };
+        let headers = request
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                let value = String::from_utf8_lossy(value.as_bytes()).to_string();
+                (name.as_str().to_ascii_lowercase(), value)
+            })
+            .collect::<HashMap<_, _>>();
+        let range_start = headers
+            .get("range")
+            .and_then(|range| range.strip_prefix("bytes="))
+            .and_then(|range| range.strip_suffix('-'))
+            .and_then(|start| start.parse::<usize>().ok());
+
+        let response = {
+            let mut state = self.0.lock().unwrap();
+            state.requests.push(MockRequest {
+                path: path.clone(),
+                headers,
+            });
+            state.routes.get(&path).cloned()
+        };
+
+        let Some(response) = response else {
+            return ResponseTemplate::new(404).set_body_raw(
+                br#"{"message":"Not Found","documentation_url":"https://docs.github.com/rest"}"#
+                    .to_vec(),
+                "application/json",
+            );
+        };
+
+        let total = response.body.len();
+        let template = match range_start {
+            Some(start) if response.status == 200 && start < total => ResponseTemplate::new(206)
+                .insert_header(
+                    "Content-Range",
+                    format!("bytes {start}-{}/{total}", total - 1),
+                )
+                .set_body_raw(response.body[start..].to_vec(), response.content_type),
+            Some(_) if response.status == 200 => ResponseTemplate::new(416)
+                .insert_header("Content-Range", format!("bytes */{total}"))
+                .set_body_raw(Vec::new(), response.content_type),
+            _ => ResponseTemplate::new(response.status)
+                .set_body_raw(response.body, response.content_type),
+        };
+        response
+            .headers
+            .into_iter()
+            .fold(template, |template, (name, value)| {
+                template.insert_header(name, value)
+            })
+    }
+}
@@ -23,6 +23,8 @@ pub enum ToolIdParseError {
     InvalidAuthor(String),
     #[error("name '{0}' is empty or invalid")]
     InvalidName(String),
+    #[error("missing '#' separator and workflow file name, such as '#build.yml'")]
+    MissingWorkflow,
 }
 
 /**
@@ -32,7 +34,11 @@ pub enum ToolIdParseError {
     their original casing for display and serialization purposes.
     See [`CaseInsensitiveString`] for more information.
 
-    Also includes the provider of the artifact, which by default is `GitHub`.
+    Also includes the provider of the artifact, which by default is `GitHub`,
+    and is only included when displayed if it is not the default provider.
+
+    Tools using the [`ArtifactProvider::GitHubActions`] provider also include
+    the file name of their workflow, after the name, such as `#build.yml`.
 
     Used to uniquely identify a tool, but not its version.
 */
@@ -41,6 +47,7 @@ pub struct ToolId {
     pub(crate) provider: ArtifactProvider,
     pub(crate) author: CaseInsensitiveString,
     pub(crate) name: CaseInsensitiveString,
+    pub(crate) workflow: Option<CaseInsensitiveString>,
 }
 
 impl ToolId {
@@ -59,6 +66,17 @@ impl ToolId {
         self.name.original_str()
     }
 
+    /**
+        Gets the file name of the GitHub Actions workflow that builds this
+        tool, such as `build.yml`, if this tool uses that provider.
+    */
+    #[must_use]
+    pub fn workflow(&self) -> Option<&str> {
+        self.workflow
+            .as_ref()
+            .map(CaseInsensitiveString::original_str)
+    }
+
     #[must_use]
     pub fn into_spec(self, version: Version) -> ToolSpec {
         ToolSpec::from((self, version))
@@ -75,6 +93,8 @@ impl Ord for ToolId {
         self.author
             .cmp(&other.author)
             .then_with(|| self.name.cmp(&other.name))
+            .then_with(|| self.provider.cmp(&other.provider))
+            .then_with(|| self.workflow.cmp(&other.workflow))
     }
 }
 
@@ -107,6 +127,19 @@ impl FromStr for ToolId {
         let before = before.trim();
         let after = after.trim();
 
+        let (after, workflow) = if provider == ArtifactProvider::GitHubActions {
+            let Some((name, workflow)) = after.split_once('#') else {
+                return Err(ToolIdParseError::MissingWorkflow);
+            };
+            let workflow = workflow.trim();
+            if is_invalid_identifier(workflow) || workflow.contains('#') {
+                return Err(ToolIdParseError::MissingWorkflow);
+            }
+            (name.trim(), Some(CaseInsensitiveString::new(workflow)))
+        } else {
+            (after, None)
+        };
+
         if is_invalid_identifier(before) {
             return Err(ToolIdParseError::InvalidAuthor(before.to_string()));
         }
@@ -118,18 +151,26 @@ impl FromStr for ToolId {
             provider,
             author: CaseInsensitiveString::new(before),
             name: CaseInsensitiveString::new(after),
+            workflow,
         })
     }
 }
 
 impl fmt::Display for ToolId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.provider != ArtifactProvider::default() {
+            write!(f, "{}:", self.provider)?;
+        }
         write!(
             f,
             "{}/{}",
             self.author.original_str(),
             self.name.original_str()
-        )
+        )?;
+        if let Some(workflow) = &self.workflow {
+            write!(f, "#{}", workflow.original_str())?;
+        }
+        Ok(())
     }
 }
 
@@ -142,6 +183,7 @@ mod tests {
             provider,
             author: CaseInsensitiveString::new(author),
             name: CaseInsensitiveString::new(name),
+            workflow: None,
         }
     }
 
@@ -193,6 +235,37 @@ mod tests {
             "github:a/b".parse::<ToolId>().unwrap(),
             new_id_with_provider(ArtifactProvider::GitHub, "a", "b")
         );
+        assert_eq!(
+            "github-branch:a/b".parse::<ToolId>().unwrap(),
+            new_id_with_provider(ArtifactProvider::GitHubBranch, "a", "b")
+        );
+    }
+
+    #[test]
+    fn parse_workflow() {
+        let id = "github-actions:a/b#build.yml".parse::<ToolId>().unwrap();
+        assert_eq!(id.provider(), ArtifactProvider::GitHubActions);
+        assert_eq!(id.name(), "b");
+        assert_eq!(id.workflow(), Some("build.yml"));
+        assert_eq!(id.to_string(), "github-actions:a/b#build.yml");
+        assert_ne!(
+            id,
+            "github-actions:a/b#release.yml".parse::<ToolId>().unwrap()
+        );
+
+        // Workflows are required for, and only used by, the GitHub Actions provider
+        assert!("github-actions:a/b".parse::<ToolId>().is_err());
+        assert!("github-actions:a/b#".parse::<ToolId>().is_err());
+        assert_eq!("a/b".parse::<ToolId>().unwrap().workflow(), None);
+    }
+
+    #[test]
+    fn display_non_default_provider() {
+        assert_eq!(new_id("a", "b").to_string(), "a/b");
+        assert_eq!(
+            "github-branch:a/b".parse::<ToolId>().unwrap().to_string(),
+            "github-branch:a/b"
+        );
     }
 
     #[test]
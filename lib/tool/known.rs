@@ -3,8 +3,8 @@ use std::{
     str::FromStr,
 };
 
+use super::ToolId;
 use once_cell::sync::Lazy;
-use rokit::tool::ToolId;
 
 const KNOWN_TOOL_AUTHORS_AND_IDS: [(&str, &[&str]); 8] = [
     ("evaera", &["moonwave"]),
@@ -41,6 +41,13 @@ static KNOWN_TOOLS: Lazy<BTreeMap<String, ToolId>> = Lazy::new(|| {
     map
 });
 
+/**
+    Gets the id of a well-known tool from its name, such as
+    `rojo-rbx/rojo` for `rojo`, ignoring case.
+
+    Returns `None` if the tool is not well-known.
+*/
+#[must_use]
 pub fn get_known_tool(tool: impl AsRef<str>) -> Option<ToolId> {
     let tool = tool.as_ref().to_ascii_lowercase();
     KNOWN_TOOLS.get(tool.as_str()).cloned()
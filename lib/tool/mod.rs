@@ -1,8 +1,10 @@
 mod alias;
 mod id;
+mod known;
 mod spec;
 mod util;
 
 pub use self::alias::{ToolAlias, ToolAliasParseError};
 pub use self::id::{ToolId, ToolIdParseError};
+pub use self::known::get_known_tool;
 pub use self::spec::{ToolSpec, ToolSpecParseError};
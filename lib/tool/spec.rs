@@ -1,6 +1,6 @@
 use std::{fmt, str::FromStr};
 
-use semver::{Version, VersionReq};
+use semver::{BuildMetadata, Version, VersionReq};
 use serde_with::{DeserializeFromStr, SerializeDisplay};
 use thiserror::Error;
 
@@ -36,6 +36,11 @@ pub enum ToolSpecParseError {
 
     This is an extension of [`ToolId`] used to uniquely identify
     a *specific version requirement* of a given tool.
+
+    Tools using the [`ArtifactProvider::GitHubBranch`] provider are versioned using a
+    git ref instead, such as a branch name or commit SHA - see [`ToolSpec::git_ref`].
+    Tools using the [`ArtifactProvider::GitHubActions`] provider are versioned using a
+    workflow run ID, or a branch name - see [`ToolSpec::workflow_ref`].
 */
 #[derive(
     Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, DeserializeFromStr, SerializeDisplay,
@@ -75,6 +80,70 @@ impl ToolSpec {
     pub fn matches_id(&self, id: &ToolId) -> bool {
         self.id == *id
     }
+
+    /**
+        Gets the git ref - a branch, tag, or commit - that this tool is pinned to,
+        if it is downloaded as a repository archive instead of from a release.
+
+        Git refs are stored as the build metadata of an otherwise empty version.
+    */
+    #[must_use]
+    pub fn git_ref(&self) -> Option<&str> {
+        if self.provider() == ArtifactProvider::GitHubBranch {
+            Some(self.version.build.as_str())
+        } else {
+            None
+        }
+    }
+
+    /**
+        Gets the ID of the GitHub Actions workflow run that this tool is pinned
+        to, or the branch to use the latest successful workflow run of, if it
+        is downloaded from the artifacts of a workflow run instead of a release.
+
+        Workflow refs are stored as the build metadata of an otherwise empty version.
+    */
+    #[must_use]
+    pub fn workflow_ref(&self) -> Option<&str> {
+        if self.provider() == ArtifactProvider::GitHubActions {
+            Some(self.version.build.as_str())
+        } else {
+            None
+        }
+    }
+
+    /**
+        Creates a new tool specification for the given tool,
+        pinned to the given git ref - a branch, tag, or commit.
+
+        Returns `None` if the git ref can not be stored in a version,
+        which only allows ASCII letters, digits, hyphens, and dots.
+    */
+    #[must_use]
+    pub fn with_git_ref(id: ToolId, git_ref: &str) -> Option<Self> {
+        Self::with_build_metadata(id, git_ref)
+    }
+
+    /**
+        Creates a new tool specification for the given tool, pinned to the
+        given workflow ref - a workflow run ID, or a branch name.
+
+        Returns `None` if the workflow ref can not be stored in a version,
+        which only allows ASCII letters, digits, hyphens, and dots.
+    */
+    #[must_use]
+    pub fn with_workflow_ref(id: ToolId, workflow_ref: &str) -> Option<Self> {
+        Self::with_build_metadata(id, workflow_ref)
+    }
+
+    fn with_build_metadata(id: ToolId, metadata: &str) -> Option<Self> {
+        let build = BuildMetadata::new(metadata).ok()?;
+        let version = Version {
+            build,
+            ..Version::new(0, 0, 0)
+        };
+        Some(Self { id, version })
+    }
 }
 
 impl FromStr for ToolSpec {
@@ -97,6 +166,15 @@ impl FromStr for ToolSpec {
             return Err(ToolSpecParseError::InvalidVersion(after.to_string()));
         }
 
+        if id.provider() == ArtifactProvider::GitHubBranch {
+            return ToolSpec::with_git_ref(id, after)
+                .ok_or_else(|| ToolSpecParseError::InvalidVersion(after.to_string()));
+        }
+        if id.provider() == ArtifactProvider::GitHubActions {
+            return ToolSpec::with_workflow_ref(id, after)
+                .ok_or_else(|| ToolSpecParseError::InvalidVersion(after.to_string()));
+        }
+
         let version = match after.parse::<Version>() {
             Ok(version) => version,
             Err(e) => {
@@ -115,7 +193,10 @@ impl FromStr for ToolSpec {
 
 impl fmt::Display for ToolSpec {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}@{}", self.id, self.version)
+        match self.git_ref().or(self.workflow_ref()) {
+            Some(git_ref) => write!(f, "{}@{git_ref}", self.id),
+            None => write!(f, "{}@{}", self.id, self.version),
+        }
     }
 }
 
@@ -184,6 +265,49 @@ mod tests {
         assert!("/@".parse::<ToolSpec>().is_err());
     }
 
+    #[test]
+    fn parse_valid_git_ref() {
+        // Branch archives use git refs instead of versions
+        let spec = "github-branch:author/name@main"
+            .parse::<ToolSpec>()
+            .unwrap();
+        assert_eq!(spec.git_ref(), Some("main"));
+        assert_eq!(spec.to_string(), "github-branch:author/name@main");
+        let spec = "github-branch:author/name@0a1b2c3"
+            .parse::<ToolSpec>()
+            .unwrap();
+        assert_eq!(spec.git_ref(), Some("0a1b2c3"));
+        // Release versions never have git refs
+        let spec = "author/name@1.2.3+main".parse::<ToolSpec>().unwrap();
+        assert_eq!(spec.git_ref(), None);
+        // Git refs must be valid version build metadata
+        assert!("github-branch:author/name@my_branch"
+            .parse::<ToolSpec>()
+            .is_err());
+    }
+
+    #[test]
+    fn parse_valid_workflow_ref() {
+        // Workflow artifacts use run IDs or branches instead of versions
+        let spec = "github-actions:author/name#build.yml@main"
+            .parse::<ToolSpec>()
+            .unwrap();
+        assert_eq!(spec.workflow_ref(), Some("main"));
+        assert_eq!(spec.git_ref(), None);
+        assert_eq!(spec.id().workflow(), Some("build.yml"));
+        assert_eq!(
+            spec.to_string(),
+            "github-actions:author/name#build.yml@main"
+        );
+        let spec = "github-actions:author/name#build.yml@123456789"
+            .parse::<ToolSpec>()
+            .unwrap();
+        assert_eq!(spec.workflow_ref(), Some("123456789"));
+        assert!("github-actions:author/name@main"
+            .parse::<ToolSpec>()
+            .is_err());
+    }
+
     #[test]
     fn parse_invalid_extra_separator() {
         // Superfluous separators should not be allowed
@@ -1,14 +1,33 @@
-use std::{env::consts::EXE_EXTENSION, path::Path, str::FromStr};
+use std::{
+    env::consts::EXE_EXTENSION,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
-use tokio::fs::{metadata, read_to_string, write};
+use tokio::{
+    fs::{metadata, read_to_string, remove_file},
+    task::spawn_blocking,
+};
 use tracing::{error, warn};
 
 use crate::result::{RokitError, RokitResult};
 
+/**
+    Returns the path of the backup file for the file at the given path.
+*/
+fn backup_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".bak");
+    path.with_file_name(file_name)
+}
+
 /**
     Loads the given type from the file at the given path.
 
     Will return an error if the file does not exist or could not be parsed.
+
+    If the file could not be parsed, or is unexpectedly empty, and a backup
+    of its previous contents exists, the error will point to the backup.
 */
 pub(crate) async fn load_from_file<P, T, E>(path: P) -> RokitResult<T>
 where
@@ -17,29 +36,87 @@ where
     E: Into<RokitError>,
 {
     let path = path.as_ref();
-    match read_to_string(path).await {
+    let contents = match read_to_string(path).await {
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-            Err(RokitError::FileNotFound(path.into()))
+            return Err(RokitError::FileNotFound(path.into()));
         }
+        Err(e) => return Err(e.into()),
+        Ok(s) => s,
+    };
+
+    let backup_path = backup_path(path);
+    let backup_contents = read_to_string(&backup_path).await.ok();
+    let has_backup = backup_contents.is_some();
+
+    // NOTE: Rokit never writes empty files, so an empty file next to a
+    // non-empty backup most likely means a previous save was interrupted
+    let backup_is_non_empty = backup_contents.is_some_and(|b| !b.trim().is_empty());
+    if contents.trim().is_empty() && backup_is_non_empty {
+        return Err(RokitError::CorruptedFile {
+            path: path.into(),
+            backup_path: Some(backup_path),
+            error: Box::new(RokitError::Io(Box::new(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "file is empty",
+            )))),
+        });
+    }
+
+    match contents.parse() {
+        Ok(t) => Ok(t),
+        Err(e) if has_backup => Err(RokitError::CorruptedFile {
+            path: path.into(),
+            backup_path: Some(backup_path),
+            error: Box::new(e.into()),
+        }),
         Err(e) => Err(e.into()),
-        Ok(s) => match s.parse() {
-            Ok(t) => Ok(t),
-            Err(e) => Err(e.into()),
-        },
     }
 }
 
 /**
     Saves the given data, stringified, to the file at the given path.
+
+    The file is written atomically, and if the previous contents of the
+    file were valid, they are kept in a backup file next to it - see
+    [`load_from_file`] for how the backup is used for error reporting.
 */
 pub(crate) async fn save_to_file<P, T>(path: P, data: T) -> RokitResult<()>
 where
     P: AsRef<Path>,
-    T: Clone + ToString,
+    T: Clone + ToString + FromStr,
 {
     let path = path.as_ref();
-    write(path, data.to_string()).await?;
-    Ok(())
+    let contents = data.to_string();
+
+    if let Ok(previous) = read_to_string(path).await {
+        let is_valid = !previous.trim().is_empty() && previous.parse::<T>().is_ok();
+        if is_valid && previous != contents {
+            write_atomic(backup_path(path), previous.into_bytes()).await?;
+        }
+    }
+
+    write_atomic(path, contents.into_bytes()).await
+}
+
+/**
+    Saves the given data, stringified, to the file at the given path.
+
+    Unlike [`save_to_file`], no backup of the previous contents is kept,
+    and any existing backup is removed, since this is meant for files
+    that contain secrets which must not linger on disk once replaced.
+*/
+pub(crate) async fn save_to_file_without_backup<P, T>(path: P, data: T) -> RokitResult<()>
+where
+    P: AsRef<Path>,
+    T: ToString,
+{
+    let path = path.as_ref();
+    write_atomic(path, data.to_string().into_bytes()).await?;
+
+    match remove_file(backup_path(path)).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
 }
 
 /**
@@ -73,32 +150,172 @@ pub async fn write_executable_file(
             ),
         }
     }
-    if let Err(e) = write(path, contents).await {
+    if let Err(e) = write_file_atomic(path, contents.as_ref().to_vec(), true).await {
         error!("Failed to write executable to {path:?}:\n{e}");
         return Err(e.into());
     }
 
-    add_executable_permissions(path).await?;
+    Ok(())
+}
+
+/**
+    Writes the given contents to the file at the given path atomically.
 
+    See [`write_file_atomic`] for more information.
+*/
+pub(crate) async fn write_atomic(path: impl AsRef<Path>, contents: Vec<u8>) -> RokitResult<()> {
+    write_file_atomic(path.as_ref(), contents, false).await?;
     Ok(())
 }
 
+/**
+    Writes the given contents to the file at the given path atomically.
+
+    The contents are first written to a temporary file in the same directory,
+    which is then renamed to the destination path. This guarantees that other
+    processes reading the file will never see partially written contents, even
+    if several processes are writing to the same path at the same time.
+*/
+async fn write_file_atomic(
+    path: &Path,
+    contents: Vec<u8>,
+    executable: bool,
+) -> std::io::Result<()> {
+    let path: PathBuf = path.to_path_buf();
+    spawn_blocking(move || {
+        use std::io::Write;
+
+        let dir = path
+            .parent()
+            .expect("should not be given empty or root path");
+        let mut temp = tempfile::Builder::new()
+            .prefix(".rokit-")
+            .suffix(".tmp")
+            .tempfile_in(dir)?;
+        temp.write_all(&contents)?;
+        temp.as_file().sync_all()?;
+        // NOTE: Temporary files are only readable by their owner, so
+        // we always need to set the usual permissions before renaming
+        set_file_permissions(temp.path(), executable)?;
+        temp.persist(&path).map_err(|e| e.error)?;
+        Ok(())
+    })
+    .await
+    .expect("blocking writer task panicked unexpectedly")
+}
+
 #[cfg(unix)]
-async fn add_executable_permissions(path: impl AsRef<Path>) -> RokitResult<()> {
-    use std::fs::Permissions;
+fn set_file_permissions(path: &Path, executable: bool) -> std::io::Result<()> {
+    use std::fs::{set_permissions, Permissions};
     use std::os::unix::fs::PermissionsExt;
-    use tokio::fs::set_permissions;
 
-    let path = path.as_ref();
-    if let Err(e) = set_permissions(path, Permissions::from_mode(0o755)).await {
-        error!("Failed to set executable permissions on {path:?}:\n{e}");
-        return Err(e.into());
+    let mode = if executable { 0o755 } else { 0o644 };
+    if let Err(e) = set_permissions(path, Permissions::from_mode(mode)) {
+        error!("Failed to set permissions on {path:?}:\n{e}");
+        return Err(e);
     }
 
     Ok(())
 }
 
 #[cfg(not(unix))]
-async fn add_executable_permissions(_path: impl AsRef<Path>) -> RokitResult<()> {
+#[allow(clippy::unnecessary_wraps)]
+fn set_file_permissions(_path: &Path, _executable: bool) -> std::io::Result<()> {
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::manifests::RokitManifest;
+
+    use super::*;
+
+    fn manifest(tools: &str) -> RokitManifest {
+        format!("[tools]\n{tools}\n").parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn write_atomic_replaces_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.txt");
+
+        write_atomic(&path, b"first".to_vec()).await.unwrap();
+        write_atomic(&path, b"second".to_vec()).await.unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+
+        // No temporary files should be left behind
+        let entries = std::fs::read_dir(dir.path()).unwrap().count();
+        assert_eq!(entries, 1);
+    }
+
+    #[tokio::test]
+    async fn save_keeps_backup_of_valid_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rokit.toml");
+
+        save_to_file(&path, manifest("a = \"b/a@1.0.0\""))
+            .await
+            .unwrap();
+        assert!(!backup_path(&path).exists());
+
+        save_to_file(&path, manifest("a = \"b/a@2.0.0\""))
+            .await
+            .unwrap();
+        let backup = std::fs::read_to_string(backup_path(&path)).unwrap();
+        assert!(backup.contains("1.0.0"));
+
+        // Invalid contents should never replace a valid backup
+        std::fs::write(&path, "[tools").unwrap();
+        save_to_file(&path, manifest("a = \"b/a@3.0.0\""))
+            .await
+            .unwrap();
+        let backup = std::fs::read_to_string(backup_path(&path)).unwrap();
+        assert!(backup.contains("1.0.0"));
+    }
+
+    #[tokio::test]
+    async fn save_without_backup_removes_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("auth.toml");
+        std::fs::write(backup_path(&path), "github = \"ghp_old\"").unwrap();
+
+        save_to_file_without_backup(&path, "github = \"ghp_new\"")
+            .await
+            .unwrap();
+        save_to_file_without_backup(&path, "").await.unwrap();
+        assert!(!backup_path(&path).exists());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+    }
+
+    #[tokio::test]
+    async fn load_reports_corruption_with_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rokit.toml");
+
+        std::fs::write(&path, "[tools").unwrap();
+        let err = load_from_file::<_, RokitManifest, _>(&path)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RokitError::TomlParseError(_)));
+
+        std::fs::write(backup_path(&path), "[tools]").unwrap();
+        let err = load_from_file::<_, RokitManifest, _>(&path)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            RokitError::CorruptedFile {
+                backup_path: Some(_),
+                ..
+            }
+        ));
+        assert!(err.to_string().contains("rokit.toml.bak"));
+
+        // Truncated files are also corrupted if a backup exists
+        std::fs::write(&path, "").unwrap();
+        let err = load_from_file::<_, RokitManifest, _>(&path)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RokitError::CorruptedFile { .. }));
+    }
+}
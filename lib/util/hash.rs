@@ -0,0 +1,34 @@
+use std::fmt::Write;
+
+use ring::digest::{digest, SHA256};
+
+/**
+    Computes the SHA-256 hash of the given bytes,
+    returned as a lowercase hexadecimal string.
+*/
+pub(crate) fn sha256_hex(bytes: impl AsRef<[u8]>) -> String {
+    digest(&SHA256, bytes.as_ref()).as_ref().iter().fold(
+        String::with_capacity(64),
+        |mut s, byte| {
+            let _ = write!(s, "{byte:02x}");
+            s
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sha256_known_values() {
+        assert_eq!(
+            sha256_hex(""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            sha256_hex("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
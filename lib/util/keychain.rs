@@ -0,0 +1,80 @@
+#[cfg(feature = "keychain")]
+use keyring::{Entry, Error as KeyringError};
+#[cfg(feature = "keychain")]
+use tokio::task::spawn_blocking;
+
+#[cfg(not(feature = "keychain"))]
+use crate::result::RokitError;
+use crate::{result::RokitResult, sources::ArtifactProvider};
+
+/*
+    Helpers for storing authentication tokens in the secure storage provided
+    by the operating system - Windows Credential Manager, macOS Keychain, and
+    the Secret Service on Linux - instead of in the plaintext auth manifest.
+
+    Note that all keychain operations may block, and some backends can not be
+    used from within an async runtime, so they always run on a blocking thread.
+
+    Without the `keychain` feature, every operation fails with an error instead.
+*/
+
+#[cfg(feature = "keychain")]
+const SERVICE_NAME: &str = "rokit";
+
+#[cfg(feature = "keychain")]
+fn entry(provider: ArtifactProvider) -> Result<Entry, KeyringError> {
+    Entry::new(SERVICE_NAME, provider.as_str())
+}
+
+/**
+    Gets the token for the given provider from the keychain, if one exists.
+*/
+#[cfg(feature = "keychain")]
+pub(crate) async fn get_token(provider: ArtifactProvider) -> RokitResult<Option<String>> {
+    let result = spawn_blocking(move || match entry(provider)?.get_password() {
+        Ok(token) => Ok(Some(token)),
+        Err(KeyringError::NoEntry) => Ok(None),
+        Err(e) => Err(e),
+    });
+    Ok(result.await??)
+}
+
+/**
+    Stores the token for the given provider in the keychain,
+    replacing any token that was previously stored.
+*/
+#[cfg(feature = "keychain")]
+pub(crate) async fn set_token(provider: ArtifactProvider, token: String) -> RokitResult<()> {
+    let result = spawn_blocking(move || entry(provider)?.set_password(&token));
+    Ok(result.await??)
+}
+
+/**
+    Removes the token for the given provider from the keychain, if one exists.
+*/
+#[cfg(feature = "keychain")]
+pub(crate) async fn delete_token(provider: ArtifactProvider) -> RokitResult<()> {
+    let result = spawn_blocking(move || match entry(provider)?.delete_credential() {
+        Ok(()) | Err(KeyringError::NoEntry) => Ok(()),
+        Err(e) => Err(e),
+    });
+    Ok(result.await??)
+}
+
+#[cfg(not(feature = "keychain"))]
+#[allow(clippy::unused_async)]
+pub(crate) async fn get_token(_provider: ArtifactProvider) -> RokitResult<Option<String>> {
+    Err(RokitError::KeychainUnsupported)
+}
+
+#[cfg(not(feature = "keychain"))]
+#[allow(clippy::unused_async)]
+pub(crate) async fn set_token(_provider: ArtifactProvider, _token: String) -> RokitResult<()> {
+    Err(RokitError::KeychainUnsupported)
+}
+
+#[cfg(not(feature = "keychain"))]
+#[allow(clippy::unused_async)]
+pub(crate) async fn delete_token(_provider: ArtifactProvider) -> RokitResult<()> {
+    Err(RokitError::KeychainUnsupported)
+}
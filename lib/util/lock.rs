@@ -0,0 +1,85 @@
+use std::{fs::File, path::PathBuf};
+
+use tokio::task::spawn_blocking;
+use tracing::trace;
+
+use crate::result::RokitResult;
+
+/**
+    An exclusive, advisory lock on a file, shared across processes.
+
+    The lock is released when this guard is dropped.
+*/
+#[derive(Debug)]
+pub(crate) struct FileLock {
+    file: File,
+}
+
+impl FileLock {
+    /**
+        Acquires an exclusive lock on the file at the given path,
+        waiting for any other process holding the lock to release it.
+
+        The file, and its parent directory, is created if it does not exist.
+    */
+    pub(crate) async fn acquire(path: impl Into<PathBuf>) -> RokitResult<Self> {
+        let path = path.into();
+        let result = spawn_blocking(move || {
+            use std::fs::{create_dir_all, OpenOptions};
+
+            create_dir_all(path.parent().expect("should not be given root path"))?;
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&path)?;
+
+            trace!(?path, "acquiring file lock");
+            file.lock()?;
+            trace!(?path, "acquired file lock");
+
+            Ok::<_, std::io::Error>(Self { file })
+        });
+
+        Ok(result
+            .await
+            .expect("blocking lock task panicked unexpectedly")?)
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        // NOTE: The lock is also released when the file is closed, so
+        // this is mostly to make sure it happens as early as possible
+        let _ = self.file.unlock();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{OpenOptions, TryLockError};
+
+    use super::*;
+
+    fn try_lock_other(path: &std::path::Path) -> bool {
+        let file = OpenOptions::new().write(true).open(path).unwrap();
+        match file.try_lock() {
+            Ok(()) => true,
+            Err(TryLockError::WouldBlock) => false,
+            Err(TryLockError::Error(e)) => panic!("{e}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn lock_is_exclusive_until_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("test.lock");
+
+        let lock = FileLock::acquire(&path).await.unwrap();
+        assert!(!try_lock_other(&path));
+
+        drop(lock);
+        assert!(try_lock_other(&path));
+    }
+}
@@ -1,3 +1,6 @@
 pub(crate) mod fs;
+pub(crate) mod hash;
+pub(crate) mod keychain;
+pub(crate) mod lock;
 pub(crate) mod path;
 pub(crate) mod str;
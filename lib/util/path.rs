@@ -1,6 +1,6 @@
 #![allow(dead_code)] // Some path utilities are only used on Windows
 
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 /**
     Cleans up a path and simplifies it for writing to storage or environment variables.
@@ -12,3 +12,13 @@ use std::path::{Path, PathBuf};
 pub fn simplify_path(path: impl AsRef<Path>) -> PathBuf {
     dunce::simplified(path.as_ref()).to_path_buf()
 }
+
+/**
+    Checks if the given path is a non-empty relative path that can not escape
+    the directory it is joined onto - meaning that it has no root, prefix,
+    `.` or `..` components, and only consists of normal file names.
+*/
+pub fn is_safe_relative_path(path: &Path) -> bool {
+    path.components().next().is_some()
+        && path.components().all(|c| matches!(c, Component::Normal(_)))
+}
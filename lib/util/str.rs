@@ -9,6 +9,17 @@ pub(crate) const fn char_is_word_separator(c: char) -> bool {
     c.is_ascii_whitespace() || matches!(c, '-' | '_')
 }
 
+/**
+    Gets the date part of an RFC 3339 timestamp, formatted as `YYYY-MM-DD`.
+
+    For internal use only.
+*/
+pub(crate) fn timestamp_date(timestamp: &str) -> &str {
+    timestamp
+        .split_once('T')
+        .map_or(timestamp, |(date, _)| date)
+}
+
 /**
     A case-insensitive string wrapper.
 
@@ -1,16 +1,22 @@
 use anyhow::{bail, Context, Result};
 use clap::Parser;
 use console::style;
+use tracing::warn;
 
 use rokit::{
-    discovery::discover_all_manifests,
+    discovery::{
+        discover_alias_rules, discover_all_manifests, discover_extract_limits,
+        discover_large_download_threshold, discover_strict_platform, ManifestKind,
+    },
     manifests::RokitManifest,
-    storage::Home,
+    result::ErrorKind,
+    storage::{Home, InstalledToolMetadata},
     tool::{ToolAlias, ToolId},
 };
 
 use crate::util::{
-    find_most_compatible_artifact, prompt_for_trust, CliProgressTracker, ToolIdOrSpec,
+    display_path, find_most_compatible_artifact, prompt_for_trust, warn_if_emulated, warn_if_large,
+    CliError, CliProgressTracker, ToolIdOrSpec,
 };
 
 /// Adds a new tool to Rokit and installs it.
@@ -20,6 +26,9 @@ pub struct AddSubcommand {
     /// to get the tool, and optionally what version to install.
     pub tool: ToolIdOrSpec,
     /// The name that will be used to run the tool.
+    ///
+    /// Defaults to the name of the tool, transformed using the
+    /// `alias-rules` setting of the nearest or global manifest.
     pub alias: Option<ToolAlias>,
     /// Add this tool globally instead of adding
     /// it to the nearest manifest file.
@@ -34,21 +43,37 @@ pub struct AddSubcommand {
 impl AddSubcommand {
     pub async fn run(self, home: &Home) -> Result<()> {
         let id: ToolId = self.tool.clone().into();
-        let alias: ToolAlias = match self.alias.as_ref() {
-            Some(alias) => alias.clone(),
-            None => self.tool.clone().into(),
+        // NOTE: Aliases default to the name of the tool, which may be
+        // transformed using alias rules set by the project or globally
+        let alias: ToolAlias = if let Some(alias) = self.alias.as_ref() {
+            alias.clone()
+        } else {
+            let default: ToolAlias = self.tool.clone().into();
+            let rules = discover_alias_rules().await;
+            rules.apply(default.name()).parse().unwrap_or(default)
         };
 
         let tool_cache = home.tool_cache();
         let tool_storage = home.tool_storage();
         let source = home.artifact_source().await?;
+        let strict_platform = discover_strict_platform().await;
 
-        // 1. Check for trust, or prompt the user to trust the tool
-        if !tool_cache.is_trusted(&id) {
-            if !self.force && !prompt_for_trust(id.clone()).await? {
-                bail!("Tool is not trusted - operation was aborted");
-            }
-            let _ = tool_cache.add_trust(id.clone());
+        // 1. Check for trust, or prompt the user to trust the tool - tools that
+        // are only trusted for some versions are checked once the version is known
+        let is_trusted = match &self.tool {
+            ToolIdOrSpec::Spec(spec) => tool_cache.is_trusted_spec(spec),
+            ToolIdOrSpec::Id(id) => tool_cache.is_trusted(id),
+        };
+        let version = match &self.tool {
+            ToolIdOrSpec::Spec(spec) => Some(spec.version().clone()),
+            ToolIdOrSpec::Id(_) => None,
+        };
+        if !is_trusted && !self.force && !prompt_for_trust(&source, id.clone(), version).await? {
+            return Err(CliError::new(
+                ErrorKind::Trust,
+                "Tool is not trusted - operation was aborted",
+            )
+            .into());
         }
 
         // 2. Load manifest and do a preflight check to
@@ -71,8 +96,23 @@ impl AddSubcommand {
         } else {
             RokitManifest::load(&manifest_path).await?
         };
-        if manifest.has_tool(&alias) && !self.force {
-            let global_flag = if self.global { "--global " } else { "" };
+        let global_flag = if self.global { "--global " } else { "" };
+        let existing = manifest.get_tool(&alias);
+        // NOTE: Tools from different authors may share the same name, and must
+        // be given distinct aliases, so that one never silently replaces the
+        // other - unless a user explicitly asks to replace it using --force
+        if let Some(existing) = existing.filter(|existing| existing.id() != &id) {
+            if self.alias.is_none() || !self.force {
+                bail!(
+                    "Alias '{alias}' is already used by another tool: {}\n\
+                    \nTo add {id} next to it, give it a different alias:\
+                    \n  rokit add {global_flag}{id} {}-{}",
+                    existing.id(),
+                    id.author(),
+                    id.name(),
+                );
+            }
+        } else if manifest.has_tool(&alias) && !self.force {
             bail!(
                 "Tool already exists and can't be added: {id}\n\
                 \n  - To update the tool, run `rokit update {global_flag}{id}`\
@@ -80,40 +120,107 @@ impl AddSubcommand {
             );
         }
 
+        // Aliases in other manifests for different tools are not replaced,
+        // but either shadow the new tool, or are shadowed by it
+        let mut manifests = discover_all_manifests(false, false).await;
+        manifests.retain(|m| {
+            m.kind != ManifestKind::Rokit || m.path.parent() != Some(manifest_path.as_path())
+        });
+        let other_tools = manifests
+            .iter()
+            .filter_map(|m| {
+                m.tools
+                    .get(&alias)
+                    .map(|spec| (spec.clone(), m.path.clone()))
+            })
+            .filter(|(spec, _)| spec.id() != &id)
+            .collect::<Vec<_>>();
+        for (other, path) in other_tools {
+            warn!(
+                "Alias '{alias}' is also declared for {} in {} - only the tool in \
+                the nearest manifest runs when using '{alias}'.",
+                other.id(),
+                display_path(path),
+            );
+        }
+
         // 3. If we only got an id without a specified version, we
         // will fetch the latest non-prerelease release and use that
         let pt = CliProgressTracker::new_with_message("Fetching", 3);
-        let (spec, artifact) = match self.tool.clone() {
+        let (spec, artifact, release_artifacts) = match self.tool.clone() {
             ToolIdOrSpec::Spec(spec) => {
                 let release_artifact = source.get_specific_release(&spec).await?;
-                let artifact = find_most_compatible_artifact(&release_artifact.artifacts, &id)?;
-                (spec, artifact)
+                let artifact = find_most_compatible_artifact(
+                    &release_artifact.artifacts,
+                    &id,
+                    strict_platform,
+                )?;
+                let release_artifacts = release_artifact.artifacts;
+                // NOTE: Tools downloaded as repository archives are pinned to the
+                // commit that their branch or tag currently points to, and tools
+                // downloaded from workflow artifacts to the latest run for their
+                // branch, so that the manifest, and the installed tool, never
+                // change unexpectedly
+                if spec.git_ref().or(spec.workflow_ref()).is_some() {
+                    (artifact.tool_spec.clone(), artifact, release_artifacts)
+                } else {
+                    (spec, artifact, release_artifacts)
+                }
             }
             ToolIdOrSpec::Id(id) => {
                 let release_artifact = source.get_latest_release(&id).await?;
-                let artifact = find_most_compatible_artifact(&release_artifact.artifacts, &id)?;
-                (artifact.tool_spec.clone(), artifact)
+                let artifact = find_most_compatible_artifact(
+                    &release_artifact.artifacts,
+                    &id,
+                    strict_platform,
+                )?;
+                (
+                    artifact.tool_spec.clone(),
+                    artifact,
+                    release_artifact.artifacts,
+                )
             }
         };
         pt.task_completed();
+        if !is_trusted {
+            let _ = tool_cache.add_trust_spec(&spec);
+        }
 
         // 4. Add the tool spec to the desired manifest file and save it
         manifest.add_tool(&alias, &spec);
+        let options = manifest.get_tool_options(&alias);
         manifest.save(manifest_path).await?;
 
         // 5. Download and install the tool
         if !tool_cache.is_installed(&spec) || self.force {
+            warn_if_large(&artifact, discover_large_download_threshold().await);
             let contents = source
                 .download_artifact_contents(&artifact)
                 .await
                 .with_context(|| format!("Failed to download contents for {spec}"))?;
+            let metadata = InstalledToolMetadata::new(&artifact, &contents);
+            let extract_limits = discover_extract_limits().await;
             pt.task_completed();
             pt.update_message("Installing");
-            let extracted = artifact
-                .extract_contents(contents)
-                .await
-                .with_context(|| format!("Failed to extract contents for {spec}"))?;
-            tool_storage.replace_tool_contents(&spec, extracted).await?;
+            let file_pattern = options.file.as_deref();
+            if options.extract_all {
+                let (extracted, files) = artifact
+                    .extract_all_contents_matching(contents, file_pattern, extract_limits)
+                    .await
+                    .with_context(|| format!("Failed to extract contents for {spec}"))?;
+                warn_if_emulated(&artifact, &release_artifacts, &extracted);
+                tool_storage
+                    .replace_tool_contents_with_files(&spec, extracted, &files)
+                    .await?;
+            } else {
+                let extracted = artifact
+                    .extract_contents_matching(contents, file_pattern, extract_limits)
+                    .await
+                    .with_context(|| format!("Failed to extract contents for {spec}"))?;
+                warn_if_emulated(&artifact, &release_artifacts, &extracted);
+                tool_storage.replace_tool_contents(&spec, extracted).await?;
+            }
+            tool_storage.save_tool_metadata(&spec, metadata).await?;
             pt.task_completed();
             let _ = tool_cache.add_installed(spec.clone());
         } else {
@@ -128,7 +235,12 @@ impl AddSubcommand {
         // 7. Finally, display a nice message to the user
         pt.finish_with_message(format!(
             "Added version {} of tool {}{} {}",
-            style(spec.version()).bold().yellow(),
+            style(match spec.git_ref().or(spec.workflow_ref()) {
+                Some(git_ref) => git_ref.to_string(),
+                None => spec.version().to_string(),
+            })
+            .bold()
+            .yellow(),
             style(spec.name()).bold().magenta(),
             if alias.name() == id.name() {
                 String::new()
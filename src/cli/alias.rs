@@ -0,0 +1,167 @@
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use console::style;
+
+use rokit::{
+    discovery::discover_all_manifests,
+    manifests::RokitManifest,
+    storage::Home,
+    tool::{ToolAlias, ToolSpec},
+};
+
+use crate::util::{CliProgressTracker, ToolAliasOrIdOrSpec};
+
+/// Adds or removes an extra alias for a tool in a manifest.
+///
+/// This lets several names point at the same tool, or at
+/// different pinned versions of the same tool, in a project.
+#[derive(Debug, Parser)]
+pub struct AliasSubcommand {
+    /// The alias to add or remove.
+    pub alias: ToolAlias,
+    /// The existing tool alias, identifier, or specification that
+    /// the new alias should point to. Omit when removing an alias.
+    #[clap(required_unless_present = "remove")]
+    pub target: Option<ToolAliasOrIdOrSpec>,
+    /// Add or remove the alias in the global
+    /// manifest instead of the nearest manifest file.
+    #[clap(long)]
+    pub global: bool,
+    /// Remove the alias instead of adding it.
+    #[clap(long, conflicts_with = "target")]
+    pub remove: bool,
+    /// Overwrite the alias if it already exists.
+    #[clap(long)]
+    pub force: bool,
+}
+
+impl AliasSubcommand {
+    pub async fn run(self, home: &Home) -> Result<()> {
+        let pt = CliProgressTracker::new_with_message("Loading", 2);
+
+        // 1. Load the manifest that the alias should be added to or removed from
+        let manifest_path = if self.global {
+            home.path().to_path_buf()
+        } else {
+            let non_global_manifests = discover_all_manifests(true, true).await;
+            non_global_manifests
+                .first()
+                .map(|m| m.path.parent().unwrap().to_path_buf())
+                .context(
+                    "No manifest was found for the current directory.\
+                    \nRun `rokit init` in your project root to create one.",
+                )?
+        };
+        let mut manifest = if self.global {
+            RokitManifest::load_or_create(&manifest_path).await?
+        } else {
+            RokitManifest::load(&manifest_path).await?
+        };
+        pt.task_completed();
+
+        let tool_storage = home.tool_storage();
+        let styled_alias = style(self.alias.to_string()).bold().cyan();
+
+        // 2a. Remove the alias, and its link if no other manifest uses it
+        let Some(target) = self.target else {
+            if !manifest.remove_tool(&self.alias) {
+                bail!(
+                    "No tool with the alias '{}' exists in the manifest.",
+                    self.alias
+                );
+            }
+            manifest.save(&manifest_path).await?;
+
+            pt.update_message("Unlinking");
+            let still_used = discover_all_manifests(false, false)
+                .await
+                .iter()
+                .any(|m| m.tools.contains_key(&self.alias));
+            if !still_used {
+                tool_storage.remove_tool_link(&self.alias).await?;
+            }
+            pt.task_completed();
+
+            pt.finish_with_message(format!(
+                "Removed alias {styled_alias} {}",
+                pt.formatted_elapsed()
+            ));
+            return Ok(());
+        };
+
+        // 2b. Figure out which tool the alias should point to, and add it
+        let spec = resolve_target(home, &manifest, target)?;
+        if manifest.has_tool(&self.alias) && !self.force {
+            bail!(
+                "A tool with the alias '{}' already exists in the manifest.\
+                \nRun the command again with `--force` to overwrite it.",
+                self.alias
+            );
+        }
+        if !manifest.add_tool(&self.alias, &spec) {
+            manifest.update_tool(&self.alias, &spec);
+        }
+        manifest.save(&manifest_path).await?;
+
+        pt.update_message("Linking");
+        tool_storage.create_tool_link(&self.alias).await?;
+        pt.task_completed();
+
+        pt.finish_with_message(format!(
+            "Added alias {styled_alias} for version {} of tool {} {}",
+            style(spec.version()).bold().yellow(),
+            style(spec.name()).bold().magenta(),
+            pt.formatted_elapsed(),
+        ));
+
+        Ok(())
+    }
+}
+
+/**
+    Resolves the tool that a new alias should point to.
+
+    The target must either already be in the manifest, or be an installed tool
+    specification - anything else must be added using `rokit add` instead.
+*/
+fn resolve_target(
+    home: &Home,
+    manifest: &RokitManifest,
+    target: ToolAliasOrIdOrSpec,
+) -> Result<ToolSpec> {
+    match target {
+        ToolAliasOrIdOrSpec::Alias(alias) => manifest
+            .get_tool(&alias)
+            .with_context(|| format!("No tool with the alias '{alias}' exists in the manifest.")),
+        ToolAliasOrIdOrSpec::Id(id) => {
+            let mut specs = manifest
+                .tool_specs()
+                .into_iter()
+                .map(|(_, spec)| spec)
+                .filter(|spec| spec.matches_id(&id))
+                .collect::<Vec<_>>();
+            specs.sort();
+            specs.dedup();
+            match specs.len() {
+                0 => bail!("No tool with the identifier '{id}' exists in the manifest."),
+                1 => Ok(specs.remove(0)),
+                _ => bail!(
+                    "Several versions of '{id}' exist in the manifest.\
+                    \nSpecify the version to use, such as '{}'.",
+                    specs[0]
+                ),
+            }
+        }
+        ToolAliasOrIdOrSpec::Spec(spec) => {
+            let in_manifest = manifest.tool_specs().iter().any(|(_, s)| s == &spec);
+            if in_manifest || home.tool_cache().is_installed(&spec) {
+                Ok(spec)
+            } else {
+                bail!(
+                    "Tool '{spec}' is not installed.\
+                    \nRun `rokit add {spec} <alias>` to add and install it."
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,286 @@
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use console::style;
+use futures::{stream::FuturesUnordered, TryStreamExt};
+use semver::Version;
+use serde_json::json;
+
+use rokit::{
+    discovery::discover_all_manifests,
+    result::ErrorKind,
+    sources::{Advisory, ArtifactProvider, ArtifactSource},
+    storage::Home,
+    tool::{ToolAlias, ToolId, ToolSpec},
+};
+
+use crate::util::{display_path, CliError, CliProgressTracker, Symbol};
+
+/// Checks tools in all discovered manifests against published security
+/// advisories, and for releases that have since been deleted.
+///
+/// Exits with a nonzero exit code if any tool is affected.
+#[derive(Debug, Parser)]
+pub struct AuditSubcommand {
+    /// Print the report as JSON, instead of in a human-readable format.
+    #[clap(long)]
+    pub json: bool,
+}
+
+impl AuditSubcommand {
+    pub async fn run(self, home: &Home) -> Result<()> {
+        // 1. Find all manifests, and the unique tools in them - tools pinned
+        // to git refs or workflow runs have no releases or versions to audit
+        let source = home.artifact_source().await?;
+        let manifests = discover_all_manifests(false, false).await;
+        let unique_specs = manifests
+            .iter()
+            .flat_map(|manifest| manifest.tools.values())
+            .filter(|spec| spec.provider() == ArtifactProvider::GitHub)
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        let unique_ids = unique_specs
+            .iter()
+            .map(|spec| spec.id().clone())
+            .collect::<BTreeSet<_>>();
+
+        let pt = if self.json {
+            None
+        } else {
+            Some(CliProgressTracker::new_with_message(
+                "Auditing",
+                unique_ids.len() + unique_specs.len(),
+            ))
+        };
+
+        // 2. Fetch advisories for all tools, and check that all releases still exist
+        let advisories = unique_ids
+            .into_iter()
+            .map(|id| async {
+                let advisories = source
+                    .get_security_advisories(&id)
+                    .await
+                    .with_context(|| format!("Failed to fetch security advisories for '{id}'"))?;
+                if let Some(pt) = &pt {
+                    pt.task_completed();
+                }
+                Ok::<_, anyhow::Error>((id, advisories))
+            })
+            .collect::<FuturesUnordered<_>>()
+            .try_collect::<HashMap<_, _>>()
+            .await?;
+        let missing_releases = unique_specs
+            .into_iter()
+            .map(|spec| async {
+                let missing = find_missing_release(&source, &spec).await?;
+                if let Some(pt) = &pt {
+                    pt.task_completed();
+                }
+                Ok::<_, anyhow::Error>((spec, missing))
+            })
+            .collect::<FuturesUnordered<_>>()
+            .try_collect::<HashMap<_, _>>()
+            .await?;
+
+        // 3. Figure out which tools are affected in each manifest
+        let report = manifests
+            .iter()
+            .map(|manifest| {
+                let mut tools = manifest
+                    .tools
+                    .iter()
+                    .filter_map(|(alias, spec)| {
+                        let advisories = advisories
+                            .get(spec.id())?
+                            .iter()
+                            .filter(|advisory| advisory.affects(spec.version()))
+                            .cloned()
+                            .collect::<Vec<_>>();
+                        let missing = missing_releases.get(spec)?.clone();
+                        if advisories.is_empty() && missing.is_none() {
+                            return None;
+                        }
+                        Some(AffectedTool {
+                            alias: alias.clone(),
+                            spec: spec.clone(),
+                            advisories,
+                            missing,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                tools.sort_by(|a, b| a.alias.name().cmp(b.alias.name()));
+                (display_path(&manifest.path), tools)
+            })
+            .filter(|(_, tools)| !tools.is_empty())
+            .collect::<Vec<_>>();
+        let num_affected = report.iter().map(|(_, tools)| tools.len()).sum::<usize>();
+
+        // 4. Print the report in the desired format
+        if self.json {
+            println!("{}", format_json(&report));
+        } else if let Some(pt) = pt {
+            if num_affected == 0 {
+                pt.finish_with_message(format!(
+                    "No known vulnerable or deleted tool versions were found! {}",
+                    pt.formatted_elapsed()
+                ));
+            } else {
+                pt.finish_with_symbol_and_message(
+                    Symbol::Alert,
+                    format!(
+                        "Found {} affected tool{} {}\n\n{}",
+                        style(num_affected).bold().red(),
+                        if num_affected == 1 { "" } else { "s" },
+                        pt.formatted_elapsed(),
+                        format_human(&report),
+                    ),
+                );
+            }
+        }
+
+        // 5. Exit with a nonzero exit code if anything is affected,
+        // without printing an error, since the report says it all
+        if num_affected > 0 {
+            return Err(CliError::silent(ErrorKind::Other).into());
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct AffectedTool {
+    alias: ToolAlias,
+    spec: ToolSpec,
+    advisories: Vec<Advisory>,
+    missing: Option<MissingRelease>,
+}
+
+impl AffectedTool {
+    /**
+        Gets the version that the tool should be upgraded to, if known -
+        the highest patched version for any of the affected advisories,
+        or the latest version if the release itself no longer exists.
+    */
+    fn suggested_version(&self) -> Option<Version> {
+        let patched = self
+            .advisories
+            .iter()
+            .filter_map(|advisory| advisory.patched_version_for(self.spec.version()))
+            .max()
+            .cloned();
+        let latest = self.missing.as_ref().and_then(|m| m.latest.clone());
+        patched.max(latest)
+    }
+}
+
+/*
+    A release that no longer exists, such as one deleted due to a bad build.
+*/
+#[derive(Debug, Clone)]
+struct MissingRelease {
+    latest: Option<Version>,
+}
+
+async fn find_missing_release(
+    source: &ArtifactSource,
+    spec: &ToolSpec,
+) -> Result<Option<MissingRelease>> {
+    match source.get_specific_release(spec).await {
+        Ok(_) => Ok(None),
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            let latest = latest_version(source, spec.id()).await;
+            Ok(Some(MissingRelease { latest }))
+        }
+        Err(e) => Err(e).with_context(|| format!("Failed to fetch release for '{spec}'")),
+    }
+}
+
+async fn latest_version(source: &ArtifactSource, id: &ToolId) -> Option<Version> {
+    let release = source.get_latest_release(id).await.ok()?;
+    let artifact = release.artifacts.first()?;
+    Some(artifact.tool_spec.version().clone())
+}
+
+fn format_human(report: &[(String, Vec<AffectedTool>)]) -> String {
+    let bullet = style(Symbol::Bullet).dim();
+    let arrow = style(Symbol::Arrow).dim();
+
+    report
+        .iter()
+        .map(|(path, tools)| {
+            let lines = tools
+                .iter()
+                .map(|tool| {
+                    let mut lines = vec![format!(
+                        "{bullet} {} {}{}",
+                        style(tool.alias.name()).bold().cyan(),
+                        style(tool.spec.version()).dim(),
+                        match tool.suggested_version() {
+                            Some(version) => format!(" {arrow} {}", style(version).bold().green()),
+                            None => String::new(),
+                        }
+                    )];
+                    for advisory in &tool.advisories {
+                        lines.push(format!(
+                            "    {} {}{}",
+                            style(&advisory.id).bold().red(),
+                            advisory.summary,
+                            match &advisory.severity {
+                                Some(severity) =>
+                                    format!(" {}", style(format!("({severity})")).dim()),
+                                None => String::new(),
+                            }
+                        ));
+                    }
+                    if tool.missing.is_some() {
+                        lines.push(format!(
+                            "    {}",
+                            style("The release for this version no longer exists").yellow()
+                        ));
+                    }
+                    lines.join("\n")
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!("{}\n{lines}", style(path).dim())
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn format_json(report: &[(String, Vec<AffectedTool>)]) -> String {
+    let manifests = report
+        .iter()
+        .map(|(path, tools)| {
+            let tools = tools
+                .iter()
+                .map(|tool| {
+                    let advisories = tool
+                        .advisories
+                        .iter()
+                        .map(|advisory| {
+                            json!({
+                                "id": advisory.id,
+                                "summary": advisory.summary,
+                                "severity": advisory.severity,
+                                "url": advisory.url.as_ref().map(ToString::to_string),
+                            })
+                        })
+                        .collect::<Vec<_>>();
+                    json!({
+                        "alias": tool.alias.name(),
+                        "id": tool.spec.id().to_string(),
+                        "current": tool.spec.version().to_string(),
+                        "suggested": tool.suggested_version().map(|v| v.to_string()),
+                        "advisories": advisories,
+                        "release_missing": tool.missing.is_some(),
+                    })
+                })
+                .collect::<Vec<_>>();
+            json!({ "path": path, "tools": tools })
+        })
+        .collect::<Vec<_>>();
+    json!({ "manifests": manifests }).to_string()
+}
@@ -3,12 +3,12 @@ use clap::Parser;
 
 use console::style;
 use rokit::{
-    manifests::AuthManifest,
+    manifests::{AuthManifest, AuthStorage},
     sources::{github::GithubProvider, ArtifactProvider},
     storage::Home,
 };
 
-use crate::util::CliProgressTracker;
+use crate::util::{CliProgressTracker, Symbol};
 
 /// Authenticate with an artifact provider, such as GitHub.
 #[derive(Debug, Parser)]
@@ -28,10 +28,24 @@ pub struct AuthenticateSubcommand {
     /// If live API verification should be skipped when adding a new token.
     #[clap(long, default_value = "false")]
     pub skip_verify: bool,
+    /// Where to store authentication tokens - either `plaintext`, in the
+    /// auth manifest, or `keychain`, using the secure storage of the OS
+    /// in builds of Rokit with the `keychain` feature enabled.
+    /// Any existing tokens are moved to the new storage.
+    #[clap(long)]
+    pub storage: Option<AuthStorage>,
+    /// The owner of the tools that the token should be used for, such as
+    /// a GitHub user or organization. Omit to use the token for all tools.
+    #[clap(long, conflicts_with = "storage")]
+    pub owner: Option<String>,
 }
 
 impl AuthenticateSubcommand {
-    pub async fn run(self, home: &Home) -> Result<()> {
+    pub async fn run(mut self, home: &Home) -> Result<()> {
+        // NOTE: Some providers download artifacts from the same host as
+        // another provider, and always use the authentication of that host
+        self.provider = self.provider.host();
+
         let pt = CliProgressTracker::new_with_message(
             "Authenticating",
             if self.token.is_some() { 4 } else { 3 },
@@ -42,23 +56,73 @@ impl AuthenticateSubcommand {
             .context("Failed to load or create auth manifest")?;
         pt.task_completed();
 
-        let styled_provider = style(self.provider.display_name())
-            .bold()
-            .white()
-            .to_string();
+        let styled_provider = match &self.owner {
+            Some(owner) => format!(
+                "{} {}",
+                style(self.provider.display_name()).bold().white(),
+                style(format!("({owner})")).bold().white(),
+            ),
+            None => style(self.provider.display_name())
+                .bold()
+                .white()
+                .to_string(),
+        };
+        let owner_arg = self
+            .owner
+            .as_ref()
+            .map(|owner| format!(" --owner {owner}"))
+            .unwrap_or_default();
         let styled_add_command = style(format!(
-            "rokit authenticate {} --token YOUR_TOKEN_HERE",
+            "rokit authenticate {}{owner_arg} --token YOUR_TOKEN_HERE",
             self.provider
         ))
         .bold()
         .green()
         .to_string();
-        let styled_remove_command = style(format!("rokit authenticate {} --remove", self.provider))
-            .bold()
-            .green()
-            .to_string();
+        let styled_remove_command = style(format!(
+            "rokit authenticate {}{owner_arg} --remove",
+            self.provider
+        ))
+        .bold()
+        .green()
+        .to_string();
+
+        // Changing the storage on its own is allowed, without touching any tokens
+        let storage_changed = self
+            .storage
+            .is_some_and(|storage| auth.set_storage(storage));
+        if let Some(storage) = self
+            .storage
+            .filter(|_| self.token.is_none() && !self.remove)
+        {
+            pt.update_message("Saving");
+            auth.save(home.path()).await?;
+            pt.finish_with_symbol_and_message(
+                Symbol::Check,
+                format!(
+                    "{} {} {}",
+                    if storage_changed {
+                        "Authentication tokens are now stored in"
+                    } else {
+                        "Authentication tokens were already stored in"
+                    },
+                    style(match storage {
+                        AuthStorage::Plaintext => "the auth manifest",
+                        AuthStorage::Keychain => "the OS keychain",
+                    })
+                    .bold(),
+                    pt.formatted_elapsed()
+                ),
+            );
+            return Ok(());
+        }
 
-        let exists = auth.has_token(self.provider);
+        let exists = match &self.owner {
+            Some(owner) => auth
+                .get_owner_tokens(self.provider)
+                .contains_key(&owner.to_lowercase()),
+            None => auth.has_token(self.provider),
+        };
         if self.remove {
             if !exists {
                 bail!(
@@ -74,7 +138,10 @@ impl AuthenticateSubcommand {
         }
 
         if self.remove {
-            let was_removed = auth.unset_token(self.provider);
+            let was_removed = match &self.owner {
+                Some(owner) => auth.unset_owner_token(self.provider, owner),
+                None => auth.unset_token(self.provider),
+            };
             assert!(was_removed, "token was not removed");
         } else if let Some(token) = self.token {
             let token = token.trim().to_string();
@@ -83,7 +150,10 @@ impl AuthenticateSubcommand {
             verify_token(self.provider, &token, self.skip_parse, self.skip_verify).await?;
             pt.task_completed();
 
-            let had_token = auth.set_token(self.provider, token);
+            let had_token = match &self.owner {
+                Some(owner) => auth.set_owner_token(self.provider, owner, token),
+                None => auth.set_token(self.provider, token),
+            };
             assert!(!had_token, "token was overwritten");
         } else {
             bail!(
@@ -95,8 +165,8 @@ impl AuthenticateSubcommand {
         pt.update_message("Saving");
         auth.save(home.path()).await?;
 
-        pt.finish_with_emoji_and_message(
-            "✓",
+        pt.finish_with_symbol_and_message(
+            Symbol::Check,
             format!(
                 "{}{} {styled_provider} authentication successfully. {}",
                 if self.remove { "Removed" } else { "Added" },
@@ -122,15 +192,17 @@ async fn verify_token(
     // Verify the formatting of the token, if desired.
     if !skip_parse {
         let validated = match provider {
-            ArtifactProvider::GitHub => {
+            ArtifactProvider::GitHub
+            | ArtifactProvider::GitHubBranch
+            | ArtifactProvider::GitHubActions => {
                 is_gh_classic_token(token) || is_gh_fine_grained_token(token)
             }
         };
 
         if !validated {
-            let bullet = style("•").dim();
+            let bullet = style(Symbol::Bullet).dim();
             let valid_formats = match provider {
-                ArtifactProvider::GitHub => vec![
+                ArtifactProvider::GitHub | ArtifactProvider::GitHubBranch | ArtifactProvider::GitHubActions => vec![
                     format!("{bullet} Starting with 'gh' followed by a lowercase letter and an underscore"),
                     format!("{bullet} Starting with 'github_pat_'"),
                 ],
@@ -162,7 +234,9 @@ async fn verify_token(
     // Verify the actual validity of the token, if desired.
     if !skip_verify {
         let verified = match provider {
-            ArtifactProvider::GitHub => {
+            ArtifactProvider::GitHub
+            | ArtifactProvider::GitHubBranch
+            | ArtifactProvider::GitHubActions => {
                 let client = GithubProvider::new_authenticated(token)?;
                 let verify_res = client.verify_authentication().await;
                 verify_res.context("GitHub API returned an error during token verification")?
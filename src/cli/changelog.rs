@@ -0,0 +1,99 @@
+use std::io::{stdout, IsTerminal};
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use console::style;
+use semver::Version;
+
+use rokit::{discovery::discover_tool_spec, storage::Home, tool::ToolId};
+
+use crate::util::{render_markdown, CliProgressTracker, Symbol, ToolAliasOrIdOrSpec, ToolIdOrSpec};
+
+/// Shows the changelog for a tool, using the notes of one of its releases.
+#[derive(Debug, Parser)]
+pub struct ChangelogSubcommand {
+    /// The tool to show the changelog for - can be an alias, identifier, or specification.
+    pub tool: ToolAliasOrIdOrSpec,
+    /// The version to show the changelog for.
+    /// Omit to show the changelog for the latest version.
+    pub version: Option<Version>,
+}
+
+impl ChangelogSubcommand {
+    pub async fn run(self, home: &Home) -> Result<()> {
+        let (id, version) = resolve_tool(self.tool, self.version).await?;
+
+        let pt = CliProgressTracker::new_with_message("Fetching", 1);
+        let source = home.artifact_source().await?;
+        let changelog = source
+            .get_release_changelog(&id, version.as_ref())
+            .await
+            .with_context(|| format!("Failed to fetch release for '{id}'"))?;
+        pt.task_completed();
+
+        let styled_version = match &version {
+            Some(version) => style(format!("version {version}")).bold().yellow(),
+            None => style("the latest version".to_string()).bold().yellow(),
+        };
+        let Some(changelog) = changelog else {
+            pt.finish_with_message(format!(
+                "No changelog was published for {styled_version} of tool {} {}",
+                style(id.name()).bold().magenta(),
+                pt.formatted_elapsed(),
+            ));
+            return Ok(());
+        };
+
+        pt.finish_with_symbol_and_message(
+            Symbol::Changelog,
+            format!(
+                "Fetched the changelog for {styled_version} of tool {} {}",
+                style(id.name()).bold().magenta(),
+                pt.formatted_elapsed(),
+            ),
+        );
+
+        // Only render the changelog when it is shown in a terminal,
+        // so that it can also be piped to files and other programs
+        println!();
+        if stdout().is_terminal() {
+            render_markdown(&changelog)?;
+        } else {
+            println!("{changelog}");
+        }
+
+        Ok(())
+    }
+}
+
+/**
+    Resolves the tool identifier and version to fetch the changelog for.
+
+    Aliases are looked up in discovered manifests first, and then in
+    the list of well-known tools, and an explicitly given version always
+    takes precedence over the version of a tool specification.
+*/
+async fn resolve_tool(
+    tool: ToolAliasOrIdOrSpec,
+    version: Option<Version>,
+) -> Result<(ToolId, Option<Version>)> {
+    Ok(match tool {
+        ToolAliasOrIdOrSpec::Alias(alias) => {
+            if let Some(tool) = discover_tool_spec(&alias, false, false).await {
+                (tool.spec.id().clone(), version)
+            } else if let Ok(ToolIdOrSpec::Id(id)) = alias.name().parse::<ToolIdOrSpec>() {
+                (id, version)
+            } else {
+                bail!(
+                    "No tool with the alias '{alias}' was found in any manifest.\
+                    \nUse a tool identifier, such as `author/name`, instead."
+                );
+            }
+        }
+        ToolAliasOrIdOrSpec::Id(id) => (id, version),
+        ToolAliasOrIdOrSpec::Spec(spec) => {
+            let version = version.unwrap_or_else(|| spec.version().clone());
+            (spec.id().clone(), Some(version))
+        }
+    })
+}
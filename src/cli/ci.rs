@@ -0,0 +1,268 @@
+use std::{
+    collections::BTreeSet,
+    env::var,
+    fmt::Write as _,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use ring::digest::{digest, SHA256};
+use tokio::{fs::OpenOptions, io::AsyncWriteExt};
+use tracing::warn;
+
+use rokit::{
+    descriptor::Descriptor,
+    discovery::{discover_all_manifests, discover_strict_platform, DiscoveredManifest},
+    storage::Home,
+    tool::ToolAlias,
+};
+
+use super::outdated::{fetch_latest_versions, tool_channel};
+
+/// Prepares Rokit for use in CI, and reports problems with tools.
+///
+/// Prints a cache key for installed tools, based on the tools in all discovered
+/// manifests and the current system, along with the directory to cache, and the
+/// bin directory to add to the PATH. In GitHub Actions, these are also set as
+/// step outputs, the bin directory is added to the PATH, and outdated or
+/// untrusted tools are reported as workflow annotations.
+#[derive(Debug, Parser)]
+pub struct CiSubcommand {
+    /// Skip checking for outdated tools, which requires network access.
+    #[clap(long)]
+    pub skip_outdated: bool,
+}
+
+impl CiSubcommand {
+    pub async fn run(self, home: &Home) -> Result<()> {
+        let manifests = discover_all_manifests(false, false).await;
+        let github = var("GITHUB_ACTIONS").is_ok_and(|value| value == "true");
+
+        // 1. Print the cache key and paths, and pass them on to GitHub Actions
+        // NOTE: Only tool storage is cached, never the entire home directory,
+        // since the home directory also contains authentication tokens
+        let outputs = [
+            ("cache-key", cache_key(&manifests)),
+            (
+                "cache-path",
+                home.path().join("tool-storage").display().to_string(),
+            ),
+            ("bin-dir", home.bin_dir().display().to_string()),
+        ];
+        let outputs = outputs.iter().fold(String::new(), |mut s, (name, value)| {
+            let _ = writeln!(s, "{name}={value}");
+            s
+        });
+        print!("{outputs}");
+
+        if let Some(path) = github_file("GITHUB_OUTPUT") {
+            append_to_file(&path, &outputs)
+                .await
+                .context("Failed to write GitHub Actions step outputs")?;
+        }
+        if let Some(path) = github_file("GITHUB_PATH") {
+            append_to_file(&path, &format!("{}\n", home.bin_dir().display()))
+                .await
+                .context("Failed to add the bin directory to the GitHub Actions PATH")?;
+        }
+
+        // 2. Report any tools that have not been trusted, which
+        // would otherwise make `rokit install` fail further along
+        let tool_cache = home.tool_cache();
+        for manifest in &manifests {
+            let mut tools = manifest.tools.iter().collect::<Vec<_>>();
+            tools.sort_by_key(|(alias, _)| *alias);
+            for (alias, spec) in tools {
+                if !tool_cache.is_trusted_spec(spec) {
+                    annotate(
+                        github,
+                        Annotation::Warning,
+                        &manifest.path,
+                        alias,
+                        "Untrusted tool",
+                        &format!(
+                            "Tool '{alias}' ({}) is not trusted - run `rokit trust {}` \
+                            or `rokit install --no-trust-check` to install it.",
+                            spec.id(),
+                            spec.id(),
+                        ),
+                    )
+                    .await;
+                }
+            }
+        }
+
+        // 3. Report any tools with newer versions available
+        if self.skip_outdated {
+            return Ok(());
+        }
+        let source = home.artifact_source().await?;
+        let strict_platform = discover_strict_platform().await;
+        let latest_versions =
+            match fetch_latest_versions(&source, &manifests, strict_platform, None).await {
+                Ok(versions) => versions,
+                Err(e) => {
+                    warn!("Failed to check for outdated tools: {e:#}");
+                    return Ok(());
+                }
+            };
+        for manifest in &manifests {
+            let mut tools = manifest.tools.iter().collect::<Vec<_>>();
+            tools.sort_by_key(|(alias, _)| *alias);
+            for (alias, spec) in tools {
+                let key = (spec.id().clone(), tool_channel(manifest, alias));
+                let Some(latest) = latest_versions.get(&key) else {
+                    continue;
+                };
+                if latest > spec.version() {
+                    annotate(
+                        github,
+                        Annotation::Notice,
+                        &manifest.path,
+                        alias,
+                        "Outdated tool",
+                        &format!(
+                            "Tool '{alias}' can be updated from {} to {latest} - \
+                            run `rokit update {alias}` to update it.",
+                            spec.version(),
+                        ),
+                    )
+                    .await;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/*
+    Computes a deterministic cache key for the tools in the given
+    manifests, which changes whenever any tool or the system changes,
+    but not when manifests are reordered or reformatted.
+*/
+fn cache_key(manifests: &[DiscoveredManifest]) -> String {
+    let specs = manifests
+        .iter()
+        .flat_map(|manifest| manifest.tools.values())
+        .map(ToString::to_string)
+        .collect::<BTreeSet<_>>();
+    let contents = specs.into_iter().collect::<Vec<_>>().join("\n");
+    let hash = digest(&SHA256, contents.as_bytes())
+        .as_ref()
+        .iter()
+        .take(8)
+        .fold(String::with_capacity(16), |mut s, byte| {
+            let _ = write!(s, "{byte:02x}");
+            s
+        });
+
+    let system = Descriptor::current_system();
+    let arch = system.arch().map_or("unknown", |arch| arch.as_str());
+    format!("rokit-{}-{arch}-{hash}", system.os().as_str())
+}
+
+fn github_file(name: &str) -> Option<PathBuf> {
+    var(name)
+        .ok()
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
+async fn append_to_file(path: &Path, contents: &str) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    file.write_all(contents.as_bytes()).await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Annotation {
+    Notice,
+    Warning,
+}
+
+/*
+    Emits a workflow annotation for a tool in a manifest, when running in
+    GitHub Actions, or a plain warning log for any other CI - annotations
+    point at the line declaring the tool, if the manifest is in the workspace.
+*/
+async fn annotate(
+    github: bool,
+    kind: Annotation,
+    manifest_path: &Path,
+    alias: &ToolAlias,
+    title: &str,
+    message: &str,
+) {
+    if !github {
+        warn!("{title}: {message}");
+        return;
+    }
+
+    let mut properties = Vec::new();
+    let relative = var("GITHUB_WORKSPACE")
+        .ok()
+        .filter(|workspace| !workspace.is_empty())
+        .and_then(|workspace| manifest_path.strip_prefix(workspace).ok());
+    if let Some(relative) = relative {
+        let file = relative.display().to_string().replace('\\', "/");
+        properties.push(format!("file={}", escape_property(&file)));
+        let contents = tokio::fs::read_to_string(manifest_path)
+            .await
+            .unwrap_or_default();
+        if let Some(line) = find_alias_line(&contents, alias) {
+            properties.push(format!("line={line}"));
+        }
+    }
+    properties.push(format!("title={}", escape_property(title)));
+
+    let command = match kind {
+        Annotation::Notice => "notice",
+        Annotation::Warning => "warning",
+    };
+    println!(
+        "::{command} {}::{}",
+        properties.join(","),
+        escape_data(message)
+    );
+}
+
+/*
+    Finds the one-based line number where the given tool alias is declared
+    in the contents of a manifest, either as a key or as a table header.
+*/
+fn find_alias_line(contents: &str, alias: &ToolAlias) -> Option<usize> {
+    let alias = alias.name().to_lowercase();
+    contents
+        .lines()
+        .position(|line| {
+            let line = line.trim_start().to_lowercase();
+            let key = line
+                .strip_prefix("[tools.")
+                .unwrap_or(&line)
+                .trim_start_matches(['"', '\'']);
+            key.strip_prefix(&alias).is_some_and(|rest| {
+                rest.trim_start_matches(['"', '\''])
+                    .trim_start()
+                    .starts_with(['=', ']'])
+            })
+        })
+        .map(|index| index + 1)
+}
+
+fn escape_data(data: &str) -> String {
+    data.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(property: &str) -> String {
+    escape_data(property)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
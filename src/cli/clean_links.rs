@@ -0,0 +1,116 @@
+use std::{collections::HashSet, env::consts::EXE_SUFFIX, path::Path};
+
+use anyhow::Result;
+use clap::Parser;
+use console::style;
+
+use rokit::{discovery::discover_all_manifests, storage::Home};
+
+use crate::util::{display_path, CliProgressTracker, Symbol};
+
+/// Removes tool links for aliases that are no longer in any known manifest.
+///
+/// Aliases are known from the global manifest and all manifests discovered
+/// from the current directory. Since other projects can not be discovered,
+/// links named after any installed tool are also kept - this is best-effort,
+/// and running `rokit install` in a project recreates any links it needs.
+#[derive(Debug, Parser)]
+pub struct CleanLinksSubcommand {
+    /// Print the links that would be removed, without removing them.
+    #[clap(long)]
+    pub dry_run: bool,
+}
+
+impl CleanLinksSubcommand {
+    pub async fn run(self, home: &Home) -> Result<()> {
+        let storage = home.tool_storage();
+
+        // 1. Gather all aliases that may still be in use - note that
+        // aliases are case-insensitive, same as the links for them
+        let mut known = discover_all_manifests(false, false)
+            .await
+            .into_iter()
+            .flat_map(|manifest| manifest.tools.into_keys())
+            .map(|alias| alias.name().to_lowercase())
+            .collect::<HashSet<_>>();
+        known.extend(
+            home.tool_cache()
+                .all_installed_ids()
+                .into_iter()
+                .map(|id| id.name().to_lowercase()),
+        );
+
+        // 2. Find all links that are not for any of those aliases, where
+        // links with a version suffix, such as `rojo@7.4.4`, use their alias
+        let mut orphans = storage
+            .all_link_paths()
+            .await?
+            .into_iter()
+            .filter(|path| {
+                let alias = link_alias(path);
+                !alias.is_empty() && !known.contains(&alias)
+            })
+            .collect::<Vec<_>>();
+        orphans.sort();
+
+        let list_bullet = style(Symbol::Bullet).dim();
+        let list = orphans
+            .iter()
+            .map(|path| format!("  {list_bullet} {}", display_path(path)))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if self.dry_run {
+            if orphans.is_empty() {
+                println!("No tool links would be removed.");
+            } else {
+                println!(
+                    "These tool links would be removed:\n{list}\n\nRun without `{}` to remove them.",
+                    style("--dry-run").bold()
+                );
+            }
+            return Ok(());
+        }
+
+        // 3. Remove the orphaned links
+        let pt = CliProgressTracker::new_with_message("Removing", orphans.len());
+        for path in &orphans {
+            storage.remove_link_path(path).await?;
+            pt.task_completed();
+        }
+
+        if orphans.is_empty() {
+            pt.finish_with_message(format!(
+                "No tool links needed to be removed {}",
+                pt.formatted_elapsed()
+            ));
+        } else {
+            pt.finish_with_message(format!(
+                "Removed {} tool link{} {}\n\n{list}",
+                orphans.len(),
+                if orphans.len() == 1 { "" } else { "s" },
+                pt.formatted_elapsed(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/**
+    Gets the lowercase alias for the tool link at the given path,
+    without any executable extension or version suffix.
+*/
+fn link_alias(path: &Path) -> String {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let name = file_name
+        .strip_suffix(&EXE_SUFFIX.to_lowercase())
+        .unwrap_or(&file_name);
+    name.split(['@', '+'])
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
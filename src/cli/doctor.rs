@@ -0,0 +1,173 @@
+use std::fmt::Write as _;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use console::style;
+
+use rokit::{
+    discovery::{discover_all_manifests, discover_shadowed_tools, ShadowedTool},
+    storage::Home,
+};
+
+use crate::util::{display_path, CliProgressTracker, Symbol};
+
+/// Checks the Rokit home directory for problems, such as pending migrations,
+/// installed tools that were modified after being installed, and tools that
+/// are shadowed by other executables earlier in the PATH.
+///
+/// Migrations upgrade homes created by older versions of Rokit, and run
+/// automatically, but may be retried here if they failed to run before.
+#[derive(Debug, Parser)]
+pub struct DoctorSubcommand {
+    /// Run any pending migrations for the Rokit home directory.
+    #[clap(long)]
+    pub migrate: bool,
+}
+
+impl DoctorSubcommand {
+    pub async fn run(self, home: &Home) -> Result<()> {
+        self.check_migrations(home).await?;
+        check_installed_tools(home).await;
+
+        let manifests = discover_all_manifests(false, false).await;
+        let shadowed = discover_shadowed_tools(home, &manifests).await;
+        if !shadowed.is_empty() {
+            println!(
+                "\nSome tools run executables that are not managed by Rokit:\n{}\
+                \nMake sure that the Rokit bin directory at {} comes first in your PATH to use Rokit tools.",
+                format_shadowed_tools(&shadowed),
+                style(display_path(home.bin_dir())).bold(),
+            );
+        }
+
+        Ok(())
+    }
+
+    async fn check_migrations(&self, home: &Home) -> Result<()> {
+        let bullet = style(Symbol::Bullet).dim();
+        let pending = home.pending_migrations().await;
+
+        if pending.is_empty() {
+            println!(
+                "The Rokit home directory at {} is up to date.",
+                style(display_path(home.path())).bold()
+            );
+            return Ok(());
+        }
+
+        let list = pending
+            .iter()
+            .map(|m| format!("  {bullet} {} (version {})", m.description, m.version))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if !self.migrate {
+            println!(
+                "The Rokit home directory at {} has pending migrations:\n{list}\
+                \n\nRun `{}` to apply them.",
+                style(display_path(home.path())).bold(),
+                style("rokit doctor --migrate").bold().green(),
+            );
+            return Ok(());
+        }
+
+        let pt = CliProgressTracker::new_with_message("Migrating", 1);
+        let applied = home
+            .migrate()
+            .await
+            .context("Failed to migrate the Rokit home directory")?;
+        pt.task_completed();
+        pt.finish_with_message(format!(
+            "Applied {} migration{} {}\n\n{list}",
+            style(applied.len()).bold().magenta(),
+            if applied.len() == 1 { "" } else { "s" },
+            pt.formatted_elapsed(),
+        ));
+
+        Ok(())
+    }
+}
+
+/*
+    Checks installed tools against the checksums recorded in their metadata
+    when they were installed, to find binaries that have since gone missing,
+    or were modified, such as by a partially restored cache or by hand.
+*/
+async fn check_installed_tools(home: &Home) {
+    let bullet = style(Symbol::Bullet).dim();
+    let storage = home.tool_storage();
+
+    let mut problems = Vec::new();
+    for spec in home.tool_cache().all_installed() {
+        let Some(meta) = storage.tool_metadata(&spec).await else {
+            continue;
+        };
+        let Some(expected) = meta.binary_sha256() else {
+            continue;
+        };
+        match storage.tool_sha256(&spec).await {
+            None => problems.push(format!("  {bullet} {spec} {}", style("(missing)").dim())),
+            Some(actual) if actual != expected => {
+                problems.push(format!("  {bullet} {spec} {}", style("(modified)").dim()));
+            }
+            Some(_) => {}
+        }
+    }
+
+    if !problems.is_empty() {
+        println!(
+            "\nSome installed tools no longer match the binaries they were installed with:\n{}\
+            \n\nRun `{}` to reinstall them.",
+            problems.join("\n"),
+            style("rokit install --force").bold().green(),
+        );
+    }
+}
+
+/**
+    Formats the given shadowed tools as a list, with every executable
+    found for each alias, marking the one that runs and the Rokit link.
+*/
+pub(super) fn format_shadowed_tools(tools: &[ShadowedTool]) -> String {
+    let bullet = style(Symbol::Bullet).dim();
+    let arrow = style(Symbol::Arrow).dim();
+
+    let mut s = String::new();
+    for tool in tools {
+        let _ = writeln!(
+            s,
+            "  {bullet} {} {arrow} runs {}",
+            style(tool.alias.name()).bold().cyan(),
+            style(display_path(tool.resolved())).bold().yellow(),
+        );
+        for (index, path) in tool.executables.iter().enumerate() {
+            let branch = if index + 1 == tool.executables.len() {
+                Symbol::TreeEnd
+            } else {
+                Symbol::TreeBranch
+            };
+            let note = if index == 0 {
+                " (runs)"
+            } else if tool.rokit_link.as_ref() == Some(path) {
+                " (Rokit)"
+            } else {
+                ""
+            };
+            let _ = writeln!(
+                s,
+                "    {} {}{}",
+                style(branch).dim(),
+                display_path(path),
+                style(note).dim()
+            );
+        }
+        if tool.rokit_link.is_none() {
+            let _ = writeln!(
+                s,
+                "    {}",
+                style("The Rokit link for this tool is not in the PATH").dim()
+            );
+        }
+    }
+    s
+}
@@ -0,0 +1,84 @@
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use console::style;
+
+use rokit::{
+    discovery::discover_all_manifests,
+    manifests::{RokitManifest, ROKIT_MANIFEST_FILE_NAME},
+    storage::Home,
+};
+
+use crate::util::{display_path, CliProgressTracker};
+
+/// Formats the nearest Rokit manifest, sorting tools alphabetically.
+///
+/// Whitespace and quoting are normalized, and comments are kept
+/// along with the tools they describe, to reduce noise in diffs.
+#[derive(Debug, Parser)]
+pub struct FmtSubcommand {
+    /// Format the global manifest instead of the nearest manifest file.
+    #[clap(long)]
+    pub global: bool,
+    /// Check if the manifest is formatted, without changing it,
+    /// and exit with an error if it is not - useful for CI.
+    #[clap(long)]
+    pub check: bool,
+}
+
+impl FmtSubcommand {
+    pub async fn run(self, home: &Home) -> Result<()> {
+        let manifest_dir = if self.global {
+            home.path().to_path_buf()
+        } else {
+            discover_all_manifests(true, true)
+                .await
+                .first()
+                .map(|m| m.path.parent().unwrap().to_path_buf())
+                .context(
+                    "No manifest was found for the current directory.\
+                    \nRun `rokit init` in your project root to create one.",
+                )?
+        };
+        let manifest_path = manifest_dir.join(ROKIT_MANIFEST_FILE_NAME);
+
+        let mut manifest = RokitManifest::load(&manifest_dir).await?;
+        let changed = manifest.format();
+
+        if self.check {
+            if changed {
+                bail!(
+                    "Manifest is not formatted: {}\
+                    \nRun `{}` to format it.",
+                    display_path(&manifest_path),
+                    style("rokit fmt").bold().green(),
+                );
+            }
+            println!("Manifest is formatted: {}", display_path(&manifest_path));
+            return Ok(());
+        }
+
+        // NOTE: We use a progress bar only to show the final message to the
+        // user below, to maintain consistent formatting with other commands.
+        let pt = CliProgressTracker::new_with_message("Formatting", 1);
+        if changed {
+            manifest
+                .save(&manifest_dir)
+                .await
+                .context("Failed to save formatted manifest")?;
+        }
+        pt.task_completed();
+
+        pt.finish_with_message(format!(
+            "{} {} {}",
+            if changed {
+                "Formatted manifest at"
+            } else {
+                "Manifest is already formatted at"
+            },
+            display_path(&manifest_path),
+            pt.formatted_elapsed(),
+        ));
+
+        Ok(())
+    }
+}
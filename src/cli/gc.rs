@@ -0,0 +1,62 @@
+use anyhow::Result;
+use clap::Parser;
+use console::style;
+
+use rokit::storage::Home;
+
+use crate::util::{format_bytes, CliProgressTracker};
+
+/// Removes stored tool files that are no longer used by any installed tool.
+///
+/// Installed tools share identical files through a content-addressed store,
+/// so removing or reinstalling a tool version does not free its files right
+/// away - this removes any stored files that no installed version links to.
+#[derive(Debug, Parser)]
+pub struct GcSubcommand {
+    /// Print how much would be removed, without removing anything.
+    #[clap(long)]
+    pub dry_run: bool,
+}
+
+impl GcSubcommand {
+    pub async fn run(self, home: &Home) -> Result<()> {
+        let storage = home.tool_storage();
+
+        if self.dry_run {
+            let collected = storage.collect_garbage(true).await?;
+            if collected.blobs == 0 {
+                println!("No unused tool files would be removed.");
+            } else {
+                println!(
+                    "{} unused tool file{} would be removed, freeing {}.\n\nRun without `{}` to remove them.",
+                    collected.blobs,
+                    if collected.blobs == 1 { "" } else { "s" },
+                    format_bytes(collected.bytes),
+                    style("--dry-run").bold()
+                );
+            }
+            return Ok(());
+        }
+
+        let pt = CliProgressTracker::new_with_message("Removing", 1);
+        let collected = storage.collect_garbage(false).await?;
+        pt.task_completed();
+
+        if collected.blobs == 0 {
+            pt.finish_with_message(format!(
+                "No unused tool files needed to be removed {}",
+                pt.formatted_elapsed()
+            ));
+        } else {
+            pt.finish_with_message(format!(
+                "Removed {} unused tool file{}, freeing {} {}",
+                collected.blobs,
+                if collected.blobs == 1 { "" } else { "s" },
+                format_bytes(collected.bytes),
+                pt.formatted_elapsed(),
+            ));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+use console::style;
+
+use rokit::{
+    discovery::{discover_all_manifests, discover_extract_limits, discover_strict_platform},
+    install::{execute, plan, ExecuteOptions, PlanOptions},
+    manifests::RokitManifest,
+    storage::Home,
+};
+
+/// Prints a shell hook that checks for missing project tools
+/// whenever the current directory changes.
+///
+/// Add the output to your shell configuration to enable it:
+///
+/// - bash: `eval "$(rokit hook bash)"` in ~/.bashrc
+///
+/// - zsh: `eval "$(rokit hook zsh)"` in ~/.zshrc
+///
+/// - fish: `rokit hook fish | source` in ~/.config/fish/config.fish
+///
+/// When entering a project with tools that are not installed, the hook prints
+/// a hint to install them, or installs them, if `hook-install = true` is set
+/// in the global manifest and all of the tools have been trusted.
+#[derive(Debug, Parser)]
+pub struct HookSubcommand {
+    /// The shell to print the hook for.
+    #[clap(value_enum, required_unless_present = "check")]
+    pub shell: Option<HookShell>,
+    /// Check the current directory for missing tools - used by the hook itself.
+    #[clap(long, hide = true, conflicts_with = "shell")]
+    pub check: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum HookShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl HookShell {
+    fn script(self) -> &'static str {
+        match self {
+            Self::Bash => BASH_HOOK,
+            Self::Zsh => ZSH_HOOK,
+            Self::Fish => FISH_HOOK,
+        }
+    }
+}
+
+// NOTE: All hooks only check once per directory, before the prompt
+// is shown, so that they also check the directory a shell starts in
+
+const BASH_HOOK: &str = r#"_rokit_hook() {
+  local previous_exit_status=$?
+  if [[ "${_ROKIT_LAST_DIR-}" != "$PWD" ]]; then
+    _ROKIT_LAST_DIR="$PWD"
+    rokit hook --check --quiet
+  fi
+  return $previous_exit_status
+}
+if [[ ";${PROMPT_COMMAND[*]:-};" != *";_rokit_hook;"* ]]; then
+  PROMPT_COMMAND="_rokit_hook${PROMPT_COMMAND:+;$PROMPT_COMMAND}"
+fi
+"#;
+
+const ZSH_HOOK: &str = r#"_rokit_hook() {
+  if [[ "${_ROKIT_LAST_DIR-}" != "$PWD" ]]; then
+    _ROKIT_LAST_DIR="$PWD"
+    rokit hook --check --quiet
+  fi
+}
+typeset -ag precmd_functions
+if (( ! ${precmd_functions[(I)_rokit_hook]} )); then
+  precmd_functions=(_rokit_hook $precmd_functions)
+fi
+"#;
+
+const FISH_HOOK: &str = r#"function _rokit_hook --on-event fish_prompt
+    if test "$_ROKIT_LAST_DIR" != "$PWD"
+        set -g _ROKIT_LAST_DIR "$PWD"
+        rokit hook --check --quiet
+    end
+end
+"#;
+
+impl HookSubcommand {
+    pub async fn run(self, home: &Home) -> Result<()> {
+        if let Some(shell) = self.shell {
+            print!("{}", shell.script());
+            return Ok(());
+        }
+
+        // NOTE: This runs on every directory change, so it must be fast - only
+        // project manifests are read, Foreman requirements use the versions
+        // that were previously resolved, and nothing is fetched from the network
+        let manifests = discover_all_manifests(false, true).await;
+        if manifests.is_empty() {
+            return Ok(());
+        }
+
+        let options = PlanOptions {
+            force: false,
+            skip_trust_check: true,
+        };
+        let missing_plan = plan(home, &manifests, options).await;
+        let missing = missing_plan.tools_to_install();
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        // Aliases are friendlier than full tool specs in a one-line hint
+        let aliases = manifests
+            .iter()
+            .flat_map(|manifest| &manifest.tools)
+            .filter(|(_, spec)| missing.contains(spec))
+            .map(|(alias, _)| alias.name().to_string())
+            .collect::<BTreeSet<_>>();
+        let count = aliases.len();
+        let aliases = aliases.into_iter().collect::<Vec<_>>().join(", ");
+
+        let tool_cache = home.tool_cache();
+        let all_trusted = missing.iter().all(|spec| tool_cache.is_trusted_spec(spec));
+        let install_enabled = RokitManifest::load(home.path())
+            .await
+            .ok()
+            .and_then(|manifest| manifest.get_hook_install())
+            .unwrap_or(false);
+
+        if install_enabled && all_trusted {
+            let source = home.artifact_source().await?;
+            let install_plan = plan(home, &manifests, PlanOptions::default()).await;
+            let options = ExecuteOptions {
+                strict_platform: discover_strict_platform().await,
+                extract_limits: discover_extract_limits().await,
+                ..ExecuteOptions::default()
+            };
+            let failure = match execute(home, &source, &install_plan, &options, |_| {}).await {
+                Ok(results) => results.into_iter().find_map(|(_, result)| result.err()),
+                Err(e) => Some(e),
+            };
+            match failure {
+                None => eprintln!("rokit: installed {}", style(&aliases).bold().cyan()),
+                Some(e) => eprintln!(
+                    "rokit: failed to install {} - {e}",
+                    style(&aliases).bold().cyan()
+                ),
+            }
+        } else {
+            eprintln!(
+                "rokit: {} {} not installed for this project - run {} to install",
+                style(&aliases).bold().cyan(),
+                if count == 1 { "is" } else { "are" },
+                style("rokit install").bold().green(),
+            );
+        }
+
+        Ok(())
+    }
+}
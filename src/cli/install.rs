@@ -1,16 +1,38 @@
-use std::collections::BTreeSet;
+use std::{collections::BTreeSet, path::Path};
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Error, Result};
 use clap::Parser;
 
 use console::style;
-use futures::{stream::FuturesUnordered, TryStreamExt};
-use rokit::{discovery::discover_all_manifests, storage::Home};
+use rokit::{
+    descriptor::Descriptor,
+    discovery::{
+        discover_all_manifests, discover_extract_limits, discover_large_download_threshold,
+        discover_lockfile_dir, discover_strict_platform, discover_unmigrated_aftman_manifest,
+        find_alias_collisions, resolve_foreman_requirements, AliasCollision, DiscoveredManifest,
+    },
+    install::{
+        execute, lock, plan, ExecuteOptions, InstallAction, InstallOutcome, InstallPlan,
+        InstallProgress, PlanOptions, SkipReason,
+    },
+    manifests::{Lockfile, LOCKFILE_NAME},
+    result::{ErrorKind, RokitError, RokitResult},
+    sources::ArtifactSource,
+    storage::{Home, TrustScope},
+    tool::{ToolAlias, ToolSpec},
+};
+use tracing::warn;
 
-use crate::util::{find_most_compatible_artifact, prompt_for_trust_specs, CliProgressTracker};
+use crate::util::{
+    display_path, format_bytes, format_speed, prompt_for_aftman_migration, prompt_for_trust_specs,
+    warn_if_emulated, warn_if_large, CliError, CliProgressTracker, Symbol,
+};
+
+use super::migrate::migrate_aftman_manifest;
 
 /// Adds a new tool using Rokit and installs it.
 #[derive(Debug, Parser)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct InstallSubcommand {
     /// Skip checking if tools have been trusted before.
     /// It is recommended to only use this on CI machines.
@@ -19,6 +41,18 @@ pub struct InstallSubcommand {
     /// Force install all tools, even if they are already installed.
     #[clap(long)]
     pub force: bool,
+    /// Stop installing tools as soon as any tool fails to install,
+    /// instead of installing all other tools and summarizing failures.
+    #[clap(long)]
+    pub fail_fast: bool,
+    /// Show what would be resolved, downloaded, extracted,
+    /// linked, or skipped, without installing anything.
+    #[clap(long)]
+    pub dry_run: bool,
+    /// Install the exact tools recorded in the lockfile (rokit.lock),
+    /// without reading any manifests or updating the lockfile.
+    #[clap(long)]
+    pub locked: bool,
 }
 
 impl InstallSubcommand {
@@ -26,112 +60,407 @@ impl InstallSubcommand {
         let force = self.force;
 
         let source = home.artifact_source().await?;
-        let manifests = discover_all_manifests(false, false).await;
-
-        let tool_cache = home.tool_cache();
-        let tool_storage = home.tool_storage();
+        let large_download_threshold = discover_large_download_threshold().await;
+        let options = ExecuteOptions {
+            strict_platform: discover_strict_platform().await,
+            extract_limits: discover_extract_limits().await,
+            fail_fast: self.fail_fast,
+            ..ExecuteOptions::default()
+        };
 
-        // 1. Gather tool specifications from all known manifests
+        let manifests = if self.locked {
+            vec![load_locked_manifest().await?]
+        } else {
+            discover_manifests(home, &source, self.dry_run).await?
+        };
 
-        let tools = manifests
-            .iter()
-            .flat_map(|manifest| manifest.tools.clone().into_iter())
-            .collect::<Vec<_>>();
+        let plan_options = PlanOptions {
+            force,
+            skip_trust_check: self.no_trust_check,
+        };
+        if self.dry_run {
+            let plan = plan(home, &manifests, plan_options).await;
+            println!("{}", format_plan(&plan));
+            return Ok(());
+        }
 
-        // 2. Check for trust
+        // 1. Check for trust - tools that are still not
+        // trusted afterwards are skipped by the install plan
 
-        // NOTE: Deduplicate tool aliases and specs since they may appear in several manifests
-        let tool_aliases = tools
-            .iter()
-            .map(|(alias, _)| alias.clone())
-            .collect::<BTreeSet<_>>();
-        let tool_specs = tools
-            .into_iter()
-            .map(|(_, spec)| spec)
-            .collect::<BTreeSet<_>>();
-
-        let tool_specs = if self.no_trust_check {
-            tool_specs
-        } else {
-            let (trusted_specs, untrusted_specs) = tool_specs
-                .into_iter()
-                .partition(|spec| tool_cache.is_trusted(spec.id()));
-            let newly_trusted_specs = prompt_for_trust_specs(untrusted_specs).await?;
-            for spec in &newly_trusted_specs {
-                let _ = tool_cache.add_trust(spec.id().clone());
-            }
-            trusted_specs
+        if !self.no_trust_check {
+            let tool_cache = home.tool_cache();
+            // NOTE: Deduplicate tool specs since they may appear in several manifests
+            let untrusted_specs = manifests
                 .iter()
-                .chain(newly_trusted_specs.iter())
+                .flat_map(|manifest| manifest.tools.values())
+                .filter(|spec| !tool_cache.is_trusted_spec(spec))
                 .cloned()
-                .collect::<BTreeSet<_>>()
-        };
+                .collect::<BTreeSet<_>>();
+            let per_version = tool_cache.trust_scope() == TrustScope::Major;
+            let newly_trusted_specs =
+                prompt_for_trust_specs(&source, untrusted_specs.into_iter().collect(), per_version)
+                    .await?;
+            for spec in &newly_trusted_specs {
+                let _ = tool_cache.add_trust_spec(spec);
+            }
+        }
 
-        // 3. Find artifacts, download and install them
+        // 2. Plan and install all tools, then link all of the (possibly new) aliases
 
+        let plan = plan(home, &manifests, plan_options).await;
         let pt =
-            CliProgressTracker::new_with_message_and_subtasks("Installing", tool_specs.len(), 5);
-        let installed_specs = tool_specs
-            .into_iter()
-            .map(|tool_spec| async {
-                if tool_cache.is_installed(&tool_spec) && !force {
-                    pt.task_completed();
-                    // HACK: Force the async closure to take ownership
-                    // of tool_spec by returning it from the closure
-                    return anyhow::Ok(tool_spec);
+            CliProgressTracker::new_with_message_and_subtasks("Installing", plan.tools().len(), 3);
+        let mut results = execute(home, &source, &plan, &options, |progress| match progress {
+            InstallProgress::Completed(action) => match action {
+                InstallAction::Resolve { .. }
+                | InstallAction::Download { .. }
+                | InstallAction::Extract { .. } => pt.subtask_completed(),
+                InstallAction::Skip { .. } => pt.task_completed(),
+                InstallAction::Link { .. } => pt.update_message("Linking"),
+            },
+            InstallProgress::ArtifactChosen { artifact, .. } => {
+                warn_if_large(artifact, large_download_threshold);
+            }
+            InstallProgress::BinaryExtracted {
+                artifact,
+                release_artifacts,
+                binary,
+            } => warn_if_emulated(artifact, release_artifacts, binary),
+        })
+        .await?;
+
+        if self.fail_fast {
+            if let Some(index) = results.iter().position(|(_, result)| result.is_err()) {
+                if let (spec, Err(e)) = results.swap_remove(index) {
+                    return Err(Error::from(e).context(format!("Failed to install {spec}")));
                 }
+            }
+        }
 
-                let release_artifact = source.get_specific_release(&tool_spec).await?;
-                pt.subtask_completed();
+        // 3. Record what was installed in the lockfile for the project, if any,
+        // unless installing from the lockfile - which must then stay unchanged
 
-                let artifact =
-                    find_most_compatible_artifact(&release_artifact.artifacts, tool_spec.id())?;
-                pt.subtask_completed();
+        if !self.locked {
+            update_lockfile(home).await?;
+        }
 
-                let contents = source
-                    .download_artifact_contents(&artifact)
-                    .await
-                    .with_context(|| format!("Failed to download contents for {tool_spec}"))?;
-                pt.subtask_completed();
+        // 4. Finally, display a nice message and summary to the user
+        let num_failed = results.iter().filter(|(_, r)| r.is_err()).count();
+        let num_succeeded = results
+            .iter()
+            .filter(|(_, r)| {
+                matches!(
+                    r,
+                    Ok(InstallOutcome::Installed { .. }
+                        | InstallOutcome::Skipped(SkipReason::AlreadyInstalled))
+                )
+            })
+            .count();
+        let s = if num_succeeded == 1 { "" } else { "s" };
+        if num_failed == 0 {
+            pt.finish_with_message(format!(
+                "Installed and created link{s} for {} tool{s} {}",
+                style(num_succeeded).bold().magenta(),
+                pt.formatted_elapsed(),
+            ));
+        } else {
+            pt.finish_with_symbol_and_message(
+                Symbol::Warning,
+                format!(
+                    "Installed {} tool{s}, and {} failed to install {}",
+                    style(num_succeeded).bold().magenta(),
+                    style(num_failed).bold().red(),
+                    pt.formatted_elapsed(),
+                ),
+            );
+        }
+        if !results.is_empty() {
+            println!("{}", format_summary(&results));
+        }
 
-                let extracted = artifact
-                    .extract_contents(contents)
-                    .await
-                    .with_context(|| format!("Failed to extract contents for {tool_spec}"))?;
-                pt.subtask_completed();
+        // NOTE: Failures have already been reported in the summary above,
+        // so only the exit code is set - using the kind of the first failure,
+        // or a general kind if tools failed to install for different reasons
+        let mut failed_kinds = results
+            .iter()
+            .filter_map(|(_, result)| result.as_ref().err().map(RokitError::kind));
+        if let Some(kind) = failed_kinds.next() {
+            let kind = if failed_kinds.all(|k| k == kind) {
+                kind
+            } else {
+                ErrorKind::Other
+            };
+            return Err(CliError::silent(kind).into());
+        }
 
-                tool_storage
-                    .replace_tool_contents(&tool_spec, extracted)
-                    .await?;
-                pt.subtask_completed();
+        Ok(())
+    }
+}
 
-                let _ = tool_cache.add_installed(tool_spec.clone());
-                Ok(tool_spec)
-            })
-            .collect::<FuturesUnordered<_>>()
-            .try_collect::<Vec<_>>()
-            .await?;
+/**
+    Discovers all manifests to install tools from, after offering to
+    migrate Aftman manifests, and resolving Foreman version requirements.
+*/
+async fn discover_manifests(
+    home: &Home,
+    source: &ArtifactSource,
+    dry_run: bool,
+) -> Result<Vec<DiscoveredManifest>> {
+    // Offer to migrate projects that only have an Aftman manifest, so that
+    // teams can switch to Rokit incrementally - the Aftman manifest is kept
+    let aftman_path = if dry_run {
+        None
+    } else {
+        discover_unmigrated_aftman_manifest().await
+    };
+    if let Some(aftman_path) = aftman_path {
+        if prompt_for_aftman_migration(aftman_path.clone()).await? {
+            let rokit_path = migrate_aftman_manifest(&aftman_path).await?;
+            println!(
+                "Created {} from the Aftman manifest.\n",
+                style(display_path(rokit_path)).bold().green()
+            );
+        }
+    }
+
+    // NOTE: Foreman manifests use version requirements, which must be resolved
+    // to the latest matching versions before discovering manifests - this is
+    // also stored so that running tools later does not need to resolve them
+    resolve_foreman_requirements(home, source)
+        .await
+        .context("Failed to resolve versions for tools in Foreman manifests")?;
+    let manifests = discover_all_manifests(false, false).await;
+    warn_alias_collisions(&find_alias_collisions(&manifests));
+
+    Ok(manifests)
+}
+
+/**
+    Loads the nearest lockfile, as a manifest that pins every tool
+    in it to the artifact that was recorded for the current system.
+*/
+async fn load_locked_manifest() -> Result<DiscoveredManifest> {
+    let Some(dir) = discover_lockfile_dir().await else {
+        return Err(CliError::new(
+            ErrorKind::NotFound,
+            format!(
+                "No lockfile was found for this project.\
+                \nRun `rokit install` without `--locked` to create {LOCKFILE_NAME}."
+            ),
+        )
+        .into());
+    };
 
-        // 4. Link all of the (possibly new) aliases, we do this even if the
-        // tool is already installed in case the link(s) have been corrupted
-        // and the user tries to re-install tools to fix it.
+    let lockfile = Lockfile::load(&dir)
+        .await
+        .with_context(|| format!("Failed to load {}", display_path(dir.join(LOCKFILE_NAME))))?;
+
+    // NOTE: Tools without an artifact for this system can still be installed,
+    // but without the guarantee of getting the exact same binary as elsewhere
+    let system = Descriptor::current_system();
+    for (alias, tool) in lockfile.tools() {
+        if tool.artifact_for(&system).is_none() {
+            warn!(
+                "No artifact for {} is recorded for '{alias}' in {LOCKFILE_NAME}.\
+                \nIt will be installed from {}, without a pinned asset or checksum.",
+                system.platform_name(),
+                tool.spec,
+            );
+        }
+    }
+
+    Ok(lockfile.to_manifest(dir.join(LOCKFILE_NAME)))
+}
+
+/**
+    Updates the lockfile next to the nearest project manifest, if there is one,
+    with the tools in project manifests and the artifacts installed for them.
+*/
+async fn update_lockfile(home: &Home) -> Result<()> {
+    let manifests = discover_all_manifests(false, true).await;
+    let Some(dir) = manifests
+        .first()
+        .and_then(|manifest| manifest.path.parent())
+        .map(Path::to_path_buf)
+    else {
+        return Ok(());
+    };
+
+    let previous = match Lockfile::load(&dir).await {
+        Err(RokitError::FileNotFound(_)) => None,
+        result => Some(result.with_context(|| {
+            format!("Failed to load {}", display_path(dir.join(LOCKFILE_NAME)))
+        })?),
+    };
+    let lockfile = lock(home, &manifests, previous.clone().unwrap_or_default()).await;
+    if previous.as_ref() != Some(&lockfile) {
+        lockfile
+            .save(&dir)
+            .await
+            .with_context(|| format!("Failed to save {}", display_path(dir.join(LOCKFILE_NAME))))?;
+    }
 
-        pt.update_message("Linking");
-        tool_aliases
+    Ok(())
+}
+
+/**
+    Warns the user about aliases declared for different tools in several
+    manifests, such as tools with the same name from different authors,
+    since only the tool in the nearest manifest can be run using the alias.
+*/
+fn warn_alias_collisions(collisions: &[AliasCollision]) {
+    for collision in collisions {
+        let (spec, path) = &collision.declarations[0];
+        let shadowed = collision
+            .declarations
             .iter()
-            .map(|alias| tool_storage.create_tool_link(alias))
-            .collect::<FuturesUnordered<_>>()
-            .try_collect::<Vec<_>>()
-            .await?;
-
-        // 5. Finally, display a nice message to the user
-        let s = if installed_specs.len() == 1 { "" } else { "s" };
-        pt.finish_with_message(format!(
-            "Installed and created link{s} for {} tool{s} {}",
-            style(installed_specs.len()).bold().magenta(),
-            pt.formatted_elapsed(),
-        ));
+            .skip(1)
+            .filter(|(other, _)| other.id() != spec.id())
+            .map(|(other, path)| format!("{} from {}", other.id(), display_path(path)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        warn!(
+            "Alias '{}' is declared for different tools - it runs {} from {}, and not {shadowed}.\
+            \nGive one of the tools a different alias to use both of them.",
+            collision.alias,
+            spec.id(),
+            display_path(path),
+        );
+    }
+}
 
-        Ok(())
+fn format_plan(plan: &InstallPlan) -> String {
+    if plan.actions.is_empty() {
+        return String::from("No tools would be installed or linked.");
     }
+
+    let bullet = style(Symbol::Bullet).dim();
+    let arrow = style(Symbol::Arrow).dim();
+    let spec_str = |spec: &ToolSpec| {
+        format!(
+            "{} {}",
+            style(spec.id()).bold().cyan(),
+            style(spec.version()).dim()
+        )
+    };
+
+    let lines = plan
+        .actions
+        .iter()
+        .map(|action| {
+            let (name, details) = match action {
+                InstallAction::Resolve {
+                    spec,
+                    exclude_assets,
+                    asset,
+                    requires,
+                } => {
+                    let excluded = if let Some(asset) = asset {
+                        format!(" (using asset `{asset}`)")
+                    } else if exclude_assets.is_empty() {
+                        String::new()
+                    } else {
+                        format!(
+                            " (excluding assets matching `{}`)",
+                            exclude_assets.join("`, `")
+                        )
+                    };
+                    let required = if requires.is_empty() {
+                        String::new()
+                    } else {
+                        let aliases = requires.keys().map(ToolAlias::name).collect::<Vec<_>>();
+                        format!(" (after `{}`)", aliases.join("`, `"))
+                    };
+                    (
+                        "resolve",
+                        format!(
+                            "{}{}{}",
+                            spec_str(spec),
+                            style(excluded).dim(),
+                            style(required).dim()
+                        ),
+                    )
+                }
+                InstallAction::Download { spec, sha256 } => {
+                    let verified = if sha256.is_some() {
+                        " (verified against manifest checksum)"
+                    } else {
+                        ""
+                    };
+                    (
+                        "download",
+                        format!("{}{}", spec_str(spec), style(verified).dim()),
+                    )
+                }
+                InstallAction::Extract {
+                    spec,
+                    extract_all,
+                    file_pattern,
+                } => {
+                    let files = match (extract_all, file_pattern) {
+                        (true, Some(pattern)) => format!(" (all files matching `{pattern}`)"),
+                        (true, None) => String::from(" (all files)"),
+                        (false, Some(pattern)) => format!(" (binary matching `{pattern}`)"),
+                        (false, None) => String::new(),
+                    };
+                    (
+                        "extract",
+                        format!("{}{}", spec_str(spec), style(files).dim()),
+                    )
+                }
+                InstallAction::Link { alias, reason } => (
+                    "link",
+                    format!(
+                        "{} {arrow} {}",
+                        style(alias.name()).bold().cyan(),
+                        style(reason).dim()
+                    ),
+                ),
+                InstallAction::Skip { spec, reason } => (
+                    "skip",
+                    format!("{} {arrow} {}", spec_str(spec), style(reason).dim()),
+                ),
+            };
+            format!("{bullet} {:<8} {details}", style(name).bold())
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!("Install plan, in order:\n\n{lines}")
+}
+
+fn format_summary(results: &[(ToolSpec, RokitResult<InstallOutcome>)]) -> String {
+    let bullet = style(Symbol::Bullet).dim();
+    let arrow = style(Symbol::Arrow).dim();
+
+    let longest_id_len = results
+        .iter()
+        .map(|(spec, _)| spec.id().to_string().len())
+        .max()
+        .unwrap_or(0);
+    results
+        .iter()
+        .map(|(spec, result)| {
+            let id = spec.id().to_string();
+            let status = match result {
+                Ok(InstallOutcome::Installed {
+                    downloaded,
+                    elapsed,
+                }) => style(format!(
+                    "installed ({} at {})",
+                    format_bytes(*downloaded),
+                    format_speed(*downloaded, *elapsed)
+                ))
+                .green(),
+                Ok(InstallOutcome::Skipped(reason)) => style(reason.to_string()).dim(),
+                Err(e) => style(format!("failed: {e}")).red(),
+            };
+            format!(
+                "{bullet} {}{} {} {arrow} {status}",
+                style(&id).bold().cyan(),
+                " ".repeat(longest_id_len - id.len()),
+                style(spec.version()).dim(),
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
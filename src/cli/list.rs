@@ -1,22 +1,43 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
 use anyhow::Result;
 use clap::Parser;
 use console::style;
 
-use rokit::{discovery::discover_all_manifests, storage::Home, system::current_dir, tool::ToolId};
+use rokit::{
+    discovery::{discover_all_manifests, DiscoveredManifest},
+    storage::Home,
+    system::current_dir,
+    tool::{ToolAlias, ToolId, ToolSpec},
+};
+
+use crate::util::Symbol;
 
 /// Lists all existing tools managed by Rokit.
 #[derive(Debug, Parser)]
 pub struct ListSubcommand {
     /// A specific tool identifier to list installed versions for.
     pub id: Option<ToolId>,
+    /// List only the tools in the global manifest.
+    #[clap(long, conflicts_with = "id")]
+    pub global: bool,
+    /// List every discovered manifest, including Aftman and Foreman
+    /// manifests, with its tools nested under it.
+    #[clap(long, conflicts_with = "id")]
+    pub tree: bool,
 }
 
 impl ListSubcommand {
     pub async fn run(self, home: &Home) -> Result<()> {
         let (header, lines) = if let Some(id) = self.id {
             list_versions_for_id(home, &id)
+        } else if self.tree {
+            list_manifest_tree(home, self.global).await
         } else {
-            list_versions(home).await
+            list_versions(home, self.global).await
         };
 
         println!("{header}\n{}", lines.join("\n"));
@@ -33,11 +54,11 @@ fn list_versions_for_id(home: &Home, id: &ToolId) -> (String, Vec<String>) {
     versions.reverse(); // List newest versions first
 
     if versions.is_empty() {
-        let header = format!("🛠️  No versions of {id} are installed.");
+        let header = format!("{} No versions of {id} are installed.", Symbol::Tools);
         (header, Vec::new())
     } else {
-        let header = format!("🛠️  Installed versions of {id}:");
-        let bullet = style("•").dim();
+        let header = format!("{} Installed versions of {id}:", Symbol::Tools);
+        let bullet = style(Symbol::Bullet).dim();
         let lines = versions
             .into_iter()
             .map(|version| format!("  {bullet} {version}"))
@@ -47,12 +68,12 @@ fn list_versions_for_id(home: &Home, id: &ToolId) -> (String, Vec<String>) {
 }
 
 // Lists versions for the current manifest, and the global manifest
-async fn list_versions(home: &Home) -> (String, Vec<String>) {
+async fn list_versions(home: &Home, global_only: bool) -> (String, Vec<String>) {
     let cwd = current_dir().await;
-    let manifests = discover_all_manifests(true, false).await;
+    let manifests = discover_manifests(home, true, global_only).await;
 
-    let bullet = style("•").dim();
-    let arrow = style("→").dim();
+    let bullet = style(Symbol::Bullet).dim();
+    let arrow = style(Symbol::Arrow).dim();
     let at = style("@").dim();
 
     let mut manifest_lines = Vec::new();
@@ -96,13 +117,7 @@ async fn list_versions(home: &Home) -> (String, Vec<String>) {
 
     let mut lines = vec![];
     for (index, (path, mlines)) in manifest_lines.iter().enumerate() {
-        if let Ok(stripped) = path.strip_prefix(home.path()) {
-            lines.push(format!("~/.rokit/{}", stripped.display()));
-        } else if let Ok(stripped) = path.strip_prefix(&cwd) {
-            lines.push(format!("./{}", stripped.display()));
-        } else {
-            lines.push(path.display().to_string());
-        }
+        lines.push(display_path(home, &cwd, path));
         lines.extend_from_slice(mlines);
         if index < manifest_lines.len() - 1 {
             lines.push(String::new()); // Add a newline between manifests
@@ -110,10 +125,125 @@ async fn list_versions(home: &Home) -> (String, Vec<String>) {
     }
 
     if lines.is_empty() {
-        let header = String::from("🛠️  No tools found.");
+        let header = format!("{} No tools found.", Symbol::Tools);
         (header, Vec::new())
     } else {
-        let header = String::from("🛠️  Found tools:\n");
+        let header = format!("{} Found tools:\n", Symbol::Tools);
         (header, lines)
     }
 }
+
+// Lists all discovered manifests of any kind, with their tools nested under
+// them, and marks tools that are shadowed by a tool in a nearer manifest
+async fn list_manifest_tree(home: &Home, global_only: bool) -> (String, Vec<String>) {
+    let cwd = current_dir().await;
+    let manifests = discover_manifests(home, false, global_only).await;
+
+    let arrow = style(Symbol::Arrow).dim();
+    let at = style("@").dim();
+
+    // NOTE: Manifests are discovered nearest first, so the
+    // first manifest that declares an alias is the one used
+    let mut used_by: HashMap<ToolAlias, &PathBuf> = HashMap::new();
+    for manifest in &manifests {
+        for alias in manifest.tools.keys() {
+            used_by.entry(alias.clone()).or_insert(&manifest.path);
+        }
+    }
+
+    let mut lines = vec![];
+    for (index, manifest) in manifests.iter().enumerate() {
+        lines.push(format!(
+            "{} {}",
+            display_path(home, &cwd, &manifest.path),
+            style(format!("({})", manifest.kind)).dim(),
+        ));
+
+        let mut sorted_tools = manifest
+            .tools
+            .iter()
+            .collect::<Vec<(&ToolAlias, &ToolSpec)>>();
+        sorted_tools.sort_by(|(alias_a, _), (alias_b, _)| alias_a.name().cmp(alias_b.name()));
+        if sorted_tools.is_empty() {
+            lines.push(format!(
+                "{} {}",
+                style(Symbol::TreeEnd).dim(),
+                style("No tools").dim()
+            ));
+        }
+
+        let longest_alias_len = sorted_tools
+            .iter()
+            .map(|(alias, _)| alias.name().len())
+            .max()
+            .unwrap_or(0);
+        let longest_id_len = sorted_tools
+            .iter()
+            .map(|(_, spec)| spec.id().to_string().len())
+            .max()
+            .unwrap_or(0);
+
+        for (tool_index, (alias, spec)) in sorted_tools.iter().enumerate() {
+            let branch = if tool_index == sorted_tools.len() - 1 {
+                style(Symbol::TreeEnd).dim()
+            } else {
+                style(Symbol::TreeBranch).dim()
+            };
+            let shadowed = match used_by.get(*alias) {
+                Some(path) if **path != manifest.path => format!(
+                    " {}",
+                    style(format!("(shadowed by {})", display_path(home, &cwd, path))).yellow()
+                ),
+                _ => String::new(),
+            };
+            lines.push(format!(
+                "{branch} {}{} {arrow} {} {}{at} {}{shadowed}",
+                style(alias.name()).bold().cyan(),
+                " ".repeat(longest_alias_len - alias.name().len()),
+                spec.id(),
+                " ".repeat(longest_id_len - spec.id().to_string().len()),
+                spec.version(),
+            ));
+        }
+
+        if index < manifests.len() - 1 {
+            lines.push(String::new()); // Add a newline between manifests
+        }
+    }
+
+    if lines.is_empty() {
+        let header = format!("{} No manifests found.", Symbol::Tools);
+        (header, Vec::new())
+    } else {
+        let header = format!("{} Found manifests:\n", Symbol::Tools);
+        (header, lines)
+    }
+}
+
+// Discovers manifests, optionally only keeping the global Rokit manifest
+async fn discover_manifests(
+    home: &Home,
+    rokit_only: bool,
+    global_only: bool,
+) -> Vec<DiscoveredManifest> {
+    let manifests = discover_all_manifests(rokit_only || global_only, false).await;
+    if global_only {
+        manifests
+            .into_iter()
+            .filter(|manifest| manifest.path.starts_with(home.path()))
+            .collect()
+    } else {
+        manifests
+    }
+}
+
+// Formats a manifest path relative to the Rokit home directory or the current directory
+fn display_path(home: &Home, cwd: &Path, path: &Path) -> String {
+    if let Ok(stripped) = path.strip_prefix(home.path()) {
+        format!("~/.rokit/{}", stripped.display())
+    } else if let Ok(stripped) = path.strip_prefix(cwd) {
+        format!("./{}", stripped.display())
+    } else {
+        path.display().to_string()
+    }
+}
@@ -0,0 +1,68 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use console::style;
+use tokio::fs::read_to_string;
+
+use rokit::{
+    discovery::discover_unmigrated_aftman_manifest,
+    manifests::{RokitManifest, ROKIT_MANIFEST_FILE_NAME},
+    storage::Home,
+};
+
+use crate::util::{display_path, CliProgressTracker};
+
+/// Creates a Rokit manifest from the Aftman manifest of the current project.
+///
+/// The Aftman manifest is left untouched, so that teams can switch to Rokit incrementally.
+#[derive(Debug, Parser)]
+pub struct MigrateSubcommand {}
+
+impl MigrateSubcommand {
+    pub async fn run(self, _: &Home) -> Result<()> {
+        let Some(aftman_path) = discover_unmigrated_aftman_manifest().await else {
+            bail!(
+                "No Aftman manifest to migrate was found.\
+                \nThe current project either already has a Rokit manifest, \
+                or does not use Aftman."
+            )
+        };
+
+        let pt = CliProgressTracker::new_with_message("Migrating", 1);
+        let rokit_path = migrate_aftman_manifest(&aftman_path).await?;
+
+        pt.finish_with_message(format!(
+            "Created {} from {} {}\n\
+            \nThe Aftman manifest has not been changed, and can be removed once \
+            everyone working on the project has switched to Rokit.",
+            style(display_path(&rokit_path)).bold().green(),
+            style(display_path(&aftman_path)).bold(),
+            pt.formatted_elapsed(),
+        ));
+
+        Ok(())
+    }
+}
+
+/**
+    Creates a Rokit manifest next to the given Aftman manifest,
+    using its tools, and returns the path to the new Rokit manifest.
+*/
+pub(super) async fn migrate_aftman_manifest(aftman_path: &Path) -> Result<PathBuf> {
+    let contents = read_to_string(aftman_path)
+        .await
+        .context("Failed to read Aftman manifest")?;
+    let manifest = RokitManifest::from_aftman_manifest(&contents)
+        .context("Failed to convert Aftman manifest")?;
+
+    let dir = aftman_path
+        .parent()
+        .context("Aftman manifest has no parent directory")?;
+    manifest
+        .save(dir)
+        .await
+        .context("Failed to save new Rokit manifest")?;
+
+    Ok(dir.join(ROKIT_MANIFEST_FILE_NAME))
+}
@@ -1,34 +1,84 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
 use anyhow::{Context, Result};
-use clap::{ArgAction, CommandFactory, Parser};
-use tokio::time::Instant;
+use clap::{ArgAction, CommandFactory, Parser, ValueEnum};
+use tokio::time::{timeout, Instant};
 use tracing::level_filters::LevelFilter;
 
+use rokit::descriptor::Descriptor;
+use rokit::discovery::{discover_command_aliases, set_manifest_dir};
+use rokit::result::ErrorKind;
 use rokit::storage::Home;
-use rokit::system::ProcessParent;
+use rokit::system::{current_dir, ProcessParent};
 
-use crate::util::init_tracing;
+use crate::util::{
+    check_for_updates, init_output, init_tracing, should_check_for_updates, CliError, LogFormat,
+    ProgressMode,
+};
 
 mod add;
+mod alias;
+mod audit;
 mod authenticate;
+mod changelog;
+mod ci;
+mod clean_links;
+mod doctor;
+mod fmt;
+mod gc;
+mod hook;
 mod init;
 mod install;
 mod list;
+mod migrate;
+mod outdated;
+mod prune;
+mod run;
+mod sbom;
+mod search;
 mod self_install;
 mod self_update;
 mod system_info;
 mod trust;
 mod update;
+mod upgrade;
+mod why;
 
 use self::add::AddSubcommand;
+use self::alias::AliasSubcommand;
+use self::audit::AuditSubcommand;
 use self::authenticate::AuthenticateSubcommand;
+use self::changelog::ChangelogSubcommand;
+use self::ci::CiSubcommand;
+use self::clean_links::CleanLinksSubcommand;
+use self::doctor::DoctorSubcommand;
+use self::fmt::FmtSubcommand;
+use self::gc::GcSubcommand;
+use self::hook::HookSubcommand;
 use self::init::InitSubcommand;
 use self::install::InstallSubcommand;
 use self::list::ListSubcommand;
+use self::migrate::MigrateSubcommand;
+use self::outdated::OutdatedSubcommand;
+use self::prune::PruneSubcommand;
+use self::run::RunSubcommand;
+use self::sbom::SbomSubcommand;
+use self::search::SearchSubcommand;
 use self::self_install::SelfInstallSubcommand;
 use self::self_update::SelfUpdateSubcommand;
 use self::system_info::SystemInfoSubcommand;
 use self::trust::TrustSubcommand;
 use self::update::UpdateSubcommand;
+use self::upgrade::UpgradeSubcommand;
+use self::why::WhySubcommand;
+
+/**
+    The longest time to wait for the periodic update check
+    to complete, after the subcommand has finished running.
+*/
+const UPDATE_CHECK_TIMEOUT: Duration = Duration::from_secs(1);
 
 #[derive(Debug, Parser)]
 #[clap(author, version, about)]
@@ -40,9 +90,56 @@ pub struct Cli {
 }
 
 impl Cli {
+    /**
+        Parses the command line arguments, expanding any user-defined
+        command alias from the `command-aliases` setting of discovered
+        manifests into the arguments that it stands for.
+
+        Built-in subcommands and their aliases always take precedence, and
+        aliases are only expanded once, so they can not refer to each other.
+    */
+    pub async fn parse_with_aliases() -> Self {
+        let mut args = std::env::args_os().collect::<Vec<_>>();
+
+        // NOTE: Aliases are discovered from manifests, so the manifest directory
+        // must be set first, or aliases would be taken from the current directory -
+        // any invalid path is ignored here, and reported once the arguments are parsed
+        if let Some(path) = find_manifest_path(&args) {
+            if let Ok(dir) = resolve_manifest_dir(&path).await {
+                set_manifest_dir(dir);
+            }
+        }
+
+        if let Some(index) = find_subcommand_index(&args) {
+            let name = args[index].to_string_lossy().to_string();
+            if !is_builtin_subcommand(&name) {
+                if let Some(expansion) = discover_command_aliases().await.remove(&name) {
+                    args.splice(index..=index, expansion.into_iter().map(Into::into));
+                }
+            }
+        }
+
+        Self::parse_from(args)
+    }
+
     pub async fn run(self) -> Result<()> {
-        // Enable the appropriate level of tracing / logging
-        init_tracing(self.options.tracing_level_filter());
+        // Enable the appropriate level of tracing / logging, and
+        // the desired output style, which tracing depends on
+        init_output(
+            self.options.quiet,
+            self.options.no_color,
+            self.options.ascii,
+            self.options.progress,
+        );
+        init_tracing(
+            self.options.tracing_level_filter(),
+            self.options.trace_http,
+            self.options.log_format,
+        );
+
+        // Make sure that Rokit supports the current system, before
+        // anything tries to find tools or artifacts compatible with it
+        Descriptor::check_current_system().context("Rokit does not support this system")?;
 
         // If we didn't get a subcommand, we should either print the help,
         // or automatically run self-install if launched from the explorer
@@ -52,16 +149,31 @@ impl Cli {
             .await
             .is_some_and(ProcessParent::is_launcher)
         {
-            let subcommand = Subcommand::SelfInstall(SelfInstallSubcommand {});
+            let subcommand = Subcommand::SelfInstall(SelfInstallSubcommand {
+                bin_dir: None,
+                repair: false,
+                system: false,
+            });
             (true, subcommand)
         } else {
             Cli::command().print_help()?;
             std::process::exit(0);
         };
 
+        // Use the desired manifest directory for all discovery, if any - this
+        // must be set before loading the home, which may be isolated to it
+        if let Some(path) = &self.options.manifest_path {
+            set_manifest_dir(resolve_manifest_dir(path).await?);
+        }
+
         // Load Rokit data structures
         let start_home = Instant::now();
-        let home = Home::load_from_env().await.context(
+        let home = if self.options.isolated {
+            Home::load_isolated().await
+        } else {
+            Home::load_from_env().await
+        };
+        let home = home.context(
             "Failed to load Rokit home!\
             \nYour installation or environment may be corrupted.",
         )?;
@@ -70,6 +182,32 @@ impl Cli {
             "Rokit loaded"
         );
 
+        let is_self_command = matches!(
+            command,
+            Subcommand::SelfInstall(_) | Subcommand::SelfUpdate(_)
+        );
+
+        // Retry updating any links that were in use the last time they
+        // were recreated - updating or installing Rokit recreates them all
+        if !is_self_command {
+            if let Err(e) = home.tool_storage().retry_pending_links().await {
+                tracing::debug!("Failed to retry pending links: {e}");
+            }
+        }
+
+        // Check for updates in the background while the subcommand runs, if enabled,
+        // but never while updating or installing Rokit itself, or for machine output
+        let update_check = if is_self_command
+            || self.options.quiet
+            || self.options.output != OutputFormat::Human
+            || !should_check_for_updates(&home).await
+        {
+            None
+        } else {
+            let home = home.clone();
+            Some(tokio::spawn(async move { check_for_updates(&home).await }))
+        };
+
         // Run the subcommand and capture the result - note that we
         // do not (!!!) use the question mark operator here, because
         // we want to save our data below even if the subcommand fails.
@@ -92,6 +230,14 @@ impl Cli {
             "Rokit saved"
         );
 
+        // Let the user know about any updates, waiting only a short moment
+        // for the check to complete, so that commands never feel slower
+        if let Some(task) = update_check {
+            if let Ok(Ok(Some(notice))) = timeout(UPDATE_CHECK_TIMEOUT, task).await {
+                eprintln!("\n{notice}");
+            }
+        }
+
         // Wait for user input if we automatically ran the
         // self-install from clicking Rokit in the explorer,
         // so that the window doesn't immediately close.
@@ -111,49 +257,218 @@ impl Cli {
     }
 }
 
+/*
+    Finds the index of the first positional argument, which is the
+    subcommand, skipping over global options and any values they take.
+*/
+fn find_subcommand_index(args: &[OsString]) -> Option<usize> {
+    let command = Cli::command();
+    let takes_value = |arg: &str| {
+        let long = arg.strip_prefix("--");
+        let short = arg.strip_prefix('-').and_then(|s| {
+            let mut chars = s.chars();
+            chars.next().filter(|_| chars.next().is_none())
+        });
+        command.get_arguments().any(|a| {
+            let matches = match long {
+                Some(long) => a.get_long() == Some(long),
+                None => short.is_some() && a.get_short() == short,
+            };
+            matches && a.get_action().takes_values()
+        })
+    };
+
+    let mut index = 1;
+    while let Some(arg) = args.get(index) {
+        let arg = arg.to_string_lossy();
+        if arg == "--" {
+            return None;
+        } else if !arg.starts_with('-') || arg == "-" {
+            return Some(index);
+        } else if !arg.contains('=') && takes_value(&arg) {
+            index += 1;
+        }
+        index += 1;
+    }
+    None
+}
+
+/*
+    Finds the value of the `--manifest-path` option before the arguments are
+    parsed - it is a global option, so it may come before or after the subcommand.
+*/
+fn find_manifest_path(args: &[OsString]) -> Option<PathBuf> {
+    let mut path = None;
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        let arg_str = arg.to_string_lossy();
+        if arg_str == "--" {
+            break;
+        } else if arg_str == "--manifest-path" {
+            path = args.next().map(PathBuf::from);
+        } else if let Some(value) = arg_str.strip_prefix("--manifest-path=") {
+            path = Some(PathBuf::from(value));
+        }
+    }
+    path
+}
+
+fn is_builtin_subcommand(name: &str) -> bool {
+    name == "help"
+        || Cli::command()
+            .get_subcommands()
+            .any(|cmd| cmd.get_name() == name || cmd.get_all_aliases().any(|alias| alias == name))
+}
+
+/*
+    Resolves the directory to discover manifests in from the
+    `--manifest-path` option, which may be a directory or a manifest file.
+*/
+async fn resolve_manifest_dir(path: &Path) -> Result<PathBuf> {
+    let path = current_dir().await.join(path);
+    if path.is_dir() {
+        Ok(path)
+    } else if let (true, Some(parent)) = (path.is_file(), path.parent()) {
+        Ok(parent.to_path_buf())
+    } else {
+        Err(CliError::new(
+            ErrorKind::Usage,
+            format!(
+                "Manifest path does not exist: {}\
+                \nIt must be a directory, or a manifest file such as rokit.toml.",
+                path.display()
+            ),
+        )
+        .into())
+    }
+}
+
 #[derive(Debug, Parser)]
 pub enum Subcommand {
     Add(AddSubcommand),
+    Alias(AliasSubcommand),
+    Audit(AuditSubcommand),
     Authenticate(AuthenticateSubcommand),
+    Changelog(ChangelogSubcommand),
+    Ci(CiSubcommand),
+    CleanLinks(CleanLinksSubcommand),
+    Doctor(DoctorSubcommand),
+    Fmt(FmtSubcommand),
+    Gc(GcSubcommand),
+    Hook(HookSubcommand),
     Init(InitSubcommand),
+    #[clap(visible_alias = "i")]
     Install(InstallSubcommand),
+    #[clap(visible_alias = "ls")]
     List(ListSubcommand),
+    Migrate(MigrateSubcommand),
+    Outdated(OutdatedSubcommand),
+    Prune(PruneSubcommand),
+    Run(RunSubcommand),
+    Sbom(SbomSubcommand),
+    Search(SearchSubcommand),
     SelfInstall(SelfInstallSubcommand),
     SelfUpdate(SelfUpdateSubcommand),
     SystemInfo(SystemInfoSubcommand),
     Trust(TrustSubcommand),
+    #[clap(visible_alias = "up")]
     Update(UpdateSubcommand),
+    Upgrade(UpgradeSubcommand),
+    Why(WhySubcommand),
 }
 
 impl Subcommand {
     pub async fn run(self, home: &Home) -> Result<()> {
         match self {
             Self::Add(cmd) => cmd.run(home).await,
+            Self::Alias(cmd) => cmd.run(home).await,
+            Self::Audit(cmd) => cmd.run(home).await,
             Self::Authenticate(cmd) => cmd.run(home).await,
+            Self::Changelog(cmd) => cmd.run(home).await,
+            Self::Ci(cmd) => cmd.run(home).await,
+            Self::CleanLinks(cmd) => cmd.run(home).await,
+            Self::Doctor(cmd) => cmd.run(home).await,
+            Self::Fmt(cmd) => cmd.run(home).await,
+            Self::Gc(cmd) => cmd.run(home).await,
+            Self::Hook(cmd) => cmd.run(home).await,
             Self::Init(cmd) => cmd.run(home).await,
             Self::Install(cmd) => cmd.run(home).await,
             Self::List(cmd) => cmd.run(home).await,
+            Self::Migrate(cmd) => cmd.run(home).await,
+            Self::Outdated(cmd) => cmd.run(home).await,
+            Self::Prune(cmd) => cmd.run(home).await,
+            Self::Run(cmd) => cmd.run(home).await,
+            Self::Sbom(cmd) => cmd.run(home).await,
+            Self::Search(cmd) => cmd.run(home).await,
             Self::SelfInstall(cmd) => cmd.run(home).await,
             Self::SelfUpdate(cmd) => cmd.run(home).await,
             Self::SystemInfo(cmd) => cmd.run(home).await,
             Self::Trust(cmd) => cmd.run(home).await,
             Self::Update(cmd) => cmd.run(home).await,
+            Self::Upgrade(cmd) => cmd.run(home).await,
+            Self::Why(cmd) => cmd.run(home).await,
         }
     }
 }
 
 #[derive(Debug, Parser)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct GlobalOptions {
     #[clap(short, long, action = ArgAction::Count)]
     pub verbose: u8,
+    /// The format to use for machine-readable output, such as errors.
+    #[clap(long, global = true, value_enum, default_value_t)]
+    pub output: OutputFormat,
+    /// Log details about every HTTP request, such as urls,
+    /// status codes, timings, and headers, with credentials redacted.
+    #[clap(long, global = true)]
+    pub trace_http: bool,
+    /// The format to use for logs, such as JSON lines
+    /// for ingesting Rokit logs into CI log processors.
+    #[clap(long, global = true, value_enum, default_value_t)]
+    pub log_format: LogFormat,
+    /// Keep tool storage and all other data in a `.rokit` directory next to
+    /// the project manifest, instead of sharing it with other projects.
+    /// Tools run through links in the bin directory of an isolated home use it automatically.
+    #[clap(long, global = true)]
+    pub isolated: bool,
+    /// Use the manifest in the given directory, or the given manifest file,
+    /// instead of discovering manifests in the current directory and up.
+    #[clap(long, global = true, value_name = "PATH")]
+    pub manifest_path: Option<PathBuf>,
+    /// Hide progress bars and status messages, and only log warnings and errors.
+    #[clap(short, long, global = true)]
+    pub quiet: bool,
+    /// Disable colors and other styling in all output.
+    /// Also disabled if the `NO_COLOR` environment variable is set.
+    #[clap(long, global = true)]
+    pub no_color: bool,
+    /// Use only ASCII characters in output, instead of unicode symbols.
+    /// Also used if the `ROKIT_ASCII` environment variable is set,
+    /// or if the terminal is not known to support unicode.
+    #[clap(long, global = true)]
+    pub ascii: bool,
+    /// How to display progress - progress bars are only
+    /// shown in terminals by default, with plain status lines
+    /// used instead when the output is not a terminal.
+    #[clap(long, global = true, value_enum, default_value_t)]
+    pub progress: ProgressMode,
 }
 
 impl GlobalOptions {
     pub fn tracing_level_filter(&self) -> LevelFilter {
         match self.verbose {
+            0 if self.quiet => LevelFilter::WARN,
             0 => LevelFilter::INFO,
             1 => LevelFilter::DEBUG,
             _ => LevelFilter::TRACE,
         }
     }
 }
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Human,
+    Json,
+}
@@ -0,0 +1,267 @@
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use console::{style, StyledObject};
+use futures::{stream::FuturesUnordered, TryStreamExt};
+use semver::Version;
+use serde_json::json;
+
+use rokit::{
+    discovery::{discover_all_manifests, discover_strict_platform, DiscoveredManifest},
+    result::ErrorKind,
+    sources::ArtifactSource,
+    storage::Home,
+    tool::{ToolAlias, ToolId},
+};
+
+use crate::util::{
+    display_path, find_most_compatible_artifact, CliError, CliProgressTracker, Symbol,
+};
+
+/// Lists tools with newer versions available, in all discovered manifests.
+///
+/// Exits with a nonzero exit code if any tool is outdated.
+#[derive(Debug, Parser)]
+pub struct OutdatedSubcommand {
+    /// Print the report as JSON, instead of in a human-readable format.
+    #[clap(long)]
+    pub json: bool,
+}
+
+impl OutdatedSubcommand {
+    pub async fn run(self, home: &Home) -> Result<()> {
+        // 1. Find all manifests, and the unique tools in them, along
+        // with the release channels that they should stay within
+        let source = home.artifact_source().await?;
+        let strict_platform = discover_strict_platform().await;
+        let manifests = discover_all_manifests(false, false).await;
+        let num_tools = unique_tool_ids(&manifests).len();
+        let pt = if self.json {
+            None
+        } else {
+            Some(CliProgressTracker::new_with_message("Fetching", num_tools))
+        };
+
+        // 2. Fetch the latest versions for all tools, concurrently
+        let latest_versions =
+            fetch_latest_versions(&source, &manifests, strict_platform, pt.as_ref()).await?;
+
+        // 3. Figure out which tools are outdated in each manifest
+        let report = manifests
+            .iter()
+            .map(|manifest| {
+                let mut tools = manifest
+                    .tools
+                    .iter()
+                    .filter_map(|(alias, spec)| {
+                        let key = (spec.id().clone(), tool_channel(manifest, alias));
+                        let latest = &latest_versions[&key];
+                        if latest > spec.version() {
+                            Some(OutdatedTool {
+                                alias: alias.clone(),
+                                id: spec.id().clone(),
+                                current: spec.version().clone(),
+                                latest: latest.clone(),
+                            })
+                        } else {
+                            None
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                tools.sort_by(|a, b| a.alias.name().cmp(b.alias.name()));
+                (display_path(&manifest.path), tools)
+            })
+            .filter(|(_, tools)| !tools.is_empty())
+            .collect::<Vec<_>>();
+        let num_outdated = report.iter().map(|(_, tools)| tools.len()).sum::<usize>();
+
+        // 4. Print the report in the desired format
+        if self.json {
+            println!("{}", format_json(&report));
+        } else if let Some(pt) = pt {
+            if num_outdated == 0 {
+                pt.finish_with_message(format!(
+                    "All tools are up-to-date! {}",
+                    pt.formatted_elapsed()
+                ));
+            } else {
+                pt.finish_with_message(format!(
+                    "Found {} outdated tool{} {}\n\n{}\n\nRun `{}` to update the tools.",
+                    style(num_outdated).bold().magenta(),
+                    if num_outdated == 1 { "" } else { "s" },
+                    pt.formatted_elapsed(),
+                    format_human(&report),
+                    style("rokit update --all").bold().green(),
+                ));
+            }
+        }
+
+        // 5. Exit with a nonzero exit code if anything is outdated,
+        // without printing an error, since the report says it all
+        if num_outdated > 0 {
+            return Err(CliError::silent(ErrorKind::Other).into());
+        }
+
+        Ok(())
+    }
+}
+
+pub(super) fn tool_channel(manifest: &DiscoveredManifest, alias: &ToolAlias) -> Option<String> {
+    manifest.options.get(alias)?.channel.clone()
+}
+
+/**
+    A unique tool in any manifest, along with the
+    release channel it should stay within, if any.
+*/
+pub(super) type ChannelToolId = (ToolId, Option<String>);
+
+fn unique_tool_ids(manifests: &[DiscoveredManifest]) -> BTreeSet<ChannelToolId> {
+    manifests
+        .iter()
+        .flat_map(|manifest| {
+            manifest
+                .tools
+                .iter()
+                .map(|(alias, spec)| (spec.id().clone(), tool_channel(manifest, alias)))
+        })
+        .collect()
+}
+
+/**
+    Fetches the latest versions of all unique tools in the given manifests,
+    concurrently, within the release channel set for each tool, if any.
+*/
+pub(super) async fn fetch_latest_versions(
+    source: &ArtifactSource,
+    manifests: &[DiscoveredManifest],
+    strict_platform: bool,
+    pt: Option<&CliProgressTracker>,
+) -> Result<HashMap<ChannelToolId, Version>> {
+    unique_tool_ids(manifests)
+        .into_iter()
+        .map(|(id, channel)| async {
+            let release = match &channel {
+                Some(channel) => source.get_latest_release_in_channel(&id, channel).await,
+                None => source.get_latest_release(&id).await,
+            };
+            let release = release.with_context(|| {
+                format!(
+                    "Failed to fetch latest release for '{id}'!\
+                    \nMake sure the given tool identifier exists."
+                )
+            })?;
+            let artifact = find_most_compatible_artifact(&release.artifacts, &id, strict_platform)?;
+            if let Some(pt) = pt {
+                pt.task_completed();
+            }
+            let version = artifact.tool_spec.version().clone();
+            Ok::<_, anyhow::Error>(((id, channel), version))
+        })
+        .collect::<FuturesUnordered<_>>()
+        .try_collect()
+        .await
+}
+
+#[derive(Debug, Clone)]
+struct OutdatedTool {
+    alias: ToolAlias,
+    id: ToolId,
+    current: Version,
+    latest: Version,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BumpKind {
+    Major,
+    Minor,
+    Patch,
+}
+
+impl BumpKind {
+    /**
+        Determines the kind of semver bump between two versions,
+        using the most significant version component that changed.
+    */
+    fn between(current: &Version, latest: &Version) -> Self {
+        if current.major != latest.major {
+            Self::Major
+        } else if current.minor != latest.minor {
+            Self::Minor
+        } else {
+            Self::Patch
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Major => "major",
+            Self::Minor => "minor",
+            Self::Patch => "patch",
+        }
+    }
+
+    fn styled<D>(self, value: D) -> StyledObject<D> {
+        match self {
+            Self::Major => style(value).red(),
+            Self::Minor => style(value).yellow(),
+            Self::Patch => style(value).green(),
+        }
+    }
+}
+
+fn format_human(report: &[(String, Vec<OutdatedTool>)]) -> String {
+    let bullet = style(Symbol::Bullet).dim();
+    let arrow = style(Symbol::Arrow).dim();
+
+    report
+        .iter()
+        .map(|(path, tools)| {
+            let longest_alias_len = tools
+                .iter()
+                .map(|tool| tool.alias.name().len())
+                .max()
+                .unwrap_or(0);
+            let lines = tools
+                .iter()
+                .map(|tool| {
+                    let bump = BumpKind::between(&tool.current, &tool.latest);
+                    format!(
+                        "{bullet} {}{} {} {arrow} {} {}",
+                        style(tool.alias.name()).bold().cyan(),
+                        " ".repeat(longest_alias_len - tool.alias.name().len()),
+                        style(&tool.current).dim(),
+                        bump.styled(&tool.latest).bold(),
+                        bump.styled(format!("({})", bump.as_str())).dim(),
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!("{}\n{lines}", style(path).dim())
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn format_json(report: &[(String, Vec<OutdatedTool>)]) -> String {
+    let manifests = report
+        .iter()
+        .map(|(path, tools)| {
+            let tools = tools
+                .iter()
+                .map(|tool| {
+                    json!({
+                        "alias": tool.alias.name(),
+                        "id": tool.id.to_string(),
+                        "current": tool.current.to_string(),
+                        "latest": tool.latest.to_string(),
+                        "bump": BumpKind::between(&tool.current, &tool.latest).as_str(),
+                    })
+                })
+                .collect::<Vec<_>>();
+            json!({ "path": path, "tools": tools })
+        })
+        .collect::<Vec<_>>();
+    json!({ "manifests": manifests }).to_string()
+}
@@ -0,0 +1,138 @@
+use std::{
+    collections::HashSet,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use console::style;
+use tokio::fs::metadata;
+
+use rokit::{discovery::discover_all_manifests, storage::Home};
+
+use crate::util::{CliProgressTracker, Symbol};
+
+/// Removes installed tool versions that have not been used recently.
+///
+/// Tool versions used by any discovered manifest are never removed.
+#[derive(Debug, Parser)]
+pub struct PruneSubcommand {
+    /// Remove tool versions that have not been used for at least
+    /// this long - for example `90d`, `12w`, or `48h`.
+    #[clap(long, value_parser = parse_duration)]
+    pub unused_for: Duration,
+    /// Print the tool versions that would be removed, without removing them.
+    #[clap(long)]
+    pub dry_run: bool,
+}
+
+impl PruneSubcommand {
+    pub async fn run(self, home: &Home) -> Result<()> {
+        let cache = home.tool_cache();
+        let storage = home.tool_storage();
+
+        // 1. Find all tool versions that are still in use by some manifest
+        let usage = home.tool_usage().await?;
+        let in_use = discover_all_manifests(false, false)
+            .await
+            .into_iter()
+            .flat_map(|manifest| manifest.tools.into_values())
+            .collect::<HashSet<_>>();
+
+        // 2. Find all installed tool versions that have gone unused - tools
+        // that have never been run through Rokit fall back to the time they
+        // were installed, and tools with missing binaries are always removed
+        let now = SystemTime::now();
+        let mut stale = Vec::new();
+        for spec in cache.all_installed() {
+            if in_use.contains(&spec) {
+                continue;
+            }
+            let last_used = match usage.last_used(&spec) {
+                Some(time) => time,
+                None => metadata(storage.tool_path(&spec))
+                    .await
+                    .and_then(|meta| meta.modified())
+                    .unwrap_or(UNIX_EPOCH),
+            };
+            let unused_for = now.duration_since(last_used).unwrap_or_default();
+            if unused_for >= self.unused_for {
+                stale.push(spec);
+            }
+        }
+
+        let list_bullet = style(Symbol::Bullet).dim();
+        let list = stale
+            .iter()
+            .map(|spec| format!("  {list_bullet} {spec}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if self.dry_run {
+            if stale.is_empty() {
+                println!("No tool versions would be removed.");
+            } else {
+                println!(
+                    "These tool versions would be removed:\n{list}\n\nRun without `{}` to remove them.",
+                    style("--dry-run").bold()
+                );
+            }
+            return Ok(());
+        }
+
+        // 3. Remove the stale tool versions, and everything we know about them
+        let pt = CliProgressTracker::new_with_message("Removing", stale.len());
+        for spec in &stale {
+            storage.remove_tool_contents(spec).await?;
+            let _ = cache.remove_installed(spec);
+            pt.task_completed();
+        }
+        home.forget_tool_usage(&stale).await?;
+
+        // NOTE: Files of removed tool versions may be shared with other
+        // versions, so only files that nothing links to anymore are freed
+        if !stale.is_empty() {
+            storage.collect_garbage(false).await?;
+        }
+
+        if stale.is_empty() {
+            pt.finish_with_message(format!(
+                "No tool versions needed to be removed {}",
+                pt.formatted_elapsed()
+            ));
+        } else {
+            pt.finish_with_message(format!(
+                "Removed {} tool version{} {}\n\n{list}",
+                stale.len(),
+                if stale.len() == 1 { "" } else { "s" },
+                pt.formatted_elapsed(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/**
+    Parses a duration such as `90d`, consisting of a whole
+    number and a unit - weeks, days, hours, or minutes.
+*/
+fn parse_duration(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (amount, unit) = s.split_at(split);
+
+    let Ok(amount) = amount.parse::<u64>() else {
+        bail!("'{s}' must start with a whole number, such as '90d'");
+    };
+    let unit_secs = match unit.trim() {
+        "w" | "week" | "weeks" => 7 * 24 * 60 * 60,
+        "d" | "day" | "days" => 24 * 60 * 60,
+        "h" | "hour" | "hours" => 60 * 60,
+        "m" | "minute" | "minutes" => 60,
+        "" => bail!("'{s}' is missing a unit - use one of 'w', 'd', 'h', or 'm'"),
+        unit => bail!("unknown unit '{unit}' - use one of 'w', 'd', 'h', or 'm'"),
+    };
+
+    Ok(Duration::from_secs(amount.saturating_mul(unit_secs)))
+}
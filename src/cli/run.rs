@@ -0,0 +1,140 @@
+use std::process::exit;
+
+use anyhow::{Context, Result};
+use clap::{Parser, ValueEnum};
+use serde_json::json;
+use tokio::time::Instant;
+use tracing::debug;
+
+use rokit::{
+    discovery::{discover_tool, DiscoveredTool},
+    result::ErrorKind,
+    storage::Home,
+    system::{run_interruptible_captured, run_interruptible_with, CapturedOutput, Sandbox},
+    tool::ToolAlias,
+};
+
+use crate::runner::{install_tool, missing_tool_error, should_auto_install};
+use crate::util::CliError;
+
+/// Runs a tool from the current project, just like running its link.
+///
+/// With `--capture json`, everything the tool writes to stdout and stderr is
+/// captured, and a single JSON object with the exit code and output of the tool
+/// is printed once it exits - Rokit then exits successfully, even if the tool
+/// failed, since its exit code is part of the result. Without `--capture`,
+/// Rokit exits with the exit code of the tool.
+#[derive(Debug, Parser)]
+pub struct RunSubcommand {
+    /// Capture the output of the tool, and print it in the given format.
+    #[clap(long, value_enum)]
+    pub capture: Option<CaptureFormat>,
+    /// The alias of the tool to run, such as `rojo`.
+    pub alias: ToolAlias,
+    /// Arguments to pass to the tool, after `--`.
+    #[clap(last = true)]
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CaptureFormat {
+    Json,
+}
+
+impl RunSubcommand {
+    pub async fn run(self, home: &Home) -> Result<()> {
+        let alias = &self.alias;
+        let Some(tool) = discover_tool(alias, false, false).await else {
+            return Err(CliError::new(
+                ErrorKind::NotFound,
+                format!(
+                    "Failed to find tool '{alias}' in any project manifest file.\
+                    \nAdd the tool to a project using 'rokit add' before running it."
+                ),
+            )
+            .into());
+        };
+
+        // Tools are installed on demand, the same way as when running their links
+        if !home.tool_storage().has_tool_binary(&tool.spec).await {
+            if should_auto_install(home, &tool).await {
+                debug!(spec = %tool.spec, "Installing tool with missing binary");
+                install_tool(home, &tool).await?;
+            } else {
+                return Err(missing_tool_error(home, alias, &tool).into());
+            }
+        }
+
+        // NOTE: Usage statistics are not critical, so failing
+        // to record them should never fail the program itself
+        if let Err(e) = home.record_tool_usage(&tool.spec).await {
+            debug!("Failed to record tool usage: {e}");
+        }
+
+        let program_path = home.tool_storage().tool_path(&tool.spec);
+        let program_cwd = tool.working_dir();
+        let program_args = tool
+            .options
+            .prepend_args
+            .iter()
+            .chain(&self.args)
+            .collect::<Vec<_>>();
+        let sandbox = if tool.options.sandbox {
+            Some(Sandbox::new().context("Failed to create sandbox for tool")?)
+        } else {
+            None
+        };
+
+        let Some(CaptureFormat::Json) = self.capture else {
+            let code = run_interruptible_with(
+                &program_path,
+                &program_args,
+                &tool.options.env,
+                program_cwd.as_deref(),
+                sandbox.as_ref(),
+            )
+            .await
+            .with_context(|| format!("Failed to run tool '{alias}'"))?;
+            // NOTE: Running a tool never changes any data that must be saved,
+            // but exiting skips all destructors, so the sandbox must be removed
+            drop(sandbox);
+            if code != 0 {
+                exit(code);
+            }
+            return Ok(());
+        };
+
+        let start = Instant::now();
+        let output = run_interruptible_captured(
+            &program_path,
+            &program_args,
+            &tool.options.env,
+            program_cwd.as_deref(),
+            sandbox.as_ref(),
+        )
+        .await
+        .with_context(|| format!("Failed to run tool '{alias}'"))?;
+
+        println!("{}", capture_json(alias, &tool, &output, start));
+
+        Ok(())
+    }
+}
+
+fn capture_json(
+    alias: &ToolAlias,
+    tool: &DiscoveredTool,
+    output: &CapturedOutput,
+    start: Instant,
+) -> String {
+    json!({
+        "alias": alias.name(),
+        "spec": tool.spec.to_string(),
+        "code": output.code,
+        "success": output.code == 0,
+        "elapsed_ms": u64::try_from(start.elapsed().as_millis()).unwrap_or(u64::MAX),
+        "stdout": String::from_utf8_lossy(&output.stdout),
+        "stderr": String::from_utf8_lossy(&output.stderr),
+    })
+    .to_string()
+}
@@ -0,0 +1,274 @@
+use std::{
+    collections::BTreeSet,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+use futures::{stream::FuturesOrdered, StreamExt};
+use serde_json::{json, Value as JsonValue};
+
+use rokit::{
+    discovery::discover_all_manifests,
+    storage::{Home, InstalledToolMetadata},
+    system::current_dir,
+    tool::{ToolId, ToolSpec},
+};
+
+/// Generates a software bill of materials (SBOM) for all tools
+/// in discovered manifests, and prints it to stdout.
+///
+/// Checksums are included for tools that are currently installed,
+/// and are computed from the installed tool binaries. The artifacts
+/// that tools were installed from are also included, when known.
+#[derive(Debug, Parser)]
+pub struct SbomSubcommand {
+    /// The SBOM format to generate.
+    #[clap(long, value_enum, default_value_t)]
+    pub format: SbomFormat,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SbomFormat {
+    /// `CycloneDX` 1.5, in JSON format.
+    #[default]
+    Cyclonedx,
+    /// SPDX 2.3, in JSON format.
+    Spdx,
+}
+
+/*
+    A single tool to include in the SBOM.
+*/
+struct SbomTool {
+    spec: ToolSpec,
+    version: String,
+    sha256: Option<String>,
+    metadata: Option<InstalledToolMetadata>,
+}
+
+impl SbomTool {
+    fn purl(&self) -> String {
+        format!(
+            "pkg:github/{}/{}@{}",
+            self.spec.author().to_ascii_lowercase(),
+            self.spec.name().to_ascii_lowercase(),
+            self.version,
+        )
+    }
+
+    // The metadata and URL of the artifact the tool was installed from, if known
+    fn artifact(&self) -> Option<(&InstalledToolMetadata, &str)> {
+        let meta = self.metadata.as_ref()?;
+        Some((meta, meta.url()?))
+    }
+}
+
+impl SbomSubcommand {
+    pub async fn run(self, home: &Home) -> Result<()> {
+        let specs = discover_all_manifests(false, false)
+            .await
+            .into_iter()
+            .flat_map(|manifest| manifest.tools.into_values())
+            .collect::<BTreeSet<_>>();
+
+        let storage = home.tool_storage();
+        let tools = specs
+            .into_iter()
+            .map(|spec| async move {
+                let sha256 = storage.tool_sha256(&spec).await;
+                let metadata = storage.tool_metadata(&spec).await;
+                let version = match spec.git_ref().or(spec.workflow_ref()) {
+                    Some(git_ref) => git_ref.to_string(),
+                    None => spec.version().to_string(),
+                };
+                SbomTool {
+                    spec,
+                    version,
+                    sha256,
+                    metadata,
+                }
+            })
+            .collect::<FuturesOrdered<_>>()
+            .collect::<Vec<_>>()
+            .await;
+
+        let cwd = current_dir().await;
+        let project_name = cwd.file_name().map_or_else(
+            || "project".to_string(),
+            |n| n.to_string_lossy().to_string(),
+        );
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        let document = match self.format {
+            SbomFormat::Cyclonedx => cyclonedx_document(&project_name, timestamp, &tools),
+            SbomFormat::Spdx => spdx_document(&project_name, timestamp, &tools),
+        };
+        println!("{}", serde_json::to_string_pretty(&document)?);
+
+        Ok(())
+    }
+}
+
+fn cyclonedx_document(project_name: &str, timestamp: u64, tools: &[SbomTool]) -> JsonValue {
+    let components = tools
+        .iter()
+        .map(|tool| {
+            let purl = tool.purl();
+            let mut component = json!({
+                "type": "application",
+                "bom-ref": purl,
+                "group": tool.spec.author(),
+                "name": tool.spec.name(),
+                "version": tool.version,
+                "purl": purl,
+                "externalReferences": [{
+                    "type": "vcs",
+                    "url": repository_url(tool.spec.id()),
+                }],
+            });
+            if let Some(sha256) = &tool.sha256 {
+                component["hashes"] = json!([{ "alg": "SHA-256", "content": sha256 }]);
+            }
+            if let Some((meta, url)) = tool.artifact() {
+                component["externalReferences"]
+                    .as_array_mut()
+                    .unwrap()
+                    .push(json!({
+                        "type": "distribution",
+                        "url": url,
+                        "hashes": [{ "alg": "SHA-256", "content": meta.sha256() }],
+                    }));
+            }
+            component
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": {
+            "timestamp": format_timestamp(timestamp),
+            "tools": {
+                "components": [{
+                    "type": "application",
+                    "name": "rokit",
+                    "version": env!("CARGO_PKG_VERSION"),
+                }],
+            },
+            "component": {
+                "type": "application",
+                "bom-ref": project_name,
+                "name": project_name,
+            },
+        },
+        "components": components,
+    })
+}
+
+fn spdx_document(project_name: &str, timestamp: u64, tools: &[SbomTool]) -> JsonValue {
+    let packages = tools
+        .iter()
+        .map(|tool| {
+            let mut package = json!({
+                "name": tool.spec.name(),
+                "SPDXID": spdx_id(&tool.spec),
+                "versionInfo": tool.version,
+                "supplier": format!("Organization: {}", tool.spec.author()),
+                "downloadLocation": match tool.artifact() {
+                    Some((_, url)) => url.to_string(),
+                    None => format!("git+{}", repository_url(tool.spec.id())),
+                },
+                "filesAnalyzed": false,
+                "licenseConcluded": "NOASSERTION",
+                "licenseDeclared": "NOASSERTION",
+                "copyrightText": "NOASSERTION",
+                "externalRefs": [{
+                    "referenceCategory": "PACKAGE-MANAGER",
+                    "referenceType": "purl",
+                    "referenceLocator": tool.purl(),
+                }],
+            });
+            if let Some(sha256) = &tool.sha256 {
+                package["checksums"] = json!([{ "algorithm": "SHA256", "checksumValue": sha256 }]);
+            }
+            package
+        })
+        .collect::<Vec<_>>();
+    let relationships = tools
+        .iter()
+        .map(|tool| {
+            json!({
+                "spdxElementId": "SPDXRef-DOCUMENT",
+                "relationshipType": "DESCRIBES",
+                "relatedSpdxElement": spdx_id(&tool.spec),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": format!("{project_name}-tools"),
+        "documentNamespace": format!(
+            "{}/sbom/{project_name}-{timestamp}",
+            env!("CARGO_PKG_REPOSITORY").trim_end_matches(".git"),
+        ),
+        "creationInfo": {
+            "created": format_timestamp(timestamp),
+            "creators": [format!("Tool: rokit-{}", env!("CARGO_PKG_VERSION"))],
+        },
+        "packages": packages,
+        "relationships": relationships,
+    })
+}
+
+fn repository_url(id: &ToolId) -> String {
+    format!("https://github.com/{}/{}", id.author(), id.name())
+}
+
+// SPDX identifiers may only contain letters, numbers, periods, and hyphens
+fn spdx_id(spec: &ToolSpec) -> String {
+    let id = format!("{}-{}-{}", spec.author(), spec.name(), spec.version())
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>();
+    format!("SPDXRef-Package-{id}")
+}
+
+// Formats a unix timestamp as an RFC 3339 date and time in UTC, such as
+// "2024-05-01T12:30:00Z", which both CycloneDX and SPDX documents require
+fn format_timestamp(secs: u64) -> String {
+    let days = i64::try_from(secs / 86_400).unwrap_or_default();
+    let secs_of_day = secs % 86_400;
+
+    // NOTE: Converts days since the unix epoch to a civil date, using
+    // the algorithm from http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60,
+    )
+}
@@ -0,0 +1,131 @@
+use std::fmt::Write as _;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use console::style;
+use futures::future::join_all;
+use semver::Version;
+
+use rokit::{sources::SearchResult, storage::Home};
+
+use crate::util::{prompt_for_search_result, CliProgressTracker, Symbol, ToolIdOrSpec};
+
+use super::add::AddSubcommand;
+
+/**
+    The topic used by tools for the Roblox ecosystem, on GitHub.
+*/
+const ROBLOX_TOPIC: &str = "roblox";
+
+/// Searches GitHub for tools, and optionally adds one of them.
+///
+/// Results are sorted by stars, and show the latest version of each tool.
+/// In an interactive terminal, a tool can then be picked from the results,
+/// which adds it the same way as `rokit add` would.
+#[derive(Debug, Parser)]
+pub struct SearchSubcommand {
+    /// Keywords to search for, in the names and descriptions of tools.
+    #[clap(required = true)]
+    pub query: Vec<String>,
+    /// Only include tools for the Roblox ecosystem,
+    /// using the `roblox` topic on GitHub.
+    #[clap(long)]
+    pub roblox: bool,
+    /// The maximum number of results to show.
+    #[clap(long, default_value_t = 10, value_parser = clap::value_parser!(u8).range(1..=100))]
+    pub limit: u8,
+    /// Add the picked tool globally instead of adding
+    /// it to the nearest manifest file.
+    #[clap(long)]
+    pub global: bool,
+}
+
+impl SearchSubcommand {
+    pub async fn run(self, home: &Home) -> Result<()> {
+        let source = home.artifact_source().await?;
+        let query = self.query.join(" ");
+        let topic = self.roblox.then_some(ROBLOX_TOPIC);
+        let pt = CliProgressTracker::new_with_message("Searching", 2);
+
+        // 1. Search for tools, and fetch the latest version of each
+        // of them - tools without any release can not be added
+        let results = source
+            .search_tools(&query, topic, usize::from(self.limit))
+            .await
+            .with_context(|| format!("Failed to search for tools matching '{query}'"))?;
+        pt.task_completed();
+
+        let versions = join_all(results.iter().map(|result| async {
+            let release = source.get_latest_release(&result.id).await.ok()?;
+            let artifact = release.artifacts.first()?;
+            Some(artifact.tool_spec.version().clone())
+        }))
+        .await;
+        pt.task_completed();
+
+        if results.is_empty() {
+            pt.finish_with_message(format!(
+                "No tools were found matching '{query}' {}",
+                pt.formatted_elapsed(),
+            ));
+            return Ok(());
+        }
+        pt.finish_with_message(format!(
+            "Found {} tool{} {}\n\n{}",
+            style(results.len()).bold().magenta(),
+            if results.len() == 1 { "" } else { "s" },
+            pt.formatted_elapsed(),
+            format_results(&results, &versions),
+        ));
+
+        // 2. Let the user pick a tool to add, if any can be added
+        let addable = results
+            .iter()
+            .zip(&versions)
+            .filter_map(|(result, version)| Some((result, version.as_ref()?)))
+            .collect::<Vec<_>>();
+        if addable.is_empty() {
+            return Ok(());
+        }
+        let choices = addable
+            .iter()
+            .map(|(result, version)| format!("{} ({version})", result.id))
+            .collect();
+        let Some(index) = prompt_for_search_result(choices).await? else {
+            return Ok(());
+        };
+
+        let (result, _) = addable[index];
+        let add = AddSubcommand {
+            tool: ToolIdOrSpec::Id(result.id.clone()),
+            alias: None,
+            global: self.global,
+            force: false,
+        };
+        add.run(home).await
+    }
+}
+
+fn format_results(results: &[SearchResult], versions: &[Option<Version>]) -> String {
+    let bullet = style(Symbol::Bullet).dim();
+    results
+        .iter()
+        .zip(versions)
+        .map(|(result, version)| {
+            let version = match version {
+                Some(version) => style(version.to_string()).bold().yellow(),
+                None => style(String::from("no releases")).dim(),
+            };
+            let mut lines = format!(
+                "{bullet} {} {version} {}",
+                style(result.id.to_string()).bold().cyan(),
+                style(format!("{} {}", Symbol::Star, result.stars)).dim(),
+            );
+            if let Some(description) = &result.description {
+                let _ = write!(lines, "\n  {}", description.trim());
+            }
+            lines
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -1,32 +1,109 @@
-use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
 use clap::Parser;
 use console::style;
 use tracing::warn;
 
 use rokit::{
+    manifests::RokitManifest,
+    result::ErrorKind,
     storage::Home,
-    system::{add_to_path, exists_in_path},
+    system::{
+        add_to_path, add_to_system_path, current_dir, exists_in_path, repair_path, PathRepair,
+    },
 };
 
-use crate::util::CliProgressTracker;
+use crate::util::{display_path, CliError, CliProgressTracker};
 
 /// Installs / re-installs Rokit, and updates all tool links.
 #[derive(Debug, Parser)]
-pub struct SelfInstallSubcommand {}
+pub struct SelfInstallSubcommand {
+    /// Install Rokit and tool links into a custom directory, instead of `~/.rokit/bin`.
+    ///
+    /// The directory is remembered in the global manifest, and may
+    /// also be set using the `ROKIT_BIN_DIR` environment variable.
+    #[clap(long)]
+    pub bin_dir: Option<PathBuf>,
+    /// Re-validate the PATH, removing duplicate entries and entries for
+    /// Rokit installations that no longer exist, and adding Rokit if it is missing.
+    #[clap(long)]
+    pub repair: bool,
+    /// Install Rokit for all users on this machine, instead of only the current user.
+    ///
+    /// This is only supported on Windows, and must be run as an administrator -
+    /// Rokit is installed into `%ProgramData%\Rokit`, and added to the machine PATH.
+    #[clap(long, conflicts_with_all = ["bin_dir", "repair"])]
+    pub system: bool,
+}
 
 impl SelfInstallSubcommand {
     pub async fn run(self, home: &Home) -> Result<()> {
+        if self.system {
+            return run_system().await;
+        }
+
+        // Remember any custom bin directory, so that Rokit
+        // and tools can find it during later invocations
+        let home = match &self.bin_dir {
+            None => home.clone(),
+            Some(bin_dir) => {
+                let bin_dir = current_dir().await.join(bin_dir);
+                let mut manifest = RokitManifest::load_or_create(home.path()).await?;
+                manifest
+                    .set_bin_dir(&bin_dir)
+                    .context("Failed to set bin directory")?;
+                manifest.save(home.path()).await?;
+                home.with_bin_dir(&bin_dir)
+                    .await
+                    .context("Failed to create bin directory")?
+            }
+        };
+        let home = &home;
         let storage = home.tool_storage();
 
-        let pt = CliProgressTracker::new_with_message("Linking", 2);
-        let (had_rokit_installed, was_rokit_updated) = storage.recreate_all_links().await.context(
-            "Failed to recreate tool links!\
-            \nYour installation may be corrupted.",
-        )?;
+        // NOTE: Every tool link is a task, since writing many links
+        // can be slow, such as for home directories on network drives
+        let num_links = storage
+            .all_link_paths()
+            .await
+            .map_or(0, |paths| paths.len());
+        let pt = CliProgressTracker::new_with_message("Linking", num_links + 2);
+        let (had_rokit_installed, was_rokit_updated) = storage
+            .recreate_all_links_with_progress(|_| pt.task_completed())
+            .await
+            .context(
+                "Failed to recreate tool links!\
+                \nYour installation may be corrupted.",
+            )?;
 
         pt.task_completed();
+
+        // Isolated homes belong to a single project, so they are never
+        // added to the PATH for the user - CI or scripts must do that instead
+        if Home::is_isolated() {
+            pt.finish_with_message(format!(
+                "Rokit has been installed into an isolated home! {}\
+                \n\nAdd {} to your {} to run tools for this project.",
+                pt.formatted_elapsed(),
+                style(display_path(home.bin_dir())).bold(),
+                style("$PATH").bold(),
+            ));
+            return Ok(());
+        }
+
         pt.update_message("Pathifying");
 
+        if self.repair {
+            let repair = repair_path(home).await.context("Failed to repair PATH")?;
+            pt.finish_with_message(format_repair(
+                &repair,
+                home.bin_dir(),
+                &pt.formatted_elapsed(),
+            ));
+            return Ok(());
+        }
+
         let mut path_errored = false;
         let path_was_changed = add_to_path(home)
             .await
@@ -34,8 +111,9 @@ impl SelfInstallSubcommand {
                 path_errored = true;
                 warn!(
                     "Failed to automatically add Rokit to your PATH!\
-                    \nPlease add `~/.rokit/bin` to be able to run tools.
+                    \nPlease add `{}` to be able to run tools.
                     \nError: {e:?}",
+                    home.bin_dir().display(),
                 );
             })
             .unwrap_or(false);
@@ -85,3 +163,97 @@ impl SelfInstallSubcommand {
         Ok(())
     }
 }
+
+async fn run_system() -> Result<()> {
+    if !cfg!(windows) {
+        return Err(CliError::new(
+            ErrorKind::IncompatiblePlatform,
+            "Installing Rokit for all users is only supported on Windows.\
+            \nOn other systems, install Rokit into a shared directory using `--bin-dir` instead.",
+        )
+        .into());
+    }
+    if Home::is_isolated() {
+        bail!("Installing Rokit for all users is not supported in isolated mode.");
+    }
+
+    let home = Home::load_system()
+        .await
+        .context("Failed to load the system Rokit home directory")?;
+    let storage = home.tool_storage();
+    let num_links = storage
+        .all_link_paths()
+        .await
+        .map_or(0, |paths| paths.len());
+    let pt = CliProgressTracker::new_with_message("Pathifying", num_links + 2);
+
+    // NOTE: The machine PATH is changed before linking, since it fails
+    // right away without elevation, leaving the system home untouched
+    let path_was_changed = add_to_system_path(&home).await.context(
+        "Failed to add Rokit to the machine PATH!\
+        \nInstalling Rokit for all users requires running as an administrator.",
+    )?;
+    pt.task_completed();
+
+    pt.update_message("Linking");
+    let (had_rokit_installed, _) = storage
+        .recreate_all_links_with_progress(|_| pt.task_completed())
+        .await
+        .context(
+            "Failed to recreate tool links!\
+            \nYour installation may be corrupted.",
+        )?;
+    pt.task_completed();
+    home.save().await?;
+
+    let main_message = if had_rokit_installed {
+        "Rokit was re-linked for all users successfully!"
+    } else {
+        "Rokit has been installed for all users successfully!"
+    };
+    let restart_message = if path_was_changed {
+        format!(
+            "\n\nExecutables for Rokit and tools have been added to the machine {}.\
+            \nUsers must restart their computer for the changes to take effect.",
+            style("$PATH").bold(),
+        )
+    } else {
+        String::new()
+    };
+
+    pt.finish_with_message(format!(
+        "{main_message} {}\n\nInstalled into {}{restart_message}",
+        pt.formatted_elapsed(),
+        style(display_path(home.path())).bold(),
+    ));
+
+    Ok(())
+}
+
+fn format_repair(repair: &PathRepair, bin_dir: &Path, elapsed: &str) -> String {
+    if !repair.is_changed() {
+        return format!("Your PATH is already up-to-date. {elapsed}");
+    }
+
+    let mut lines = vec![format!("Your PATH has been repaired! {elapsed}\n")];
+    if repair.added {
+        lines.push(format!(
+            "{} {}",
+            style("+").bold().green(),
+            display_path(bin_dir)
+        ));
+    }
+    for entry in &repair.removed {
+        lines.push(format!(
+            "{} {}",
+            style("-").bold().red(),
+            style(entry).dim()
+        ));
+    }
+    if cfg!(unix) && repair.added {
+        lines.push(String::from(
+            "\nPlease restart your terminal for the changes to take effect.",
+        ));
+    }
+    lines.join("\n")
+}
@@ -1,68 +1,98 @@
-use std::io::{stdout, BufWriter};
-
 use anyhow::{bail, Context, Result};
-use clap::Parser;
-use console::{style, Style};
-use dialoguer::{theme::ColorfulTheme, Confirm};
-use pulldown_cmark::{Options, Parser as MarkdownParser};
-use pulldown_cmark_mdcat::{
-    resources::FileResourceHandler, Environment, Settings, TerminalProgram, TerminalSize, Theme,
-};
-use syntect::parsing::SyntaxSet;
+use clap::{Parser, ValueEnum};
+use console::style;
+use dialoguer::Confirm;
 
 use semver::Version;
+use tracing::warn;
 
-use rokit::{storage::Home, tool::ToolId};
+use rokit::{discovery::discover_strict_platform, result::ErrorKind, storage::Home};
 
-use crate::util::{find_most_compatible_artifact, CliProgressTracker};
+use crate::util::{
+    find_most_compatible_artifact, prompt_theme, render_markdown, rokit_tool_id, CliError,
+    CliProgressTracker, Symbol,
+};
 
 /// Updates Rokit to the latest version.
 #[derive(Debug, Parser)]
 pub struct SelfUpdateSubcommand {
-    /// Update even if the latest version is already installed.
-    #[clap(long, hide = true)]
+    /// Install a specific version of Rokit instead of the
+    /// latest version, such as to roll back a bad release.
+    #[clap(long, conflicts_with = "channel")]
+    pub version: Option<Version>,
+    /// The release channel to update from.
+    #[clap(long, value_enum, default_value_t)]
+    pub channel: UpdateChannel,
+    /// Update even if the latest version is already installed, if the given
+    /// version is older than the current version, or if the downloaded
+    /// binary can not be verified using a published checksum.
+    #[clap(long)]
     pub force: bool,
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum UpdateChannel {
+    /// Stable releases only.
+    #[default]
+    Stable,
+    /// The latest release, including prereleases.
+    Prerelease,
+}
+
 impl SelfUpdateSubcommand {
     pub async fn run(self, home: &Home) -> Result<()> {
-        let repo = env!("CARGO_PKG_REPOSITORY")
-            .trim_start_matches("https://github.com/")
-            .trim_end_matches(".git");
-        let Ok(tool_id) = repo.parse::<ToolId>() else {
-            bail!(
-                "Failed to parse manifest repository URL!\
-                \nThis is a bug in Rokit, please report it at:
-                \n{repo}"
-            );
-        };
+        let tool_id = rokit_tool_id()?;
 
-        let pt = CliProgressTracker::new_with_message("Loading", 4);
+        let pt = CliProgressTracker::new_with_message("Loading", 5);
         let source = home.artifact_source().await?;
+        let strict_platform = discover_strict_platform().await;
 
         pt.task_completed();
         pt.update_message("Fetching");
 
-        let release = source.get_latest_release(&tool_id).await?;
+        let release = match (&self.version, self.channel) {
+            (Some(version), _) => {
+                let spec = (tool_id.clone(), version.clone()).into();
+                source.get_specific_release(&spec).await?
+            }
+            (None, UpdateChannel::Stable) => source.get_latest_release(&tool_id).await?,
+            (None, UpdateChannel::Prerelease) => source.get_latest_prerelease(&tool_id).await?,
+        };
 
-        // Skip updating if we are already on the latest version
+        // Skip updating if we are already on the latest or desired version,
+        // and never downgrade to an older version unless explicitly forced to
         let version_current = env!("CARGO_PKG_VERSION").parse::<Version>().unwrap();
         let version_latest = release
             .artifacts
             .first()
-            .unwrap()
+            .context("No Rokit artifacts were found for the release")?
             .tool_spec
             .version()
             .clone();
-        if version_current >= version_latest && !self.force {
-            let msg = format!(
-                "Rokit is already up-to-date! {}\n\n\
-                The latest version is {}.",
-                pt.formatted_elapsed(),
-                style(&version_latest).bold().magenta(),
-            );
-            pt.finish_with_message(msg);
-            return Ok(());
+        if !self.force {
+            if self.version.is_some() && version_latest < version_current {
+                bail!(
+                    "Version {version_latest} is older than the current version {version_current}.\
+                    \nRun the command again with `--force` to downgrade Rokit."
+                );
+            } else if self.version.is_some() && version_latest == version_current {
+                let msg = format!(
+                    "Rokit is already on version {}! {}",
+                    style(&version_latest).bold().magenta(),
+                    pt.formatted_elapsed(),
+                );
+                pt.finish_with_message(msg);
+                return Ok(());
+            } else if self.version.is_none() && version_current >= version_latest {
+                let msg = format!(
+                    "Rokit is already up-to-date! {}\n\n\
+                    The latest version is {}.",
+                    pt.formatted_elapsed(),
+                    style(&version_latest).bold().magenta(),
+                );
+                pt.finish_with_message(msg);
+                return Ok(());
+            }
         }
 
         // Download the most compatible artifact - this should always exist,
@@ -70,12 +100,39 @@ impl SelfUpdateSubcommand {
         pt.task_completed();
         pt.update_message("Downloading");
 
-        let artifact = find_most_compatible_artifact(&release.artifacts, &tool_id)
+        let artifact = find_most_compatible_artifact(&release.artifacts, &tool_id, strict_platform)
             .context("No compatible Rokit artifact was found (WAT???)")?;
         let artifact_contents = source
             .download_artifact_contents(&artifact)
             .await
-            .context("Failed to download latest Rokit binary")?;
+            .context("Failed to download Rokit binary")?;
+
+        // Verify the contents against any checksums published with the release,
+        // before replacing the current binary with something we can not trust
+        pt.task_completed();
+        pt.update_message("Verifying");
+        let verified = source
+            .verify_release_checksum(&release, &artifact, &artifact_contents)
+            .await
+            .context("Failed to verify Rokit binary")?;
+        // NOTE: Rokit replaces itself and every link to it, so an unverified
+        // binary is only ever installed if the user explicitly asked for it
+        if !verified && artifact.sha256.is_none() {
+            if !self.force {
+                Err(CliError::new(
+                    ErrorKind::Trust,
+                    format!(
+                        "No checksum was published for Rokit version {version_latest}.\
+                        \nThe downloaded binary could not be verified, so Rokit was not updated.\
+                        \nRun the command again with `--force` to update anyway."
+                    ),
+                ))?;
+            }
+            warn!(
+                "No checksum was published for Rokit version {version_latest}.\
+                \nThe downloaded binary could not be verified."
+            );
+        }
 
         // Extract the binary contents from the artifact
         pt.task_completed();
@@ -108,41 +165,20 @@ impl SelfUpdateSubcommand {
 
         // If there is a changelog, and the user wants to see it, show it
         if let Some(changelog) = release.changelog {
-            let to_show_changelog = Confirm::with_theme(&ColorfulTheme {
-                active_item_prefix: style("📋 ".to_string()),
-                prompt_style: Style::new(),
-                ..Default::default()
-            })
-            .with_prompt("View changelogs for this update?")
-            .interact_opt()?
-            .unwrap_or_default();
+            let to_show_changelog =
+                Confirm::with_theme(prompt_theme(Some(Symbol::Changelog)).as_ref())
+                    .with_prompt("View changelogs for this update?")
+                    .interact_opt()?
+                    .unwrap_or_default();
 
             if to_show_changelog {
                 println!();
-                pulldown_cmark_mdcat::push_tty(
-                    &Settings {
-                        terminal_capabilities: TerminalProgram::detect().capabilities(),
-                        terminal_size: TerminalSize::detect()
-                            .context("Failed to detect terminal size")?,
-                        syntax_set: &SyntaxSet::load_defaults_newlines(),
-                        theme: Theme::default(),
-                    },
-                    &Environment::for_local_directory(&tempfile::tempdir()?.path())?,
-                    &FileResourceHandler::new(104_857_600), // TODO: Maybe make this be a DispatchingResourceHandler?
-                    &mut BufWriter::new(stdout()),
-                    MarkdownParser::new_ext(
-                        format!(
-                            "# Changelog - {} v{}\n{}",
-                            tool_id.name(),
-                            version_current,
-                            changelog
-                        )
-                        .as_str(),
-                        Options::ENABLE_FOOTNOTES
-                            | Options::ENABLE_TABLES
-                            | Options::ENABLE_STRIKETHROUGH,
-                    ),
-                )?;
+                render_markdown(&format!(
+                    "# Changelog - {} v{}\n{}",
+                    tool_id.name(),
+                    version_current,
+                    changelog
+                ))?;
             }
         }
 
@@ -1,32 +1,44 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::Write,
-    path::{Path, MAIN_SEPARATOR_STR},
+    fs::Metadata,
+    path::{Path, PathBuf, MAIN_SEPARATOR_STR},
+    time::SystemTime,
 };
 
 use anyhow::Result;
 use clap::Parser;
 use console::style;
-use futures::{stream::FuturesOrdered, TryStreamExt};
+use futures::{stream::FuturesOrdered, StreamExt, TryStreamExt};
 use tokio::{fs::read, task::spawn_blocking};
 
 use rokit::{
     descriptor::Descriptor,
+    discovery::{discover_all_manifests, discover_shadowed_tools},
     storage::Home,
     system::{current_dir, current_exe, exists_in_path},
 };
 
+use crate::util::{display_path, Symbol};
+
+use super::doctor::format_shadowed_tools;
+
 /// Prints out information about the current system and installed tools.
 #[derive(Debug, Parser)]
-pub struct SystemInfoSubcommand {}
+pub struct SystemInfoSubcommand {
+    /// Also print disk usage, installed versions, and
+    /// when each tool was last used - useful for pruning.
+    #[clap(long)]
+    pub stats: bool,
+}
 
 impl SystemInfoSubcommand {
     pub async fn run(self, home: &Home) -> Result<()> {
         let cache = home.tool_cache();
         let storage = home.tool_storage();
 
-        let bullet = style("•").dim();
-        let arrow = style("→").dim();
+        let bullet = style(Symbol::Bullet).dim();
+        let arrow = style(Symbol::Arrow).dim();
 
         // Gather all installed tools and their descriptors
 
@@ -53,12 +65,20 @@ impl SystemInfoSubcommand {
             .collect::<FuturesOrdered<_>>()
             .try_collect::<HashMap<_, _>>()
             .await?;
+        let tool_metadata = tool_specs
+            .iter()
+            .map(|t| async { (t.clone(), storage.tool_metadata(t).await) })
+            .collect::<FuturesOrdered<_>>()
+            .collect::<HashMap<_, _>>()
+            .await;
 
         // Write sections of information:
         // 1. Paths
         // 2. System
         // 3. Binaries
         // 4. Links
+        // 5. Shadowed tools (if any)
+        // 6. Statistics (optional)
 
         let mut s = String::new();
 
@@ -70,6 +90,11 @@ impl SystemInfoSubcommand {
             "  {bullet} Rokit dir   {arrow} {}",
             style(display_path(home.path()))
         )?;
+        writeln!(
+            s,
+            "  {bullet} Bin dir     {arrow} {}",
+            style(display_path(home.bin_dir()))
+        )?;
         writeln!(
             s,
             "  {bullet} Current dir {arrow} {}",
@@ -144,6 +169,20 @@ impl SystemInfoSubcommand {
                     style("UNKNOWN").bold().yellow()
                 )?;
             }
+            if let Some(meta) = tool_metadata.get(&tool_spec).and_then(Option::as_ref) {
+                writeln!(
+                    s,
+                    "    {} {}",
+                    style(Symbol::TreeEnd).dim(),
+                    style(format!(
+                        "{} from {}, installed {}",
+                        meta.artifact_name().unwrap_or("unknown artifact"),
+                        meta.provider().display_name(),
+                        format_time_ago(meta.installed_at()),
+                    ))
+                    .dim()
+                )?;
+            }
         }
 
         // Links
@@ -165,18 +204,169 @@ impl SystemInfoSubcommand {
             )?;
         }
 
+        // Shadowed tools
+
+        let manifests = discover_all_manifests(false, false).await;
+        let shadowed = discover_shadowed_tools(home, &manifests).await;
+        if !shadowed.is_empty() {
+            writeln!(s, "\nShadowed tools:")?;
+            writeln!(s, "{}", format_shadowed_tools(&shadowed).trim_end())?;
+        }
+
+        // Statistics
+
+        if self.stats {
+            let usage = home.tool_usage().await?;
+            let tool_ids = cache.all_installed_ids();
+            let tool_sizes = tool_ids
+                .iter()
+                .map(|id| {
+                    let version_dirs = cache
+                        .all_installed_versions_for_id(id)
+                        .into_iter()
+                        .filter_map(|version| {
+                            let spec = (id.clone(), version).into();
+                            storage.tool_path(&spec).parent().map(Path::to_path_buf)
+                        })
+                        .collect::<Vec<_>>();
+                    dir_sizes(version_dirs)
+                })
+                .collect::<FuturesOrdered<_>>()
+                .collect::<Vec<_>>()
+                .await;
+            let total_size = dir_sizes(vec![home.path().to_path_buf()]).await;
+
+            writeln!(s, "\nStatistics:")?;
+            let longest_id = tool_ids
+                .iter()
+                .map(|id| id.to_string().len())
+                .max()
+                .unwrap_or(0);
+            for (id, size) in tool_ids.iter().zip(tool_sizes) {
+                let num_versions = cache.all_installed_versions_for_id(id).len();
+                let padding = " ".repeat(longest_id - id.to_string().len());
+                writeln!(
+                    s,
+                    "  {bullet} {id} {padding}{arrow} {} version{}, {}, last used {}",
+                    num_versions,
+                    if num_versions == 1 { "" } else { "s" },
+                    style(format_size(size)).bold(),
+                    match usage.last_used_for_id(id) {
+                        Some(time) => style(format_time_ago(time)),
+                        None => style("never".to_string()).dim(),
+                    },
+                )?;
+            }
+            writeln!(
+                s,
+                "  {bullet} {} {arrow} {}",
+                style("Total size").bold(),
+                style(format_size(total_size)).bold().magenta()
+            )?;
+        }
+
         println!("{s}");
 
         Ok(())
     }
 }
 
-fn display_path(path: impl AsRef<Path>) -> String {
-    let path = path.as_ref();
-    if let Some(user_home) = dirs::home_dir() {
-        if let Ok(path) = path.strip_prefix(user_home) {
-            return format!("~/{}", dunce::simplified(path).display());
-        }
+/**
+    Sums up the sizes of all files in the given directories,
+    recursively - symlinks are not followed, and any files
+    that can not be read are simply skipped.
+
+    Files with several hardlinks, such as tools sharing storage, are
+    only counted once, since they only take up space on disk once.
+*/
+async fn dir_sizes(dirs: Vec<PathBuf>) -> u64 {
+    fn dir_size(dir: &Path, seen: &mut HashSet<(u64, u64)>) -> u64 {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return 0;
+        };
+        entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let path = entry.path();
+                let meta = path.symlink_metadata().ok()?;
+                if meta.is_dir() {
+                    return Some(dir_size(&path, seen));
+                }
+                match file_identity(&path, &meta) {
+                    Some(identity) if !seen.insert(identity) => None,
+                    _ => Some(meta.len()),
+                }
+            })
+            .sum()
+    }
+
+    spawn_blocking(move || {
+        let mut seen = HashSet::new();
+        dirs.iter().map(|dir| dir_size(dir, &mut seen)).sum()
+    })
+    .await
+    .unwrap()
+}
+
+/*
+    Gets the identity of the file at the given path, which is the same
+    for all hardlinks to the file - the device and inode on Unix, and
+    the volume serial number and file index on Windows.
+*/
+#[cfg(unix)]
+#[allow(clippy::unnecessary_wraps)]
+fn file_identity(_path: &Path, meta: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(windows)]
+fn file_identity(path: &Path, _meta: &Metadata) -> Option<(u64, u64)> {
+    use std::os::windows::io::AsRawHandle;
+    use winapi::um::fileapi::{GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION};
+
+    let file = std::fs::File::open(path).ok()?;
+    // SAFETY: The handle is valid until the file is dropped, and the
+    // information struct is plain data that may be zero-initialized
+    let info = unsafe {
+        let mut info: BY_HANDLE_FILE_INFORMATION = std::mem::zeroed();
+        let ok = GetFileInformationByHandle(file.as_raw_handle().cast(), &mut info);
+        (ok != 0).then_some(info)
+    }?;
+    let index = (u64::from(info.nFileIndexHigh) << 32) | u64::from(info.nFileIndexLow);
+    Some((u64::from(info.dwVolumeSerialNumber), index))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn file_identity(_path: &Path, _meta: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
     }
-    dunce::simplified(path).display().to_string()
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+fn format_time_ago(time: SystemTime) -> String {
+    let secs = SystemTime::now()
+        .duration_since(time)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let (amount, unit) = match secs {
+        0..=59 => return "just now".to_string(),
+        60..=3_599 => (secs / 60, "minute"),
+        3_600..=86_399 => (secs / 3_600, "hour"),
+        _ => (secs / 86_400, "day"),
+    };
+    format!("{amount} {unit}{} ago", if amount == 1 { "" } else { "s" })
 }
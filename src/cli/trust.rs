@@ -1,33 +1,129 @@
-use anyhow::{bail, Result};
+use std::{collections::BTreeSet, str::FromStr};
+
+use anyhow::{bail, Context, Result};
 use clap::Parser;
 use console::style;
+use semver::VersionReq;
+use tokio::io::{stdin, AsyncReadExt};
 
-use rokit::{storage::Home, tool::ToolId};
+use rokit::{discovery::discover_all_manifests, storage::Home};
 
-use crate::util::CliProgressTracker;
+use crate::util::{CliProgressTracker, Symbol, ToolIdOrSpec};
 
 /// Mark the given tool(s) as being trusted.
+///
+/// Tools are trusted for all of their versions, unless a specific
+/// version is given, such as `rojo-rbx/rojo@7.4.1`, or `--versions`.
+///
+/// Many tools can be trusted at once, such as in Docker builds, by passing
+/// `-` to read tools from stdin, or using `--from-manifest`.
 #[derive(Debug, Parser)]
 pub struct TrustSubcommand {
-    /// The tool(s) to mark as trusted.
-    pub tools: Vec<ToolId>,
+    /// The tool(s) to mark as trusted, or `-` to read tools from stdin,
+    /// separated by whitespace or newlines, ignoring lines starting with `#`.
+    pub tools: Vec<TrustTarget>,
+    /// Only trust versions of the tool(s) matching this
+    /// version requirement, such as `^7` or `>=0.27, <0.29`.
+    #[clap(long, value_name = "REQ")]
+    pub versions: Option<VersionReq>,
+    /// Trust all tools in the discovered manifests, at the versions they
+    /// declare, using the trust scope set in the global manifest.
+    #[clap(long, conflicts_with = "versions")]
+    pub from_manifest: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum TrustTarget {
+    Tool(ToolIdOrSpec),
+    Stdin,
+}
+
+impl FromStr for TrustTarget {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "-" {
+            Ok(Self::Stdin)
+        } else {
+            Ok(Self::Tool(s.parse()?))
+        }
+    }
 }
 
 impl TrustSubcommand {
     pub async fn run(self, home: &Home) -> Result<()> {
-        if self.tools.is_empty() {
+        if self.tools.is_empty() && !self.from_manifest {
             bail!("Please provide at least one tool to trust.");
         }
 
+        let mut tools = Vec::new();
+        let mut read_stdin = false;
+        for target in self.tools {
+            match target {
+                TrustTarget::Tool(tool) => tools.push(tool),
+                TrustTarget::Stdin if read_stdin => {}
+                TrustTarget::Stdin => {
+                    read_stdin = true;
+                    tools.extend(read_tools_from_stdin().await?);
+                }
+            }
+        }
+
+        let manifest_specs = if self.from_manifest {
+            let specs = discover_all_manifests(false, false)
+                .await
+                .into_iter()
+                .flat_map(|manifest| manifest.tools.into_values())
+                .collect::<BTreeSet<_>>();
+            if specs.is_empty() {
+                bail!("No tools were found in any manifest to trust.");
+            }
+            specs
+        } else {
+            BTreeSet::new()
+        };
+        if tools.is_empty() && manifest_specs.is_empty() {
+            bail!("No tools were read from stdin to trust.");
+        }
+
         // NOTE: We use a progress bar only to show the final message to the
         // user below, to maintain consistent formatting with other commands.
         let pt = CliProgressTracker::new_with_message("Trusting", 1);
 
         let cache = home.tool_cache();
-        let (added_tools, existing_tools) = self
-            .tools
-            .into_iter()
-            .partition::<Vec<_>, _>(|tool| cache.add_trust(tool.clone()));
+        let mut added_tools = Vec::new();
+        let mut existing_tools = Vec::new();
+        for spec in manifest_specs {
+            // NOTE: Tools that are trusted already, such as for all of
+            // their versions, must not have a narrower trust recorded
+            let added = !cache.is_trusted_spec(&spec) && cache.add_trust_spec(&spec);
+            if added {
+                added_tools.push(spec.to_string());
+            } else {
+                existing_tools.push(spec.to_string());
+            }
+        }
+        for tool in tools {
+            let (label, added) = match (tool, &self.versions) {
+                (ToolIdOrSpec::Id(id), None) => (id.to_string(), cache.add_trust(id)),
+                (ToolIdOrSpec::Id(id), Some(versions)) => (
+                    format!("{id} ({versions})"),
+                    cache.add_trust_versions(id, versions.clone()),
+                ),
+                (ToolIdOrSpec::Spec(spec), None) => {
+                    let exact = format!("={}", spec.version()).parse::<VersionReq>()?;
+                    let added = cache.add_trust_versions(spec.id().clone(), exact);
+                    (spec.to_string(), added)
+                }
+                (ToolIdOrSpec::Spec(spec), Some(_)) => {
+                    bail!("Tool {spec} has a version, and can not be trusted using `--versions`.")
+                }
+            };
+            if added {
+                added_tools.push(label);
+            } else {
+                existing_tools.push(label);
+            }
+        }
 
         if added_tools.len() == 1 && existing_tools.is_empty() {
             // Special case 1 with shorter output - a singular tool was added
@@ -46,7 +142,7 @@ impl TrustSubcommand {
         } else {
             // General case with multiple tools added and/or already trusted
             let mut lines = Vec::new();
-            let list_bullet = style("•").dim();
+            let list_bullet = style(Symbol::Bullet).dim();
 
             if !added_tools.is_empty() {
                 lines.push(String::from("These tools are now trusted:"));
@@ -74,3 +170,25 @@ impl TrustSubcommand {
         Ok(())
     }
 }
+
+/*
+    Reads tools to trust from stdin, separated by whitespace or newlines,
+    skipping empty lines and comments, such as from a file piped into Rokit.
+*/
+async fn read_tools_from_stdin() -> Result<Vec<ToolIdOrSpec>> {
+    let mut input = String::new();
+    stdin()
+        .read_to_string(&mut input)
+        .await
+        .context("Failed to read tools from stdin")?;
+    input
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .flat_map(str::split_whitespace)
+        .map(|tool| {
+            tool.parse::<ToolIdOrSpec>()
+                .with_context(|| format!("Invalid tool '{tool}' read from stdin"))
+        })
+        .collect()
+}
@@ -1,16 +1,31 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+};
+
 use anyhow::{bail, Context, Result};
 use clap::Parser;
 use console::style;
 use futures::{stream::FuturesUnordered, TryStreamExt};
+use tracing::warn;
 
-use rokit::{discovery::discover_all_manifests, manifests::RokitManifest, storage::Home};
+use rokit::{
+    descriptor::Descriptor,
+    discovery::{discover_all_manifests, discover_strict_platform},
+    manifests::{exclude_artifacts, find_asset_artifact, RokitManifest},
+    sources::{Artifact, ArtifactSource},
+    storage::Home,
+    tool::{ToolAlias, ToolSpec},
+};
 
 use crate::util::{
-    find_most_compatible_artifact, CliProgressTracker, ToolAliasOrIdOrSpec, ToolIdOrSpec,
+    display_path, find_most_compatible_artifact, prompt_for_trust_specs,
+    prompt_for_update_selection, CliProgressTracker, Symbol, ToolAliasOrIdOrSpec, ToolIdOrSpec,
 };
 
 /// Updates all tools, or specific tools, to the latest version.
 #[derive(Debug, Parser)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct UpdateSubcommand {
     /// The tools to update - can be aliases, ids, or specifications.
     /// Omit to update all tools.
@@ -18,174 +33,106 @@ pub struct UpdateSubcommand {
     /// Update tools globally instead of using the nearest manifest file.
     #[clap(long)]
     pub global: bool,
+    /// Update all tools in every discovered manifest file,
+    /// including the global manifest and all project manifests.
+    #[clap(long, conflicts_with_all = ["global", "tools"])]
+    pub all: bool,
     /// Check for updates without actually updating the tools.
     #[clap(long)]
     pub check: bool,
+    /// Choose which of the available updates to apply,
+    /// using a list of all tools with updates available.
+    #[clap(long, conflicts_with = "check")]
+    pub interactive: bool,
 }
 
 impl UpdateSubcommand {
     pub async fn run(self, home: &Home) -> Result<()> {
-        // 1. Load tool source and the desired manifest
+        // 1. Load tool source and the desired manifest(s)
         let source = home.artifact_source().await?;
-        let manifest_path = if self.global {
-            home.path().to_path_buf()
-        } else {
-            let non_global_manifests = discover_all_manifests(true, true).await;
-            non_global_manifests
-                .first()
-                .map(|m| m.path.parent().unwrap().to_path_buf())
-                .context(
-                    "No manifest was found for the current directory.\
-                    \nRun `rokit init` in your project root to create one.",
-                )?
-        };
-
-        let mut manifest = if self.global {
-            RokitManifest::load_or_create(&manifest_path).await?
-        } else {
-            RokitManifest::load(&manifest_path).await?
-        };
+        let mut manifests = Vec::new();
+        for manifest_path in self.manifest_paths(home).await? {
+            let manifest = if manifest_path == home.path() {
+                RokitManifest::load_or_create(&manifest_path).await?
+            } else {
+                RokitManifest::load(&manifest_path).await?
+            };
+            manifests.push((manifest_path, manifest));
+        }
 
         // 2. Try to convert aliases into ids using existing tools,
         // or fill with existing tools if no tools were provided
-        let tools = if self.tools.is_empty() {
-            manifest
-                .tool_specs()
-                .iter()
-                .cloned()
-                .map(|(alias, spec)| (alias, ToolIdOrSpec::Id(spec.id().clone())))
-                .collect::<Vec<_>>()
-        } else {
-            // FUTURE: Refactor this logic here below, it's quite difficult to read
-            self.tools
-                .into_iter()
-                .map(|tool| {
-                    // NOTE: If we were given a tool id or spec, we need
-                    // to find the proper alias for it in the manifest,
-                    // which may or may not be correlated to the id or spec!
-                    let alias = if let ToolAliasOrIdOrSpec::Alias(alias) = &tool {
-                        alias.clone()
-                    } else {
-                        let search_id = match &tool {
-                            ToolAliasOrIdOrSpec::Id(id) => id.clone(),
-                            ToolAliasOrIdOrSpec::Spec(spec) => spec.id().clone(),
-                            ToolAliasOrIdOrSpec::Alias(_) => unreachable!(),
-                        };
-                        let found = manifest
-                            .tool_specs()
-                            .iter()
-                            .filter_map(|(a, s)| {
-                                if s.id() == &search_id {
-                                    Some(a.clone())
-                                } else {
-                                    None
-                                }
-                            })
-                            .collect::<Vec<_>>();
-                        if found.is_empty() {
-                            bail!(
-                                "No tool with the id '{search_id}' has been added to this project.\
-                                \nYou can add the tool to the project using `{}`.",
-                                style("rokit add").bold().green(),
-                            )
-                        } else if found.len() > 1 {
-                            bail!(
-                                "Multiple tools with the id '{search_id}' have been added to this project.\
-                                \nPlease specify the tool by its alias, or update the manifest manually."
-                            )
-                        }
-                        found.first().unwrap().clone()
-                    };
-                    // Transform tool alias, id, or spec -> (alias, id or spec)
-                    match tool {
-                        ToolAliasOrIdOrSpec::Id(id) => Ok((alias, id.into())),
-                        ToolAliasOrIdOrSpec::Spec(spec) => Ok((alias, spec.into())),
-                        ToolAliasOrIdOrSpec::Alias(alias) => {
-                            let spec = manifest.get_tool(&alias).with_context(|| {
-                                format!(
-                                "No tool with the alias '{alias}' has been added to this project.\
-                                \nYou can add the tool to the project using `{}`.",
-                                style("rokit add").bold().green(),
-                            )
-                            })?;
-                            let id = ToolIdOrSpec::Id(spec.id().clone());
-                            Ok::<_, anyhow::Error>((alias, id))
-                        }
-                    }
-                })
-                .collect::<Result<Vec<_>>>()?
-        };
-        let pt = CliProgressTracker::new_with_message_and_subtasks("Fetching", tools.len(), 3);
-
-        // 3. Fetch the latest or desired versions of the tools
-        let tool_releases = tools
-            .into_iter()
-            .map(|(alias, tool)| async {
-                let (alias, id, artifacts) = match tool {
-                    ToolIdOrSpec::Spec(spec) => {
-                        let artifacts =
-                            source.get_specific_release(&spec).await.with_context(|| {
-                                format!(
-                                    "Failed to fetch release for '{spec}'!\
-                                    \nMake sure the given tool version exists."
-                                )
-                            })?;
-                        (alias, spec.id().clone(), artifacts)
-                    }
-                    ToolIdOrSpec::Id(id) => {
-                        let artifacts =
-                            source.get_latest_release(&id).await.with_context(|| {
-                                format!(
-                                    "Failed to fetch latest release for '{id}'!\
-                                    \nMake sure the given tool identifier exists."
-                                )
-                            })?;
-                        (alias, id, artifacts)
-                    }
-                };
+        let manifest_tools = manifests
+            .iter()
+            .map(|(_, manifest)| resolve_tools(manifest, &self.tools))
+            .collect::<Result<Vec<_>>>()?;
 
-                let artifact = find_most_compatible_artifact(&artifacts.artifacts, &id)?;
-                pt.subtask_completed();
+        // NOTE: Deduplicate tools since they may appear in several manifests,
+        // and we only want to fetch the latest release for each of them once
+        let unique_tools = manifest_tools
+            .iter()
+            .flatten()
+            .map(|(_, tool)| tool.clone())
+            .collect::<BTreeSet<_>>();
+        let pt =
+            CliProgressTracker::new_with_message_and_subtasks("Fetching", unique_tools.len(), 3);
 
-                Ok::<_, anyhow::Error>((alias, id, artifact))
-            })
-            .collect::<FuturesUnordered<_>>()
-            .try_collect::<Vec<_>>()
-            .await?;
+        // 3. Fetch the latest or desired versions of the tools
+        let tool_artifacts = fetch_tool_artifacts(&source, unique_tools, &pt).await?;
 
-        // 4. Check if the --check flag was used, and if so, check for updates
-        let tools_changed = tool_releases
+        // 4. Figure out which tools changed in each of the manifests
+        let mut manifest_changes = manifests
             .iter()
-            .filter_map(|(alias, _, artifact)| {
-                let spec_old = manifest.get_tool(alias).unwrap();
-                let spec_new = artifact.tool_spec.clone();
-                if spec_old == spec_new {
-                    None
-                } else {
-                    Some((alias.clone(), spec_old, spec_new))
-                }
+            .zip(&manifest_tools)
+            .map(|((path, manifest), tools)| {
+                let changes = find_changes(manifest, tools, &tool_artifacts);
+                (path.clone(), changes)
             })
             .collect::<Vec<_>>();
-        if self.check {
-            let bullet = style("•").dim();
-            let arrow = style("→").dim();
 
-            let updated_tool_lines = tools_changed
+        // NOTE: Updates are chosen before asking for trust, so
+        // that users are never asked about updates they skip
+        if self.interactive {
+            let choices = manifest_changes
                 .iter()
-                .map(|(alias, spec_old, spec_new)| {
-                    format!(
-                        "{bullet} {} {} {arrow} {}",
-                        style(alias.to_string()).bold().cyan(),
-                        style(spec_old.version()).yellow(),
-                        style(spec_new.version()).bold().yellow()
-                    )
-                })
-                .collect::<Vec<_>>()
-                .join("\n");
+                .flat_map(|(path, changes)| changes.iter().map(move |change| (path, change)))
+                .map(|(path, change)| format_choice(path, change, manifest_changes.len() > 1))
+                .collect::<Vec<_>>();
+            let selected = if choices.is_empty() {
+                Vec::new()
+            } else {
+                pt.suspend_while(prompt_for_update_selection(choices))
+                    .await?
+            };
+            let mut index = 0;
+            for (_, changes) in &mut manifest_changes {
+                changes.retain(|_| {
+                    index += 1;
+                    selected.contains(&(index - 1))
+                });
+            }
+        }
+
+        // NOTE: New versions may need to be trusted again, if trust is
+        // recorded per version, and any untrusted versions are left as-is
+        if !self.check {
+            let all_changes = manifest_changes.iter().flat_map(|(_, changes)| changes);
+            let declined = prompt_for_untrusted_updates(home, &source, &pt, all_changes).await?;
+            for (_, changes) in &mut manifest_changes {
+                changes.retain(|(_, _, spec_new)| !declined.contains(spec_new));
+            }
+        }
+        let num_changed = manifest_changes
+            .iter()
+            .map(|(_, changes)| changes.len())
+            .sum::<usize>();
+        let changed_tool_lines = format_changes(&manifest_changes);
 
+        // 5. Check if the --check flag was used, and if so, only report updates
+        if self.check {
             pt.update_message("Checking for updates");
 
-            if tools_changed.is_empty() {
+            if num_changed == 0 {
                 pt.finish_with_message(format!(
                     "All tools are already up-to-date! {}",
                     pt.formatted_elapsed(),
@@ -193,57 +140,40 @@ impl UpdateSubcommand {
             } else {
                 pt.finish_with_message(format!(
                     "New versions are available for {} tool{} {}\
-                     \n\n{updated_tool_lines}\n\n\
+                     \n\n{changed_tool_lines}\n\n\
                     Run `{}` to update the tools.",
-                    style(tools_changed.len()).bold().magenta(),
-                    if tools_changed.len() == 1 { "" } else { "s" },
+                    style(num_changed).bold().magenta(),
+                    if num_changed == 1 { "" } else { "s" },
                     pt.formatted_elapsed(),
-                    style("rokit update").bold().green(),
+                    style(if self.all {
+                        "rokit update --all"
+                    } else {
+                        "rokit update"
+                    })
+                    .bold()
+                    .green(),
                 ));
             }
             pt.subtask_completed();
             return Ok(());
         }
 
-        // 5. Modify the manifest with the desired new tools, save
+        // 6. Modify the manifests with the desired new tools, save
         pt.update_message("Modifying");
 
-        for (alias, _, spec_new) in &tools_changed {
-            manifest.update_tool(alias, spec_new);
-            pt.subtask_completed();
+        for ((path, manifest), (_, changes)) in manifests.iter_mut().zip(&manifest_changes) {
+            if changes.is_empty() {
+                continue;
+            }
+            for (alias, _, spec_new) in changes {
+                update_manifest_tool(manifest, alias, spec_new);
+                pt.subtask_completed();
+            }
+            manifest.save(path).await?;
         }
-        manifest.save(&manifest_path).await?;
 
-        // 6. Finally, display a nice message to the user
-        let tools_changed = tool_releases
-            .iter()
-            .filter_map(|(alias, _, artifact)| {
-                let spec_old = manifest.get_tool(alias).unwrap();
-                let spec_new = artifact.tool_spec.clone();
-                if spec_old == spec_new {
-                    None
-                } else {
-                    Some((alias.clone(), spec_old, spec_new))
-                }
-            })
-            .collect::<Vec<_>>();
-        let bullet = style("•").dim();
-        let arrow = style("→").dim();
-
-        let updated_tool_lines = tools_changed
-            .iter()
-            .map(|(alias, spec_old, spec_new)| {
-                format!(
-                    "{bullet} {} {} {arrow} {}",
-                    style(alias.to_string()).bold().cyan(),
-                    style(spec_old.version()).yellow(),
-                    style(spec_new.version()).bold().yellow()
-                )
-            })
-            .collect::<Vec<_>>()
-            .join("\n");
-
-        if tools_changed.is_empty() {
+        // 7. Finally, display a nice message to the user
+        if num_changed == 0 {
             pt.finish_with_message(format!(
                 "All tools are already up-to-date! {}",
                 pt.formatted_elapsed(),
@@ -251,10 +181,10 @@ impl UpdateSubcommand {
         } else {
             pt.finish_with_message(format!(
                 "Updated versions for {} tool{} {}\
-                \n\n{updated_tool_lines}\n\n\
+                \n\n{changed_tool_lines}\n\n\
                 Run `{}` to install the updated tools.",
-                style(tools_changed.len()).bold().magenta(),
-                if tools_changed.len() == 1 { "" } else { "s" },
+                style(num_changed).bold().magenta(),
+                if num_changed == 1 { "" } else { "s" },
                 pt.formatted_elapsed(),
                 style("rokit install").bold().green(),
             ));
@@ -264,4 +194,344 @@ impl UpdateSubcommand {
 
         Ok(())
     }
+
+    async fn manifest_paths(&self, home: &Home) -> Result<Vec<PathBuf>> {
+        if self.global {
+            return Ok(vec![home.path().to_path_buf()]);
+        }
+
+        let non_global_paths = discover_all_manifests(true, true)
+            .await
+            .into_iter()
+            .map(|m| m.path.parent().unwrap().to_path_buf())
+            .collect::<Vec<_>>();
+
+        if self.all {
+            let mut paths = non_global_paths;
+            if !paths.iter().any(|path| path == home.path()) {
+                paths.push(home.path().to_path_buf());
+            }
+            Ok(paths)
+        } else {
+            let nearest = non_global_paths.into_iter().next().context(
+                "No manifest was found for the current directory.\
+                \nRun `rokit init` in your project root to create one.",
+            )?;
+            Ok(vec![nearest])
+        }
+    }
+}
+
+pub(super) type ToolChange = (ToolAlias, ToolSpec, ToolSpec);
+
+/**
+    Asks the user to trust new versions of tools that were trusted for their
+    previous version, but not for their new one - this only happens when trust
+    is recorded per version, such as when updating across major versions with
+    `trust-scope = "major"` set, since trust otherwise covers all versions.
+
+    Returns the new versions that the user did not trust, which must not be used.
+*/
+pub(super) async fn prompt_for_untrusted_updates<'a>(
+    home: &Home,
+    source: &ArtifactSource,
+    pt: &CliProgressTracker,
+    changes: impl IntoIterator<Item = &'a ToolChange>,
+) -> Result<BTreeSet<ToolSpec>> {
+    let tool_cache = home.tool_cache();
+    let untrusted_specs = changes
+        .into_iter()
+        .filter(|(_, spec_old, spec_new)| {
+            tool_cache.is_trusted_spec(spec_old) && !tool_cache.is_trusted_spec(spec_new)
+        })
+        .map(|(_, _, spec_new)| spec_new.clone())
+        .collect::<BTreeSet<_>>();
+    if untrusted_specs.is_empty() {
+        return Ok(BTreeSet::new());
+    }
+
+    let newly_trusted_specs = pt
+        .suspend_while(prompt_for_trust_specs(
+            source,
+            untrusted_specs.iter().cloned().collect(),
+            true,
+        ))
+        .await?;
+    for spec in &newly_trusted_specs {
+        let _ = tool_cache.add_trust_spec(spec);
+    }
+
+    let declined_specs = untrusted_specs
+        .into_iter()
+        .filter(|spec| !newly_trusted_specs.contains(spec))
+        .collect::<BTreeSet<_>>();
+    for spec in &declined_specs {
+        warn!("Skipping update to {spec}, since it was not trusted.");
+    }
+    Ok(declined_specs)
+}
+
+/**
+    Updates a tool in a manifest, warning the user if this removed a recorded
+    checksum for the tool, since it must be recorded again after reviewing it.
+*/
+pub(super) fn update_manifest_tool(
+    manifest: &mut RokitManifest,
+    alias: &ToolAlias,
+    spec: &ToolSpec,
+) {
+    let had_sha256 = !manifest.get_tool_options(alias).sha256.is_empty();
+    manifest.update_tool(alias, spec);
+    if had_sha256 && manifest.get_tool_options(alias).sha256.is_empty() {
+        warn!(
+            "The 'sha256' checksum for tool '{alias}' was removed, since it was recorded \
+            for a previous version.\nReview {spec}, and record its checksum if necessary."
+        );
+    }
+}
+
+/**
+    A tool to fetch the latest or desired version of, along with the
+    release channel to stay within, if set for the tool in the manifest,
+    patterns for release assets that should never be installed, and the
+    exact name of the release asset to install on this system, if set.
+
+    The channel is only used for tool ids, since specs are always exact.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(super) struct ToolRequest {
+    pub tool: ToolIdOrSpec,
+    pub channel: Option<String>,
+    pub exclude_assets: Vec<String>,
+    pub asset: Option<String>,
+}
+
+impl ToolRequest {
+    fn new(manifest: &RokitManifest, alias: &ToolAlias, tool: ToolIdOrSpec) -> Self {
+        let options = manifest.get_tool_options(alias);
+        let asset = options
+            .asset_for(&Descriptor::current_system())
+            .map(ToString::to_string);
+        let channel = match &tool {
+            ToolIdOrSpec::Id(_) => options.channel,
+            ToolIdOrSpec::Spec(_) => None,
+        };
+        Self {
+            tool,
+            channel,
+            exclude_assets: options.exclude_assets,
+            asset,
+        }
+    }
+}
+
+/**
+    Fetches the most compatible artifact for each of the given tools -
+    the latest version for tool ids, within their release channel
+    if they have one, or the given version for specs.
+*/
+pub(super) async fn fetch_tool_artifacts(
+    source: &ArtifactSource,
+    tools: BTreeSet<ToolRequest>,
+    pt: &CliProgressTracker,
+) -> Result<HashMap<ToolRequest, Artifact>> {
+    let strict_platform = discover_strict_platform().await;
+    tools
+        .into_iter()
+        .map(|request| async {
+            let (id, artifacts) = match (&request.tool, &request.channel) {
+                (ToolIdOrSpec::Spec(spec), _) => {
+                    let artifacts = source.get_specific_release(spec).await.with_context(|| {
+                        format!(
+                            "Failed to fetch release for '{spec}'!\
+                            \nMake sure the given tool version exists."
+                        )
+                    })?;
+                    (spec.id().clone(), artifacts)
+                }
+                (ToolIdOrSpec::Id(id), Some(channel)) => {
+                    let artifacts = source
+                        .get_latest_release_in_channel(id, channel)
+                        .await
+                        .with_context(|| {
+                            format!(
+                                "Failed to fetch latest release in the '{channel}' channel for '{id}'!\
+                                \nMake sure the channel set in the manifest is correct."
+                            )
+                        })?;
+                    (id.clone(), artifacts)
+                }
+                (ToolIdOrSpec::Id(id), None) => {
+                    let artifacts = source.get_latest_release(id).await.with_context(|| {
+                        format!(
+                            "Failed to fetch latest release for '{id}'!\
+                            \nMake sure the given tool identifier exists."
+                        )
+                    })?;
+                    (id.clone(), artifacts)
+                }
+            };
+
+            // NOTE: Releases without any artifacts can not have the named asset,
+            // and are reported the same way as for automatic artifact selection
+            let artifact = if let (Some(asset), Some(first)) =
+                (&request.asset, artifacts.artifacts.first())
+            {
+                find_asset_artifact(&first.tool_spec, &artifacts.artifacts, asset)?
+            } else {
+                let artifacts = exclude_artifacts(&artifacts.artifacts, &request.exclude_assets);
+                find_most_compatible_artifact(&artifacts, &id, strict_platform)?
+            };
+            pt.subtask_completed();
+
+            Ok::<_, anyhow::Error>((request, artifact))
+        })
+        .collect::<FuturesUnordered<_>>()
+        .try_collect::<HashMap<_, _>>()
+        .await
+}
+
+pub(super) fn resolve_tools(
+    manifest: &RokitManifest,
+    tools: &[ToolAliasOrIdOrSpec],
+) -> Result<Vec<(ToolAlias, ToolRequest)>> {
+    if tools.is_empty() {
+        return Ok(manifest
+            .tool_specs()
+            .iter()
+            .cloned()
+            .map(|(alias, spec)| {
+                let request = ToolRequest::new(manifest, &alias, spec.id().clone().into());
+                (alias, request)
+            })
+            .collect::<Vec<_>>());
+    }
+
+    // FUTURE: Refactor this logic here below, it's quite difficult to read
+    tools
+        .iter()
+        .cloned()
+        .map(|tool| {
+            // NOTE: If we were given a tool id or spec, we need
+            // to find the proper alias for it in the manifest,
+            // which may or may not be correlated to the id or spec!
+            let alias = if let ToolAliasOrIdOrSpec::Alias(alias) = &tool {
+                alias.clone()
+            } else {
+                let search_id = match &tool {
+                    ToolAliasOrIdOrSpec::Id(id) => id.clone(),
+                    ToolAliasOrIdOrSpec::Spec(spec) => spec.id().clone(),
+                    ToolAliasOrIdOrSpec::Alias(_) => unreachable!(),
+                };
+                let found = manifest
+                    .tool_specs()
+                    .iter()
+                    .filter_map(|(a, s)| {
+                        if s.id() == &search_id {
+                            Some(a.clone())
+                        } else {
+                            None
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                if found.is_empty() {
+                    bail!(
+                        "No tool with the id '{search_id}' has been added to this project.\
+                        \nYou can add the tool to the project using `{}`.",
+                        style("rokit add").bold().green(),
+                    )
+                } else if found.len() > 1 {
+                    bail!(
+                        "Multiple tools with the id '{search_id}' have been added to this project.\
+                        \nPlease specify the tool by its alias, or update the manifest manually."
+                    )
+                }
+                found.first().unwrap().clone()
+            };
+            // Transform tool alias, id, or spec -> (alias, id or spec)
+            let tool = match tool {
+                ToolAliasOrIdOrSpec::Id(id) => id.into(),
+                ToolAliasOrIdOrSpec::Spec(spec) => spec.into(),
+                ToolAliasOrIdOrSpec::Alias(_) => {
+                    let spec = manifest.get_tool(&alias).with_context(|| {
+                        format!(
+                            "No tool with the alias '{alias}' has been added to this project.\
+                            \nYou can add the tool to the project using `{}`.",
+                            style("rokit add").bold().green(),
+                        )
+                    })?;
+                    ToolIdOrSpec::Id(spec.id().clone())
+                }
+            };
+            let request = ToolRequest::new(manifest, &alias, tool);
+            Ok::<_, anyhow::Error>((alias, request))
+        })
+        .collect()
+}
+
+pub(super) fn find_changes(
+    manifest: &RokitManifest,
+    tools: &[(ToolAlias, ToolRequest)],
+    tool_artifacts: &HashMap<ToolRequest, Artifact>,
+) -> Vec<ToolChange> {
+    tools
+        .iter()
+        .filter_map(|(alias, tool)| {
+            let spec_old = manifest.get_tool(alias).unwrap();
+            let spec_new = tool_artifacts[tool].tool_spec.clone();
+            if spec_old == spec_new {
+                None
+            } else {
+                Some((alias.clone(), spec_old, spec_new))
+            }
+        })
+        .collect()
+}
+
+fn format_choice(path: &Path, (alias, spec_old, spec_new): &ToolChange, show_path: bool) -> String {
+    let choice = format!(
+        "{alias} {} {} {}",
+        spec_old.version(),
+        Symbol::Arrow,
+        style(spec_new.version()).bold()
+    );
+    if show_path {
+        format!("{choice} {}", style(display_path(path)).dim())
+    } else {
+        choice
+    }
+}
+
+pub(super) fn format_changes(manifest_changes: &[(PathBuf, Vec<ToolChange>)]) -> String {
+    let bullet = style(Symbol::Bullet).dim();
+    let arrow = style(Symbol::Arrow).dim();
+
+    // NOTE: We only show which manifest each change belongs to
+    // when there is more than one manifest, to keep output short
+    let show_paths = manifest_changes.len() > 1;
+
+    manifest_changes
+        .iter()
+        .filter(|(_, changes)| !changes.is_empty())
+        .map(|(path, changes)| {
+            let lines = changes
+                .iter()
+                .map(|(alias, spec_old, spec_new)| {
+                    format!(
+                        "{bullet} {} {} {arrow} {}",
+                        style(alias.to_string()).bold().cyan(),
+                        style(spec_old.version()).yellow(),
+                        style(spec_new.version()).bold().yellow()
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            if show_paths {
+                format!("{}\n{lines}", style(display_path(path)).dim())
+            } else {
+                lines
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
 }
@@ -0,0 +1,153 @@
+use std::collections::BTreeSet;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use console::style;
+use futures::{stream::FuturesUnordered, TryStreamExt};
+
+use rokit::{
+    discovery::{discover_extract_limits, discover_large_download_threshold},
+    manifests::RokitManifest,
+    storage::{Home, InstalledToolMetadata},
+};
+
+use crate::util::{warn_if_emulated, warn_if_large, CliProgressTracker, ToolAliasOrIdOrSpec};
+
+use super::update::{
+    fetch_tool_artifacts, find_changes, format_changes, prompt_for_untrusted_updates,
+    resolve_tools, update_manifest_tool,
+};
+
+/// Upgrades global tools to their latest versions, and installs them.
+///
+/// Unlike `rokit update`, this only ever changes the global manifest,
+/// and installs the new versions right away - project manifests are never changed.
+#[derive(Debug, Parser)]
+pub struct UpgradeSubcommand {
+    /// The global tools to upgrade - can be aliases, ids, or specifications.
+    /// Omit to upgrade all global tools.
+    pub tools: Vec<ToolAliasOrIdOrSpec>,
+}
+
+impl UpgradeSubcommand {
+    pub async fn run(self, home: &Home) -> Result<()> {
+        let tool_cache = home.tool_cache();
+        let tool_storage = home.tool_storage();
+
+        // 1. Load tool source and the global manifest, and find the tools to upgrade
+        let source = home.artifact_source().await?;
+        let manifest_path = home.path().to_path_buf();
+        let mut manifest = RokitManifest::load_or_create(&manifest_path).await?;
+        let tools = resolve_tools(&manifest, &self.tools)?;
+
+        // 2. Fetch the latest or desired versions of the tools
+        let unique_tools = tools
+            .iter()
+            .map(|(_, tool)| tool.clone())
+            .collect::<BTreeSet<_>>();
+        let pt =
+            CliProgressTracker::new_with_message_and_subtasks("Fetching", unique_tools.len(), 3);
+        let tool_artifacts = fetch_tool_artifacts(&source, unique_tools, &pt).await?;
+        let mut changes = find_changes(&manifest, &tools, &tool_artifacts);
+
+        // 3. Download and install the new versions - tool identifiers never change
+        // when upgrading, but new versions may need to be trusted again, if trust
+        // is recorded per version, and any untrusted versions are not installed
+        let declined = prompt_for_untrusted_updates(home, &source, &pt, &changes).await?;
+        changes.retain(|(_, _, spec_new)| !declined.contains(spec_new));
+        pt.update_message("Installing");
+        let large_download_threshold = discover_large_download_threshold().await;
+        let extract_limits = discover_extract_limits().await;
+        changes
+            .iter()
+            .map(|(alias, _, spec_new)| {
+                let tool_cache = &tool_cache;
+                let tool_storage = &tool_storage;
+                let source = &source;
+                let manifest = &manifest;
+                let pt = &pt;
+                let artifact = tool_artifacts
+                    .values()
+                    .find(|artifact| &artifact.tool_spec == spec_new)
+                    .expect("changed tools should have an artifact");
+                async move {
+                    if !tool_cache.is_installed(spec_new) {
+                        warn_if_large(artifact, large_download_threshold);
+                        let contents = source
+                            .download_artifact_contents(artifact)
+                            .await
+                            .with_context(|| {
+                                format!("Failed to download contents for {spec_new}")
+                            })?;
+                        let metadata = InstalledToolMetadata::new(artifact, &contents);
+                        let options = manifest.get_tool_options(alias);
+                        let file_pattern = options.file.as_deref();
+                        if options.extract_all {
+                            let (extracted, files) = artifact
+                                .extract_all_contents_matching(
+                                    contents,
+                                    file_pattern,
+                                    extract_limits,
+                                )
+                                .await
+                                .with_context(|| {
+                                    format!("Failed to extract contents for {spec_new}")
+                                })?;
+                            warn_if_emulated(artifact, &[], &extracted);
+                            tool_storage
+                                .replace_tool_contents_with_files(spec_new, extracted, &files)
+                                .await?;
+                        } else {
+                            let extracted = artifact
+                                .extract_contents_matching(contents, file_pattern, extract_limits)
+                                .await
+                                .with_context(|| {
+                                    format!("Failed to extract contents for {spec_new}")
+                                })?;
+                            warn_if_emulated(artifact, &[], &extracted);
+                            tool_storage
+                                .replace_tool_contents(spec_new, extracted)
+                                .await?;
+                        }
+                        tool_storage.save_tool_metadata(spec_new, metadata).await?;
+                        let _ = tool_cache.add_installed(spec_new.clone());
+                    }
+                    pt.subtask_completed();
+                    tool_storage.create_tool_link(alias).await?;
+                    pt.subtask_completed();
+                    anyhow::Ok(())
+                }
+            })
+            .collect::<FuturesUnordered<_>>()
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        // 4. Update the global manifest only once everything is installed,
+        // so that it never points to versions that could not be installed
+        for (alias, _, spec_new) in &changes {
+            update_manifest_tool(&mut manifest, alias, spec_new);
+        }
+        if !changes.is_empty() {
+            manifest.save(&manifest_path).await?;
+        }
+
+        // 5. Finally, display a nice message to the user
+        let num_changed = changes.len();
+        if num_changed == 0 {
+            pt.finish_with_message(format!(
+                "All global tools are already up-to-date! {}",
+                pt.formatted_elapsed(),
+            ));
+        } else {
+            let changed_tool_lines = format_changes(&[(manifest_path, changes)]);
+            pt.finish_with_message(format!(
+                "Upgraded and installed {} global tool{} {}\n\n{changed_tool_lines}",
+                style(num_changed).bold().magenta(),
+                if num_changed == 1 { "" } else { "s" },
+                pt.formatted_elapsed(),
+            ));
+        }
+
+        Ok(())
+    }
+}
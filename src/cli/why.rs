@@ -0,0 +1,173 @@
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use clap::Parser;
+use console::style;
+
+use rokit::{
+    discovery::{
+        discover_all_manifests, discover_fallthrough, discover_non_rokit_tool, discover_tool,
+        trace_tool_discovery,
+    },
+    manifests::Fallthrough,
+    storage::Home,
+    system::current_dir,
+    tool::ToolAlias,
+};
+
+use crate::util::{display_path, Symbol};
+
+/// Explains which tool an alias resolves to, and why.
+///
+/// Lists the manifests consulted, in order of precedence, which
+/// one declares the tool, and which binary would be run for it.
+#[derive(Debug, Parser)]
+pub struct WhySubcommand {
+    /// The alias of the tool to explain, such as `rojo`.
+    pub alias: ToolAlias,
+}
+
+impl WhySubcommand {
+    pub async fn run(self, home: &Home) -> Result<()> {
+        let alias = &self.alias;
+        let cwd = current_dir().await;
+
+        let bullet = style(Symbol::Bullet).dim();
+        let arrow = style(Symbol::Arrow).dim();
+        let mut lines = vec![format!(
+            "{} Resolving {} from {}\n",
+            Symbol::Search,
+            style(alias.name()).bold().cyan(),
+            display_path(&cwd),
+        )];
+
+        // 1. List all of the manifests consulted, and what they declare
+        let consulted = trace_tool_discovery(alias, false, false).await;
+        if consulted.is_empty() {
+            lines.push(format!("{bullet} No manifests were found."));
+        } else {
+            lines.push(String::from("Manifests, in order of precedence:"));
+        }
+        let mut matched = false;
+        for manifest in &consulted {
+            let status = match &manifest.spec {
+                Some(spec) if !matched => {
+                    matched = true;
+                    format!("{} {}", style(spec).bold().green(), style("(used)").green())
+                }
+                Some(spec) => format!("{} {}", style(spec).dim(), style("(shadowed)").dim()),
+                None => style(format!("does not declare '{alias}'"))
+                    .dim()
+                    .to_string(),
+            };
+            lines.push(format!(
+                "  {bullet} {} {} {arrow} {status}",
+                display_path(&manifest.path),
+                style(format!("({})", manifest.kind)).dim(),
+            ));
+        }
+        lines.push(String::new());
+
+        // 2. Explain what the runner would do with the tool, which uses
+        // the same discovery as the runner, so that the two never disagree
+        if let Some(tool) = discover_tool(alias, false, false).await {
+            let path = home.tool_storage().tool_path(&tool.spec);
+            let installed = if path.exists() {
+                style("installed").green()
+            } else {
+                style("not installed - run `rokit install`").yellow()
+            };
+            lines.push(format!(
+                "Resolved to {} from the {} manifest at {}",
+                style(&tool.spec).bold().magenta(),
+                tool.kind,
+                display_path(&tool.path),
+            ));
+            lines.push(format!(
+                "  {bullet} Binary: {} ({installed})",
+                display_path(&path)
+            ));
+            if let Some(cwd) = tool.working_dir() {
+                lines.push(format!(
+                    "  {bullet} Working directory: {}",
+                    display_path(cwd)
+                ));
+            }
+            if !tool.options.prepend_args.is_empty() {
+                lines.push(format!(
+                    "  {bullet} Prepended arguments: {}",
+                    tool.options.prepend_args.join(" ")
+                ));
+            }
+            if tool.options.sandbox {
+                lines.push(format!("  {bullet} Runs in a sandbox"));
+            }
+            for required in &tool.options.requires {
+                let resolved = match discover_tool(required, false, false).await {
+                    Some(required_tool) => style(required_tool.spec.to_string()).magenta(),
+                    None => style(String::from("not declared in any manifest")).red(),
+                };
+                lines.push(format!(
+                    "  {bullet} Requires {} {arrow} {resolved}",
+                    style(required.name()).bold().cyan(),
+                ));
+            }
+            let required_by = discover_required_by(alias).await;
+            if !required_by.is_empty() {
+                lines.push(format!(
+                    "  {bullet} Required by {}",
+                    required_by
+                        .iter()
+                        .map(|dependent| style(dependent.name()).bold().cyan().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ));
+            }
+        } else {
+            let fallthrough = discover_fallthrough().await;
+            let system_path = discover_non_rokit_tool(home, alias).await;
+            lines.push(format!(
+                "Not declared in any manifest - the `fallthrough` setting is {}",
+                style(fallthrough.as_str()).bold()
+            ));
+            lines.push(match (fallthrough, system_path) {
+                (Fallthrough::Deny, Some(path)) => format!(
+                    "  {bullet} Would {} instead of running {} from the system PATH",
+                    style("error").bold().red(),
+                    display_path(path),
+                ),
+                (Fallthrough::Allow | Fallthrough::Warn, Some(path)) => format!(
+                    "  {bullet} Would run {} from the system PATH{}",
+                    style(display_path(path)).bold(),
+                    if fallthrough == Fallthrough::Warn {
+                        ", with a warning"
+                    } else {
+                        ""
+                    },
+                ),
+                (_, None) => format!(
+                    "  {bullet} Would {}, since no other '{alias}' exists in the system PATH",
+                    style("error").bold().red(),
+                ),
+            });
+        }
+
+        println!("{}", lines.join("\n"));
+
+        Ok(())
+    }
+}
+
+/*
+    Finds the aliases of all tools, in all discovered manifests,
+    that require the tool with the given alias to be installed.
+*/
+async fn discover_required_by(alias: &ToolAlias) -> BTreeSet<ToolAlias> {
+    discover_all_manifests(false, false)
+        .await
+        .into_iter()
+        .flat_map(|manifest| manifest.options)
+        .filter(|(_, options)| options.requires.contains(alias))
+        .map(|(dependent, _)| dependent)
+        .collect()
+}
@@ -1,14 +1,14 @@
 use std::process::exit;
 
-use clap::Parser;
 use tracing::error;
 
 mod cli;
 mod runner;
 mod util;
 
-use self::cli::Cli;
+use self::cli::{Cli, OutputFormat};
 use self::runner::Runner;
+use self::util::{error_hint, error_json, error_kind, is_silent_error};
 
 #[tokio::main]
 async fn main() {
@@ -22,10 +22,12 @@ async fn main() {
           run a CLI interface for managing / installing tools
     */
     let runner = Runner::new();
-    let result = if runner.should_run() {
-        runner.run().await
+    let (result, output) = if runner.should_run() {
+        (runner.run().await, OutputFormat::default())
     } else {
-        Cli::parse().run().await
+        let cli = Cli::parse_with_aliases().await;
+        let output = cli.options.output;
+        (cli.run().await, output)
     };
 
     /*
@@ -35,9 +37,23 @@ async fn main() {
 
         For more information about how tracing is set up, check the
         respective `run` methods for the `Cli` and `Runner` structs.
+
+        Each kind of error exits with its own distinct exit code, and when
+        JSON output is requested, the error is printed as a single JSON
+        object to stderr instead, for scripts and other tools to consume.
     */
     if let Err(e) = result {
-        error!("{e:?}");
-        exit(1);
+        match output {
+            _ if is_silent_error(&e) => {}
+            OutputFormat::Human => {
+                if let Some(hint) = error_hint(&e) {
+                    error!("{e:?}\n\n{hint}");
+                } else {
+                    error!("{e:?}");
+                }
+            }
+            OutputFormat::Json => eprintln!("{}", error_json(&e)),
+        }
+        exit(error_kind(&e).exit_code());
     }
 }
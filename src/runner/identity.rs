@@ -0,0 +1,98 @@
+use std::{
+    env::var,
+    path::{Path, PathBuf},
+};
+
+use console::style;
+
+use rokit::{
+    discovery::DiscoveredTool,
+    tool::{ToolAlias, ToolSpec},
+};
+
+/**
+    The reserved argument that makes a tool link print
+    information about itself, instead of running the tool.
+*/
+const INFO_FLAG: &str = "--rokit-info";
+
+/**
+    The environment variable that, when set, makes tool links
+    print information about themselves, instead of running the tool.
+*/
+const INFO_VAR: &str = "ROKIT_INFO";
+
+/**
+    Checks if the user asked a tool link for information about itself,
+    either by passing `--rokit-info` as the first argument, or by setting
+    the `ROKIT_INFO` environment variable to a truthy value.
+
+    The flag is only reserved as the first argument, so that tools
+    receiving it later on, such as in a list of files, still work.
+*/
+pub fn is_info_requested(first_arg: Option<&str>) -> bool {
+    first_arg == Some(INFO_FLAG)
+        || var(INFO_VAR).is_ok_and(|value| {
+            let value = value.trim();
+            !(value.is_empty() || value == "0" || value.eq_ignore_ascii_case("false"))
+        })
+}
+
+/**
+    Prints information about which tool the link for the given alias
+    resolves to - the tool spec, the manifest it was declared in,
+    and the path to the binary that would be run.
+
+    Other tools declared for the same alias in manifests further away,
+    such as a tool with the same name from a different author, are
+    listed as shadowed, since the link never runs them.
+*/
+pub fn print_tool_info(
+    alias: &ToolAlias,
+    tool: Option<&DiscoveredTool>,
+    tool_path: Option<&Path>,
+    system_path: Option<&Path>,
+    shadowed: &[(ToolSpec, PathBuf)],
+) {
+    let mut lines = vec![
+        format!("Rokit:    {}", env!("CARGO_PKG_VERSION")),
+        format!("Alias:    {}", style(alias.name()).bold().cyan()),
+    ];
+
+    if let Some(tool) = tool {
+        lines.push(format!("Spec:     {}", style(&tool.spec).bold()));
+        lines.push(format!("Author:   {}", tool.spec.author()));
+        lines.push(format!("Manifest: {} ({})", tool.path.display(), tool.kind));
+        if let Some(path) = tool_path {
+            let installed = if path.exists() {
+                style("installed").green()
+            } else {
+                style("not installed").yellow()
+            };
+            lines.push(format!("Binary:   {} ({installed})", path.display()));
+        }
+        if let Some(cwd) = tool.working_dir() {
+            lines.push(format!("Cwd:      {}", cwd.display()));
+        }
+        if tool.options.sandbox {
+            lines.push(format!("Sandbox:  {}", style("enabled").green()));
+        }
+        for (spec, path) in shadowed {
+            lines.push(format!(
+                "Shadows:  {} ({})",
+                style(spec).yellow(),
+                path.display()
+            ));
+        }
+    } else {
+        lines.push(format!(
+            "Spec:     {}",
+            style("not found in any manifest").yellow()
+        ));
+        if let Some(path) = system_path {
+            lines.push(format!("Binary:   {} (system PATH)", path.display()));
+        }
+    }
+
+    println!("{}", lines.join("\n"));
+}
@@ -1,14 +1,18 @@
+use std::{io::ErrorKind as IoErrorKind, path::Path};
+
 use anyhow::Error;
 use console::style;
 
 use rokit::{
-    descriptor::{Arch, Descriptor, OS},
+    descriptor::{Arch, Descriptor, Toolchain, OS},
     tool::ToolAlias,
 };
 
-pub fn inform_user_about_potential_fixes(alias: &ToolAlias, e: &Error) {
+pub fn inform_user_about_potential_fixes(alias: &ToolAlias, path: &Path, e: &Error) {
     if is_likely_rosetta2_error(e) {
         suggest_installing_rosetta(alias);
+    } else if is_likely_missing_glibc_error(path, e) {
+        suggest_using_musl(alias);
     }
 }
 
@@ -37,3 +41,36 @@ fn suggest_installing_rosetta(alias: &ToolAlias) {
         style("softwareupdate --install-rosetta").bold()
     );
 }
+
+fn is_likely_missing_glibc_error(path: &Path, e: &Error) -> bool {
+    // A missing dynamic loader shows up as a "file not found" error,
+    // even though the executable itself exists, so check for both
+    let is_not_found = e
+        .chain()
+        .filter_map(|e| e.downcast_ref::<std::io::Error>())
+        .any(|e| e.kind() == IoErrorKind::NotFound);
+    if !is_not_found || !path.exists() {
+        return false;
+    }
+
+    let requires_glibc = std::fs::read(path)
+        .ok()
+        .and_then(Descriptor::detect_from_executable)
+        .is_some_and(|desc| desc.toolchain() == Some(Toolchain::Gnu));
+    let has_glibc = Toolchain::current_system() == Some(Toolchain::Gnu);
+
+    requires_glibc && !has_glibc
+}
+
+fn suggest_using_musl(alias: &ToolAlias) {
+    tracing::error!(
+        "Rokit failed to run tool {} because it requires glibc, which is not available on this system.\
+        \nThis is common on Alpine Linux and other distributions that use musl instead of glibc.\
+        \n\nReinstalling the tool will prefer a musl build if the tool provides one:\
+        \n\n{}\n\nOtherwise, on Alpine Linux, glibc compatibility can be installed using:\
+        \n\n{}\n",
+        style(alias.to_string()).bold().cyan(),
+        style("rokit install --force").bold(),
+        style("apk add gcompat").bold()
+    );
+}
@@ -0,0 +1,148 @@
+use anyhow::{Context, Result};
+
+use rokit::{
+    descriptor::Descriptor,
+    discovery::{
+        discover_extract_limits, discover_large_download_threshold, discover_strict_platform,
+        DiscoveredTool,
+    },
+    manifests::{find_asset_artifact, RokitManifest},
+    result::ErrorKind,
+    storage::{Home, InstalledToolMetadata},
+    tool::ToolAlias,
+};
+
+use crate::util::{
+    find_most_compatible_artifact, warn_if_emulated, warn_if_large, CliError, CliProgressTracker,
+};
+
+/**
+    Checks if tools with a missing binary should be installed automatically
+    when running them, using the `auto-install` setting of the global manifest.
+*/
+pub async fn should_auto_install(home: &Home, tool: &DiscoveredTool) -> bool {
+    let enabled = RokitManifest::load(home.path())
+        .await
+        .ok()
+        .and_then(|manifest| manifest.get_auto_install())
+        .unwrap_or(true);
+    enabled && home.tool_cache().is_trusted_spec(&tool.spec)
+}
+
+/**
+    Creates the error for a discovered tool that has a missing or empty binary,
+    separating tools that were never installed from tools whose binary was
+    removed after installing, such as by a partially restored CI cache.
+*/
+pub fn missing_tool_error(home: &Home, alias: &ToolAlias, tool: &DiscoveredTool) -> CliError {
+    let path = home.tool_storage().tool_path(&tool.spec);
+    let message = if home.tool_cache().is_installed(&tool.spec) {
+        format!(
+            "Tool '{alias}' ({}) is installed, but its binary is missing or empty.\
+            \nExpected a binary for '{}' at '{}'.\
+            \nThis can happen if the Rokit home directory was only partially restored, \
+            such as from a CI cache.\
+            \nRun 'rokit install' to reinstall all tools for the current project.",
+            tool.spec.id(),
+            tool.spec,
+            path.display(),
+        )
+    } else {
+        format!(
+            "Tool '{alias}' ({}) is not installed.\
+            \nThe {} manifest at '{}' requires '{}'.\
+            \nRun 'rokit install' to install all tools for the current project.",
+            tool.spec.id(),
+            tool.kind,
+            tool.path.display(),
+            tool.spec,
+        )
+    };
+    CliError::new(ErrorKind::ToolNotInstalled, message)
+}
+
+/**
+    Installs a discovered tool from the runner, such as when running
+    a specific version of it, or when its binary has gone missing.
+
+    The tool must already be trusted, since it is installed
+    using a tool link instead of the Rokit CLI.
+*/
+pub async fn install_tool(home: &Home, tool: &DiscoveredTool) -> Result<()> {
+    let spec = &tool.spec;
+    let tool_cache = home.tool_cache();
+    if !tool_cache.is_trusted_spec(spec) {
+        let message = if tool_cache.trusted_versions(spec.id()).is_empty() {
+            format!(
+                "Tool '{}' is not trusted.\
+                \nRun 'rokit trust {}' before running it.",
+                spec.id(),
+                spec.id(),
+            )
+        } else {
+            format!(
+                "Tool '{}' is not trusted for version {}.\
+                \nRun 'rokit trust {spec}' before running it.",
+                spec.id(),
+                spec.version(),
+            )
+        };
+        return Err(CliError::new(ErrorKind::Trust, message).into());
+    }
+
+    let source = home.artifact_source().await?;
+    let strict_platform = discover_strict_platform().await;
+    let extract_limits = discover_extract_limits().await;
+    let tool_storage = home.tool_storage();
+
+    let pt = CliProgressTracker::new_with_message("Installing", 4);
+    let release_artifact = source.get_specific_release(spec).await?;
+    let artifacts = tool.options.exclude_artifacts(&release_artifact.artifacts);
+    pt.task_completed();
+
+    let artifact = match tool.options.asset_for(&Descriptor::current_system()) {
+        Some(asset) => find_asset_artifact(spec, &release_artifact.artifacts, asset)?,
+        None => find_most_compatible_artifact(&artifacts, spec.id(), strict_platform)?,
+    };
+    warn_if_large(&artifact, discover_large_download_threshold().await);
+    let contents = source
+        .download_artifact_contents(&artifact)
+        .await
+        .with_context(|| format!("Failed to download contents for {spec}"))?;
+    tool.options
+        .verify_sha256(spec, &Descriptor::current_system(), &contents)?;
+    let metadata = InstalledToolMetadata::new(&artifact, &contents);
+    pt.task_completed();
+
+    // NOTE: Options for extracting the tool are taken from the manifest that
+    // declares the tool, since other versions are likely packaged the same way
+    let file_pattern = tool.options.file.as_deref();
+    if tool.options.extract_all {
+        let (extracted, files) = artifact
+            .extract_all_contents_matching(contents, file_pattern, extract_limits)
+            .await
+            .with_context(|| format!("Failed to extract contents for {spec}"))?;
+        pt.task_completed();
+        warn_if_emulated(&artifact, &artifacts, &extracted);
+        tool_storage
+            .replace_tool_contents_with_files(spec, extracted, &files)
+            .await?;
+    } else {
+        let extracted = artifact
+            .extract_contents_matching(contents, file_pattern, extract_limits)
+            .await
+            .with_context(|| format!("Failed to extract contents for {spec}"))?;
+        pt.task_completed();
+        warn_if_emulated(&artifact, &artifacts, &extracted);
+        tool_storage.replace_tool_contents(spec, extracted).await?;
+    }
+    tool_storage.save_tool_metadata(spec, metadata).await?;
+    pt.task_completed();
+
+    let _ = tool_cache.add_installed(spec.clone());
+    home.save().await?;
+
+    pt.finish_with_message(format!("Installed {spec} {}", pt.formatted_elapsed()));
+
+    Ok(())
+}
@@ -1,20 +1,35 @@
 use std::{env::args, process::exit, str::FromStr};
 
-use anyhow::{bail, Error, Result};
-use tracing::level_filters::LevelFilter;
+use anyhow::{Context, Error, Result};
+use tokio::time::Instant;
+use tracing::{debug, level_filters::LevelFilter, warn};
 
 use rokit::{
-    discovery::{discover_non_rokit_tool, discover_tool_spec},
+    descriptor::Descriptor,
+    discovery::{
+        discover_fallthrough_in, discover_non_rokit_tool, discover_tool_in,
+        trace_tool_discovery_in, DiscoveredTool,
+    },
+    manifests::Fallthrough,
+    result::ErrorKind,
     storage::Home,
-    system::{current_exe_name, run_interruptible},
+    system::{current_dir, current_exe_name, run_interruptible_with, Sandbox},
     tool::ToolAlias,
 };
 
-use crate::util::init_tracing;
+use crate::util::{init_output, init_tracing, CliError, LogFormat, ProgressMode};
 
+mod identity;
 mod info;
+mod install;
+mod telemetry;
+mod versioned;
 
+use self::identity::{is_info_requested, print_tool_info};
 use self::info::inform_user_about_potential_fixes;
+pub use self::install::{install_tool, missing_tool_error, should_auto_install};
+use self::telemetry::record_invocation;
+use self::versioned::{split_versioned_name, take_version_arg};
 
 #[derive(Debug, Clone)]
 pub struct Runner {
@@ -35,34 +50,205 @@ impl Runner {
     pub async fn run(&self) -> Result<()> {
         // Always log at INFO level when running a managed program
         // unless the user has explicitly set a different level
-        // using the RUST_LOG environment variable.
-        init_tracing(LevelFilter::INFO);
+        // using the RUST_LOG environment variable. Colors may
+        // still be disabled using the NO_COLOR environment variable.
+        init_output(false, false, false, ProgressMode::default());
+        init_tracing(LevelFilter::INFO, false, LogFormat::default());
 
-        let alias = ToolAlias::from_str(&self.exe_name)?;
+        // NOTE: Only the OS and architecture are checked here, since detecting
+        // the preferred toolchain may read from disk, and is only needed when
+        // choosing artifacts to install
+        Descriptor::check_current_system().context("Rokit does not support this system")?;
 
+        // A specific version of a tool may be requested using a suffixed
+        // link name, such as `rojo@7.4.4`, or a leading `+7.4.4` argument
+        let mut tool_args = args().skip(1).collect::<Vec<_>>();
+        let (alias, requested_version) = match split_versioned_name(&self.exe_name) {
+            Some((alias, version)) => (ToolAlias::from_str(alias)?, Some(version)),
+            None => (
+                ToolAlias::from_str(&self.exe_name)?,
+                take_version_arg(&mut tool_args),
+            ),
+        };
+
+        // NOTE: Tools always resolve relative to the directory the tool
+        // is run in, and the same directory is used for all discovery
         let home = Home::load_from_env().await?;
-        let spec = discover_tool_spec(&alias, false, false).await;
-
-        let program_args = args().skip(1).collect::<Vec<_>>();
-        let program_path = match spec {
-            // TODO: Prompt for trust and install tool if not already installed
-            Some(spec) => home.tool_storage().tool_path(&spec),
-            // FUTURE: Maybe we should add some kind of "fall-through" setting in
-            // Rokit manifests instead of always falling through to non-rokit tools?
-            None => match discover_non_rokit_tool(&home, &alias).await {
-                Some(path) => path,
-                None => bail!(
-                    "Failed to find tool '{alias}' in any project manifest file.\
-                    \nAdd the tool to a project using 'rokit add' before running it."
-                ),
-            },
+        let cwd = current_dir().await;
+        let mut tool = discover_tool_in(&cwd, &alias, false, false).await;
+
+        if let Some(version) = &requested_version {
+            let Some(tool) = tool.as_mut() else {
+                return Err(CliError::new(
+                    ErrorKind::NotFound,
+                    format!(
+                        "Failed to find tool '{alias}' in any project manifest file.\
+                        \nA specific version can only be run for tools that have been added."
+                    ),
+                )
+                .into());
+            };
+            debug!(spec = %tool.spec, %version, "Using requested tool version");
+            tool.spec = (tool.spec.id().clone(), version.clone()).into();
+            // NOTE: Checksums in manifests are for the declared version only
+            tool.options.sha256.clear();
+        }
+
+        // Tool links may be asked which tool they resolve to, which
+        // should never run the tool itself, nor record any usage of it
+        if is_info_requested(tool_args.first().map(String::as_str)) {
+            let tool_path = tool
+                .as_ref()
+                .map(|tool| home.tool_storage().tool_path(&tool.spec));
+            let system_path = if tool.is_none() {
+                discover_non_rokit_tool(&home, &alias).await
+            } else {
+                None
+            };
+            // NOTE: Tools with the same name from different authors may be
+            // declared for the same alias, and are listed to tell them apart
+            let shadowed = match &tool {
+                Some(tool) => trace_tool_discovery_in(&cwd, &alias, false, false)
+                    .await
+                    .into_iter()
+                    .filter_map(|consulted| Some((consulted.spec?, consulted.path)))
+                    .filter(|(spec, _)| spec.id() != tool.spec.id())
+                    .collect(),
+                None => Vec::new(),
+            };
+            print_tool_info(
+                &alias,
+                tool.as_ref(),
+                tool_path.as_deref(),
+                system_path.as_deref(),
+                &shadowed,
+            );
+            return Ok(());
+        }
+
+        // Requested versions are always installed on demand, since they are not
+        // in any manifest that `rokit install` would use - other tools are only
+        // installed automatically if trusted, and if not disabled by the user
+        if let Some(tool) = &tool {
+            if !home.tool_storage().has_tool_binary(&tool.spec).await {
+                if requested_version.is_some() || should_auto_install(&home, tool).await {
+                    debug!(spec = %tool.spec, "Installing tool with missing binary");
+                    install_tool(&home, tool).await?;
+                } else {
+                    return Err(missing_tool_error(&home, &alias, tool).into());
+                }
+            }
+        }
+
+        // Record usage of the tool in the background while it runs,
+        // so that it does not delay starting the program at all
+        let usage_task = tool.as_ref().map(|tool| {
+            let home = home.clone();
+            let spec = tool.spec.clone();
+            tokio::spawn(async move { home.record_tool_usage(&spec).await })
+        });
+
+        // Tools may be configured to run with extra environment variables,
+        // in a specific directory, and with some arguments always prepended
+        let program_env = tool
+            .as_ref()
+            .map(|tool| tool.options.env.clone())
+            .unwrap_or_default();
+        let program_cwd = tool.as_ref().and_then(DiscoveredTool::working_dir);
+        let program_args = tool
+            .as_ref()
+            .map(|tool| tool.options.prepend_args.clone())
+            .unwrap_or_default()
+            .into_iter()
+            .chain(tool_args)
+            .collect::<Vec<_>>();
+        let program_spec = tool.as_ref().map(|tool| tool.spec.clone());
+
+        // Less trusted tools may opt in to running in a sandbox, which
+        // must stay alive until the tool has finished running
+        let sandbox = if tool.as_ref().is_some_and(|tool| tool.options.sandbox) {
+            let sandbox = Sandbox::new().context("Failed to create sandbox for tool")?;
+            debug!(home = %sandbox.home_dir().display(), "Running tool in sandbox");
+            Some(sandbox)
+        } else {
+            None
+        };
+        let program_path = if let Some(tool) = tool {
+            debug!(
+                spec = %tool.spec,
+                kind = %tool.kind,
+                manifest = %tool.path.display(),
+                "Resolved tool from manifest"
+            );
+            home.tool_storage().tool_path(&tool.spec)
+        } else {
+            // Fall through to non-rokit tools, depending on the manifest settings
+            let fallthrough = discover_fallthrough_in(&cwd).await;
+            match (fallthrough, discover_non_rokit_tool(&home, &alias).await) {
+                (Fallthrough::Allow, Some(path)) => path,
+                (Fallthrough::Warn, Some(path)) => {
+                    warn!(
+                        "Tool '{alias}' was not found in any project manifest file.\
+                        \nRunning '{}' from the system PATH instead.\
+                        \nAdd `fallthrough = \"allow\"` to the [settings] of a Rokit \
+                        manifest to silence this warning, or use \"deny\" to disallow it.",
+                        path.display()
+                    );
+                    path
+                }
+                (Fallthrough::Deny, Some(_)) => {
+                    return Err(CliError::new(
+                        ErrorKind::NotFound,
+                        format!(
+                            "Failed to find tool '{alias}' in any project manifest file.\
+                            \nThe tool exists in the system PATH, but running it through Rokit \
+                            is disabled by the `fallthrough = \"deny\"` manifest setting."
+                        ),
+                    )
+                    .into())
+                }
+                (_, None) => {
+                    return Err(CliError::new(
+                        ErrorKind::NotFound,
+                        format!(
+                            "Failed to find tool '{alias}' in any project manifest file.\
+                            \nAdd the tool to a project using 'rokit add' before running it."
+                        ),
+                    )
+                    .into())
+                }
+            }
         };
 
-        let code = run_interruptible(&program_path, &program_args)
-            .await
-            .map_err(Error::from)
-            .inspect_err(|e| inform_user_about_potential_fixes(&alias, e))?;
+        let start = Instant::now();
+        let code = run_interruptible_with(
+            &program_path,
+            &program_args,
+            &program_env,
+            program_cwd.as_deref(),
+            sandbox.as_ref(),
+        )
+        .await
+        .map_err(Error::from)
+        .inspect_err(|e| inform_user_about_potential_fixes(&alias, &program_path, e))?;
+
+        // NOTE: Usage statistics are not critical, so failing
+        // to record them should never fail the program itself
+        if let Some(task) = usage_task {
+            if let Ok(Err(e)) = task.await {
+                debug!("Failed to record tool usage: {e}");
+            }
+        }
+
+        // Invocations are only recorded for tools managed by Rokit,
+        // and only if telemetry has been enabled in the global manifest
+        if let Some(spec) = program_spec {
+            record_invocation(&home, &alias, &spec, start.elapsed(), code).await;
+        }
 
+        // NOTE: Exiting skips all destructors, so the sandbox
+        // must be removed explicitly before exiting, if any
+        drop(sandbox);
         exit(code);
     }
 }
@@ -0,0 +1,109 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use serde::Serialize;
+use tokio::{fs::OpenOptions, io::AsyncWriteExt, time::timeout};
+use tracing::debug;
+
+use rokit::{
+    manifests::{RokitManifest, TelemetrySettings},
+    storage::Home,
+    tool::{ToolAlias, ToolSpec},
+};
+
+/**
+    The maximum amount of time to spend recording an invocation,
+    so that a slow endpoint never noticeably delays exiting.
+*/
+const RECORD_TIMEOUT: Duration = Duration::from_secs(2);
+
+/**
+    A single invocation of a managed tool, as recorded by the runner.
+*/
+#[derive(Debug, Clone, Serialize)]
+struct InvocationRecord {
+    alias: String,
+    tool: String,
+    version: String,
+    timestamp: u64,
+    duration_ms: u64,
+    exit_code: i32,
+}
+
+impl InvocationRecord {
+    fn new(alias: &ToolAlias, spec: &ToolSpec, duration: Duration, exit_code: i32) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        Self {
+            alias: alias.to_string(),
+            tool: spec.id().to_string(),
+            version: spec.version().to_string(),
+            timestamp,
+            duration_ms: u64::try_from(duration.as_millis()).unwrap_or(u64::MAX),
+            exit_code,
+        }
+    }
+}
+
+/**
+    Records an invocation of a managed tool to the sinks configured
+    in the `[settings.telemetry]` table of the global manifest.
+
+    Does nothing if telemetry is not configured, which is the default.
+    Recording is not critical, so any errors are only logged.
+*/
+pub async fn record_invocation(
+    home: &Home,
+    alias: &ToolAlias,
+    spec: &ToolSpec,
+    duration: Duration,
+    exit_code: i32,
+) {
+    let Some(settings) = RokitManifest::load(home.path())
+        .await
+        .ok()
+        .and_then(|manifest| manifest.get_telemetry())
+    else {
+        return;
+    };
+
+    let record = InvocationRecord::new(alias, spec, duration, exit_code);
+    match timeout(RECORD_TIMEOUT, record_to_sinks(home, &settings, &record)).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => debug!("Failed to record tool invocation: {e}"),
+        Err(_) => debug!("Timed out while recording tool invocation"),
+    }
+}
+
+async fn record_to_sinks(
+    home: &Home,
+    settings: &TelemetrySettings,
+    record: &InvocationRecord,
+) -> Result<()> {
+    if let Some(file) = &settings.file {
+        let path = home.path().join(file);
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        // NOTE: Appending a single short line is effectively atomic,
+        // so concurrent tool invocations will not corrupt the file
+        let mut handle = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await?;
+        handle.write_all(&line).await?;
+    }
+
+    if let Some(endpoint) = &settings.endpoint {
+        reqwest::Client::new()
+            .post(endpoint.clone())
+            .json(record)
+            .send()
+            .await?
+            .error_for_status()?;
+    }
+
+    Ok(())
+}
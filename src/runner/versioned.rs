@@ -0,0 +1,32 @@
+use semver::Version;
+
+/**
+    Splits a tool link name with a version suffix, such as `rojo@7.4.4`
+    or `rojo+7.4.4`, into the tool alias and the requested version.
+
+    Returns `None` if the name has no suffix, or if the suffix is not a valid
+    version, in which case the name should be used as the alias as-is.
+*/
+pub fn split_versioned_name(exe_name: &str) -> Option<(&str, Version)> {
+    let (alias, version) = exe_name.split_once(['@', '+'])?;
+    let version = version.trim_start_matches('v').parse().ok()?;
+    Some((alias, version))
+}
+
+/**
+    Takes a leading `+<version>` argument, such as in `rojo +7.4.4 build`,
+    from the given tool arguments, and returns the requested version.
+
+    Arguments are left untouched if the first one is not a valid version,
+    so that tools which accept arguments starting with `+` still work.
+*/
+pub fn take_version_arg(args: &mut Vec<String>) -> Option<Version> {
+    let version = args
+        .first()?
+        .strip_prefix('+')?
+        .trim_start_matches('v')
+        .parse()
+        .ok()?;
+    args.remove(0);
+    Some(version)
+}
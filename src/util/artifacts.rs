@@ -1,25 +1,53 @@
-use anyhow::{Context, Result};
+use std::fmt::Write;
+
+use anyhow::Result;
+use tracing::warn;
 
 use rokit::{
-    descriptor::{Arch, OS},
-    sources::Artifact,
+    descriptor::{Arch, Descriptor, OS},
+    result::ErrorKind,
+    sources::{Artifact, ArtifactProvider},
     tool::ToolId,
 };
 
-pub fn find_most_compatible_artifact(artifacts: &[Artifact], tool_id: &ToolId) -> Result<Artifact> {
-    let mut artifact_opt = Artifact::sort_by_system_compatibility(artifacts)
-        .first()
-        .cloned();
+use super::{format_bytes, CliError};
+
+/**
+    Finds the artifact that is most compatible with the current system.
+
+    If no artifact is fully compatible, a partially compatible artifact may
+    be used as a fallback, unless `strict_platform` is set - see the
+    `strict-platform` setting in Rokit manifests for more information.
+*/
+pub fn find_most_compatible_artifact(
+    artifacts: &[Artifact],
+    tool_id: &ToolId,
+    strict_platform: bool,
+) -> Result<Artifact> {
+    // Repository archives are the only artifact for their commit, and contain
+    // files for all systems, so they can not be checked for compatibility here
+    if tool_id.provider() == ArtifactProvider::GitHubBranch {
+        if let Some(artifact) = artifacts.first() {
+            return Ok(artifact.clone());
+        }
+    }
+
+    if let Some(artifact) = Artifact::sort_by_system_compatibility(artifacts).first() {
+        return Ok(artifact.clone());
+    }
 
-    if artifact_opt.is_none() {
-        if let Some(artifact) = Artifact::find_partially_compatible_fallback(artifacts) {
+    let fallback = Artifact::find_partially_compatible_fallback(artifacts);
+    match &fallback {
+        Some(artifact) if !strict_platform => {
             tracing::debug!(
                 %tool_id,
                 name = %artifact.name.as_deref().unwrap_or("N/A"),
                 "found fallback artifact for tool",
             );
-            artifact_opt.replace(artifact);
-        } else {
+            return Ok(artifact.clone());
+        }
+        Some(_) => {}
+        None => {
             // If we failed to find an artifact compatible with the current system,
             // we may be able to give additional information to Rokit's users, or tool
             // maintainers who want to be Rokit-compatible, by examining the artifacts
@@ -38,5 +66,104 @@ pub fn find_most_compatible_artifact(artifacts: &[Artifact], tool_id: &ToolId) -
 
     // If we did not find a compatible artifact, either directly
     // or through a fallback mechanism, this should be a hard error
-    artifact_opt.with_context(|| format!("No compatible artifact found for {tool_id}"))
+    let current = Descriptor::current_system();
+    let mut message = format!(
+        "This {} system is not supported by the release of {tool_id}.",
+        current.platform_name()
+    );
+    if let Some(name) = fallback.as_ref().and_then(|a| a.name.as_deref()) {
+        let _ = write!(
+            message,
+            "\nThe partially compatible artifact '{name}' was not used, \
+            since the 'strict-platform' setting is enabled."
+        );
+    } else if !current.can_run_other_architectures() && has_artifacts_for_os(artifacts, current) {
+        let _ = write!(
+            message,
+            "\nArtifacts for other architectures of {} can not run on this system.",
+            current.os().as_str()
+        );
+    }
+    message.push_str(&format_artifacts_by_platform(artifacts));
+    Err(CliError::new(ErrorKind::IncompatiblePlatform, message).into())
+}
+
+fn has_artifacts_for_os(artifacts: &[Artifact], current: Descriptor) -> bool {
+    artifacts
+        .iter()
+        .filter_map(|artifact| Descriptor::detect(artifact.name.as_deref()?))
+        .any(|desc| desc.os() == current.os())
+}
+
+/*
+    Lists the names of the given artifacts grouped by the platform
+    they were built for, or an empty string if there are none.
+*/
+fn format_artifacts_by_platform(artifacts: &[Artifact]) -> String {
+    let platforms = Artifact::group_by_platform(artifacts);
+    if platforms.is_empty() {
+        return String::new();
+    }
+
+    let mut list = String::from("\nThe release only has artifacts for these platforms:");
+    for (platform, names) in platforms {
+        let _ = write!(list, "\n  {platform}: {}", names.join(", "));
+    }
+    list
+}
+
+/**
+    Warns the user if the given extracted binary can only run on the current system
+    using emulation, such as x64 binaries on Apple Silicon (Rosetta) or x86 binaries
+    on 64-bit Windows (WOW64), and suggests a native artifact if the release has one.
+*/
+pub fn warn_if_emulated(artifact: &Artifact, artifacts: &[Artifact], binary_contents: &[u8]) {
+    let current = Descriptor::current_system();
+    let Some(binary) = Descriptor::detect_from_executable(binary_contents) else {
+        return;
+    };
+    if current.is_natively_compatible_with(&binary) || !current.is_compatible_with(&binary) {
+        return;
+    }
+
+    let (Some(current_arch), Some(binary_arch)) = (current.arch(), binary.arch()) else {
+        return;
+    };
+    let suggestion = match artifact
+        .find_native_alternative(artifacts)
+        .and_then(|native| native.name.as_deref())
+    {
+        Some(name) => format!(
+            "\nThe release also contains '{name}', which is named as a native build \
+            for this system - consider letting the tool maintainers know about this."
+        ),
+        None => String::new(),
+    };
+    warn!(
+        "Tool {} was built for {binary_arch:?}, and will run using emulation on this \
+        {current_arch:?} system, which may be slower.{suggestion}",
+        artifact.tool_spec,
+    );
+}
+
+/**
+    Warns the user if the given artifact is larger than the given threshold,
+    in bytes, before it is downloaded - unusually large artifacts are often
+    the wrong asset, such as an archive of debug symbols, instead of the tool.
+*/
+pub fn warn_if_large(artifact: &Artifact, threshold: Option<u64>) {
+    let (Some(size), Some(threshold)) = (artifact.size, threshold) else {
+        return;
+    };
+    if size <= threshold {
+        return;
+    }
+
+    warn!(
+        "The artifact '{}' for {} is unusually large ({}), and may not be the right \
+        asset for this tool - the 'large-download-threshold' setting changes this limit.",
+        artifact.name.as_deref().unwrap_or("N/A"),
+        artifact.tool_spec,
+        format_bytes(size),
+    );
 }
@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+/**
+    Formats a number of bytes using the largest fitting unit, such as `4.2 MB`.
+*/
+#[allow(clippy::cast_precision_loss)]
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = UNITS[0];
+    for next in &UNITS[1..] {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = next;
+    }
+    format!("{size:.1} {unit}")
+}
+
+/**
+    Formats the average speed of transferring a number of bytes
+    in the given duration, such as `4.2 MB/s`.
+*/
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+pub fn format_speed(bytes: u64, elapsed: Duration) -> String {
+    let secs = elapsed.as_secs_f64().max(0.001);
+    let per_sec = (bytes as f64 / secs) as u64;
+    format!("{}/s", format_bytes(per_sec))
+}
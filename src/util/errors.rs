@@ -0,0 +1,148 @@
+use std::{fmt, io::Error as IoError, time::SystemTime};
+
+use anyhow::Error;
+use serde_json::{json, Value as JsonValue};
+
+use rokit::{
+    result::{ErrorKind, RokitError},
+    sources::{github::GithubError, DownloadError, ExtractError},
+};
+
+/**
+    An error originating in the Rokit CLI itself, with an explicit [`ErrorKind`].
+
+    Errors from the Rokit library already know their own kind, so
+    this is only needed for failures in CLI logic, such as trust.
+*/
+#[derive(Debug)]
+pub struct CliError {
+    kind: ErrorKind,
+    message: String,
+    silent: bool,
+}
+
+impl CliError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            silent: false,
+        }
+    }
+
+    /**
+        Creates an error that should only set the exit code, and not be printed,
+        for commands that have already reported the failure in their output.
+    */
+    pub fn silent(kind: ErrorKind) -> Self {
+        Self {
+            kind,
+            message: String::new(),
+            silent: true,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.message.fmt(f)
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/**
+    Finds the kind of the given error, by searching through its full chain
+    of sources and picking the first error that has a known kind.
+
+    Returns [`ErrorKind::Other`] if no error in the chain has a known kind.
+*/
+pub fn error_kind(error: &Error) -> ErrorKind {
+    error
+        .chain()
+        .find_map(|e| {
+            if let Some(e) = e.downcast_ref::<CliError>() {
+                Some(e.kind)
+            } else if let Some(e) = e.downcast_ref::<RokitError>() {
+                Some(e.kind())
+            } else if let Some(e) = e.downcast_ref::<GithubError>() {
+                Some(e.kind())
+            } else if let Some(e) = e.downcast_ref::<ExtractError>() {
+                Some(e.kind())
+            } else if let Some(e) = e.downcast_ref::<DownloadError>() {
+                Some(e.kind())
+            } else if e.is::<IoError>() {
+                Some(ErrorKind::Io)
+            } else {
+                None
+            }
+        })
+        .unwrap_or(ErrorKind::Other)
+}
+
+/**
+    Finds a hint for resolving the given error, for errors that users
+    can resolve themselves, such as exceeded GitHub API rate limits.
+*/
+pub fn error_hint(error: &Error) -> Option<String> {
+    let github_error = error.chain().find_map(|e| {
+        if let Some(e) = e.downcast_ref::<GithubError>() {
+            Some(e)
+        } else if let Some(RokitError::GitHub(e)) = e.downcast_ref::<RokitError>() {
+            Some(e.as_ref())
+        } else {
+            None
+        }
+    })?;
+
+    match github_error {
+        GithubError::RateLimited { reset_at } => {
+            let resets = reset_at
+                .and_then(|time| time.duration_since(SystemTime::now()).ok())
+                .map(|remaining| match remaining.as_secs().div_ceil(60) {
+                    0 | 1 => " - it resets in about a minute".to_string(),
+                    minutes => format!(" - it resets in about {minutes} minutes"),
+                })
+                .unwrap_or_default();
+            Some(format!(
+                "GitHub limits how many requests can be made without authentication{resets}.\
+                \nRun `rokit authenticate github --token <token>` to get a higher limit."
+            ))
+        }
+        GithubError::Unauthorized => Some(
+            "The GitHub access token used by Rokit was rejected.\
+            \nRun `rokit authenticate github --token <token>` to replace it, \
+            or `rokit authenticate github --remove` to remove it."
+                .to_string(),
+        ),
+        GithubError::WorkflowRequiresAuth => Some(
+            "GitHub only allows downloading workflow artifacts with an access token.\
+            \nRun `rokit authenticate github --token <token>` to add one."
+                .to_string(),
+        ),
+        _ => None,
+    }
+}
+
+/**
+    Checks if the given error should only set the exit code, and not be printed.
+*/
+pub fn is_silent_error(error: &Error) -> bool {
+    error.downcast_ref::<CliError>().is_some_and(|e| e.silent)
+}
+
+/**
+    Creates a machine-readable JSON representation of the given error.
+*/
+pub fn error_json(error: &Error) -> JsonValue {
+    let kind = error_kind(error);
+    json!({
+        "error": {
+            "kind": kind.as_str(),
+            "code": kind.exit_code(),
+            "message": error.to_string(),
+            "hint": error_hint(error),
+            "causes": error.chain().skip(1).map(ToString::to_string).collect::<Vec<_>>(),
+        }
+    })
+}
@@ -2,9 +2,7 @@ use std::str::FromStr;
 
 use serde_with::DeserializeFromStr;
 
-use rokit::tool::{ToolAlias, ToolId, ToolSpec};
-
-use super::constants::get_known_tool;
+use rokit::tool::{get_known_tool, ToolAlias, ToolId, ToolSpec};
 
 /**
     A tool identifier *or* specification, which includes
@@ -0,0 +1,35 @@
+use std::io::{stdout, BufWriter};
+
+use anyhow::{Context, Result};
+use pulldown_cmark::{Options, Parser as MarkdownParser};
+use pulldown_cmark_mdcat::{
+    resources::FileResourceHandler, Environment, Settings, TerminalProgram, TerminalSize, Theme,
+};
+use syntect::parsing::SyntaxSet;
+
+/**
+    Renders markdown, such as a changelog, to the terminal.
+
+    # Errors
+
+    - If the terminal size could not be detected.
+    - If the markdown could not be written to stdout.
+*/
+pub fn render_markdown(markdown: &str) -> Result<()> {
+    pulldown_cmark_mdcat::push_tty(
+        &Settings {
+            terminal_capabilities: TerminalProgram::detect().capabilities(),
+            terminal_size: TerminalSize::detect().context("Failed to detect terminal size")?,
+            syntax_set: &SyntaxSet::load_defaults_newlines(),
+            theme: Theme::default(),
+        },
+        &Environment::for_local_directory(&tempfile::tempdir()?.path())?,
+        &FileResourceHandler::new(104_857_600), // TODO: Maybe make this be a DispatchingResourceHandler?
+        &mut BufWriter::new(stdout()),
+        MarkdownParser::new_ext(
+            markdown,
+            Options::ENABLE_FOOTNOTES | Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH,
+        ),
+    )?;
+    Ok(())
+}
@@ -1,14 +1,30 @@
 mod alias_or_id_or_spec;
 mod artifacts;
-mod constants;
+mod bytes;
+mod errors;
 mod id_or_spec;
+mod markdown;
+mod output;
+mod paths;
 mod progress;
 mod prompts;
+mod symbols;
 mod tracing;
+mod updates;
 
 pub use self::alias_or_id_or_spec::ToolAliasOrIdOrSpec;
-pub use self::artifacts::find_most_compatible_artifact;
+pub use self::artifacts::{find_most_compatible_artifact, warn_if_emulated, warn_if_large};
+pub use self::bytes::{format_bytes, format_speed};
+pub use self::errors::{error_hint, error_json, error_kind, is_silent_error, CliError};
 pub use self::id_or_spec::ToolIdOrSpec;
+pub use self::markdown::render_markdown;
+pub use self::output::{init as init_output, ProgressMode};
+pub use self::paths::display_path;
 pub use self::progress::CliProgressTracker;
-pub use self::prompts::{prompt_for_trust, prompt_for_trust_specs};
-pub use self::tracing::init as init_tracing;
+pub use self::prompts::{
+    prompt_for_aftman_migration, prompt_for_search_result, prompt_for_trust,
+    prompt_for_trust_specs, prompt_for_update_selection,
+};
+pub use self::symbols::{prompt_theme, Symbol};
+pub use self::tracing::{init as init_tracing, LogFormat};
+pub use self::updates::{check_for_updates, rokit_tool_id, should_check_for_updates};
@@ -0,0 +1,120 @@
+use std::{
+    env::var_os,
+    sync::atomic::{AtomicBool, AtomicU8, Ordering},
+};
+
+use clap::ValueEnum;
+use console::Term;
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+static UNICODE: AtomicBool = AtomicBool::new(true);
+static PROGRESS: AtomicU8 = AtomicU8::new(ProgressMode::Auto as u8);
+
+/**
+    How progress should be displayed by the Rokit CLI.
+*/
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[repr(u8)]
+pub enum ProgressMode {
+    /// Progress bars in terminals, and plain status lines otherwise, such as in CI logs.
+    #[default]
+    Auto,
+    /// Progress bars, even if the output is not a terminal.
+    Always,
+    /// No progress bars or status lines, only final messages.
+    Never,
+}
+
+/**
+    How progress is actually displayed, after resolving
+    the [`ProgressMode`] for the current environment.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressDisplay {
+    Bar,
+    Lines,
+    FinalOnly,
+    Hidden,
+}
+
+/**
+    Configures output for the current process - this must be called
+    before any output is printed, and before tracing is initialized.
+
+    - In quiet mode, progress bars and status messages are hidden,
+      and only warnings and errors are logged.
+    - Progress is displayed as plain status lines instead of progress bars
+      when not writing to a terminal, unless overridden using the progress mode.
+    - Colors and other styling are disabled if requested, or if the
+      `NO_COLOR` environment variable is set to a non-empty value.
+    - Unicode symbols are replaced with ASCII if requested, if the
+      `ROKIT_ASCII` environment variable is set to a non-empty value,
+      or if the terminal is not known to support unicode.
+*/
+pub fn init(quiet: bool, no_color: bool, ascii: bool, progress: ProgressMode) {
+    QUIET.store(quiet, Ordering::Relaxed);
+    PROGRESS.store(progress as u8, Ordering::Relaxed);
+
+    let ascii = ascii
+        || var_os("ROKIT_ASCII").is_some_and(|value| !value.is_empty())
+        || !terminal_supports_unicode();
+    UNICODE.store(!ascii, Ordering::Relaxed);
+
+    let no_color = no_color || var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    if no_color {
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+    }
+}
+
+/**
+    Returns `true` if unicode symbols may be used in output.
+*/
+pub fn is_unicode() -> bool {
+    UNICODE.load(Ordering::Relaxed)
+}
+
+/*
+    Checks if the terminal that output is written to can display unicode.
+
+    Output that is not written to a terminal, such as CI logs and files, is
+    assumed to be UTF-8. On Windows, the legacy console host and its code pages,
+    such as cp437, can not display unicode, so only terminals known to support
+    it are trusted - Windows Terminal, VS Code and other terminals that set
+    `TERM_PROGRAM`, ConEmu, and MSYS / Cygwin terminals that set `TERM`.
+*/
+fn terminal_supports_unicode() -> bool {
+    let term = Term::stderr();
+    if !term.is_term() {
+        return true;
+    }
+    if cfg!(windows) {
+        ["WT_SESSION", "TERM_PROGRAM", "ConEmuANSI", "TERM"]
+            .iter()
+            .any(|name| var_os(name).is_some())
+    } else {
+        term.features().wants_emoji()
+    }
+}
+
+/**
+    Returns `true` if progress bars and status messages should be hidden.
+*/
+fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/**
+    Returns how progress should currently be displayed.
+*/
+pub fn progress_display() -> ProgressDisplay {
+    if is_quiet() {
+        return ProgressDisplay::Hidden;
+    }
+    match PROGRESS.load(Ordering::Relaxed) {
+        p if p == ProgressMode::Always as u8 => ProgressDisplay::Bar,
+        p if p == ProgressMode::Never as u8 => ProgressDisplay::FinalOnly,
+        _ if Term::stderr().is_term() => ProgressDisplay::Bar,
+        _ => ProgressDisplay::Lines,
+    }
+}
@@ -0,0 +1,15 @@
+use std::path::Path;
+
+/**
+    Formats a path for display to the user, replacing the
+    user's home directory with `~` if the path is within it.
+*/
+pub fn display_path(path: impl AsRef<Path>) -> String {
+    let path = path.as_ref();
+    if let Some(user_home) = dirs::home_dir() {
+        if let Ok(path) = path.strip_prefix(user_home) {
+            return format!("~/{}", dunce::simplified(path).display());
+        }
+    }
+    dunce::simplified(path).display().to_string()
+}
@@ -1,10 +1,25 @@
-use std::{fmt::Write, time::Duration};
+use std::{
+    fmt::Write,
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 
-use console::style;
-use indicatif::{ProgressBar, ProgressState, ProgressStyle};
+use console::{style, Term};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressState, ProgressStyle};
 
-const PROGRESS_BAR_CHARACTERS: &str = "█▉▊▋▌▍▎▏ ";
-const PROGRESS_BAR_TICKERS: &str = "⠙⠹⠸⠼⠴⠦⠧⠇⠏ ";
+use super::{
+    output::{progress_display, ProgressDisplay},
+    symbols::{progress_bar_chars, progress_tick_chars, Symbol},
+};
+
+/**
+    The shortest time between plain status lines, which are
+    used instead of progress bars when not writing to a terminal.
+*/
+const STATUS_LINE_INTERVAL: Duration = Duration::from_secs(2);
+
+const STEADY_TICK_INTERVAL: Duration = Duration::from_millis(50);
 
 const PROGRESS_TEMPLATE_DEFAULT: &str =
     "{spinner:.bold.cyan} {msg:11.bold.cyan} [{bar:32.bold}] {current_task:>2} / {total_tasks:2}";
@@ -14,10 +29,17 @@ const PROGRESS_TEMPLATE_DEFAULT: &str =
 
     Tracks subtasks (partial tasks) and a more granular progress
     bar while still only displaying the main task count to the user.
+
+    When not writing to a terminal, such as in CI logs, periodic plain
+    status lines are printed instead of the progress bar. In quiet mode,
+    the progress bar and all of its messages are hidden.
 */
 pub struct CliProgressTracker {
     inner: ProgressBar,
+    display: ProgressDisplay,
+    num_tasks: usize,
     num_subtasks: Option<usize>,
+    last_status: Mutex<Instant>,
 }
 
 impl CliProgressTracker {
@@ -31,10 +53,7 @@ impl CliProgressTracker {
         num_tasks: usize,
         subtasks_per_task: usize,
     ) -> Self {
-        Self {
-            inner: new_progress_bar(message, num_tasks, subtasks_per_task),
-            num_subtasks: Some(subtasks_per_task),
-        }
+        Self::new_inner(message, num_tasks, Some(subtasks_per_task))
     }
 
     /**
@@ -43,9 +62,25 @@ impl CliProgressTracker {
         Does not have any subtasks.
     */
     pub fn new_with_message(message: impl Into<String>, num_tasks: usize) -> Self {
+        Self::new_inner(message, num_tasks, None)
+    }
+
+    fn new_inner(
+        message: impl Into<String>,
+        num_tasks: usize,
+        num_subtasks: Option<usize>,
+    ) -> Self {
+        let display = progress_display();
+        let message = message.into();
+        if display == ProgressDisplay::Lines {
+            eprintln!("{message} 0 / {num_tasks}");
+        }
         Self {
-            inner: new_progress_bar(message, num_tasks, 1),
-            num_subtasks: None,
+            inner: new_progress_bar(message, display, num_tasks, num_subtasks.unwrap_or(1)),
+            display,
+            num_tasks,
+            num_subtasks,
+            last_status: Mutex::new(Instant::now()),
         }
     }
 
@@ -57,6 +92,7 @@ impl CliProgressTracker {
             Some(n) => self.inner.inc(n as u64),
             None => self.inner.inc(1),
         }
+        self.print_status_line();
     }
 
     /**
@@ -71,6 +107,29 @@ impl CliProgressTracker {
             "subtask_completed called without subtasks"
         );
         self.inner.inc(1);
+        self.print_status_line();
+    }
+
+    /*
+        Prints the current task count as a plain status line, if progress is
+        displayed using status lines, and enough time has passed since the last
+        one - the final task is always printed, so that logs show completion.
+    */
+    fn print_status_line(&self) {
+        if self.display != ProgressDisplay::Lines {
+            return;
+        }
+        let current_task = self.inner.position() / self.num_subtasks.unwrap_or(1) as u64;
+        let is_finished = current_task >= self.num_tasks as u64;
+        let mut last_status = self.last_status.lock().unwrap();
+        if is_finished || last_status.elapsed() >= STATUS_LINE_INTERVAL {
+            *last_status = Instant::now();
+            eprintln!(
+                "{} {current_task} / {}",
+                self.inner.message(),
+                self.num_tasks
+            );
+        }
     }
 
     /**
@@ -89,7 +148,32 @@ impl CliProgressTracker {
         Updates the message in front of the progress bar.
     */
     pub fn update_message(&self, message: impl Into<String>) {
-        self.inner.set_message(message.into());
+        let message = message.into();
+        if self.display == ProgressDisplay::Lines && message != self.inner.message() {
+            eprintln!("{message}");
+        }
+        self.inner.set_message(message);
+    }
+
+    /**
+        Hides the progress bar while the given future runs, such as
+        while prompting the user, and shows it again once it completes.
+    */
+    pub async fn suspend_while<F: Future>(&self, future: F) -> F::Output {
+        if self.display != ProgressDisplay::Bar {
+            return future.await;
+        }
+
+        self.inner.disable_steady_tick();
+        self.inner.set_draw_target(ProgressDrawTarget::hidden());
+        let _ = Term::stderr().clear_line();
+
+        let output = future.await;
+
+        self.inner
+            .set_draw_target(ProgressDrawTarget::term_like(Box::new(Term::stderr())));
+        self.inner.enable_steady_tick(STEADY_TICK_INTERVAL);
+        output
     }
 
     /**
@@ -97,7 +181,11 @@ impl CliProgressTracker {
     */
     #[allow(dead_code)]
     pub fn print_message(&self, message: impl Into<String>) {
-        self.inner.println(message.into());
+        match self.display {
+            ProgressDisplay::Bar => self.inner.println(message.into()),
+            ProgressDisplay::Lines | ProgressDisplay::FinalOnly => eprintln!("{}", message.into()),
+            ProgressDisplay::Hidden => {}
+        }
     }
 
     /**
@@ -106,18 +194,18 @@ impl CliProgressTracker {
         This will clear the progress bar and display the final message given.
     */
     pub fn finish_with_message(&self, final_message: impl Into<String>) {
-        self.finish_with_emoji_and_message("🚀", final_message);
+        self.finish_with_symbol_and_message(Symbol::Rocket, final_message);
     }
 
     /**
-        Finishes the progress tracker with a final message and a custom emoji prefix.
+        Finishes the progress tracker with a final message and a custom symbol prefix.
 
         This will clear the progress bar and display the final message given.
     */
-    pub fn finish_with_emoji_and_message(&self, emoji: &str, final_message: impl Into<String>) {
-        self.inner.println(format!(
+    pub fn finish_with_symbol_and_message(&self, symbol: Symbol, final_message: impl Into<String>) {
+        self.print_message(format!(
             "{} {}",
-            style(emoji).bold().green(),
+            style(symbol).bold().green(),
             final_message.into()
         ));
         self.inner.finish_and_clear();
@@ -141,20 +229,32 @@ fn new_progress_style(num_tasks: usize, subtasks_per_task: usize) -> ProgressSty
                 writer.write_str(num_tasks.to_string().as_str()).unwrap();
             },
         )
-        .progress_chars(PROGRESS_BAR_CHARACTERS)
-        .tick_chars(PROGRESS_BAR_TICKERS)
+        .progress_chars(progress_bar_chars())
+        .tick_chars(progress_tick_chars())
 }
 
 fn new_progress_bar(
     message: impl Into<String>,
+    display: ProgressDisplay,
     num_tasks: usize,
     subtasks_per_task: usize,
 ) -> ProgressBar {
-    let pb = ProgressBar::new_spinner()
-        .with_style(new_progress_style(num_tasks, subtasks_per_task))
-        .with_message(message.into());
+    if display != ProgressDisplay::Bar {
+        let pb = ProgressBar::hidden().with_message(message.into());
+        pb.set_length((num_tasks * subtasks_per_task) as u64);
+        return pb;
+    }
+
+    // NOTE: Progress bars are only drawn to terminals by default, but may
+    // also be explicitly requested when the output is not a terminal
+    let pb = ProgressBar::with_draw_target(
+        None,
+        ProgressDrawTarget::term_like(Box::new(Term::stderr())),
+    )
+    .with_style(new_progress_style(num_tasks, subtasks_per_task))
+    .with_message(message.into());
 
-    pb.enable_steady_tick(Duration::from_millis(50));
+    pb.enable_steady_tick(STEADY_TICK_INTERVAL);
     pb.set_length((num_tasks * subtasks_per_task) as u64);
     pb.tick();
 
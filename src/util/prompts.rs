@@ -1,13 +1,29 @@
 use std::{
     collections::BTreeSet,
+    fmt::{self, Write as _},
     io::{stderr, IsTerminal},
+    path::PathBuf,
 };
 
-use anyhow::{bail, Context, Result};
-use console::{style, Style};
-use dialoguer::theme::ColorfulTheme;
-use rokit::tool::{ToolId, ToolSpec};
+use anyhow::Result;
+use console::style;
+use futures::future::join_all;
+use rokit::{
+    result::ErrorKind,
+    sources::{ArtifactSource, Provenance},
+    tool::{ToolId, ToolSpec},
+};
+use semver::Version;
 use tokio::task::spawn_blocking;
+use tracing::debug;
+
+use super::{display_path, format_bytes, prompt_theme, CliError, Symbol};
+
+/**
+    The most assets to list for a release when prompting for trust,
+    since some tools publish a large number of them.
+*/
+const MAX_PROVENANCE_ASSETS: usize = 10;
 
 #[derive(Debug, Clone, Copy)]
 pub enum TrustPromptKind {
@@ -15,79 +31,343 @@ pub enum TrustPromptKind {
     InstallMany,
 }
 
-pub async fn prompt_for_trust(tool_id: ToolId) -> Result<bool> {
-    spawn_blocking(move || prompt_for_install_trust_inner(TrustPromptKind::Install, &tool_id))
-        .await?
+pub async fn prompt_for_trust(
+    source: &ArtifactSource,
+    tool_id: ToolId,
+    version: Option<Version>,
+) -> Result<bool> {
+    let provenance = fetch_provenance(source, &tool_id, version.as_ref()).await;
+    spawn_blocking(move || {
+        prompt_for_install_trust_inner(
+            TrustPromptKind::Install,
+            &TrustTarget::Id(tool_id),
+            provenance.as_ref(),
+        )
+    })
+    .await?
 }
 
-pub async fn prompt_for_trust_specs(tool_specs: Vec<ToolSpec>) -> Result<Vec<ToolSpec>> {
+/**
+    Prompts the user to trust each of the given tools - tools are prompted
+    for once per tool identifier, unless `per_version` is set, in which case
+    the user is asked about each version, such as when trust is only recorded
+    for some versions, and a tool is about to be installed with a new version.
+
+    Details about where each tool comes from are fetched from the
+    given source and shown along with each prompt, if available.
+*/
+pub async fn prompt_for_trust_specs(
+    source: &ArtifactSource,
+    tool_specs: Vec<ToolSpec>,
+    per_version: bool,
+) -> Result<Vec<ToolSpec>> {
+    let targets = if per_version {
+        tool_specs
+            .iter()
+            .cloned()
+            .map(TrustTarget::Spec)
+            .collect::<Vec<_>>()
+    } else {
+        tool_specs
+            .iter()
+            .map(|spec| spec.id().clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(TrustTarget::Id)
+            .collect()
+    };
+
+    // NOTE: Tools prompted for by id show details for the
+    // first version of them that is about to be installed
+    let provenances = join_all(targets.iter().map(|target| {
+        let version = match target {
+            TrustTarget::Id(id) => tool_specs
+                .iter()
+                .find(|spec| spec.id() == id)
+                .map(ToolSpec::version),
+            TrustTarget::Spec(spec) => Some(spec.version()),
+        };
+        fetch_provenance(source, target.id(), version)
+    }))
+    .await;
+
     spawn_blocking(move || {
-        if tool_specs.is_empty() {
-            Ok(Vec::new())
-        } else if tool_specs.len() == 1 {
+        let mut trusted_targets = Vec::new();
+        if targets.len() == 1 {
             println!("A tool is not yet trusted and needs your approval.");
-            let spec = tool_specs.first().unwrap();
-            if prompt_for_install_trust_inner(TrustPromptKind::Install, spec.id())? {
-                Ok(vec![spec.clone()])
+            let target = targets.first().unwrap();
+            let provenance = provenances.first().unwrap().as_ref();
+            if prompt_for_install_trust_inner(TrustPromptKind::Install, target, provenance)? {
+                trusted_targets.push(target.clone());
+            }
+        } else if !targets.is_empty() {
+            if per_version {
+                println!(
+                    "Some tool versions are not yet trusted and need your approval.\
+                    \nYou will be prompted for each version individually, and \
+                    any version you do not trust will not be installed."
+                );
             } else {
-                Ok(Vec::new())
+                println!(
+                    "Some tools are not yet trusted and need your approval.\
+                    \nYou will be prompted for each tool individually, and \
+                    any tool you do not trust will not be installed."
+                );
             }
-        } else {
-            println!(
-                "Some tools are not yet trusted and need your approval.\
-                \nYou will be prompted for each tool individually, and \
-                any tool you do not trust will not be installed."
-            );
-            let ids_to_prompt_for = tool_specs
-                .iter()
-                .map(|spec| spec.id().clone())
-                .collect::<BTreeSet<_>>();
-
-            let mut newly_trusted_ids = Vec::new();
-            for id in ids_to_prompt_for {
-                if prompt_for_install_trust_inner(TrustPromptKind::InstallMany, &id)? {
-                    newly_trusted_ids.push(id);
+            for (target, provenance) in targets.iter().zip(&provenances) {
+                let kind = TrustPromptKind::InstallMany;
+                if prompt_for_install_trust_inner(kind, target, provenance.as_ref())? {
+                    trusted_targets.push(target.clone());
                 }
             }
-
-            let newly_trusted_specs = tool_specs
-                .into_iter()
-                .filter(|spec| newly_trusted_ids.contains(spec.id()))
-                .collect();
-            Ok(newly_trusted_specs)
         }
+
+        let newly_trusted_specs = tool_specs
+            .into_iter()
+            .filter(|spec| {
+                trusted_targets.iter().any(|target| match target {
+                    TrustTarget::Id(id) => spec.id() == id,
+                    TrustTarget::Spec(trusted) => spec == trusted,
+                })
+            })
+            .collect();
+        Ok(newly_trusted_specs)
     })
     .await?
 }
 
-fn prompt_for_install_trust_inner(kind: TrustPromptKind, tool_id: &ToolId) -> Result<bool> {
-    let theme = ColorfulTheme {
-        active_item_prefix: style("🔒 ".to_string()),
-        prompt_style: Style::new(),
-        ..Default::default()
-    };
+/**
+    A tool to prompt for trust for - either the entire tool, or a specific version of it.
+*/
+#[derive(Debug, Clone)]
+enum TrustTarget {
+    Id(ToolId),
+    Spec(ToolSpec),
+}
+
+impl TrustTarget {
+    fn id(&self) -> &ToolId {
+        match self {
+            Self::Id(id) => id,
+            Self::Spec(spec) => spec.id(),
+        }
+    }
+}
+
+impl fmt::Display for TrustTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Id(id) => id.fmt(f),
+            Self::Spec(spec) => spec.fmt(f),
+        }
+    }
+}
+
+/*
+    Fetches details about where a tool comes from, to show when prompting
+    for trust - nothing is fetched when the terminal is not interactive,
+    since no prompt is shown, and failing to fetch details is not an error,
+    since the user may still decide to trust the tool without them.
+*/
+async fn fetch_provenance(
+    source: &ArtifactSource,
+    id: &ToolId,
+    version: Option<&Version>,
+) -> Option<Provenance> {
+    if !stderr().is_terminal() {
+        return None;
+    }
+    match source.get_provenance(id, version).await {
+        Ok(provenance) => Some(provenance),
+        Err(e) => {
+            debug!(%id, "failed to fetch provenance for tool: {e}");
+            None
+        }
+    }
+}
+
+fn format_provenance(provenance: &Provenance) -> String {
+    let mut lines = Vec::new();
+    if let Some(description) = &provenance.description {
+        lines.push(format!("  {}", description.trim()));
+    }
+
+    if let Some(url) = &provenance.repository_url {
+        let stars = provenance
+            .stars
+            .map(|stars| format!(" ({stars} star{})", if stars == 1 { "" } else { "s" }))
+            .unwrap_or_default();
+        lines.push(format!("  {} {url}{stars}", style("Repository:").dim()));
+    }
+
+    if let Some(tag) = &provenance.release_tag {
+        let mut release = format!("  {} {tag}", style("Release:").dim());
+        if let Some(date) = provenance.published_date() {
+            let _ = write!(release, ", published {date}");
+        }
+        if let Some(publisher) = &provenance.publisher {
+            let _ = write!(release, " by {publisher}");
+        }
+        lines.push(release);
+    }
+
+    if !provenance.assets.is_empty() {
+        lines.push(format!("  {}", style("Assets:").dim()));
+        for asset in provenance.assets.iter().take(MAX_PROVENANCE_ASSETS) {
+            let size = asset
+                .size
+                .map(|size| format!(" ({})", format_bytes(size)))
+                .unwrap_or_default();
+            lines.push(format!(
+                "    {} {}{size}",
+                style(Symbol::Bullet).dim(),
+                asset.name
+            ));
+        }
+        let remaining = provenance
+            .assets
+            .len()
+            .saturating_sub(MAX_PROVENANCE_ASSETS);
+        if remaining > 0 {
+            lines.push(format!(
+                "    {} and {remaining} more",
+                style(Symbol::Bullet).dim()
+            ));
+        }
+    }
+
+    lines.join("\n")
+}
+
+fn prompt_for_install_trust_inner(
+    kind: TrustPromptKind,
+    tool: &TrustTarget,
+    provenance: Option<&Provenance>,
+) -> Result<bool> {
+    let theme = prompt_theme(Some(Symbol::Lock));
 
     // If the terminal isn't interactive, tell the user that they
     // need to open an interactive terminal to trust this tool.
     if !stderr().is_terminal() {
-        bail!(
-            "The following tool has not been marked as trusted: {tool_id}\
-            \nRun `rokit add {tool_id}` to install and trust this tool.",
-        );
+        return Err(CliError::new(
+            ErrorKind::Trust,
+            match tool {
+                TrustTarget::Id(tool_id) => format!(
+                    "The following tool has not been marked as trusted: {tool_id}\
+                    \nRun `rokit add {tool_id}` to install and trust this tool.",
+                ),
+                TrustTarget::Spec(spec) => format!(
+                    "The following tool version has not been marked as trusted: {spec}\
+                    \nRun `rokit trust {spec}` to trust this version of the tool.",
+                ),
+            },
+        )
+        .into());
     }
 
-    // Since the terminal is interactive, ask the user
-    // if they're sure they want to install this tool.
-    let trusted = dialoguer::Confirm::with_theme(&theme)
+    // Since the terminal is interactive, show where the tool comes from,
+    // and ask the user if they're sure they want to install this tool.
+    if let Some(provenance) = provenance {
+        let details = format_provenance(provenance);
+        if !details.is_empty() {
+            println!("\n{}\n{details}\n", style(tool).bold());
+        }
+    }
+    let trusted = dialoguer::Confirm::with_theme(theme.as_ref())
         .with_prompt(match kind {
-            TrustPromptKind::Install => format!("Trust and install {tool_id}?"),
-            TrustPromptKind::InstallMany => format!("Trust {tool_id}?"),
+            TrustPromptKind::Install => format!("Trust and install {tool}?"),
+            TrustPromptKind::InstallMany => format!("Trust {tool}?"),
         })
         .interact_opt()?
-        .with_context(|| match kind {
-            TrustPromptKind::Install => format!("Exited without trusting tool {tool_id}"),
-            TrustPromptKind::InstallMany => String::from("Exited without trusting tools"),
+        .ok_or_else(|| {
+            CliError::new(
+                ErrorKind::Trust,
+                match kind {
+                    TrustPromptKind::Install => format!("Exited without trusting tool {tool}"),
+                    TrustPromptKind::InstallMany => String::from("Exited without trusting tools"),
+                },
+            )
         })?;
 
     Ok(trusted)
 }
+
+/**
+    Prompts the user to pick one of the given tools found when searching.
+
+    Returns `None` if the user did not pick any tool,
+    or if the terminal is not interactive.
+*/
+pub async fn prompt_for_search_result(choices: Vec<String>) -> Result<Option<usize>> {
+    spawn_blocking(move || {
+        if !stderr().is_terminal() {
+            return Ok(None);
+        }
+        let picked = dialoguer::Select::with_theme(prompt_theme(None).as_ref())
+            .with_prompt("Pick a tool to add, or press Esc to skip")
+            .items(&choices)
+            .default(0)
+            .interact_opt()?;
+        Ok(picked)
+    })
+    .await?
+}
+
+/**
+    Prompts the user to choose which of the given updates to apply, with
+    all of them chosen by default, and returns the indices of chosen updates.
+
+    # Errors
+
+    - If the terminal is not interactive.
+*/
+pub async fn prompt_for_update_selection(choices: Vec<String>) -> Result<Vec<usize>> {
+    spawn_blocking(move || {
+        if !stderr().is_terminal() {
+            return Err(CliError::new(
+                ErrorKind::Usage,
+                "Choosing updates interactively requires an interactive terminal.\
+                \nRun `rokit update` with specific tools to only update those tools.",
+            )
+            .into());
+        }
+        let defaults = vec![true; choices.len()];
+        let selected = dialoguer::MultiSelect::with_theme(prompt_theme(None).as_ref())
+            .with_prompt("Choose updates to apply, using Space to toggle and Enter to confirm")
+            .items(&choices)
+            .defaults(&defaults)
+            .interact_opt()?
+            .unwrap_or_default();
+        Ok(selected)
+    })
+    .await?
+}
+
+pub async fn prompt_for_aftman_migration(aftman_path: PathBuf) -> Result<bool> {
+    spawn_blocking(move || {
+        let path = display_path(&aftman_path);
+
+        // If the terminal isn't interactive, only let the user
+        // know that they can migrate the project to Rokit.
+        if !stderr().is_terminal() {
+            tracing::warn!(
+                "This project uses an Aftman manifest at '{path}', but no Rokit manifest.\
+                \nRun `rokit migrate` to create a Rokit manifest from it."
+            );
+            return Ok(false);
+        }
+
+        println!(
+            "This project uses an Aftman manifest at '{path}', but no Rokit manifest.\
+            \nA Rokit manifest can be created from it, leaving the Aftman manifest untouched."
+        );
+        let migrate = dialoguer::Confirm::with_theme(prompt_theme(None).as_ref())
+            .with_prompt("Create a Rokit manifest for this project?")
+            .default(true)
+            .interact_opt()?
+            .unwrap_or_default();
+
+        Ok(migrate)
+    })
+    .await?
+}
@@ -0,0 +1,120 @@
+use std::fmt;
+
+use console::{style, Style};
+use dialoguer::theme::{ColorfulTheme, SimpleTheme, Theme};
+
+use super::output::is_unicode;
+
+/**
+    A symbol used in output, such as bullets in lists, or emoji in front of
+    final messages - each symbol has a plain ASCII fallback that is used for
+    terminals that can not display unicode, such as old Windows consoles.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symbol {
+    Bullet,
+    Arrow,
+    TreeBranch,
+    TreeEnd,
+    Star,
+    Check,
+    Rocket,
+    Tools,
+    Search,
+    Changelog,
+    Alert,
+    Warning,
+    Lock,
+}
+
+impl Symbol {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        if is_unicode() {
+            self.as_unicode()
+        } else {
+            self.as_ascii()
+        }
+    }
+
+    fn as_unicode(self) -> &'static str {
+        match self {
+            Self::Bullet => "•",
+            Self::Arrow => "→",
+            Self::TreeBranch => "├─",
+            Self::TreeEnd => "└─",
+            Self::Star => "★",
+            Self::Check => "✓",
+            Self::Rocket => "🚀",
+            Self::Tools => "🛠️ ",
+            Self::Search => "🔎",
+            Self::Changelog => "📋",
+            Self::Alert => "🚨",
+            Self::Warning => "⚠️",
+            Self::Lock => "🔒",
+        }
+    }
+
+    fn as_ascii(self) -> &'static str {
+        match self {
+            Self::Bullet | Self::Star => "*",
+            Self::Arrow => "->",
+            Self::TreeBranch => "|-",
+            Self::TreeEnd => "`-",
+            Self::Check => "+",
+            Self::Rocket | Self::Tools | Self::Search | Self::Changelog | Self::Lock => ">",
+            Self::Alert | Self::Warning => "!",
+        }
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+/**
+    Characters used to draw the filled part of progress bars, from full to empty.
+*/
+#[must_use]
+pub fn progress_bar_chars() -> &'static str {
+    if is_unicode() {
+        "█▉▊▋▌▍▎▏ "
+    } else {
+        "#> "
+    }
+}
+
+/**
+    Characters used to draw the spinner in front of progress bars, in order.
+*/
+#[must_use]
+pub fn progress_tick_chars() -> &'static str {
+    if is_unicode() {
+        "⠙⠹⠸⠼⠴⠦⠧⠇⠏ "
+    } else {
+        "|/-\\ "
+    }
+}
+
+/**
+    Creates the theme used for interactive prompts, optionally with the
+    given symbol in front of the active item - the plain theme is used
+    for terminals that can not display unicode, since the default theme
+    uses unicode symbols for checkboxes and the selected item.
+*/
+#[must_use]
+pub fn prompt_theme(active_symbol: Option<Symbol>) -> Box<dyn Theme> {
+    if !is_unicode() {
+        return Box::new(SimpleTheme);
+    }
+    match active_symbol {
+        Some(symbol) => Box::new(ColorfulTheme {
+            active_item_prefix: style(format!("{symbol} ")),
+            prompt_style: Style::new(),
+            ..Default::default()
+        }),
+        None => Box::new(ColorfulTheme::default()),
+    }
+}
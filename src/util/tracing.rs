@@ -1,15 +1,38 @@
-use std::io::stderr;
+use std::io::{self, stderr, Write};
 
+use clap::ValueEnum;
 use tracing::level_filters::LevelFilter;
 use tracing_subscriber::EnvFilter;
 
+use rokit::sources::{redact_tokens, HTTP_TRACE_TARGET};
+
 #[cfg(debug_assertions)]
 const FMT_PRETTY: bool = true;
 
 #[cfg(not(debug_assertions))]
 const FMT_PRETTY: bool = false;
 
-pub fn init(default_level_filter: LevelFilter) {
+/**
+    The format to use for logs written by Rokit.
+*/
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable log messages.
+    #[default]
+    Text,
+    /// One JSON object per line, with a timestamp, level, fields, and spans.
+    Json,
+}
+
+pub fn init(default_level_filter: LevelFilter, trace_http: bool, log_format: LogFormat) {
+    // Detailed HTTP logs are very noisy, so they are only
+    // enabled when explicitly requested, at any verbosity
+    let http_directive = if trace_http {
+        format!("{HTTP_TRACE_TARGET}=debug")
+    } else {
+        format!("{HTTP_TRACE_TARGET}=off")
+    };
+
     let tracing_env_filter = EnvFilter::builder()
         .with_default_directive(default_level_filter.into())
         .from_env_lossy()
@@ -21,14 +44,53 @@ pub fn init(default_level_filter: LevelFilter) {
         .add_directive("goblin=info".parse().unwrap())
         .add_directive("tower=info".parse().unwrap())
         .add_directive("hyper=info".parse().unwrap())
-        .add_directive("h2=info".parse().unwrap());
+        .add_directive("h2=info".parse().unwrap())
+        .add_directive(http_directive.parse().unwrap());
+
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(tracing_env_filter)
+        .with_writer(RedactingWriter::default);
 
     // Show the target module in the tracing output during development
     // so that we can track down issues and trace origins faster.
-    tracing_subscriber::fmt()
-        .with_env_filter(tracing_env_filter)
-        .with_writer(stderr)
-        .with_target(FMT_PRETTY)
-        .without_time()
-        .init();
+    match log_format {
+        LogFormat::Text => builder
+            .with_ansi(console::colors_enabled_stderr())
+            .with_target(FMT_PRETTY)
+            .without_time()
+            .init(),
+        LogFormat::Json => builder
+            .json()
+            .with_ansi(false)
+            .with_current_span(false)
+            .with_span_list(true)
+            .init(),
+    }
+}
+
+/*
+    Writes logs to stderr, with any access tokens redacted.
+
+    A new writer is created for every event, and the event is buffered until
+    the writer is dropped, so that tokens split across writes are also caught.
+*/
+#[derive(Default)]
+struct RedactingWriter(Vec<u8>);
+
+impl Write for RedactingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for RedactingWriter {
+    fn drop(&mut self) {
+        let contents = String::from_utf8_lossy(&self.0);
+        let _ = stderr().write_all(redact_tokens(&contents).as_bytes());
+    }
 }
@@ -0,0 +1,148 @@
+use std::{collections::BTreeSet, env::var_os};
+
+use anyhow::{bail, Result};
+use console::style;
+use futures::{stream::FuturesUnordered, StreamExt};
+use semver::Version;
+
+use rokit::{
+    discovery::discover_all_manifests, manifests::RokitManifest, storage::Home, tool::ToolId,
+};
+
+use super::Symbol;
+
+/**
+    Environment variables that, when set to any non-empty value,
+    disable the periodic update check - useful for CI environments.
+*/
+const DISABLE_VARS: [&str; 2] = ["ROKIT_NO_UPDATE_CHECK", "CI"];
+
+/**
+    Gets the tool identifier for Rokit itself.
+
+    # Errors
+
+    - If the repository URL that Rokit was built with is not a valid tool identifier.
+*/
+pub fn rokit_tool_id() -> Result<ToolId> {
+    let repo = env!("CARGO_PKG_REPOSITORY")
+        .trim_start_matches("https://github.com/")
+        .trim_end_matches(".git");
+    let Ok(tool_id) = repo.parse::<ToolId>() else {
+        bail!(
+            "Failed to parse manifest repository URL!\
+            \nThis is a bug in Rokit, please report it at:
+            \n{repo}"
+        );
+    };
+    Ok(tool_id)
+}
+
+/**
+    Checks if the periodic update check should run right now.
+
+    The update check is opt-in using the `update-check` setting in the global
+    manifest, may be disabled using environment variables, and runs at most once a day.
+*/
+pub async fn should_check_for_updates(home: &Home) -> bool {
+    let disabled = DISABLE_VARS
+        .iter()
+        .any(|name| var_os(name).is_some_and(|value| !value.is_empty()));
+    if disabled {
+        return false;
+    }
+
+    let enabled = RokitManifest::load(home.path())
+        .await
+        .ok()
+        .and_then(|manifest| manifest.get_update_check())
+        .unwrap_or_default();
+    enabled && home.is_update_check_due().await
+}
+
+/**
+    Checks for newer versions of Rokit, and of tools in all discovered manifests,
+    and returns a message to show to the user if any newer versions were found.
+
+    The check is recorded as completed if the latest version of Rokit could be
+    fetched, and any errors are ignored, since the check is not critical.
+*/
+pub async fn check_for_updates(home: &Home) -> Option<String> {
+    let source = home.artifact_source().await.ok()?;
+    let rokit_id = rokit_tool_id().ok()?;
+
+    let rokit_latest = source
+        .get_latest_release(&rokit_id)
+        .await
+        .ok()?
+        .artifacts
+        .first()?
+        .tool_spec
+        .version()
+        .clone();
+    if let Err(e) = home.record_update_check().await {
+        tracing::debug!("Failed to record update check: {e}");
+    }
+
+    // NOTE: We only check tools that are actually used in manifests,
+    // and tools that fail to fetch are simply not counted as outdated
+    let specs = discover_all_manifests(false, false)
+        .await
+        .into_iter()
+        .flat_map(|manifest| manifest.tools.into_values())
+        .collect::<BTreeSet<_>>();
+    let ids = specs
+        .iter()
+        .map(|spec| spec.id().clone())
+        .collect::<BTreeSet<_>>();
+    let latest_versions = ids
+        .into_iter()
+        .map(|id| {
+            let source = &source;
+            async move {
+                let release = source.get_latest_release(&id).await.ok()?;
+                let version = release.artifacts.first()?.tool_spec.version().clone();
+                Some((id, version))
+            }
+        })
+        .collect::<FuturesUnordered<_>>()
+        .filter_map(|latest| async move { latest })
+        .collect::<Vec<_>>()
+        .await;
+    let num_outdated_tools = specs
+        .iter()
+        .filter(|spec| {
+            latest_versions
+                .iter()
+                .any(|(id, latest)| spec.id() == id && latest > spec.version())
+        })
+        .count();
+
+    let rokit_current = env!("CARGO_PKG_VERSION").parse::<Version>().ok()?;
+    let mut lines = Vec::new();
+    if rokit_latest > rokit_current {
+        lines.push(format!(
+            "A new version of Rokit is available: {} {} {}\
+            \nRun `{}` to update.",
+            style(&rokit_current).dim(),
+            Symbol::Arrow,
+            style(&rokit_latest).bold().magenta(),
+            style("rokit self-update").bold().green(),
+        ));
+    }
+    if num_outdated_tools > 0 {
+        lines.push(format!(
+            "Newer versions are available for {} project tool{}.\
+            \nRun `{}` for details.",
+            style(num_outdated_tools).bold().magenta(),
+            if num_outdated_tools == 1 { "" } else { "s" },
+            style("rokit outdated").bold().green(),
+        ));
+    }
+
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join("\n\n"))
+    }
+}